@@ -0,0 +1,93 @@
+#![feature(test)]
+
+extern crate test;
+use pyo3::prelude::*;
+use pyo3::types::{IntoPyDict, PyDict, PyTuple};
+use pyo3::wrap_pyfunction;
+use test::Bencher;
+
+#[pyfunction]
+fn add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+#[pyfunction(a, b = 2, "*", c = 3)]
+fn add_with_defaults(a: i32, b: i32, c: i32) -> i32 {
+    a + b + c
+}
+
+#[pyfunction(args = "*", kwargs = "**")]
+fn forward(args: &PyTuple, kwargs: Option<&PyDict>) -> usize {
+    args.len() + kwargs.map_or(0, |kwargs| kwargs.len())
+}
+
+#[pyclass]
+struct Adder {}
+
+#[pymethods]
+impl Adder {
+    #[call]
+    fn __call__(&self, a: i32, b: i32) -> i32 {
+        a + b
+    }
+}
+
+fn bench_calls(b: &mut Bencher, py: Python, f: &PyAny, code: &str) {
+    let locals = [("f", f)].into_py_dict(py);
+    let code = format!("for _ in range(1000): {}", code);
+    b.iter(|| py.run(&code, None, Some(locals)).unwrap());
+}
+
+#[bench]
+fn call_positional(b: &mut Bencher) {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    bench_calls(b, py, wrap_pyfunction!(add)(py).as_ref(py), "f(1, 2)");
+}
+
+#[bench]
+fn call_keywords(b: &mut Bencher) {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    bench_calls(b, py, wrap_pyfunction!(add)(py).as_ref(py), "f(a=1, b=2)");
+}
+
+#[bench]
+fn call_defaults(b: &mut Bencher) {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    bench_calls(
+        b,
+        py,
+        wrap_pyfunction!(add_with_defaults)(py).as_ref(py),
+        "f(1, c=4)",
+    );
+}
+
+#[bench]
+fn call_forwarding(b: &mut Bencher) {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    bench_calls(
+        b,
+        py,
+        wrap_pyfunction!(forward)(py).as_ref(py),
+        "f(1, 2, x=3)",
+    );
+}
+
+#[bench]
+fn call_object_positional(b: &mut Bencher) {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let adder = PyCell::new(py, Adder {}).unwrap();
+    bench_calls(b, py, adder, "f(1, 2)");
+}
+
+#[bench]
+fn call_object_keywords(b: &mut Bencher) {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let adder = PyCell::new(py, Adder {}).unwrap();
+    bench_calls(b, py, adder, "f(a=1, b=2)");
+}
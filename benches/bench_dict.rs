@@ -3,6 +3,7 @@
 extern crate test;
 use pyo3::prelude::*;
 use pyo3::types::IntoPyDict;
+use std::collections::{BTreeMap, HashMap};
 use test::Bencher;
 
 #[bench]
@@ -33,3 +34,27 @@ fn dict_get_item(b: &mut Bencher) {
         }
     });
 }
+
+#[bench]
+fn extract_hashmap(b: &mut Bencher) {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    const LEN: usize = 1_000_000;
+    let dict = (0..LEN as u64).map(|i| (i, i * 2)).into_py_dict(py);
+    b.iter(|| {
+        let map: HashMap<u64, u64> = dict.extract().unwrap();
+        map
+    });
+}
+
+#[bench]
+fn extract_btreemap(b: &mut Bencher) {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    const LEN: usize = 1_000_000;
+    let dict = (0..LEN as u64).map(|i| (i, i * 2)).into_py_dict(py);
+    b.iter(|| {
+        let map: BTreeMap<u64, u64> = dict.extract().unwrap();
+        map
+    });
+}
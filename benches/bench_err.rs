@@ -0,0 +1,43 @@
+#![feature(test)]
+
+extern crate test;
+use pyo3::exceptions::ValueError;
+use pyo3::{ffi, prelude::*};
+use test::Bencher;
+
+#[bench]
+fn err_new_in_pool(b: &mut Bencher) {
+    let _gil = Python::acquire_gil();
+    b.iter(|| {
+        for _ in 0..1000 {
+            std::mem::drop(PyErr::new::<ValueError, _>("error"));
+        }
+    });
+}
+
+#[bench]
+fn err_new_outside_pool(b: &mut Bencher) {
+    std::mem::drop(Python::acquire_gil());
+    // The GIL is held without PyO3 knowing it, as in a callback called directly from C.
+    let state = unsafe { ffi::PyGILState_Ensure() };
+    b.iter(|| {
+        for _ in 0..1000 {
+            std::mem::drop(PyErr::new::<ValueError, _>("error"));
+        }
+    });
+    unsafe { ffi::PyGILState_Release(state) };
+}
+
+#[bench]
+fn err_new_with_embedded_gil_state(b: &mut Bencher) {
+    std::mem::drop(Python::acquire_gil());
+    let state = unsafe { ffi::PyGILState_Ensure() };
+    b.iter(|| unsafe {
+        Python::with_embedded_gil_state(|_| {
+            for _ in 0..1000 {
+                std::mem::drop(PyErr::new::<ValueError, _>("error"));
+            }
+        })
+    });
+    unsafe { ffi::PyGILState_Release(state) };
+}
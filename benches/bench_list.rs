@@ -33,3 +33,96 @@ fn list_get_item(b: &mut Bencher) {
         }
     });
 }
+
+#[bench]
+fn list_from_vec_f64(b: &mut Bencher) {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    const LEN: usize = 100_000;
+    let values: Vec<f64> = (0..LEN).map(|i| i as f64).collect();
+    b.iter(|| {
+        // Release the references to the items created without the `nightly` fast path.
+        let _pool = unsafe { py.new_pool() };
+        values.to_object(py)
+    });
+}
+
+#[bench]
+fn list_from_exact_iter_f64(b: &mut Bencher) {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    const LEN: usize = 100_000;
+    let values: Vec<f64> = (0..LEN).map(|i| i as f64).collect();
+    b.iter(|| {
+        let pool = unsafe { py.new_pool() };
+        let py = pool.python();
+        PyList::new_from_exact_iter(py, values.iter().map(|&v| Ok(v))).unwrap();
+    });
+}
+
+#[bench]
+fn list_from_vec_i64(b: &mut Bencher) {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    const LEN: usize = 100_000;
+    let values: Vec<i64> = (0..LEN as i64).collect();
+    b.iter(|| {
+        // Release the references to the items created without the `nightly` fast path.
+        let _pool = unsafe { py.new_pool() };
+        values.to_object(py)
+    });
+}
+
+#[bench]
+fn extract_vec_f64(b: &mut Bencher) {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    const LEN: usize = 100_000;
+    let list = PyList::new(py, (0..LEN).map(|i| i as f64));
+    b.iter(|| {
+        // Release the references to the items created without the `nightly` fast path.
+        let _pool = unsafe { py.new_pool() };
+        list.extract::<Vec<f64>>().unwrap()
+    });
+}
+
+#[bench]
+fn extract_vec_i64(b: &mut Bencher) {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    const LEN: usize = 100_000;
+    let list = PyList::new(py, (0..LEN).map(|i| i as i64));
+    b.iter(|| {
+        // Release the references to the items created without the `nightly` fast path.
+        let _pool = unsafe { py.new_pool() };
+        list.extract::<Vec<i64>>().unwrap()
+    });
+}
+
+#[bench]
+fn extract_vec_u8(b: &mut Bencher) {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    const LEN: usize = 100_000;
+    let list = PyList::new(py, (0..LEN).map(|i| i as u8));
+    b.iter(|| {
+        // Release the references to the items created without the `nightly` fast path.
+        let _pool = unsafe { py.new_pool() };
+        list.extract::<Vec<u8>>().unwrap()
+    });
+}
+
+#[bench]
+fn extract_vec_f64_mixed(b: &mut Bencher) {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    const LEN: usize = 100_000;
+    // The first item is an `int`, so the items are extracted one by one.
+    let list = PyList::new(py, (0..LEN).map(|i| i as f64));
+    list.set_item(0, 0).unwrap();
+    b.iter(|| {
+        // Release the references to the items created by the generic path.
+        let _pool = unsafe { py.new_pool() };
+        list.extract::<Vec<f64>>().unwrap()
+    });
+}
@@ -14,3 +14,69 @@ fn drop_many_objects(b: &mut Bencher) {
         }
     });
 }
+
+#[bench]
+fn extract_ints(b: &mut Bencher) {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let small = 42.to_object(py);
+    let large = u64::max_value().to_object(py);
+    b.iter(|| {
+        let mut sum = 0u64;
+        for _ in 0..1000 {
+            sum = sum.wrapping_add(small.extract::<i32>(py).unwrap() as u64);
+            sum = sum.wrapping_add(small.extract::<i64>(py).unwrap() as u64);
+            sum = sum.wrapping_add(large.extract::<u64>(py).unwrap());
+        }
+        sum
+    });
+}
+
+#[bench]
+fn clone_ref_with_token(b: &mut Bencher) {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let obj: Py<PyAny> = py.None().as_ref(py).into();
+    b.iter(|| {
+        for _ in 0..10_000_000 {
+            std::mem::drop(obj.clone_ref(py));
+        }
+    });
+}
+
+#[bench]
+fn clone_without_token(b: &mut Bencher) {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let obj: Py<PyAny> = py.None().as_ref(py).into();
+    b.iter(|| {
+        for _ in 0..10_000_000 {
+            std::mem::drop(obj.clone());
+        }
+    });
+}
+
+#[bench]
+fn clone_drop_without_gil(b: &mut Bencher) {
+    let obj: PyObject = {
+        let gil = Python::acquire_gil();
+        gil.python().None()
+    };
+    // The updates are deferred, and applied the next time the GIL is acquired.
+    b.iter(|| {
+        for _ in 0..1000 {
+            std::mem::drop(obj.clone());
+        }
+        std::mem::drop(Python::acquire_gil());
+    });
+    std::mem::drop(Python::acquire_gil());
+}
+
+#[bench]
+fn acquire_gil_nothing_pending(b: &mut Bencher) {
+    b.iter(|| {
+        for _ in 0..1000 {
+            std::mem::drop(Python::acquire_gil());
+        }
+    });
+}
@@ -0,0 +1,41 @@
+#![feature(test)]
+
+extern crate test;
+use pyo3::prelude::*;
+use pyo3::types::PyString;
+use test::Bencher;
+
+fn bench_new(b: &mut Bencher, s: &str) {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    b.iter(|| {
+        let pool = unsafe { py.new_pool() };
+        let py = pool.python();
+        for _ in 0..1000 {
+            PyString::new(py, s);
+        }
+    });
+}
+
+#[bench]
+fn new_ascii_identifier(b: &mut Bencher) {
+    bench_new(b, "some_attribute_name");
+}
+
+#[bench]
+fn new_ascii_long(b: &mut Bencher) {
+    bench_new(
+        b,
+        &"The quick brown fox jumps over the lazy dog. ".repeat(100),
+    );
+}
+
+#[bench]
+fn new_latin1(b: &mut Bencher) {
+    bench_new(b, "d\u{e9}j\u{e0} vu, na\u{ef}ve caf\u{e9}");
+}
+
+#[bench]
+fn new_emoji(b: &mut Bencher) {
+    bench_new(b, "\u{1f980} rustacean \u{1f40d} pythonista");
+}
@@ -33,3 +33,16 @@ fn tuple_get_item(b: &mut Bencher) {
         }
     });
 }
+
+#[bench]
+fn tuple_from_slice_f64(b: &mut Bencher) {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    const LEN: usize = 100_000;
+    let values: Vec<f64> = (0..LEN).map(|i| i as f64).collect();
+    b.iter(|| {
+        let pool = unsafe { py.new_pool() };
+        let py = pool.python();
+        PyTuple::new(py, &values);
+    });
+}
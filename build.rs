@@ -2,11 +2,12 @@ use std::{
     collections::HashMap,
     convert::AsRef,
     env, fmt,
-    fs::File,
+    fs::{self, File},
     io::{self, BufRead, BufReader},
+    iter::Peekable,
     path::{Path, PathBuf},
     process::{Command, Stdio},
-    str::FromStr,
+    str::{Chars, FromStr},
 };
 
 const PY3_MIN_MINOR: u8 = 5;
@@ -88,7 +89,6 @@ impl FromStr for PythonInterpreterKind {
 ///
 /// (hrm, this is sort of re-implementing what distutils does, except
 /// by passing command line args instead of referring to a python.h)
-#[cfg(not(target_os = "windows"))]
 static SYSCONFIG_FLAGS: [&str; 7] = [
     "Py_USING_UNICODE",
     "Py_UNICODE_WIDE",
@@ -134,10 +134,129 @@ fn fix_config_map(mut config_map: HashMap<String, String>) -> HashMap<String, St
     config_map
 }
 
-fn load_cross_compile_info() -> Result<(InterpreterConfig, HashMap<String, String>)> {
-    let python_include_dir = env::var("PYO3_CROSS_INCLUDE_DIR")?;
-    let python_include_dir = Path::new(&python_include_dir);
+/// The target of the build, which differs from the host when cross compiling.
+struct Target {
+    os: String,
+    family: String,
+}
+
+impl Target {
+    fn from_env() -> Result<Self> {
+        Ok(Target {
+            os: env::var("CARGO_CFG_TARGET_OS")?,
+            family: env::var("CARGO_CFG_TARGET_FAMILY")?,
+        })
+    }
+
+    fn is_windows(&self) -> bool {
+        self.family.split(',').any(|family| family == "windows")
+    }
+}
+
+/// The configuration of a cross compilation, given by the `PYO3_CROSS_*` environment variables.
+struct CrossCompileConfig {
+    /// `PYO3_CROSS_LIB_DIR`, the directory containing the libraries of the target's Python
+    lib_dir: PathBuf,
+    /// `PYO3_CROSS_PYTHON_VERSION`, required when `lib_dir` contains several versions
+    version: Option<PythonVersion>,
+    /// `PYO3_CROSS_SYSCONFIGDATA`, the `_sysconfigdata_*.py` file of a Unix target
+    sysconfigdata: Option<PathBuf>,
+    /// `PYO3_CROSS_INCLUDE_DIR`, the headers of the target's Python, used instead of the
+    /// sysconfigdata when set
+    include_dir: Option<PathBuf>,
+}
+
+/// Reads the cross compilation variables, returning `None` if `PYO3_CROSS_LIB_DIR` is not set.
+fn cross_compile_config() -> Result<Option<CrossCompileConfig>> {
+    let lib_dir = match env::var_os("PYO3_CROSS_LIB_DIR") {
+        Some(lib_dir) => PathBuf::from(lib_dir),
+        None => {
+            for var in &[
+                "PYO3_CROSS_PYTHON_VERSION",
+                "PYO3_CROSS_SYSCONFIGDATA",
+                "PYO3_CROSS_INCLUDE_DIR",
+            ] {
+                if env::var_os(var).is_some() {
+                    bail!(
+                        "{} is set, but PYO3_CROSS_LIB_DIR is not; set PYO3_CROSS_LIB_DIR to the \
+                         directory containing the libraries of the target's Python to cross compile",
+                        var
+                    );
+                }
+            }
+            return Ok(None);
+        }
+    };
+    if !lib_dir.is_dir() {
+        bail!(
+            "PYO3_CROSS_LIB_DIR={} is not a directory",
+            lib_dir.display()
+        );
+    }
+
+    let version = match env::var_os("PYO3_CROSS_PYTHON_VERSION") {
+        Some(version) => Some(parse_cross_python_version(
+            version
+                .to_str()
+                .ok_or("PYO3_CROSS_PYTHON_VERSION is not valid unicode")?,
+        )?),
+        None => None,
+    };
+
+    Ok(Some(CrossCompileConfig {
+        lib_dir,
+        version,
+        sysconfigdata: env::var_os("PYO3_CROSS_SYSCONFIGDATA").map(PathBuf::from),
+        include_dir: env::var_os("PYO3_CROSS_INCLUDE_DIR").map(PathBuf::from),
+    }))
+}
 
+/// Parses a version of the form `3.x`, as given by `PYO3_CROSS_PYTHON_VERSION`.
+fn parse_cross_python_version(version: &str) -> Result<PythonVersion> {
+    parse_python_version(version).ok_or_else(|| {
+        format!(
+            "PYO3_CROSS_PYTHON_VERSION={} is invalid; it must be of the form 3.x, e.g. 3.8",
+            version
+        )
+        .into()
+    })
+}
+
+fn parse_python_version(version: &str) -> Option<PythonVersion> {
+    let mut parts = version.trim().splitn(2, '.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    Some(PythonVersion {
+        major,
+        minor: Some(minor),
+        implementation: PythonInterpreterKind::CPython,
+    })
+}
+
+fn load_cross_compile_info(
+    cross: &CrossCompileConfig,
+    target: &Target,
+) -> Result<(InterpreterConfig, HashMap<String, String>)> {
+    if let Some(include_dir) = &cross.include_dir {
+        load_cross_compile_from_headers(include_dir, &cross.lib_dir)
+    } else if target.is_windows() {
+        load_cross_compile_from_windows_libs(&cross.lib_dir, cross.version.as_ref())
+    } else {
+        let sysconfigdata = match &cross.sysconfigdata {
+            Some(sysconfigdata) => sysconfigdata.clone(),
+            None => find_sysconfigdata(&cross.lib_dir, cross.version.as_ref())?,
+        };
+        let vars = parse_sysconfigdata(&sysconfigdata)
+            .map_err(|e| format!("Failed to parse {}: {}", sysconfigdata.display(), e))?;
+        load_cross_compile_from_sysconfigdata(&vars, &cross.lib_dir, cross.version.as_ref())
+            .map_err(|e| format!("Invalid {}: {}", sysconfigdata.display(), e).into())
+    }
+}
+
+fn load_cross_compile_from_headers(
+    python_include_dir: &Path,
+    lib_dir: &Path,
+) -> Result<(InterpreterConfig, HashMap<String, String>)> {
     let patchlevel_defines = parse_header_defines(python_include_dir.join("patchlevel.h"))?;
 
     let major = match patchlevel_defines
@@ -177,7 +296,7 @@ fn load_cross_compile_info() -> Result<(InterpreterConfig, HashMap<String, Strin
 
     let interpreter_config = InterpreterConfig {
         version: python_version,
-        libdir: Some(env::var("PYO3_CROSS_LIB_DIR")?),
+        libdir: Some(lib_dir.display().to_string()),
         shared,
         ld_version: "".to_string(),
         base_prefix: "".to_string(),
@@ -188,6 +307,319 @@ fn load_cross_compile_info() -> Result<(InterpreterConfig, HashMap<String, Strin
     Ok((interpreter_config, fix_config_map(config_map)))
 }
 
+/// Finds the `_sysconfigdata_*.py` file of the target's Python in `lib_dir`, e.g. in
+/// `lib_dir/python3.8/`, selecting the one of `version` if there are several.
+fn find_sysconfigdata(lib_dir: &Path, version: Option<&PythonVersion>) -> Result<PathBuf> {
+    let mut candidates = Vec::new();
+    search_sysconfigdata(lib_dir, 3, &mut candidates)?;
+    if let Some(version) = version {
+        let mut matching = Vec::new();
+        for candidate in candidates.drain(..) {
+            let vars = parse_sysconfigdata(&candidate)
+                .map_err(|e| format!("Failed to parse {}: {}", candidate.display(), e))?;
+            if vars
+                .get("VERSION")
+                .and_then(|v| parse_python_version(v))
+                .as_ref()
+                == Some(version)
+            {
+                matching.push(candidate);
+            }
+        }
+        candidates = matching;
+    }
+
+    match candidates.len() {
+        0 => bail!(
+            "Could not find a _sysconfigdata_*.py file{} in PYO3_CROSS_LIB_DIR={}; set \
+             PYO3_CROSS_SYSCONFIGDATA to the sysconfigdata file of the target's Python, or \
+             PYO3_CROSS_INCLUDE_DIR to its headers",
+            version.map_or(String::new(), |v| format!(" for Python {}", v)),
+            lib_dir.display()
+        ),
+        1 => Ok(candidates.remove(0)),
+        _ => bail!(
+            "Found several _sysconfigdata_*.py files in PYO3_CROSS_LIB_DIR={}: {}; set {} to \
+             select one",
+            lib_dir.display(),
+            candidates
+                .iter()
+                .map(|c| c.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+            if version.is_some() {
+                "PYO3_CROSS_SYSCONFIGDATA"
+            } else {
+                "PYO3_CROSS_PYTHON_VERSION or PYO3_CROSS_SYSCONFIGDATA"
+            }
+        ),
+    }
+}
+
+fn search_sysconfigdata(dir: &Path, depth: u32, found: &mut Vec<PathBuf>) -> Result<()> {
+    let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let path = entry.path();
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if path.is_dir() {
+            // The site-packages of the target may contain other Pythons' files.
+            if depth > 0 && name != "site-packages" && name != "dist-packages" {
+                search_sysconfigdata(&path, depth - 1, found)?;
+            }
+        } else if name.starts_with("_sysconfigdata_") && name.ends_with(".py") {
+            found.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn parse_sysconfigdata(path: &Path) -> Result<HashMap<String, String>> {
+    parse_sysconfigdata_vars(&fs::read_to_string(path)?)
+}
+
+/// Parses the `build_time_vars` dictionary of a `_sysconfigdata_*.py` file.
+///
+/// The dictionary is written by `pprint`, so it only contains string literals, which are split
+/// into adjacent literals when they are long, and integers. Integers are returned as strings.
+fn parse_sysconfigdata_vars(source: &str) -> Result<HashMap<String, String>> {
+    let start = source
+        .find("build_time_vars")
+        .ok_or("build_time_vars is not defined")?;
+    let mut chars = source[start + "build_time_vars".len()..].chars().peekable();
+    expect_char(&mut chars, '=')?;
+    expect_char(&mut chars, '{')?;
+
+    let mut vars = HashMap::new();
+    loop {
+        skip_whitespace(&mut chars);
+        if chars.peek() == Some(&'}') {
+            break;
+        }
+        let key = parse_python_literal(&mut chars)?;
+        expect_char(&mut chars, ':')?;
+        let value = parse_python_literal(&mut chars)?;
+        vars.insert(key, value);
+        skip_whitespace(&mut chars);
+        match chars.next() {
+            Some(',') => (),
+            Some('}') => break,
+            Some(c) => bail!("expected ',' or '}}' in build_time_vars, found '{}'", c),
+            None => bail!("build_time_vars is not terminated"),
+        }
+    }
+    Ok(vars)
+}
+
+/// Skips whitespace and comments.
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while let Some(&c) = chars.peek() {
+        if c == '#' {
+            while chars.peek().map_or(false, |&c| c != '\n') {
+                chars.next();
+            }
+        } else if c.is_whitespace() {
+            chars.next();
+        } else {
+            break;
+        }
+    }
+}
+
+fn expect_char(chars: &mut Peekable<Chars>, expected: char) -> Result<()> {
+    skip_whitespace(chars);
+    match chars.next() {
+        Some(c) if c == expected => Ok(()),
+        Some(c) => bail!("expected '{}', found '{}'", expected, c),
+        None => bail!("expected '{}', found the end of the file", expected),
+    }
+}
+
+/// Parses a string, which may be made of adjacent literals in parentheses, or an integer.
+fn parse_python_literal(chars: &mut Peekable<Chars>) -> Result<String> {
+    skip_whitespace(chars);
+    let parenthesized = chars.peek() == Some(&'(');
+    if parenthesized {
+        chars.next();
+        skip_whitespace(chars);
+    }
+
+    let mut value = String::new();
+    match chars.peek() {
+        Some(&quote) if quote == '\'' || quote == '"' => {
+            while let Some(&quote) = chars.peek().filter(|&&c| c == '\'' || c == '"') {
+                chars.next();
+                parse_python_string(chars, quote, &mut value)?;
+                skip_whitespace(chars);
+            }
+        }
+        Some(&c) if c == '-' || c.is_ascii_digit() => {
+            while let Some(&c) = chars.peek().filter(|&&c| c == '-' || c.is_ascii_digit()) {
+                value.push(c);
+                chars.next();
+            }
+        }
+        Some(&c) => bail!("unsupported value starting with '{}'", c),
+        None => bail!("expected a value, found the end of the file"),
+    }
+
+    if parenthesized {
+        expect_char(chars, ')')?;
+    }
+    Ok(value)
+}
+
+/// Parses the rest of a string literal opened by `quote`, appending its contents to `value`.
+fn parse_python_string(chars: &mut Peekable<Chars>, quote: char, value: &mut String) -> Result<()> {
+    loop {
+        match chars.next() {
+            Some(c) if c == quote => return Ok(()),
+            Some('\\') => match chars.next() {
+                Some('n') => value.push('\n'),
+                Some('t') => value.push('\t'),
+                Some('r') => value.push('\r'),
+                Some(c) if c == '\\' || c == '\'' || c == '"' => value.push(c),
+                Some(c) => {
+                    value.push('\\');
+                    value.push(c);
+                }
+                None => break,
+            },
+            Some('\n') | None => break,
+            Some(c) => value.push(c),
+        }
+    }
+    bail!("unterminated string literal")
+}
+
+fn load_cross_compile_from_sysconfigdata(
+    vars: &HashMap<String, String>,
+    lib_dir: &Path,
+    expected_version: Option<&PythonVersion>,
+) -> Result<(InterpreterConfig, HashMap<String, String>)> {
+    let get = |key: &str| vars.get(key).map(|value| value.as_str());
+    let version = get("VERSION").ok_or("VERSION is not defined")?;
+    let mut version =
+        parse_python_version(version).ok_or_else(|| format!("invalid VERSION {}", version))?;
+    if get("SOABI").map_or(false, |soabi| soabi.starts_with("pypy")) {
+        version.implementation = PythonInterpreterKind::PyPy;
+    }
+    if let Some(expected_version) = expected_version {
+        if *expected_version != version {
+            bail!(
+                "the Python version is {}, but PYO3_CROSS_PYTHON_VERSION={}",
+                version,
+                expected_version
+            );
+        }
+    }
+
+    let ld_version = match get("LDVERSION") {
+        Some(ld_version) => ld_version.to_string(),
+        None => format!(
+            "{}{}",
+            get("VERSION").unwrap(),
+            get("ABIFLAGS").unwrap_or("")
+        ),
+    };
+    let calcsize_pointer = match get("SIZEOF_VOID_P") {
+        Some(size) => Some(
+            size.parse()
+                .map_err(|_| format!("invalid SIZEOF_VOID_P {}", size))?,
+        ),
+        None => None,
+    };
+
+    let interpreter_config = InterpreterConfig {
+        shared: version.implementation == PythonInterpreterKind::PyPy
+            || get("Py_ENABLE_SHARED") == Some("1"),
+        version,
+        libdir: Some(lib_dir.display().to_string()),
+        ld_version,
+        base_prefix: "".to_string(),
+        executable: PathBuf::new(),
+        calcsize_pointer,
+    };
+
+    let mut config_map = HashMap::new();
+    for &key in SYSCONFIG_FLAGS.iter() {
+        config_map.insert(key.to_string(), get(key).unwrap_or("0").to_string());
+    }
+    for &key in SYSCONFIG_VALUES.iter() {
+        if let Some(value) = get(key) {
+            config_map.insert(key.to_string(), value.to_string());
+        }
+    }
+
+    Ok((interpreter_config, fix_config_map(config_map)))
+}
+
+/// Configures a cross compilation to Windows from the `pythonXY.lib` import library in
+/// `lib_dir`, the `libs` directory of a Windows installation of Python.
+fn load_cross_compile_from_windows_libs(
+    lib_dir: &Path,
+    version: Option<&PythonVersion>,
+) -> Result<(InterpreterConfig, HashMap<String, String>)> {
+    let mut minors = Vec::new();
+    for entry in fs::read_dir(lib_dir)? {
+        let name = entry?.file_name().to_string_lossy().to_lowercase();
+        // `python3.lib` is the library of the stable ABI, which has no minor version.
+        if name.len() > "python3.lib".len() && name.starts_with("python3") && name.ends_with(".lib")
+        {
+            if let Ok(minor) = name["python3".len()..name.len() - ".lib".len()].parse::<u8>() {
+                minors.push(minor);
+            }
+        }
+    }
+    minors.sort();
+
+    let version = match version {
+        Some(version) => {
+            if !minors.iter().any(|&minor| version.minor == Some(minor)) {
+                bail!(
+                    "PYO3_CROSS_LIB_DIR={} does not contain python{}{}.lib for \
+                     PYO3_CROSS_PYTHON_VERSION={}",
+                    lib_dir.display(),
+                    version.major,
+                    version.minor.unwrap_or(0),
+                    version
+                );
+            }
+            version.clone()
+        }
+        None => match minors.as_slice() {
+            [minor] => PythonVersion {
+                major: 3,
+                minor: Some(*minor),
+                implementation: PythonInterpreterKind::CPython,
+            },
+            [] => bail!(
+                "Could not find a python3X.lib file in PYO3_CROSS_LIB_DIR={}; set it to the \
+                 libs directory of the target's Python",
+                lib_dir.display()
+            ),
+            _ => bail!(
+                "Found libraries of several Python versions in PYO3_CROSS_LIB_DIR={}; set \
+                 PYO3_CROSS_PYTHON_VERSION to select one",
+                lib_dir.display()
+            ),
+        },
+    };
+
+    let interpreter_config = InterpreterConfig {
+        version,
+        libdir: Some(lib_dir.display().to_string()),
+        shared: true,
+        ld_version: "".to_string(),
+        base_prefix: "".to_string(),
+        executable: PathBuf::new(),
+        calcsize_pointer: None,
+    };
+
+    Ok((interpreter_config, fix_config_map(windows_config_vars())))
+}
+
 /// Examine python's compile flags to pass to cfg by launching
 /// the interpreter and printing variables of interest from
 /// sysconfig.get_config_vars.
@@ -230,7 +662,10 @@ fn get_config_vars(python_path: &Path) -> Result<HashMap<String, String>> {
 fn get_config_vars(_: &Path) -> Result<HashMap<String, String>> {
     // sysconfig is missing all the flags on windows, so we can't actually
     // query the interpreter directly for its build flags.
-    //
+    Ok(fix_config_map(windows_config_vars()))
+}
+
+fn windows_config_vars() -> HashMap<String, String> {
     // For the time being, this is the flags as defined in the python source's
     // PC\pyconfig.h. This won't work correctly if someone has built their
     // python with a modified pyconfig.h - sorry if that is you, you will have
@@ -252,7 +687,7 @@ fn get_config_vars(_: &Path) -> Result<HashMap<String, String>> {
     // map.insert("Py_REF_DEBUG", "1");
     // map.insert("Py_TRACE_REFS", "1");
     // map.insert("COUNT_ALLOCS", 1");
-    Ok(fix_config_map(map))
+    map
 }
 
 fn is_value(key: &str) -> bool {
@@ -300,8 +735,8 @@ fn run_python_script(interpreter: &Path, script: &str) -> Result<String> {
     }
 }
 
-fn get_library_link_name(version: &PythonVersion, ld_version: &str) -> String {
-    if cfg!(target_os = "windows") {
+fn get_library_link_name(version: &PythonVersion, ld_version: &str, target: &Target) -> String {
+    if target.is_windows() {
         let minor_or_empty_string = match version.minor {
             Some(minor) => format!("{}", minor),
             None => String::new(),
@@ -320,23 +755,6 @@ fn get_library_link_name(version: &PythonVersion, ld_version: &str) -> String {
     }
 }
 
-#[cfg(not(target_os = "macos"))]
-#[cfg(not(target_os = "windows"))]
-fn get_rustc_link_lib(config: &InterpreterConfig) -> Result<String> {
-    if config.shared {
-        Ok(format!(
-            "cargo:rustc-link-lib={}",
-            get_library_link_name(&config.version, &config.ld_version)
-        ))
-    } else {
-        Ok(format!(
-            "cargo:rustc-link-lib=static={}",
-            get_library_link_name(&config.version, &config.ld_version)
-        ))
-    }
-}
-
-#[cfg(target_os = "macos")]
 fn get_macos_linkmodel(config: &InterpreterConfig) -> Result<String> {
     let script = r#"
 import sysconfig
@@ -352,34 +770,31 @@ else:
     Ok(out.trim_end().to_owned())
 }
 
-#[cfg(target_os = "macos")]
-fn get_rustc_link_lib(config: &InterpreterConfig) -> Result<String> {
-    // os x can be linked to a framework or static or dynamic, and
-    // Py_ENABLE_SHARED is wrong; framework means shared library
-    match get_macos_linkmodel(config)?.as_ref() {
-        "static" => Ok(format!(
-            "cargo:rustc-link-lib=static={}",
-            get_library_link_name(&config.version, &config.ld_version)
-        )),
-        "shared" => Ok(format!(
-            "cargo:rustc-link-lib={}",
-            get_library_link_name(&config.version, &config.ld_version)
-        )),
-        "framework" => Ok(format!(
-            "cargo:rustc-link-lib={}",
-            get_library_link_name(&config.version, &config.ld_version)
-        )),
-        other => bail!("unknown linkmodel {}", other),
+fn get_rustc_link_lib(config: &InterpreterConfig, target: &Target) -> Result<String> {
+    let link_name = get_library_link_name(&config.version, &config.ld_version, target);
+    if target.is_windows() {
+        // Py_ENABLE_SHARED doesn't seem to be present on windows.
+        return Ok(format!("cargo:rustc-link-lib=pythonXY:{}", link_name));
     }
-}
 
-#[cfg(target_os = "windows")]
-fn get_rustc_link_lib(config: &InterpreterConfig) -> Result<String> {
-    // Py_ENABLE_SHARED doesn't seem to be present on windows.
-    Ok(format!(
-        "cargo:rustc-link-lib=pythonXY:{}",
-        get_library_link_name(&config.version, &config.ld_version)
-    ))
+    // When cross compiling, there is no interpreter to ask for the linkmodel.
+    let shared = if target.os == "macos" && !config.executable.as_os_str().is_empty() {
+        // os x can be linked to a framework or static or dynamic, and
+        // Py_ENABLE_SHARED is wrong; framework means shared library
+        match get_macos_linkmodel(config)?.as_ref() {
+            "static" => false,
+            "shared" | "framework" => true,
+            other => bail!("unknown linkmodel {}", other),
+        }
+    } else {
+        config.shared
+    };
+
+    if shared {
+        Ok(format!("cargo:rustc-link-lib={}", link_name))
+    } else {
+        Ok(format!("cargo:rustc-link-lib=static={}", link_name))
+    }
 }
 
 /// Locate a suitable python interpreter and extract config from it.
@@ -472,7 +887,7 @@ print("calcsize_pointer", struct.calcsize("P"))
     })
 }
 
-fn configure(interpreter_config: &InterpreterConfig) -> Result<String> {
+fn configure(interpreter_config: &InterpreterConfig, target: &Target) -> Result<String> {
     if let Some(minor) = interpreter_config.version.minor {
         if minor < PY3_MIN_MINOR {
             bail!(
@@ -486,11 +901,11 @@ fn configure(interpreter_config: &InterpreterConfig) -> Result<String> {
     check_target_architecture(interpreter_config)?;
 
     let is_extension_module = env::var_os("CARGO_FEATURE_EXTENSION_MODULE").is_some();
-    if !is_extension_module || cfg!(target_os = "windows") {
-        println!("{}", get_rustc_link_lib(&interpreter_config)?);
+    if !is_extension_module || target.is_windows() {
+        println!("{}", get_rustc_link_lib(&interpreter_config, target)?);
         if let Some(libdir) = &interpreter_config.libdir {
             println!("cargo:rustc-link-search=native={}", libdir);
-        } else if cfg!(target_os = "windows") {
+        } else if target.is_windows() {
             println!(
                 "cargo:rustc-link-search=native={}\\libs",
                 interpreter_config.base_prefix
@@ -564,22 +979,21 @@ fn main() -> Result<()> {
     // 1. Setup cfg variables so we can do conditional compilation in this library based on the
     // python interpeter's compilation flags. This is necessary for e.g. matching the right unicode
     // and threading interfaces.  First check if we're cross compiling, if so, we cannot run the
-    // target Python interpreter and have to parse pyconfig.h instead. If we're not cross
-    // compiling, locate the python interpreter based on the PATH, which should work smoothly with
-    // an activated virtualenv, and load from there.
+    // target Python interpreter and have to read its sysconfigdata, its libs directory on
+    // Windows, or its pyconfig.h instead. If we're not cross compiling, locate the python
+    // interpreter based on the PATH, which should work smoothly with an activated virtualenv,
+    // and load from there.
     //
     // If you have troubles with your shell accepting '.' in a var name,
     // try using 'env' (sorry but this isn't our fault - it just has to
     // match the pkg-config package name, which is going to have a . in it).
-    let cross_compiling =
-        env::var("PYO3_CROSS_INCLUDE_DIR").is_ok() && env::var("PYO3_CROSS_LIB_DIR").is_ok();
-    let (interpreter_config, mut config_map) = if cross_compiling {
-        load_cross_compile_info()?
-    } else {
-        find_interpreter_and_get_config()?
+    let target = Target::from_env()?;
+    let (interpreter_config, mut config_map) = match cross_compile_config()? {
+        Some(cross) => load_cross_compile_info(&cross, &target)?,
+        None => find_interpreter_and_get_config()?,
     };
 
-    let flags = configure(&interpreter_config)?;
+    let flags = configure(&interpreter_config, &target)?;
 
     // These flags need to be enabled manually for PyPy, because it does not expose
     // them in `sysconfig.get_config_vars()`
@@ -637,7 +1051,16 @@ fn main() -> Result<()> {
         // TODO: Find out how we can set -undefined dynamic_lookup here (if this is possible)
     }
 
-    let env_vars = ["LD_LIBRARY_PATH", "PATH", "PYTHON_SYS_EXECUTABLE", "LIB"];
+    let env_vars = [
+        "LD_LIBRARY_PATH",
+        "PATH",
+        "PYTHON_SYS_EXECUTABLE",
+        "LIB",
+        "PYO3_CROSS_LIB_DIR",
+        "PYO3_CROSS_PYTHON_VERSION",
+        "PYO3_CROSS_SYSCONFIGDATA",
+        "PYO3_CROSS_INCLUDE_DIR",
+    ];
 
     for var in env_vars.iter() {
         println!("cargo:rerun-if-env-changed={}", var);
@@ -645,3 +1068,203 @@ fn main() -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sample(path: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/build_script")
+            .join(path)
+    }
+
+    fn version(major: u8, minor: u8) -> PythonVersion {
+        PythonVersion {
+            major,
+            minor: Some(minor),
+            implementation: PythonInterpreterKind::CPython,
+        }
+    }
+
+    #[test]
+    fn test_parse_sysconfigdata_vars() {
+        let vars = parse_sysconfigdata_vars(
+            r#"
+# a comment
+build_time_vars = {'EMPTY': '',
+ 'INT': 1,
+ 'NEGATIVE': -1,
+ "DOUBLE": "it's",
+ 'ESCAPES': 'a\\b\'c\n',
+ 'SPLIT': 'one '
+          "'two'",
+ 'PARENTHESIZED': ('three '
+                   'four'),
+}
+"#,
+        )
+        .unwrap();
+        assert_eq!(7, vars.len());
+        assert_eq!("", vars["EMPTY"]);
+        assert_eq!("1", vars["INT"]);
+        assert_eq!("-1", vars["NEGATIVE"]);
+        assert_eq!("it's", vars["DOUBLE"]);
+        assert_eq!("a\\b'c\n", vars["ESCAPES"]);
+        assert_eq!("one 'two'", vars["SPLIT"]);
+        assert_eq!("three four", vars["PARENTHESIZED"]);
+
+        assert!(parse_sysconfigdata_vars("other_vars = {}").is_err());
+        assert!(parse_sysconfigdata_vars("build_time_vars = {'A': 'b").is_err());
+        assert!(parse_sysconfigdata_vars("build_time_vars = {'A': None}").is_err());
+        assert!(parse_sysconfigdata_vars("build_time_vars = {'A' 1}").is_err());
+    }
+
+    #[test]
+    fn test_parse_manylinux_sysconfigdata() {
+        let path = sample("manylinux/lib/python3.8/_sysconfigdata__linux_x86_64-linux-gnu.py");
+        let vars = parse_sysconfigdata(&path).unwrap();
+        assert_eq!(
+            "gcc -pthread -shared -L/opt/_internal/cpython-3.8.5/lib \
+             -Wl,-rpath,/opt/_internal/cpython-3.8.5/lib",
+            vars["BLDSHARED"]
+        );
+        assert_eq!("\\", vars["BOOTSTRAP_HEADERS"]);
+        assert_eq!(
+            "'--prefix=/opt/_internal/cpython-3.8.5' '--disable-shared' \
+             'LDFLAGS=-Wl,-rpath /opt/_internal/cpython-3.8.5/lib'",
+            vars["CONFIG_ARGS"]
+        );
+
+        let lib_dir = sample("manylinux/lib");
+        let (config, config_map) =
+            load_cross_compile_from_sysconfigdata(&vars, &lib_dir, Some(&version(3, 8))).unwrap();
+        assert_eq!(version(3, 8), config.version);
+        assert_eq!(
+            PythonInterpreterKind::CPython,
+            config.version.implementation
+        );
+        assert!(!config.shared);
+        assert_eq!("3.8", config.ld_version);
+        assert_eq!(Some(8), config.calcsize_pointer);
+        assert_eq!(Some(lib_dir.display().to_string()), config.libdir);
+        assert_eq!("1", config_map["WITH_THREAD"]);
+        assert_eq!("0", config_map["Py_DEBUG"]);
+        assert_eq!("0", config_map["Py_UNICODE_WIDE"]);
+        assert!(!config_map.contains_key("Py_UNICODE_SIZE"));
+
+        let err = load_cross_compile_from_sysconfigdata(&vars, &lib_dir, Some(&version(3, 7)))
+            .unwrap_err();
+        assert!(err.to_string().contains("PYO3_CROSS_PYTHON_VERSION=3.7"));
+    }
+
+    #[test]
+    fn test_parse_debug_sysconfigdata() {
+        let path = sample("manylinux/lib/python3.7/_sysconfigdata_m_linux_aarch64-linux-gnu.py");
+        let vars = parse_sysconfigdata(&path).unwrap();
+        let (config, config_map) =
+            load_cross_compile_from_sysconfigdata(&vars, &sample("manylinux/lib"), None).unwrap();
+        assert_eq!(version(3, 7), config.version);
+        assert!(config.shared);
+        // Without LDVERSION, the ABI flags are appended to the version.
+        assert_eq!("3.7m", config.ld_version);
+        assert_eq!("1", config_map["Py_DEBUG"]);
+        assert_eq!("1", config_map["Py_TRACE_REFS"]);
+    }
+
+    #[test]
+    fn test_find_sysconfigdata() {
+        let lib_dir = sample("manylinux/lib");
+        assert_eq!(
+            sample("manylinux/lib/python3.8/_sysconfigdata__linux_x86_64-linux-gnu.py"),
+            find_sysconfigdata(&lib_dir, Some(&version(3, 8))).unwrap()
+        );
+        assert_eq!(
+            sample("manylinux/lib/python3.7/_sysconfigdata_m_linux_aarch64-linux-gnu.py"),
+            find_sysconfigdata(&lib_dir, Some(&version(3, 7))).unwrap()
+        );
+        assert_eq!(
+            sample("manylinux/lib/python3.8/_sysconfigdata__linux_x86_64-linux-gnu.py"),
+            find_sysconfigdata(&sample("manylinux/lib/python3.8"), None).unwrap()
+        );
+
+        let err = find_sysconfigdata(&lib_dir, None).unwrap_err().to_string();
+        assert!(err.contains("several"), err);
+        assert!(err.contains("PYO3_CROSS_PYTHON_VERSION"), err);
+        let err = find_sysconfigdata(&lib_dir, Some(&version(3, 9)))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("for Python 3.9"), err);
+        assert!(err.contains("PYO3_CROSS_SYSCONFIGDATA"), err);
+        assert!(find_sysconfigdata(&sample("windows"), None).is_err());
+    }
+
+    #[test]
+    fn test_windows_libs() {
+        let lib_dir = sample("windows/libs");
+        let (config, config_map) = load_cross_compile_from_windows_libs(&lib_dir, None).unwrap();
+        assert_eq!(version(3, 8), config.version);
+        assert!(config.shared);
+        assert_eq!(Some(lib_dir.display().to_string()), config.libdir);
+        assert_eq!("1", config_map["WITH_THREAD"]);
+
+        let (config, _) =
+            load_cross_compile_from_windows_libs(&lib_dir, Some(&version(3, 8))).unwrap();
+        assert_eq!(version(3, 8), config.version);
+        let err = load_cross_compile_from_windows_libs(&lib_dir, Some(&version(3, 9)))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("python39.lib"), err);
+        assert!(load_cross_compile_from_windows_libs(&sample("manylinux/lib"), None).is_err());
+
+        let target = Target {
+            os: "windows".to_string(),
+            family: "windows".to_string(),
+        };
+        assert_eq!(
+            "cargo:rustc-link-lib=pythonXY:python38",
+            get_rustc_link_lib(&config, &target).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_link_lib_for_target() {
+        let vars = parse_sysconfigdata(&sample(
+            "manylinux/lib/python3.7/_sysconfigdata_m_linux_aarch64-linux-gnu.py",
+        ))
+        .unwrap();
+        let (mut config, _) =
+            load_cross_compile_from_sysconfigdata(&vars, &sample("manylinux/lib"), None).unwrap();
+        let linux = Target {
+            os: "linux".to_string(),
+            family: "unix".to_string(),
+        };
+        let macos = Target {
+            os: "macos".to_string(),
+            family: "unix".to_string(),
+        };
+        assert_eq!(
+            "cargo:rustc-link-lib=python3.7m",
+            get_rustc_link_lib(&config, &linux).unwrap()
+        );
+        assert_eq!(
+            "cargo:rustc-link-lib=python3.7m",
+            get_rustc_link_lib(&config, &macos).unwrap()
+        );
+        config.shared = false;
+        assert_eq!(
+            "cargo:rustc-link-lib=static=python3.7m",
+            get_rustc_link_lib(&config, &linux).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_cross_python_version() {
+        assert_eq!(version(3, 8), parse_cross_python_version("3.8").unwrap());
+        assert_eq!(version(3, 10), parse_cross_python_version("3.10").unwrap());
+        for invalid in &["3", "3.x", "38", ""] {
+            let err = parse_cross_python_version(invalid).unwrap_err();
+            assert!(err.to_string().contains("PYO3_CROSS_PYTHON_VERSION"));
+        }
+    }
+}
@@ -2,7 +2,7 @@
 
 set -e -u -o pipefail
 
-cargo test --features "${FEATURES:-} num-bigint num-complex"
+cargo test --features "${FEATURES:-} num-bigint num-complex async tokio-runtime async-std-runtime"
 (cd pyo3-derive-backend; cargo test)
 
 for example_dir in examples/*; do
@@ -7,7 +7,7 @@ set -ex
 export CARGO_INCREMENTAL=0
 export RUSTFLAGS="-Zpanic_abort_tests -Zprofile -Cpanic=abort -Ccodegen-units=1 -Cinline-threshold=0 -Clink-dead-code -Coverflow-checks=off"
 export RUSTDOCFLAGS="-Cpanic=abort"
-cargo test --features "$FEATURES num-bigint num-complex"
+cargo test --features "$FEATURES num-bigint num-complex async tokio-runtime async-std-runtime"
 
 zip -0 ccov.zip `find . \( -name "pyo3*.gc*" \) -print`;
 ./grcov ccov.zip -s . -t lcov --llvm --branch --ignore-not-existing --ignore "/*" -o lcov.info;
@@ -13,11 +13,13 @@ mod pyfunction;
 mod pyimpl;
 mod pymethod;
 mod pyproto;
+mod test;
 mod utils;
 
-pub use module::{add_fn_to_module, process_functions_in_module, py_init};
+pub use module::{add_fn_to_module, process_functions_in_module, py_init, PyModuleArgs};
 pub use pyclass::{build_py_class, PyClassArgs};
 pub use pyfunction::{build_py_function, PyFunctionAttr};
 pub use pyimpl::{build_py_methods, impl_methods};
 pub use pyproto::build_py_proto;
+pub use test::{build_py_test, PyTestArgs};
 pub use utils::get_doc;
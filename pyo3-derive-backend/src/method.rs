@@ -17,6 +17,11 @@ pub struct FnArg<'a> {
     pub ty: &'a syn::Type,
     pub optional: Option<&'a syn::Type>,
     pub py: bool,
+    /// The argument is a `CancelHandle` of an `async fn`, which is not passed by Python.
+    pub cancel_handle: bool,
+    /// The argument is the `ModuleState` of the module of a function, which is not passed by
+    /// Python.
+    pub module_state: bool,
     pub reference: bool,
 }
 
@@ -57,19 +62,24 @@ pub enum SelfType {
 }
 
 impl SelfType {
-    pub fn receiver(&self, cls: &syn::Type) -> TokenStream {
+    /// Borrows `self` for the method `python_name`, which is recorded in the borrow error.
+    pub fn receiver(&self, cls: &syn::Type, python_name: &syn::Ident) -> TokenStream {
         match self {
             SelfType::Receiver { mutable: false } => {
                 quote! {
                     let _cell = _py.from_borrowed_ptr::<pyo3::PyCell<#cls>>(_slf);
-                    let _ref = _cell.try_borrow()?;
+                    let _ref = _cell
+                        .try_borrow()
+                        .map_err(|e| e.entering(stringify!(#python_name)))?;
                     let _slf = &_ref;
                 }
             }
             SelfType::Receiver { mutable: true } => {
                 quote! {
                     let _cell = _py.from_borrowed_ptr::<pyo3::PyCell<#cls>>(_slf);
-                    let mut _ref = _cell.try_borrow_mut()?;
+                    let mut _ref = _cell
+                        .try_borrow_mut()
+                        .map_err(|e| e.entering(stringify!(#python_name)))?;
                     let _slf = &mut _ref;
                 }
             }
@@ -223,6 +233,8 @@ impl<'a> FnSpec<'a> {
                         ty,
                         optional: opt,
                         py,
+                        cancel_handle: crate::utils::if_type_is_cancel_handle(ty),
+                        module_state: crate::utils::if_type_is_module_state(ty),
                         reference: is_ref(name, ty),
                     });
                 }
@@ -9,13 +9,61 @@ use crate::pymethod::get_arg_names;
 use crate::utils;
 use proc_macro2::{Span, TokenStream};
 use quote::{format_ident, quote};
-use syn::Ident;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{Ident, Token};
+
+/// The arguments of `#[pymodule]`: the name of the module, which defaults to the name of the
+/// function, and `multi_phase` to use multi-phase initialization.
+#[derive(Default)]
+pub struct PyModuleArgs {
+    pub name: Option<Ident>,
+    pub multi_phase: bool,
+}
+
+impl Parse for PyModuleArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut args = PyModuleArgs::default();
+        for ident in Punctuated::<Ident, Token![,]>::parse_terminated(input)? {
+            if ident == "multi_phase" && !args.multi_phase {
+                args.multi_phase = true;
+            } else if args.name.is_none() && ident != "multi_phase" {
+                args.name = Some(ident);
+            } else {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "expected the module name and `multi_phase`",
+                ));
+            }
+        }
+        Ok(args)
+    }
+}
 
 /// Generates the function that is called by the python interpreter to initialize the native
 /// module
-pub fn py_init(fnname: &Ident, name: &Ident, doc: syn::LitStr) -> TokenStream {
+///
+/// With `multi_phase`, it returns the definition of the module, and the module is initialized
+/// when the interpreter executes the module object it created (PEP 489).
+pub fn py_init(fnname: &Ident, name: &Ident, doc: syn::LitStr, multi_phase: bool) -> TokenStream {
     let cb_name = Ident::new(&format!("PyInit_{}", name), Span::call_site());
 
+    let body = if multi_phase {
+        quote! {
+            unsafe extern "C" fn exec(module: *mut pyo3::ffi::PyObject) -> std::os::raw::c_int {
+                MODULE_DEF.exec_module(module, #doc, #fnname)
+            }
+            MODULE_DEF.init_multi_phase(exec)
+        }
+    } else {
+        quote! {
+            match MODULE_DEF.make_module(#doc, #fnname) {
+                Ok(m) => m,
+                Err(e) => e.restore_and_null(unsafe { pyo3::Python::assume_gil_acquired() }),
+            }
+        }
+    };
+
     quote! {
         #[no_mangle]
         #[allow(non_snake_case)]
@@ -25,17 +73,15 @@ pub fn py_init(fnname: &Ident, name: &Ident, doc: syn::LitStr) -> TokenStream {
             use pyo3::derive_utils::ModuleDef;
             const NAME: &'static str = concat!(stringify!(#name), "\0");
             static MODULE_DEF: ModuleDef = unsafe { ModuleDef::new(NAME) };
-            match MODULE_DEF.make_module(#doc, #fnname) {
-                Ok(m) => m,
-                Err(e) => e.restore_and_null(unsafe { pyo3::Python::assume_gil_acquired() }),
-            }
+            #body
         }
     }
 }
 
-/// Finds and takes care of the #[pyfn(...)] in `#[pymodule]`
+/// Finds and takes care of the #[pyfn(...)] and nested #[pymodule] in `#[pymodule]`
 pub fn process_functions_in_module(func: &mut syn::ItemFn) -> syn::Result<()> {
     let mut stmts: Vec<syn::Stmt> = Vec::new();
+    let parent_sig = func.sig.clone();
 
     for stmt in func.block.stmts.iter_mut() {
         if let syn::Stmt::Item(syn::Item::Fn(ref mut func)) = stmt {
@@ -43,11 +89,30 @@ pub fn process_functions_in_module(func: &mut syn::ItemFn) -> syn::Result<()> {
                 extract_pyfn_attrs(&mut func.attrs)
             {
                 let function_to_python = add_fn_to_module(func, python_name, pyfn_attrs)?;
-                let function_wrapper_ident = function_wrapper_ident(&func.sig.ident);
+                let function_in_module_ident = function_in_module_ident(&func.sig.ident);
                 let item: syn::ItemFn = syn::parse_quote! {
                     fn block_wrapper() {
                         #function_to_python
-                        #module_name.add_wrapped(&#function_wrapper_ident)?;
+                        #module_name.add_wrapped(&|py| #function_in_module_ident(py, Some(#module_name)))?;
+                    }
+                };
+                stmts.extend(item.block.stmts.into_iter());
+            } else if let Some(submodule_name) = extract_pymodule_attr(func)? {
+                process_functions_in_module(func)?;
+                let parent = module_argument(&parent_sig)?;
+                let init = &func.sig.ident;
+                let doc = utils::get_doc(&func.attrs, None, false)?;
+                let item: syn::ItemFn = syn::parse_quote! {
+                    fn block_wrapper() {
+                        {
+                            let __pyo3_py = pyo3::PyNativeType::py(#parent);
+                            let __pyo3_submodule =
+                                pyo3::types::PyModule::new(__pyo3_py, stringify!(#submodule_name))?;
+                            __pyo3_submodule.add("__doc__", #doc)?;
+                            __pyo3_submodule.index()?;
+                            #parent.add_submodule(__pyo3_submodule)?;
+                            #init(__pyo3_py, __pyo3_submodule)?;
+                        }
                     }
                 };
                 stmts.extend(item.block.stmts.into_iter());
@@ -60,6 +125,42 @@ pub fn process_functions_in_module(func: &mut syn::ItemFn) -> syn::Result<()> {
     Ok(())
 }
 
+/// Finds and removes a `#[pymodule]` attribute from a function nested in a `#[pymodule]`,
+/// returning the name of the submodule
+fn extract_pymodule_attr(func: &mut syn::ItemFn) -> syn::Result<Option<Ident>> {
+    let position = match func
+        .attrs
+        .iter()
+        .position(|attr| attr.path.is_ident("pymodule"))
+    {
+        Some(position) => position,
+        None => return Ok(None),
+    };
+    let attr = func.attrs.remove(position);
+    if attr.tokens.is_empty() {
+        Ok(Some(func.sig.ident.clone()))
+    } else {
+        attr.parse_args().map(Some)
+    }
+}
+
+/// Returns the name of the module argument of a `#[pymodule]` function, i.e. its second argument
+fn module_argument(sig: &syn::Signature) -> syn::Result<&Ident> {
+    match sig.inputs.iter().nth(1) {
+        Some(syn::FnArg::Typed(syn::PatType { pat, .. })) => match &**pat {
+            syn::Pat::Ident(pat) => Ok(&pat.ident),
+            _ => Err(syn::Error::new_spanned(
+                pat,
+                "the module argument must be an identifier to declare submodules",
+            )),
+        },
+        _ => Err(syn::Error::new_spanned(
+            sig,
+            "expected a module argument to declare submodules",
+        )),
+    }
+}
+
 /// Transforms a rust fn arg parsed with syn into a method::FnArg
 fn wrap_fn_argument<'a>(cap: &'a syn::PatType, name: &'a Ident) -> syn::Result<method::FnArg<'a>> {
     let (mutability, by_ref, ident) = match *cap.pat {
@@ -76,6 +177,8 @@ fn wrap_fn_argument<'a>(cap: &'a syn::PatType, name: &'a Ident) -> syn::Result<m
         ty: &cap.ty,
         optional: opt,
         py,
+        cancel_handle: crate::utils::if_type_is_cancel_handle(&cap.ty),
+        module_state: crate::utils::if_type_is_module_state(&cap.ty),
         reference: method::is_ref(&name, &cap.ty),
     })
 }
@@ -132,6 +235,13 @@ fn function_wrapper_ident(name: &Ident) -> Ident {
     format_ident!("__pyo3_get_function_{}", name)
 }
 
+/// Coordinates the naming of the function creating the function bound to a module, whose
+/// `ModuleState` arguments are the state of that module
+fn function_in_module_ident(name: &Ident) -> Ident {
+    // Make sure this ident matches the one of wrap_pyfunction
+    format_ident!("__pyo3_get_function_in_module_{}", name)
+}
+
 /// Generates python wrapper over a function that allows adding it to a python module as a python
 /// function
 pub fn add_fn_to_module(
@@ -161,6 +271,7 @@ pub fn add_fn_to_module(
     let doc = utils::get_doc(&func.attrs, text_signature, true)?;
 
     let function_wrapper_ident = function_wrapper_ident(&func.sig.ident);
+    let function_in_module_ident = function_in_module_ident(&func.sig.ident);
 
     let spec = method::FnSpec {
         tp: method::FnType::FnStatic,
@@ -176,10 +287,27 @@ pub fn add_fn_to_module(
 
     let python_name = &spec.python_name;
 
-    let wrapper = function_c_wrapper(&func.sig.ident, &spec);
+    pymethod::check_cancel_handle(&spec, func.sig.asyncness.is_some())?;
+    if func.sig.asyncness.is_some() {
+        if let Some(py_arg) = spec.args.iter().find(|arg| arg.py) {
+            return Err(syn::Error::new_spanned(
+                py_arg.ty,
+                "async functions cannot take an argument of type pyo3::Python",
+            ));
+        }
+    }
+
+    let wrapper = function_c_wrapper(&func.sig.ident, &spec, func.sig.asyncness.is_some());
 
     Ok(quote! {
         fn #function_wrapper_ident(py: pyo3::Python) -> pyo3::PyObject {
+            #function_in_module_ident(py, None)
+        }
+
+        fn #function_in_module_ident(
+            py: pyo3::Python,
+            module: Option<&pyo3::types::PyModule>,
+        ) -> pyo3::PyObject {
             #wrapper
 
             let _def = pyo3::class::PyMethodDef {
@@ -194,7 +322,7 @@ pub fn add_fn_to_module(
                     py,
                     pyo3::ffi::PyCFunction_New(
                         Box::into_raw(Box::new(_def.as_method_def())),
-                        ::std::ptr::null_mut()
+                        module.map_or(::std::ptr::null_mut(), pyo3::AsPyPointer::as_ptr)
                     )
                 )
             };
@@ -205,11 +333,14 @@ pub fn add_fn_to_module(
 }
 
 /// Generate static function wrapper (PyCFunction, PyCFunctionWithKeywords)
-fn function_c_wrapper(name: &Ident, spec: &method::FnSpec<'_>) -> TokenStream {
+fn function_c_wrapper(name: &Ident, spec: &method::FnSpec<'_>, is_async: bool) -> TokenStream {
     let names: Vec<Ident> = get_arg_names(&spec);
-    let cb = quote! {
+    let mut cb = quote! {
         #name(#(#names),*)
     };
+    if is_async {
+        cb = pymethod::impl_async_call(spec, cb);
+    }
 
     let body = pymethod::impl_arg_params(spec, cb);
 
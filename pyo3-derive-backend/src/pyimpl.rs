@@ -26,8 +26,10 @@ pub fn impl_methods(ty: &syn::Type, impls: &mut Vec<syn::ImplItem>) -> syn::Resu
     for iimpl in impls.iter_mut() {
         match iimpl {
             syn::ImplItem::Method(meth) => {
-                methods.push(pymethod::gen_py_method(ty, &mut meth.sig, &mut meth.attrs)?);
-                cfg_attributes.push(get_cfg_attributes(&meth.attrs));
+                for def in pymethod::gen_py_method(ty, &mut meth.sig, &mut meth.attrs)? {
+                    methods.push(def);
+                    cfg_attributes.push(get_cfg_attributes(&meth.attrs));
+                }
             }
             syn::ImplItem::Const(konst) => {
                 if let Some(meth) = pymethod::gen_py_const(ty, &konst.ident, &mut konst.attrs)? {
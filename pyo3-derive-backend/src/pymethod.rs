@@ -11,19 +11,52 @@ pub enum PropertyType<'a> {
     Function(&'a FnSpec<'a>),
 }
 
+/// Generates the method definitions of a method; `__call__` has two of them, for `tp_call` and
+/// for vectorcall.
 pub fn gen_py_method(
     cls: &syn::Type,
     sig: &mut syn::Signature,
     meth_attrs: &mut Vec<syn::Attribute>,
-) -> syn::Result<TokenStream> {
+) -> syn::Result<Vec<TokenStream>> {
     check_generic(sig)?;
+    let asyncness = sig.asyncness;
     let spec = FnSpec::parse(sig, &mut *meth_attrs, true)?;
 
-    Ok(match &spec.tp {
+    let is_async_iter_slot = spec.python_name == "__aiter__" || spec.python_name == "__anext__";
+    check_cancel_handle(&spec, asyncness.is_some())?;
+    if let Some(arg) = spec.args.iter().find(|arg| arg.module_state) {
+        return Err(syn::Error::new_spanned(
+            arg.ty,
+            "ModuleState can only be an argument of a #[pyfunction]",
+        ));
+    }
+    if let Some(asyncness) = asyncness {
+        match spec.tp {
+            FnType::Fn(_) if spec.python_name != "__aiter__" => {}
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    asyncness,
+                    "async is only supported for instance methods, except __aiter__",
+                ))
+            }
+        }
+    }
+
+    Ok(vec![match &spec.tp {
+        FnType::Fn(self_ty) if is_async_iter_slot => impl_py_method_def_async_iter(
+            &spec,
+            &impl_wrap_async_iter(cls, &spec, self_ty, asyncness.is_some())?,
+        ),
+        FnType::Fn(self_ty) if asyncness.is_some() => {
+            impl_py_method_def(&spec, &impl_wrap_async(cls, &spec, self_ty)?)
+        }
         FnType::Fn(self_ty) => impl_py_method_def(&spec, &impl_wrap(cls, &spec, self_ty, true)),
         FnType::FnNew => impl_py_method_def_new(&spec, &impl_wrap_new(cls, &spec)),
         FnType::FnCall(self_ty) => {
-            impl_py_method_def_call(&spec, &impl_wrap(cls, &spec, self_ty, false))
+            return Ok(vec![
+                impl_py_method_def_call(&spec, &impl_wrap(cls, &spec, self_ty, false)),
+                impl_py_method_def_vectorcall(&impl_wrap_vectorcall(cls, &spec, self_ty)),
+            ]);
         }
         FnType::FnClass => impl_py_method_def_class(&spec, &impl_wrap_class(cls, &spec)),
         FnType::FnStatic => impl_py_method_def_static(&spec, &impl_wrap_static(cls, &spec)),
@@ -40,7 +73,27 @@ pub fn gen_py_method(
             &spec.doc,
             &impl_wrap_setter(cls, PropertyType::Function(&spec), self_ty)?,
         ),
-    })
+    }])
+}
+
+/// Checks that a `CancelHandle` argument belongs to an `async fn`.
+pub(crate) fn check_cancel_handle(spec: &FnSpec, is_async: bool) -> syn::Result<()> {
+    let mut handles = spec.args.iter().filter(|arg| arg.cancel_handle);
+    if let Some(handle) = handles.next() {
+        if !is_async {
+            return Err(syn::Error::new_spanned(
+                handle.ty,
+                "CancelHandle can only be an argument of an async fn",
+            ));
+        }
+        if let Some(handle) = handles.next() {
+            return Err(syn::Error::new_spanned(
+                handle.ty,
+                "an async fn can only have one CancelHandle argument",
+            ));
+        }
+    }
+    Ok(())
 }
 
 fn check_generic(sig: &syn::Signature) -> syn::Result<()> {
@@ -84,10 +137,57 @@ pub fn impl_wrap(
     noargs: bool,
 ) -> TokenStream {
     let body = impl_call(cls, &spec);
-    let slf = self_ty.receiver(cls);
+    let slf = self_ty.receiver(cls, &spec.python_name);
     impl_wrap_common(cls, spec, noargs, slf, body)
 }
 
+/// Generate function wrapper for an `async fn`, which returns a Python awaitable
+fn impl_wrap_async(
+    cls: &syn::Type,
+    spec: &FnSpec<'_>,
+    self_ty: &SelfType,
+) -> syn::Result<TokenStream> {
+    let slf = async_receiver(cls, spec, self_ty)?;
+    let body = impl_async_call(spec, impl_call(cls, &spec));
+    Ok(impl_wrap_common(cls, spec, true, slf, body))
+}
+
+/// Converts the future returned by the call of an `async fn` into a Python awaitable, which
+/// cancels the `CancelHandle` argument of the function when it is cancelled.
+pub(crate) fn impl_async_call(spec: &FnSpec<'_>, call: TokenStream) -> TokenStream {
+    let cancel_handle = match spec.args.iter().position(|arg| arg.cancel_handle) {
+        Some(pos) => {
+            let arg_name = syn::Ident::new(&format!("arg{}", pos), Span::call_site());
+            quote!(Some(#arg_name.clone()))
+        }
+        None => quote!(None),
+    };
+    quote!(pyo3::asyncio::async_fn_into_py(_py, #cancel_handle, #call))
+}
+
+/// The receiver of an `async fn`, which owns a `Py<Self>` since the future outlives the call.
+fn async_receiver(
+    cls: &syn::Type,
+    spec: &FnSpec<'_>,
+    self_ty: &SelfType,
+) -> syn::Result<TokenStream> {
+    if let SelfType::Receiver { .. } = self_ty {
+        return Err(syn::Error::new_spanned(
+            spec.name,
+            "async methods must take `slf: Py<Self>`, since the future cannot borrow self",
+        ));
+    }
+    if let Some(py_arg) = spec.args.iter().find(|arg| arg.py) {
+        return Err(syn::Error::new_spanned(
+            py_arg.ty,
+            "async methods cannot take an argument of type pyo3::Python",
+        ));
+    }
+    Ok(quote! {
+        let _slf = pyo3::Py::<#cls>::from(_py.from_borrowed_ptr::<pyo3::PyCell<#cls>>(_slf));
+    })
+}
+
 fn impl_wrap_common(
     cls: &syn::Type,
     spec: &FnSpec<'_>,
@@ -134,12 +234,36 @@ fn impl_wrap_common(
     }
 }
 
+/// Generate function wrapper for the vectorcall protocol, which parses the arguments directly
+/// from the array passed by the caller
+pub fn impl_wrap_vectorcall(cls: &syn::Type, spec: &FnSpec<'_>, self_ty: &SelfType) -> TokenStream {
+    let python_name = &spec.python_name;
+    let slf = self_ty.receiver(cls, python_name);
+    let body = impl_arg_params_fastcall(&spec, impl_call(cls, &spec));
+
+    quote! {
+        unsafe extern "C" fn __wrap(
+            _slf: *mut pyo3::ffi::PyObject,
+            _args: *const *mut pyo3::ffi::PyObject,
+            _nargsf: usize,
+            _kwnames: *mut pyo3::ffi::PyObject) -> *mut pyo3::ffi::PyObject
+        {
+            const _LOCATION: &'static str = concat!(
+                stringify!(#cls), ".", stringify!(#python_name), "()");
+            pyo3::callback_body_without_convert!(_py, {
+                #slf
+                pyo3::callback::convert(_py, #body)
+            })
+        }
+    }
+}
+
 /// Generate function wrapper for protocol method (PyCFunction, PyCFunctionWithKeywords)
 pub fn impl_proto_wrap(cls: &syn::Type, spec: &FnSpec<'_>, self_ty: &SelfType) -> TokenStream {
     let python_name = &spec.python_name;
     let cb = impl_call(cls, &spec);
     let body = impl_arg_params(&spec, cb);
-    let slf = self_ty.receiver(cls);
+    let slf = self_ty.receiver(cls, python_name);
 
     quote! {
         #[allow(unused_mut)]
@@ -160,6 +284,65 @@ pub fn impl_proto_wrap(cls: &syn::Type, spec: &FnSpec<'_>, self_ty: &SelfType) -
     }
 }
 
+/// Generate the function of the `am_aiter` or `am_anext` slot for `__aiter__` or `__anext__`,
+/// which may be an `async fn`
+fn impl_wrap_async_iter(
+    cls: &syn::Type,
+    spec: &FnSpec<'_>,
+    self_ty: &SelfType,
+    is_async: bool,
+) -> syn::Result<TokenStream> {
+    let python_name = &spec.python_name;
+    let (py_arg, args) = split_off_python_arg(&spec.args);
+    if !args.is_empty() {
+        return Err(syn::Error::new_spanned(
+            args[0].ty,
+            format!(
+                "{} can only have one argument of type pyo3::Python",
+                python_name
+            ),
+        ));
+    }
+
+    let name = &spec.name;
+    let (slf, body) = if is_async {
+        let slf = async_receiver(cls, spec, self_ty)?;
+        let body = quote! {
+            pyo3::callback::convert(_py, pyo3::asyncio::anext_into_py(_py, #cls::#name(_slf)))
+        };
+        (slf, body)
+    } else {
+        let slf = self_ty.receiver(cls, &spec.python_name);
+        let fncall = if py_arg.is_some() {
+            quote!(#cls::#name(_slf, _py))
+        } else {
+            quote!(#cls::#name(_slf))
+        };
+        let body = if python_name == "__anext__" {
+            // `None` ends the iteration, as with `#[pyproto]`.
+            quote! {
+                let _output: pyo3::class::pyasync::PyIterANextOutput =
+                    pyo3::callback::convert(_py, #fncall)?;
+                pyo3::callback::convert(_py, _output)
+            }
+        } else {
+            quote!(pyo3::callback::convert(_py, #fncall))
+        };
+        (slf, body)
+    };
+
+    Ok(quote! {
+        unsafe extern "C" fn __wrap(_slf: *mut pyo3::ffi::PyObject) -> *mut pyo3::ffi::PyObject {
+            const _LOCATION: &'static str = concat!(
+                stringify!(#cls), ".", stringify!(#python_name), "()");
+            pyo3::callback_body_without_convert!(_py, {
+                #slf
+                #body
+            })
+        }
+    })
+}
+
 /// Generate class method wrapper (PyCFunction, PyCFunctionWithKeywords)
 pub fn impl_wrap_new(cls: &syn::Type, spec: &FnSpec<'_>) -> TokenStream {
     let name = &spec.name;
@@ -298,7 +481,7 @@ pub(crate) fn impl_wrap_getter(
         PropertyType::Function(spec) => (spec.python_name.clone(), impl_call_getter(cls, spec)?),
     };
 
-    let slf = self_ty.receiver(cls);
+    let slf = self_ty.receiver(cls, &python_name);
     Ok(quote! {
         unsafe extern "C" fn __wrap(
             _slf: *mut pyo3::ffi::PyObject, _: *mut ::std::os::raw::c_void) -> *mut pyo3::ffi::PyObject
@@ -351,7 +534,7 @@ pub(crate) fn impl_wrap_setter(
         PropertyType::Function(spec) => (spec.python_name.clone(), impl_call_setter(cls, spec)?),
     };
 
-    let slf = self_ty.receiver(cls);
+    let slf = self_ty.receiver(cls, &python_name);
     Ok(quote! {
         #[allow(unused_mut)]
         unsafe extern "C" fn __wrap(
@@ -384,6 +567,16 @@ fn impl_call(cls: &syn::Type, spec: &FnSpec<'_>) -> TokenStream {
 }
 
 pub fn impl_arg_params(spec: &FnSpec<'_>, body: TokenStream) -> TokenStream {
+    impl_arg_params_common(spec, body, false)
+}
+
+/// Like `impl_arg_params`, for a vectorcall wrapper with the `_args`, `_nargsf` and `_kwnames`
+/// arguments instead of the `_args` tuple and the `_kwargs` dictionary.
+fn impl_arg_params_fastcall(spec: &FnSpec<'_>, body: TokenStream) -> TokenStream {
+    impl_arg_params_common(spec, body, true)
+}
+
+fn impl_arg_params_common(spec: &FnSpec<'_>, body: TokenStream, fastcall: bool) -> TokenStream {
     if spec.args.is_empty() {
         return quote! {
             #body
@@ -393,7 +586,12 @@ pub fn impl_arg_params(spec: &FnSpec<'_>, body: TokenStream) -> TokenStream {
     let mut params = Vec::new();
 
     for arg in spec.args.iter() {
-        if arg.py || spec.is_args(&arg.name) || spec.is_kwargs(&arg.name) {
+        if arg.py
+            || arg.cancel_handle
+            || arg.module_state
+            || spec.is_args(&arg.name)
+            || spec.is_kwargs(&arg.name)
+        {
             continue;
         }
         let name = arg.name;
@@ -426,6 +624,33 @@ pub fn impl_arg_params(spec: &FnSpec<'_>, body: TokenStream) -> TokenStream {
         }
     }
     let num_normal_params = params.len();
+    let parse = if fastcall {
+        quote! {
+            pyo3::derive_utils::parse_fn_args_fastcall(
+                _py,
+                Some(_LOCATION),
+                PARAMS,
+                _args,
+                _nargsf,
+                _kwnames,
+                #accept_args,
+                #accept_kwargs,
+                &mut output
+            )?
+        }
+    } else {
+        quote! {
+            pyo3::derive_utils::parse_fn_args(
+                Some(_LOCATION),
+                PARAMS,
+                _args,
+                _kwargs,
+                #accept_args,
+                #accept_kwargs,
+                &mut output
+            )?
+        }
+    };
     // create array of arguments, and then parse
     quote! {{
         const PARAMS: &'static [pyo3::derive_utils::ParamDescription] = &[
@@ -433,18 +658,8 @@ pub fn impl_arg_params(spec: &FnSpec<'_>, body: TokenStream) -> TokenStream {
         ];
 
         let mut output = [None; #num_normal_params];
-        let mut _args = _args;
-        let mut _kwargs = _kwargs;
-
-        let (_args, _kwargs) = pyo3::derive_utils::parse_fn_args(
-            Some(_LOCATION),
-            PARAMS,
-            _args,
-            _kwargs,
-            #accept_args,
-            #accept_kwargs,
-            &mut output
-        )?;
+
+        let (_args, _kwargs) = #parse;
 
         #(#param_conversion)*
 
@@ -467,8 +682,19 @@ fn impl_arg_param(
             let #arg_name = _py;
         };
     }
+    if arg.cancel_handle {
+        return quote! {
+            let #arg_name = pyo3::asyncio::CancelHandle::new();
+        };
+    }
 
     let ty = arg.ty;
+    if arg.module_state {
+        // The `self` of a function added to a module is the module.
+        return quote! {
+            let #arg_name: #ty = pyo3::types::ModuleState::from_module_ptr(_py, _slf)?;
+        };
+    }
     let name = arg.name;
 
     if spec.is_args(&name) {
@@ -674,6 +900,35 @@ pub fn impl_py_method_def_call(spec: &FnSpec, wrapper: &TokenStream) -> TokenStr
     }
 }
 
+pub fn impl_py_method_def_vectorcall(wrapper: &TokenStream) -> TokenStream {
+    quote! {
+        pyo3::class::PyMethodDefType::Vectorcall({
+            #wrapper
+
+            pyo3::class::PyVectorcallDef {
+                meth: __wrap,
+            }
+        })
+    }
+}
+
+fn impl_py_method_def_async_iter(spec: &FnSpec, wrapper: &TokenStream) -> TokenStream {
+    let variant = if spec.python_name == "__aiter__" {
+        quote!(AIter)
+    } else {
+        quote!(ANext)
+    };
+    quote! {
+        pyo3::class::PyMethodDefType::#variant({
+            #wrapper
+
+            pyo3::class::PyUnarySlotDef {
+                meth: __wrap,
+            }
+        })
+    }
+}
+
 pub(crate) fn impl_py_setter_def(
     python_name: &syn::Ident,
     doc: &syn::LitStr,
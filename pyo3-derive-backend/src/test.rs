@@ -0,0 +1,94 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+//! Code generation for `#[pyo3::test]`
+use crate::utils;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{Ident, Token};
+
+/// The arguments of `#[pyo3::test]`
+#[derive(Default)]
+pub struct PyTestArgs {
+    /// The test does not run concurrently with the other `#[pyo3::test]`s.
+    pub exclusive: bool,
+}
+
+impl Parse for PyTestArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut args = PyTestArgs::default();
+        for ident in Punctuated::<Ident, Token![,]>::parse_terminated(input)? {
+            if ident == "exclusive" && !args.exclusive {
+                args.exclusive = true;
+            } else {
+                return Err(syn::Error::new_spanned(ident, "expected `exclusive`"));
+            }
+        }
+        Ok(args)
+    }
+}
+
+/// Generates a `#[test]` which runs the function with the GIL.
+///
+/// The function is moved into the test, which calls it with a `Python` if it takes one, and
+/// turns an error it returns into a panic. An async function is run with `asyncio::run`, which
+/// cannot run concurrently, so async tests are always exclusive.
+pub fn build_py_test(func: &syn::ItemFn, args: &PyTestArgs) -> syn::Result<TokenStream> {
+    let sig = &func.sig;
+    if !sig.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &sig.generics,
+            "#[pyo3::test] functions cannot be generic",
+        ));
+    }
+
+    let mut takes_py = false;
+    for input in &sig.inputs {
+        match input {
+            syn::FnArg::Typed(cap) if !takes_py && utils::if_type_is_python(&cap.ty) => {
+                takes_py = true
+            }
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    input,
+                    "#[pyo3::test] functions can only take an argument of type pyo3::Python",
+                ))
+            }
+        }
+    }
+
+    let name = &sig.ident;
+    let is_async = sig.asyncness.is_some();
+    let body = if is_async {
+        if takes_py {
+            return Err(syn::Error::new_spanned(
+                &sig.inputs,
+                "async tests cannot take an argument of type pyo3::Python",
+            ));
+        }
+        quote! { pyo3::derive_utils::run_async_test(py, #name()) }
+    } else if takes_py {
+        quote! { #name(py) }
+    } else {
+        quote! { #name() }
+    };
+    let exclusive = args.exclusive || is_async;
+
+    // The attributes, such as `#[should_panic]`, apply to the test.
+    let attrs = &func.attrs;
+    let vis = &func.vis;
+    let mut inner = func.clone();
+    inner.attrs.clear();
+
+    Ok(quote! {
+        #[test]
+        #(#attrs)*
+        #vis fn #name() {
+            #inner
+
+            pyo3::derive_utils::run_test(#exclusive, |py| {
+                pyo3::derive_utils::TestOutput::finish(#body, py)
+            })
+        }
+    })
+}
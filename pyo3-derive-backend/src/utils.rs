@@ -20,6 +20,32 @@ pub fn if_type_is_python(ty: &syn::Type) -> bool {
     }
 }
 
+/// Check if the given type `ty` is `pyo3::types::ModuleState`.
+pub fn if_type_is_module_state(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(ref typath) => typath
+            .path
+            .segments
+            .last()
+            .map(|seg| seg.ident == "ModuleState")
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+/// Check if the given type `ty` is `pyo3::asyncio::CancelHandle`.
+pub fn if_type_is_cancel_handle(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(ref typath) => typath
+            .path
+            .segments
+            .last()
+            .map(|seg| seg.ident == "CancelHandle")
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
 pub fn is_text_signature_attr(attr: &syn::Attribute) -> bool {
     attr.path.is_ident("text_signature")
 }
@@ -5,8 +5,8 @@
 extern crate proc_macro;
 use proc_macro::TokenStream;
 use pyo3_derive_backend::{
-    build_py_class, build_py_function, build_py_methods, build_py_proto, get_doc,
-    process_functions_in_module, py_init, PyClassArgs, PyFunctionAttr,
+    build_py_class, build_py_function, build_py_methods, build_py_proto, build_py_test, get_doc,
+    process_functions_in_module, py_init, PyClassArgs, PyFunctionAttr, PyModuleArgs, PyTestArgs,
 };
 use quote::quote;
 use syn::parse_macro_input;
@@ -17,11 +17,8 @@ use syn::parse_macro_input;
 pub fn pymodule(attr: TokenStream, input: TokenStream) -> TokenStream {
     let mut ast = parse_macro_input!(input as syn::ItemFn);
 
-    let modname = if attr.is_empty() {
-        ast.sig.ident.clone()
-    } else {
-        parse_macro_input!(attr as syn::Ident)
-    };
+    let args = parse_macro_input!(attr as PyModuleArgs);
+    let modname = args.name.unwrap_or_else(|| ast.sig.ident.clone());
 
     if let Err(err) = process_functions_in_module(&mut ast) {
         return err.to_compile_error().into();
@@ -32,7 +29,7 @@ pub fn pymodule(attr: TokenStream, input: TokenStream) -> TokenStream {
         Err(err) => return err.to_compile_error().into(),
     };
 
-    let expanded = py_init(&ast.sig.ident, &modname, doc);
+    let expanded = py_init(&ast.sig.ident, &modname, doc, args.multi_phase);
 
     quote!(
         #ast
@@ -91,3 +88,13 @@ pub fn pyfunction(attr: TokenStream, input: TokenStream) -> TokenStream {
     )
     .into()
 }
+
+/// Runs a test with the GIL, see `pyo3::test`.
+#[proc_macro_attribute]
+pub fn test(attr: TokenStream, input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as syn::ItemFn);
+    let args = parse_macro_input!(attr as PyTestArgs);
+    build_py_test(&ast, &args)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
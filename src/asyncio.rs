@@ -0,0 +1,2113 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Conversion between Rust futures and Python awaitables.
+//!
+//! Python can `await` the future returned by [future_into_py], which is completed when the Rust
+//! future is. The Rust futures are driven by a runtime which must be initialized once with
+//! [init_runtime], e.g. a [TokioRuntime] with the `tokio-runtime` feature or an
+//! [AsyncStdRuntime] with the `async-std-runtime` feature.
+//! Conversely, Rust can `.await` the future returned by [into_future], which is completed when
+//! the Python awaitable is, and a Rust program can run an asyncio event loop with [run]. A
+//! synchronous program can also run a Python coroutine to completion with [run_until_complete].
+//! Streams can be consumed with `async for` using [stream_into_py] or, with the `aclose` and
+//! `athrow` methods of asynchronous generators, [stream_into_async_gen], or a `#[pyclass]` with
+//! `__aiter__` and an `async fn __anext__` in its `#[pymethods]`. Other instance methods in
+//! `#[pymethods]`, such as the `__aenter__` and `__aexit__` of asynchronous context managers, can
+//! also be `async fn`s taking `slf: Py<Self>`, which return awaitables to Python.
+//!
+//! A `#[pyfunction]` can also be an `async fn`. Cancelling its awaitable drops the Rust future,
+//! and the function can take a [CancelHandle] to observe the cancellation of work which does not
+//! yield. Conversely, dropping the future returned by [into_future] cancels the Python awaitable.
+//!
+//! Only available with the `async` feature.
+//!
+//! # Example
+//! With a [Tokio](https://tokio.rs) runtime:
+//! ```ignore
+//! use pyo3::asyncio::{self, TokioRuntime};
+//! use pyo3::prelude::*;
+//! use pyo3::wrap_pyfunction;
+//! use std::time::Duration;
+//!
+//! #[pyfunction]
+//! fn sleep(py: Python, seconds: u64) -> PyResult<&PyAny> {
+//!     asyncio::future_into_py(py, async move {
+//!         tokio::time::delay_for(Duration::from_secs(seconds)).await;
+//!         Ok("slept")
+//!     })
+//! }
+//!
+//! #[pymodule]
+//! fn timers(_py: Python, m: &PyModule) -> PyResult<()> {
+//!     asyncio::init_runtime(TokioRuntime::multi_thread()?)?;
+//!     m.add_wrapped(wrap_pyfunction!(sleep))
+//! }
+//! ```
+use crate::callback::IntoPyCallbackOutput;
+use crate::class::pyasync::{IterANextOutput, PyIterANextOutput};
+use crate::instance::PyNativeType;
+use crate::panic::PanicException;
+use crate::prelude::*;
+use crate::types::PyDict;
+use futures_core::Stream;
+use parking_lot::{const_mutex, Mutex};
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+use std::thread::{self, Thread};
+
+#[cfg(feature = "async-std-runtime")]
+mod async_std_runtime;
+#[cfg(feature = "tokio-runtime")]
+mod tokio_runtime;
+
+#[cfg(feature = "async-std-runtime")]
+pub use self::async_std_runtime::AsyncStdRuntime;
+#[cfg(feature = "tokio-runtime")]
+pub use self::tokio_runtime::TokioRuntime;
+
+/// A future spawned on a [Runtime].
+pub type BoxFuture = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+/// The runtime which drives the Rust futures converted by [future_into_py].
+///
+/// This is implemented for closures, so that any runtime can be initialized with e.g.
+/// `init_runtime(move |future| { handle.spawn(future); })`.
+pub trait Runtime: Send + Sync + 'static {
+    /// Runs `future` to completion in the background.
+    fn spawn(&self, future: BoxFuture);
+}
+
+impl<F> Runtime for F
+where
+    F: Fn(BoxFuture) + Send + Sync + 'static,
+{
+    fn spawn(&self, future: BoxFuture) {
+        self(future)
+    }
+}
+
+static RUNTIME: Mutex<Option<Arc<dyn Runtime>>> = const_mutex(None);
+
+/// Initializes the runtime used by [future_into_py] for the whole process.
+///
+/// Extension modules usually call this in their `#[pymodule]` function. Returns a
+/// `RuntimeError` if the runtime was already initialized.
+pub fn init_runtime(runtime: impl Runtime) -> PyResult<()> {
+    let mut current = RUNTIME.lock();
+    if current.is_some() {
+        return Err(crate::exceptions::RuntimeError::py_err(
+            "the async runtime was already initialized",
+        ));
+    }
+    *current = Some(Arc::new(runtime));
+    Ok(())
+}
+
+/// Initializes a runtime which runs each future in its own thread, unless a runtime was already
+/// initialized.
+///
+/// This is the runtime of the async tests of `#[pyo3::test]`, which don't initialize one.
+pub(crate) fn init_thread_runtime() {
+    let mut current = RUNTIME.lock();
+    if current.is_none() {
+        *current = Some(Arc::new(|future: BoxFuture| {
+            thread::spawn(move || block_on(future));
+        }));
+    }
+}
+
+/// Runs `future` in the current thread, which is parked while the future is pending.
+fn block_on(mut future: BoxFuture) {
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, wake, wake_by_ref, drop);
+    unsafe fn clone(thread: *const ()) -> RawWaker {
+        let thread = Arc::from_raw(thread as *const Thread);
+        let raw = RawWaker::new(Arc::into_raw(thread.clone()) as *const (), &VTABLE);
+        std::mem::forget(thread);
+        raw
+    }
+    unsafe fn wake(thread: *const ()) {
+        Arc::from_raw(thread as *const Thread).unpark();
+    }
+    unsafe fn wake_by_ref(thread: *const ()) {
+        (*(thread as *const Thread)).unpark();
+    }
+    unsafe fn drop(thread: *const ()) {
+        std::mem::drop(Arc::from_raw(thread as *const Thread));
+    }
+
+    let thread = Arc::new(thread::current());
+    let waker =
+        unsafe { Waker::from_raw(RawWaker::new(Arc::into_raw(thread) as *const (), &VTABLE)) };
+    let mut cx = Context::from_waker(&waker);
+    while future.as_mut().poll(&mut cx).is_pending() {
+        thread::park();
+    }
+}
+
+/// `Some` while [run] is running, with its event loop once it is started, which is used by
+/// [into_future] in the threads which don't run an event loop.
+static EVENT_LOOP: Mutex<Option<Option<PyObject>>> = const_mutex(None);
+
+/// The claim of a call of [run] on `EVENT_LOOP`, which is released when the call returns.
+struct RunClaim(());
+
+impl RunClaim {
+    fn acquire() -> PyResult<RunClaim> {
+        let mut event_loop = EVENT_LOOP.lock();
+        if event_loop.is_some() {
+            return Err(crate::exceptions::RuntimeError::py_err(
+                "pyo3::asyncio::run is already running",
+            ));
+        }
+        *event_loop = Some(None);
+        Ok(RunClaim(()))
+    }
+}
+
+impl Drop for RunClaim {
+    fn drop(&mut self) {
+        // The event loop is dropped after the lock is released.
+        let _ = EVENT_LOOP.lock().take();
+    }
+}
+
+/// Runs `future` with an asyncio event loop, and returns its output.
+///
+/// This creates an event loop which runs until `future` completes, like `asyncio.run`. `future`
+/// is spawned on the runtime initialized with [init_runtime], and can call [into_future] in any
+/// thread to await Python awaitables on this event loop. This is useful for Rust programs which
+/// call asynchronous Python code.
+///
+/// Returns a `RuntimeError` if `run` is already running.
+///
+/// # Example
+/// ```ignore
+/// use pyo3::asyncio::{self, TokioRuntime};
+/// use pyo3::prelude::*;
+///
+/// fn main() -> PyResult<()> {
+///     asyncio::init_runtime(TokioRuntime::multi_thread()?)?;
+///     let gil = Python::acquire_gil();
+///     let py = gil.python();
+///     asyncio::run(py, async {
+///         let sleep = {
+///             let gil = Python::acquire_gil();
+///             let asyncio = gil.python().import("asyncio")?;
+///             asyncio::into_future(asyncio.call1("sleep", (1,))?)?
+///         };
+///         sleep.await?;
+///         Ok(())
+///     })
+/// }
+/// ```
+pub fn run<F, T>(py: Python, future: F) -> PyResult<T>
+where
+    F: Future<Output = PyResult<T>> + Send + 'static,
+    T: Send + 'static,
+{
+    let _claim = RunClaim::acquire()?;
+    let output = Arc::new(Mutex::new(None));
+    let slot = output.clone();
+    let start = Py::new(
+        py,
+        Start {
+            future: Some(Box::pin(async move {
+                let value = future.await?;
+                *slot.lock() = Some(value);
+                Ok(())
+            })),
+        },
+    )?;
+    let globals = PyDict::new(py);
+    py.run(
+        "async def main(start):\n    await start()\n",
+        Some(globals),
+        None,
+    )?;
+    let main = globals
+        .get_item("main")
+        .expect("main is defined")
+        .call1((start,))?;
+
+    run_until_complete(py, main)?;
+    let output = output.lock().take().expect("the future completed");
+    Ok(output)
+}
+
+/// Runs a Python `coroutine` in a new event loop until it completes, and returns its result.
+///
+/// This has the semantics of `asyncio.run`: after the coroutine completes, the tasks it left
+/// running are cancelled, the asynchronous generators are finalized and the event loop is closed.
+/// An exception raised by the coroutine, or a `KeyboardInterrupt` raised while the loop runs, is
+/// returned as the error. Unlike [run], this does not require a runtime.
+///
+/// Returns a `RuntimeError` if an event loop is already running in the current thread.
+///
+/// # Example
+/// ```ignore
+/// use pyo3::prelude::*;
+/// use pyo3::types::PyDict;
+///
+/// let gil = Python::acquire_gil();
+/// let py = gil.python();
+/// let globals = PyDict::new(py);
+/// py.run("async def main():\n    return 42\n", Some(globals), None)?;
+/// let main = globals.get_item("main").unwrap().call0()?;
+/// let result = pyo3::asyncio::run_until_complete(py, main)?;
+/// assert_eq!(42, result.extract::<i32>(py)?);
+/// # Ok::<(), PyErr>(())
+/// ```
+pub fn run_until_complete(py: Python, coroutine: &PyAny) -> PyResult<PyObject> {
+    let asyncio = py.import("asyncio")?;
+    #[cfg(Py_3_7)]
+    {
+        Ok(asyncio.call1("run", (coroutine,))?.into())
+    }
+    #[cfg(not(Py_3_7))]
+    {
+        let event_loop = asyncio.call0("new_event_loop")?;
+        asyncio.call1("set_event_loop", (event_loop,))?;
+        let result = run_until_complete_in(event_loop, coroutine);
+        let shutdown = shutdown_loop(py, event_loop);
+        asyncio.call1("set_event_loop", (py.None(),))?;
+        event_loop.call_method0("close")?;
+        let result = result?;
+        shutdown?;
+        Ok(result)
+    }
+}
+
+/// Cancels the tasks left running in `event_loop`, and finalizes its asynchronous generators.
+#[cfg(not(Py_3_7))]
+fn shutdown_loop(py: Python, event_loop: &PyAny) -> PyResult<()> {
+    let asyncio = py.import("asyncio")?;
+    let mut pending = Vec::new();
+    let tasks = asyncio
+        .getattr("Task")?
+        .call_method1("all_tasks", (event_loop,))?;
+    for task in tasks.iter()? {
+        let task = task?;
+        if !task.call_method0("done")?.is_true()? {
+            task.call_method0("cancel")?;
+            pending.push(task);
+        }
+    }
+    if !pending.is_empty() {
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("loop", event_loop)?;
+        kwargs.set_item("return_exceptions", true)?;
+        let gather = asyncio.call(
+            "gather",
+            crate::types::PyTuple::new(py, pending),
+            Some(kwargs),
+        )?;
+        event_loop.call_method1("run_until_complete", (gather,))?;
+    }
+    let shutdown = event_loop.call_method0("shutdown_asyncgens")?;
+    event_loop.call_method1("run_until_complete", (shutdown,))?;
+    Ok(())
+}
+
+/// Runs a Python `coroutine` in `event_loop` until it completes, and returns its result.
+///
+/// This calls `event_loop.run_until_complete(coroutine)`. Unlike [run_until_complete], the event
+/// loop is left open, so it can run other coroutines later, and must be closed by the caller.
+pub fn run_until_complete_in(event_loop: &PyAny, coroutine: &PyAny) -> PyResult<PyObject> {
+    Ok(event_loop
+        .call_method1("run_until_complete", (coroutine,))?
+        .into())
+}
+
+/// Starts the future of [run] in its event loop.
+#[pyclass]
+struct Start {
+    future: Option<Pin<Box<dyn Future<Output = PyResult<()>> + Send>>>,
+}
+
+#[pymethods]
+impl Start {
+    #[call]
+    fn __call__(&mut self, py: Python) -> PyResult<PyObject> {
+        let future = self.future.take().expect("the future is started once");
+        let event_loop = get_running_loop(py)?.expect("run runs an event loop");
+        if let Some(ref mut running) = *EVENT_LOOP.lock() {
+            *running = Some(event_loop.into());
+        }
+        future_into_py(py, future).map(Into::into)
+    }
+}
+
+/// Converts a Rust future into a Python awaitable.
+///
+/// This creates an `asyncio.Future` attached to the running event loop, and spawns `future` on
+/// the runtime initialized with [init_runtime]. When `future` completes, its output is passed to the
+/// `asyncio.Future` with `call_soon_threadsafe`: a value is converted into a Python object, and an
+/// error is raised by the `await` expression. A panic is raised as a [PanicException].
+///
+/// Cancelling the `asyncio.Future`, e.g. by cancelling the task awaiting it, or dropping it before
+/// it is done cancels `future`, which is then dropped without being polled again.
+///
+/// Returns a `RuntimeError` if the runtime was not initialized or if there is no running event
+/// loop in the current thread.
+pub fn future_into_py<F, T>(py: Python, future: F) -> PyResult<&PyAny>
+where
+    F: Future<Output = PyResult<T>> + Send + 'static,
+    T: IntoPy<PyObject>,
+{
+    spawn_into_py(py, future, Arc::new(Mutex::new(Cancellation::default())))
+}
+
+/// Spawns `future` on the runtime, and returns a Python future which completes with its output,
+/// and which sets `cancellation` when it is cancelled or dropped.
+fn spawn_into_py<F, T>(
+    py: Python,
+    future: F,
+    cancellation: Arc<Mutex<Cancellation>>,
+) -> PyResult<&PyAny>
+where
+    F: Future<Output = PyResult<T>> + Send + 'static,
+    T: IntoPy<PyObject>,
+{
+    let runtime = RUNTIME.lock().clone().ok_or_else(|| {
+        crate::exceptions::RuntimeError::py_err(
+            "the async runtime was not initialized with pyo3::asyncio::init_runtime",
+        )
+    })?;
+    let event_loop = get_running_loop(py)?.ok_or_else(no_running_loop)?;
+    let py_future = event_loop.call_method0("create_future")?;
+
+    let canceller = Py::new(
+        py,
+        Canceller {
+            cancellation: cancellation.clone(),
+        },
+    )?;
+    py_future.call_method1("add_done_callback", (canceller,))?;
+
+    // Only a weak reference is kept, so that dropping the Python future cancels the Rust one.
+    let weak_future = py.import("weakref")?.call1("ref", (py_future,))?;
+    runtime.spawn(Box::pin(Task {
+        future: Some(Box::pin(future)),
+        cancellation,
+        event_loop: event_loop.into(),
+        py_future: weak_future.into(),
+    }));
+    Ok(py_future)
+}
+
+/// Converts a Python awaitable, such as a coroutine, into a Rust future.
+///
+/// The awaitable is scheduled with `asyncio.ensure_future` on the event loop running in the
+/// current thread or, in the other threads, on the event loop of the running [run]. The returned
+/// future can be awaited in any thread, e.g. by a task of the runtime initialized with
+/// [init_runtime], and completes with the result of the awaitable or the exception it raised.
+/// Dropping the future before it completes cancels the awaitable.
+///
+/// Returns a `RuntimeError` if there is no running event loop in the current thread and [run]
+/// is not running.
+///
+/// # Example
+/// ```ignore
+/// use pyo3::prelude::*;
+///
+/// #[pyfunction]
+/// fn call_later(py: Python, callback: PyObject) -> PyResult<&PyAny> {
+///     let future = pyo3::asyncio::into_future(callback.call0(py)?.as_ref(py))?;
+///     pyo3::asyncio::future_into_py(py, async move {
+///         let result = future.await?;
+///         Ok(format!("callback returned {}", result))
+///     })
+/// }
+/// ```
+pub fn into_future(
+    awaitable: &PyAny,
+) -> PyResult<impl Future<Output = PyResult<PyObject>> + Send + 'static> {
+    let py = awaitable.py();
+    let state = Arc::new(Mutex::new(Completion::default()));
+    let event_loop: PyObject = match get_running_loop(py)? {
+        Some(event_loop) => {
+            schedule_task(py, awaitable, &state)?;
+            event_loop.into()
+        }
+        None => {
+            let event_loop = match *EVENT_LOOP.lock() {
+                Some(Some(ref event_loop)) => event_loop.clone_ref(py),
+                _ => return Err(no_running_loop()),
+            };
+            let schedule = Schedule {
+                awaitable: Some(awaitable.into()),
+                state: state.clone(),
+            };
+            event_loop.call_method1(py, "call_soon_threadsafe", (Py::new(py, schedule)?,))?;
+            event_loop
+        }
+    };
+    Ok(PyFuture {
+        state,
+        done: false,
+        event_loop,
+    })
+}
+
+/// Returns the event loop running in the current thread.
+fn get_running_loop(py: Python) -> PyResult<Option<&PyAny>> {
+    // Unlike `get_running_loop`, this is available before Python 3.7.
+    let event_loop = py.import("asyncio")?.call0("_get_running_loop")?;
+    Ok(if event_loop.is_none() {
+        None
+    } else {
+        Some(event_loop)
+    })
+}
+
+fn no_running_loop() -> PyErr {
+    crate::exceptions::RuntimeError::py_err("no running asyncio event loop in the current thread")
+}
+
+/// The state shared by a [Task], the [Canceller] of its Python future and its [CancelHandle]s.
+#[derive(Default)]
+struct Cancellation {
+    cancelled: bool,
+    waker: Option<Waker>,
+    /// The wakers of the [Cancelled] futures.
+    observers: Vec<Waker>,
+}
+
+fn cancel(cancellation: &Mutex<Cancellation>) {
+    let (waker, observers) = {
+        let mut cancellation = cancellation.lock();
+        cancellation.cancelled = true;
+        let observers: Vec<Waker> = cancellation.observers.drain(..).collect();
+        (cancellation.waker.take(), observers)
+    };
+    waker.into_iter().chain(observers).for_each(Waker::wake);
+}
+
+/// Observes the cancellation of the Python awaitable of an `async fn`.
+///
+/// An `async fn` of `#[pyfunction]` or `#[pymethods]` can take a `CancelHandle` argument, which
+/// is not passed by Python. When the awaitable is cancelled, the future of the function is dropped
+/// the next time it yields, but work which does not yield, such as a long computation or a
+/// blocking task running in another thread, can check the handle to stop early.
+///
+/// # Example
+/// ```ignore
+/// use pyo3::asyncio::CancelHandle;
+/// use pyo3::prelude::*;
+///
+/// #[pyfunction]
+/// async fn count_primes(below: u64, cancel: CancelHandle) -> PyResult<usize> {
+///     let count = tokio::task::spawn_blocking(move || {
+///         let mut count = 0;
+///         for n in 2..below {
+///             if cancel.is_cancelled() {
+///                 return None;
+///             }
+///             if (2..n).take_while(|d| d * d <= n).all(|d| n % d != 0) {
+///                 count += 1;
+///             }
+///         }
+///         Some(count)
+///     });
+///     Ok(count.await.unwrap().unwrap_or(0))
+/// }
+/// ```
+#[derive(Clone)]
+pub struct CancelHandle {
+    cancellation: Arc<Mutex<Cancellation>>,
+}
+
+impl CancelHandle {
+    #[doc(hidden)]
+    pub fn new() -> CancelHandle {
+        CancelHandle {
+            cancellation: Arc::new(Mutex::new(Cancellation::default())),
+        }
+    }
+
+    /// Returns whether the Python awaitable was cancelled, or dropped before it completed.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.lock().cancelled
+    }
+
+    /// Returns a future which completes when the Python awaitable is cancelled.
+    pub fn cancelled(&self) -> Cancelled {
+        Cancelled {
+            cancellation: self.cancellation.clone(),
+        }
+    }
+}
+
+/// The future returned by [CancelHandle::cancelled].
+pub struct Cancelled {
+    cancellation: Arc<Mutex<Cancellation>>,
+}
+
+impl Future for Cancelled {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        let mut cancellation = self.cancellation.lock();
+        if cancellation.cancelled {
+            return Poll::Ready(());
+        }
+        if !cancellation
+            .observers
+            .iter()
+            .any(|w| w.will_wake(cx.waker()))
+        {
+            cancellation.observers.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
+
+/// The future spawned on the runtime, which drives the Rust future and completes the Python one.
+struct Task<F> {
+    future: Option<Pin<Box<F>>>,
+    cancellation: Arc<Mutex<Cancellation>>,
+    event_loop: PyObject,
+    /// A weak reference to the Python future.
+    py_future: PyObject,
+}
+
+impl<F, T> Future for Task<F>
+where
+    F: Future<Output = PyResult<T>>,
+    T: IntoPy<PyObject>,
+{
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        let this = self.get_mut();
+        let future = match this.future.as_mut() {
+            Some(future) => future,
+            None => return Poll::Ready(()),
+        };
+        {
+            let mut cancellation = this.cancellation.lock();
+            if cancellation.cancelled {
+                this.future = None;
+                return Poll::Ready(());
+            }
+            cancellation.waker = Some(cx.waker().clone());
+        }
+        let output = match panic::catch_unwind(AssertUnwindSafe(|| future.as_mut().poll(cx))) {
+            Ok(Poll::Pending) => return Poll::Pending,
+            Ok(Poll::Ready(output)) => Ok(output),
+            Err(payload) => Err(payload),
+        };
+        this.future = None;
+        this.cancellation.lock().waker = None;
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let result = match output {
+            Ok(output) => output.map(|value| value.into_py(py)),
+            Err(payload) => Err(PanicException::from_panic_payload(py, payload)),
+        };
+        let py_future = match this.py_future.call0(py) {
+            Ok(py_future) if !py_future.is_none(py) => py_future,
+            _ => return Poll::Ready(()),
+        };
+        let set_result = SetResult {
+            py_future,
+            result: Some(result),
+        };
+        // This fails if the event loop was closed, in which case nothing awaits the result.
+        let _ = Py::new(py, set_result).and_then(|set_result| {
+            this.event_loop
+                .call_method1(py, "call_soon_threadsafe", (set_result,))
+        });
+        Poll::Ready(())
+    }
+}
+
+impl<F> Drop for Task<F> {
+    fn drop(&mut self) {
+        // The runtime dropped the task before it completed, e.g. while shutting down, so nothing
+        // will complete the Python future: cancel it instead.
+        if self.future.is_none() || self.cancellation.lock().cancelled {
+            return;
+        }
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        if let Ok(py_future) = self.py_future.call0(py) {
+            if !py_future.is_none(py) {
+                // This fails if the event loop was closed, which cancelled the future.
+                let _ = py_future.getattr(py, "cancel").and_then(|cancel| {
+                    self.event_loop
+                        .call_method1(py, "call_soon_threadsafe", (cancel,))
+                });
+            }
+        }
+    }
+}
+
+/// The done callback of a Python future, which cancels the Rust future when the Python one is
+/// cancelled or dropped.
+#[pyclass]
+struct Canceller {
+    cancellation: Arc<Mutex<Cancellation>>,
+}
+
+#[pymethods]
+impl Canceller {
+    #[call]
+    fn __call__(&self, py_future: &PyAny) -> PyResult<()> {
+        if py_future.call_method0("cancelled")?.is_true()? {
+            cancel(&self.cancellation);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Canceller {
+    fn drop(&mut self) {
+        cancel(&self.cancellation);
+    }
+}
+
+/// Sets the result of a Python future, called in the thread of its event loop.
+#[pyclass]
+struct SetResult {
+    py_future: PyObject,
+    result: Option<PyResult<PyObject>>,
+}
+
+#[pymethods]
+impl SetResult {
+    #[call]
+    fn __call__(&mut self, py: Python) -> PyResult<()> {
+        let py_future = self.py_future.as_ref(py);
+        // The future may have been cancelled since the result was scheduled.
+        if py_future.call_method0("done")?.is_true()? {
+            return Ok(());
+        }
+        match self.result.take() {
+            Some(Ok(value)) => py_future.call_method1("set_result", (value,))?,
+            Some(Err(err)) => py_future.call_method1("set_exception", (err.to_object(py),))?,
+            None => return Ok(()),
+        };
+        Ok(())
+    }
+}
+
+/// The result of a Python task, shared by a [PyFuture] and the [TaskWaker] of the task.
+#[derive(Default)]
+struct Completion {
+    result: Option<PyResult<PyObject>>,
+    waker: Option<Waker>,
+    /// The task, once it is scheduled.
+    task: Option<PyObject>,
+    cancelled: bool,
+}
+
+fn complete(state: &Mutex<Completion>, result: PyResult<PyObject>) {
+    let waker = {
+        let mut state = state.lock();
+        state.result = Some(result);
+        state.waker.take()
+    };
+    if let Some(waker) = waker {
+        waker.wake();
+    }
+}
+
+/// Schedules `awaitable` as a task of the event loop running in the current thread.
+fn schedule_task(py: Python, awaitable: &PyAny, state: &Arc<Mutex<Completion>>) -> PyResult<()> {
+    let task = py.import("asyncio")?.call1("ensure_future", (awaitable,))?;
+    let waker = Py::new(
+        py,
+        TaskWaker {
+            state: state.clone(),
+        },
+    )?;
+    task.call_method1("add_done_callback", (waker,))?;
+    let cancelled = {
+        let mut state = state.lock();
+        state.task = Some(task.into());
+        state.cancelled
+    };
+    if cancelled {
+        task.call_method0("cancel")?;
+    }
+    Ok(())
+}
+
+/// The future returned by [into_future].
+struct PyFuture {
+    state: Arc<Mutex<Completion>>,
+    done: bool,
+    event_loop: PyObject,
+}
+
+impl Future for PyFuture {
+    type Output = PyResult<PyObject>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<PyResult<PyObject>> {
+        let this = self.get_mut();
+        let mut state = this.state.lock();
+        match state.result.take() {
+            Some(result) => {
+                this.done = true;
+                Poll::Ready(result)
+            }
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl Drop for PyFuture {
+    fn drop(&mut self) {
+        let task = {
+            let mut state = self.state.lock();
+            if self.done || state.result.is_some() {
+                return;
+            }
+            state.cancelled = true;
+            state.task.take()
+        };
+        // Otherwise the task is cancelled when it is scheduled.
+        if let Some(task) = task {
+            // The task may belong to an event loop running in another thread.
+            let gil = Python::acquire_gil();
+            let py = gil.python();
+            // This fails if the event loop was closed, which cancelled the task.
+            let _ = task.getattr(py, "cancel").and_then(|cancel| {
+                self.event_loop
+                    .call_method1(py, "call_soon_threadsafe", (cancel,))
+            });
+        }
+    }
+}
+
+/// Schedules an awaitable given to [into_future] in another thread, called in the thread of the
+/// event loop.
+#[pyclass]
+struct Schedule {
+    awaitable: Option<PyObject>,
+    state: Arc<Mutex<Completion>>,
+}
+
+#[pymethods]
+impl Schedule {
+    #[call]
+    fn __call__(&mut self, py: Python) {
+        if let Some(awaitable) = self.awaitable.take() {
+            if let Err(err) = schedule_task(py, awaitable.as_ref(py), &self.state) {
+                complete(&self.state, Err(err));
+            }
+        }
+    }
+}
+
+/// The done callback of a Python task, which passes its result to a [PyFuture].
+#[pyclass]
+struct TaskWaker {
+    state: Arc<Mutex<Completion>>,
+}
+
+#[pymethods]
+impl TaskWaker {
+    #[call]
+    fn __call__(&self, task: &PyAny) {
+        complete(&self.state, task.call_method0("result").map(PyObject::from));
+    }
+}
+
+/// Converts a stream into a Python async iterator, which yields its items in `async for`.
+///
+/// The items are awaited on the runtime initialized with [init_runtime], like the futures of
+/// [future_into_py]. An error item is raised in Python, and the iteration stops when the stream
+/// ends.
+///
+/// # Example
+/// ```ignore
+/// use pyo3::prelude::*;
+///
+/// #[pyfunction]
+/// fn ticks(py: Python, count: u64) -> PyResult<&PyAny> {
+///     let interval = tokio::time::interval(std::time::Duration::from_secs(1));
+///     let ticks = interval.take(count as usize).enumerate();
+///     pyo3::asyncio::stream_into_py(py, tokio::stream::StreamExt::map(ticks, |(i, _)| Ok(i)))
+/// }
+/// ```
+pub fn stream_into_py<S, T>(py: Python, stream: S) -> PyResult<&PyAny>
+where
+    S: Stream<Item = PyResult<T>> + Send + 'static,
+    T: IntoPy<PyObject>,
+{
+    let iter = PyCell::new(
+        py,
+        StreamIter {
+            stream: Arc::new(Mutex::new(Box::pin(IntoPyStream(Box::pin(stream))))),
+        },
+    )?;
+    Ok(iter.as_ref())
+}
+
+type BoxStream = Pin<Box<dyn Stream<Item = PyResult<PyObject>> + Send>>;
+
+/// The async iterator returned by [stream_into_py].
+#[pyclass]
+struct StreamIter {
+    stream: Arc<Mutex<BoxStream>>,
+}
+
+#[pymethods]
+impl StreamIter {
+    fn __aiter__(slf: PyRef<Self>) -> Py<StreamIter> {
+        slf.into()
+    }
+
+    async fn __anext__(slf: Py<Self>) -> PyResult<Option<PyObject>> {
+        let stream = {
+            let gil = Python::acquire_gil();
+            let stream = slf.as_ref(gil.python()).borrow().stream.clone();
+            stream
+        };
+        NextItem { stream }.await.transpose()
+    }
+}
+
+/// Converts the items of a stream into Python objects.
+struct IntoPyStream<S>(Pin<Box<S>>);
+
+impl<S, T> Stream for IntoPyStream<S>
+where
+    S: Stream<Item = PyResult<T>>,
+    T: IntoPy<PyObject>,
+{
+    type Item = PyResult<PyObject>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<PyResult<PyObject>>> {
+        self.0.as_mut().poll_next(cx).map(|item| {
+            item.map(|item| {
+                item.map(|value| {
+                    let gil = Python::acquire_gil();
+                    value.into_py(gil.python())
+                })
+            })
+        })
+    }
+}
+
+/// The next item of the stream of a [StreamIter].
+struct NextItem {
+    stream: Arc<Mutex<BoxStream>>,
+}
+
+impl Future for NextItem {
+    type Output = Option<PyResult<PyObject>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<PyResult<PyObject>>> {
+        self.stream.lock().as_mut().poll_next(cx)
+    }
+}
+
+/// Converts a stream into a Python asynchronous generator.
+///
+/// Unlike the iterator of [stream_into_py], the returned object also has the `aclose` and
+/// `athrow` methods of asynchronous generators. Each `__anext__` polls the stream for exactly one
+/// item, so the stream only produces the items which are consumed. Like a Python asynchronous
+/// generator, the generator is closed, dropping the stream, when:
+/// - the stream ends, or an error item is raised by `__anext__`;
+/// - `aclose()` is awaited;
+/// - `athrow(type[, value[, traceback]])` is awaited, which then raises the exception, e.g.
+///   `GeneratorExit`.
+///
+/// Once the generator is closed, `__anext__` raises `StopAsyncIteration`, and `aclose` and
+/// `athrow` return `None`. While an `__anext__` is awaited, the other methods raise a
+/// `RuntimeError`.
+///
+/// # Example
+/// ```ignore
+/// use pyo3::prelude::*;
+///
+/// #[pyfunction]
+/// fn ticks(py: Python) -> PyResult<&PyAny> {
+///     // The interval is only polled when Python awaits the next tick, so it never runs ahead.
+///     let interval = tokio::time::interval(std::time::Duration::from_secs(1));
+///     let ticks = tokio::stream::StreamExt::map(interval, |tick| Ok(tick.elapsed().as_secs_f64()));
+///     pyo3::asyncio::stream_into_async_gen(py, ticks)
+/// }
+/// ```
+pub fn stream_into_async_gen<S, T>(py: Python, stream: S) -> PyResult<&PyAny>
+where
+    S: Stream<Item = PyResult<T>> + Send + 'static,
+    T: IntoPy<PyObject>,
+{
+    let generator = PyCell::new(
+        py,
+        StreamGenerator {
+            state: Arc::new(Mutex::new(GeneratorState {
+                stream: Some(Box::pin(IntoPyStream(Box::pin(stream)))),
+                running: false,
+            })),
+        },
+    )?;
+    Ok(generator.as_ref())
+}
+
+/// The asynchronous generator returned by [stream_into_async_gen].
+#[pyclass]
+struct StreamGenerator {
+    state: Arc<Mutex<GeneratorState>>,
+}
+
+struct GeneratorState {
+    /// The stream, until the generator is closed.
+    stream: Option<BoxStream>,
+    /// Whether an `__anext__` is awaited.
+    running: bool,
+}
+
+impl StreamGenerator {
+    fn state(slf: &Py<Self>) -> Arc<Mutex<GeneratorState>> {
+        let gil = Python::acquire_gil();
+        let state = slf.as_ref(gil.python()).borrow().state.clone();
+        state
+    }
+
+    /// Takes the stream out of the generator, to close it.
+    fn close(slf: &Py<Self>, method: &str) -> PyResult<Option<BoxStream>> {
+        let state = Self::state(slf);
+        let mut state = state.lock();
+        if state.running {
+            return Err(already_running(method));
+        }
+        Ok(state.stream.take())
+    }
+}
+
+#[pymethods]
+impl StreamGenerator {
+    fn __aiter__(slf: PyRef<Self>) -> Py<StreamGenerator> {
+        slf.into()
+    }
+
+    async fn __anext__(slf: Py<Self>) -> PyResult<Option<PyObject>> {
+        let state = Self::state(&slf);
+        {
+            let mut state = state.lock();
+            if state.running {
+                return Err(already_running("anext"));
+            }
+            if state.stream.is_none() {
+                return Ok(None);
+            }
+            state.running = true;
+        }
+        GeneratorNext { state }.await.transpose()
+    }
+
+    async fn aclose(slf: Py<Self>) -> PyResult<()> {
+        drop(Self::close(&slf, "aclose")?);
+        Ok(())
+    }
+
+    #[args(value = "None", traceback = "None")]
+    async fn athrow(
+        slf: Py<Self>,
+        exc_type: PyObject,
+        value: Option<PyObject>,
+        traceback: Option<PyObject>,
+    ) -> PyResult<()> {
+        match Self::close(&slf, "athrow")? {
+            Some(stream) => drop(stream),
+            None => return Ok(()),
+        }
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        Err(thrown_exception(
+            exc_type.as_ref(py),
+            value.as_ref().map(|value| value.as_ref(py)),
+            traceback.as_ref().map(|traceback| traceback.as_ref(py)),
+        )?)
+    }
+}
+
+fn already_running(method: &str) -> PyErr {
+    crate::exceptions::RuntimeError::py_err(format!(
+        "{}(): asynchronous generator is already running",
+        method
+    ))
+}
+
+/// Creates the exception of `athrow(exc_type, value, traceback)`, following the rules of the
+/// `throw` method of Python generators.
+fn thrown_exception(
+    exc_type: &PyAny,
+    value: Option<&PyAny>,
+    traceback: Option<&PyAny>,
+) -> PyResult<PyErr> {
+    let exc = match exc_type.downcast::<crate::types::PyType>() {
+        Ok(ty) => match value {
+            Some(value) if ty.is_instance(value)? => value,
+            Some(value) => ty.call1((value,))?,
+            None => ty.call0()?,
+        },
+        Err(_) => exc_type,
+    };
+    if let Some(traceback) = traceback {
+        if exc.is_instance_of::<crate::exceptions::BaseException>()? {
+            exc.call_method1("with_traceback", (traceback,))?;
+        }
+    }
+    Ok(PyErr::from_instance(exc))
+}
+
+/// The next item of the stream of a [StreamGenerator], which closes it if the stream ends or
+/// fails.
+struct GeneratorNext {
+    state: Arc<Mutex<GeneratorState>>,
+}
+
+impl Future for GeneratorNext {
+    type Output = Option<PyResult<PyObject>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<PyResult<PyObject>>> {
+        let mut state = self.state.lock();
+        let item = match state.stream.as_mut() {
+            Some(stream) => match stream.as_mut().poll_next(cx) {
+                Poll::Ready(item) => item,
+                Poll::Pending => return Poll::Pending,
+            },
+            None => None,
+        };
+        match item {
+            Some(Ok(_)) => (),
+            _ => state.stream = None,
+        }
+        Poll::Ready(item)
+    }
+}
+
+impl Drop for GeneratorNext {
+    fn drop(&mut self) {
+        self.state.lock().running = false;
+    }
+}
+
+/// Converts the future of an `async fn` of `#[pyfunction]` or `#[pymethods]` into a Python
+/// awaitable, which cancels `cancel_handle` when it is cancelled.
+#[doc(hidden)]
+pub fn async_fn_into_py<F>(
+    py: Python,
+    cancel_handle: Option<CancelHandle>,
+    future: F,
+) -> PyResult<&PyAny>
+where
+    F: Future + Send + 'static,
+    F::Output: IntoPyCallbackOutput<PyObject>,
+{
+    let cancellation = cancel_handle
+        .map(|handle| handle.cancellation)
+        .unwrap_or_default();
+    let future = async move {
+        let output = future.await;
+        let gil = Python::acquire_gil();
+        crate::callback::convert(gil.python(), output)
+    };
+    spawn_into_py(py, future, cancellation)
+}
+
+/// Converts the future of an `async fn __anext__` into a Python awaitable, which raises
+/// `StopAsyncIteration` when the future returns `None`.
+#[doc(hidden)]
+pub fn anext_into_py<F>(py: Python, future: F) -> PyResult<&PyAny>
+where
+    F: Future + Send + 'static,
+    F::Output: IntoPyCallbackOutput<PyIterANextOutput>,
+{
+    future_into_py(py, async move {
+        let output = future.await;
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        match crate::callback::convert(py, output)? {
+            IterANextOutput::Yield(item) => Ok(item),
+            IterANextOutput::Return(value) => Err(PyErr::new_with_gil::<
+                crate::exceptions::StopAsyncIteration,
+                _,
+            >(py, (value,))),
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        block_on, future_into_py, init_thread_runtime, into_future, run_until_complete,
+        run_until_complete_in, stream_into_async_gen, stream_into_py, CancelHandle, RunClaim,
+        EVENT_LOOP,
+    };
+    use crate::exceptions::{RuntimeError, ValueError};
+    use crate::instance::PyNativeType;
+    use crate::prelude::*;
+    use crate::types::{IntoPyDict, PyDict};
+    use crate::wrap_pyfunction;
+    use futures_core::Stream;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::{mpsc, Arc};
+    use std::task::{Context, Poll};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    fn set_thread_runtime() {
+        init_thread_runtime();
+        // Finalizing the interpreter waits for the thread which first imported `threading`,
+        // which never happens if that thread is the one which initialized the interpreter, so
+        // import it in a thread which releases its state.
+        crate::prepare_freethreaded_python();
+        thread::spawn(|| {
+            let gil = Python::acquire_gil();
+            gil.python().import("threading").unwrap();
+        })
+        .join()
+        .unwrap();
+    }
+
+    /// Completes after `duration`.
+    struct Sleep {
+        duration: Duration,
+        done: Option<Arc<AtomicBool>>,
+    }
+
+    impl Future for Sleep {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+            match self.done {
+                Some(ref done) if done.load(Ordering::SeqCst) => Poll::Ready(()),
+                Some(_) => Poll::Pending,
+                None => {
+                    let done = Arc::new(AtomicBool::new(false));
+                    self.done = Some(done.clone());
+                    let (duration, waker) = (self.duration, cx.waker().clone());
+                    thread::spawn(move || {
+                        thread::sleep(duration);
+                        done.store(true, Ordering::SeqCst);
+                        waker.wake();
+                    });
+                    Poll::Pending
+                }
+            }
+        }
+    }
+
+    /// Never completes, and reports when it is dropped.
+    struct Forever(mpsc::Sender<()>);
+
+    impl Future for Forever {
+        type Output = PyResult<()>;
+
+        fn poll(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<PyResult<()>> {
+            Poll::Pending
+        }
+    }
+
+    impl Drop for Forever {
+        fn drop(&mut self) {
+            let _ = self.0.send(());
+        }
+    }
+
+    #[pyfunction]
+    fn delayed(py: Python, value: i32, millis: u64) -> PyResult<&PyAny> {
+        future_into_py(py, async move {
+            Sleep {
+                duration: Duration::from_millis(millis),
+                done: None,
+            }
+            .await;
+            if value < 0 {
+                Err(ValueError::py_err(format!("negative value: {}", value)))
+            } else {
+                Ok(value)
+            }
+        })
+    }
+
+    #[test]
+    fn test_await() {
+        set_thread_runtime();
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let locals = [("delayed", wrap_pyfunction!(delayed)(py))].into_py_dict(py);
+        py.run(
+            r#"
+import asyncio
+
+async def main():
+    assert await delayed(42, 10) == 42
+    try:
+        await delayed(-1, 0)
+    except ValueError as e:
+        assert str(e) == "negative value: -1"
+    else:
+        assert False
+    assert await asyncio.gather(delayed(1, 50), delayed(2, 10)) == [1, 2]
+
+asyncio.run(main())
+"#,
+            Some(locals),
+            None,
+        )
+        .map_err(|e| e.print(py))
+        .unwrap();
+    }
+
+    /// Creates Python futures which never complete, and reports when they are dropped.
+    #[pyclass]
+    struct Forevers {
+        dropped: mpsc::Sender<()>,
+    }
+
+    #[pymethods]
+    impl Forevers {
+        fn create(&self, py: Python) -> PyResult<PyObject> {
+            future_into_py(py, Forever(self.dropped.clone())).map(Into::into)
+        }
+    }
+
+    #[test]
+    fn test_cancel() {
+        set_thread_runtime();
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let (dropped, receiver) = mpsc::channel();
+        let forevers = Py::new(py, Forevers { dropped }).unwrap();
+        let locals = [("forevers", forevers)].into_py_dict(py);
+        py.run(
+            r#"
+import asyncio
+
+async def main():
+    # Cancelling the task which awaits the future.
+    task = asyncio.ensure_future(asyncio.wait_for(forevers.create(), 10))
+    await asyncio.sleep(0.01)
+    task.cancel()
+    try:
+        await task
+    except asyncio.CancelledError:
+        pass
+    else:
+        assert False
+
+    # Dropping the future.
+    forevers.create()
+
+    # Timing out.
+    try:
+        await asyncio.wait_for(forevers.create(), 0.01)
+    except asyncio.TimeoutError:
+        pass
+    else:
+        assert False
+
+asyncio.run(main())
+"#,
+            Some(locals),
+            None,
+        )
+        .map_err(|e| e.print(py))
+        .unwrap();
+        py.allow_threads(move || {
+            for _ in 0..3 {
+                receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+            }
+        });
+    }
+
+    /// Awaits `awaitable` in a Rust task.
+    #[pyfunction]
+    fn rust_await(awaitable: &PyAny) -> PyResult<&PyAny> {
+        future_into_py(awaitable.py(), into_future(awaitable)?)
+    }
+
+    /// Returns whether `awaitable` raises a `ValueError`, checked in a Rust task.
+    #[pyfunction]
+    fn raises_value_error(awaitable: &PyAny) -> PyResult<&PyAny> {
+        let future = into_future(awaitable)?;
+        future_into_py(awaitable.py(), async move {
+            let result = future.await;
+            let gil = Python::acquire_gil();
+            Ok(match result {
+                Ok(_) => false,
+                Err(err) => err.is_instance::<ValueError>(gil.python()),
+            })
+        })
+    }
+
+    #[pyfunction]
+    fn drop_future(awaitable: &PyAny) -> PyResult<()> {
+        into_future(awaitable).map(drop)
+    }
+
+    #[crate::test]
+    async fn test_async_test() -> PyResult<()> {
+        let sleep = Python::with_gil(|py| -> PyResult<_> {
+            let asyncio = py.import("asyncio")?;
+            into_future(asyncio.call1("sleep", (0.001, "slept"))?)
+        })?;
+        let slept = sleep.await?;
+        Python::with_gil(|py| assert_eq!("slept", slept.extract::<String>(py).unwrap()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_into_future() {
+        set_thread_runtime();
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let locals = [
+            ("rust_await", wrap_pyfunction!(rust_await)(py)),
+            (
+                "raises_value_error",
+                wrap_pyfunction!(raises_value_error)(py),
+            ),
+            ("drop_future", wrap_pyfunction!(drop_future)(py)),
+        ]
+        .into_py_dict(py);
+        py.run(
+            r#"
+import asyncio
+
+async def fail(message):
+    await asyncio.sleep(0.01)
+    raise ValueError(message)
+
+async def main():
+    assert await rust_await(asyncio.sleep(0.01, "slept")) == "slept"
+    results = await asyncio.gather(*(rust_await(asyncio.sleep(0.01 * i, i)) for i in range(5)))
+    assert results == list(range(5))
+
+    try:
+        await rust_await(fail("from Python"))
+    except ValueError as e:
+        assert str(e) == "from Python"
+    else:
+        assert False
+    assert await raises_value_error(fail("from Python"))
+    assert not await raises_value_error(asyncio.sleep(0))
+
+    # Dropping the Rust future cancels the awaitable.
+    future = asyncio.get_running_loop().create_future()
+    drop_future(future)
+    await asyncio.sleep(0.01)
+    assert future.cancelled()
+
+asyncio.run(main())
+"#,
+            Some(locals),
+            None,
+        )
+        .map_err(|e| e.print(py))
+        .unwrap();
+    }
+
+    /// Yields the items of a vector.
+    struct Items(std::vec::IntoIter<PyResult<u32>>);
+
+    impl Stream for Items {
+        type Item = PyResult<u32>;
+
+        fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context) -> Poll<Option<PyResult<u32>>> {
+            Poll::Ready(self.0.next())
+        }
+    }
+
+    /// Yields 0, 1 and 2, or fails instead of yielding `fail_at`.
+    #[pyfunction]
+    fn numbers(py: Python, fail_at: Option<u32>) -> PyResult<&PyAny> {
+        let items = (0..3)
+            .map(|i| match fail_at {
+                Some(fail_at) if i == fail_at => Err(ValueError::py_err(format!("item {}", i))),
+                _ => Ok(i),
+            })
+            .collect::<Vec<_>>();
+        stream_into_py(py, Items(items.into_iter()))
+    }
+
+    /// Counts down in an `async fn __anext__`.
+    #[pyclass]
+    struct Countdown {
+        remaining: u32,
+    }
+
+    #[pymethods]
+    impl Countdown {
+        #[new]
+        fn new(remaining: u32) -> Self {
+            Countdown { remaining }
+        }
+
+        fn __aiter__(slf: PyRef<Self>) -> Py<Countdown> {
+            slf.into()
+        }
+
+        async fn __anext__(slf: Py<Self>) -> Option<u32> {
+            Sleep {
+                duration: Duration::from_millis(1),
+                done: None,
+            }
+            .await;
+            let gil = Python::acquire_gil();
+            let mut countdown = slf.as_ref(gil.python()).borrow_mut();
+            if countdown.remaining == 0 {
+                return None;
+            }
+            countdown.remaining -= 1;
+            Some(countdown.remaining)
+        }
+    }
+
+    /// Counts down in a `__anext__` which returns completed asyncio futures.
+    #[pyclass]
+    struct ReadyCountdown {
+        remaining: u32,
+    }
+
+    #[pymethods]
+    impl ReadyCountdown {
+        #[new]
+        fn new(remaining: u32) -> Self {
+            ReadyCountdown { remaining }
+        }
+
+        fn __aiter__(slf: PyRef<Self>) -> Py<ReadyCountdown> {
+            slf.into()
+        }
+
+        fn __anext__(&mut self, py: Python) -> PyResult<Option<PyObject>> {
+            if self.remaining == 0 {
+                return Ok(None);
+            }
+            self.remaining -= 1;
+            let event_loop = py.import("asyncio")?.call0("get_running_loop")?;
+            let future = event_loop.call_method0("create_future")?;
+            future.call_method1("set_result", (self.remaining,))?;
+            Ok(Some(future.into()))
+        }
+    }
+
+    #[test]
+    fn test_async_iter() {
+        set_thread_runtime();
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let locals = [
+            ("numbers", wrap_pyfunction!(numbers)(py).to_object(py)),
+            ("Countdown", py.get_type::<Countdown>().to_object(py)),
+            (
+                "ReadyCountdown",
+                py.get_type::<ReadyCountdown>().to_object(py),
+            ),
+        ]
+        .into_py_dict(py);
+        py.run(
+            r#"
+import asyncio
+
+async def main():
+    assert [i async for i in numbers(None)] == [0, 1, 2]
+
+    yielded = []
+    try:
+        async for i in numbers(1):
+            yielded.append(i)
+    except ValueError as e:
+        assert str(e) == "item 1"
+    else:
+        assert False
+    assert yielded == [0]
+
+    assert [i async for i in Countdown(3)] == [2, 1, 0]
+    assert [i async for i in ReadyCountdown(2)] == [1, 0]
+    countdown = Countdown(0)
+    assert countdown.__aiter__() is countdown
+    try:
+        await countdown.__anext__()
+    except StopAsyncIteration:
+        pass
+    else:
+        assert False
+
+asyncio.run(main())
+"#,
+            Some(locals),
+            None,
+        )
+        .map_err(|e| e.print(py))
+        .unwrap();
+    }
+
+    /// Counts the items produced by a [Naturals] stream, and whether it was dropped.
+    #[pyclass]
+    struct Probe {
+        produced: Arc<AtomicUsize>,
+        dropped: Arc<AtomicBool>,
+    }
+
+    #[pymethods]
+    impl Probe {
+        #[getter]
+        fn produced(&self) -> usize {
+            self.produced.load(Ordering::SeqCst)
+        }
+
+        #[getter]
+        fn dropped(&self) -> bool {
+            self.dropped.load(Ordering::SeqCst)
+        }
+    }
+
+    /// Yields 1, 2, 3... or never yields if `pending`.
+    struct Naturals {
+        produced: Arc<AtomicUsize>,
+        dropped: Arc<AtomicBool>,
+        pending: bool,
+    }
+
+    impl Stream for Naturals {
+        type Item = PyResult<usize>;
+
+        fn poll_next(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<Option<PyResult<usize>>> {
+            if self.pending {
+                return Poll::Pending;
+            }
+            Poll::Ready(Some(Ok(self.produced.fetch_add(1, Ordering::SeqCst) + 1)))
+        }
+    }
+
+    impl Drop for Naturals {
+        fn drop(&mut self) {
+            self.dropped.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[pyfunction]
+    fn naturals(py: Python, pending: bool) -> PyResult<(&PyAny, Probe)> {
+        let (produced, dropped) = (
+            Arc::new(AtomicUsize::new(0)),
+            Arc::new(AtomicBool::new(false)),
+        );
+        let stream = Naturals {
+            produced: produced.clone(),
+            dropped: dropped.clone(),
+            pending,
+        };
+        Ok((
+            stream_into_async_gen(py, stream)?,
+            Probe { produced, dropped },
+        ))
+    }
+
+    /// Yields 0, fails, and would yield 2.
+    #[pyfunction]
+    fn failing_gen(py: Python) -> PyResult<&PyAny> {
+        let items = vec![Ok(0), Err(ValueError::py_err("item 1")), Ok(2)];
+        stream_into_async_gen(py, Items(items.into_iter()))
+    }
+
+    #[test]
+    fn test_async_gen() {
+        set_thread_runtime();
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let locals = [
+            ("naturals", wrap_pyfunction!(naturals)(py).to_object(py)),
+            (
+                "failing_gen",
+                wrap_pyfunction!(failing_gen)(py).to_object(py),
+            ),
+        ]
+        .into_py_dict(py);
+        py.run(
+            r#"
+import asyncio
+
+async def assert_closed(gen):
+    try:
+        await gen.__anext__()
+    except StopAsyncIteration:
+        pass
+    else:
+        assert False
+    assert await gen.aclose() is None
+    assert await gen.athrow(ValueError) is None
+
+async def main():
+    # Items are produced lazily, and `aclose` drops the stream.
+    gen, probe = naturals(False)
+    assert gen.__aiter__() is gen
+    assert probe.produced == 0
+    assert await gen.__anext__() == 1
+    await asyncio.sleep(0.01)
+    assert probe.produced == 1
+    async for i in gen:
+        if i == 3:
+            break
+    assert probe.produced == 3
+    assert not probe.dropped
+    assert await gen.aclose() is None
+    assert probe.dropped
+    await assert_closed(gen)
+
+    # Throwing an exception drops the stream, and raises it.
+    for exc, args in [(GeneratorExit, ()), (ValueError, ("boom",)), (ValueError("boom"), ())]:
+        gen, probe = naturals(False)
+        await gen.__anext__()
+        try:
+            await gen.athrow(exc, *args)
+        except BaseException as e:
+            assert isinstance(e, exc if isinstance(exc, type) else type(exc))
+            assert str(e) == ("boom" if exc is not GeneratorExit else "")
+        else:
+            assert False
+        assert probe.dropped
+        await assert_closed(gen)
+
+    # The generator is closed by an error item.
+    gen = failing_gen()
+    assert await gen.__anext__() == 0
+    try:
+        await gen.__anext__()
+    except ValueError as e:
+        assert str(e) == "item 1"
+    else:
+        assert False
+    await assert_closed(gen)
+
+    # The generator cannot be closed while an item is awaited.
+    gen, probe = naturals(True)
+    task = asyncio.ensure_future(gen.__anext__())
+    await asyncio.sleep(0.01)
+    for method in [gen.__anext__, gen.aclose, lambda: gen.athrow(ValueError)]:
+        try:
+            await method()
+        except RuntimeError as e:
+            assert "already running" in str(e)
+        else:
+            assert False
+    assert not probe.dropped
+    task.cancel()
+
+asyncio.run(main())
+"#,
+            Some(locals),
+            None,
+        )
+        .map_err(|e| e.print(py))
+        .unwrap();
+    }
+
+    /// Records the names of the exceptions passed to `__aexit__`, and suppresses `ValueError`s.
+    #[pyclass]
+    struct Connection {
+        exits: Vec<String>,
+    }
+
+    #[pymethods]
+    impl Connection {
+        #[new]
+        fn new() -> Self {
+            Connection { exits: Vec::new() }
+        }
+
+        #[getter]
+        fn exits(&self) -> Vec<String> {
+            self.exits.clone()
+        }
+
+        async fn __aenter__(slf: Py<Self>) -> Py<Self> {
+            slf
+        }
+
+        async fn __aexit__(
+            slf: Py<Self>,
+            exc_type: Option<PyObject>,
+            _exc_value: Option<PyObject>,
+            _traceback: Option<PyObject>,
+        ) -> PyResult<bool> {
+            Sleep {
+                duration: Duration::from_millis(1),
+                done: None,
+            }
+            .await;
+            let gil = Python::acquire_gil();
+            let py = gil.python();
+            let name = match exc_type {
+                Some(exc_type) => exc_type.getattr(py, "__name__")?.extract(py)?,
+                None => "None".to_string(),
+            };
+            let suppress = name == "ValueError";
+            slf.as_ref(py).borrow_mut().exits.push(name);
+            Ok(suppress)
+        }
+    }
+
+    /// Like `Connection`, with methods which return awaitables.
+    #[pyclass]
+    struct ReadyConnection {
+        exits: Vec<String>,
+    }
+
+    #[pymethods]
+    impl ReadyConnection {
+        #[new]
+        fn new() -> Self {
+            ReadyConnection { exits: Vec::new() }
+        }
+
+        #[getter]
+        fn exits(&self) -> Vec<String> {
+            self.exits.clone()
+        }
+
+        fn __aenter__(slf: &PyCell<Self>) -> PyResult<PyObject> {
+            let entered: Py<Self> = slf.into();
+            future_into_py(slf.py(), async move { Ok(entered) }).map(Into::into)
+        }
+
+        fn __aexit__(
+            &mut self,
+            py: Python,
+            exc_type: Option<&PyAny>,
+            _exc_value: Option<&PyAny>,
+            _traceback: Option<&PyAny>,
+        ) -> PyResult<PyObject> {
+            let name = match exc_type {
+                Some(exc_type) => exc_type.getattr("__name__")?.extract()?,
+                None => "None".to_string(),
+            };
+            let suppress = name == "ValueError";
+            self.exits.push(name);
+            future_into_py(py, async move { Ok(suppress) }).map(Into::into)
+        }
+    }
+
+    #[test]
+    fn test_async_context_manager() {
+        set_thread_runtime();
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let locals = [
+            ("Connection", py.get_type::<Connection>()),
+            ("ReadyConnection", py.get_type::<ReadyConnection>()),
+        ]
+        .into_py_dict(py);
+        py.run(
+            r#"
+import asyncio
+
+async def check(conn):
+    async with conn as entered:
+        assert entered is conn
+    async with conn:
+        raise ValueError("suppressed")
+    try:
+        async with conn:
+            raise KeyError("propagated")
+    except KeyError:
+        pass
+    else:
+        assert False
+
+    # `__aexit__` runs when the body is cancelled.
+    async def body():
+        async with conn:
+            await asyncio.sleep(10)
+    try:
+        await asyncio.wait_for(body(), 0.01)
+    except asyncio.TimeoutError:
+        pass
+    else:
+        assert False
+    assert conn.exits == ["None", "ValueError", "KeyError", "CancelledError"], conn.exits
+
+async def main():
+    await check(Connection())
+    await check(ReadyConnection())
+
+asyncio.run(main())
+"#,
+            Some(locals),
+            None,
+        )
+        .map_err(|e| e.print(py))
+        .unwrap();
+    }
+
+    /// Reports events of a test to Rust.
+    #[pyclass]
+    struct Events {
+        sender: mpsc::Sender<()>,
+    }
+
+    fn event_sender(events: &Py<Events>) -> mpsc::Sender<()> {
+        let gil = Python::acquire_gil();
+        let sender = events.as_ref(gil.python()).borrow().sender.clone();
+        sender
+    }
+
+    #[pyfunction]
+    async fn add(a: u32, b: u32) -> u32 {
+        a + b
+    }
+
+    /// Never completes, and reports when its future is dropped.
+    #[pyfunction]
+    async fn wait_forever(events: Py<Events>) -> PyResult<()> {
+        Forever(event_sender(&events)).await
+    }
+
+    /// Reports the cancellation observed by two threads, which poll the handle and await it.
+    #[pyfunction]
+    async fn crunch(events: Py<Events>, cancel: CancelHandle) -> PyResult<()> {
+        let (sender, cancelled) = (event_sender(&events), cancel.clone());
+        thread::spawn(move || {
+            block_on(Box::pin(async move {
+                cancelled.cancelled().await;
+                sender.send(()).unwrap();
+            }))
+        });
+        let sender = event_sender(&events);
+        thread::spawn(move || {
+            let start = Instant::now();
+            while !cancel.is_cancelled() && start.elapsed() < Duration::from_secs(10) {
+                thread::sleep(Duration::from_millis(1));
+            }
+            sender.send(()).unwrap();
+        });
+        Forever(event_sender(&events)).await
+    }
+
+    /// Completes with `None` after `duration`, dropping `future` if it is still pending.
+    struct Timeout<F> {
+        future: Option<Pin<Box<F>>>,
+        sleep: Sleep,
+    }
+
+    impl<F: Future<Output = PyResult<PyObject>>> Future for Timeout<F> {
+        type Output = PyResult<Option<PyObject>>;
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+            if let Some(future) = self.future.as_mut() {
+                if let Poll::Ready(result) = future.as_mut().poll(cx) {
+                    return Poll::Ready(result.map(Some));
+                }
+            }
+            match Pin::new(&mut self.sleep).poll(cx) {
+                Poll::Ready(()) => {
+                    self.future = None;
+                    Poll::Ready(Ok(None))
+                }
+                Poll::Pending => Poll::Pending,
+            }
+        }
+    }
+
+    #[pyfunction]
+    fn with_timeout(awaitable: &PyAny, millis: u64) -> PyResult<&PyAny> {
+        let timeout = Timeout {
+            future: Some(Box::pin(into_future(awaitable)?)),
+            sleep: Sleep {
+                duration: Duration::from_millis(millis),
+                done: None,
+            },
+        };
+        future_into_py(awaitable.py(), timeout)
+    }
+
+    #[test]
+    fn test_cancellation_propagation() {
+        set_thread_runtime();
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let (sender, receiver) = mpsc::channel();
+        let events = Py::new(py, Events { sender }).unwrap();
+        let locals = [
+            ("events", events.to_object(py)),
+            ("add", wrap_pyfunction!(add)(py).to_object(py)),
+            (
+                "wait_forever",
+                wrap_pyfunction!(wait_forever)(py).to_object(py),
+            ),
+            ("crunch", wrap_pyfunction!(crunch)(py).to_object(py)),
+            (
+                "with_timeout",
+                wrap_pyfunction!(with_timeout)(py).to_object(py),
+            ),
+        ]
+        .into_py_dict(py);
+        py.run(
+            r#"
+import asyncio
+
+async def main():
+    assert await add(1, 2) == 3
+
+    # Cancelling a task awaiting a Rust future drops it.
+    task = asyncio.ensure_future(wait_forever(events))
+    await asyncio.sleep(0.01)
+    task.cancel()
+    try:
+        await task
+    except asyncio.CancelledError:
+        pass
+    else:
+        assert False
+
+    # Timing out cancels the handle.
+    try:
+        await asyncio.wait_for(crunch(events), 0.01)
+    except asyncio.TimeoutError:
+        pass
+    else:
+        assert False
+
+    # Dropping the Rust future awaiting a coroutine cancels it.
+    cancelled = asyncio.Event()
+    async def slow():
+        try:
+            await asyncio.sleep(10)
+        except asyncio.CancelledError:
+            cancelled.set()
+            raise
+    assert await with_timeout(slow(), 10) is None
+    await asyncio.wait_for(cancelled.wait(), 1)
+
+asyncio.run(main())
+"#,
+            Some(locals),
+            None,
+        )
+        .map_err(|e| e.print(py))
+        .unwrap();
+        // `wait_forever` and `crunch` dropped their futures, and both threads of `crunch` saw the
+        // cancellation.
+        py.allow_threads(move || {
+            for _ in 0..4 {
+                receiver.recv_timeout(Duration::from_secs(1)).unwrap();
+            }
+        });
+    }
+
+    #[test]
+    fn test_run_until_complete() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let globals = PyDict::new(py);
+        py.run(
+            r#"
+import asyncio
+
+finalized = []
+
+async def double(x):
+    await asyncio.sleep(0.001)
+    return 2 * x
+
+async def ticks():
+    try:
+        while True:
+            yield
+    finally:
+        finalized.append('ticks')
+
+async def left_running():
+    try:
+        await asyncio.sleep(10)
+    finally:
+        finalized.append('left_running')
+
+async def main(base):
+    asyncio.ensure_future(left_running())
+    gen = ticks()
+    await gen.__anext__()
+    results = await asyncio.gather(*(double(x) for x in range(4)))
+    task = asyncio.ensure_future(double(base))
+    return sum(results) + await task
+
+async def interrupted():
+    raise KeyboardInterrupt
+"#,
+            Some(globals),
+            None,
+        )
+        .unwrap();
+        let main = globals.get_item("main").unwrap();
+
+        let result = run_until_complete(py, main.call1((10,)).unwrap()).unwrap();
+        assert_eq!(32, result.extract::<i32>(py).unwrap());
+        // The task left running was cancelled, and the generator finalized.
+        let mut finalized: Vec<String> = globals.get_item("finalized").unwrap().extract().unwrap();
+        finalized.sort();
+        assert_eq!(vec!["left_running", "ticks"], finalized);
+        let interrupted = globals.get_item("interrupted").unwrap();
+        let err = run_until_complete(py, interrupted.call0().unwrap()).unwrap_err();
+        assert!(err.is_instance::<crate::exceptions::KeyboardInterrupt>(py));
+
+        // A loop provided by the caller is left open.
+        let asyncio = py.import("asyncio").unwrap();
+        let event_loop = asyncio.call0("new_event_loop").unwrap();
+        let double = globals.get_item("double").unwrap();
+        for x in 0..2 {
+            let coroutine = double.call1((x,)).unwrap();
+            let result = run_until_complete_in(event_loop, coroutine).unwrap();
+            assert_eq!(2 * x, result.extract::<i32>(py).unwrap());
+        }
+        assert!(!event_loop
+            .call_method0("is_closed")
+            .unwrap()
+            .is_true()
+            .unwrap());
+        event_loop.call_method0("close").unwrap();
+        let coroutine = double.call1((0,)).unwrap();
+        let err = run_until_complete_in(event_loop, coroutine).unwrap_err();
+        assert!(err.is_instance::<RuntimeError>(py));
+        coroutine.call_method0("close").unwrap();
+    }
+
+    #[test]
+    fn test_no_running_loop() {
+        set_thread_runtime();
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let err = future_into_py(py, async { Ok(()) }).unwrap_err();
+        assert!(err.is_instance::<RuntimeError>(py));
+
+        let coroutine = py
+            .eval("__import__('asyncio').sleep(0)", None, None)
+            .unwrap();
+        let err = into_future(coroutine).map(drop).unwrap_err();
+        assert_eq!(
+            "no running asyncio event loop in the current thread",
+            err.to_object(py).as_ref(py).to_string()
+        );
+        coroutine.call_method0("close").unwrap();
+    }
+
+    #[test]
+    fn test_run_claim() {
+        // `run` is claimed before its event loop is started.
+        let claim = RunClaim::acquire().unwrap();
+        assert!(EVENT_LOOP.lock().is_some());
+        assert!(RunClaim::acquire().is_err());
+        // A failed claim leaves the claim of the running `run` alone.
+        assert!(EVENT_LOOP.lock().is_some());
+        drop(claim);
+        assert!(EVENT_LOOP.lock().is_none());
+        drop(RunClaim::acquire().unwrap());
+    }
+}
@@ -0,0 +1,14 @@
+use super::{BoxFuture, Runtime};
+
+/// A [Runtime] which spawns the futures on the global executor of
+/// [async-std](https://async.rs).
+///
+/// Only available with the `async-std-runtime` feature.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AsyncStdRuntime;
+
+impl Runtime for AsyncStdRuntime {
+    fn spawn(&self, future: BoxFuture) {
+        async_std::task::spawn(future);
+    }
+}
@@ -0,0 +1,79 @@
+use super::{BoxFuture, Runtime};
+use std::io;
+use std::thread::{self, JoinHandle};
+use tokio::runtime::{Builder, Handle};
+use tokio::sync::oneshot;
+
+/// A [Runtime] which spawns the futures on a [Tokio](https://tokio.rs) runtime.
+///
+/// The I/O and time drivers of Tokio are enabled if the corresponding features of the `tokio`
+/// crate are.
+///
+/// Only available with the `tokio-runtime` feature.
+pub struct TokioRuntime {
+    handle: Handle,
+    // Keeps the runtime created by `multi_thread` alive.
+    _runtime: Option<tokio::runtime::Runtime>,
+    // Stops and joins the thread running the runtime created by `current_thread`.
+    thread: Option<(oneshot::Sender<()>, JoinHandle<()>)>,
+}
+
+impl TokioRuntime {
+    /// Creates a Tokio runtime which runs the futures on a pool of threads.
+    pub fn multi_thread() -> io::Result<TokioRuntime> {
+        let runtime = Builder::new().threaded_scheduler().enable_all().build()?;
+        Ok(TokioRuntime {
+            handle: runtime.handle().clone(),
+            _runtime: Some(runtime),
+            thread: None,
+        })
+    }
+
+    /// Creates a Tokio runtime which runs the futures on a single thread, started for it.
+    ///
+    /// Dropping the `TokioRuntime` shuts the runtime down, dropping the futures which are still
+    /// pending, and joins the thread.
+    pub fn current_thread() -> io::Result<TokioRuntime> {
+        let mut runtime = Builder::new().basic_scheduler().enable_all().build()?;
+        let handle = runtime.handle().clone();
+        let (stop, stopped) = oneshot::channel();
+        let thread = thread::Builder::new()
+            .name("pyo3-tokio".to_string())
+            .spawn(move || {
+                // Returns when `stop` is sent or dropped.
+                let _ = runtime.block_on(stopped);
+            })?;
+        Ok(TokioRuntime {
+            handle,
+            _runtime: None,
+            thread: Some((stop, thread)),
+        })
+    }
+
+    /// Spawns the futures on an existing Tokio runtime, which must be kept running.
+    pub fn from_handle(handle: Handle) -> TokioRuntime {
+        TokioRuntime {
+            handle,
+            _runtime: None,
+            thread: None,
+        }
+    }
+}
+
+impl Runtime for TokioRuntime {
+    fn spawn(&self, future: BoxFuture) {
+        self.handle.spawn(future);
+    }
+}
+
+impl Drop for TokioRuntime {
+    fn drop(&mut self) {
+        if let Some((stop, thread)) = self.thread.take() {
+            let _ = stop.send(());
+            // The runtime may be dropped by one of its own futures, which cannot wait for itself.
+            if thread.thread().id() != thread::current().id() {
+                let _ = thread.join();
+            }
+        }
+    }
+}
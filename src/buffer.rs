@@ -25,6 +25,12 @@ use std::os::raw;
 use std::pin::Pin;
 use std::{cell, mem, ptr, slice};
 
+#[cfg(feature = "macros")]
+mod export;
+
+#[cfg(feature = "macros")]
+pub use self::export::{ArrayElement, ArrayExport};
+
 /// Allows access to the underlying buffer used by a python object such as `bytes`, `bytearray` or `array.array`.
 // use Pin<Box> because Python expects that the Py_buffer struct has a stable memory address
 #[repr(transparent)]
@@ -172,25 +178,48 @@ impl<'source, T: Element> FromPyObject<'source> for PyBuffer<T> {
 
 impl<T: Element> PyBuffer<T> {
     /// Get the underlying buffer from the specified python object.
+    ///
+    /// Fails with a `BufferError` if the buffer format, including any byte order marker, does
+    /// not describe elements of type `T`.
     pub fn get(obj: &PyAny) -> PyResult<PyBuffer<T>> {
+        Self::get_with_flags(obj, ffi::PyBUF_FULL_RO)
+    }
+
+    /// Get the underlying buffer from the specified python object, requesting write access.
+    ///
+    /// Unlike with [get](PyBuffer::get), the exporter must provide a writable buffer, so that
+    /// [as_mut_slice](PyBuffer::as_mut_slice) and [copy_from_slice](PyBuffer::copy_from_slice)
+    /// succeed. Fails with a `BufferError` for read-only objects such as `bytes`, and like `get`
+    /// if the buffer format does not describe elements of type `T`.
+    pub fn get_writable(obj: &PyAny) -> PyResult<PyBuffer<T>> {
+        Self::get_with_flags(obj, ffi::PyBUF_FULL)
+    }
+
+    fn get_with_flags(obj: &PyAny, flags: raw::c_int) -> PyResult<PyBuffer<T>> {
         unsafe {
             let mut buf = Box::pin(ffi::Py_buffer::new());
             err::error_on_minusone(
                 obj.py(),
-                ffi::PyObject_GetBuffer(obj.as_ptr(), &mut *buf, ffi::PyBUF_FULL_RO),
+                ffi::PyObject_GetBuffer(obj.as_ptr(), &mut *buf, flags),
             )?;
             validate(&buf)?;
             let buf = PyBuffer(buf, PhantomData);
-            // Type Check
-            if mem::size_of::<T>() == buf.item_size()
-                && (buf.0.buf as usize) % mem::align_of::<T>() == 0
-                && T::is_compatible_format(buf.format())
-            {
-                Ok(buf)
+            // Type Check. The data pointer of an empty buffer may be a misaligned placeholder,
+            // which is never dereferenced.
+            if mem::size_of::<T>() != buf.item_size() || !T::is_compatible_format(buf.format()) {
+                Err(exceptions::BufferError::py_err(format!(
+                    "buffer format '{}' with item size {} is not compatible with `{}`",
+                    buf.format().to_string_lossy(),
+                    buf.item_size(),
+                    std::any::type_name::<T>()
+                )))
+            } else if buf.len_bytes() != 0 && (buf.0.buf as usize) % mem::align_of::<T>() != 0 {
+                Err(exceptions::BufferError::py_err(format!(
+                    "buffer is not aligned for `{}`",
+                    std::any::type_name::<T>()
+                )))
             } else {
-                Err(exceptions::BufferError::py_err(
-                    "Incompatible type as buffer",
-                ))
+                Ok(buf)
             }
         }
     }
@@ -334,7 +363,7 @@ impl<T: Element> PyBuffer<T> {
         if self.is_c_contiguous() {
             unsafe {
                 Some(slice::from_raw_parts(
-                    self.0.buf as *mut ReadOnlyCell<T>,
+                    self.slice_ptr::<ReadOnlyCell<T>>(),
                     self.item_count(),
                 ))
             }
@@ -357,7 +386,7 @@ impl<T: Element> PyBuffer<T> {
         if !self.readonly() && self.is_c_contiguous() {
             unsafe {
                 Some(slice::from_raw_parts(
-                    self.0.buf as *mut cell::Cell<T>,
+                    self.slice_ptr::<cell::Cell<T>>(),
                     self.item_count(),
                 ))
             }
@@ -379,7 +408,7 @@ impl<T: Element> PyBuffer<T> {
         if mem::size_of::<T>() == self.item_size() && self.is_fortran_contiguous() {
             unsafe {
                 Some(slice::from_raw_parts(
-                    self.0.buf as *mut ReadOnlyCell<T>,
+                    self.slice_ptr::<ReadOnlyCell<T>>(),
                     self.item_count(),
                 ))
             }
@@ -402,7 +431,7 @@ impl<T: Element> PyBuffer<T> {
         if !self.readonly() && self.is_fortran_contiguous() {
             unsafe {
                 Some(slice::from_raw_parts(
-                    self.0.buf as *mut cell::Cell<T>,
+                    self.slice_ptr::<cell::Cell<T>>(),
                     self.item_count(),
                 ))
             }
@@ -411,6 +440,25 @@ impl<T: Element> PyBuffer<T> {
         }
     }
 
+    /// Returns the data pointer to build a slice of the buffer elements from, which is dangling
+    /// for an empty buffer, as slices require an aligned pointer even if they are empty.
+    fn slice_ptr<U>(&self) -> *mut U {
+        if self.len_bytes() == 0 {
+            ptr::NonNull::dangling().as_ptr()
+        } else {
+            self.0.buf as *mut U
+        }
+    }
+
+    /// Returns whether the buffer elements can be copied to or from a contiguous slice in order
+    /// `fort` with a single `memcpy`.
+    fn is_contiguous_in(&self, fort: u8) -> bool {
+        match fort {
+            b'C' => self.is_c_contiguous(),
+            _ => self.is_fortran_contiguous(),
+        }
+    }
+
     /// Copies the buffer elements to the specified slice.
     /// If the buffer is multi-dimensional, the elements are written in C-style order.
     ///
@@ -438,11 +486,17 @@ impl<T: Element> PyBuffer<T> {
     }
 
     fn copy_to_slice_impl(&self, py: Python, target: &mut [T], fort: u8) -> PyResult<()> {
-        if mem::size_of_val(target) != self.len_bytes() {
-            return Err(exceptions::BufferError::py_err(
-                "Slice length does not match buffer length.",
-            ));
+        self.check_slice_len(target.len())?;
+        if self.len_bytes() == 0 {
+            return Ok(());
+        }
+        if self.is_contiguous_in(fort) {
+            unsafe {
+                ptr::copy_nonoverlapping(self.0.buf as *const T, target.as_mut_ptr(), target.len())
+            };
+            return Ok(());
         }
+        // Strided buffers are copied element by element by Python.
         unsafe {
             err::error_on_minusone(
                 py,
@@ -525,11 +579,17 @@ impl<T: Element> PyBuffer<T> {
         if self.readonly() {
             return buffer_readonly_error();
         }
-        if mem::size_of_val(source) != self.len_bytes() {
-            return Err(exceptions::BufferError::py_err(
-                "Slice length does not match buffer length.",
-            ));
+        self.check_slice_len(source.len())?;
+        if self.len_bytes() == 0 {
+            return Ok(());
         }
+        if self.is_contiguous_in(fort) {
+            unsafe {
+                ptr::copy_nonoverlapping(source.as_ptr(), self.0.buf as *mut T, source.len())
+            };
+            return Ok(());
+        }
+        // Strided buffers are copied element by element by Python.
         unsafe {
             err::error_on_minusone(
                 py,
@@ -543,6 +603,19 @@ impl<T: Element> PyBuffer<T> {
         }
     }
 
+    /// Returns a `BufferError` if a slice of `len` elements cannot hold the buffer elements.
+    fn check_slice_len(&self, len: usize) -> PyResult<()> {
+        if len == self.item_count() {
+            Ok(())
+        } else {
+            Err(exceptions::BufferError::py_err(format!(
+                "slice length {} does not match buffer length {}",
+                len,
+                self.item_count()
+            )))
+        }
+    }
+
     pub fn release(self, _py: Python) {
         // First move self into a ManuallyDrop, so that PyBuffer::drop will
         // never be called. (It would acquire the GIL and call PyBuffer_Release
@@ -623,9 +696,16 @@ impl_element!(f64, Float);
 
 #[cfg(test)]
 mod test {
-    use super::PyBuffer;
+    use super::{Element, PyBuffer};
+    use crate::exceptions::BufferError;
     use crate::ffi;
-    use crate::Python;
+    use crate::types::{IntoPyDict, PyByteArray};
+    use crate::{AsPyRef, Python, ToPyObject};
+    use std::ffi::CStr;
+
+    fn format(format: &[u8]) -> &CStr {
+        CStr::from_bytes_with_nul(format).unwrap()
+    }
 
     #[test]
     fn test_compatible_size() {
@@ -698,4 +778,101 @@ mod test {
 
         assert_eq!(buffer.to_vec(py).unwrap(), [10.0, 11.0, 12.0, 13.0]);
     }
+
+    #[test]
+    fn test_bytearray_buffer() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let bytearray = PyByteArray::new(py, b"abcd");
+        let buffer = PyBuffer::<u8>::get(bytearray).unwrap();
+        assert!(!buffer.readonly());
+        buffer.copy_from_slice(py, b"wxyz").unwrap();
+        assert_eq!(bytearray.to_vec(), b"wxyz");
+
+        let err = buffer.copy_from_slice(py, b"abc").unwrap_err();
+        assert!(err.is_instance::<BufferError>(py));
+        assert_eq!(
+            err.to_object(py).as_ref(py).to_string(),
+            "slice length 3 does not match buffer length 4"
+        );
+        let err = buffer.copy_to_slice(py, &mut [0; 5]).unwrap_err();
+        assert_eq!(
+            err.to_object(py).as_ref(py).to_string(),
+            "slice length 5 does not match buffer length 4"
+        );
+    }
+
+    #[test]
+    fn test_rejected_casts() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let array = py
+            .import("array")
+            .unwrap()
+            .call1("array", ("i", (1, 2, 3)))
+            .unwrap();
+        assert!(PyBuffer::<i32>::get(array).is_ok());
+        // Same size, but a different kind of element.
+        let err = PyBuffer::<f32>::get(array).map(|_| ()).unwrap_err();
+        assert!(err.is_instance::<BufferError>(py));
+        assert_eq!(
+            err.to_object(py).as_ref(py).to_string(),
+            "buffer format 'i' with item size 4 is not compatible with `f32`"
+        );
+        assert!(PyBuffer::<u32>::get(array).is_err());
+        assert!(PyBuffer::<i64>::get(array).is_err());
+        assert!(PyBuffer::<i16>::get(array).is_err());
+
+        // Unsigned bytes are not signed bytes.
+        let bytearray = PyByteArray::new(py, b"abcd");
+        assert!(PyBuffer::<i8>::get(bytearray).is_err());
+        assert!(PyBuffer::<u16>::get(bytearray).is_err());
+
+        // Explicit byte orders are only accepted if they are native.
+        assert!(i32::is_compatible_format(format(b"=i\0")));
+        assert!(i32::is_compatible_format(format(b"@i\0")));
+        #[cfg(target_endian = "little")]
+        {
+            assert!(i32::is_compatible_format(format(b"<i\0")));
+            assert!(!i32::is_compatible_format(format(b">i\0")));
+            assert!(!i32::is_compatible_format(format(b"!i\0")));
+        }
+        assert!(!i32::is_compatible_format(format(b"=I\0")));
+        assert!(!i32::is_compatible_format(format(b"xi\0")));
+    }
+
+    #[test]
+    fn test_strided_copy() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let array = py
+            .import("array")
+            .unwrap()
+            .call1("array", ("d", (0.0, 1.0, 2.0, 3.0, 4.0)))
+            .unwrap();
+        let strided = py
+            .eval(
+                "memoryview(array)[::2]",
+                Some([("array", array)].into_py_dict(py)),
+                None,
+            )
+            .unwrap();
+        let buffer = PyBuffer::<f64>::get(strided).unwrap();
+        assert!(!buffer.is_c_contiguous());
+        assert!(buffer.as_slice(py).is_none());
+        assert_eq!(buffer.strides(), [16]);
+
+        let mut target = [0.0; 3];
+        buffer.copy_to_slice(py, &mut target).unwrap();
+        assert_eq!(target, [0.0, 2.0, 4.0]);
+        buffer.copy_from_slice(py, &[10.0, 12.0, 14.0]).unwrap();
+        assert_eq!(
+            array
+                .call_method0("tolist")
+                .unwrap()
+                .extract::<Vec<f64>>()
+                .unwrap(),
+            [10.0, 1.0, 12.0, 3.0, 14.0]
+        );
+    }
 }
@@ -0,0 +1,239 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Export of Rust arrays through the buffer protocol and `__array_interface__`.
+use super::Element;
+use crate::class::PyBufferProtocol;
+use crate::exceptions;
+use crate::prelude::*;
+use crate::types::{PyDict, PyTuple};
+use crate::{ffi, AsPyPointer};
+use std::any::Any;
+use std::cell::Cell;
+use std::ffi::CStr;
+use std::os::raw::{c_int, c_void};
+use std::{mem, ptr, slice};
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// Element types which can be exported with [ArrayExport].
+///
+/// This trait is sealed: it is implemented for `u8`, `i32`, `i64`, `f32` and `f64`.
+pub trait ArrayElement: Element + private::Sealed + Send + 'static {
+    /// The buffer protocol format of the element type.
+    #[doc(hidden)]
+    const FORMAT: &'static [u8];
+    /// The `__array_interface__` type string of the element type, in native byte order.
+    #[doc(hidden)]
+    const TYPESTR: &'static str;
+}
+
+macro_rules! array_element {
+    ($t:ty, $format:expr, $little:expr, $big:expr) => {
+        impl private::Sealed for $t {}
+
+        impl ArrayElement for $t {
+            const FORMAT: &'static [u8] = $format;
+            #[cfg(target_endian = "little")]
+            const TYPESTR: &'static str = $little;
+            #[cfg(target_endian = "big")]
+            const TYPESTR: &'static str = $big;
+        }
+    };
+}
+
+array_element!(u8, b"B\0", "|u1", "|u1");
+array_element!(i32, b"i\0", "<i4", ">i4");
+array_element!(i64, b"q\0", "<i8", ">i8");
+array_element!(f32, b"f\0", "<f4", ">f4");
+array_element!(f64, b"d\0", "<f8", ">f8");
+
+/// A Python object owning a Rust array, which other Python libraries can access without copying
+/// it.
+///
+/// The array is exposed through the buffer protocol, e.g. to `memoryview`, and through the
+/// `__array_interface__` attribute, so that `numpy.asarray` creates an array sharing its memory
+/// without PyO3 depending on numpy. The `Vec` holding the elements is owned by the object, so it
+/// stays alive as long as any consumer, such as a numpy array created from it, references it.
+///
+/// # Example
+/// ```
+/// use pyo3::buffer::ArrayExport;
+/// use pyo3::prelude::*;
+///
+/// #[pyfunction]
+/// fn identity(py: Python, n: usize) -> PyResult<Py<ArrayExport>> {
+///     let mut values = vec![0.0; n * n];
+///     for i in 0..n {
+///         values[i * n + i] = 1.0;
+///     }
+///     ArrayExport::from_vec(py, values, &[n, n])
+/// }
+///
+/// let gil = Python::acquire_gil();
+/// let py = gil.python();
+/// let identity = pyo3::wrap_pyfunction!(identity)(py);
+/// pyo3::py_run!(py, identity, r#"
+/// view = memoryview(identity(2))
+/// assert view.shape == (2, 2)
+/// assert view.tolist() == [[1.0, 0.0], [0.0, 1.0]]
+/// "#);
+/// ```
+#[pyclass]
+pub struct ArrayExport {
+    /// The `Vec<T>` holding the elements.
+    owner: Box<dyn Any + Send>,
+    /// A pointer to the first element, obtained from `owner` before it was boxed.
+    data: *mut c_void,
+    len_bytes: usize,
+    item_size: usize,
+    format: &'static CStr,
+    typestr: &'static str,
+    shape: Vec<ffi::Py_ssize_t>,
+    strides: Vec<ffi::Py_ssize_t>,
+    readonly: bool,
+}
+
+// The data pointer points into the `Vec` owned by `owner`, which is `Send`.
+unsafe impl Send for ArrayExport {}
+
+impl ArrayExport {
+    /// Creates a writable export of `vec`, with the given shape in C-style (row-major) order.
+    ///
+    /// Returns a `ValueError` if the number of elements implied by `shape` is not the length of
+    /// `vec`. An empty `shape` exports a single scalar value.
+    pub fn from_vec<T: ArrayElement>(
+        py: Python,
+        vec: Vec<T>,
+        shape: &[usize],
+    ) -> PyResult<Py<ArrayExport>> {
+        Py::new(py, ArrayExport::new(vec, shape, false)?)
+    }
+
+    /// Creates a read-only export of `vec`, with the given shape in C-style (row-major) order.
+    ///
+    /// Consumers cannot write to the elements: requesting a writable buffer fails with a
+    /// `BufferError`, and numpy arrays created from the export are not writeable.
+    pub fn from_vec_readonly<T: ArrayElement>(
+        py: Python,
+        vec: Vec<T>,
+        shape: &[usize],
+    ) -> PyResult<Py<ArrayExport>> {
+        Py::new(py, ArrayExport::new(vec, shape, true)?)
+    }
+
+    fn new<T: ArrayElement>(
+        mut vec: Vec<T>,
+        shape: &[usize],
+        readonly: bool,
+    ) -> PyResult<ArrayExport> {
+        let count: usize = shape.iter().product();
+        if count != vec.len() {
+            return Err(exceptions::ValueError::py_err(format!(
+                "shape {:?} requires {} elements, but the vector has {}",
+                shape,
+                count,
+                vec.len()
+            )));
+        }
+        let item_size = mem::size_of::<T>();
+        // C-style strides: the last dimension is contiguous.
+        let mut strides = vec![0; shape.len()];
+        let mut stride = item_size;
+        for (i, &len) in shape.iter().enumerate().rev() {
+            strides[i] = stride as ffi::Py_ssize_t;
+            stride *= len;
+        }
+        Ok(ArrayExport {
+            data: vec.as_mut_ptr() as *mut c_void,
+            len_bytes: item_size * vec.len(),
+            owner: Box::new(vec),
+            item_size,
+            format: CStr::from_bytes_with_nul(T::FORMAT).unwrap(),
+            typestr: T::TYPESTR,
+            shape: shape.iter().map(|&len| len as ffi::Py_ssize_t).collect(),
+            strides,
+            readonly,
+        })
+    }
+
+    /// Returns the elements, or `None` if they are not of type `T`.
+    ///
+    /// The elements are `Cell`s, as consumers of a writable export may modify them.
+    pub fn as_slice<T: ArrayElement>(&self) -> Option<&[Cell<T>]> {
+        let vec = self.owner.downcast_ref::<Vec<T>>()?;
+        unsafe {
+            Some(slice::from_raw_parts(
+                self.data as *const Cell<T>,
+                vec.len(),
+            ))
+        }
+    }
+
+    /// Returns the shape of the array.
+    pub fn shape(&self) -> Vec<usize> {
+        self.shape.iter().map(|&len| len as usize).collect()
+    }
+
+    /// Returns whether the export is read-only.
+    pub fn readonly(&self) -> bool {
+        self.readonly
+    }
+}
+
+#[pymethods]
+impl ArrayExport {
+    /// The numpy array interface, version 3.
+    #[getter]
+    fn __array_interface__(&self, py: Python) -> PyResult<PyObject> {
+        let interface = PyDict::new(py);
+        interface.set_item("version", 3)?;
+        interface.set_item("typestr", self.typestr)?;
+        interface.set_item("shape", PyTuple::new(py, self.shape()))?;
+        interface.set_item("strides", PyTuple::new(py, &self.strides))?;
+        interface.set_item("data", (self.data as usize, self.readonly))?;
+        Ok(interface.into())
+    }
+}
+
+#[pyproto]
+impl PyBufferProtocol for ArrayExport {
+    fn bf_getbuffer(slf: PyRefMut<Self>, view: *mut ffi::Py_buffer, flags: c_int) -> PyResult<()> {
+        if view.is_null() {
+            return Err(exceptions::BufferError::py_err("view is null"));
+        }
+        if slf.readonly && (flags & ffi::PyBUF_WRITABLE) == ffi::PyBUF_WRITABLE {
+            return Err(exceptions::BufferError::py_err("array export is read-only"));
+        }
+        unsafe {
+            (*view).obj = slf.as_ptr();
+            ffi::Py_INCREF((*view).obj);
+            (*view).buf = slf.data;
+            (*view).len = slf.len_bytes as ffi::Py_ssize_t;
+            (*view).readonly = slf.readonly as c_int;
+            (*view).itemsize = slf.item_size as ffi::Py_ssize_t;
+            (*view).format = if (flags & ffi::PyBUF_FORMAT) == ffi::PyBUF_FORMAT {
+                slf.format.as_ptr() as *mut _
+            } else {
+                ptr::null_mut()
+            };
+            (*view).ndim = slf.shape.len() as c_int;
+            (*view).shape = if (flags & ffi::PyBUF_ND) == ffi::PyBUF_ND {
+                slf.shape.as_ptr() as *mut _
+            } else {
+                ptr::null_mut()
+            };
+            (*view).strides = if (flags & ffi::PyBUF_STRIDES) == ffi::PyBUF_STRIDES {
+                slf.strides.as_ptr() as *mut _
+            } else {
+                ptr::null_mut()
+            };
+            (*view).suboffsets = ptr::null_mut();
+            (*view).internal = ptr::null_mut();
+        }
+        Ok(())
+    }
+
+    fn bf_releasebuffer(_slf: PyRefMut<Self>, _view: *mut ffi::Py_buffer) {}
+}
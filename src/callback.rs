@@ -2,7 +2,7 @@
 
 //! Utilities for a Python callable object that invokes a Rust function.
 
-use crate::err::PyResult;
+use crate::err::{PyErr, PyResult};
 use crate::exceptions::OverflowError;
 use crate::ffi::{self, Py_hash_t};
 use crate::IntoPyPointer;
@@ -81,11 +81,11 @@ impl IntoPyCallbackOutput<()> for () {
 
 impl IntoPyCallbackOutput<ffi::Py_ssize_t> for usize {
     #[inline]
-    fn convert(self, _py: Python) -> PyResult<ffi::Py_ssize_t> {
+    fn convert(self, py: Python) -> PyResult<ffi::Py_ssize_t> {
         if self <= (isize::MAX as usize) {
             Ok(self as isize)
         } else {
-            Err(OverflowError::py_err(()))
+            Err(PyErr::new_with_gil::<OverflowError, _>(py, ()))
         }
     }
 }
@@ -185,7 +185,8 @@ where
 /// It sets up the GILPool and converts the output into a Python object. It also restores
 /// any python error returned as an Err variant from the body.
 ///
-/// Finally, any panics inside the callback body will be caught and translated into PanicExceptions.
+/// Finally, any panics inside the callback body will be caught and translated into PanicExceptions
+/// (or whatever the hook registered with `pyo3::panic::set_hook` returns).
 ///
 /// # Safety
 /// This macro assumes the GIL is held. (It makes use of unsafe code, so usage of it is only
@@ -240,18 +241,10 @@ macro_rules! callback_body_without_convert {
             $body
         }) {
             Ok(result) => result,
-            Err(e) => {
-                // Try to format the error in the same way panic does
-                if let Some(string) = e.downcast_ref::<String>() {
-                    Err($crate::panic::PanicException::py_err((string.clone(),)))
-                } else if let Some(s) = e.downcast_ref::<&str>() {
-                    Err($crate::panic::PanicException::py_err((s.to_string(),)))
-                } else {
-                    Err($crate::panic::PanicException::py_err((
-                        "panic from Rust code",
-                    )))
-                }
-            }
+            Err(e) => Err($crate::panic::PanicException::from_panic_payload(
+                pool.python(),
+                e,
+            )),
         };
 
         result.unwrap_or_else(|e| {
@@ -87,7 +87,29 @@ impl PyDescrMethods {
     where
         T: for<'p> PyDescrGetProtocol<'p>,
     {
-        self.tp_descr_get = py_ternarys_func!(PyDescrGetProtocol, T::__get__);
+        unsafe extern "C" fn wrap<T>(
+            slf: *mut ffi::PyObject,
+            instance: *mut ffi::PyObject,
+            owner: *mut ffi::PyObject,
+        ) -> *mut ffi::PyObject
+        where
+            T: for<'p> PyDescrGetProtocol<'p>,
+        {
+            crate::callback_body!(py, {
+                let slf = py.from_borrowed_ptr::<crate::PyCell<T>>(slf);
+                let slf =
+                    <T::Receiver as crate::derive_utils::TryFromPyCell<_>>::try_from_pycell(slf)
+                        .map_err(|e| e.into())?;
+                // `instance` is NULL when the descriptor is accessed on its owner, and `owner`
+                // may be NULL; Python passes `None` to the `__get__` of Python classes instead.
+                let or_none =
+                    |ptr: *mut ffi::PyObject| if ptr.is_null() { ffi::Py_None() } else { ptr };
+                let instance = py.from_borrowed_ptr::<PyAny>(or_none(instance)).extract()?;
+                let owner = py.from_borrowed_ptr::<PyAny>(or_none(owner)).extract()?;
+                T::__get__(slf, instance, owner).convert(py)
+            })
+        }
+        self.tp_descr_get = Some(wrap::<T>);
     }
     pub fn set_descr_set<T>(&mut self)
     where
@@ -3,7 +3,7 @@
 //! Python GC support
 //!
 
-use crate::{ffi, AsPyPointer, PyCell, PyClass, Python};
+use crate::{ffi, AsPyPointer, PyCell, PyClass, PyErr, Python};
 use std::os::raw::{c_int, c_void};
 
 #[repr(transparent)]
@@ -120,7 +120,18 @@ where
         let py = pool.python();
         let slf = py.from_borrowed_ptr::<PyCell<T>>(slf);
 
-        slf.borrow_mut().__clear__();
+        // tp_clear cannot raise, so failures are reported as unraisable.
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            slf.try_borrow_mut()
+                .map(|mut borrow| borrow.__clear__())
+                .map_err(PyErr::from)
+        }));
+        let err = match result {
+            Ok(Ok(())) => return 0,
+            Ok(Err(err)) => err,
+            Err(payload) => crate::panic::PanicException::from_panic_payload(py, payload),
+        };
+        err.write_unraisable(py, Some(slf));
         0
     }
     Some(tp_clear::<T>)
@@ -4,7 +4,7 @@
 
 use crate::callback::IntoPyCallbackOutput;
 use crate::derive_utils::TryFromPyCell;
-use crate::err::PyResult;
+use crate::err::{PyErr, PyResult};
 use crate::{ffi, IntoPy, IntoPyPointer, PyClass, PyObject, Python};
 
 /// Python Iterator Interface.
@@ -109,10 +109,13 @@ pub enum IterNextOutput<T, U> {
 pub type PyIterNextOutput = IterNextOutput<PyObject, PyObject>;
 
 impl IntoPyCallbackOutput<*mut ffi::PyObject> for PyIterNextOutput {
-    fn convert(self, _py: Python) -> PyResult<*mut ffi::PyObject> {
+    fn convert(self, py: Python) -> PyResult<*mut ffi::PyObject> {
         match self {
             IterNextOutput::Yield(o) => Ok(o.into_ptr()),
-            IterNextOutput::Return(opt) => Err(crate::exceptions::StopIteration::py_err((opt,))),
+            IterNextOutput::Return(opt) => Err(PyErr::new_with_gil::<
+                crate::exceptions::StopIteration,
+                _,
+            >(py, (opt,))),
         }
     }
 }
@@ -387,13 +387,19 @@ macro_rules! _call_impl {
 /// Call `slf.try_borrow()?.$fn(...)`
 macro_rules! call_ref {
     ($slf: expr, $fn: ident $(,$raw_args: expr)* $(; $args: expr)*) => {
-        _call_impl!($slf.try_borrow()?, $fn $(,$raw_args)* $(;$args)*)
+        _call_impl!(
+            $slf.try_borrow().map_err(|e| e.entering(stringify!($fn)))?,
+            $fn $(,$raw_args)* $(;$args)*
+        )
     };
 }
 
 /// Call `slf.try_borrow_mut()?.$fn(...)`
 macro_rules! call_mut {
     ($slf: expr, $fn: ident $(,$raw_args: expr)* $(; $args: expr)*) => {
-        _call_impl!($slf.try_borrow_mut()?, $fn $(,$raw_args)* $(;$args)*)
+        _call_impl!(
+            $slf.try_borrow_mut().map_err(|e| e.entering(stringify!($fn)))?,
+            $fn $(,$raw_args)* $(;$args)*
+        )
     };
 }
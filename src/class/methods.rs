@@ -13,6 +13,12 @@ pub enum PyMethodDefType {
     New(PyMethodDef),
     /// Represents class `__call__` method
     Call(PyMethodDef),
+    /// Represents the vectorcall implementation of the class `__call__` method
+    Vectorcall(PyVectorcallDef),
+    /// Represents class `__aiter__` method, which fills the `am_aiter` slot
+    AIter(PyUnarySlotDef),
+    /// Represents class `__anext__` method, which fills the `am_anext` slot
+    ANext(PyUnarySlotDef),
     /// Represents class method
     Class(PyMethodDef),
     /// Represents static method
@@ -43,6 +49,25 @@ pub struct PyMethodDef {
     pub ml_doc: &'static str,
 }
 
+/// A function implementing the vectorcall protocol (PEP 590), i.e. `ffi::vectorcallfunc`.
+pub type PyVectorcallFunc = unsafe extern "C" fn(
+    callable: *mut ffi::PyObject,
+    args: *const *mut ffi::PyObject,
+    nargsf: usize,
+    kwnames: *mut ffi::PyObject,
+) -> *mut ffi::PyObject;
+
+#[derive(Copy, Clone, Debug)]
+pub struct PyVectorcallDef {
+    pub meth: PyVectorcallFunc,
+}
+
+/// A function filling a type slot which takes only the object, such as `am_anext`.
+#[derive(Copy, Clone, Debug)]
+pub struct PyUnarySlotDef {
+    pub meth: ffi::unaryfunc,
+}
+
 #[derive(Copy, Clone)]
 pub struct PyClassAttributeDef {
     pub name: &'static str,
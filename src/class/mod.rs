@@ -27,6 +27,7 @@ pub use self::iter::PyIterProtocol;
 pub use self::mapping::PyMappingProtocol;
 pub use self::methods::{
     PyClassAttributeDef, PyGetterDef, PyMethodDef, PyMethodDefType, PyMethodType, PySetterDef,
+    PyUnarySlotDef, PyVectorcallDef,
 };
 pub use self::number::PyNumberProtocol;
 pub use self::pyasync::PyAsyncProtocol;
@@ -10,7 +10,7 @@
 
 use crate::callback::IntoPyCallbackOutput;
 use crate::derive_utils::TryFromPyCell;
-use crate::err::PyResult;
+use crate::err::{PyErr, PyResult};
 use crate::{ffi, IntoPy, IntoPyPointer, PyClass, PyObject, Python};
 
 /// Python Async/Await support interface.
@@ -115,12 +115,13 @@ pub enum IterANextOutput<T, U> {
 pub type PyIterANextOutput = IterANextOutput<PyObject, PyObject>;
 
 impl IntoPyCallbackOutput<*mut ffi::PyObject> for PyIterANextOutput {
-    fn convert(self, _py: Python) -> PyResult<*mut ffi::PyObject> {
+    fn convert(self, py: Python) -> PyResult<*mut ffi::PyObject> {
         match self {
             IterANextOutput::Yield(o) => Ok(o.into_ptr()),
-            IterANextOutput::Return(opt) => {
-                Err(crate::exceptions::StopAsyncIteration::py_err((opt,)))
-            }
+            IterANextOutput::Return(opt) => Err(PyErr::new_with_gil::<
+                crate::exceptions::StopAsyncIteration,
+                _,
+            >(py, (opt,))),
         }
     }
 }
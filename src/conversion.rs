@@ -1,6 +1,7 @@
 // Copyright (c) 2017-present PyO3 Project and Contributors
 
 //! Conversions between various states of Rust and Python types and their wrappers.
+use crate::debug::{self, RefcountOp};
 use crate::err::{self, PyDowncastError, PyResult};
 use crate::object::PyObject;
 use crate::type_object::PyTypeInfo;
@@ -68,6 +69,7 @@ where
 {
     fn into_ptr(self) -> *mut ffi::PyObject {
         let ptr = self.as_ptr();
+        debug::record(RefcountOp::IntoPtr, ptr);
         if !ptr.is_null() {
             unsafe {
                 ffi::Py_INCREF(ptr);
@@ -309,7 +311,7 @@ where
         } else {
             match T::extract(obj) {
                 Ok(v) => Ok(Some(v)),
-                Err(e) => Err(e),
+                Err(e) => Err(e.in_wrapper::<Self>()),
             }
         }
     }
@@ -321,10 +323,10 @@ where
 /// This trait is similar to `std::convert::TryFrom`
 pub trait PyTryFrom<'v>: Sized + PyNativeType {
     /// Cast from a concrete Python object type to PyObject.
-    fn try_from<V: Into<&'v PyAny>>(value: V) -> Result<&'v Self, PyDowncastError>;
+    fn try_from<V: Into<&'v PyAny>>(value: V) -> Result<&'v Self, PyDowncastError<'v>>;
 
     /// Cast from a concrete Python object type to PyObject. With exact type check.
-    fn try_from_exact<V: Into<&'v PyAny>>(value: V) -> Result<&'v Self, PyDowncastError>;
+    fn try_from_exact<V: Into<&'v PyAny>>(value: V) -> Result<&'v Self, PyDowncastError<'v>>;
 
     /// Cast a PyAny to a specific type of PyObject. The caller must
     /// have already verified the reference is for this type.
@@ -354,28 +356,35 @@ where
     }
 }
 
+/// Returns the error of downcasting `value` to the native type `T`.
+///
+/// The Rust name of a native type differs from its Python name, which is looked up lazily.
+fn native_downcast_error<'v, T: PyTypeInfo>(value: &'v PyAny) -> PyDowncastError<'v> {
+    unsafe { PyDowncastError::with_static_type(value, T::type_object_raw(value.py())) }
+}
+
 impl<'v, T> PyTryFrom<'v> for T
 where
     T: PyTypeInfo + PyNativeType,
 {
-    fn try_from<V: Into<&'v PyAny>>(value: V) -> Result<&'v Self, PyDowncastError> {
+    fn try_from<V: Into<&'v PyAny>>(value: V) -> Result<&'v Self, PyDowncastError<'v>> {
         let value = value.into();
         unsafe {
             if T::is_instance(value) {
                 Ok(Self::try_from_unchecked(value))
             } else {
-                Err(PyDowncastError)
+                Err(native_downcast_error::<T>(value))
             }
         }
     }
 
-    fn try_from_exact<V: Into<&'v PyAny>>(value: V) -> Result<&'v Self, PyDowncastError> {
+    fn try_from_exact<V: Into<&'v PyAny>>(value: V) -> Result<&'v Self, PyDowncastError<'v>> {
         let value = value.into();
         unsafe {
             if T::is_exact_instance(value) {
                 Ok(Self::try_from_unchecked(value))
             } else {
-                Err(PyDowncastError)
+                Err(native_downcast_error::<T>(value))
             }
         }
     }
@@ -390,23 +399,23 @@ impl<'v, T> PyTryFrom<'v> for PyCell<T>
 where
     T: 'v + PyClass,
 {
-    fn try_from<V: Into<&'v PyAny>>(value: V) -> Result<&'v Self, PyDowncastError> {
+    fn try_from<V: Into<&'v PyAny>>(value: V) -> Result<&'v Self, PyDowncastError<'v>> {
         let value = value.into();
         unsafe {
             if T::is_instance(value) {
                 Ok(Self::try_from_unchecked(value))
             } else {
-                Err(PyDowncastError)
+                Err(PyDowncastError::new(value, T::NAME))
             }
         }
     }
-    fn try_from_exact<V: Into<&'v PyAny>>(value: V) -> Result<&'v Self, PyDowncastError> {
+    fn try_from_exact<V: Into<&'v PyAny>>(value: V) -> Result<&'v Self, PyDowncastError<'v>> {
         let value = value.into();
         unsafe {
             if T::is_exact_instance(value) {
                 Ok(Self::try_from_unchecked(value))
             } else {
-                Err(PyDowncastError)
+                Err(PyDowncastError::new(value, T::NAME))
             }
         }
     }
@@ -468,6 +477,7 @@ where
     T: 'p + crate::PyNativeType,
 {
     unsafe fn from_owned_ptr_or_opt(py: Python<'p>, ptr: *mut ffi::PyObject) -> Option<&'p Self> {
+        debug::record(RefcountOp::FromOwnedPtr, ptr);
         gil::register_owned(py, NonNull::new(ptr)?);
         Some(&*(ptr as *mut Self))
     }
@@ -475,6 +485,7 @@ where
         _py: Python<'p>,
         ptr: *mut ffi::PyObject,
     ) -> Option<&'p Self> {
+        debug::record(RefcountOp::FromBorrowedPtr, ptr);
         NonNull::new(ptr as *mut Self).map(|p| &*p.as_ptr())
     }
 }
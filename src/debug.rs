@@ -0,0 +1,281 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Helpers to debug reference counting errors in FFI code.
+//!
+//! With the `debug-refcount` feature, and in builds with debug assertions, PyO3 records every
+//! handoff of an object pointer: borrowing it with `as_ptr`, giving it away with `into_ptr`,
+//! wrapping it with `from_owned_ptr` or `from_borrowed_ptr`, and the reference count updates of
+//! [Py] and [PyObject](crate::PyObject) handles. The events of an object, with the Rust
+//! backtrace of each, are printed by [dump_refcount_trace], e.g. to find which code released a
+//! reference it did not own.
+//!
+//! Each thread records its own events, and keeps only the last [TRACE_CAPACITY] of them. As
+//! memory is reused, the events of an object which was freed may be reported for a new object
+//! at the same address.
+//!
+//! Without the feature, or in release builds, the recording compiles to nothing.
+//!
+//! The `debug-refcount` feature requires Rust 1.65 or later.
+
+/// A handoff of an object pointer, recorded with the `debug-refcount` feature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RefcountOp {
+    /// The pointer was borrowed with `AsPyPointer::as_ptr`.
+    AsPtr,
+    /// The pointer was given away with `IntoPyPointer::into_ptr`, with the reference it owned.
+    IntoPtr,
+    /// An owned pointer was wrapped, taking over its reference.
+    FromOwnedPtr,
+    /// A borrowed pointer was wrapped.
+    FromBorrowedPtr,
+    /// The reference count was increased by `register_incref`, or the increase was deferred
+    /// until the GIL is acquired.
+    IncRef {
+        /// Whether the GIL was not held, so that the increase was deferred.
+        deferred: bool,
+    },
+    /// The reference count was decreased by `register_decref`, or the decrease was deferred
+    /// until the GIL is acquired.
+    DecRef {
+        /// Whether the GIL was not held, so that the decrease was deferred.
+        deferred: bool,
+    },
+}
+
+/// The number of events each thread keeps with the `debug-refcount` feature.
+pub const TRACE_CAPACITY: usize = 8192;
+
+#[cfg(all(feature = "debug-refcount", debug_assertions))]
+pub use self::trace::{clear_refcount_trace, dump_refcount_trace, refcount_trace, RefcountEvent};
+
+#[cfg(all(feature = "debug-refcount", debug_assertions))]
+pub(crate) use self::trace::record;
+
+#[cfg(not(all(feature = "debug-refcount", debug_assertions)))]
+#[inline(always)]
+pub(crate) fn record(_op: RefcountOp, _ptr: *mut crate::ffi::PyObject) {}
+
+#[cfg(all(feature = "debug-refcount", debug_assertions))]
+mod trace {
+    use super::{RefcountOp, TRACE_CAPACITY};
+    use crate::{ffi, AsPyPointer};
+    use std::backtrace::Backtrace;
+    use std::cell::{Cell, RefCell};
+    use std::collections::VecDeque;
+    use std::fmt;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    /// The number of frames shown for each event.
+    const SNIPPET_FRAMES: usize = 6;
+
+    /// The frames of the recording itself, and of the standard library, which are not shown.
+    const HIDDEN_FRAMES: &[&str] = &[
+        "pyo3::debug::trace::",
+        "std::",
+        "core::",
+        "alloc::",
+        "<std::",
+        "<core::",
+        "<alloc::",
+        "__rust",
+        "rust_begin_unwind",
+    ];
+
+    struct Entry {
+        index: u64,
+        ptr: usize,
+        op: RefcountOp,
+        backtrace: Backtrace,
+    }
+
+    static NEXT_INDEX: AtomicU64 = AtomicU64::new(0);
+
+    thread_local! {
+        static EVENTS: RefCell<VecDeque<Entry>> = RefCell::new(VecDeque::new());
+        // Set while the events are inspected, so that doing so is not recorded.
+        static PAUSED: Cell<bool> = Cell::new(false);
+    }
+
+    pub(crate) fn record(op: RefcountOp, ptr: *mut ffi::PyObject) {
+        if ptr.is_null() || PAUSED.try_with(Cell::get).unwrap_or(true) {
+            return;
+        }
+        let entry = Entry {
+            index: NEXT_INDEX.fetch_add(1, Ordering::Relaxed),
+            ptr: ptr as usize,
+            op,
+            backtrace: Backtrace::force_capture(),
+        };
+        // Ignoring the error means we do nothing if the TLS is destroyed.
+        let _ = EVENTS.try_with(|events| {
+            if let Ok(mut events) = events.try_borrow_mut() {
+                if events.len() == TRACE_CAPACITY {
+                    events.pop_front();
+                }
+                events.push_back(entry);
+            }
+        });
+    }
+
+    fn paused<R>(f: impl FnOnce() -> R) -> R {
+        struct Resume(bool);
+        impl Drop for Resume {
+            fn drop(&mut self) {
+                let was_paused = self.0;
+                let _ = PAUSED.try_with(|p| p.set(was_paused));
+            }
+        }
+        let _resume = Resume(PAUSED.with(|p| p.replace(true)));
+        f()
+    }
+
+    /// An event recorded by the current thread for an object.
+    #[derive(Clone, Debug)]
+    pub struct RefcountEvent {
+        /// The position of the event among the events of all threads.
+        pub index: u64,
+        /// The kind of handoff.
+        pub op: RefcountOp,
+        /// The innermost frames of the backtrace of the event, skipping the standard library.
+        pub backtrace: String,
+    }
+
+    impl fmt::Display for RefcountEvent {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            writeln!(f, "#{} {:?}", self.index, self.op)?;
+            for line in self.backtrace.lines() {
+                writeln!(f, "    {}", line)?;
+            }
+            Ok(())
+        }
+    }
+
+    /// Keeps the first frames of `backtrace` which are not hidden, renumbered from 0.
+    fn snippet(backtrace: &Backtrace) -> String {
+        let rendered = backtrace.to_string();
+        let mut frames: Vec<Vec<&str>> = Vec::new();
+        for line in rendered.lines() {
+            let trimmed = line.trim_start();
+            match trimmed.find(": ") {
+                Some(colon) if trimmed[..colon].bytes().all(|b| b.is_ascii_digit()) => {
+                    frames.push(vec![&trimmed[colon + 2..]])
+                }
+                _ => {
+                    if let Some(frame) = frames.last_mut() {
+                        frame.push(trimmed)
+                    }
+                }
+            }
+        }
+        let mut out = String::new();
+        let shown = frames
+            .iter()
+            .filter(|frame| !HIDDEN_FRAMES.iter().any(|h| frame[0].starts_with(h)))
+            .take(SNIPPET_FRAMES);
+        for (i, frame) in shown.enumerate() {
+            out.push_str(&format!("{:>2}: {}\n", i, frame[0]));
+            for location in &frame[1..] {
+                out.push_str(&format!("        {}\n", location));
+            }
+        }
+        out
+    }
+
+    /// Returns the events recorded by the current thread for `obj`, oldest first.
+    ///
+    /// Only available with the `debug-refcount` feature, in builds with debug assertions.
+    pub fn refcount_trace(obj: &impl AsPyPointer) -> Vec<RefcountEvent> {
+        paused(|| {
+            let ptr = obj.as_ptr() as usize;
+            EVENTS.with(|events| {
+                events
+                    .borrow()
+                    .iter()
+                    .filter(|entry| entry.ptr == ptr)
+                    .map(|entry| RefcountEvent {
+                        index: entry.index,
+                        op: entry.op,
+                        backtrace: snippet(&entry.backtrace),
+                    })
+                    .collect()
+            })
+        })
+    }
+
+    /// Prints the events recorded by the current thread for `obj` to stderr, with the innermost
+    /// frames of the backtrace of each.
+    ///
+    /// Only available with the `debug-refcount` feature, in builds with debug assertions.
+    pub fn dump_refcount_trace(obj: &impl AsPyPointer) {
+        let ptr = paused(|| obj.as_ptr());
+        let events = refcount_trace(obj);
+        eprintln!("refcount trace of {:?}: {} events", ptr, events.len());
+        for event in events {
+            eprint!("{}", event);
+        }
+    }
+
+    /// Forgets the events recorded by the current thread.
+    ///
+    /// Only available with the `debug-refcount` feature, in builds with debug assertions.
+    pub fn clear_refcount_trace() {
+        EVENTS.with(|events| events.borrow_mut().clear());
+    }
+}
+
+#[cfg(test)]
+#[cfg(all(feature = "debug-refcount", debug_assertions))]
+mod test {
+    use super::*;
+    use crate::{ffi, AsPyPointer, PyObject, Python, ToPyObject};
+
+    // A reference counting error: `ptr` is borrowed, but released as if it were owned.
+    #[inline(never)]
+    fn release_borrowed_once(py: Python, ptr: *mut ffi::PyObject) {
+        drop(unsafe { PyObject::from_owned_ptr(py, ptr) });
+    }
+
+    #[inline(never)]
+    fn release_borrowed_again(py: Python, ptr: *mut ffi::PyObject) {
+        drop(unsafe { PyObject::from_owned_ptr(py, ptr) });
+    }
+
+    #[test]
+    fn test_double_decref() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let obj = py.eval("object()", None, None).unwrap().to_object(py);
+        // Keep the object alive through the erroneous decrefs.
+        let extra = vec![obj.clone_ref(py), obj.clone_ref(py)];
+        clear_refcount_trace();
+
+        let ptr = obj.as_ptr();
+        release_borrowed_once(py, ptr);
+        release_borrowed_again(py, ptr);
+
+        let trace = refcount_trace(&obj);
+        dump_refcount_trace(&obj);
+        let decrefs: Vec<_> = trace
+            .iter()
+            .filter(|event| event.op == RefcountOp::DecRef { deferred: false })
+            .collect();
+        assert_eq!(2, decrefs.len());
+        assert!(decrefs[0].backtrace.contains("release_borrowed_once"));
+        assert!(decrefs[1].backtrace.contains("release_borrowed_again"));
+        assert!(decrefs[0].index < decrefs[1].index);
+        assert_eq!(
+            2,
+            trace
+                .iter()
+                .filter(|event| event.op == RefcountOp::FromOwnedPtr)
+                .count()
+        );
+
+        // Restore the references which were released.
+        unsafe {
+            ffi::Py_INCREF(ptr);
+            ffi::Py_INCREF(ptr);
+        }
+        drop(extra);
+    }
+}
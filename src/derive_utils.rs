@@ -7,10 +7,13 @@
 use crate::err::{PyErr, PyResult};
 use crate::exceptions::TypeError;
 use crate::instance::PyNativeType;
+use crate::once_cell::GILOnceCell;
 use crate::pyclass::{PyClass, PyClassThreadChecker};
-use crate::types::{PyAny, PyDict, PyModule, PyTuple};
-use crate::{ffi, GILPool, IntoPy, PyCell, Python};
+use crate::types::{PyAny, PyDict, PyModule, PyString, PyTuple};
+use crate::{ffi, AsPyPointer, AsPyRef, GILPool, IntoPy, Py, PyCell, Python, ToPyObject};
+use parking_lot::{const_rwlock, RwLock};
 use std::cell::UnsafeCell;
+use std::sync::Once;
 
 /// Description of a python parameter; used for `parse_args()`.
 #[derive(Debug)]
@@ -31,6 +34,10 @@ pub struct ParamDescription {
 /// * kwargs: Keyword arguments
 /// * output: Output array that receives the arguments.
 ///           Must have same length as `params` and must be initialized to `None`.
+///
+/// Keyword arguments are matched by comparing their names with the static names of `params`,
+/// so no Python object is created unless `accept_args` or `accept_kwargs` requires collecting
+/// the remaining arguments.
 pub fn parse_fn_args<'p>(
     fname: Option<&str>,
     params: &[ParamDescription],
@@ -40,50 +47,153 @@ pub fn parse_fn_args<'p>(
     accept_kwargs: bool,
     output: &mut [Option<&'p PyAny>],
 ) -> PyResult<(&'p PyTuple, Option<&'p PyDict>)> {
+    let py = args.py();
     let nargs = args.len();
+    let (used_args, remaining_kwargs) = parse_args(
+        py,
+        fname,
+        params,
+        nargs,
+        |i| args.get_item(i),
+        kwargs.into_iter().flatten(),
+        accept_args,
+        accept_kwargs,
+        output,
+    )?;
+    // Adjust the remaining args
+    let args = if accept_args && used_args > 0 {
+        let slice = args.get_slice(used_args, nargs).into_py(py);
+        py.checked_cast_as(slice).unwrap()
+    } else {
+        args
+    };
+    Ok((args, remaining_kwargs))
+}
+
+/// Parse the arguments of a call with the vectorcall (`METH_FASTCALL`) convention.
+///
+/// This is equivalent to [parse_fn_args], except that the positional arguments are the first
+/// `PyVectorcall_NARGS(nargsf)` objects of the `args` array, and the keyword arguments are the
+/// following objects, named by the tuple `kwnames`. The array is read in place, so no tuple
+/// or dictionary is created unless `accept_args` or `accept_kwargs` requires collecting the
+/// remaining arguments.
+///
+/// # Safety
+/// `args` must point to the positional arguments followed by the values of the keyword
+/// arguments, and `kwnames` must be null or a tuple of strings, as passed to a vectorcall
+/// function.
+pub unsafe fn parse_fn_args_fastcall<'p>(
+    py: Python<'p>,
+    fname: Option<&str>,
+    params: &[ParamDescription],
+    args: *const *mut ffi::PyObject,
+    nargsf: usize,
+    kwnames: *mut ffi::PyObject,
+    accept_args: bool,
+    accept_kwargs: bool,
+    output: &mut [Option<&'p PyAny>],
+) -> PyResult<(&'p PyTuple, Option<&'p PyDict>)> {
+    // Clear PY_VECTORCALL_ARGUMENTS_OFFSET, which allows the callee to modify `args[-1]`.
+    let nargs = nargsf & !(1 << (8 * std::mem::size_of::<usize>() - 1));
+    let kwnames: Option<&PyTuple> = py.from_borrowed_ptr_or_opt(kwnames);
+    let nkwargs = kwnames.map_or(0, PyTuple::len);
+    let objects: &[&PyAny] = if args.is_null() {
+        &[]
+    } else {
+        // &PyAny has the same memory layout as *mut ffi::PyObject.
+        std::slice::from_raw_parts(args as *const &PyAny, nargs + nkwargs)
+    };
+    let (positional, kwvalues) = objects.split_at(nargs);
+    let kwargs = kwnames
+        .into_iter()
+        .flat_map(PyTuple::iter)
+        .zip(kwvalues.iter().cloned());
+    let (used_args, remaining_kwargs) = parse_args(
+        py,
+        fname,
+        params,
+        nargs,
+        |i| positional[i],
+        kwargs,
+        accept_args,
+        accept_kwargs,
+        output,
+    )?;
+    let args = if accept_args {
+        PyTuple::new(py, &positional[used_args..])
+    } else {
+        // Unused by the caller: avoid registering a new reference in the pool.
+        static EMPTY: GILOnceCell<Py<PyTuple>> = GILOnceCell::new();
+        EMPTY
+            .get_or_init(py, || PyTuple::empty(py).into())
+            .as_ref(py)
+    };
+    Ok((args, remaining_kwargs))
+}
+
+/// Assigns the arguments to `output`, as described in [parse_fn_args].
+///
+/// Returns the number of positional arguments which were assigned, and the keyword arguments
+/// collected for `**kwargs`.
+#[allow(clippy::too_many_arguments)]
+fn parse_args<'p>(
+    py: Python<'p>,
+    fname: Option<&str>,
+    params: &[ParamDescription],
+    nargs: usize,
+    arg: impl Fn(usize) -> &'p PyAny,
+    kwargs: impl Iterator<Item = (&'p PyAny, &'p PyAny)>,
+    accept_args: bool,
+    accept_kwargs: bool,
+    output: &mut [Option<&'p PyAny>],
+) -> PyResult<(usize, Option<&'p PyDict>)> {
     let mut used_args = 0;
     macro_rules! raise_error {
-        ($s: expr $(,$arg:expr)*) => (return Err(TypeError::py_err(format!(
+        ($s: expr $(,$arg:expr)*) => (return Err(PyErr::new_with_gil::<TypeError, _>(py, format!(
             concat!("{} ", $s), fname.unwrap_or("function") $(,$arg)*
         ))))
     }
-    // Copy kwargs not to modify it
-    let kwargs = match kwargs {
-        Some(k) => Some(k.copy()?),
-        None => None,
-    };
+    // Assign the keyword arguments to their parameters, keeping the others for `**kwargs` or
+    // for the error raised below.
+    let mut unexpected_kwarg = None;
+    let mut remaining_kwargs = None;
+    for (key, value) in kwargs {
+        let position = key
+            .downcast::<PyString>()
+            .ok()
+            .and_then(|key| key.to_str().ok())
+            .and_then(|key| params.iter().position(|p| p.name == key));
+        match position {
+            Some(i) => output[i] = Some(value),
+            None if accept_kwargs => remaining_kwargs
+                .get_or_insert_with(|| PyDict::new(py))
+                .set_item(key, value)?,
+            None => {
+                if unexpected_kwarg.is_none() {
+                    unexpected_kwarg = Some(key);
+                }
+            }
+        }
+    }
     // Iterate through the parameters and assign values to output:
     for (i, (p, out)) in params.iter().zip(output).enumerate() {
-        *out = match kwargs.and_then(|d| d.get_item(p.name)) {
-            Some(kwarg) => {
-                if i < nargs {
-                    raise_error!("got multiple values for argument: {}", p.name)
-                }
-                kwargs.as_ref().unwrap().del_item(p.name)?;
-                Some(kwarg)
+        if out.is_some() {
+            if i < nargs {
+                raise_error!("got multiple values for argument: {}", p.name)
             }
-            None => {
-                if p.kw_only {
-                    if !p.is_optional {
-                        raise_error!("missing required keyword-only argument: {}", p.name)
-                    }
-                    None
-                } else if i < nargs {
-                    used_args += 1;
-                    Some(args.get_item(i))
-                } else {
-                    if !p.is_optional {
-                        raise_error!("missing required positional argument: {}", p.name)
-                    }
-                    None
-                }
+        } else if p.kw_only {
+            if !p.is_optional {
+                raise_error!("missing required keyword-only argument: {}", p.name)
             }
+        } else if i < nargs {
+            used_args += 1;
+            *out = Some(arg(i));
+        } else if !p.is_optional {
+            raise_error!("missing required positional argument: {}", p.name)
         }
     }
-    let is_kwargs_empty = kwargs.as_ref().map_or(true, |dict| dict.is_empty());
     // Raise an error when we get an unknown key
-    if !accept_kwargs && !is_kwargs_empty {
-        let (key, _) = kwargs.unwrap().iter().next().unwrap();
+    if let Some(key) = unexpected_kwarg {
         raise_error!("got an unexpected keyword argument: {}", key)
     }
     // Raise an error when we get too many positional args
@@ -95,25 +205,16 @@ pub fn parse_fn_args<'p>(
             nargs
         )
     }
-    // Adjust the remaining args
-    let args = if accept_args {
-        let py = args.py();
-        let slice = args.slice(used_args as isize, nargs as isize).into_py(py);
-        py.checked_cast_as(slice).unwrap()
-    } else {
-        args
-    };
-    let kwargs = if accept_kwargs && is_kwargs_empty {
-        None
-    } else {
-        kwargs
-    };
-    Ok((args, kwargs))
+    Ok((used_args, remaining_kwargs))
 }
 
-/// `Sync` wrapper of `ffi::PyModuleDef`.
+/// `Sync` wrapper of `ffi::PyModuleDef`, and of the slots of a module using multi-phase
+/// initialization.
 #[doc(hidden)]
-pub struct ModuleDef(UnsafeCell<ffi::PyModuleDef>);
+pub struct ModuleDef {
+    def: UnsafeCell<ffi::PyModuleDef>,
+    slots: UnsafeCell<[ffi::PyModuleDef_Slot; 2]>,
+}
 
 unsafe impl Sync for ModuleDef {}
 
@@ -125,8 +226,17 @@ impl ModuleDef {
     pub const unsafe fn new(name: &'static str) -> Self {
         let mut init = ffi::PyModuleDef_INIT;
         init.m_name = name.as_ptr() as *const _;
-        ModuleDef(UnsafeCell::new(init))
+        ModuleDef {
+            def: UnsafeCell::new(init),
+            slots: UnsafeCell::new(
+                [ffi::PyModuleDef_Slot {
+                    slot: 0,
+                    value: std::ptr::null_mut(),
+                }; 2],
+            ),
+        }
     }
+
     /// Builds a module using user given initializer. Used for `#[pymodule]`.
     ///
     /// # Safety
@@ -136,23 +246,108 @@ impl ModuleDef {
         doc: &str,
         initializer: impl Fn(Python, &PyModule) -> PyResult<()>,
     ) -> PyResult<*mut ffi::PyObject> {
-        #[cfg(py_sys_config = "WITH_THREAD")]
-        // > Changed in version 3.7: This function is now called by Py_Initialize(), so you don’t have
-        // > to call it yourself anymore.
-        #[cfg(not(Py_3_7))]
-        ffi::PyEval_InitThreads();
-
-        let module = ffi::PyModule_Create(self.0.get());
+        init_threads();
+        let module = ffi::PyModule_Create(self.def.get());
         let pool = GILPool::new();
         let py = pool.python();
         if module.is_null() {
             return Err(crate::PyErr::fetch(py));
         }
         let module = py.from_owned_ptr_or_err::<PyModule>(module)?;
-        module.add("__doc__", doc)?;
-        initializer(py, module)?;
+        init_module(module, doc, initializer)?;
         Ok(crate::IntoPyPointer::into_ptr(module))
     }
+
+    /// Returns the definition of a module using multi-phase initialization (PEP 489), which is
+    /// executed by `exec`. Used for `#[pymodule(multi_phase)]`.
+    ///
+    /// # Safety
+    /// The caller must have GIL.
+    pub unsafe fn init_multi_phase(
+        &'static self,
+        exec: unsafe extern "C" fn(*mut ffi::PyObject) -> std::os::raw::c_int,
+    ) -> *mut ffi::PyObject {
+        init_threads();
+        let slots = &mut *self.slots.get();
+        slots[0] = ffi::PyModuleDef_Slot {
+            slot: ffi::Py_mod_exec,
+            value: exec as *mut std::os::raw::c_void,
+        };
+        (*self.def.get()).m_slots = slots.as_mut_ptr();
+        ffi::PyModuleDef_Init(self.def.get())
+    }
+
+    /// Runs the initializer of a module using multi-phase initialization, when the module object
+    /// created by the interpreter is executed.
+    ///
+    /// # Safety
+    /// The caller must have GIL, and `module` must be a module object.
+    pub unsafe fn exec_module(
+        &'static self,
+        module: *mut ffi::PyObject,
+        doc: &str,
+        initializer: impl Fn(Python, &PyModule) -> PyResult<()>,
+    ) -> std::os::raw::c_int {
+        let initializer = std::panic::AssertUnwindSafe(initializer);
+        crate::callback_body_without_convert!(py, {
+            init_module(py.from_borrowed_ptr::<PyModule>(module), doc, &*initializer)?;
+            Ok(0)
+        })
+    }
+}
+
+unsafe fn init_threads() {
+    #[cfg(py_sys_config = "WITH_THREAD")]
+    // > Changed in version 3.7: This function is now called by Py_Initialize(), so you don’t have
+    // > to call it yourself anymore.
+    #[cfg(not(Py_3_7))]
+    ffi::PyEval_InitThreads();
+}
+
+/// Adds the members every `#[pymodule]` has, then runs the user given initializer.
+fn init_module(
+    module: &PyModule,
+    doc: &str,
+    initializer: impl Fn(Python, &PyModule) -> PyResult<()>,
+) -> PyResult<()> {
+    let py = module.py();
+    // Make sure `pyo3_runtime.PanicException` is importable from Python.
+    py.get_type::<crate::panic::PanicException>();
+    module.add("__doc__", doc)?;
+    // Create `__all__` upfront, so that only the members added with `PyModule::add` are
+    // imported by `from module import *`.
+    module.index()?;
+    initializer(py, module)
+}
+
+/// Converts the output of the `PyInit_` function of a `#[pymodule]` into a module, creating it
+/// from its definition if it uses multi-phase initialization. Used for `wrap_pymodule!`.
+///
+/// # Safety
+/// `init` must be the output of the `PyInit_` function of a `#[pymodule]`.
+pub unsafe fn module_from_init(py: Python, init: *mut ffi::PyObject) -> PyResult<crate::PyObject> {
+    if init.is_null() {
+        return Err(PyErr::fetch(py));
+    }
+    if ffi::PyObject_TypeCheck(init, &mut ffi::PyModuleDef_Type) == 0 {
+        return Ok(crate::PyObject::from_owned_ptr(py, init));
+    }
+    // The definition is static, so it is borrowed. Create and execute the module like the import
+    // system does, with a spec which only has a name.
+    let def = init as *mut ffi::PyModuleDef;
+    let name = std::ffi::CStr::from_ptr((*def).m_name).to_string_lossy();
+    let spec = py
+        .import("importlib.machinery")?
+        .getattr("ModuleSpec")?
+        .call1((name.as_ref(), py.None()))?;
+    let module = crate::PyObject::from_owned_ptr_or_err(
+        py,
+        ffi::PyModule_FromDefAndSpec(def, spec.as_ptr()),
+    )?;
+    if ffi::PyModule_ExecDef(module.as_ptr(), def) != 0 {
+        return Err(PyErr::fetch(py));
+    }
+    Ok(module)
 }
 
 /// Utilities for basetype
@@ -207,3 +402,98 @@ where
         <R as std::convert::TryFrom<&'a PyCell<T>>>::try_from(cell)
     }
 }
+
+/// Serializes the `#[pyo3::test]`s: the tests share it, except the exclusive ones.
+static TEST_LOCK: RwLock<()> = const_rwlock(());
+
+/// Runs the body of a `#[pyo3::test]` with the GIL.
+///
+/// An exclusive test does not run concurrently with the other `#[pyo3::test]`s.
+#[doc(hidden)]
+pub fn run_test<F, R>(exclusive: bool, test: F) -> R
+where
+    F: FnOnce(Python) -> R,
+{
+    // The lock is taken before the GIL, so that waiting for it does not hold the GIL.
+    let _exclusive = if exclusive {
+        Some(TEST_LOCK.write())
+    } else {
+        None
+    };
+    let _shared = if exclusive {
+        None
+    } else {
+        Some(TEST_LOCK.read())
+    };
+    prepare_test_interpreter();
+    Python::with_gil(test)
+}
+
+fn prepare_test_interpreter() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        crate::prepare_freethreaded_python();
+        // Finalizing the interpreter waits for the thread which first imported `threading`,
+        // which never happens if that thread is the test thread which initialized the
+        // interpreter, so import it in a thread which releases its state.
+        std::thread::spawn(|| {
+            Python::with_gil(|py| py.import("threading").map(drop))
+                .expect("failed to import threading");
+        })
+        .join()
+        .unwrap();
+    });
+}
+
+/// Runs the future of an async `#[pyo3::test]` with [crate::asyncio::run].
+///
+/// If no runtime was initialized, a runtime running each future in its own thread is used.
+#[doc(hidden)]
+#[cfg(feature = "async")]
+pub fn run_async_test<F, R>(py: Python, test: F) -> R
+where
+    F: std::future::Future<Output = R> + Send + 'static,
+    R: Send + 'static,
+{
+    crate::asyncio::init_thread_runtime();
+    crate::asyncio::run(py, async move { Ok(test.await) }).unwrap_or_else(|err| fail_test(py, err))
+}
+
+/// The output of a `#[pyo3::test]`: `()`, or a `Result` whose error is converted into a
+/// `PyErr`.
+#[doc(hidden)]
+pub trait TestOutput {
+    /// Panics with the traceback of the error, if any.
+    fn finish(self, py: Python);
+}
+
+impl TestOutput for () {
+    fn finish(self, _py: Python) {}
+}
+
+impl<E: Into<PyErr>> TestOutput for Result<(), E> {
+    fn finish(self, py: Python) {
+        if let Err(err) = self {
+            fail_test(py, err.into())
+        }
+    }
+}
+
+/// Panics with the traceback of `err`, formatted like Python reports uncaught exceptions.
+fn fail_test(py: Python, mut err: PyErr) -> ! {
+    err.normalize(py);
+    let format = || -> PyResult<String> {
+        let traceback = err.ptraceback.as_ref().map(|tb| tb.clone_ref(py));
+        let lines = py.import("traceback")?.call1(
+            "format_exception",
+            (&err.ptype, err.to_object(py), traceback),
+        )?;
+        PyString::new(py, "")
+            .call_method1("join", (lines,))?
+            .extract()
+    };
+    match format() {
+        Ok(traceback) => panic!("the test raised a Python exception:\n{}", traceback),
+        Err(_) => panic!("the test raised a Python exception: {:?}", err),
+    }
+}
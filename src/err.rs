@@ -6,11 +6,13 @@ use crate::type_object::PyTypeObject;
 use crate::types::PyType;
 use crate::{exceptions, ffi};
 use crate::{
-    AsPyPointer, FromPy, FromPyPointer, IntoPy, IntoPyPointer, Py, PyAny, PyNativeType, PyObject,
-    Python, ToBorrowedObject, ToPyObject,
+    AsPyPointer, AsPyRef, FromPy, FromPyPointer, IntoPy, IntoPyPointer, Py, PyAny, PyNativeType,
+    PyObject, Python, ToBorrowedObject, ToPyObject,
 };
 use libc::c_int;
-use std::ffi::CString;
+use std::any::Any;
+use std::borrow::Cow;
+use std::ffi::{CStr, CString};
 use std::io;
 use std::os::raw::c_char;
 use std::ptr::NonNull;
@@ -26,7 +28,7 @@ pub enum PyErrValue {
     None,
     Value(PyObject),
     ToArgs(Box<dyn PyErrArguments>),
-    ToObject(Box<dyn ToPyObject>),
+    ToObject(Box<dyn ToPyObject + Send + Sync>),
 }
 
 impl PyErrValue {
@@ -37,6 +39,11 @@ impl PyErrValue {
 }
 
 /// Represents a Python exception that was raised.
+///
+/// `PyErr` is `Send` and `Sync`, so it can be stored in shared state and moved between threads.
+/// The Python objects it references are managed like [Py]: dropping a `PyErr` on a thread which
+/// does not hold the GIL defers the reference count decrements until the GIL is next acquired
+/// by PyO3. Use [PyErr::clone_ref] to duplicate an error.
 pub struct PyErr {
     /// The type of the exception. This should be either a `PyClass` or a `PyType`.
     pub ptype: Py<PyType>,
@@ -55,13 +62,199 @@ pub struct PyErr {
 /// Represents the result of a Python call.
 pub type PyResult<T> = Result<T, PyErr>;
 
-/// Marker type that indicates an error while downcasting
-pub struct PyDowncastError;
+/// Extension trait for adding context to the error of a `PyResult`.
+///
+/// On error, a new exception of the same type as the original one is created with the context
+/// message as its only argument, and the original exception is attached to it as `__cause__`.
+/// Python therefore reports both, joined by "The above exception was the direct cause of the
+/// following exception". If the exception type cannot be constructed from a single message
+/// (e.g. `UnicodeDecodeError`), a `RuntimeError` is used instead.
+///
+/// # Example
+/// ```
+/// use pyo3::prelude::*;
+/// use pyo3::PyResultExt;
+///
+/// fn parse(py: Python, line: usize, value: &str) -> PyResult<i64> {
+///     py.eval(&format!("int({:?})", value), None, None)?
+///         .extract()
+///         .with_context(py, || format!("failed to parse line {}", line))
+/// }
+///
+/// let gil = Python::acquire_gil();
+/// let py = gil.python();
+/// let err = parse(py, 3, "abc").unwrap_err();
+/// assert!(err.is_instance::<pyo3::exceptions::ValueError>(py));
+/// ```
+pub trait PyResultExt<T> {
+    /// Wraps the error, if any, in a new exception with the message `context`.
+    fn context<C>(self, py: Python, context: C) -> PyResult<T>
+    where
+        C: std::fmt::Display;
+
+    /// Like [PyResultExt::context], but only computes the message if there is an error.
+    fn with_context<C, F>(self, py: Python, f: F) -> PyResult<T>
+    where
+        C: std::fmt::Display,
+        F: FnOnce() -> C;
+}
+
+impl<T> PyResultExt<T> for PyResult<T> {
+    fn context<C>(self, py: Python, context: C) -> PyResult<T>
+    where
+        C: std::fmt::Display,
+    {
+        self.map_err(|err| err.into_context(py, context.to_string()))
+    }
+
+    fn with_context<C, F>(self, py: Python, f: F) -> PyResult<T>
+    where
+        C: std::fmt::Display,
+        F: FnOnce() -> C,
+    {
+        self.map_err(|err| err.into_context(py, f().to_string()))
+    }
+}
+
+/// Error that indicates a failure to downcast a Python object to a more specific type.
+///
+/// It keeps the object which failed to be downcast, so that it can be recovered with
+/// [from](#method.from), e.g. to try another type. Converting it into a `PyErr` gives a
+/// `TypeError` naming the expected type, and the type and representation of the object.
+#[derive(Debug, Clone)]
+pub struct PyDowncastError<'a> {
+    from: &'a PyAny,
+    to: DowncastTarget,
+}
+
+/// The type which an object failed to be downcast to, whose name is looked up only when needed.
+#[derive(Debug, Clone)]
+enum DowncastTarget {
+    Name(Cow<'static, str>),
+    // A static type object, such as `PyDict_Type`.
+    Type(NonNull<ffi::PyTypeObject>),
+}
+
+impl<'a> PyDowncastError<'a> {
+    /// Creates a new `PyDowncastError` for `from`, which failed to be downcast to the type
+    /// named `to`.
+    pub fn new(from: &'a PyAny, to: impl Into<Cow<'static, str>>) -> Self {
+        PyDowncastError {
+            from,
+            to: DowncastTarget::Name(to.into()),
+        }
+    }
+
+    /// Creates a new `PyDowncastError` for `from`, which failed to be downcast to the type `to`.
+    ///
+    /// # Safety
+    /// `to` must be a valid type object which lives as long as the interpreter.
+    pub(crate) unsafe fn with_static_type(from: &'a PyAny, to: *mut ffi::PyTypeObject) -> Self {
+        PyDowncastError {
+            from,
+            to: DowncastTarget::Type(NonNull::new(to).expect("type object is not null")),
+        }
+    }
+
+    /// Returns the object which failed to be downcast.
+    pub fn from(&self) -> &'a PyAny {
+        self.from
+    }
+
+    /// Returns the name of the type which the object failed to be downcast to.
+    pub fn to(&self) -> Cow<str> {
+        match self.to {
+            DowncastTarget::Name(ref name) => Cow::Borrowed(name),
+            DowncastTarget::Type(ty) => {
+                unsafe { CStr::from_ptr(ty.as_ref().tp_name) }.to_string_lossy()
+            }
+        }
+    }
+}
+
+/// The maximum length of the representation of the object in the message of a
+/// `PyDowncastError`.
+const DOWNCAST_REPR_MAX_CHARS: usize = 100;
+
+impl std::fmt::Display for PyDowncastError<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let ty = self.from.get_type();
+        let from = ty
+            .getattr("__qualname__")
+            .and_then(|name| name.extract())
+            .unwrap_or_else(|_| ty.name().into_owned());
+        write!(
+            f,
+            "expected '{}', got '{}' ({})",
+            self.to(),
+            from,
+            self.from.repr_safe(Some(DOWNCAST_REPR_MAX_CHARS))
+        )
+    }
+}
+
+impl std::error::Error for PyDowncastError<'_> {}
 
 /// Helper conversion trait that allows to use custom arguments for exception constructor.
-pub trait PyErrArguments {
+///
+/// Implementors must be `Send + Sync` so that a `PyErr` holding them can be shared between
+/// threads.
+pub trait PyErrArguments: Send + Sync {
     /// Arguments for exception
     fn arguments(&self, _: Python) -> PyObject;
+
+    /// Allows PyO3 to recognize its own argument types.
+    #[doc(hidden)]
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any> {
+        None
+    }
+}
+
+/// The arguments of an error raised while extracting an item of a container, recording where the
+/// item is within the (possibly nested) containers being extracted.
+///
+/// The message is only formatted when the exception is created, so that containers wrapping the
+/// error as it propagates only have to record their own location.
+struct ExtractionPath {
+    /// The name of the outermost type being extracted.
+    target: String,
+    /// The location of the item in each container, starting with the outermost one.
+    segments: Vec<String>,
+    /// The message of the original error.
+    message: String,
+}
+
+impl PyErrArguments for ExtractionPath {
+    fn arguments(&self, py: Python) -> PyObject {
+        format!(
+            "error extracting {} at {}: {}",
+            self.target,
+            self.segments.join(" \u{2192} "),
+            self.message
+        )
+        .to_object(py)
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any> {
+        Some(self)
+    }
+}
+
+/// Returns the name of `T` without module paths, e.g. `Vec<HashMap<String, i32>>`.
+fn short_type_name<T: ?Sized>() -> String {
+    let full = std::any::type_name::<T>();
+    let mut name = String::with_capacity(full.len());
+    let mut segment_start = 0;
+    for (i, c) in full.char_indices() {
+        if c.is_alphanumeric() || c == '_' || c == ':' {
+            continue;
+        }
+        name.push_str(full[segment_start..i].rsplit("::").next().unwrap());
+        name.push(c);
+        segment_start = i + c.len_utf8();
+    }
+    name.push_str(full[segment_start..].rsplit("::").next().unwrap());
+    name
 }
 
 impl PyErr {
@@ -84,22 +277,29 @@ impl PyErr {
     /// return Err(exceptions::TypeError::py_err("Error message"));
     /// return exceptions::TypeError::into("Error message");
     /// ```
+    ///
+    /// `value` is converted to Python objects only when the exception is raised or inspected, so
+    /// it must be `Send + Sync` for the `PyErr` to be. Values which are not, such as `&PyAny`,
+    /// can be converted with [ToPyObject::to_object] first.
     pub fn new<T, V>(value: V) -> PyErr
     where
         T: PyTypeObject,
-        V: ToPyObject + 'static,
+        V: ToPyObject + Send + Sync + 'static,
     {
         let gil = ensure_gil();
-        let py = unsafe { gil.python() };
-
-        let ty = T::type_object(py);
-        assert_ne!(unsafe { ffi::PyExceptionClass_Check(ty.as_ptr()) }, 0);
+        PyErr::new_with_gil::<T, V>(unsafe { gil.python() }, value)
+    }
 
-        PyErr {
-            ptype: ty.into(),
-            pvalue: PyErrValue::ToObject(Box::new(value)),
-            ptraceback: None,
-        }
+    /// Creates a new PyErr of type `T`, like [PyErr::new], with the GIL token of the caller.
+    ///
+    /// Error paths which already have a token should use this, since it does not need to check
+    /// whether the GIL is held.
+    pub(crate) fn new_with_gil<T, V>(py: Python, value: V) -> PyErr
+    where
+        T: PyTypeObject,
+        V: ToPyObject + Send + Sync + 'static,
+    {
+        PyErr::from_value_with_gil::<T>(py, PyErrValue::ToObject(Box::new(value)))
     }
 
     /// Constructs a new error, with the usual lazy initialization of Python exceptions.
@@ -109,7 +309,7 @@ impl PyErr {
     /// `args` is the a tuple of arguments to pass to the exception constructor.
     pub fn from_type<A>(exc: &PyType, args: A) -> PyErr
     where
-        A: ToPyObject + 'static,
+        A: ToPyObject + Send + Sync + 'static,
     {
         PyErr {
             ptype: exc.into(),
@@ -124,8 +324,13 @@ impl PyErr {
         T: PyTypeObject,
     {
         let gil = ensure_gil();
-        let py = unsafe { gil.python() };
+        PyErr::from_value_with_gil::<T>(unsafe { gil.python() }, value)
+    }
 
+    fn from_value_with_gil<T>(py: Python, value: PyErrValue) -> PyErr
+    where
+        T: PyTypeObject,
+    {
         let ty = T::type_object(py);
         assert_ne!(unsafe { ffi::PyExceptionClass_Check(ty.as_ptr()) }, 0);
 
@@ -282,6 +487,18 @@ impl PyErr {
         unsafe { ffi::PyErr_PrintEx(1) }
     }
 
+    /// Reports the error as unraisable, for use in contexts where an exception cannot be
+    /// raised, such as destructors or garbage collector callbacks.
+    ///
+    /// This wraps `PyErr_WriteUnraisable`: the error is passed to `sys.unraisablehook` (on
+    /// Python 3.8 and up), which by default prints it to `sys.stderr`. `obj` describes the
+    /// context in which the error occurred, e.g. the object whose destructor failed.
+    pub fn write_unraisable(self, py: Python, obj: Option<&PyAny>) {
+        self.restore(py);
+        let obj = obj.map_or(std::ptr::null_mut(), AsPyPointer::as_ptr);
+        unsafe { ffi::PyErr_WriteUnraisable(obj) }
+    }
+
     /// Returns true if the current exception matches the exception in `exc`.
     ///
     /// If `exc` is a class object, this also returns `true` when `self` is an instance of a subclass.
@@ -352,6 +569,82 @@ impl PyErr {
         }
     }
 
+    /// Creates a new exception of the same type with the message `context`, caused by `self`.
+    fn into_context(self, py: Python, context: String) -> PyErr {
+        let ptype = self.ptype.clone_ref(py);
+        let ptraceback = self.ptraceback.as_ref().map(|tb| tb.clone_ref(py));
+        let cause = self.instance(py);
+        if let Some(tb) = ptraceback {
+            unsafe { ffi::PyException_SetTraceback(cause.as_ptr(), tb.as_ptr()) };
+        }
+
+        let new = match ptype.as_ref(py).call1((context.as_str(),)) {
+            Ok(new) if unsafe { ffi::PyExceptionInstance_Check(new.as_ptr()) } != 0 => new,
+            _ => match exceptions::RuntimeError::type_object(py).call1((context,)) {
+                Ok(new) => new,
+                Err(e) => return e,
+            },
+        };
+        unsafe { ffi::PyException_SetCause(new.as_ptr(), cause.into_ptr()) };
+        PyErr::from_instance(new)
+    }
+
+    /// Returns the path recorded by [PyErr::in_container], if any.
+    fn extraction_path(&mut self) -> Option<&mut ExtractionPath> {
+        match &mut self.pvalue {
+            PyErrValue::ToArgs(args) => args.as_any_mut()?.downcast_mut(),
+            _ => None,
+        }
+    }
+
+    /// Records that this error was raised while extracting the item of a container of type `T`
+    /// at the location described by `segment`, such as `index 2`.
+    ///
+    /// The message of the error becomes "error extracting `T` at `segment`: message", with the
+    /// segments of nested containers joined by arrows. `segment` is only called on error.
+    pub(crate) fn in_container<T: ?Sized>(
+        mut self,
+        py: Python,
+        segment: impl FnOnce() -> String,
+    ) -> PyErr {
+        if let Some(path) = self.extraction_path() {
+            path.target = short_type_name::<T>();
+            path.segments.insert(0, segment());
+            return self;
+        }
+        let ptraceback = self.ptraceback.as_ref().map(|tb| tb.clone_ref(py));
+        let original = self.instance(py);
+        let ptype: Py<PyType> = original.as_ref(py).get_type().into();
+        let message = original.as_ref(py).str_safe(None);
+        let path = ExtractionPath {
+            target: short_type_name::<T>(),
+            segments: vec![segment()],
+            message,
+        };
+        // Keep the original error if its type cannot be created from a message alone.
+        match ptype.as_ref(py).call1((path.arguments(py),)) {
+            Ok(new) if unsafe { ffi::PyExceptionInstance_Check(new.as_ptr()) } != 0 => PyErr {
+                ptype,
+                pvalue: PyErrValue::ToArgs(Box::new(path)),
+                ptraceback,
+            },
+            _ => PyErr {
+                ptype,
+                pvalue: PyErrValue::Value(original),
+                ptraceback,
+            },
+        }
+    }
+
+    /// Records that this error was raised while extracting a value of type `T` wrapping another
+    /// container, such as `Option<T>`, without a location of its own.
+    pub(crate) fn in_wrapper<T: ?Sized>(mut self) -> PyErr {
+        if let Some(path) = self.extraction_path() {
+            path.target = short_type_name::<T>();
+        }
+        self
+    }
+
     /// Writes the error back to the Python interpreter's global state.
     /// This is the opposite of `PyErr::fetch()`.
     #[inline]
@@ -401,6 +694,10 @@ impl PyErr {
         }
     }
 
+    /// Creates a copy of this error.
+    ///
+    /// Lazily-constructed arguments are converted into Python objects, so the copy does not need
+    /// to create them again when it is raised.
     pub fn clone_ref(&self, py: Python) -> PyErr {
         let v = match self.pvalue {
             PyErrValue::None => PyErrValue::None,
@@ -449,25 +746,76 @@ impl<'a> IntoPy<PyObject> for &'a PyErr {
 }
 
 /// Convert `PyDowncastError` to Python `TypeError`.
-impl std::convert::From<PyDowncastError> for PyErr {
-    fn from(_err: PyDowncastError) -> PyErr {
-        exceptions::TypeError.into()
-    }
-}
-
-impl<'p> std::fmt::Debug for PyDowncastError {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
-        f.write_str("PyDowncastError")
+impl std::convert::From<PyDowncastError<'_>> for PyErr {
+    fn from(err: PyDowncastError) -> PyErr {
+        exceptions::TypeError::py_err(err.to_string())
     }
 }
 
 /// Convert `PyErr` to `io::Error`
+///
+/// If the exception is an `OSError` carrying an OS error code (`errno` on Unix, `winerror` on
+/// Windows), the code is preserved and can be recovered with `io::Error::raw_os_error`.
+/// Otherwise the `io::ErrorKind` is derived from the `OSError` subclass.
 impl std::convert::From<PyErr> for std::io::Error {
     fn from(err: PyErr) -> Self {
-        std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Python exception: {:?}", err),
-        )
+        let gil = ensure_gil();
+        let py = unsafe { gil.python() };
+
+        if !err.is_instance::<exceptions::OSError>(py) {
+            return io::Error::new(io::ErrorKind::Other, format!("Python exception: {:?}", err));
+        }
+
+        let kind = io_error_kind(&err, py);
+        let instance = err.clone_ref(py).instance(py);
+        let instance = instance.as_ref(py);
+
+        #[cfg(not(windows))]
+        let code_attr = "errno";
+        #[cfg(windows)]
+        let code_attr = "winerror";
+
+        if let Some(code) = instance
+            .getattr(code_attr)
+            .and_then(|code| code.extract::<Option<i32>>())
+            .ok()
+            .and_then(|code| code)
+        {
+            return io::Error::from_raw_os_error(code);
+        }
+
+        let message = instance
+            .str()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|_| format!("Python exception: {:?}", err));
+        io::Error::new(kind, message)
+    }
+}
+
+/// Maps the `OSError` subclass of `err` to the corresponding `io::ErrorKind`.
+fn io_error_kind(err: &PyErr, py: Python) -> io::ErrorKind {
+    if err.is_instance::<exceptions::BrokenPipeError>(py) {
+        io::ErrorKind::BrokenPipe
+    } else if err.is_instance::<exceptions::ConnectionRefusedError>(py) {
+        io::ErrorKind::ConnectionRefused
+    } else if err.is_instance::<exceptions::ConnectionAbortedError>(py) {
+        io::ErrorKind::ConnectionAborted
+    } else if err.is_instance::<exceptions::ConnectionResetError>(py) {
+        io::ErrorKind::ConnectionReset
+    } else if err.is_instance::<exceptions::InterruptedError>(py) {
+        io::ErrorKind::Interrupted
+    } else if err.is_instance::<exceptions::FileNotFoundError>(py) {
+        io::ErrorKind::NotFound
+    } else if err.is_instance::<exceptions::FileExistsError>(py) {
+        io::ErrorKind::AlreadyExists
+    } else if err.is_instance::<exceptions::PermissionError>(py) {
+        io::ErrorKind::PermissionDenied
+    } else if err.is_instance::<exceptions::BlockingIOError>(py) {
+        io::ErrorKind::WouldBlock
+    } else if err.is_instance::<exceptions::TimeoutError>(py) {
+        io::ErrorKind::TimedOut
+    } else {
+        io::ErrorKind::Other
     }
 }
 
@@ -525,53 +873,131 @@ impl std::convert::From<io::Error> for PyErr {
                 PyErr::from_value::<exceptions::BlockingIOError>(err_value!())
             }
             io::ErrorKind::TimedOut => PyErr::from_value::<exceptions::TimeoutError>(err_value!()),
+            io::ErrorKind::AlreadyExists => {
+                PyErr::from_value::<exceptions::FileExistsError>(err_value!())
+            }
+            io::ErrorKind::PermissionDenied => {
+                PyErr::from_value::<exceptions::PermissionError>(err_value!())
+            }
             _ => PyErr::from_value::<exceptions::OSError>(err_value!()),
         }
     }
 }
 
+/// Errors carrying an OS error code are passed to the exception constructor as
+/// `(errno, strerror)` on Unix and `(errno, strerror, filename, winerror)` on Windows, so that
+/// the `errno` (and `winerror`) attributes of the Python exception are populated.
 impl PyErrArguments for io::Error {
     fn arguments(&self, py: Python) -> PyObject {
-        self.to_string().to_object(py)
+        match self.raw_os_error() {
+            Some(code) => {
+                let message = self.to_string();
+                let suffix = format!(" (os error {})", code);
+                let strerror = message.trim_end_matches(suffix.as_str());
+                #[cfg(not(windows))]
+                {
+                    (code, strerror).to_object(py)
+                }
+                // Python derives `errno` from `winerror` when the latter is given.
+                #[cfg(windows)]
+                {
+                    (0, strerror, py.None(), code).to_object(py)
+                }
+            }
+            None => self.to_string().to_object(py),
+        }
     }
 }
 
-impl<W: 'static + Send + std::fmt::Debug> std::convert::From<std::io::IntoInnerError<W>> for PyErr {
+impl<W: 'static + Send + Sync + std::fmt::Debug> std::convert::From<std::io::IntoInnerError<W>>
+    for PyErr
+{
     fn from(err: std::io::IntoInnerError<W>) -> PyErr {
         PyErr::from_value::<exceptions::OSError>(PyErrValue::from_err_args(err))
     }
 }
 
-impl<W: Send + std::fmt::Debug> PyErrArguments for std::io::IntoInnerError<W> {
+impl<W: Send + Sync + std::fmt::Debug> PyErrArguments for std::io::IntoInnerError<W> {
     fn arguments(&self, py: Python) -> PyObject {
         self.to_string().to_object(py)
     }
 }
 
 impl PyErrArguments for std::convert::Infallible {
-    fn arguments(&self, py: Python) -> PyObject {
-        "Infalliable!".to_object(py)
+    fn arguments(&self, _: Python) -> PyObject {
+        match *self {}
     }
 }
 
 impl std::convert::From<std::convert::Infallible> for PyErr {
-    fn from(_: std::convert::Infallible) -> PyErr {
-        PyErr::new::<exceptions::ValueError, _>("Infalliable!")
+    fn from(err: std::convert::Infallible) -> PyErr {
+        match err {}
     }
 }
 
-impl_to_pyerr!(std::array::TryFromSliceError, exceptions::ValueError);
-impl_to_pyerr!(std::num::ParseIntError, exceptions::ValueError);
-impl_to_pyerr!(std::num::ParseFloatError, exceptions::ValueError);
-impl_to_pyerr!(std::num::TryFromIntError, exceptions::ValueError);
-impl_to_pyerr!(std::str::ParseBoolError, exceptions::ValueError);
-impl_to_pyerr!(std::ffi::IntoStringError, exceptions::UnicodeDecodeError);
 impl_to_pyerr!(std::ffi::NulError, exceptions::ValueError);
-impl_to_pyerr!(std::str::Utf8Error, exceptions::UnicodeDecodeError);
-impl_to_pyerr!(std::string::FromUtf8Error, exceptions::UnicodeDecodeError);
-impl_to_pyerr!(std::string::FromUtf16Error, exceptions::UnicodeDecodeError);
-impl_to_pyerr!(std::char::DecodeUtf16Error, exceptions::UnicodeDecodeError);
-impl_to_pyerr!(std::net::AddrParseError, exceptions::ValueError);
+
+#[cfg(feature = "std-error-conversions")]
+mod std_error_conversions {
+    use super::{PyErr, PyErrArguments, PyErrValue};
+    use crate::types::PyBytes;
+    use crate::{exceptions, PyObject, Python, ToPyObject};
+
+    impl_to_pyerr!(std::array::TryFromSliceError, exceptions::ValueError);
+    impl_to_pyerr!(std::char::CharTryFromError, exceptions::ValueError);
+    impl_to_pyerr!(std::num::ParseIntError, exceptions::ValueError);
+    impl_to_pyerr!(std::num::ParseFloatError, exceptions::ValueError);
+    impl_to_pyerr!(std::num::TryFromIntError, exceptions::OverflowError);
+    impl_to_pyerr!(std::str::ParseBoolError, exceptions::ValueError);
+    impl_to_pyerr!(std::ffi::IntoStringError, exceptions::UnicodeDecodeError);
+    impl_to_pyerr!(std::string::FromUtf16Error, exceptions::UnicodeDecodeError);
+    impl_to_pyerr!(std::char::DecodeUtf16Error, exceptions::UnicodeDecodeError);
+    impl_to_pyerr!(std::net::AddrParseError, exceptions::ValueError);
+
+    /// Builds the `UnicodeDecodeError(encoding, object, start, end, reason)` arguments for the
+    /// invalid sequence described by `err` within `input`.
+    fn utf8_error_arguments(py: Python, input: &[u8], err: &std::str::Utf8Error) -> PyObject {
+        let start = err.valid_up_to();
+        let (end, reason) = match err.error_len() {
+            Some(len) => (start + len, "invalid utf-8"),
+            None => (input.len(), "unexpected end of data"),
+        };
+        ("utf-8", PyBytes::new(py, input), start, end, reason).to_object(py)
+    }
+
+    /// `Utf8Error` does not carry the input it was created from, so the resulting
+    /// `UnicodeDecodeError` is created without calling its constructor: its `args` hold the
+    /// message of the error, and its `object`, `start` and `end` attributes are not set. Use
+    /// `UnicodeDecodeError::new_utf8` if the input is at hand.
+    impl PyErrArguments for std::str::Utf8Error {
+        fn arguments(&self, py: Python) -> PyObject {
+            let ty = py.get_type::<exceptions::UnicodeDecodeError>();
+            // An instance of the exception type is used as is by `PyErr_NormalizeException`.
+            match ty.call_method1("__new__", (ty, self.to_string())) {
+                Ok(instance) => instance.to_object(py),
+                Err(err) => err.instance(py),
+            }
+        }
+    }
+
+    impl std::convert::From<std::str::Utf8Error> for PyErr {
+        fn from(err: std::str::Utf8Error) -> PyErr {
+            PyErr::from_value::<exceptions::UnicodeDecodeError>(PyErrValue::from_err_args(err))
+        }
+    }
+
+    impl PyErrArguments for std::string::FromUtf8Error {
+        fn arguments(&self, py: Python) -> PyObject {
+            utf8_error_arguments(py, self.as_bytes(), &self.utf8_error())
+        }
+    }
+
+    impl std::convert::From<std::string::FromUtf8Error> for PyErr {
+        fn from(err: std::string::FromUtf8Error) -> PyErr {
+            PyErr::from_value::<exceptions::UnicodeDecodeError>(PyErrValue::from_err_args(err))
+        }
+    }
+}
 
 pub fn panic_after_error(_py: Python) -> ! {
     unsafe {
@@ -594,7 +1020,8 @@ pub fn error_on_minusone(py: Python, result: c_int) -> PyResult<()> {
 mod tests {
     use crate::exceptions;
     use crate::panic::PanicException;
-    use crate::{PyErr, Python};
+    use crate::type_object::PyTypeObject;
+    use crate::{PyErr, PyResult, Python, ToPyObject};
 
     #[test]
     fn set_typeerror() {
@@ -606,6 +1033,353 @@ mod tests {
         drop(PyErr::fetch(py));
     }
 
+    fn assert_io_error_round_trip<T: PyTypeObject>(
+        py: Python,
+        code: i32,
+        kind: std::io::ErrorKind,
+    ) {
+        let err: PyErr = std::io::Error::from_raw_os_error(code).into();
+        assert!(err.is_instance::<T>(py));
+
+        let instance = err.to_object(py);
+        #[cfg(not(windows))]
+        let code_attr = "errno";
+        #[cfg(windows)]
+        let code_attr = "winerror";
+        let py_code: i32 = instance
+            .getattr(py, code_attr)
+            .unwrap()
+            .extract(py)
+            .unwrap();
+        assert_eq!(py_code, code);
+
+        let io_err: std::io::Error = err.into();
+        assert_eq!(io_err.kind(), kind);
+        assert_eq!(io_err.raw_os_error(), Some(code));
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn io_error_round_trip() {
+        use std::io::ErrorKind;
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        assert_io_error_round_trip::<exceptions::FileNotFoundError>(
+            py,
+            libc::ENOENT,
+            ErrorKind::NotFound,
+        );
+        assert_io_error_round_trip::<exceptions::PermissionError>(
+            py,
+            libc::EACCES,
+            ErrorKind::PermissionDenied,
+        );
+        assert_io_error_round_trip::<exceptions::BrokenPipeError>(
+            py,
+            libc::EPIPE,
+            ErrorKind::BrokenPipe,
+        );
+        assert_io_error_round_trip::<exceptions::FileExistsError>(
+            py,
+            libc::EEXIST,
+            ErrorKind::AlreadyExists,
+        );
+        assert_io_error_round_trip::<exceptions::ConnectionRefusedError>(
+            py,
+            libc::ECONNREFUSED,
+            ErrorKind::ConnectionRefused,
+        );
+        assert_io_error_round_trip::<exceptions::TimeoutError>(
+            py,
+            libc::ETIMEDOUT,
+            ErrorKind::TimedOut,
+        );
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn io_error_round_trip() {
+        use std::io::ErrorKind;
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        // ERROR_FILE_NOT_FOUND
+        assert_io_error_round_trip::<exceptions::FileNotFoundError>(py, 2, ErrorKind::NotFound);
+        // ERROR_ACCESS_DENIED
+        assert_io_error_round_trip::<exceptions::PermissionError>(
+            py,
+            5,
+            ErrorKind::PermissionDenied,
+        );
+        // ERROR_BROKEN_PIPE
+        assert_io_error_round_trip::<exceptions::BrokenPipeError>(py, 109, ErrorKind::BrokenPipe);
+        // ERROR_ALREADY_EXISTS
+        assert_io_error_round_trip::<exceptions::FileExistsError>(
+            py,
+            183,
+            ErrorKind::AlreadyExists,
+        );
+        // WSAECONNREFUSED
+        assert_io_error_round_trip::<exceptions::ConnectionRefusedError>(
+            py,
+            10061,
+            ErrorKind::ConnectionRefused,
+        );
+        // WSAETIMEDOUT
+        assert_io_error_round_trip::<exceptions::TimeoutError>(py, 10060, ErrorKind::TimedOut);
+    }
+
+    #[test]
+    fn io_error_kind_from_python() {
+        use std::io::ErrorKind;
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let err: PyErr = std::io::Error::new(ErrorKind::NotFound, "missing").into();
+        assert!(err.is_instance::<exceptions::FileNotFoundError>(py));
+        let io_err: std::io::Error = err.into();
+        assert_eq!(io_err.kind(), ErrorKind::NotFound);
+        assert_eq!(io_err.raw_os_error(), None);
+        assert_eq!(io_err.to_string(), "missing");
+
+        let err = py
+            .run("open('/this/path/does/not/exist')", None, None)
+            .unwrap_err();
+        let io_err: std::io::Error = err.into();
+        assert_eq!(io_err.kind(), ErrorKind::NotFound);
+        assert!(io_err.raw_os_error().is_some());
+
+        let io_err: std::io::Error = exceptions::ValueError::py_err("oops").into();
+        assert_eq!(io_err.kind(), ErrorKind::Other);
+    }
+
+    #[test]
+    #[cfg(feature = "std-error-conversions")]
+    fn std_error_conversions() {
+        use std::convert::TryFrom;
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let err: PyErr = "x".parse::<i32>().unwrap_err().into();
+        assert!(err.is_instance::<exceptions::ValueError>(py));
+        let err: PyErr = "x".parse::<f64>().unwrap_err().into();
+        assert!(err.is_instance::<exceptions::ValueError>(py));
+        let err: PyErr = "x".parse::<bool>().unwrap_err().into();
+        assert!(err.is_instance::<exceptions::ValueError>(py));
+        let err: PyErr = u8::try_from(256i32).unwrap_err().into();
+        assert!(err.is_instance::<exceptions::OverflowError>(py));
+        let err: PyErr = char::try_from(0xD800u32).unwrap_err().into();
+        assert!(err.is_instance::<exceptions::ValueError>(py));
+        let err: PyErr = <[u8; 2]>::try_from(&[1u8, 2, 3][..]).unwrap_err().into();
+        assert!(err.is_instance::<exceptions::ValueError>(py));
+    }
+
+    #[test]
+    #[cfg(feature = "std-error-conversions")]
+    fn utf8_error_conversions() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let bytes = b"ab\xffcd".to_vec();
+        let err: PyErr = String::from_utf8(bytes).unwrap_err().into();
+        assert!(err.is_instance::<exceptions::UnicodeDecodeError>(py));
+        let instance = err.to_object(py);
+        let object: Vec<u8> = instance.getattr(py, "object").unwrap().extract(py).unwrap();
+        assert_eq!(object, b"ab\xffcd");
+        let start: usize = instance.getattr(py, "start").unwrap().extract(py).unwrap();
+        let end: usize = instance.getattr(py, "end").unwrap().extract(py).unwrap();
+        assert_eq!((start, end), (2, 3));
+        let encoding: String = instance
+            .getattr(py, "encoding")
+            .unwrap()
+            .extract(py)
+            .unwrap();
+        assert_eq!(encoding, "utf-8");
+
+        let bytes = b"ab\xe2\x82".to_vec();
+        let err: PyErr = String::from_utf8(bytes).unwrap_err().into();
+        let instance = err.to_object(py);
+        let start: usize = instance.getattr(py, "start").unwrap().extract(py).unwrap();
+        let end: usize = instance.getattr(py, "end").unwrap().extract(py).unwrap();
+        let reason: String = instance.getattr(py, "reason").unwrap().extract(py).unwrap();
+        assert_eq!((start, end), (2, 4));
+        assert_eq!(reason, "unexpected end of data");
+
+        let utf8_err = std::str::from_utf8(b"a\xff").unwrap_err();
+        let err: PyErr = utf8_err.into();
+        assert!(err.is_instance::<exceptions::UnicodeDecodeError>(py));
+        let instance = err.to_object(py);
+        let args: (String,) = instance.getattr(py, "args").unwrap().extract(py).unwrap();
+        assert_eq!(args.0, utf8_err.to_string());
+        assert!(instance.getattr(py, "object").unwrap().is_none(py));
+    }
+
+    #[test]
+    fn in_container_keeps_error_with_unprintable_message() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let instance = py
+            .eval(
+                "type('BadStr', (ValueError,), {'__str__': lambda self: 1 / 0})()",
+                None,
+                None,
+            )
+            .unwrap();
+        let err = PyErr::from_instance(instance).in_container::<Vec<i32>>(py, || "index 0".into());
+        assert!(err.is_instance::<exceptions::ValueError>(py));
+        assert!(!PyErr::occurred(py));
+    }
+
+    #[test]
+    #[cfg(Py_3_8)]
+    fn write_unraisable() {
+        use crate::types::PyDict;
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let locals = PyDict::new(py);
+        py.run(
+            r#"
+import sys
+reported = []
+original_hook = sys.unraisablehook
+sys.unraisablehook = reported.append
+context = object()
+"#,
+            None,
+            Some(locals),
+        )
+        .unwrap();
+
+        let context = locals.get_item("context").unwrap();
+        exceptions::ValueError::py_err("unraisable").write_unraisable(py, Some(context));
+        assert!(!PyErr::occurred(py));
+
+        py.run(
+            r#"
+sys.unraisablehook = original_hook
+assert len(reported) == 1
+assert reported[0].exc_type is ValueError
+assert str(reported[0].exc_value) == "unraisable"
+assert reported[0].object is context
+"#,
+            None,
+            Some(locals),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn err_is_send_sync() {
+        fn is_send_sync<T: Send + Sync>() {}
+        is_send_sync::<PyErr>();
+    }
+
+    #[test]
+    fn err_new_with_converted_object() {
+        use crate::AsPyRef;
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        // `&PyAny` is not `Send + Sync`, so it is converted to a `PyObject` first.
+        let obj = py.eval("[1, 2]", None, None).unwrap();
+        let err = PyErr::new::<exceptions::ValueError, _>(obj.to_object(py));
+        let args = err.instance(py).getattr(py, "args").unwrap();
+        assert!(args.as_ref(py).get_item(0).unwrap().is(obj));
+    }
+
+    #[test]
+    fn clone_ref_and_drop_on_other_thread() {
+        use crate::{ffi, AsPyPointer};
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let instance = py.eval("ValueError('shared')", None, None).unwrap();
+        let ptr = instance.as_ptr();
+        let count = unsafe { ffi::Py_REFCNT(ptr) };
+
+        let err = PyErr::from_instance(instance);
+        let clone = err.clone_ref(py);
+        assert_eq!(unsafe { ffi::Py_REFCNT(ptr) }, count + 2);
+
+        // Dropping without the GIL defers the decrement to the reference pool.
+        py.allow_threads(move || std::thread::spawn(move || drop(err)).join().unwrap());
+        {
+            let _pool = unsafe { py.new_pool() };
+        }
+        assert_eq!(unsafe { ffi::Py_REFCNT(ptr) }, count + 1);
+
+        clone.restore(py);
+        let fetched = PyErr::fetch(py);
+        assert!(fetched.is_instance::<exceptions::ValueError>(py));
+        assert_eq!(fetched.to_object(py).as_ptr(), ptr);
+        drop(fetched);
+        assert_eq!(unsafe { ffi::Py_REFCNT(ptr) }, count);
+    }
+
+    #[test]
+    fn result_context() {
+        use crate::types::PyDict;
+        use crate::PyResultExt;
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let result: PyResult<i32> = py
+            .eval("int('x')", None, None)
+            .and_then(|obj| obj.extract())
+            .context(py, "while parsing record 7");
+        let err = result.unwrap_err();
+        assert!(err.is_instance::<exceptions::ValueError>(py));
+
+        let locals = PyDict::new(py);
+        locals.set_item("err", err.to_object(py)).unwrap();
+        py.run(
+            r#"
+assert str(err) == "while parsing record 7"
+assert isinstance(err.__cause__, ValueError)
+assert "invalid literal" in str(err.__cause__)
+assert err.__cause__.__traceback__ is not None
+"#,
+            None,
+            Some(locals),
+        )
+        .unwrap();
+
+        let exc = py
+            .eval(
+                "UnicodeDecodeError('utf-8', b'\\xff', 0, 1, 'invalid start byte')",
+                None,
+                None,
+            )
+            .unwrap();
+        let result: PyResult<()> = Err(PyErr::from_instance(exc));
+        let err = result
+            .with_context(py, || format!("reading {}", "file.txt"))
+            .unwrap_err();
+        assert!(err.is_instance::<exceptions::RuntimeError>(py));
+        locals.set_item("err", err.to_object(py)).unwrap();
+        py.run(
+            r#"
+assert str(err) == "reading file.txt"
+assert isinstance(err.__cause__, UnicodeDecodeError)
+"#,
+            None,
+            Some(locals),
+        )
+        .unwrap();
+
+        let ok: PyResult<i32> = Ok(1);
+        assert_eq!(
+            ok.with_context(py, || -> String { unreachable!() })
+                .unwrap(),
+            1
+        );
+    }
+
     #[test]
     fn fetching_panic_exception_panics() {
         // If -Cpanic=abort is specified, we can't catch panic.
@@ -29,11 +29,13 @@ macro_rules! impl_exception_boilerplate {
         }
 
         impl $name {
-            pub fn py_err<T: $crate::ToPyObject + 'static>(args: T) -> $crate::PyErr {
+            pub fn py_err<T: $crate::ToPyObject + Send + Sync + 'static>(args: T) -> $crate::PyErr {
                 $crate::PyErr::new::<Self, T>(args)
             }
 
-            pub fn into<R, T: $crate::ToPyObject + 'static>(args: T) -> $crate::PyResult<R> {
+            pub fn into<R, T: $crate::ToPyObject + Send + Sync + 'static>(
+                args: T,
+            ) -> $crate::PyResult<R> {
                 $crate::PyErr::new::<Self, T>(args).into()
             }
         }
@@ -212,10 +214,10 @@ macro_rules! impl_native_exception (
             }
         }
         impl $name {
-            pub fn py_err<V: ToPyObject + 'static>(args: V) -> PyErr {
+            pub fn py_err<V: ToPyObject + Send + Sync + 'static>(args: V) -> PyErr {
                 PyErr::new::<$name, V>(args)
             }
-            pub fn into<R, V: ToPyObject + 'static>(args: V) -> PyResult<R> {
+            pub fn into<R, V: ToPyObject + Send + Sync + 'static>(args: V) -> PyResult<R> {
                 PyErr::new::<$name, V>(args).into()
             }
         }
@@ -289,6 +291,18 @@ impl_native_exception!(IOError, PyExc_IOError);
 #[cfg(target_os = "windows")]
 impl_native_exception!(WindowsError, PyExc_WindowsError);
 
+/// The `SyntaxError` raised by [Python::run_interactive](crate::Python::run_interactive) when
+/// the source is valid so far, but incomplete, e.g. an unterminated block.
+///
+/// An interactive interpreter can then read continuation lines, instead of reporting an error.
+/// In Python, the type is available as `pyo3_runtime.IncompleteInputError`.
+pub struct IncompleteInputError {
+    _private: (),
+}
+
+crate::impl_exception_boilerplate!(IncompleteInputError);
+crate::create_exception_type_object!(pyo3_runtime, IncompleteInputError, SyntaxError);
+
 impl UnicodeDecodeError {
     pub fn new_err<'p>(
         py: Python<'p>,
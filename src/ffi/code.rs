@@ -68,6 +68,8 @@ pub const CO_FUTURE_PRINT_FUNCTION: c_int = 0x1_0000;
 pub const CO_FUTURE_UNICODE_LITERALS: c_int = 0x2_0000;
 pub const CO_FUTURE_BARRY_AS_BDFL: c_int = 0x4_0000;
 pub const CO_FUTURE_GENERATOR_STOP: c_int = 0x8_0000;
+#[cfg(Py_3_7)]
+pub const CO_FUTURE_ANNOTATIONS: c_int = 0x100_0000;
 
 pub const CO_MAXBLOCKS: usize = 20;
 
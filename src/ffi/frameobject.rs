@@ -75,3 +75,32 @@ extern "C" {
     pub fn PyFrame_ClearFreeList() -> c_int;
     pub fn PyFrame_GetLineNumber(f: *mut PyFrameObject) -> c_int;
 }
+
+#[cfg(Py_3_9)]
+#[cfg_attr(windows, link(name = "pythonXY"))]
+extern "C" {
+    pub fn PyFrame_GetCode(f: *mut PyFrameObject) -> *mut PyCodeObject;
+    pub fn PyFrame_GetBack(f: *mut PyFrameObject) -> *mut PyFrameObject;
+    pub fn PyThreadState_GetFrame(tstate: *mut PyThreadState) -> *mut PyFrameObject;
+}
+
+// Before Python 3.9, frames were accessed through their fields. `f_back` and `f_code` are the
+// first fields of `PyFrameObject` up to Python 3.10, so reading them is layout-independent.
+
+/// Returns a new reference to the code object of the frame.
+#[cfg(not(Py_3_9))]
+#[inline]
+pub unsafe fn PyFrame_GetCode(f: *mut PyFrameObject) -> *mut PyCodeObject {
+    let code = (*f).f_code;
+    Py_INCREF(code as *mut PyObject);
+    code
+}
+
+/// Returns a new reference to the previous frame, or NULL for the outermost frame.
+#[cfg(not(Py_3_9))]
+#[inline]
+pub unsafe fn PyFrame_GetBack(f: *mut PyFrameObject) -> *mut PyFrameObject {
+    let back = (*f).f_back;
+    Py_XINCREF(back as *mut PyObject);
+    back
+}
@@ -49,6 +49,11 @@ pub unsafe fn PyCoro_Check(op: *mut PyObject) -> c_int {
     PyObject_TypeCheck(op, &mut PyCoro_Type)
 }
 
+#[inline]
+pub unsafe fn PyCoro_CheckExact(op: *mut PyObject) -> c_int {
+    (Py_TYPE(op) == &mut PyCoro_Type) as c_int
+}
+
 #[cfg_attr(windows, link(name = "pythonXY"))]
 extern "C" {
     pub static mut _PyCoroWrapper_Type: PyTypeObject;
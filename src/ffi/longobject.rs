@@ -83,6 +83,7 @@ extern "C" {
 #[cfg(not(Py_LIMITED_API))]
 #[cfg_attr(windows, link(name = "pythonXY"))]
 extern "C" {
+    pub fn _PyLong_Sign(v: *mut PyObject) -> c_int;
     pub fn _PyLong_NumBits(obj: *mut PyObject) -> c_int;
 
     #[cfg_attr(PyPy, link_name = "_PyPyLong_FromByteArray")]
@@ -11,6 +11,8 @@ pub use self::code::*;
 pub use self::codecs::*;
 pub use self::compile::*;
 pub use self::complexobject::*;
+#[cfg(all(Py_3_7, not(Py_LIMITED_API)))]
+pub use self::context::*;
 pub use self::datetime::*;
 pub use self::descrobject::*;
 pub use self::dictobject::*;
@@ -81,6 +83,8 @@ mod unicodeobject; // TODO supports PEP-384 only; needs adjustment for Python 3.
                    // mod longintrepr; TODO excluded by PEP-384
 mod boolobject; // TODO supports PEP-384 only; needs adjustment for Python 3.3 and 3.5
 mod complexobject; // TODO supports PEP-384 only; needs adjustment for Python 3.3 and 3.5
+#[cfg(all(Py_3_7, not(Py_LIMITED_API)))]
+mod context;
 mod dictobject;
 mod floatobject; // TODO supports PEP-384 only; needs adjustment for Python 3.3 and 3.5
 mod listobject; // TODO supports PEP-384 only; needs adjustment for Python 3.3 and 3.5
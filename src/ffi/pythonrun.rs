@@ -5,13 +5,22 @@ use libc::FILE;
 use std::os::raw::{c_char, c_int};
 use std::ptr;
 
-// TODO: PyCF_MASK etc. constants
+pub const PyCF_SOURCE_IS_UTF8: c_int = 0x0100;
+pub const PyCF_DONT_IMPLY_DEDENT: c_int = 0x0200;
+pub const PyCF_ONLY_AST: c_int = 0x0400;
+pub const PyCF_IGNORE_COOKIE: c_int = 0x0800;
+#[cfg(Py_3_8)]
+pub const PyCF_TYPE_COMMENTS: c_int = 0x1000;
+#[cfg(Py_3_8)]
+pub const PyCF_ALLOW_TOP_LEVEL_AWAIT: c_int = 0x2000;
 
 #[repr(C)]
 #[derive(Copy, Clone)]
 #[cfg(not(Py_LIMITED_API))]
 pub struct PyCompilerFlags {
     pub cf_flags: c_int,
+    #[cfg(Py_3_8)]
+    pub cf_feature_version: c_int, /* minor Python version (PyCF_ONLY_AST) */
 }
 
 #[cfg(not(Py_LIMITED_API))]
@@ -5,9 +5,9 @@ pub enum PyWeakReference {}
 
 #[cfg_attr(windows, link(name = "pythonXY"))]
 extern "C" {
-    static mut _PyWeakref_RefType: PyTypeObject;
-    static mut _PyWeakref_ProxyType: PyTypeObject;
-    static mut _PyWeakref_CallableProxyType: PyTypeObject;
+    pub static mut _PyWeakref_RefType: PyTypeObject;
+    pub static mut _PyWeakref_ProxyType: PyTypeObject;
+    pub static mut _PyWeakref_CallableProxyType: PyTypeObject;
 }
 
 #[inline]
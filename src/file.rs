@@ -0,0 +1,351 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Rust I/O adapters for Python file objects.
+use crate::exceptions;
+use crate::instance::{AsPyRef, PyNativeType};
+use crate::once_cell::GILOnceCell;
+use crate::types::{PyAny, PyBytes, PyString, PyType};
+use crate::{FromPyObject, Py, PyErr, PyObject, PyResult, Python};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::str;
+
+#[cfg(feature = "macros")]
+mod redirect;
+
+#[cfg(feature = "macros")]
+pub use self::redirect::RedirectGuard;
+
+/// Wraps a Python file object, such as an open file or an `io.BytesIO`, to implement
+/// `io::Read`, `io::Write` and `io::Seek` by calling its `read`, `write`, `flush` and `seek`
+/// methods.
+///
+/// Objects in text mode (instances of `io.TextIOBase`, such as files opened with mode `"w"` and
+/// `io.StringIO`) are also supported: the data written must then be UTF-8, and the text read is
+/// encoded as UTF-8. As in Python, seeking a text file is only possible to positions returned by
+/// `seek` or to its start or end.
+///
+/// Exceptions raised by the Python methods are converted into `io::Error`s, preserving the
+/// error code of an `OSError`. The GIL is acquired for each operation, so the adapter can be
+/// used in a section which released it with [Python::allow_threads].
+///
+/// # Example
+/// ```
+/// use pyo3::file::PyFileLikeObject;
+/// use pyo3::prelude::*;
+/// use std::io::Write;
+///
+/// #[pyfunction]
+/// fn write_report(py: Python, mut file: PyFileLikeObject) -> PyResult<()> {
+///     py.allow_threads(|| {
+///         for i in 0..3 {
+///             writeln!(file, "line {}", i)?;
+///         }
+///         file.flush()
+///     })?;
+///     Ok(())
+/// }
+///
+/// let gil = Python::acquire_gil();
+/// let py = gil.python();
+/// let write_report = pyo3::wrap_pyfunction!(write_report)(py);
+/// pyo3::py_run!(py, write_report, r#"
+/// import io
+/// text = io.StringIO()
+/// write_report(text)
+/// assert text.getvalue() == "line 0\nline 1\nline 2\n"
+/// binary = io.BytesIO()
+/// write_report(binary)
+/// assert binary.getvalue() == b"line 0\nline 1\nline 2\n"
+/// "#);
+/// ```
+pub struct PyFileLikeObject {
+    inner: PyObject,
+    text: bool,
+    /// Encoded text which was read, but not yet returned by `read`.
+    read_buffer: Vec<u8>,
+    /// The start of a UTF-8 sequence which was written, but is not complete yet.
+    write_buffer: Vec<u8>,
+}
+
+impl PyFileLikeObject {
+    /// Wraps `obj`, which must have a `read` or a `write` method.
+    ///
+    /// Returns a `TypeError` if `obj` has neither.
+    pub fn new(obj: &PyAny) -> PyResult<PyFileLikeObject> {
+        if !obj.hasattr("read")? && !obj.hasattr("write")? {
+            return Err(exceptions::TypeError::py_err(format!(
+                "'{}' object is not a file object: it has no read or write method",
+                obj.get_type().name()
+            )));
+        }
+        Ok(PyFileLikeObject {
+            inner: obj.into(),
+            text: get_text_io_base(obj.py()).is_instance(obj)?,
+            read_buffer: Vec::new(),
+            write_buffer: Vec::new(),
+        })
+    }
+
+    /// Returns whether the object is in text mode, i.e. reads and writes `str` objects.
+    pub fn is_text(&self) -> bool {
+        self.text
+    }
+
+    /// Returns the wrapped object.
+    pub fn as_object(&self) -> &PyObject {
+        &self.inner
+    }
+
+    /// Returns the wrapped object.
+    pub fn into_object(self) -> PyObject {
+        self.inner
+    }
+}
+
+impl<'source> FromPyObject<'source> for PyFileLikeObject {
+    fn extract(obj: &'source PyAny) -> PyResult<Self> {
+        PyFileLikeObject::new(obj)
+    }
+}
+
+/// Returns `io.TextIOBase`.
+fn get_text_io_base(py: Python) -> &PyType {
+    static TEXT_IO_BASE: GILOnceCell<Py<PyType>> = GILOnceCell::new();
+
+    TEXT_IO_BASE
+        .get_or_init(py, || {
+            py.import("io")
+                .and_then(|io| Ok(io.get("TextIOBase")?.downcast::<PyType>()?.into()))
+                .expect("failed to import io.TextIOBase")
+        })
+        .as_ref(py)
+}
+
+impl Read for PyFileLikeObject {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        if !self.text {
+            let data = self.inner.call_method1(py, "read", (buf.len(),))?;
+            if data.is_none(py) {
+                return Err(io::Error::new(
+                    io::ErrorKind::WouldBlock,
+                    "no data is available from the non-blocking stream",
+                ));
+            }
+            let data = data.as_ref(py).downcast::<PyBytes>().map_err(PyErr::from)?;
+            let data = data.as_bytes();
+            if data.len() > buf.len() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "read returned more bytes than requested",
+                ));
+            }
+            buf[..data.len()].copy_from_slice(data);
+            return Ok(data.len());
+        }
+
+        if self.read_buffer.is_empty() {
+            // Each character is encoded as at least one byte.
+            let data = self.inner.call_method1(py, "read", (buf.len(),))?;
+            let data = data
+                .as_ref(py)
+                .downcast::<PyString>()
+                .map_err(PyErr::from)?;
+            self.read_buffer
+                .extend_from_slice(data.to_string()?.as_bytes());
+        }
+        let len = self.read_buffer.len().min(buf.len());
+        buf[..len].copy_from_slice(&self.read_buffer[..len]);
+        self.read_buffer.drain(..len);
+        Ok(len)
+    }
+}
+
+impl Write for PyFileLikeObject {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        if !self.text {
+            let written = self
+                .inner
+                .call_method1(py, "write", (PyBytes::new(py, buf),))?;
+            // Some objects return `None`, having written everything.
+            return Ok(written.extract::<Option<usize>>(py)?.unwrap_or(buf.len()));
+        }
+
+        let buffered = self.write_buffer.len();
+        let mut data = std::mem::replace(&mut self.write_buffer, Vec::new());
+        data.extend_from_slice(buf);
+        let text = match str::from_utf8(&data) {
+            Ok(text) => text,
+            Err(err) if err.error_len().is_none() => {
+                // Keep the start of a UTF-8 sequence which was split between two writes.
+                self.write_buffer = data[err.valid_up_to()..].to_vec();
+                unsafe { str::from_utf8_unchecked(&data[..err.valid_up_to()]) }
+            }
+            Err(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "cannot write data which is not valid UTF-8 to a text file object",
+                ))
+            }
+        };
+        if !text.is_empty() {
+            self.inner.call_method1(py, "write", (text,))?;
+        }
+        // The buffered bytes were already reported as written by the previous call.
+        Ok(data.len() - buffered)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.write_buffer.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "cannot flush an incomplete UTF-8 sequence to a text file object",
+            ));
+        }
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        if self.inner.as_ref(py).hasattr("flush")? {
+            self.inner.call_method0(py, "flush")?;
+        }
+        Ok(())
+    }
+}
+
+impl Seek for PyFileLikeObject {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let (offset, whence) = match pos {
+            SeekFrom::Start(offset) => (offset as i64, 0),
+            SeekFrom::Current(offset) => (offset, 1),
+            SeekFrom::End(offset) => (offset, 2),
+        };
+        let position = self.inner.call_method1(py, "seek", (offset, whence))?;
+        self.read_buffer.clear();
+        Ok(position.extract::<u64>(py)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PyFileLikeObject;
+    use crate::exceptions::TypeError;
+    use crate::types::IntoPyDict;
+    use crate::Python;
+    use std::io::{self, Read, Seek, SeekFrom, Write};
+
+    #[test]
+    fn test_bytes_io() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let obj = py.eval("__import__('io').BytesIO()", None, None).unwrap();
+        let mut file = PyFileLikeObject::new(obj).unwrap();
+        assert!(!file.is_text());
+
+        file.write_all(b"hello \xff world").unwrap();
+        file.flush().unwrap();
+        assert_eq!(
+            b"hello \xff world",
+            obj.call_method0("getvalue")
+                .unwrap()
+                .extract::<&[u8]>()
+                .unwrap()
+        );
+
+        assert_eq!(6, file.seek(SeekFrom::Start(6)).unwrap());
+        let mut data = Vec::new();
+        file.read_to_end(&mut data).unwrap();
+        assert_eq!(b"\xff world", &data[..]);
+        assert_eq!(3, file.seek(SeekFrom::End(-10)).unwrap());
+    }
+
+    #[test]
+    fn test_string_io() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let obj = py.eval("__import__('io').StringIO()", None, None).unwrap();
+        let mut file = PyFileLikeObject::new(obj).unwrap();
+        assert!(file.is_text());
+
+        // A character split between writes is written once it is complete.
+        let text = "caf\u{e9} \u{1f980}".as_bytes();
+        file.write_all(&text[..4]).unwrap();
+        file.write_all(&text[4..8]).unwrap();
+        file.write_all(&text[8..]).unwrap();
+        file.flush().unwrap();
+        assert_eq!(
+            "caf\u{e9} \u{1f980}",
+            obj.call_method0("getvalue")
+                .unwrap()
+                .extract::<&str>()
+                .unwrap()
+        );
+
+        let err = file.write(b"\xff").unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, err.kind());
+
+        // Characters encoded as more bytes than fit in the buffer are returned over several reads.
+        file.seek(SeekFrom::Start(0)).unwrap();
+        let mut read = Vec::new();
+        let mut buf = [0; 3];
+        loop {
+            let len = file.read(&mut buf).unwrap();
+            if len == 0 {
+                break;
+            }
+            read.extend_from_slice(&buf[..len]);
+        }
+        assert_eq!("caf\u{e9} \u{1f980}".as_bytes(), &read[..]);
+    }
+
+    #[test]
+    fn test_temp_file() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let locals = [("tempfile", py.import("tempfile").unwrap())].into_py_dict(py);
+        let obj = py
+            .eval("tempfile.TemporaryFile()", None, Some(locals))
+            .unwrap();
+        let mut file = PyFileLikeObject::new(obj).unwrap();
+        let data: Vec<u8> = (0..=255).cycle().take(100_000).collect();
+        py.allow_threads(|| {
+            file.write_all(&data)?;
+            file.flush()?;
+            file.seek(SeekFrom::Start(0))?;
+            let mut read = Vec::new();
+            file.read_to_end(&mut read)?;
+            assert_eq!(data, read);
+            Ok::<_, io::Error>(())
+        })
+        .unwrap();
+
+        obj.call_method0("close").unwrap();
+        let err = file.write(b"closed").unwrap_err();
+        assert_eq!(io::ErrorKind::Other, err.kind());
+    }
+
+    #[test]
+    fn test_os_error() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let obj = py
+            .eval(
+                "type('Full', (), {'write': lambda self, data: \
+                 (_ for _ in ()).throw(OSError(28, 'No space left on device'))})()",
+                None,
+                None,
+            )
+            .unwrap();
+        let mut file = PyFileLikeObject::new(obj).unwrap();
+        let err = file.write(b"data").unwrap_err();
+        assert_eq!(Some(28), err.raw_os_error());
+
+        let err = PyFileLikeObject::new(py.eval("42", None, None).unwrap())
+            .map(|_| ())
+            .unwrap_err();
+        assert!(err.is_instance::<TypeError>(py));
+    }
+}
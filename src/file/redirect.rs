@@ -0,0 +1,170 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Redirection of `sys.stdout` and `sys.stderr` to Rust writers.
+use crate::exceptions;
+use crate::prelude::*;
+use crate::types::PyString;
+use std::io::Write;
+
+/// The text stream installed as `sys.stdout` or `sys.stderr` by [Python::redirect_stdout] and
+/// [Python::redirect_stderr], writing the text it is given to a Rust writer as UTF-8.
+#[pyclass]
+struct RustWriter {
+    writer: Box<dyn Write + Send>,
+}
+
+#[pymethods]
+impl RustWriter {
+    fn write(&mut self, text: &PyAny) -> PyResult<usize> {
+        let text = text.downcast::<PyString>().map_err(|_| {
+            exceptions::TypeError::py_err(format!(
+                "write() argument must be str, not {}",
+                text.get_type().name()
+            ))
+        })?;
+        let data = text.to_string_lossy();
+        self.writer.write_all(data.as_bytes())?;
+        Ok(data.chars().count())
+    }
+
+    fn flush(&mut self) -> PyResult<()> {
+        Ok(self.writer.flush()?)
+    }
+
+    fn isatty(&self) -> bool {
+        false
+    }
+
+    fn writable(&self) -> bool {
+        true
+    }
+
+    #[getter]
+    fn encoding(&self) -> &'static str {
+        "utf-8"
+    }
+}
+
+/// Restores the previous `sys.stdout` or `sys.stderr` when dropped.
+///
+/// Created by [Python::redirect_stdout] and [Python::redirect_stderr]. Redirections can be
+/// nested: dropping a guard restores the stream which was installed when it was created, so
+/// guards should be dropped in the reverse order of their creation.
+#[must_use = "the stream is restored as soon as the guard is dropped"]
+pub struct RedirectGuard {
+    name: &'static str,
+    previous: PyObject,
+    writer: Py<RustWriter>,
+}
+
+impl RedirectGuard {
+    pub(crate) fn new(
+        py: Python,
+        name: &'static str,
+        writer: impl Write + Send + 'static,
+    ) -> PyResult<RedirectGuard> {
+        let sys = py.import("sys")?;
+        let previous = sys.get(name)?.into();
+        let writer = Py::new(
+            py,
+            RustWriter {
+                writer: Box::new(writer),
+            },
+        )?;
+        sys.setattr(name, &writer)?;
+        Ok(RedirectGuard {
+            name,
+            previous,
+            writer,
+        })
+    }
+}
+
+impl Drop for RedirectGuard {
+    fn drop(&mut self) {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        if let Err(err) = self.writer.as_ref(py).borrow_mut().writer.flush() {
+            PyErr::from(err).write_unraisable(py, None);
+        }
+        let result = py
+            .import("sys")
+            .and_then(|sys| sys.setattr(self.name, &self.previous));
+        if let Err(err) = result {
+            err.write_unraisable(py, None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::exceptions::TypeError;
+    use crate::Python;
+    use std::io::{self, Write};
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl SharedBuffer {
+        fn contents(&self) -> Vec<u8> {
+            self.0.lock().unwrap().clone()
+        }
+    }
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_redirect_stdout() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let sys = py.import("sys").unwrap();
+        let original = sys.get("stdout").unwrap();
+
+        let outer = SharedBuffer::default();
+        let inner = SharedBuffer::default();
+        {
+            let _outer_guard = py.redirect_stdout(outer.clone()).unwrap();
+            py.run("print('hi')", None, None).unwrap();
+            {
+                let _inner_guard = py.redirect_stdout(inner.clone()).unwrap();
+                py.run(
+                    "import sys; print('caf\u{e9}', end='', flush=True); assert not sys.stdout.isatty()",
+                    None,
+                    None,
+                )
+                .unwrap();
+            }
+            py.run("print('bye')", None, None).unwrap();
+
+            let err = py
+                .run("import sys; sys.stdout.write(b'bytes')", None, None)
+                .unwrap_err();
+            assert!(err.is_instance::<TypeError>(py));
+        }
+        assert_eq!(b"hi\nbye\n", &outer.contents()[..]);
+        assert_eq!("caf\u{e9}".as_bytes(), &inner.contents()[..]);
+        assert!(sys.get("stdout").unwrap().is(original));
+    }
+
+    #[test]
+    fn test_redirect_stderr() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let buffer = SharedBuffer::default();
+        {
+            let _guard = py.redirect_stderr(buffer.clone()).unwrap();
+            py.run("import sys; print('oops', file=sys.stderr)", None, None)
+                .unwrap();
+        }
+        assert_eq!(b"oops\n", &buffer.contents()[..]);
+    }
+}
@@ -0,0 +1,234 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Control of the Python garbage collector, and helpers to debug reference leaks.
+//!
+//! These are typed wrappers of the functions of the Python `gc` module. With the
+//! `debug-handles` feature, PyO3 also counts the live [Py] handles of each Rust type, which can
+//! be inspected with `live_rust_handles` to find handles which are never dropped.
+//!
+//! The module also reexports the [PyGCProtocol] used to support the garbage collector in
+//! `#[pyclass]` types.
+use crate::instance::PyNativeType;
+use crate::types::{PyAny, PyList};
+use crate::{PyResult, Python};
+
+// Keep the garbage collector protocol, previously reexported as `pyo3::gc`, available here.
+pub use crate::class::gc::*;
+
+/// Runs a full collection, and returns the number of unreachable objects found.
+///
+/// This is equivalent to the Python expression `gc.collect()`.
+pub fn collect(py: Python) -> PyResult<usize> {
+    py.import("gc")?.call0("collect")?.extract()
+}
+
+/// Collects the generations up to `generation`, which must be 0, 1 or 2, and returns the number
+/// of unreachable objects found.
+///
+/// This is equivalent to the Python expression `gc.collect(generation)`. Returns a
+/// `ValueError` if `generation` is invalid.
+pub fn collect_generation(py: Python, generation: u32) -> PyResult<usize> {
+    py.import("gc")?.call1("collect", (generation,))?.extract()
+}
+
+/// Returns whether `obj` is tracked by the garbage collector.
+///
+/// Objects which cannot be part of a reference cycle, such as integers and strings, are never
+/// tracked. This is equivalent to the Python expression `gc.is_tracked(obj)`.
+pub fn is_tracked(obj: &PyAny) -> bool {
+    obj.py()
+        .import("gc")
+        .and_then(|gc| gc.call1("is_tracked", (obj,))?.extract())
+        .expect("failed to call gc.is_tracked")
+}
+
+/// Returns the objects tracked by the garbage collector which refer to `obj`.
+///
+/// This is equivalent to the Python expression `gc.get_referrers(obj)`. It only finds the
+/// referrers which are tracked; in particular, references held by Rust code, such as a [Py], are
+/// not found.
+pub fn get_referrers<'p>(py: Python<'p>, obj: &PyAny) -> PyResult<&'p PyList> {
+    Ok(py
+        .import("gc")?
+        .call1("get_referrers", (obj,))?
+        .downcast()?)
+}
+
+/// Returns the objects referred to by `obj`.
+///
+/// This is equivalent to the Python expression `gc.get_referents(obj)`.
+pub fn get_referents<'p>(py: Python<'p>, obj: &PyAny) -> PyResult<&'p PyList> {
+    Ok(py
+        .import("gc")?
+        .call1("get_referents", (obj,))?
+        .downcast()?)
+}
+
+/// Returns all the objects tracked by the garbage collector.
+///
+/// This is equivalent to the Python expression `gc.get_objects()`.
+pub fn get_objects(py: Python) -> PyResult<&PyList> {
+    Ok(py.import("gc")?.call0("get_objects")?.downcast()?)
+}
+
+/// Moves all the objects tracked by the garbage collector to a permanent generation, which is
+/// ignored by collections.
+///
+/// This is equivalent to the Python expression `gc.freeze()`.
+#[cfg(Py_3_7)]
+pub fn freeze(py: Python) -> PyResult<()> {
+    py.import("gc")?.call0("freeze")?;
+    Ok(())
+}
+
+/// Moves the objects of the permanent generation back to the oldest generation.
+///
+/// This is equivalent to the Python expression `gc.unfreeze()`.
+#[cfg(Py_3_7)]
+pub fn unfreeze(py: Python) -> PyResult<()> {
+    py.import("gc")?.call0("unfreeze")?;
+    Ok(())
+}
+
+/// Returns the number of objects in the permanent generation.
+///
+/// This is equivalent to the Python expression `gc.get_freeze_count()`.
+#[cfg(Py_3_7)]
+pub fn get_freeze_count(py: Python) -> PyResult<usize> {
+    py.import("gc")?.call0("get_freeze_count")?.extract()
+}
+
+#[cfg(feature = "debug-handles")]
+pub use self::handles::live_rust_handles;
+#[cfg(feature = "debug-handles")]
+pub(crate) use self::handles::{handle_created, handle_released};
+
+#[cfg(not(feature = "debug-handles"))]
+#[inline]
+pub(crate) fn handle_created<T: ?Sized>() {}
+
+#[cfg(not(feature = "debug-handles"))]
+#[inline]
+pub(crate) fn handle_released<T: ?Sized>() {}
+
+#[cfg(feature = "debug-handles")]
+mod handles {
+    use parking_lot::{const_mutex, Mutex};
+    use std::collections::{BTreeMap, HashMap};
+
+    static LIVE_HANDLES: Mutex<Option<HashMap<&'static str, isize>>> = const_mutex(None);
+
+    fn update<T: ?Sized>(delta: isize) {
+        let mut handles = LIVE_HANDLES.lock();
+        *handles
+            .get_or_insert_with(HashMap::new)
+            .entry(std::any::type_name::<T>())
+            .or_insert(0) += delta;
+    }
+
+    pub(crate) fn handle_created<T: ?Sized>() {
+        update::<T>(1);
+    }
+
+    pub(crate) fn handle_released<T: ?Sized>() {
+        update::<T>(-1);
+    }
+
+    /// Returns the number of live `Py<T>` handles for each type `T` which has any, by type name.
+    ///
+    /// A handle is counted from its creation until it is dropped or converted into a raw
+    /// pointer or a `PyObject`. Comparing the counts before and after an operation shows the
+    /// handles it leaked.
+    ///
+    /// Only available with the `debug-handles` feature, which slows down every creation and
+    /// drop of a handle.
+    pub fn live_rust_handles() -> BTreeMap<&'static str, usize> {
+        LIVE_HANDLES
+            .lock()
+            .iter()
+            .flatten()
+            .filter(|(_, &count)| count > 0)
+            .map(|(&name, &count)| (name, count as usize))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::PyDict;
+
+    #[test]
+    fn test_collect_cycle() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let locals = PyDict::new(py);
+        py.run(
+            r#"
+import weakref
+
+class Node:
+    pass
+
+a, b = Node(), Node()
+a.other, b.other = b, a
+ref = weakref.ref(a)
+del a, b
+"#,
+            None,
+            Some(locals),
+        )
+        .unwrap();
+        {
+            // Release the references owned by the pool before collecting.
+            let pool = unsafe { py.new_pool() };
+            let py = pool.python();
+            let node = py.eval("ref()", None, Some(locals)).unwrap();
+            assert!(is_tracked(node));
+            assert!(get_referrers(py, node)
+                .unwrap()
+                .iter()
+                .any(|referrer| referrer.getattr("other").map_or(false, |o| o.is(node))));
+            assert!(!is_tracked(py.eval("42", None, None).unwrap()));
+        }
+
+        collect_generation(py, 0).unwrap();
+        collect(py).unwrap();
+        assert!(py.eval("ref()", None, Some(locals)).unwrap().is_none());
+        assert!(collect_generation(py, 3).is_err());
+    }
+
+    #[test]
+    #[cfg(Py_3_7)]
+    fn test_freeze() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        assert!(!get_objects(py).unwrap().is_empty());
+        freeze(py).unwrap();
+        assert!(get_freeze_count(py).unwrap() > 0);
+        unfreeze(py).unwrap();
+        assert_eq!(0, get_freeze_count(py).unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "debug-handles")]
+    fn test_live_rust_handles() {
+        use crate::types::PyFrozenSet;
+        use crate::Py;
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let name = std::any::type_name::<PyFrozenSet>();
+        let baseline = live_rust_handles().get(name).cloned().unwrap_or(0);
+        let set: Py<PyFrozenSet> = PyFrozenSet::new(py, &[1, 2]).unwrap().into();
+        let copy = set.clone_ref(py);
+        assert_eq!(Some(&(baseline + 2)), live_rust_handles().get(name));
+        drop(set);
+        let _ptr = crate::IntoPyPointer::into_ptr(copy);
+        assert_eq!(
+            baseline,
+            live_rust_handles().get(name).cloned().unwrap_or(0)
+        );
+        unsafe { crate::ffi::Py_DECREF(_ptr) };
+    }
+}
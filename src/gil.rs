@@ -2,10 +2,20 @@
 
 //! Interaction with python's global interpreter lock
 
-use crate::{ffi, internal_tricks::Unsendable, Python};
+use crate::debug::{self, RefcountOp};
+use crate::panic::PanicException;
+use crate::{ffi, internal_tricks::Unsendable, PyResult, Python};
 use parking_lot::{const_mutex, Mutex};
 use std::cell::{Cell, RefCell};
-use std::{any, mem::ManuallyDrop, ptr::NonNull, sync};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::{
+    any, env, fs, io,
+    mem::{self, ManuallyDrop},
+    ptr::NonNull,
+    sync,
+};
 
 static START: sync::Once = sync::Once::new();
 
@@ -50,8 +60,11 @@ thread_local! {
 ///  1) for performance
 ///  2) PyGILState_Check always returns 1 if the sub-interpreter APIs have ever been called,
 ///     which could lead to incorrect conclusions that the GIL is held.
-fn gil_is_acquired() -> bool {
-    GIL_COUNT.with(|c| c.get() > 0)
+#[inline]
+pub(crate) fn gil_is_acquired() -> bool {
+    // The TLS is destroyed when the thread exits, e.g. during `atexit`; reference count updates
+    // are then deferred to the `ReferencePool`.
+    GIL_COUNT.try_with(|c| c.get() > 0).unwrap_or(false)
 }
 
 /// Prepares the use of Python in a free-threaded context.
@@ -130,6 +143,148 @@ pub fn prepare_freethreaded_python() {
     });
 }
 
+/// Prepares the interpreter like [prepare_freethreaded_python], then activates a virtual
+/// environment, so that the packages installed in it can be imported.
+///
+/// `venv` is the directory of the virtual environment, which contains its `pyvenv.cfg`. If it is
+/// `None`, the virtual environment of the `VIRTUAL_ENV` environment variable, set by its
+/// `activate` script, is used if any.
+///
+/// An embedded interpreter does not detect virtual environments by itself, as `site.py` finds
+/// their `pyvenv.cfg` next to the executable, which is not a Python interpreter. This does what
+/// `site.py` does for the interpreter of the virtual environment:
+///
+/// - `sys.prefix` and `sys.exec_prefix` are set to the virtual environment, while
+///   `sys.base_prefix` and `sys.base_exec_prefix` remain those of the interpreter;
+/// - the site-packages of the virtual environment, `lib/pythonX.Y/site-packages` or
+///   `Lib\site-packages` on Windows, is added at the start of `sys.path`, and its `.pth` files
+///   are processed;
+/// - the site-packages of the interpreter and of the user are removed from `sys.path`, unless
+///   `include-system-site-packages` is `true` in `pyvenv.cfg`.
+///
+/// This can also be called after the interpreter is initialized, but the modules imported before
+/// may come from outside the virtual environment.
+///
+/// Returns an `OSError` if `pyvenv.cfg` cannot be read, and a `RuntimeError` if the virtual
+/// environment was created for another version of Python.
+pub fn prepare_freethreaded_python_with_venv(venv: Option<&Path>) -> PyResult<()> {
+    prepare_freethreaded_python();
+    let venv = match venv {
+        Some(venv) => venv.to_path_buf(),
+        None => match env::var_os("VIRTUAL_ENV") {
+            Some(venv) => PathBuf::from(venv),
+            None => return Ok(()),
+        },
+    };
+    let gil = GILGuard::acquire();
+    let py = gil.python();
+
+    let config = PyVenvConfig::read(&venv)?;
+    let version_info = py.import("sys")?.getattr("version_info")?;
+    let major: u8 = version_info.getattr("major")?.extract()?;
+    let minor: u8 = version_info.getattr("minor")?.extract()?;
+    if let Some(venv_version) = &config.version {
+        let mut parts = venv_version.split('.');
+        if parts.next() != Some(&major.to_string()) || parts.next() != Some(&minor.to_string()) {
+            return Err(crate::exceptions::RuntimeError::py_err(format!(
+                "the virtual environment {} was created for Python {}, but the interpreter is \
+                 Python {}.{}",
+                venv.display(),
+                venv_version,
+                major,
+                minor
+            )));
+        }
+    }
+
+    let site_packages = venv_site_packages(&venv, major, minor, cfg!(windows));
+    let globals = crate::types::PyDict::new(py);
+    globals.set_item("venv", venv.to_string_lossy())?;
+    globals.set_item("site_packages", site_packages.to_string_lossy())?;
+    globals.set_item("system_site", config.include_system_site_packages)?;
+    py.run(ACTIVATE_VENV, Some(globals), None)
+}
+
+/// Activates a virtual environment like `site.venv()`.
+const ACTIVATE_VENV: &str = r#"
+import os
+import site
+import sys
+
+def normalize(path):
+    return os.path.normcase(os.path.abspath(path))
+
+if system_site:
+    site.PREFIXES.insert(0, venv)
+else:
+    excluded = set(map(normalize, site.getsitepackages()))
+    if site.ENABLE_USER_SITE:
+        excluded.add(normalize(site.getusersitepackages()))
+    sys.path[:] = [p for p in sys.path if normalize(p) not in excluded]
+    site.PREFIXES = [venv]
+    site.ENABLE_USER_SITE = False
+sys.prefix = sys.exec_prefix = venv
+
+previous = list(sys.path)
+site.addsitedir(site_packages)
+sys.path[:] = [p for p in sys.path if p not in previous] + previous
+"#;
+
+/// The settings of a virtual environment read from its `pyvenv.cfg`.
+struct PyVenvConfig {
+    include_system_site_packages: bool,
+    /// The version of Python which created the virtual environment, e.g. `3.8.5`.
+    version: Option<String>,
+}
+
+impl PyVenvConfig {
+    fn read(venv: &Path) -> io::Result<Self> {
+        let path = venv.join("pyvenv.cfg");
+        let contents = fs::read_to_string(&path).map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("failed to read {}: {}", path.display(), e),
+            )
+        })?;
+        Ok(Self::parse(&contents))
+    }
+
+    /// Parses the `key = value` lines of `pyvenv.cfg`, like `site.py`.
+    fn parse(contents: &str) -> Self {
+        let mut config = PyVenvConfig {
+            include_system_site_packages: false,
+            version: None,
+        };
+        for line in contents.lines() {
+            let mut parts = line.splitn(2, '=');
+            let (key, value) = match (parts.next(), parts.next()) {
+                (Some(key), Some(value)) => (key.trim().to_lowercase(), value.trim()),
+                _ => continue,
+            };
+            match key.as_str() {
+                "include-system-site-packages" => {
+                    config.include_system_site_packages = value.to_lowercase() == "true"
+                }
+                // `version_info` is written by the `virtualenv` package.
+                "version" | "version_info" => config.version = Some(value.to_string()),
+                _ => (),
+            }
+        }
+        config
+    }
+}
+
+/// Returns the site-packages directory of a virtual environment.
+fn venv_site_packages(venv: &Path, major: u8, minor: u8, windows: bool) -> PathBuf {
+    if windows {
+        venv.join("Lib").join("site-packages")
+    } else {
+        venv.join("lib")
+            .join(format!("python{}.{}", major, minor))
+            .join("site-packages")
+    }
+}
+
 /// RAII type that represents the Global Interpreter Lock acquisition.
 ///
 /// # Example
@@ -188,57 +343,83 @@ impl GILGuard {
 impl Drop for GILGuard {
     fn drop(&mut self) {
         unsafe {
-            // Must drop the objects in the pool before releasing the GILGuard
-            ManuallyDrop::drop(&mut self.pool);
+            // Must drop the objects in the pool before releasing the GILGuard, and release it
+            // even if that panics, so that the GIL count and the GIL state stay consistent.
+            let result =
+                panic::catch_unwind(AssertUnwindSafe(|| ManuallyDrop::drop(&mut self.pool)));
             ffi::PyGILState_Release(self.gstate);
+            if let Err(payload) = result {
+                // Panicking again while unwinding would abort the process.
+                if !std::thread::panicking() {
+                    panic::resume_unwind(payload);
+                }
+            }
         }
     }
 }
 
 /// Thread-safe storage for objects which were inc_ref / dec_ref while the GIL was not held.
 struct ReferencePool {
-    pointers_to_incref: Mutex<Vec<NonNull<ffi::PyObject>>>,
-    pointers_to_decref: Mutex<Vec<NonNull<ffi::PyObject>>>,
+    /// Whether there may be pending updates, so that acquiring the GIL does not lock the mutex
+    /// when there are none.
+    dirty: AtomicBool,
+    pending: Mutex<PendingUpdates>,
+}
+
+/// Both lists are kept under the same lock, so that the updates taken at once always include the
+/// increments of the pointers whose decrements they include.
+struct PendingUpdates {
+    increfs: Vec<NonNull<ffi::PyObject>>,
+    decrefs: Vec<NonNull<ffi::PyObject>>,
 }
 
 impl ReferencePool {
     const fn new() -> Self {
         Self {
-            pointers_to_incref: const_mutex(Vec::new()),
-            pointers_to_decref: const_mutex(Vec::new()),
+            dirty: AtomicBool::new(false),
+            pending: const_mutex(PendingUpdates {
+                increfs: Vec::new(),
+                decrefs: Vec::new(),
+            }),
         }
     }
 
+    #[cold]
     fn register_incref(&self, obj: NonNull<ffi::PyObject>) {
-        self.pointers_to_incref.lock().push(obj)
+        self.pending.lock().increfs.push(obj);
+        self.dirty.store(true, Ordering::Release);
     }
 
     fn register_decref(&self, obj: NonNull<ffi::PyObject>) {
-        self.pointers_to_decref.lock().push(obj)
+        self.pending.lock().decrefs.push(obj);
+        self.dirty.store(true, Ordering::Release);
     }
 
     fn update_counts(&self, _py: Python) {
-        macro_rules! swap_vec_with_lock {
-            // Get vec from one of ReferencePool's mutexes via lock, swap vec if needed, unlock.
-            ($cell:expr) => {{
-                let mut locked = $cell.lock();
-                let mut out = Vec::new();
-                if !locked.is_empty() {
-                    std::mem::swap(&mut out, &mut *locked);
-                }
-                drop(locked);
-                out
-            }};
+        // An update registered after the flag is cleared sets it again, so it is either taken
+        // below or by the next call.
+        if !self.dirty.swap(false, Ordering::Acquire) {
+            return;
+        }
+
+        // Take the updates and release the lock before applying them, as `Py_DECREF` can run
+        // arbitrary Python code, which may create a new `GILPool` and so call this again.
+        let (increfs, decrefs) = {
+            let mut pending = self.pending.lock();
+            (
+                mem::replace(&mut pending.increfs, Vec::new()),
+                mem::replace(&mut pending.decrefs, Vec::new()),
+            )
         };
 
         // Always increase reference counts first - as otherwise objects which have a
         // nonzero total reference count might be incorrectly dropped by Python during
         // this update.
-        for ptr in swap_vec_with_lock!(self.pointers_to_incref) {
+        for ptr in increfs {
             unsafe { ffi::Py_INCREF(ptr.as_ptr()) };
         }
 
-        for ptr in swap_vec_with_lock!(self.pointers_to_decref) {
+        for ptr in decrefs {
             unsafe { ffi::Py_DECREF(ptr.as_ptr()) };
         }
     }
@@ -283,21 +464,31 @@ impl GILPool {
 
 impl Drop for GILPool {
     fn drop(&mut self) {
-        unsafe {
-            if let Some((obj_len_start, any_len_start)) = self.start {
-                let dropping_obj = OWNED_OBJECTS.with(|holder| {
-                    // `holder` must be dropped before calling Py_DECREF, or Py_DECREF may call
-                    // `GILPool::drop` recursively, resulting in invalid borrowing.
-                    let mut holder = holder.borrow_mut();
-                    holder.any.truncate(any_len_start);
-                    if obj_len_start < holder.obj.len() {
-                        holder.obj.split_off(obj_len_start)
-                    } else {
-                        Vec::new()
-                    }
-                });
+        if let Some((obj_len_start, any_len_start)) = self.start {
+            let dropping = OWNED_OBJECTS.try_with(|holder| {
+                // `holder` must be dropped before dropping the objects, or Py_DECREF may call
+                // `GILPool::drop` recursively, resulting in invalid borrowing.
+                let mut holder = holder.borrow_mut();
+                let any = if any_len_start < holder.any.len() {
+                    holder.any.split_off(any_len_start)
+                } else {
+                    Vec::new()
+                };
+                let obj = if obj_len_start < holder.obj.len() {
+                    holder.obj.split_off(obj_len_start)
+                } else {
+                    Vec::new()
+                };
+                (obj, any)
+            });
+            // Ignoring the error means the objects are leaked if the TLS is broken.
+            if let Ok((dropping_obj, dropping_any)) = dropping {
+                let py = self.python();
+                for value in dropping_any {
+                    drop_reporting_panic(py, move || drop(value));
+                }
                 for obj in dropping_obj {
-                    ffi::Py_DECREF(obj.as_ptr());
+                    drop_reporting_panic(py, || unsafe { ffi::Py_DECREF(obj.as_ptr()) });
                 }
             }
         }
@@ -305,6 +496,17 @@ impl Drop for GILPool {
     }
 }
 
+/// Drops a value owned by a `GILPool`, reporting a panic with `PyErr::write_unraisable`.
+///
+/// The pool may be dropped while unwinding from another panic, in which case the panic would
+/// abort the process. Otherwise, it would still leak the remaining objects of the pool and leave
+/// the GIL count inconsistent.
+fn drop_reporting_panic(py: Python, f: impl FnOnce()) {
+    if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(f)) {
+        PanicException::from_panic_payload(py, payload).write_unraisable(py, None);
+    }
+}
+
 /// Register a Python object pointer inside the release pool, to have reference count increased
 /// next time the GIL is acquired in pyo3.
 ///
@@ -313,8 +515,11 @@ impl Drop for GILPool {
 ///
 /// # Safety
 /// The object must be an owned Python reference.
+#[inline]
 pub unsafe fn register_incref(obj: NonNull<ffi::PyObject>) {
-    if gil_is_acquired() {
+    let deferred = !gil_is_acquired();
+    debug::record(RefcountOp::IncRef { deferred }, obj.as_ptr());
+    if !deferred {
         ffi::Py_INCREF(obj.as_ptr())
     } else {
         POOL.register_incref(obj);
@@ -330,7 +535,9 @@ pub unsafe fn register_incref(obj: NonNull<ffi::PyObject>) {
 /// # Safety
 /// The object must be an owned Python reference.
 pub unsafe fn register_decref(obj: NonNull<ffi::PyObject>) {
-    if gil_is_acquired() {
+    let deferred = !gil_is_acquired();
+    debug::record(RefcountOp::DecRef { deferred }, obj.as_ptr());
+    if !deferred {
         ffi::Py_DECREF(obj.as_ptr())
     } else {
         POOL.register_decref(obj);
@@ -366,7 +573,7 @@ pub unsafe fn register_any<'p, T: 'static>(obj: T) -> &'p T {
 // Ignores the error in case this function called from `atexit`.
 #[inline(always)]
 fn increment_gil_count() {
-    let _ = GIL_COUNT.with(|c| c.set(c.get() + 1));
+    let _ = GIL_COUNT.try_with(|c| c.set(c.get() + 1));
 }
 
 /// Decrement pyo3's internal GIL count - to be called whenever GILPool or GILGuard is dropped.
@@ -385,15 +592,25 @@ fn decrement_gil_count() {
 
 /// Ensure the GIL is held, useful in implementation of APIs like PyErr::new where it's
 /// inconvenient to force the user to acquire the GIL.
+///
+/// This only checks pyo3's internal count, so a thread which holds the GIL without pyo3 knowing
+/// it (e.g. in a callback called directly from C) acquires it again with `PyGILState_Ensure`;
+/// [Python::with_embedded_gil_state] avoids that.
 #[doc(hidden)]
+#[inline]
 pub fn ensure_gil() -> EnsureGIL {
     if gil_is_acquired() {
         EnsureGIL(None)
     } else {
-        EnsureGIL(Some(GILGuard::acquire()))
+        ensure_gil_slow()
     }
 }
 
+#[cold]
+fn ensure_gil_slow() -> EnsureGIL {
+    EnsureGIL(Some(GILGuard::acquire()))
+}
+
 /// Struct used internally which avoids acquiring the GIL where it's not necessary.
 #[doc(hidden)]
 pub struct EnsureGIL(Option<GILGuard>);
@@ -402,8 +619,11 @@ impl EnsureGIL {
     /// Get the GIL token.
     ///
     /// # Safety
-    /// If `self.0` is `None`, then this calls [Python::assume_gil_acquired].
-    /// Thus this method could be used to get access to a GIL token while the GIL is not held.
+    /// If `self.0` is `None`, then this calls [Python::assume_gil_acquired], relying on pyo3's
+    /// internal count, which is only increased by a `GILGuard`, a `GILPool` or
+    /// [Python::with_embedded_gil_state] while the GIL is held.
+    /// Thus this method could be used to get access to a GIL token while the GIL is not held,
+    /// e.g. after it was released by [Python::allow_threads] or by C code.
     /// Care should be taken to only use the returned Python in contexts where it is certain the
     /// GIL continues to be held.
     pub unsafe fn python(&self) -> Python {
@@ -416,9 +636,13 @@ impl EnsureGIL {
 
 #[cfg(test)]
 mod test {
-    use super::{gil_is_acquired, GILPool, GIL_COUNT, OWNED_OBJECTS, POOL};
-    use crate::{ffi, gil, AsPyPointer, IntoPyPointer, PyObject, Python, ToPyObject};
+    use super::{
+        gil_is_acquired, venv_site_packages, GILPool, PyVenvConfig, GIL_COUNT, OWNED_OBJECTS, POOL,
+    };
+    use crate::{ffi, gil, AsPyPointer, IntoPyPointer, PyObject, PyResult, Python, ToPyObject};
+    use std::path::Path;
     use std::ptr::NonNull;
+    use std::sync::atomic::Ordering;
 
     fn get_object(py: Python) -> PyObject {
         // Convenience function for getting a single unique object, using `new_pool` so as to leave
@@ -434,10 +658,8 @@ mod test {
         OWNED_OBJECTS.with(|holder| holder.borrow().obj.len())
     }
 
-    #[test]
-    fn test_owned() {
-        let gil = Python::acquire_gil();
-        let py = gil.python();
+    #[crate::test]
+    fn test_owned(py: Python) {
         let obj = get_object(py);
         let obj_ptr = obj.as_ptr();
         // Ensure that obj does not get freed
@@ -459,10 +681,8 @@ mod test {
         }
     }
 
-    #[test]
-    fn test_owned_nested() {
-        let gil = Python::acquire_gil();
-        let py = gil.python();
+    #[crate::test]
+    fn test_owned_nested(py: Python) {
         let obj = get_object(py);
         // Ensure that obj does not get freed
         let _ref = obj.clone_ref(py);
@@ -492,10 +712,8 @@ mod test {
         }
     }
 
-    #[test]
-    fn test_pyobject_drop_with_gil_decreases_refcnt() {
-        let gil = Python::acquire_gil();
-        let py = gil.python();
+    #[crate::test]
+    fn test_pyobject_drop_with_gil_decreases_refcnt(py: Python) {
         let obj = get_object(py);
         // Ensure that obj does not get freed
         let _ref = obj.clone_ref(py);
@@ -574,12 +792,9 @@ mod test {
         assert_eq!(get_gil_count(), 0);
     }
 
-    #[test]
-    fn test_allow_threads() {
+    #[crate::test]
+    fn test_allow_threads(py: Python) {
         // allow_threads should temporarily release GIL in Py03's internal tracking too.
-        let gil = Python::acquire_gil();
-        let py = gil.python();
-
         assert!(gil_is_acquired());
 
         py.allow_threads(move || {
@@ -595,6 +810,38 @@ mod test {
         assert!(gil_is_acquired());
     }
 
+    #[test]
+    fn test_with_gil_nested() {
+        let get_gil_count = || GIL_COUNT.with(|c| c.get());
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let count = owned_object_count();
+        Python::with_gil(|py| {
+            // The existing pool is reused, so references created here are only released with it.
+            assert_eq!(get_gil_count(), 1);
+            py.eval("object()", None, None).unwrap();
+        });
+        assert_eq!(get_gil_count(), 1);
+        assert_eq!(owned_object_count(), count + 1);
+
+        // Inside `allow_threads`, a new pool is created and released when the closure returns.
+        let count = owned_object_count();
+        let repr = py.allow_threads(|| {
+            assert!(!gil_is_acquired());
+            Python::with_gil(|py| -> PyResult<String> {
+                assert_eq!(get_gil_count(), 1);
+                py.eval("object()", None, None)?.repr()?.extract()
+            })
+        });
+        assert!(repr.unwrap().starts_with("<object object"));
+        assert_eq!(get_gil_count(), 1);
+        assert_eq!(owned_object_count(), count);
+
+        drop(gil);
+        assert_eq!(get_gil_count(), 0);
+    }
+
     #[test]
     fn dropping_gil_does_not_invalidate_references() {
         // Acquiring GIL for the second time should be safe - see #864
@@ -611,10 +858,30 @@ mod test {
     }
 
     #[test]
-    fn test_clone_with_gil() {
-        let gil = Python::acquire_gil();
-        let py = gil.python();
+    fn test_with_embedded_gil_state() {
+        crate::prepare_freethreaded_python();
+        // Hold the GIL in a thread without pyo3 knowing it, as in a callback called from C.
+        std::thread::spawn(|| unsafe {
+            let gstate = ffi::PyGILState_Ensure();
+            assert!(!gil_is_acquired());
+            Python::with_embedded_gil_state(|py| {
+                assert!(gil_is_acquired());
+                assert!(super::ensure_gil().0.is_none());
+                py.eval("object()", None, None).unwrap();
+                assert_eq!(owned_object_count(), 1);
+                // Nested calls use the existing pool.
+                Python::with_embedded_gil_state(|_| assert_eq!(owned_object_count(), 1));
+            });
+            assert!(!gil_is_acquired());
+            assert_eq!(owned_object_count(), 0);
+            ffi::PyGILState_Release(gstate);
+        })
+        .join()
+        .unwrap();
+    }
 
+    #[crate::test]
+    fn test_clone_with_gil(py: Python) {
         let obj = get_object(py);
         let count = obj.get_refcnt(py);
 
@@ -624,6 +891,23 @@ mod test {
         assert_eq!(count + 1, c.get_refcnt(py));
     }
 
+    #[crate::test]
+    fn test_clone_ref_does_not_register_owned(py: Python) {
+        let obj = get_object(py);
+        let count = obj.get_refcnt(py);
+
+        let owned = owned_object_count();
+        let a = obj.clone_ref(py);
+        #[allow(clippy::redundant_clone)]
+        let b = obj.clone();
+        assert_eq!(count + 2, obj.get_refcnt(py));
+        assert_eq!(owned, owned_object_count());
+
+        drop((a, b));
+        assert_eq!(count, obj.get_refcnt(py));
+        assert_eq!(owned, owned_object_count());
+    }
+
     #[test]
     fn test_clone_without_gil() {
         let gil = Python::acquire_gil();
@@ -679,15 +963,17 @@ mod test {
 
         // The pointer should appear once in the incref pool, and once in the
         // decref pool (for the clone being created and also dropped)
-        assert_eq!(&*POOL.pointers_to_incref.lock(), &vec![ptr]);
-        assert_eq!(&*POOL.pointers_to_decref.lock(), &vec![ptr]);
+        assert_eq!(&POOL.pending.lock().increfs, &vec![ptr]);
+        assert_eq!(&POOL.pending.lock().decrefs, &vec![ptr]);
+        assert!(POOL.dirty.load(Ordering::Acquire));
 
         // Re-acquring GIL will clear these pending changes
         drop(gil);
         let gil = Python::acquire_gil();
 
-        assert!(POOL.pointers_to_incref.lock().is_empty());
-        assert!(POOL.pointers_to_decref.lock().is_empty());
+        assert!(POOL.pending.lock().increfs.is_empty());
+        assert!(POOL.pending.lock().decrefs.is_empty());
+        assert!(!POOL.dirty.load(Ordering::Acquire));
 
         // Overall count is still unchanged
         assert_eq!(count, obj.get_refcnt(gil.python()));
@@ -723,4 +1009,145 @@ mod test {
             POOL.update_counts(gil.python())
         }
     }
+
+    #[test]
+    fn test_venv_site_packages() {
+        let venv = Path::new("venv");
+        assert_eq!(
+            venv_site_packages(venv, 3, 8, false),
+            Path::new("venv/lib/python3.8/site-packages")
+        );
+        assert_eq!(
+            venv_site_packages(venv, 3, 8, true),
+            venv.join("Lib").join("site-packages")
+        );
+    }
+
+    #[test]
+    fn test_pyvenv_config() {
+        let config = PyVenvConfig::parse(
+            "home = /usr/bin\n\
+             Include-System-Site-Packages = True\n\
+             version = 3.8.5\n",
+        );
+        assert!(config.include_system_site_packages);
+        assert_eq!(config.version.as_ref().map(String::as_str), Some("3.8.5"));
+
+        let config = PyVenvConfig::parse(
+            "home = /usr/bin\n\
+             implementation = CPython\n\
+             version_info = 3.7.9.final.0\n\
+             include-system-site-packages = false\n",
+        );
+        assert!(!config.include_system_site_packages);
+        assert_eq!(
+            config.version.as_ref().map(String::as_str),
+            Some("3.7.9.final.0")
+        );
+
+        let config = PyVenvConfig::parse("");
+        assert!(!config.include_system_site_packages);
+        assert!(config.version.is_none());
+    }
+
+    #[test]
+    #[cfg(Py_3_8)]
+    fn test_pool_drop_reports_panics_while_unwinding() {
+        struct PanicOnDrop;
+
+        impl Drop for PanicOnDrop {
+            fn drop(&mut self) {
+                panic!("drop failed");
+            }
+        }
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let globals = crate::types::PyDict::new(py);
+        py.run(
+            "import sys\n\
+             reported = []\n\
+             original_hook = sys.unraisablehook\n\
+             sys.unraisablehook = reported.append",
+            Some(globals),
+            None,
+        )
+        .unwrap();
+
+        let gil_count = GIL_COUNT.with(|c| c.get());
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _pool = unsafe { GILPool::new() };
+            unsafe {
+                gil::register_any(PanicOnDrop);
+                gil::register_any(PanicOnDrop);
+            }
+            panic!("unwinding");
+        }));
+        assert!(result.is_err());
+        assert_eq!(GIL_COUNT.with(|c| c.get()), gil_count);
+
+        py.run(
+            "sys.unraisablehook = original_hook\n\
+             assert len(reported) == 2, reported\n\
+             assert all(str(r.exc_value) == 'drop failed' for r in reported)",
+            Some(globals),
+            None,
+        )
+        .unwrap();
+    }
+
+    #[cfg(feature = "macros")]
+    #[crate::prelude::pyclass]
+    struct PanicOnDealloc {}
+
+    #[cfg(feature = "macros")]
+    impl Drop for PanicOnDealloc {
+        fn drop(&mut self) {
+            panic!("dealloc failed");
+        }
+    }
+
+    #[test]
+    #[cfg(all(Py_3_8, feature = "macros"))]
+    fn test_pool_drop_reports_python_object_finalizers_while_unwinding() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let globals = crate::types::PyDict::new(py);
+        py.run(
+            "import sys\n\
+             reported = []\n\
+             original_hook = sys.unraisablehook\n\
+             sys.unraisablehook = reported.append\n\
+             class RaiseOnDel:\n    \
+                 def __del__(self):\n        \
+                     raise ValueError('__del__ failed')",
+            Some(globals),
+            None,
+        )
+        .unwrap();
+
+        let gil_count = GIL_COUNT.with(|c| c.get());
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let pool = unsafe { GILPool::new() };
+            let py = pool.python();
+            // Only owned by the pool, so that they are finalized while it is dropped.
+            py.eval("RaiseOnDel()", Some(globals), None).unwrap();
+            crate::PyCell::new(py, PanicOnDealloc {}).unwrap();
+            panic!("unwinding");
+        }));
+        assert!(result.is_err());
+        assert_eq!(GIL_COUNT.with(|c| c.get()), gil_count);
+
+        py.run(
+            "sys.unraisablehook = original_hook\n\
+             assert len(reported) == 2, reported\n\
+             assert sorted(r.exc_type.__name__ for r in reported) == \
+                 ['PanicException', 'ValueError'], reported\n\
+             assert {str(r.exc_value) for r in reported} == \
+                 {'__del__ failed', 'dealloc failed'}, reported",
+            Some(globals),
+            None,
+        )
+        .unwrap();
+    }
 }
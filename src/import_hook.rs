@@ -0,0 +1,290 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Import hooks loading Python modules provided by Rust code.
+//!
+//! This makes it possible to import modules whose source is embedded in a binary, without
+//! writing it to the filesystem first.
+//!
+//! # Example
+//! ```
+//! use pyo3::import_hook::{self, ModuleFinder, ModuleSource};
+//! use pyo3::prelude::*;
+//! use std::collections::HashMap;
+//!
+//! struct Plugins(HashMap<&'static str, &'static str>);
+//!
+//! impl ModuleFinder for Plugins {
+//!     fn find_module(&self, name: &str) -> Option<ModuleSource> {
+//!         if name == "plugins" {
+//!             return Some(ModuleSource::Package(String::new()));
+//!         }
+//!         let source = self.0.get(name.strip_prefix("plugins.")?)?;
+//!         Some(ModuleSource::Source(source.to_string()))
+//!     }
+//! }
+//!
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! let mut sources = HashMap::new();
+//! sources.insert("greeting", "def greet(name):\n    return 'Hello, ' + name\n");
+//! import_hook::register(py, Plugins(sources))?;
+//!
+//! let greeting = py.import("plugins.greeting")?;
+//! let message: String = greeting.call1("greet", ("world",))?.extract()?;
+//! assert_eq!("Hello, world", message);
+//! # Ok::<(), PyErr>(())
+//! ```
+use crate::prelude::*;
+use crate::types::{IntoPyDict, PyModule};
+
+/// A module found by a [ModuleFinder].
+pub enum ModuleSource {
+    /// A module created by executing the given Python source code.
+    Source(String),
+    /// A package created by executing the given Python source code, as if it was the
+    /// `__init__.py` file of the package.
+    ///
+    /// The `__path__` of the package is an empty list, so its submodules can only be imported
+    /// through the finders in `sys.meta_path`, such as the finder which found the package. Use
+    /// an empty source for a package which only contains submodules.
+    Package(String),
+    /// A module which was already created, e.g. with [PyModule::new].
+    ///
+    /// Reloading such a module has no effect.
+    Module(Py<PyModule>),
+}
+
+/// Finds the modules to import with an import hook registered by [register].
+pub trait ModuleFinder: Send + Sync + 'static {
+    /// Returns the module with the fully qualified `name`, such as `plugins.foo`, or `None` if
+    /// this finder does not provide it.
+    ///
+    /// This is called again when the module is reloaded with `importlib.reload`.
+    fn find_module(&self, name: &str) -> Option<ModuleSource>;
+}
+
+/// Registers an import hook which imports the modules found by `finder`.
+///
+/// The hook is inserted at the start of `sys.meta_path`, so `finder` takes precedence over the
+/// modules on the filesystem, and over the hooks registered before it.
+pub fn register(py: Python, finder: impl ModuleFinder) -> PyResult<()> {
+    let finder = Py::new(
+        py,
+        RustFinder {
+            finder: Box::new(finder),
+        },
+    )?;
+    py.import("sys")?
+        .getattr("meta_path")?
+        .call_method1("insert", (0, finder))?;
+    Ok(())
+}
+
+/// The `importlib.abc.MetaPathFinder` created by [register].
+#[pyclass]
+struct RustFinder {
+    finder: Box<dyn ModuleFinder>,
+}
+
+#[pymethods]
+impl RustFinder {
+    #[args(target = "None")]
+    fn find_spec(
+        &self,
+        py: Python,
+        fullname: &str,
+        _path: &PyAny,
+        _target: Option<&PyAny>,
+    ) -> PyResult<PyObject> {
+        let source = match self.finder.find_module(fullname) {
+            Some(source) => source,
+            None => return Ok(py.None()),
+        };
+        let is_package = match source {
+            ModuleSource::Package(_) => true,
+            ModuleSource::Source(_) => false,
+            ModuleSource::Module(ref module) => module.as_ref(py).hasattr("__path__")?,
+        };
+        let loader = Py::new(
+            py,
+            RustLoader {
+                name: fullname.to_string(),
+                source,
+            },
+        )?;
+        let kwargs = [
+            ("origin", format!("<rust:{}>", fullname).to_object(py)),
+            ("is_package", is_package.to_object(py)),
+        ]
+        .into_py_dict(py);
+        let spec = py
+            .import("importlib.machinery")?
+            .getattr("ModuleSpec")?
+            .call((fullname, loader), Some(kwargs))?;
+        Ok(spec.into())
+    }
+}
+
+/// The `importlib.abc.Loader` of a module found by a [RustFinder].
+#[pyclass]
+struct RustLoader {
+    name: String,
+    source: ModuleSource,
+}
+
+#[pymethods]
+impl RustLoader {
+    fn create_module(&self, py: Python, _spec: &PyAny) -> PyObject {
+        match self.source {
+            ModuleSource::Module(ref module) => module.to_object(py),
+            // Let Python create the module.
+            _ => py.None(),
+        }
+    }
+
+    fn exec_module(&self, py: Python, module: &PyModule) -> PyResult<()> {
+        let source = match self.source {
+            ModuleSource::Source(ref source) | ModuleSource::Package(ref source) => source,
+            ModuleSource::Module(_) => return Ok(()),
+        };
+        let builtins = py.import("builtins")?;
+        let filename = format!("<rust:{}>", self.name);
+        let code = builtins.call1("compile", (source.as_str(), filename, "exec"))?;
+        builtins.call1("exec", (code, module.dict()))?;
+        Ok(())
+    }
+
+    fn is_package(&self, py: Python, _fullname: &str) -> PyResult<bool> {
+        match self.source {
+            ModuleSource::Package(_) => Ok(true),
+            ModuleSource::Source(_) => Ok(false),
+            ModuleSource::Module(ref module) => module.as_ref(py).hasattr("__path__"),
+        }
+    }
+
+    fn get_source(&self, _fullname: &str) -> Option<&str> {
+        match self.source {
+            ModuleSource::Source(ref source) | ModuleSource::Package(ref source) => Some(source),
+            ModuleSource::Module(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{register, ModuleFinder, ModuleSource};
+    use crate::exceptions::{ImportError, ZeroDivisionError};
+    use crate::types::PyModule;
+    use crate::Python;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    struct Sources {
+        prefix: &'static str,
+        modules: Mutex<HashMap<String, String>>,
+    }
+
+    impl ModuleFinder for Sources {
+        fn find_module(&self, name: &str) -> Option<ModuleSource> {
+            if name == self.prefix {
+                return Some(ModuleSource::Package(String::new()));
+            }
+            if name == format!("{}.native", self.prefix) {
+                let gil = Python::acquire_gil();
+                let py = gil.python();
+                let module = PyModule::new(py, name).unwrap();
+                module.add("answer", 42).unwrap();
+                return Some(ModuleSource::Module(module.into()));
+            }
+            let name = name.strip_prefix(self.prefix)?.strip_prefix('.')?;
+            let source = self.modules.lock().unwrap().get(name)?.clone();
+            if name.contains('.') {
+                Some(ModuleSource::Source(source))
+            } else {
+                Some(ModuleSource::Package(source))
+            }
+        }
+    }
+
+    #[test]
+    fn test_import_from_hashmap() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let mut modules = HashMap::new();
+        modules.insert("foo".to_string(), "VALUE = 1\n".to_string());
+        modules.insert(
+            "foo.bar".to_string(),
+            "from . import VALUE\nDOUBLED = VALUE * 2\n".to_string(),
+        );
+        modules.insert("broken".to_string(), "1 / 0\n".to_string());
+        register(
+            py,
+            Sources {
+                prefix: "pyo3_hook_test",
+                modules: Mutex::new(modules),
+            },
+        )
+        .unwrap();
+
+        let bar = py.import("pyo3_hook_test.foo.bar").unwrap();
+        assert_eq!(2, bar.get("DOUBLED").unwrap().extract::<i32>().unwrap());
+        let foo = py.import("pyo3_hook_test.foo").unwrap();
+        assert_eq!(
+            0,
+            foo.getattr("__path__").unwrap().len().unwrap(),
+            "packages have an empty __path__"
+        );
+        let native = py.import("pyo3_hook_test.native").unwrap();
+        assert_eq!(42, native.get("answer").unwrap().extract::<i32>().unwrap());
+
+        let err = py.import("pyo3_hook_test.broken").map(|_| ()).unwrap_err();
+        assert!(err.is_instance::<ZeroDivisionError>(py));
+        let err = py.import("pyo3_hook_test.missing").map(|_| ()).unwrap_err();
+        assert!(err.is_instance::<ImportError>(py));
+    }
+
+    #[test]
+    fn test_reload() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let mut modules = HashMap::new();
+        modules.insert("config".to_string(), "VERSION = 1\n".to_string());
+        let sources = std::sync::Arc::new(Sources {
+            prefix: "pyo3_reload_test",
+            modules: Mutex::new(modules),
+        });
+
+        struct Shared(std::sync::Arc<Sources>);
+
+        impl ModuleFinder for Shared {
+            fn find_module(&self, name: &str) -> Option<ModuleSource> {
+                self.0.find_module(name)
+            }
+        }
+
+        register(py, Shared(sources.clone())).unwrap();
+        let config = py.import("pyo3_reload_test.config").unwrap();
+        assert_eq!(1, config.get("VERSION").unwrap().extract::<i32>().unwrap());
+
+        sources
+            .modules
+            .lock()
+            .unwrap()
+            .insert("config".to_string(), "VERSION = 2\n".to_string());
+        py.import("importlib")
+            .unwrap()
+            .call1("reload", (config,))
+            .unwrap();
+        assert_eq!(2, config.get("VERSION").unwrap().extract::<i32>().unwrap());
+        assert_eq!(
+            "VERSION = 2\n",
+            config
+                .getattr("__loader__")
+                .unwrap()
+                .call_method1("get_source", ("pyo3_reload_test.config",))
+                .unwrap()
+                .extract::<&str>()
+                .unwrap()
+        );
+    }
+}
@@ -1,6 +1,6 @@
 // Copyright (c) 2017-present PyO3 Project and Contributors
+use crate::debug::{self, RefcountOp};
 use crate::err::{PyErr, PyResult};
-use crate::gil;
 use crate::object::PyObject;
 use crate::pycell::{PyBorrowError, PyBorrowMutError, PyCell};
 use crate::type_object::PyBorrowFlagLayout;
@@ -8,6 +8,7 @@ use crate::{
     ffi, AsPyPointer, FromPyObject, IntoPy, IntoPyPointer, PyAny, PyClass, PyClassInitializer,
     PyRef, PyRefMut, PyTypeInfo, Python, ToPyObject,
 };
+use crate::{gc, gil};
 use std::marker::PhantomData;
 use std::mem;
 use std::ptr::NonNull;
@@ -125,7 +126,8 @@ impl<T> Py<T> {
             !ptr.is_null() && ffi::Py_REFCNT(ptr) > 0,
             format!("REFCNT: {:?} - {:?}", ptr, ffi::Py_REFCNT(ptr))
         );
-        Py(NonNull::new_unchecked(ptr), PhantomData)
+        debug::record(RefcountOp::FromOwnedPtr, ptr);
+        Py::from_non_null(NonNull::new_unchecked(ptr))
     }
 
     /// Creates a `Py<T>` instance for the given FFI pointer.
@@ -134,8 +136,9 @@ impl<T> Py<T> {
     /// Undefined behavior if the pointer is invalid.
     #[inline]
     pub unsafe fn from_owned_ptr_or_panic(_py: Python, ptr: *mut ffi::PyObject) -> Py<T> {
+        debug::record(RefcountOp::FromOwnedPtr, ptr);
         match NonNull::new(ptr) {
-            Some(nonnull_ptr) => Py(nonnull_ptr, PhantomData),
+            Some(nonnull_ptr) => Py::from_non_null(nonnull_ptr),
             None => {
                 crate::err::panic_after_error(_py);
             }
@@ -148,8 +151,9 @@ impl<T> Py<T> {
     /// Returns `Err(PyErr)` if the pointer is NULL.
     /// Unsafe because the pointer might be invalid.
     pub unsafe fn from_owned_ptr_or_err(py: Python, ptr: *mut ffi::PyObject) -> PyResult<Py<T>> {
+        debug::record(RefcountOp::FromOwnedPtr, ptr);
         match NonNull::new(ptr) {
-            Some(nonnull_ptr) => Ok(Py(nonnull_ptr, PhantomData)),
+            Some(nonnull_ptr) => Ok(Py::from_non_null(nonnull_ptr)),
             None => Err(PyErr::fetch(py)),
         }
     }
@@ -164,8 +168,9 @@ impl<T> Py<T> {
             !ptr.is_null() && ffi::Py_REFCNT(ptr) > 0,
             format!("REFCNT: {:?} - {:?}", ptr, ffi::Py_REFCNT(ptr))
         );
+        debug::record(RefcountOp::FromBorrowedPtr, ptr);
         ffi::Py_INCREF(ptr);
-        Py(NonNull::new_unchecked(ptr), PhantomData)
+        Py::from_non_null(NonNull::new_unchecked(ptr))
     }
 
     /// Gets the reference count of the `ffi::PyObject` pointer.
@@ -175,9 +180,28 @@ impl<T> Py<T> {
     }
 
     /// Clones self by calling `Py_INCREF()` on the ptr.
+    ///
+    /// Unlike `clone`, this does not need to check whether the GIL is held, since the `py`
+    /// token proves it, which makes it the fastest way to clone a reference.
+    #[inline]
+    pub fn clone_ref(&self, _py: Python) -> Py<T> {
+        unsafe { ffi::Py_INCREF(self.0.as_ptr()) };
+        Py::from_non_null(self.0)
+    }
+
+    /// Returns whether `self` and `other` are the same object.
+    ///
+    /// This is equivalent to the Python expression `self is other`.
+    #[inline]
+    pub fn is<U: AsPyPointer>(&self, other: &U) -> bool {
+        self.as_ptr() == other.as_ptr()
+    }
+
+    /// Returns `self` as a reference to a `Py<PyAny>`, without changing the reference count.
     #[inline]
-    pub fn clone_ref(&self, py: Python) -> Py<T> {
-        unsafe { Py::from_borrowed_ptr(py, self.0.as_ptr()) }
+    pub fn as_any(&self) -> &Py<PyAny> {
+        // Safe, since `Py<T>` is a transparent wrapper of the pointer to the object.
+        unsafe { &*(self as *const Py<T> as *const Py<PyAny>) }
     }
 
     /// Returns the inner pointer without decreasing the refcount.
@@ -186,8 +210,15 @@ impl<T> Py<T> {
     pub(crate) fn into_non_null(self) -> NonNull<ffi::PyObject> {
         let pointer = self.0;
         mem::forget(self);
+        gc::handle_released::<T>();
         pointer
     }
+
+    /// Takes ownership of the reference held by `ptr`.
+    fn from_non_null(ptr: NonNull<ffi::PyObject>) -> Py<T> {
+        gc::handle_created::<T>();
+        Py(ptr, PhantomData)
+    }
 }
 
 /// Retrieves `&'py` types from `Py<T>` or `PyObject`.
@@ -262,6 +293,7 @@ impl<T> AsPyPointer for Py<T> {
     /// Gets the underlying FFI pointer, returns a borrowed pointer.
     #[inline]
     fn as_ptr(&self) -> *mut ffi::PyObject {
+        debug::record(RefcountOp::AsPtr, self.0.as_ptr());
         self.0.as_ptr()
     }
 }
@@ -271,7 +303,9 @@ impl<T> IntoPyPointer for Py<T> {
     #[inline]
     #[must_use]
     fn into_ptr(self) -> *mut ffi::PyObject {
-        self.into_non_null().as_ptr()
+        let ptr = self.into_non_null().as_ptr();
+        debug::record(RefcountOp::IntoPtr, ptr);
+        ptr
     }
 }
 
@@ -320,12 +354,16 @@ impl<T> PartialEq for Py<T> {
     }
 }
 
+/// If the GIL is held this increases the reference count immediately, otherwise the increment
+/// is deferred until the GIL is next acquired. Prefer [Py::clone_ref] when a `Python` token is
+/// available.
 impl<T> Clone for Py<T> {
+    #[inline]
     fn clone(&self) -> Self {
         unsafe {
             gil::register_incref(self.0);
         }
-        Self(self.0, PhantomData)
+        Self::from_non_null(self.0)
     }
 }
 
@@ -335,6 +373,7 @@ impl<T> Drop for Py<T> {
         unsafe {
             gil::register_decref(self.0);
         }
+        gc::handle_released::<T>();
     }
 }
 
@@ -378,12 +417,152 @@ where
     }
 }
 
+/// A strong reference to a Python object which compares and hashes by identity, like the Python
+/// expression `a is b` and the `id` function, to be used as a key of a `HashMap` or `HashSet`.
+///
+/// Unlike a raw pointer to the object, the reference keeps the object alive, so that its address
+/// cannot be reused by another object while it is a key. This means that the objects used as keys
+/// of a map are only released when they are removed from the map, or when the map is dropped. If
+/// the map is dropped without the GIL, the release of the objects is deferred until the GIL is
+/// next acquired, like for any [Py].
+///
+/// Objects which are equal but distinct, e.g. two lists with the same items, are different keys,
+/// and the objects do not need to be hashable.
+///
+/// # Example
+/// ```
+/// use pyo3::prelude::*;
+/// use pyo3::PyIdentity;
+/// use std::collections::HashMap;
+///
+/// let gil = Python::acquire_gil();
+/// let py = gil.python();
+/// let first: Py<PyAny> = py.eval("[1]", None, None)?.into();
+/// let second: Py<PyAny> = py.eval("[1]", None, None)?.into();
+/// let mut names = HashMap::new();
+/// names.insert(PyIdentity::new(first.clone_ref(py)), "first");
+/// assert_eq!(names.get(&PyIdentity::new(first)), Some(&"first"));
+/// assert_eq!(names.get(&PyIdentity::new(second)), None);
+/// # Ok::<(), PyErr>(())
+/// ```
+#[derive(Debug)]
+pub struct PyIdentity<T = PyAny>(Py<T>);
+
+impl<T> PyIdentity<T> {
+    /// Wraps `obj` to compare and hash it by identity.
+    #[inline]
+    pub fn new(obj: Py<T>) -> Self {
+        PyIdentity(obj)
+    }
+
+    /// Returns the wrapped reference.
+    #[inline]
+    pub fn get(&self) -> &Py<T> {
+        &self.0
+    }
+
+    /// Returns the wrapped reference, consuming `self`.
+    #[inline]
+    pub fn into_inner(self) -> Py<T> {
+        self.0
+    }
+}
+
+impl<T> From<Py<T>> for PyIdentity<T> {
+    #[inline]
+    fn from(obj: Py<T>) -> Self {
+        PyIdentity(obj)
+    }
+}
+
+impl<T> Clone for PyIdentity<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        PyIdentity(self.0.clone())
+    }
+}
+
+impl<T> PartialEq for PyIdentity<T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.0.is(&other.0)
+    }
+}
+
+impl<T> Eq for PyIdentity<T> {}
+
+impl<T> std::hash::Hash for PyIdentity<T> {
+    #[inline]
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        (self.0.as_ptr() as usize).hash(state)
+    }
+}
+
+impl<T> AsPyPointer for PyIdentity<T> {
+    #[inline]
+    fn as_ptr(&self) -> *mut ffi::PyObject {
+        self.0.as_ptr()
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::Py;
+    use super::{Py, PyIdentity};
     use crate::ffi;
     use crate::types::PyDict;
-    use crate::{AsPyPointer, Python};
+    use crate::{AsPyPointer, AsPyRef, PyAny, Python};
+    use std::collections::HashMap;
+
+    #[test]
+    fn py_is_and_as_any() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let dict: Py<PyDict> = PyDict::new(py).into();
+        let same = dict.clone_ref(py);
+        let other: Py<PyDict> = PyDict::new(py).into();
+        assert!(dict.is(&same));
+        assert!(!dict.is(&other));
+        assert!(dict.is(dict.as_ref(py)));
+
+        let refcnt = dict.get_refcnt(py);
+        let any: &Py<PyAny> = dict.as_any();
+        assert!(any.is(&dict));
+        assert_eq!(any.get_refcnt(py), refcnt);
+        assert!(any.as_ref(py).downcast::<PyDict>().is_ok());
+    }
+
+    #[test]
+    fn identity_keyed_cache() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let key = |code| -> Py<PyAny> { py.eval(code, None, None).unwrap().into() };
+        let first = key("[1, 2]");
+        let equal = key("[1, 2]");
+        assert!(first.as_ref(py).eq(equal.as_ref(py)).unwrap());
+
+        let mut cache = HashMap::new();
+        let lookups = std::cell::Cell::new(0);
+        let mut cached_len = |obj: &Py<PyAny>| -> usize {
+            *cache
+                .entry(PyIdentity::new(obj.clone_ref(py)))
+                .or_insert_with(|| {
+                    lookups.set(lookups.get() + 1);
+                    obj.as_ref(py).len().unwrap()
+                })
+        };
+        assert_eq!(cached_len(&first), 2);
+        assert_eq!(cached_len(&first.clone_ref(py)), 2);
+        assert_eq!(lookups.get(), 1);
+        // Equal but distinct objects are different keys.
+        assert_eq!(cached_len(&equal), 2);
+        assert_eq!(lookups.get(), 2);
+        assert_eq!(cache.len(), 2);
+
+        // The cache keeps its keys alive.
+        let refcnt = first.get_refcnt(py);
+        drop(cache);
+        assert_eq!(first.get_refcnt(py), refcnt - 1);
+    }
 
     #[test]
     fn py_from_dict() {
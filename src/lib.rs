@@ -138,14 +138,18 @@ pub use crate::conversion::{
     AsPyPointer, FromPy, FromPyObject, FromPyPointer, IntoPy, IntoPyPointer, PyTryFrom, PyTryInto,
     ToBorrowedObject, ToPyObject,
 };
-pub use crate::err::{PyDowncastError, PyErr, PyErrArguments, PyErrValue, PyResult};
+pub use crate::err::{PyDowncastError, PyErr, PyErrArguments, PyErrValue, PyResult, PyResultExt};
 pub use crate::gil::{GILGuard, GILPool};
-pub use crate::instance::{AsPyRef, Py, PyNativeType};
+pub use crate::instance::{AsPyRef, Py, PyIdentity, PyNativeType};
 pub use crate::object::PyObject;
 pub use crate::pycell::{PyCell, PyRef, PyRefMut};
 pub use crate::pyclass::PyClass;
 pub use crate::pyclass_init::PyClassInitializer;
-pub use crate::python::{prepare_freethreaded_python, Python};
+#[cfg(not(Py_LIMITED_API))]
+pub use crate::python::CompileFlags;
+pub use crate::python::{
+    prepare_freethreaded_python, prepare_freethreaded_python_with_venv, CompileMode, Python,
+};
 pub use crate::type_object::{type_flags, PyTypeInfo};
 // Since PyAny is as important as PyObject, we expose it to the top level.
 pub use crate::types::PyAny;
@@ -164,11 +168,18 @@ pub use {
 #[doc(hidden)]
 pub use libc;
 
+// The code generated by the proc macros refers to `pyo3`, which lets them be used in this crate.
+#[cfg(feature = "macros")]
+extern crate self as pyo3;
+
+#[cfg(feature = "async")]
+pub mod asyncio;
 pub mod buffer;
 #[doc(hidden)]
 pub mod callback;
 pub mod class;
 pub mod conversion;
+pub mod debug;
 #[doc(hidden)]
 pub mod derive_utils;
 mod err;
@@ -177,8 +188,12 @@ pub mod exceptions;
 #[allow(clippy::unknown_clippy_lints)]
 #[allow(clippy::missing_safety_doc)]
 pub mod ffi;
+pub mod file;
 pub mod freelist;
+pub mod gc;
 mod gil;
+#[cfg(feature = "macros")]
+pub mod import_hook;
 mod instance;
 #[macro_use]
 mod internal_tricks;
@@ -192,6 +207,8 @@ pub mod pyclass;
 pub mod pyclass_init;
 pub mod pyclass_slots;
 mod python;
+pub mod signals;
+pub mod sys;
 pub mod type_object;
 pub mod types;
 
@@ -209,14 +226,62 @@ pub mod proc_macro {
     pub use pyo3cls::{pyclass, pyfunction, pymethods, pyproto};
 }
 
+/// Runs a test with the GIL.
+///
+/// `#[pyo3::test]` replaces `#[test]` for the tests which use Python. It initializes the
+/// interpreter once, then calls the test with the GIL held, as with [Python::with_gil], passing
+/// a [Python] if the function takes one. A test can return a [PyResult], or a `Result` whose error
+/// converts into a [PyErr]: an error fails the test with the traceback of the exception.
+///
+/// The tests run concurrently, as usual. `#[pyo3::test(exclusive)]` runs a test alone, while
+/// no other `#[pyo3::test]` runs, e.g. when it changes the global state of the interpreter.
+///
+/// With the `async` feature, a test can also be an `async fn`, which is run with
+/// [asyncio::run]. Async tests are always exclusive, and use a runtime running each future in its
+/// own thread if no runtime was initialized with [asyncio::init_runtime].
+///
+/// Other attributes, such as `#[should_panic]` or `#[ignore]`, apply to the test as usual.
+///
+/// # Example
+/// ```
+/// use pyo3::prelude::*;
+///
+/// #[pyo3::test]
+/// fn test_sum(py: Python) -> PyResult<()> {
+///     let sum: i32 = py.eval("sum(range(4))", None, None)?.extract()?;
+///     assert_eq!(6, sum);
+///     Ok(())
+/// }
+///
+/// #[pyo3::test]
+/// #[should_panic(expected = "ZeroDivisionError")]
+/// fn test_division(py: Python) -> PyResult<()> {
+///     py.eval("1 / 0", None, None)?;
+///     Ok(())
+/// }
+/// # fn main() {}
+/// ```
+#[cfg(feature = "macros")]
+pub use pyo3cls::test;
+
 /// Returns a function that takes a [Python] instance and returns a Python function.
 ///
 /// Use this together with `#[pyfunction]` and [types::PyModule::add_wrapped].
+///
+/// With a module as second argument, `wrap_pyfunction!(function, module)` creates a function
+/// bound to that module, whose [types::ModuleState] arguments are the state of the module.
 #[macro_export]
 macro_rules! wrap_pyfunction {
     ($function_name: ident) => {{
         &pyo3::paste::expr! { [<__pyo3_get_function_ $function_name>] }
     }};
+    ($function_name: ident, $module: expr) => {{
+        let module: &pyo3::types::PyModule = $module;
+        &move |py| {
+            let function = pyo3::paste::expr! { [<__pyo3_get_function_in_module_ $function_name>] };
+            function(py, Some(module))
+        }
+    }};
 }
 
 /// Returns a function that takes a [Python] instance and returns a Python module.
@@ -226,8 +291,203 @@ macro_rules! wrap_pyfunction {
 macro_rules! wrap_pymodule {
     ($module_name:ident) => {{
         pyo3::paste::expr! {
-            &|py| unsafe { pyo3::PyObject::from_owned_ptr(py, [<PyInit_ $module_name>]()) }
+            &|py| unsafe {
+                pyo3::derive_utils::module_from_init(py, [<PyInit_ $module_name>]())
+                    .expect("failed to create the module")
+            }
+        }
+    }};
+}
+
+/// Creates a `PyDict` of keyword arguments, to be passed to e.g. [PyAny::call_kwargs] or
+/// [PyAny::call_method_kwargs].
+///
+/// The first argument is a [Python] token, followed by `name = value` pairs, where each value
+/// implements [ToPyObject]. The macro evaluates to `PyResult<&PyDict>`. Passing the same name
+/// twice is a compile-time error.
+///
+/// # Example
+/// ```
+/// use pyo3::{kwargs, prelude::*};
+///
+/// # fn main() -> PyResult<()> {
+/// let gil = Python::acquire_gil();
+/// let py = gil.python();
+/// let int = py.import("builtins")?.get("int")?;
+/// let value: i32 = int.call_kwargs(("ff",), kwargs!(py, base = 16)?)?.extract()?;
+/// assert_eq!(value, 255);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// ```compile_fail
+/// # use pyo3::{kwargs, prelude::*};
+/// # let gil = Python::acquire_gil();
+/// # let py = gil.python();
+/// let kwargs = kwargs!(py, timeout = 5, timeout = 10);
+/// ```
+#[macro_export]
+macro_rules! kwargs {
+    ($py:expr $(,)?) => {
+        $crate::types::PyDict::from_pairs::<(&str, $crate::PyObject), _>($py, ::std::vec![])
+    };
+    ($py:expr, $($key:ident = $value:expr),+ $(,)?) => {{
+        // The keys are declared as variants, so that duplicates are rejected by the compiler.
+        #[allow(non_camel_case_types, dead_code)]
+        enum Keys {
+            $($key),+
+        }
+        let py: $crate::Python = $py;
+        $crate::types::PyDict::from_pairs(
+            py,
+            &[$((::std::stringify!($key), $crate::ToPyObject::to_object(&$value, py))),+],
+        )
+    }};
+}
+
+/// Creates a `PyTuple` from Rust values of different types.
+///
+/// The first argument is a [Python] token, followed by the elements, which are converted
+/// independently with [ToPyObject]. An element written `..iterable` is replaced by the items of
+/// `iterable`, e.g. an existing `&PyTuple`. The macro evaluates to `&PyTuple`.
+///
+/// # Example
+/// ```
+/// use pyo3::{prelude::*, py_tuple};
+///
+/// # fn main() -> PyResult<()> {
+/// let gil = Python::acquire_gil();
+/// let py = gil.python();
+/// let rest = py_tuple!(py, 2.5, "three");
+/// let args = py_tuple!(py, 1, ..rest, vec![4, 5]);
+/// assert_eq!(args.repr()?.to_str()?, "(1, 2.5, 'three', [4, 5])");
+/// # Ok(())
+/// # }
+/// ```
+#[macro_export]
+macro_rules! py_tuple {
+    ($py:expr $(,)?) => {
+        $crate::types::PyTuple::empty($py)
+    };
+    ($py:expr, $($elements:tt)+) => {{
+        let py: $crate::Python = $py;
+        let mut elements: ::std::vec::Vec<$crate::PyObject> = ::std::vec::Vec::new();
+        $crate::py_sequence_elements!(py, elements, $($elements)+);
+        $crate::types::PyTuple::new(py, elements)
+    }};
+}
+
+/// Creates a `PyList` from Rust values of different types.
+///
+/// This takes the same arguments as [py_tuple!], including `..iterable` elements, and evaluates
+/// to `&PyList`.
+///
+/// # Example
+/// ```
+/// use pyo3::{prelude::*, py_list};
+///
+/// # fn main() -> PyResult<()> {
+/// let gil = Python::acquire_gil();
+/// let py = gil.python();
+/// let list = py_list!(py, 1, "two", py_list!(py, 3.0), ..vec![4, 5]);
+/// assert_eq!(list.repr()?.to_str()?, "[1, 'two', [3.0], 4, 5]");
+/// # Ok(())
+/// # }
+/// ```
+#[macro_export]
+macro_rules! py_list {
+    ($py:expr $(,)?) => {
+        $crate::types::PyList::empty($py)
+    };
+    ($py:expr, $($elements:tt)+) => {{
+        let py: $crate::Python = $py;
+        let mut elements: ::std::vec::Vec<$crate::PyObject> = ::std::vec::Vec::new();
+        $crate::py_sequence_elements!(py, elements, $($elements)+);
+        $crate::types::PyList::new(py, elements)
+    }};
+}
+
+/// Converts the elements of [py_tuple!] and [py_list!] one by one, so that `..iterable` can be
+/// told apart from an element.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! py_sequence_elements {
+    ($py:ident, $elements:ident $(,)?) => {};
+    ($py:ident, $elements:ident, .. $iterable:expr $(, $($tail:tt)*)?) => {
+        for element in $iterable {
+            $elements.push($crate::ToPyObject::to_object(&element, $py));
         }
+        $crate::py_sequence_elements!($py, $elements $(, $($tail)*)?);
+    };
+    ($py:ident, $elements:ident, $element:expr $(, $($tail:tt)*)?) => {
+        $elements.push($crate::ToPyObject::to_object(&$element, $py));
+        $crate::py_sequence_elements!($py, $elements $(, $($tail)*)?);
+    };
+}
+
+/// Creates a `PyDict` from `key => value` pairs of Rust values of different types.
+///
+/// The first argument is a [Python] token, followed by the pairs, whose keys and values are
+/// converted independently with [ToPyObject]. The macro evaluates to `PyResult<&PyDict>`, which
+/// is an error if a key is not hashable. Like in a Python `dict` literal, the last value of a
+/// repeated key is kept.
+///
+/// # Example
+/// ```
+/// use pyo3::{prelude::*, py_dict, py_list};
+///
+/// # fn main() -> PyResult<()> {
+/// let gil = Python::acquire_gil();
+/// let py = gil.python();
+/// let dict = py_dict!(py, "name" => "pyo3", 1 => py_list!(py, true, 2))?;
+/// assert_eq!(dict.repr()?.to_str()?, "{'name': 'pyo3', 1: [True, 2]}");
+/// # Ok(())
+/// # }
+/// ```
+#[macro_export]
+macro_rules! py_dict {
+    ($py:expr $(,)?) => {
+        $crate::types::PyDict::from_pairs::<($crate::PyObject, $crate::PyObject), _>(
+            $py,
+            ::std::vec![],
+        )
+    };
+    ($py:expr, $($key:expr => $value:expr),+ $(,)?) => {{
+        let py: $crate::Python = $py;
+        $crate::types::PyDict::from_pairs(
+            py,
+            ::std::vec![$((
+                $crate::ToPyObject::to_object(&$key, py),
+                $crate::ToPyObject::to_object(&$value, py),
+            )),+],
+        )
+    }};
+}
+
+/// Imports a Python module, caching it in a static so that the next calls from the same call
+/// site return it without any lookup.
+///
+/// The module name must be a string literal. A module which fails to import is not cached, so
+/// that the import is tried again by the next call. See
+/// [ImportCell](once_cell/struct.ImportCell.html) for details, and
+/// [Python::import_cached](struct.Python.html#method.import_cached) for a version with a module
+/// name that is not known at the call site.
+///
+/// # Example
+/// ```
+/// use pyo3::{import_cached, prelude::*};
+///
+/// fn to_json(py: Python, value: Vec<i32>) -> PyResult<String> {
+///     import_cached!(py, "json")?.call1("dumps", (value,))?.extract()
+/// }
+/// # let gil = Python::acquire_gil();
+/// # assert_eq!(to_json(gil.python(), vec![1, 2]).unwrap(), "[1, 2]");
+/// ```
+#[macro_export]
+macro_rules! import_cached {
+    ($py:expr, $name:literal) => {{
+        static CELL: $crate::once_cell::ImportCell = $crate::once_cell::ImportCell::new();
+        CELL.import($py, $name)
     }};
 }
 
@@ -8,9 +8,15 @@ pub const VERSION: i32 = 4;
 
 /// Serialize an object to bytes using the Python built-in marshal module.
 ///
-/// The built-in marshalling only supports a limited range of objects.
+/// The built-in marshalling only supports a limited range of objects: `None`, `bool`, `int`,
+/// `float`, `complex`, `str`, `bytes`, `bytearray`, code objects, `Ellipsis`, `StopIteration`,
+/// and `tuple`, `list`, `set`, `frozenset` and `dict` objects containing only supported objects.
+/// Serializing any other object returns a `ValueError`.
+///
 /// The exact types supported depend on the version argument.
 /// The [`VERSION`] constant holds the highest version currently supported.
+/// The format is specific to the Python version, so it is suitable for caches, such as compiled
+/// code, but not for persistent storage or communication between interpreters.
 ///
 /// See the [Python documentation](https://docs.python.org/3/library/marshal.html) for more details.
 ///
@@ -35,6 +41,12 @@ pub fn dumps<'a>(py: Python<'a>, object: &impl AsPyPointer, version: i32) -> PyR
 }
 
 /// Deserialize an object from bytes using the Python built-in marshal module.
+///
+/// **Warning:** this must only be used on trusted data, such as data serialized by [dumps] in a
+/// cache owned by the application. Malicious or corrupted data can make the interpreter crash or
+/// run arbitrary code, e.g. through code objects which are executed later.
+///
+/// Returns an error, such as an `EOFError` or a `ValueError`, if `data` is not valid.
 pub fn loads<'a, B>(py: Python<'a>, data: &B) -> PyResult<&'a PyAny>
 where
     B: AsRef<[u8]> + ?Sized,
@@ -50,7 +62,10 @@ where
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::types::PyDict;
+    use crate::exceptions::{EOFError, ValueError};
+    #[cfg(not(Py_LIMITED_API))]
+    use crate::types::PyCode;
+    use crate::types::{IntoPyDict, PyDict};
 
     #[test]
     fn marhshal_roundtrip() {
@@ -70,6 +85,62 @@ mod test {
         assert!(equal(py, dict, deserialzed));
     }
 
+    #[test]
+    fn marshal_nested_containers() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let value = py
+            .eval(
+                "{'list': [1, 2.5, (None, True)], 'set': frozenset({b'x', 3j}), 'big': 2 ** 100}",
+                None,
+                None,
+            )
+            .unwrap();
+        for version in 0..=VERSION {
+            let bytes = dumps(py, value, version).unwrap().as_bytes();
+            assert!(equal(py, value, loads(py, bytes).unwrap()));
+        }
+    }
+
+    #[test]
+    #[cfg(not(Py_LIMITED_API))]
+    fn marshal_code() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let code = py
+            .compile("x * 2", "<double>", crate::CompileMode::Eval)
+            .unwrap();
+        let bytes = dumps(py, code, VERSION).unwrap().as_bytes();
+        let code: &PyCode = loads(py, bytes).unwrap().downcast().unwrap();
+        let globals = [("x", 21)].into_py_dict(py);
+        let result = py.run_code(code, Some(globals), None).unwrap();
+        assert_eq!(42, result.extract::<i32>().unwrap());
+    }
+
+    #[test]
+    fn marshal_errors() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let unsupported = py.eval("object()", None, None).unwrap();
+        let err = dumps(py, unsupported, VERSION).map(|_| ()).unwrap_err();
+        assert!(err.is_instance::<ValueError>(py));
+
+        let nested = py.eval("[1, {'a': object()}]", None, None).unwrap();
+        let err = dumps(py, nested, VERSION).map(|_| ()).unwrap_err();
+        assert!(err.is_instance::<ValueError>(py));
+
+        let bytes = dumps(py, nested.get_item(0).unwrap(), VERSION)
+            .unwrap()
+            .as_bytes();
+        let err = loads(py, &bytes[..bytes.len() - 1])
+            .map(|_| ())
+            .unwrap_err();
+        assert!(err.is_instance::<EOFError>(py));
+    }
+
     fn equal(_py: Python, a: &impl AsPyPointer, b: &impl AsPyPointer) -> bool {
         unsafe { ffi::PyObject_RichCompareBool(a.as_ptr(), b.as_ptr(), ffi::Py_EQ) != 0 }
     }
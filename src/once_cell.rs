@@ -1,5 +1,8 @@
-use crate::Python;
+use crate::types::PyModule;
+use crate::{ffi, AsPyRef, Py, PyResult, Python};
 use std::cell::UnsafeCell;
+use std::mem;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 /// A write-once cell similar to [`once_cell::OnceCell`](https://docs.rs/once_cell/1.4.0/once_cell/).
 ///
@@ -80,6 +83,22 @@ impl<T> GILOnceCell<T> {
         self.get(py).unwrap()
     }
 
+    /// Like [get_or_init](#method.get_or_init), but if `f()` fails, the error is returned and the
+    /// cell is left empty, so that the initialization is tried again by the next call.
+    pub fn get_or_try_init<F, E>(&self, py: Python, f: F) -> Result<&T, E>
+    where
+        F: FnOnce() -> Result<T, E>,
+    {
+        if let Some(value) = self.get(py) {
+            return Ok(value);
+        }
+
+        let value = f()?;
+        let _ = self.set(py, value);
+
+        Ok(self.get(py).unwrap())
+    }
+
     /// Get the contents of the cell mutably. This is only possible if the reference to the cell is
     /// unique.
     pub fn get_mut(&mut self) -> Option<&mut T> {
@@ -102,3 +121,78 @@ impl<T> GILOnceCell<T> {
         Ok(())
     }
 }
+
+/// A cell holding an imported module, used by [import_cached!](../macro.import_cached.html) and
+/// [Python::import_cached](../struct.Python.html#method.import_cached).
+///
+/// Like [GILOnceCell], the module is imported once and then returned without any lookup. A module
+/// which fails to import is not cached, so that the import is tried again by the next call.
+///
+/// The cached module belongs to the interpreter which imported it: if that interpreter is
+/// finalized, e.g. by `Py_FinalizeEx` in tests, and a new one is initialized, the module is
+/// imported again in the new interpreter.
+pub struct ImportCell(UnsafeCell<Option<(usize, Py<PyModule>)>>);
+
+// Accessed only with the GIL held, like `GILOnceCell`.
+unsafe impl Sync for ImportCell {}
+
+impl Default for ImportCell {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Incremented whenever the interpreter is finalized.
+static INTERPRETER_GENERATION: AtomicUsize = AtomicUsize::new(0);
+/// `INTERPRETER_GENERATION + 1` once `Py_AtExit` has been called for the current interpreter.
+static FINALIZATION_TRACKED: AtomicUsize = AtomicUsize::new(0);
+
+impl ImportCell {
+    /// Create an `ImportCell` which does not yet contain a module.
+    pub const fn new() -> Self {
+        Self(UnsafeCell::new(None))
+    }
+
+    /// Returns the cached module, importing it with `name` if needed.
+    pub fn import<'p>(&'p self, py: Python<'p>, name: &str) -> PyResult<&'p PyModule> {
+        let generation = INTERPRETER_GENERATION.load(Ordering::SeqCst);
+        if let Some(module) = self.get(py, generation) {
+            return Ok(module);
+        }
+
+        // The import may temporarily release the GIL, so another thread may fill the cell first.
+        let module = py.import(name)?;
+        if let Some(module) = self.get(py, generation) {
+            return Ok(module);
+        }
+        track_finalization(generation);
+        // Safe because the GIL is held, and references to the previous module, if any, belong to
+        // a finalized interpreter.
+        let slot = unsafe { &mut *self.0.get() };
+        if let Some((_, stale)) = slot.take() {
+            // Its interpreter is finalized, so decreasing its reference count would be invalid.
+            mem::forget(stale);
+        }
+        *slot = Some((generation, module.into()));
+        Ok(module)
+    }
+
+    fn get<'p>(&'p self, py: Python<'p>, generation: usize) -> Option<&'p PyModule> {
+        match unsafe { &*self.0.get() } {
+            Some((cached, module)) if *cached == generation => Some(module.as_ref(py)),
+            _ => None,
+        }
+    }
+}
+
+/// Makes sure the finalization of the current interpreter invalidates the `ImportCell`s.
+fn track_finalization(generation: usize) {
+    extern "C" fn finalized() {
+        INTERPRETER_GENERATION.fetch_add(1, Ordering::SeqCst);
+    }
+
+    // `Py_AtExit` functions are called once, at the end of `Py_FinalizeEx`.
+    if FINALIZATION_TRACKED.swap(generation + 1, Ordering::SeqCst) != generation + 1 {
+        unsafe { ffi::Py_AtExit(Some(finalized)) };
+    }
+}
@@ -1,12 +1,167 @@
+//! Conversion of Rust panics into Python exceptions.
+//!
+//! When Rust code called from Python panics, the panic is caught at the boundary and turned into
+//! a [PanicException] (by default), which is importable in Python as
+//! `pyo3_runtime.PanicException`. The conversion can be customized with [set_hook].
+
 use crate::exceptions::BaseException;
+use crate::once_cell::GILOnceCell;
+use crate::type_object::PyTypeObject;
+use crate::types::{PyDict, PyModule, PyType};
+use crate::{AsPyRef, Py, PyErr, PyResult, Python};
+use parking_lot::{const_mutex, Mutex};
+use std::any::Any;
 
 /// The exception raised when Rust code called from Python panics.
 ///
 /// Like SystemExit, this exception is derived from BaseException so that
 /// it will typically propagate all the way through the stack and cause the
 /// Python interpreter to exit.
+///
+/// In Python, the type is available as `pyo3_runtime.PanicException` once it has been
+/// created, which happens at the latest when a `#[pymodule]` is initialized.
 pub struct PanicException {
     _private: (),
 }
 
-pyo3_exception!(PanicException, BaseException);
+crate::impl_exception_boilerplate!(PanicException);
+
+unsafe impl PyTypeObject for PanicException {
+    fn type_object(py: Python) -> &PyType {
+        static TYPE_OBJECT: GILOnceCell<Py<PyType>> = GILOnceCell::new();
+        static REGISTERED: GILOnceCell<()> = GILOnceCell::new();
+
+        let ty = TYPE_OBJECT
+            .get_or_init(py, || unsafe {
+                Py::from_owned_ptr(
+                    py,
+                    PyErr::new_type(
+                        py,
+                        "pyo3_runtime.PanicException",
+                        Some(py.get_type::<BaseException>()),
+                        None,
+                    )
+                    .as_ptr() as *mut crate::ffi::PyObject,
+                )
+            })
+            .as_ref(py);
+
+        REGISTERED.get_or_init(py, || {
+            register_runtime_type(py, "PanicException", ty)
+                .expect("Failed to register PanicException in the pyo3_runtime module")
+        });
+        ty
+    }
+}
+
+/// Adds `ty` to the `pyo3_runtime` module in `sys.modules`, creating the module if necessary.
+fn register_runtime_type(py: Python, name: &str, ty: &PyType) -> PyResult<()> {
+    // Lookup failures must not go through `PyErr::fetch`, which needs this type object.
+    let modules: &PyDict = py.import("sys")?.get("modules")?.downcast()?;
+    let module = match modules.get_item("pyo3_runtime") {
+        Some(module) => module,
+        None => {
+            let module = PyModule::new(py, "pyo3_runtime")?;
+            modules.set_item("pyo3_runtime", module)?;
+            module
+        }
+    };
+    module.setattr(name, ty)
+}
+
+/// Information about a panic caught at the boundary between Rust and Python.
+pub struct PanicInfo<'a> {
+    payload: &'a (dyn Any + Send),
+}
+
+impl<'a> PanicInfo<'a> {
+    /// The payload the panic was started with, usually a `&'static str` or a `String`.
+    pub fn payload(&self) -> &(dyn Any + Send) {
+        self.payload
+    }
+
+    /// The panic message, if the payload is a `&str` or a `String`.
+    pub fn message(&self) -> Option<&str> {
+        if let Some(string) = self.payload.downcast_ref::<String>() {
+            Some(string)
+        } else if let Some(s) = self.payload.downcast_ref::<&str>() {
+            Some(s)
+        } else {
+            None
+        }
+    }
+
+    /// The name of the thread which panicked, if it has one.
+    ///
+    /// Panics are caught on the thread they happen on, so this is the name of the current thread.
+    pub fn thread_name(&self) -> Option<String> {
+        std::thread::current().name().map(ToOwned::to_owned)
+    }
+}
+
+/// A function converting a caught panic into the exception raised in Python.
+///
+/// The stack has already been unwound by the time the hook runs; use `std::panic::set_hook` to
+/// capture a backtrace.
+pub type PanicHook = fn(Python, &PanicInfo) -> PyErr;
+
+static PANIC_HOOK: Mutex<Option<PanicHook>> = const_mutex(None);
+
+/// Registers a custom hook used to convert panics in Rust code called from Python into Python
+/// exceptions, replacing any previously registered hook.
+///
+/// If the hook itself panics, the default conversion into a [PanicException] is used instead.
+///
+/// # Example
+/// ```
+/// use pyo3::panic::{PanicException, PanicInfo};
+/// use pyo3::prelude::*;
+///
+/// fn hook(py: Python, info: &PanicInfo) -> PyErr {
+///     let message = info.message().unwrap_or("panic from Rust code");
+///     match py.get_type::<PanicException>().call1((message,)) {
+///         Ok(exc) => {
+///             let _ = exc.setattr("thread_name", info.thread_name());
+///             PyErr::from_instance(exc)
+///         }
+///         Err(e) => e,
+///     }
+/// }
+///
+/// pyo3::panic::set_hook(hook);
+/// # pyo3::panic::take_hook();
+/// ```
+pub fn set_hook(hook: PanicHook) {
+    *PANIC_HOOK.lock() = Some(hook);
+}
+
+/// Unregisters the current panic hook, returning it.
+///
+/// Afterwards panics are converted into a [PanicException] carrying the panic message.
+pub fn take_hook() -> Option<PanicHook> {
+    PANIC_HOOK.lock().take()
+}
+
+fn default_hook(_py: Python, info: &PanicInfo) -> PyErr {
+    // Try to format the error in the same way panic does
+    match info.message() {
+        Some(message) => PanicException::py_err((message.to_string(),)),
+        None => PanicException::py_err(("panic from Rust code",)),
+    }
+}
+
+impl PanicException {
+    /// Converts the payload of a caught panic into a `PyErr`, using the registered hook.
+    #[doc(hidden)]
+    pub fn from_panic_payload(py: Python, payload: Box<dyn Any + Send>) -> PyErr {
+        let info = PanicInfo { payload: &*payload };
+        let hook = *PANIC_HOOK.lock();
+        match hook {
+            Some(hook) => {
+                std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| hook(py, &info)))
+                    .unwrap_or_else(|_| default_hook(py, &info))
+            }
+            None => default_hook(py, &info),
+        }
+    }
+}
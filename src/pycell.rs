@@ -3,8 +3,8 @@ use crate::conversion::{AsPyPointer, FromPyPointer, ToPyObject};
 use crate::pyclass::{PyClass, PyClassThreadChecker};
 use crate::pyclass_init::PyClassInitializer;
 use crate::pyclass_slots::{PyClassDict, PyClassWeakRef};
-use crate::type_object::{PyBorrowFlagLayout, PyLayout, PySizedLayout, PyTypeInfo};
-use crate::types::PyAny;
+use crate::type_object::{PyBorrowFlagLayout, PyLayout, PySizedLayout, PyTypeInfo, PyTypeObject};
+use crate::types::{PyAny, PySuper};
 use crate::{ffi, FromPy, PyErr, PyNativeType, PyObject, PyResult, Python};
 use std::cell::{Cell, UnsafeCell};
 use std::fmt;
@@ -184,6 +184,14 @@ impl<T: PyClass> PyCell<T> {
         }
     }
 
+    /// Returns a `super` object for this object and the class `T`, to call methods of the base
+    /// class of `T`.
+    ///
+    /// This is equivalent to the Python expression `super(T, self)`.
+    pub fn py_super(&self) -> PyResult<&PySuper> {
+        PySuper::new(self.py(), T::type_object(self.py()), self)
+    }
+
     /// Immutably borrows the value `T`. This borrow lasts untill the returned `PyRef` exists.
     ///
     /// # Panics
@@ -232,7 +240,10 @@ impl<T: PyClass> PyCell<T> {
         self.thread_checker.ensure();
         let flag = self.inner.get_borrow_flag();
         if flag == BorrowFlag::HAS_MUTABLE_BORROW {
-            Err(PyBorrowError { _private: () })
+            Err(PyBorrowError {
+                class: T::NAME,
+                method: None,
+            })
         } else {
             self.inner.set_borrow_flag(flag.increment());
             Ok(PyRef { inner: &self.inner })
@@ -263,7 +274,10 @@ impl<T: PyClass> PyCell<T> {
     pub fn try_borrow_mut(&self) -> Result<PyRefMut<'_, T>, PyBorrowMutError> {
         self.thread_checker.ensure();
         if self.inner.get_borrow_flag() != BorrowFlag::UNUSED {
-            Err(PyBorrowMutError { _private: () })
+            Err(PyBorrowMutError {
+                class: T::NAME,
+                method: None,
+            })
         } else {
             self.inner.set_borrow_flag(BorrowFlag::HAS_MUTABLE_BORROW);
             Ok(PyRefMut { inner: &self.inner })
@@ -302,7 +316,10 @@ impl<T: PyClass> PyCell<T> {
     pub unsafe fn try_borrow_unguarded(&self) -> Result<&T, PyBorrowError> {
         self.thread_checker.ensure();
         if self.inner.get_borrow_flag() == BorrowFlag::HAS_MUTABLE_BORROW {
-            Err(PyBorrowError { _private: () })
+            Err(PyBorrowError {
+                class: T::NAME,
+                method: None,
+            })
         } else {
             Ok(&*self.inner.value.get())
         }
@@ -339,6 +356,21 @@ impl<T: PyClass> PyCell<T> {
         std::mem::swap(&mut *self.borrow_mut(), &mut *other.borrow_mut())
     }
 
+    /// The offset of the `__dict__` slot, if `T` has one.
+    pub(crate) fn dict_offset() -> Option<ffi::Py_ssize_t> {
+        // `PyCell` is `repr(C)`, and the alignment of `PyCellInner<T>`, which begins with a
+        // `PyObject`, is at least the alignment of the slots.
+        T::Dict::OFFSET.map(|_| std::mem::size_of::<PyCellInner<T>>() as ffi::Py_ssize_t)
+    }
+
+    /// The offset of the `__weakref__` slot, if `T` has one.
+    pub(crate) fn weakref_offset() -> Option<ffi::Py_ssize_t> {
+        T::WeakRef::OFFSET.map(|_| {
+            (std::mem::size_of::<PyCellInner<T>>() + std::mem::size_of::<T::Dict>())
+                as ffi::Py_ssize_t
+        })
+    }
+
     /// Allocates a new PyCell given a type object `subtype`. Used by our `tp_new` implementation.
     /// Requires `T::BaseLayout: PyBorrowFlagLayout<T::BaseType>` to ensure `self` has a borrow flag.
     pub(crate) unsafe fn internal_new(
@@ -358,6 +390,7 @@ impl<T: PyClass> PyCell<T> {
         (*self_).dict = T::Dict::new();
         (*self_).weakref = T::WeakRef::new();
         (*self_).thread_checker = T::ThreadChecker::new();
+        crate::pyclass::init_vectorcall::<T>(py, subtype, self_ as _);
         Ok(self_)
     }
 }
@@ -470,6 +503,19 @@ impl<'p, T: PyClass> PyRef<'p, T> {
     pub fn py(&self) -> Python {
         unsafe { Python::assume_gil_acquired() }
     }
+
+    /// Returns a `super` object for this object and the class `T`, to call methods of the base
+    /// class of `T`.
+    ///
+    /// This is equivalent to the Python expression `super(T, self)`, and is typically used in
+    /// an overriding method to delegate to the base implementation. If that implementation
+    /// is a Rust method taking `&mut self`, `self` must be passed as `PyRef` rather than
+    /// `PyRefMut`, as the object cannot be borrowed mutably twice.
+    pub fn py_super(&self) -> PyResult<&'p PySuper> {
+        let py = unsafe { Python::assume_gil_acquired() };
+        let obj: &PyAny = unsafe { py.from_borrowed_ptr(self.as_ptr()) };
+        PySuper::new(py, T::type_object(py), obj)
+    }
 }
 
 impl<'p, T, U> AsRef<U> for PyRef<'p, T>
@@ -587,6 +633,16 @@ impl<'p, T: PyClass> PyRefMut<'p, T> {
     pub fn py(&self) -> Python {
         unsafe { Python::assume_gil_acquired() }
     }
+
+    /// Returns a `super` object for this object and the class `T`, to call methods of the base
+    /// class of `T`.
+    ///
+    /// See [PyRef::py_super].
+    pub fn py_super(&self) -> PyResult<&'p PySuper> {
+        let py = unsafe { Python::assume_gil_acquired() };
+        let obj: &PyAny = unsafe { py.from_borrowed_ptr(self.as_ptr()) };
+        PySuper::new(py, T::type_object(py), obj)
+    }
 }
 
 impl<'p, T, U> AsRef<U> for PyRefMut<'p, T>
@@ -686,43 +742,143 @@ impl BorrowFlag {
     }
 }
 
-/// An error returned by [`PyCell::try_borrow`](struct.PyCell.html#method.try_borrow).
+/// An error returned by [`PyCell::try_borrow`](struct.PyCell.html#method.try_borrow), when the
+/// value is mutably borrowed.
+///
+/// It converts to a `PyErr` of the same type, which is a subclass of `RuntimeError` available in
+/// Python as `pyo3_runtime.PyBorrowError`.
 ///
-/// In Python, you can catch this error by `except RuntimeError`.
+/// When the borrow is made to call a method of a `#[pyclass]`, the error records the method.
+/// This usually happens when a method taking `&mut self` calls back into Python code, e.g. a
+/// callback, which calls a method of the same object (a re-entrant call). To allow such calls,
+/// the first method can take `slf: &PyCell<Self>` instead, and only borrow the value while it
+/// does not call back into Python.
 pub struct PyBorrowError {
-    _private: (),
+    class: &'static str,
+    method: Option<&'static str>,
+}
+
+impl PyBorrowError {
+    /// Returns the name of the class of the borrowed object.
+    pub fn class_name(&self) -> &'static str {
+        self.class
+    }
+
+    /// Returns the name of the method for which the object was borrowed, if any.
+    pub fn method(&self) -> Option<&'static str> {
+        self.method
+    }
+
+    /// Records the method for which the object was borrowed.
+    #[doc(hidden)]
+    pub fn entering(self, method: &'static str) -> Self {
+        PyBorrowError {
+            method: Some(method),
+            ..self
+        }
+    }
 }
 
 impl fmt::Debug for PyBorrowError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("PyBorrowError").finish()
+        f.debug_struct("PyBorrowError")
+            .field("class", &self.class)
+            .field("method", &self.method)
+            .finish()
     }
 }
 
 impl fmt::Display for PyBorrowError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        fmt::Display::fmt("Already mutably borrowed", f)
+        match self.method {
+            Some(method) => write!(
+                f,
+                "cannot call '{0}.{1}': the '{0}' object is already mutably borrowed, \
+                 probably by a method of the object taking `&mut self` which called \
+                 '{0}.{1}' again, e.g. through a callback (a re-entrant call)",
+                self.class, method
+            ),
+            None => write!(f, "the '{}' object is already mutably borrowed", self.class),
+        }
     }
 }
 
-/// An error returned by [`PyCell::try_borrow_mut`](struct.PyCell.html#method.try_borrow_mut).
+impl std::error::Error for PyBorrowError {}
+
+/// An error returned by [`PyCell::try_borrow_mut`](struct.PyCell.html#method.try_borrow_mut),
+/// when the value is borrowed.
 ///
-/// In Python, you can catch this error by `except RuntimeError`.
+/// It converts to a `PyErr` of the same type, which is a subclass of `RuntimeError` available in
+/// Python as `pyo3_runtime.PyBorrowMutError`.
+///
+/// Like [PyBorrowError], it records the method for which the object was borrowed.
 pub struct PyBorrowMutError {
-    _private: (),
+    class: &'static str,
+    method: Option<&'static str>,
+}
+
+impl PyBorrowMutError {
+    /// Returns the name of the class of the borrowed object.
+    pub fn class_name(&self) -> &'static str {
+        self.class
+    }
+
+    /// Returns the name of the method for which the object was borrowed, if any.
+    pub fn method(&self) -> Option<&'static str> {
+        self.method
+    }
+
+    /// Records the method for which the object was borrowed.
+    #[doc(hidden)]
+    pub fn entering(self, method: &'static str) -> Self {
+        PyBorrowMutError {
+            method: Some(method),
+            ..self
+        }
+    }
 }
 
 impl fmt::Debug for PyBorrowMutError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("PyBorrowMutError").finish()
+        f.debug_struct("PyBorrowMutError")
+            .field("class", &self.class)
+            .field("method", &self.method)
+            .finish()
     }
 }
 
 impl fmt::Display for PyBorrowMutError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        fmt::Display::fmt("Already borrowed", f)
+        match self.method {
+            Some(method) => write!(
+                f,
+                "cannot call '{0}.{1}': the '{0}' object is already borrowed, probably by a \
+                 method of the object which called '{0}.{1}', which takes `&mut self`, e.g. \
+                 through a callback (a re-entrant call)",
+                self.class, method
+            ),
+            None => write!(f, "the '{}' object is already borrowed", self.class),
+        }
+    }
+}
+
+impl std::error::Error for PyBorrowMutError {}
+
+impl From<PyBorrowError> for PyErr {
+    fn from(err: PyBorrowError) -> PyErr {
+        PyErr::new::<PyBorrowError, _>(err.to_string())
+    }
+}
+
+impl From<PyBorrowMutError> for PyErr {
+    fn from(err: PyBorrowMutError) -> PyErr {
+        PyErr::new::<PyBorrowMutError, _>(err.to_string())
     }
 }
 
-pyo3_exception!(PyBorrowError, crate::exceptions::RuntimeError);
-pyo3_exception!(PyBorrowMutError, crate::exceptions::RuntimeError);
+crate::create_exception_type_object!(pyo3_runtime, PyBorrowError, crate::exceptions::RuntimeError);
+crate::create_exception_type_object!(
+    pyo3_runtime,
+    PyBorrowMutError,
+    crate::exceptions::RuntimeError
+);
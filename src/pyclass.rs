@@ -1,5 +1,5 @@
 //! `PyClass` and related traits.
-use crate::class::methods::{PyClassAttributeDef, PyMethodDefType, PyMethods};
+use crate::class::methods::{PyClassAttributeDef, PyMethodDefType, PyMethods, PyVectorcallFunc};
 use crate::class::proto_methods::PyProtoMethods;
 use crate::conversion::{AsPyPointer, FromPyPointer};
 use crate::derive_utils::PyBaseTypeUtils;
@@ -65,7 +65,14 @@ fn tp_dealloc<T: PyClassAlloc>() -> Option<ffi::destructor> {
     {
         let pool = crate::GILPool::new();
         let py = pool.python();
-        <T as PyClassAlloc>::dealloc(py, (obj as *mut T::Layout) as _)
+        // Unwinding into Python would abort the process; the object is leaked instead.
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            <T as PyClassAlloc>::dealloc(py, (obj as *mut T::Layout) as _)
+        }));
+        if let Err(payload) = result {
+            crate::panic::PanicException::from_panic_payload(py, payload)
+                .write_unraisable(py, None);
+        }
     }
     Some(dealloc::<T>)
 }
@@ -107,15 +114,60 @@ pub trait PyClass:
     type BaseNativeType: PyTypeInfo + PyNativeType;
 }
 
+/// The type object of a `#[pyclass]`, followed by the vectorcall function of its instances.
+#[repr(C)]
+pub(crate) struct PyClassTypeObject {
+    pub(crate) type_object: ffi::PyTypeObject,
+    /// The function stored at `tp_vectorcall_offset` in each instance, if the class supports
+    /// the vectorcall protocol.
+    pub(crate) vectorcall: Option<PyVectorcallFunc>,
+}
+
+impl PyClassTypeObject {
+    pub(crate) const fn new() -> Self {
+        PyClassTypeObject {
+            type_object: ffi::PyTypeObject_INIT,
+            vectorcall: None,
+        }
+    }
+}
+
+/// Stores the vectorcall function of `T` in `obj`, a new instance of `subtype`, if `subtype`
+/// uses the vectorcall protocol.
+#[cfg(all(Py_3_8, not(Py_LIMITED_API), not(PyPy)))]
+pub(crate) unsafe fn init_vectorcall<T: PyClass>(
+    py: Python,
+    subtype: *mut ffi::PyTypeObject,
+    obj: *mut ffi::PyObject,
+) {
+    if ffi::PyType_HasFeature(subtype, ffi::_Py_TPFLAGS_HAVE_VECTORCALL) != 0 {
+        // Only `T` itself, and its subclasses defined in Python, can inherit the vectorcall
+        // support of `T`.
+        let class_type = T::type_object_raw(py) as *mut PyClassTypeObject;
+        let slot = (obj as *mut u8).offset((*subtype).tp_vectorcall_offset);
+        *(slot as *mut Option<PyVectorcallFunc>) = (*class_type).vectorcall;
+    }
+}
+
+#[cfg(not(all(Py_3_8, not(Py_LIMITED_API), not(PyPy))))]
+#[inline]
+pub(crate) unsafe fn init_vectorcall<T: PyClass>(
+    _py: Python,
+    _subtype: *mut ffi::PyTypeObject,
+    _obj: *mut ffi::PyObject,
+) {
+}
+
 #[cfg(not(Py_LIMITED_API))]
 pub(crate) fn initialize_type_object<T>(
     py: Python,
     module_name: Option<&str>,
-    type_object: &mut ffi::PyTypeObject,
+    class_type: &mut PyClassTypeObject,
 ) -> PyResult<()>
 where
     T: PyClass,
 {
+    let type_object = &mut class_type.type_object;
     type_object.tp_doc = match T::DESCRIPTION {
         // PyPy will segfault if passed only a nul terminator as `tp_doc`, ptr::null() is OK though.
         "\0" => ptr::null(),
@@ -137,18 +189,14 @@ where
     // type size
     type_object.tp_basicsize = std::mem::size_of::<T::Layout>() as ffi::Py_ssize_t;
 
-    let mut offset = type_object.tp_basicsize;
-
     // __dict__ support
-    if let Some(dict_offset) = T::Dict::OFFSET {
-        offset += dict_offset as ffi::Py_ssize_t;
-        type_object.tp_dictoffset = offset;
+    if let Some(dict_offset) = PyCell::<T>::dict_offset() {
+        type_object.tp_dictoffset = dict_offset;
     }
 
     // weakref support
-    if let Some(weakref_offset) = T::WeakRef::OFFSET {
-        offset += weakref_offset as ffi::Py_ssize_t;
-        type_object.tp_weaklistoffset = offset;
+    if let Some(weakref_offset) = PyCell::<T>::weakref_offset() {
+        type_object.tp_weaklistoffset = weakref_offset;
     }
 
     // GC support
@@ -186,11 +234,11 @@ where
     // sequence methods
     type_object.tp_as_sequence = T::sequence_methods().map_or_else(ptr::null_mut, |p| p.as_ptr());
     // async methods
-    type_object.tp_as_async = T::async_methods().map_or_else(ptr::null_mut, |p| p.as_ptr());
+    type_object.tp_as_async = py_class_async_methods::<T>();
     // buffer protocol
     type_object.tp_as_buffer = T::buffer_methods().map_or_else(ptr::null_mut, |p| p.as_ptr());
 
-    let (new, call, mut methods) = py_class_method_defs::<T>();
+    let (new, call, vectorcall, mut methods) = py_class_method_defs::<T>();
 
     // normal methods
     if !methods.is_empty() {
@@ -203,6 +251,18 @@ where
     // __call__ method
     type_object.tp_call = call;
 
+    // vectorcall support of __call__: the function is stored in an extra slot of each instance
+    #[cfg(all(Py_3_8, not(PyPy)))]
+    {
+        if vectorcall.is_some() {
+            type_object.tp_vectorcall_offset = type_object.tp_basicsize;
+            type_object.tp_basicsize += std::mem::size_of::<PyVectorcallFunc>() as ffi::Py_ssize_t;
+            class_type.vectorcall = vectorcall;
+        }
+    }
+    #[cfg(not(all(Py_3_8, not(PyPy))))]
+    let _ = vectorcall;
+
     // properties
     let mut props = py_class_properties::<T>();
 
@@ -216,15 +276,31 @@ where
 
     // set type flags
     py_class_flags::<T>(type_object);
+    #[cfg(all(Py_3_8, not(PyPy)))]
+    {
+        if class_type.vectorcall.is_some() {
+            type_object.tp_flags |= ffi::_Py_TPFLAGS_HAVE_VECTORCALL;
+        }
+    }
 
     // register type object
     unsafe {
-        if ffi::PyType_Ready(type_object) == 0 {
-            Ok(())
-        } else {
-            PyErr::fetch(py).into()
+        if ffi::PyType_Ready(type_object) != 0 {
+            return PyErr::fetch(py).into();
         }
     }
+
+    // `PyType_Ready` makes subclasses which do not define `__call__` inherit the vectorcall
+    // support of their base, but the slot of the base is not part of their layout: they use the
+    // `tp_call` inherited from the base instead.
+    #[cfg(all(Py_3_8, not(PyPy)))]
+    {
+        if class_type.vectorcall.is_none() {
+            type_object.tp_flags &= !ffi::_Py_TPFLAGS_HAVE_VECTORCALL;
+            type_object.tp_vectorcall_offset = 0;
+        }
+    }
+    Ok(())
 }
 
 fn py_class_flags<T: PyTypeInfo>(type_object: &mut ffi::PyTypeObject) {
@@ -251,10 +327,12 @@ pub(crate) fn py_class_attributes<T: PyMethods>() -> impl Iterator<Item = PyClas
 fn py_class_method_defs<T: PyMethods>() -> (
     Option<ffi::newfunc>,
     Option<ffi::PyCFunctionWithKeywords>,
+    Option<PyVectorcallFunc>,
     Vec<ffi::PyMethodDef>,
 ) {
     let mut defs = Vec::new();
     let mut call = None;
+    let mut vectorcall = None;
     let mut new = None;
 
     for def in T::py_methods() {
@@ -271,6 +349,7 @@ fn py_class_method_defs<T: PyMethods>() -> (
                     panic!("Method type is not supoorted by tp_call slot")
                 }
             }
+            PyMethodDefType::Vectorcall(ref def) => vectorcall = Some(def.meth),
             PyMethodDefType::Method(ref def)
             | PyMethodDefType::Class(ref def)
             | PyMethodDefType::Static(ref def) => {
@@ -280,7 +359,34 @@ fn py_class_method_defs<T: PyMethods>() -> (
         }
     }
 
-    (new, call, defs)
+    (new, call, vectorcall, defs)
+}
+
+/// Returns the async methods of `#[pyproto]`, with the `__aiter__` and `__anext__` of
+/// `#[pymethods]`.
+fn py_class_async_methods<T: PyClass>() -> *mut ffi::PyAsyncMethods {
+    let mut aiter = None;
+    let mut anext = None;
+    for def in T::py_methods() {
+        match *def {
+            PyMethodDefType::AIter(ref def) => aiter = Some(def.meth),
+            PyMethodDefType::ANext(ref def) => anext = Some(def.meth),
+            _ => (),
+        }
+    }
+
+    let proto = T::async_methods();
+    if aiter.is_none() && anext.is_none() {
+        return proto.map_or_else(ptr::null_mut, |p| p.as_ptr());
+    }
+    let mut methods = proto.map_or(ffi::PyAsyncMethods_INIT, |p| unsafe { *p.as_ptr() });
+    if aiter.is_some() {
+        methods.am_aiter = aiter;
+    }
+    if anext.is_some() {
+        methods.am_anext = anext;
+    }
+    Box::into_raw(Box::new(methods))
 }
 
 fn py_class_properties<T: PyMethods>() -> Vec<ffi::PyGetSetDef> {
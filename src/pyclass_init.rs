@@ -90,7 +90,9 @@ impl<T: PyClass> PyClassInitializer<T> {
     /// impl BaseClass {
     ///     fn new(value: i32) -> PyResult<Self> {
     ///         Ok(Self {
-    ///             value: std::convert::TryFrom::try_from(value)?,
+    ///             value: std::convert::TryFrom::try_from(value).map_err(|_| {
+    ///                 pyo3::exceptions::ValueError::py_err("value must be positive")
+    ///             })?,
     ///         })
     ///     }
     /// }
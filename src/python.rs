@@ -4,14 +4,132 @@
 
 use crate::err::{PyDowncastError, PyErr, PyResult};
 use crate::gil::{self, GILGuard, GILPool};
+use crate::once_cell::{GILOnceCell, ImportCell};
 use crate::type_object::{PyTypeInfo, PyTypeObject};
 use crate::types::{PyAny, PyDict, PyModule, PyType};
-use crate::{ffi, AsPyPointer, FromPyPointer, IntoPyPointer, PyNativeType, PyObject, PyTryFrom};
+#[cfg(not(Py_LIMITED_API))]
+use crate::types::{PyCode, PyFrame};
+use crate::{
+    exceptions, ffi, AsPyPointer, FromPyPointer, IntoPyPointer, PyNativeType, PyObject, PyTryFrom,
+};
+use parking_lot::Mutex;
+use std::collections::HashMap;
 use std::ffi::CString;
+#[cfg(feature = "macros")]
+use std::io::Write;
 use std::marker::PhantomData;
 use std::os::raw::c_int;
 
-pub use gil::prepare_freethreaded_python;
+pub use gil::{prepare_freethreaded_python, prepare_freethreaded_python_with_venv};
+
+/// The kind of source code accepted by [Python::compile], corresponding to the `mode` argument
+/// of the Python `compile` builtin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompileMode {
+    /// A module: a sequence of statements (`"exec"`).
+    Exec,
+    /// A single expression, whose value is returned when the code is run (`"eval"`).
+    Eval,
+    /// A single interactive statement, whose non-`None` value is printed (`"single"`).
+    Single,
+}
+
+impl CompileMode {
+    #[cfg(not(Py_LIMITED_API))]
+    fn start(self) -> c_int {
+        match self {
+            CompileMode::Exec => ffi::Py_file_input,
+            CompileMode::Eval => ffi::Py_eval_input,
+            CompileMode::Single => ffi::Py_single_input,
+        }
+    }
+}
+
+/// A set of compiler flags for [Python::eval_with_flags] and [Python::run_with_flags],
+/// corresponding to the `flags` argument of the Python `compile` builtin.
+///
+/// Flags are combined with `|`, e.g. `CompileFlags::TOP_LEVEL_AWAIT |
+/// CompileFlags::FUTURE_ANNOTATIONS`.
+#[cfg(not(Py_LIMITED_API))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CompileFlags(c_int);
+
+#[cfg(not(Py_LIMITED_API))]
+impl CompileFlags {
+    /// Allows `await`, `async for` and `async with` outside of coroutines. Code using them
+    /// returns a coroutine, which must be driven to completion by the caller, instead of its
+    /// result.
+    #[cfg(Py_3_8)]
+    pub const TOP_LEVEL_AWAIT: CompileFlags = CompileFlags(ffi::PyCF_ALLOW_TOP_LEVEL_AWAIT);
+    /// Checks the placement of type comments, such as `x = []  # type: List[int]`, raising a
+    /// `SyntaxError` for misplaced ones.
+    #[cfg(Py_3_8)]
+    pub const TYPE_COMMENTS: CompileFlags = CompileFlags(ffi::PyCF_TYPE_COMMENTS);
+    /// As `from __future__ import generator_stop`.
+    pub const FUTURE_GENERATOR_STOP: CompileFlags = CompileFlags(ffi::CO_FUTURE_GENERATOR_STOP);
+    /// As `from __future__ import annotations`: annotations are stored as strings, instead of
+    /// being evaluated.
+    #[cfg(Py_3_7)]
+    pub const FUTURE_ANNOTATIONS: CompileFlags = CompileFlags(ffi::CO_FUTURE_ANNOTATIONS);
+    /// As `from __future__ import barry_as_FLUFL`.
+    pub const FUTURE_BARRY_AS_BDFL: CompileFlags = CompileFlags(ffi::CO_FUTURE_BARRY_AS_BDFL);
+
+    /// Returns the set without any flag.
+    pub const fn empty() -> CompileFlags {
+        CompileFlags(0)
+    }
+
+    /// Returns the set containing the flags of `bits`, the value of the Python `PyCF_*` and
+    /// `CO_FUTURE_*` constants.
+    ///
+    /// Returns a `ValueError` if `bits` contains a flag which is not supported by the running
+    /// Python version.
+    pub fn from_bits(bits: i32) -> PyResult<CompileFlags> {
+        let unknown = bits & !CompileFlags::all().0;
+        if unknown != 0 {
+            return Err(exceptions::ValueError::py_err(format!(
+                "unsupported compiler flags: {:#x}",
+                unknown
+            )));
+        }
+        Ok(CompileFlags(bits))
+    }
+
+    /// Returns the flags as an integer.
+    pub const fn bits(self) -> i32 {
+        self.0
+    }
+
+    /// Returns whether all the flags of `other` are set.
+    pub const fn contains(self, other: CompileFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    fn all() -> CompileFlags {
+        let all = CompileFlags::FUTURE_GENERATOR_STOP | CompileFlags::FUTURE_BARRY_AS_BDFL;
+        #[cfg(Py_3_7)]
+        let all = all | CompileFlags::FUTURE_ANNOTATIONS;
+        #[cfg(Py_3_8)]
+        let all = all | CompileFlags::TOP_LEVEL_AWAIT | CompileFlags::TYPE_COMMENTS;
+        all
+    }
+}
+
+#[cfg(not(Py_LIMITED_API))]
+impl std::ops::BitOr for CompileFlags {
+    type Output = CompileFlags;
+
+    fn bitor(self, other: CompileFlags) -> CompileFlags {
+        CompileFlags(self.0 | other.0)
+    }
+}
+
+#[cfg(not(Py_LIMITED_API))]
+impl std::ops::BitOrAssign for CompileFlags {
+    fn bitor_assign(&mut self, other: CompileFlags) {
+        self.0 |= other.0;
+    }
+}
 
 /// Marker type that indicates that the GIL is currently held.
 ///
@@ -71,6 +189,75 @@ impl<'p> Python<'p> {
         GILGuard::acquire()
     }
 
+    /// Acquires the global interpreter lock, and calls `f` with it.
+    ///
+    /// This is a scoped alternative to [Python::acquire_gil]: the GIL, and the references
+    /// owned by `f`, are released when `f` returns. If the Python runtime is not already
+    /// initialized, this function will initialize it.
+    ///
+    /// Calls can be nested, e.g. inside a `GILGuard` or a `#[pyfunction]`: as `f` cannot
+    /// outlive the references which already exist, they are kept in the existing pool and
+    /// released with it. Inside [Python::allow_threads], the GIL is acquired again, and the
+    /// references owned by `f` are released when it returns.
+    ///
+    /// # Example
+    /// ```
+    /// use pyo3::prelude::*;
+    ///
+    /// let sum: i32 = Python::with_gil(|py| py.eval("1 + 2", None, None)?.extract())?;
+    /// assert_eq!(3, sum);
+    /// # Ok::<(), PyErr>(())
+    /// ```
+    #[inline]
+    pub fn with_gil<F, R>(f: F) -> R
+    where
+        F: for<'py> FnOnce(Python<'py>) -> R,
+    {
+        let gil = Python::acquire_gil();
+        f(gil.python())
+    }
+
+    /// Runs `f` in a thread which holds the GIL without PyO3 knowing it, such as a callback
+    /// called directly from C code.
+    ///
+    /// PyO3 then knows that the GIL is held until `f` returns, so the APIs which need to check
+    /// it, such as `PyErr::new` or cloning a `Py`, don't acquire it again with
+    /// `PyGILState_Ensure`. The owned references created by `f` are released when it returns.
+    /// If PyO3 already knows that the GIL is held, `f` is simply called.
+    ///
+    /// This is cheaper than [Python::acquire_gil], which always calls `PyGILState_Ensure`.
+    ///
+    /// # Example
+    /// ```
+    /// use pyo3::{ffi, prelude::*};
+    ///
+    /// // Registered with a C library, which calls it with the GIL held.
+    /// extern "C" fn on_event(listener: *mut ffi::PyObject) {
+    ///     unsafe {
+    ///         Python::with_embedded_gil_state(|py| {
+    ///             let listener: &PyAny = py.from_borrowed_ptr(listener);
+    ///             if let Err(err) = listener.call_method0("notify") {
+    ///                 err.write_unraisable(py, Some(listener));
+    ///             }
+    ///         })
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Safety
+    /// The current thread must hold the GIL until `f` returns.
+    pub unsafe fn with_embedded_gil_state<F, R>(f: F) -> R
+    where
+        F: for<'py> FnOnce(Python<'py>) -> R,
+    {
+        if gil::gil_is_acquired() {
+            f(Python::assume_gil_acquired())
+        } else {
+            let pool = GILPool::new();
+            f(pool.python())
+        }
+    }
+
     /// Temporarily releases the `GIL`, thus allowing other Python threads to run.
     ///
     /// # Example
@@ -169,7 +356,44 @@ impl<'p> Python<'p> {
         globals: Option<&PyDict>,
         locals: Option<&PyDict>,
     ) -> PyResult<&'p PyAny> {
-        self.run_code(code, ffi::Py_eval_input, globals, locals)
+        self.run_string(code, ffi::Py_eval_input, globals, locals, 0)
+    }
+
+    /// Evaluates a Python expression in the given context, like [Python::eval], with additional
+    /// compiler flags.
+    ///
+    /// With [CompileFlags::TOP_LEVEL_AWAIT], an expression using `await` returns a coroutine,
+    /// which must be driven to completion by the caller, e.g. with `asyncio.run`.
+    ///
+    /// # Example:
+    /// ```
+    /// # use pyo3::{prelude::*, types::IntoPyDict, CompileFlags};
+    /// let gil = Python::acquire_gil();
+    /// let py = gil.python();
+    /// # #[cfg(Py_3_8)] {
+    /// let asyncio = py.import("asyncio").unwrap();
+    /// let globals = [("asyncio", asyncio)].into_py_dict(py);
+    /// let coro = py
+    ///     .eval_with_flags(
+    ///         "await asyncio.sleep(0, 'done')",
+    ///         Some(globals),
+    ///         None,
+    ///         CompileFlags::TOP_LEVEL_AWAIT,
+    ///     )
+    ///     .unwrap();
+    /// let result: &str = asyncio.call1("run", (coro,)).unwrap().extract().unwrap();
+    /// assert_eq!(result, "done");
+    /// # }
+    /// ```
+    #[cfg(not(Py_LIMITED_API))]
+    pub fn eval_with_flags(
+        self,
+        code: &str,
+        globals: Option<&PyDict>,
+        locals: Option<&PyDict>,
+        flags: CompileFlags,
+    ) -> PyResult<&'p PyAny> {
+        self.run_string(code, ffi::Py_eval_input, globals, locals, flags.bits())
     }
 
     /// Executes one or more Python statements in the given context.
@@ -202,12 +426,278 @@ impl<'p> Python<'p> {
         globals: Option<&PyDict>,
         locals: Option<&PyDict>,
     ) -> PyResult<()> {
-        let res = self.run_code(code, ffi::Py_file_input, globals, locals);
+        let res = self.run_string(code, ffi::Py_file_input, globals, locals, 0);
         res.map(|obj| {
             debug_assert!(obj.is_none());
         })
     }
 
+    /// Executes one or more Python statements in the given context, like [Python::run], with
+    /// additional compiler flags.
+    ///
+    /// Returns `None`, unless [CompileFlags::TOP_LEVEL_AWAIT] is set and the statements use
+    /// `await`: they then return a coroutine, which runs them when driven to completion by the
+    /// caller.
+    #[cfg(not(Py_LIMITED_API))]
+    pub fn run_with_flags(
+        self,
+        code: &str,
+        globals: Option<&PyDict>,
+        locals: Option<&PyDict>,
+        flags: CompileFlags,
+    ) -> PyResult<Option<&'p PyAny>> {
+        let res = self.run_string(code, ffi::Py_file_input, globals, locals, flags.bits())?;
+        Ok(if res.is_none() { None } else { Some(res) })
+    }
+
+    /// Executes one or more Python statements in the given context, like the interactive
+    /// interpreter, and returns the value of the last statement if it is an expression.
+    ///
+    /// Unlike the interactive interpreter, the value is not printed, nor passed to
+    /// `sys.displayhook`. The value of an expression is returned even if it is `None`; `None` is
+    /// only returned when the source does not end with an expression.
+    ///
+    /// Incomplete source, such as the first line of a function definition, raises an
+    /// [IncompleteInputError](exceptions::IncompleteInputError) instead of a `SyntaxError`, so
+    /// the caller can read more input and try again.
+    ///
+    /// If `globals` is `None`, it defaults to Python module `__main__`.
+    /// If `locals` is `None`, it defaults to the value of `globals`.
+    ///
+    /// # Example:
+    /// ```
+    /// # use pyo3::{exceptions::IncompleteInputError, prelude::*, types::PyDict};
+    /// let gil = Python::acquire_gil();
+    /// let py = gil.python();
+    /// let globals = PyDict::new(py);
+    /// let mut source = String::new();
+    /// let mut value = None;
+    /// for line in &["x = 20", "def f(y):", "    return x + y", "", "f(22)"] {
+    ///     source.push_str(line);
+    ///     source.push('\n');
+    ///     match py.run_interactive(&source, Some(globals), None) {
+    ///         Ok(result) => {
+    ///             value = result;
+    ///             source.clear();
+    ///         }
+    ///         Err(e) if e.is_instance::<IncompleteInputError>(py) => continue,
+    ///         Err(e) => panic!("{:?}", e),
+    ///     }
+    /// }
+    /// assert_eq!(42, value.unwrap().extract::<i32>(py).unwrap());
+    /// ```
+    #[cfg(not(Py_LIMITED_API))]
+    pub fn run_interactive(
+        self,
+        source: &str,
+        globals: Option<&PyDict>,
+        locals: Option<&PyDict>,
+    ) -> PyResult<Option<PyObject>> {
+        const FILENAME: &str = "<input>";
+
+        if self
+            .import("codeop")?
+            .call1("compile_command", (source, FILENAME, "exec"))?
+            .is_none()
+        {
+            return Err(exceptions::IncompleteInputError::py_err((
+                "incomplete input",
+                (FILENAME, source.lines().count(), 0, source.to_string()),
+            )));
+        }
+
+        let ast = self.import("ast")?;
+        let builtins = self.import("builtins")?;
+        let module = ast.call1("parse", (source, FILENAME, "exec"))?;
+        let body = module.getattr("body")?;
+        let last = match body.len()? {
+            0 => None,
+            len => Some(body.get_item(len - 1)?),
+        };
+        let expression = match last {
+            Some(last) if ast.get("Expr")?.downcast::<PyType>()?.is_instance(last)? => {
+                body.call_method0("pop")?;
+                Some(ast.call1("Expression", (last.getattr("value")?,))?)
+            }
+            _ => None,
+        };
+
+        let statements = builtins.call1("compile", (module, FILENAME, "exec"))?;
+        self.run_code(statements.downcast()?, globals, locals)?;
+        match expression {
+            Some(expression) => {
+                let expression = builtins.call1("compile", (expression, FILENAME, "eval"))?;
+                let value = self.run_code(expression.downcast()?, globals, locals)?;
+                Ok(Some(value.into()))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Executes one or more Python statements in the given context, converting a `SIGINT`
+    /// received in the meantime into a `KeyboardInterrupt` error.
+    ///
+    /// PyO3 initializes the interpreter without Python's signal handlers, so by default a
+    /// Ctrl-C while embedded Python code is running terminates the process. This method
+    /// temporarily installs `signal.default_int_handler` for `SIGINT` and, once the code has
+    /// finished, calls [Python::check_signals] so that a signal which arrived late is not
+    /// left pending. The previous handler is restored before returning, if possible.
+    ///
+    /// Python only runs signal handlers on the thread which initialized the interpreter. When
+    /// called from any other thread, this behaves like [Python::run] followed by
+    /// [Python::check_signals].
+    ///
+    /// # Example:
+    /// ```
+    /// # use pyo3::prelude::*;
+    /// # #[cfg(unix)]
+    /// # {
+    /// let gil = Python::acquire_gil();
+    /// let py = gil.python();
+    /// let err = py
+    ///     .run_interruptible(
+    ///         "import os, signal; os.kill(os.getpid(), signal.SIGINT)",
+    ///         None,
+    ///         None,
+    ///     )
+    ///     .unwrap_err();
+    /// assert!(err.is_instance::<pyo3::exceptions::KeyboardInterrupt>(py));
+    /// # }
+    /// ```
+    pub fn run_interruptible(
+        self,
+        code: &str,
+        globals: Option<&PyDict>,
+        locals: Option<&PyDict>,
+    ) -> PyResult<()> {
+        let signal = self.import("signal")?;
+        let sigint = signal.get("SIGINT")?;
+        let previous = match signal.call1("signal", (sigint, signal.get("default_int_handler")?)) {
+            Ok(previous) => Some(previous),
+            // `signal.signal` may only be called from the main thread.
+            Err(e) if e.is_instance::<exceptions::ValueError>(self) => None,
+            Err(e) => return Err(e),
+        };
+
+        let result = self
+            .run(code, globals, locals)
+            .and_then(|()| self.check_signals());
+
+        // Restoring the previous handler fails if it was not installed from Python, in which case
+        // `signal.getsignal` returned `None`. The result of the code takes precedence, so the
+        // error is only reported.
+        if let Some(previous) = previous {
+            if let Err(e) = signal.call1("signal", (sigint, previous)) {
+                e.write_unraisable(self, Some(signal));
+            }
+        }
+        result
+    }
+
+    /// Compiles Python source code into a code object, which can then be executed any number of
+    /// times with [Python::run_code].
+    ///
+    /// `filename` is used in tracebacks and in the `SyntaxError` raised if the source is invalid,
+    /// which also carries the line number and offset of the error.
+    ///
+    /// # Example:
+    /// ```
+    /// # use pyo3::{prelude::*, types::PyDict, CompileMode};
+    /// let gil = Python::acquire_gil();
+    /// let py = gil.python();
+    /// let code = py.compile("x * 2", "<double>", CompileMode::Eval).unwrap();
+    /// for x in 1..4 {
+    ///     let globals = PyDict::new(py);
+    ///     globals.set_item("x", x).unwrap();
+    ///     let result: i32 = py.run_code(code, Some(globals), None).unwrap().extract().unwrap();
+    ///     assert_eq!(result, x * 2);
+    /// }
+    /// ```
+    #[cfg(not(Py_LIMITED_API))]
+    pub fn compile(self, source: &str, filename: &str, mode: CompileMode) -> PyResult<&'p PyCode> {
+        self.compile_with_flags(source, filename, mode, 0)
+    }
+
+    /// Compiles Python source code into a code object, like [Python::compile], with additional
+    /// compiler flags.
+    ///
+    /// `flags` is a combination of the `CO_FUTURE_*` flags (e.g. `ffi::CO_FUTURE_ANNOTATIONS`)
+    /// and the `PyCF_*` flags (e.g. `ffi::PyCF_ALLOW_TOP_LEVEL_AWAIT`), as accepted by the
+    /// `flags` argument of the Python `compile` builtin, such as the value of
+    /// [CompileFlags::bits].
+    #[cfg(not(Py_LIMITED_API))]
+    pub fn compile_with_flags(
+        self,
+        source: &str,
+        filename: &str,
+        mode: CompileMode,
+        flags: i32,
+    ) -> PyResult<&'p PyCode> {
+        let source = CString::new(source)?;
+        let filename = CString::new(filename)?;
+        let mut flags = self.compiler_flags(flags as c_int)?;
+        unsafe {
+            #[cfg(not(PyPy))]
+            let code = ffi::Py_CompileStringExFlags(
+                source.as_ptr(),
+                filename.as_ptr(),
+                mode.start(),
+                &mut flags,
+                -1,
+            );
+            #[cfg(PyPy)]
+            let code = ffi::Py_CompileStringFlags(
+                source.as_ptr(),
+                filename.as_ptr(),
+                mode.start(),
+                &mut flags,
+            );
+            let code = self.from_owned_ptr_or_err::<PyAny>(code)?;
+            Ok(code.downcast()?)
+        }
+    }
+
+    /// Executes a code object, as returned by [Python::compile], in the given context.
+    ///
+    /// Returns the value of the expression for code compiled with [CompileMode::Eval], and
+    /// `None` otherwise. Code compiled with `PyCF_ALLOW_TOP_LEVEL_AWAIT` which uses `await`
+    /// returns a coroutine instead, which must be driven to completion by the caller.
+    ///
+    /// If `globals` is `None`, it defaults to Python module `__main__`.
+    /// If `locals` is `None`, it defaults to the value of `globals`.
+    #[cfg(not(Py_LIMITED_API))]
+    pub fn run_code(
+        self,
+        code: &PyCode,
+        globals: Option<&PyDict>,
+        locals: Option<&PyDict>,
+    ) -> PyResult<&'p PyAny> {
+        unsafe {
+            let (globals, locals) = self.resolve_namespaces(globals, locals)?;
+            let res_ptr = ffi::PyEval_EvalCode(code.as_ptr(), globals, locals);
+            self.from_owned_ptr_or_err(res_ptr)
+        }
+    }
+
+    /// Returns the `PyCompilerFlags` to compile code with `flags`.
+    #[cfg(not(Py_LIMITED_API))]
+    fn compiler_flags(self, flags: c_int) -> PyResult<ffi::PyCompilerFlags> {
+        Ok(ffi::PyCompilerFlags {
+            cf_flags: flags,
+            // Only used for `PyCF_ONLY_AST`; Python defaults it to the running minor version.
+            #[cfg(Py_3_8)]
+            cf_feature_version: {
+                static MINOR_VERSION: GILOnceCell<c_int> = GILOnceCell::new();
+                *MINOR_VERSION.get_or_try_init(self, || -> PyResult<c_int> {
+                    self.import("sys")?
+                        .getattr("version_info")?
+                        .getattr("minor")?
+                        .extract()
+                })?
+            },
+        })
+    }
+
     /// Runs code in the given context.
     ///
     /// `start` indicates the type of input expected: one of `Py_single_input`,
@@ -215,37 +705,205 @@ impl<'p> Python<'p> {
     ///
     /// If `globals` is `None`, it defaults to Python module `__main__`.
     /// If `locals` is `None`, it defaults to the value of `globals`.
-    fn run_code(
+    fn run_string(
         self,
         code: &str,
         start: c_int,
         globals: Option<&PyDict>,
         locals: Option<&PyDict>,
+        flags: c_int,
     ) -> PyResult<&'p PyAny> {
         let code = CString::new(code)?;
+        let mut flags = if flags == 0 {
+            None
+        } else {
+            Some(self.compiler_flags(flags)?)
+        };
         unsafe {
-            let mptr = ffi::PyImport_AddModule("__main__\0".as_ptr() as *const _);
-            if mptr.is_null() {
-                return Err(PyErr::fetch(self));
-            }
-
-            let globals = globals
-                .map(AsPyPointer::as_ptr)
-                .unwrap_or_else(|| ffi::PyModule_GetDict(mptr));
-            let locals = locals.map(AsPyPointer::as_ptr).unwrap_or(globals);
-
+            let (globals, locals) = self.resolve_namespaces(globals, locals)?;
             let res_ptr = ffi::PyRun_StringFlags(
                 code.as_ptr(),
                 start,
                 globals,
                 locals,
-                ::std::ptr::null_mut(),
+                flags
+                    .as_mut()
+                    .map_or(::std::ptr::null_mut(), |flags| flags as *mut _),
             );
 
             self.from_owned_ptr_or_err(res_ptr)
         }
     }
 
+    /// Returns borrowed pointers to the globals and locals to run code with, defaulting to the
+    /// namespace of the `__main__` module.
+    unsafe fn resolve_namespaces(
+        self,
+        globals: Option<&PyDict>,
+        locals: Option<&PyDict>,
+    ) -> PyResult<(*mut ffi::PyObject, *mut ffi::PyObject)> {
+        let mptr = ffi::PyImport_AddModule("__main__\0".as_ptr() as *const _);
+        if mptr.is_null() {
+            return Err(PyErr::fetch(self));
+        }
+
+        let globals = globals
+            .map(AsPyPointer::as_ptr)
+            .unwrap_or_else(|| ffi::PyModule_GetDict(mptr));
+        let locals = locals.map(AsPyPointer::as_ptr).unwrap_or(globals);
+        Ok((globals, locals))
+    }
+
+    /// Lets the Python interpreter run any pending signal handlers.
+    ///
+    /// If a handler raises an exception (e.g. the default `SIGINT` handler raising
+    /// `KeyboardInterrupt`), it is returned as an error. Signal handlers only run on the thread
+    /// which initialized the interpreter; on other threads this always returns `Ok(())`.
+    ///
+    /// The interpreter checks for signals regularly while Python code runs, but not while Rust
+    /// code runs inside [Python::allow_threads], so long-running Rust code should call this
+    /// periodically, with the GIL acquired again, and stop when it returns an error:
+    ///
+    /// ```
+    /// use pyo3::prelude::*;
+    ///
+    /// fn sum_of_squares(py: Python, n: u64) -> PyResult<u64> {
+    ///     py.allow_threads(|| {
+    ///         let mut sum = 0u64;
+    ///         for i in 0..n {
+    ///             if i % 1_000_000 == 0 {
+    ///                 // Acquiring the GIL is costly, so only check once in a while.
+    ///                 Python::with_gil(|py| py.check_signals())?;
+    ///             }
+    ///             sum = sum.wrapping_add(i * i);
+    ///         }
+    ///         Ok(sum)
+    ///     })
+    /// }
+    /// # let gil = Python::acquire_gil();
+    /// # assert_eq!(14, sum_of_squares(gil.python(), 4).unwrap());
+    /// ```
+    ///
+    /// [signals::with_interrupt_check](crate::signals::with_interrupt_check) does this without
+    /// acquiring the GIL in the loop, by running the work in another thread.
+    pub fn check_signals(self) -> PyResult<()> {
+        if unsafe { ffi::PyErr_CheckSignals() } == -1 {
+            Err(PyErr::fetch(self))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Redirects `sys.stdout` to `writer`, until the returned guard is dropped.
+    ///
+    /// While the guard is alive, `sys.stdout` is a text stream writing the text it is given to
+    /// `writer`, encoded as UTF-8; it only accepts `str`, and `isatty()` returns `False`. Code
+    /// which saved a reference to the previous `sys.stdout`, or writes to the underlying file
+    /// descriptor directly, is not affected. When the guard is dropped, `writer` is flushed and
+    /// the previous `sys.stdout` is restored.
+    ///
+    /// # Example
+    /// ```
+    /// use pyo3::prelude::*;
+    /// use std::io::{self, Write};
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// #[derive(Clone, Default)]
+    /// struct Log(Arc<Mutex<Vec<u8>>>);
+    ///
+    /// impl Write for Log {
+    ///     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    ///         self.0.lock().unwrap().write(buf)
+    ///     }
+    ///     fn flush(&mut self) -> io::Result<()> {
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let gil = Python::acquire_gil();
+    /// let py = gil.python();
+    /// let log = Log::default();
+    /// {
+    ///     let _guard = py.redirect_stdout(log.clone())?;
+    ///     py.run("print('hello')", None, None)?;
+    /// }
+    /// assert_eq!(b"hello\n", &log.0.lock().unwrap()[..]);
+    /// # Ok::<(), PyErr>(())
+    /// ```
+    #[cfg(feature = "macros")]
+    pub fn redirect_stdout(
+        self,
+        writer: impl Write + Send + 'static,
+    ) -> PyResult<crate::file::RedirectGuard> {
+        crate::file::RedirectGuard::new(self, "stdout", writer)
+    }
+
+    /// Redirects `sys.stderr` to `writer`, until the returned guard is dropped.
+    ///
+    /// See [Python::redirect_stdout].
+    #[cfg(feature = "macros")]
+    pub fn redirect_stderr(
+        self,
+        writer: impl Write + Send + 'static,
+    ) -> PyResult<crate::file::RedirectGuard> {
+        crate::file::RedirectGuard::new(self, "stderr", writer)
+    }
+
+    /// Returns the frame of the innermost Python function currently executing on this thread,
+    /// or `None` if no Python code is running (e.g. when Python is embedded and Rust code was
+    /// not called from Python).
+    #[cfg(not(Py_LIMITED_API))]
+    pub fn current_frame(self) -> Option<&'p PyFrame> {
+        unsafe {
+            #[cfg(Py_3_9)]
+            let frame = ffi::frameobject::PyThreadState_GetFrame(ffi::PyThreadState_Get());
+            #[cfg(not(Py_3_9))]
+            let frame = {
+                let frame = ffi::PyEval_GetFrame();
+                ffi::Py_XINCREF(frame as *mut ffi::PyObject);
+                frame
+            };
+            self.from_owned_ptr_or_opt(frame as *mut ffi::PyObject)
+        }
+    }
+
+    /// Returns the location of the `depth` innermost Python frames currently executing on this
+    /// thread, starting with the innermost one, as `(filename, line number, function name)`.
+    ///
+    /// When called from a Rust function exposed to Python, the first entry is the location of
+    /// the call to that function.
+    ///
+    /// # Example:
+    /// ```
+    /// # use pyo3::prelude::*;
+    /// # use pyo3::wrap_pyfunction;
+    /// #[pyfunction]
+    /// fn log(py: Python, message: &str) -> PyResult<()> {
+    ///     if let Some((filename, line, function)) = py.caller_location(1)?.pop() {
+    ///         println!("{}:{} in {}: {}", filename, line, function, message);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(not(Py_LIMITED_API))]
+    pub fn caller_location(self, depth: usize) -> PyResult<Vec<(String, usize, String)>> {
+        let mut locations = Vec::new();
+        let mut frame = self.current_frame();
+        while let Some(f) = frame {
+            if locations.len() == depth {
+                break;
+            }
+            let code = f.code();
+            locations.push((
+                code.filename()?.to_string(),
+                f.line_number(),
+                code.name()?.to_string(),
+            ));
+            frame = f.back();
+        }
+        Ok(locations)
+    }
+
     /// Gets the Python type object for type `T`.
     pub fn get_type<T>(self) -> &'p PyType
     where
@@ -259,6 +917,39 @@ impl<'p> Python<'p> {
         PyModule::import(self, name)
     }
 
+    /// Imports the Python module with the specified name, caching it for the next calls.
+    ///
+    /// Unlike [Python::import], which looks the module up in `sys.modules` on every call, the
+    /// module is only imported the first time, and then looked up in a registry keyed by `name`.
+    /// [import_cached!](macro.import_cached.html) avoids even this lookup, as it caches the module
+    /// in a static at each call site.
+    ///
+    /// A module which fails to import is not cached, so that the import is tried again by the
+    /// next call. See [ImportCell](once_cell/struct.ImportCell.html) for details.
+    ///
+    /// # Example
+    /// ```
+    /// # use pyo3::prelude::*;
+    /// # let gil = Python::acquire_gil();
+    /// # let py = gil.python();
+    /// let json = py.import_cached("json")?;
+    /// let dumped: String = json.call1("dumps", (vec![1, 2],))?.extract()?;
+    /// assert_eq!(dumped, "[1, 2]");
+    /// # Ok::<(), PyErr>(())
+    /// ```
+    pub fn import_cached(self, name: &'static str) -> PyResult<&'p PyModule> {
+        static CELLS: GILOnceCell<Mutex<HashMap<&'static str, &'static ImportCell>>> =
+            GILOnceCell::new();
+
+        // The cells are leaked, as there is one per module name.
+        let cell: &'static ImportCell = CELLS
+            .get_or_init(self, Default::default)
+            .lock()
+            .entry(name)
+            .or_insert_with(|| Box::leak(Box::new(ImportCell::default())));
+        cell.import(self, name)
+    }
+
     /// Checks whether `obj` is an instance of type `T`.
     ///
     /// This is equivalent to the Python `isinstance` function.
@@ -274,7 +965,7 @@ impl<'p> Python<'p> {
         T: PyTypeObject,
         U: PyTypeObject,
     {
-        T::type_object(self).is_subclass::<U>()
+        T::type_object(self).is_subclass_of::<U>()
     }
 
     /// Gets the Python builtin value `None`.
@@ -350,7 +1041,7 @@ impl<'p> Python<'p> {
 
 impl<'p> Python<'p> {
     /// Registers the object in the release pool, and tries to downcast to specific type.
-    pub fn checked_cast_as<T>(self, obj: PyObject) -> Result<&'p T, PyDowncastError>
+    pub fn checked_cast_as<T>(self, obj: PyObject) -> Result<&'p T, PyDowncastError<'p>>
     where
         T: PyTryFrom<'p>,
     {
@@ -465,8 +1156,9 @@ impl<'p> Python<'p> {
 
 #[cfg(test)]
 mod test {
-    use crate::types::{IntoPyDict, PyAny, PyBool, PyInt, PyList};
-    use crate::Python;
+    use crate::exceptions::{self, KeyboardInterrupt, SyntaxError};
+    use crate::types::{IntoPyDict, PyAny, PyBool, PyDict, PyInt, PyList};
+    use crate::{ffi, AsPyRef, CompileFlags, CompileMode, Python, ToPyObject};
 
     #[test]
     fn test_eval() {
@@ -509,6 +1201,290 @@ mod test {
         assert_eq!(v, 2);
     }
 
+    #[test]
+    fn test_import_cached() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let sys = py.import("sys").unwrap();
+        let modules = sys.getattr("modules").unwrap();
+
+        // Failures are not cached.
+        let err = py.import_cached("pyo3_test_import_cached").unwrap_err();
+        assert!(err.is_instance::<exceptions::ImportError>(py));
+        let module = crate::types::PyModule::new(py, "pyo3_test_import_cached").unwrap();
+        modules.set_item("pyo3_test_import_cached", module).unwrap();
+        assert!(py
+            .import_cached("pyo3_test_import_cached")
+            .unwrap()
+            .is(module));
+
+        // Once imported, the module is not looked up in `sys.modules` any more.
+        modules.del_item("pyo3_test_import_cached").unwrap();
+        assert!(py
+            .import_cached("pyo3_test_import_cached")
+            .unwrap()
+            .is(module));
+
+        let json = || crate::import_cached!(py, "json").unwrap();
+        assert!(json().is(json()));
+        assert!(json().is(py.import("json").unwrap()));
+    }
+
+    #[test]
+    fn test_compile_and_run_code() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let code = py
+            .compile("y = x * 2", "double.py", CompileMode::Exec)
+            .unwrap();
+
+        for x in &[1, 21] {
+            let globals = [("x", *x)].into_py_dict(py);
+            assert!(py.run_code(code, Some(globals), None).unwrap().is_none());
+            let y: i32 = globals.get_item("y").unwrap().extract().unwrap();
+            assert_eq!(y, x * 2);
+        }
+
+        let code = py.compile("x + 1", "<eval>", CompileMode::Eval).unwrap();
+        let globals = [("x", 41)].into_py_dict(py);
+        let v: i32 = py
+            .run_code(code, Some(globals), None)
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(v, 42);
+        assert!(py.compile("x = 1", "<eval>", CompileMode::Eval).is_err());
+    }
+
+    #[test]
+    fn test_compile_syntax_error() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let err = py
+            .compile("x = 1\nx +* 2\n", "broken.py", CompileMode::Exec)
+            .map(|_| ())
+            .unwrap_err();
+        assert!(err.is_instance::<SyntaxError>(py));
+        let err = err.to_object(py);
+        let filename: String = err.getattr(py, "filename").unwrap().extract(py).unwrap();
+        let lineno: i32 = err.getattr(py, "lineno").unwrap().extract(py).unwrap();
+        let offset: i32 = err.getattr(py, "offset").unwrap().extract(py).unwrap();
+        assert_eq!(filename, "broken.py");
+        assert_eq!(lineno, 2);
+        assert_eq!(offset, 4);
+    }
+
+    #[cfg(Py_3_8)]
+    #[test]
+    fn test_eval_with_flags_top_level_await() {
+        use crate::types::{GeneratorResult, PyCoroutine};
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let globals = [("asyncio", py.import("asyncio").unwrap())].into_py_dict(py);
+        assert!(py
+            .eval("await asyncio.sleep(0)", Some(globals), None)
+            .is_err());
+
+        let coro: &PyCoroutine = py
+            .eval_with_flags(
+                "await asyncio.sleep(0, 'slept')",
+                Some(globals),
+                None,
+                CompileFlags::TOP_LEVEL_AWAIT,
+            )
+            .unwrap()
+            .downcast()
+            .unwrap();
+        let none = py.None();
+        let result = loop {
+            match coro.send(none.as_ref(py)).unwrap() {
+                GeneratorResult::Yielded(_) => continue,
+                GeneratorResult::Returned(result) => break result,
+            }
+        };
+        assert_eq!("slept", result.extract::<&str>(py).unwrap());
+
+        let coro = py
+            .run_with_flags(
+                "await asyncio.sleep(0)\nresult = 42\n",
+                Some(globals),
+                None,
+                CompileFlags::TOP_LEVEL_AWAIT,
+            )
+            .unwrap()
+            .unwrap();
+        assert!(globals.get_item("result").is_none());
+        py.import("asyncio").unwrap().call1("run", (coro,)).unwrap();
+        assert_eq!(42, globals.get_item("result").unwrap().extract().unwrap());
+
+        // Code which does not await is run directly.
+        assert!(py
+            .run_with_flags("x = 1", Some(globals), None, CompileFlags::TOP_LEVEL_AWAIT)
+            .unwrap()
+            .is_none());
+    }
+
+    #[cfg(Py_3_7)]
+    #[test]
+    fn test_run_with_flags_future_annotations() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let source = "def f(x: undefined_name) -> int: pass\n";
+        let globals = PyDict::new(py);
+        assert!(py.run(source, Some(globals), None).is_err());
+        py.run_with_flags(
+            source,
+            Some(globals),
+            None,
+            CompileFlags::FUTURE_ANNOTATIONS | CompileFlags::FUTURE_GENERATOR_STOP,
+        )
+        .unwrap();
+        let annotation: String = py
+            .eval("f.__annotations__['x']", Some(globals), None)
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!("undefined_name", annotation);
+    }
+
+    #[test]
+    fn test_run_interactive() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let globals = PyDict::new(py);
+
+        let source = "def add(a, b):\n    total = a + b\n    return total\n";
+        let err = py
+            .run_interactive(&source[..15], Some(globals), None)
+            .unwrap_err();
+        assert!(err.is_instance::<exceptions::IncompleteInputError>(py));
+        assert!(err.is_instance::<SyntaxError>(py));
+        let err = py
+            .run_interactive("def add(a, b) pass", Some(globals), None)
+            .unwrap_err();
+        assert!(err.is_instance::<SyntaxError>(py));
+        assert!(!err.is_instance::<exceptions::IncompleteInputError>(py));
+
+        let result = py
+            .run_interactive(&format!("{}add(40, 2)\n", source), Some(globals), None)
+            .unwrap();
+        assert_eq!(42, result.unwrap().extract::<i32>(py).unwrap());
+        assert!(globals.get_item("add").is_some());
+
+        // Statements return nothing, and expressions are not printed.
+        let sys = py.import("sys").unwrap();
+        let displayhook = sys.get("displayhook").unwrap();
+        assert!(py
+            .run_interactive("x = add(1, 2)", Some(globals), None)
+            .unwrap()
+            .is_none());
+        let result = py
+            .run_interactive("y = x * 2; None", Some(globals), None)
+            .unwrap();
+        assert!(result.unwrap().is_none(py));
+        assert_eq!(6, globals.get_item("y").unwrap().extract::<i32>().unwrap());
+        assert!(py
+            .run_interactive("", Some(globals), None)
+            .unwrap()
+            .is_none());
+        assert!(sys.get("displayhook").unwrap().is(displayhook));
+    }
+
+    #[test]
+    fn test_compile_flags_from_bits() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let flags = CompileFlags::from_bits(ffi::CO_FUTURE_GENERATOR_STOP).unwrap();
+        assert_eq!(CompileFlags::FUTURE_GENERATOR_STOP, flags);
+        assert!(flags.contains(CompileFlags::FUTURE_GENERATOR_STOP));
+        assert!(!CompileFlags::empty().contains(flags));
+
+        // `PyCF_ONLY_AST` would make the code return an AST instead of running it.
+        let err = CompileFlags::from_bits(ffi::CO_FUTURE_GENERATOR_STOP | ffi::PyCF_ONLY_AST)
+            .unwrap_err();
+        assert!(err.is_instance::<exceptions::ValueError>(py));
+        assert_eq!(
+            "unsupported compiler flags: 0x400",
+            err.to_object(py).as_ref(py).to_string()
+        );
+    }
+
+    #[cfg(Py_3_8)]
+    #[test]
+    fn test_compile_top_level_await() {
+        use crate::types::{GeneratorResult, PyCoroutine};
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let source = "import asyncio\nawait asyncio.sleep(0)\nresult = 42\n";
+        assert!(py.compile(source, "await.py", CompileMode::Exec).is_err());
+
+        let code = py
+            .compile_with_flags(
+                source,
+                "await.py",
+                CompileMode::Exec,
+                ffi::PyCF_ALLOW_TOP_LEVEL_AWAIT,
+            )
+            .unwrap();
+        let globals = PyDict::new(py);
+        let coro: &PyCoroutine = py
+            .run_code(code, Some(globals), None)
+            .unwrap()
+            .downcast()
+            .unwrap();
+        let none = py.None();
+        loop {
+            match coro.send(none.as_ref(py)).unwrap() {
+                GeneratorResult::Yielded(_) => continue,
+                GeneratorResult::Returned(_) => break,
+            }
+        }
+        let result: i32 = globals.get_item("result").unwrap().extract().unwrap();
+        assert_eq!(result, 42);
+    }
+
+    #[cfg(Py_3_7)]
+    #[test]
+    fn test_compile_future_annotations() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let source = "def f(x: undefined_name): pass\nannotation = f.__annotations__['x']\n";
+        assert!(py.run(source, None, None).is_err());
+
+        let code = py
+            .compile_with_flags(
+                source,
+                "annotations.py",
+                CompileMode::Exec,
+                ffi::CO_FUTURE_ANNOTATIONS,
+            )
+            .unwrap();
+        let globals = PyDict::new(py);
+        py.run_code(code, Some(globals), None).unwrap();
+        let annotation: String = globals.get_item("annotation").unwrap().extract().unwrap();
+        assert_eq!(annotation, "undefined_name");
+    }
+
+    #[test]
+    fn test_run_interruptible() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        assert!(py.check_signals().is_ok());
+
+        let locals = PyDict::new(py);
+        py.run_interruptible("x = 1 + 1", None, Some(locals))
+            .unwrap();
+        let x: i32 = locals.get_item("x").unwrap().extract().unwrap();
+        assert_eq!(x, 2);
+
+        let err = py
+            .run_interruptible("raise KeyboardInterrupt", None, None)
+            .unwrap_err();
+        assert!(err.is_instance::<KeyboardInterrupt>(py));
+    }
+
     #[test]
     fn test_is_instance() {
         let gil = Python::acquire_gil();
@@ -0,0 +1,133 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Handling of signals, such as Ctrl-C, while Rust code runs without the GIL.
+//!
+//! Python's C-level signal handler only records that a signal arrived; the Python handler, such
+//! as `signal.default_int_handler` raising `KeyboardInterrupt` for `SIGINT`, runs later, when the
+//! interpreter checks for signals with [Python::check_signals]. This happens regularly while
+//! Python code runs, but not while Rust code runs inside [Python::allow_threads], so a Ctrl-C is
+//! ignored until the Rust code returns. Installing another handler, e.g. with the `ctrlc` crate,
+//! would replace the one of Python.
+//!
+//! [with_interrupt_check] runs a closure without the GIL while checking for signals
+//! periodically, and asks the closure to stop when a handler raised an exception.
+//!
+//! Python only runs signal handlers in the main thread, i.e. the thread which initialized the
+//! interpreter, so the signals are only checked when [with_interrupt_check] is called from that
+//! thread. Extension modules are usually called from the main thread; embedded interpreters only
+//! have Python's `SIGINT` handler if it is installed with `signal.signal`, e.g. by
+//! [Python::run_interruptible].
+use crate::{PyErr, PyResult, Python};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// The flag set by [with_interrupt_check] when a signal handler raised an exception.
+#[derive(Debug, Default)]
+pub struct Interrupt {
+    interrupted: AtomicBool,
+}
+
+impl Interrupt {
+    /// Returns whether a signal handler raised an exception, in which case the closure should
+    /// return as soon as possible.
+    pub fn is_interrupted(&self) -> bool {
+        self.interrupted.load(Ordering::SeqCst)
+    }
+}
+
+/// Runs `work` without the GIL, while checking for signals every `interval`.
+///
+/// `work` runs in a new thread, while the current thread releases the GIL and acquires it again
+/// every `interval` to run the pending signal handlers. When a handler raises an exception, such
+/// as the `KeyboardInterrupt` raised for a Ctrl-C, the [Interrupt] passed to `work` is set.
+/// `work` is expected to poll it and return early; its result is then discarded, and the exception
+/// is returned once it has returned. The signals are also checked after `work` returns, so that
+/// a signal which arrived at the end is not left pending.
+///
+/// The signals are only checked in the main thread of the interpreter, see the
+/// [module documentation](index.html). In other threads, this only runs `work` without the GIL.
+/// A panic in `work` is resumed in the current thread.
+///
+/// # Example
+/// ```
+/// use pyo3::prelude::*;
+/// use pyo3::signals;
+/// use std::time::Duration;
+///
+/// let gil = Python::acquire_gil();
+/// let py = gil.python();
+/// let sum = signals::with_interrupt_check(py, Duration::from_millis(100), |interrupt| {
+///     let mut sum = 0u64;
+///     for i in 0..1_000_000u64 {
+///         if i % 1000 == 0 && interrupt.is_interrupted() {
+///             break;
+///         }
+///         sum += i;
+///     }
+///     sum
+/// })?;
+/// assert_eq!(499_999_500_000, sum);
+/// # Ok::<(), PyErr>(())
+/// ```
+pub fn with_interrupt_check<F, T>(py: Python, interval: Duration, work: F) -> PyResult<T>
+where
+    F: FnOnce(&Interrupt) -> T + Send,
+    T: Send,
+{
+    let interrupt = Interrupt::default();
+    let (sender, receiver) = mpsc::channel();
+    let job: Box<dyn FnOnce() + Send + '_> = {
+        let interrupt = &interrupt;
+        Box::new(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| work(interrupt)));
+            let _ = sender.send(result);
+        })
+    };
+    // Safety: the thread is joined before returning or unwinding, so it cannot outlive the
+    // borrows of the job.
+    let job: Box<dyn FnOnce() + Send + 'static> = unsafe { std::mem::transmute(job) };
+    let worker = JoinOnDrop(Some(thread::spawn(job)));
+
+    let interrupt = &interrupt;
+    let (result, error) = py.allow_threads(move || {
+        let mut error: Option<PyErr> = None;
+        loop {
+            match receiver.recv_timeout(interval) {
+                Ok(result) => return (result, error),
+                Err(RecvTimeoutError::Timeout) if error.is_none() => {
+                    let gil = Python::acquire_gil();
+                    if let Err(e) = gil.python().check_signals() {
+                        error = Some(e);
+                        interrupt.interrupted.store(true, Ordering::SeqCst);
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => (),
+                Err(RecvTimeoutError::Disconnected) => {
+                    unreachable!("the worker sends its result before exiting")
+                }
+            }
+        }
+    });
+    drop(worker);
+
+    let value = result.unwrap_or_else(|payload| panic::resume_unwind(payload));
+    match error {
+        Some(error) => Err(error),
+        None => py.check_signals().map(|()| value),
+    }
+}
+
+/// Joins the worker thread of [with_interrupt_check] when dropped, even while unwinding.
+struct JoinOnDrop(Option<JoinHandle<()>>);
+
+impl Drop for JoinOnDrop {
+    fn drop(&mut self) {
+        if let Some(handle) = self.0.take() {
+            // The job catches the panics of the closure.
+            let _ = handle.join();
+        }
+    }
+}
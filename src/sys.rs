@@ -0,0 +1,232 @@
+//! Typed access to the configuration of the interpreter in the Python `sys` module.
+//!
+//! Paths are converted between Rust and Python with the filesystem encoding of Python, like
+//! `os.fsdecode` and `os.fsencode`, so that paths which are not valid UTF-8 are preserved on
+//! Unix.
+//!
+//! # Example
+//! ```
+//! use pyo3::{prelude::*, sys};
+//! use std::path::Path;
+//!
+//! # fn main() -> PyResult<()> {
+//! let gil = Python::acquire_gil();
+//! let py = gil.python();
+//! sys::prepend_path(py, Path::new("/opt/plugins"))?;
+//! let first: String = sys::path(py)?.get_item(0).extract()?;
+//! assert_eq!(first, "/opt/plugins");
+//! # sys::path(py)?.call_method1("remove", (first,))?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::exceptions::RuntimeError;
+use crate::types::{PyAny, PyBytes, PyList, PyString};
+use crate::{ffi, AsPyPointer, FromPyPointer, PyNativeType, PyResult, PyTryFrom, Python};
+use std::ffi::CStr;
+use std::path::{Path, PathBuf};
+
+/// The command line flags of the interpreter, from `sys.flags`.
+///
+/// Flags which do not exist in the running version of Python are `false`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SysFlags {
+    /// `-d`: the parser debugging output is enabled.
+    pub debug: bool,
+    /// `-i`: an interactive prompt is started after the script.
+    pub inspect: bool,
+    /// `-i`: the interpreter is interactive.
+    pub interactive: bool,
+    /// `-O` or `-OO`: the optimization level.
+    pub optimize: u32,
+    /// `-B`: no `.pyc` files are written on import.
+    pub dont_write_bytecode: bool,
+    /// `-s`: the user site-packages directory is not added to `sys.path`.
+    pub no_user_site: bool,
+    /// `-S`: the `site` module is not imported on startup.
+    pub no_site: bool,
+    /// `-E`: the `PYTHON*` environment variables are ignored.
+    pub ignore_environment: bool,
+    /// `-v`: the verbosity level.
+    pub verbose: u32,
+    /// `-b` or `-bb`: the level of warnings about comparisons of `bytes` with `str`.
+    pub bytes_warning: u32,
+    /// `-q`: the version is not printed in interactive mode.
+    pub quiet: bool,
+    /// Whether the hashes of `str` and `bytes` are randomized, see `PYTHONHASHSEED`.
+    pub hash_randomization: bool,
+    /// `-I`: the interpreter is isolated from the environment and the user site-packages.
+    pub isolated: bool,
+    /// `-X dev`: the development mode is enabled. Python 3.7 and later.
+    pub dev_mode: bool,
+    /// `-X utf8`: the UTF-8 mode is enabled. Python 3.7 and later.
+    pub utf8_mode: bool,
+}
+
+/// Returns the command line flags of the interpreter.
+pub fn flags(py: Python) -> PyResult<SysFlags> {
+    let flags = get(py, "flags")?;
+    // The flags are integers, except `dev_mode`, which is a `bool`.
+    let level = |name: &str| -> PyResult<u32> {
+        match flags.getattr_opt(name)? {
+            Some(value) => value.extract(),
+            None => Ok(0),
+        }
+    };
+    let flag = |name: &str| level(name).map(|value| value != 0);
+    Ok(SysFlags {
+        debug: flag("debug")?,
+        inspect: flag("inspect")?,
+        interactive: flag("interactive")?,
+        optimize: level("optimize")?,
+        dont_write_bytecode: flag("dont_write_bytecode")?,
+        no_user_site: flag("no_user_site")?,
+        no_site: flag("no_site")?,
+        ignore_environment: flag("ignore_environment")?,
+        verbose: level("verbose")?,
+        bytes_warning: level("bytes_warning")?,
+        quiet: flag("quiet")?,
+        hash_randomization: flag("hash_randomization")?,
+        isolated: flag("isolated")?,
+        dev_mode: flag("dev_mode")?,
+        utf8_mode: flag("utf8_mode")?,
+    })
+}
+
+/// Returns `sys.path`, the list of directories where modules are searched.
+///
+/// Returns a `TypeError` if `sys.path` was replaced by an object which is not a list.
+pub fn path(py: Python) -> PyResult<&PyList> {
+    Ok(<PyList as PyTryFrom>::try_from(get(py, "path")?)?)
+}
+
+/// Inserts `directory` at the start of `sys.path`, so that its modules are imported before
+/// those of any other directory.
+pub fn prepend_path(py: Python, directory: &Path) -> PyResult<()> {
+    path(py)?.insert(0, path_to_py(py, directory)?)
+}
+
+/// Adds `directory` at the end of `sys.path`.
+pub fn append_path(py: Python, directory: &Path) -> PyResult<()> {
+    path(py)?.append(path_to_py(py, directory)?)
+}
+
+/// Returns the maximum depth of the Python stack, from `sys.getrecursionlimit()`.
+pub fn recursion_limit(py: Python) -> PyResult<u32> {
+    py.import("sys")?.call0("getrecursionlimit")?.extract()
+}
+
+/// Sets the maximum depth of the Python stack, like `sys.setrecursionlimit(limit)`.
+///
+/// Returns a `ValueError` if `limit` is 0, and a `RecursionError` if it is lower than the
+/// current depth.
+pub fn set_recursion_limit(py: Python, limit: u32) -> PyResult<()> {
+    py.import("sys")?
+        .call1("setrecursionlimit", (limit,))
+        .map(drop)
+}
+
+/// Returns the path of the executable of the Python interpreter, from `sys.executable`.
+///
+/// Returns `None` if Python could not determine it, e.g. in some embedded interpreters.
+pub fn executable(py: Python) -> PyResult<Option<PathBuf>> {
+    let executable = get(py, "executable")?;
+    if executable.is_none() {
+        return Ok(None);
+    }
+    let executable = py_to_path(executable)?;
+    Ok(if executable.as_os_str().is_empty() {
+        None
+    } else {
+        Some(executable)
+    })
+}
+
+/// Returns the attribute `name` of `sys`, without importing it.
+fn get<'p>(py: Python<'p>, name: &str) -> PyResult<&'p PyAny> {
+    let c_name = format!("{}\0", name);
+    let c_name = CStr::from_bytes_with_nul(c_name.as_bytes()).unwrap();
+    // `PySys_GetObject` returns a borrowed reference, and does not set an exception.
+    unsafe { py.from_borrowed_ptr_or_opt(ffi::PySys_GetObject(c_name.as_ptr())) }
+        .ok_or_else(|| RuntimeError::py_err(format!("lost sys.{}", name)))
+}
+
+/// Converts a path to a Python `str` like `os.fsdecode`.
+fn path_to_py<'p>(py: Python<'p>, path: &Path) -> PyResult<&'p PyString> {
+    #[cfg(unix)]
+    unsafe {
+        use std::os::unix::ffi::OsStrExt;
+        let bytes = path.as_os_str().as_bytes();
+        let string = ffi::PyUnicode_DecodeFSDefaultAndSize(
+            bytes.as_ptr() as *const _,
+            bytes.len() as ffi::Py_ssize_t,
+        );
+        py.from_owned_ptr_or_err(string)
+    }
+    #[cfg(windows)]
+    unsafe {
+        use std::os::windows::ffi::OsStrExt;
+        let wide: Vec<u16> = path.as_os_str().encode_wide().collect();
+        let string = ffi::PyUnicode_FromWideChar(wide.as_ptr(), wide.len() as ffi::Py_ssize_t);
+        py.from_owned_ptr_or_err(string)
+    }
+}
+
+/// Converts a Python `str` to a path like `os.fsencode`.
+fn py_to_path(path: &PyAny) -> PyResult<PathBuf> {
+    let path = <PyString as PyTryFrom>::try_from(path)?;
+    #[cfg(unix)]
+    {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+        let bytes: &PyBytes = unsafe {
+            FromPyPointer::from_owned_ptr_or_err(
+                path.py(),
+                ffi::PyUnicode_EncodeFSDefault(path.as_ptr()),
+            )?
+        };
+        Ok(OsStr::from_bytes(bytes.as_bytes()).into())
+    }
+    #[cfg(windows)]
+    {
+        Ok(path.to_str()?.into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{flags, py_to_path};
+    use crate::Python;
+
+    #[test]
+    fn test_flags() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let flags = flags(py).unwrap();
+        // PyO3 initializes the interpreter with the default flags.
+        assert!(!flags.isolated);
+        assert_eq!(flags.optimize, 0);
+        let expected: bool = py
+            .eval("bool(__import__('sys').flags.no_site)", None, None)
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(flags.no_site, expected);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_non_utf8_path() {
+        use super::path_to_py;
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+        use std::path::Path;
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let path = Path::new(OsStr::from_bytes(b"/tmp/caf\xe9"));
+        let py_path = path_to_py(py, path).unwrap();
+        // Decoded with `surrogateescape` like `os.fsdecode`.
+        assert!(py_path.to_str().is_err());
+        assert_eq!(py_to_path(py_path).unwrap(), path);
+    }
+}
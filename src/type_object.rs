@@ -3,11 +3,11 @@
 
 use crate::conversion::IntoPyPointer;
 use crate::once_cell::GILOnceCell;
-use crate::pyclass::{initialize_type_object, py_class_attributes, PyClass};
+use crate::pyclass::{initialize_type_object, py_class_attributes, PyClass, PyClassTypeObject};
 use crate::pyclass_init::PyObjectInit;
 use crate::types::{PyAny, PyType};
 use crate::{ffi, AsPyPointer, PyErr, PyNativeType, PyObject, PyResult, Python};
-use parking_lot::{const_mutex, Mutex};
+use parking_lot::{const_mutex, Condvar, Mutex};
 use std::thread::{self, ThreadId};
 
 /// `T: PyLayout<U>` represents that `T` is a concrete representaion of `U` in Python heap.
@@ -140,6 +140,10 @@ where
 pub struct LazyStaticType {
     // Boxed because Python expects the type object to have a stable address.
     value: GILOnceCell<*mut ffi::PyTypeObject>,
+    // The thread creating the type object, if any. Creation may release the GIL, so other
+    // threads must wait for it to finish, instead of creating another type object.
+    creating_thread: Mutex<Option<ThreadId>>,
+    created: Condvar,
     // Threads which have begun initialization of the `tp_dict`. Used for
     // reentrant initialization detection.
     initializing_threads: Mutex<Vec<ThreadId>>,
@@ -150,20 +154,18 @@ impl LazyStaticType {
     pub const fn new() -> Self {
         LazyStaticType {
             value: GILOnceCell::new(),
+            creating_thread: const_mutex(None),
+            created: Condvar::new(),
             initializing_threads: const_mutex(Vec::new()),
             tp_dict_filled: GILOnceCell::new(),
         }
     }
 
     pub fn get_or_init<T: PyClass>(&self, py: Python) -> *mut ffi::PyTypeObject {
-        let type_object = *self.value.get_or_init(py, || {
-            let mut type_object = Box::new(ffi::PyTypeObject_INIT);
-            initialize_type_object::<T>(py, T::MODULE, type_object.as_mut()).unwrap_or_else(|e| {
-                e.print(py);
-                panic!("An error occurred while initializing class {}", T::NAME)
-            });
-            Box::into_raw(type_object)
-        });
+        let type_object = match self.value.get(py) {
+            Some(&type_object) => type_object,
+            None => self.create_type_object::<T>(py),
+        };
 
         // We might want to fill the `tp_dict` with python instances of `T`
         // itself. In order to do so, we must first initialize the type object
@@ -222,6 +224,63 @@ impl LazyStaticType {
 
         type_object
     }
+
+    /// Creates the type object, or waits for the thread creating it, so that exactly one type
+    /// object is created.
+    fn create_type_object<T: PyClass>(&self, py: Python) -> *mut ffi::PyTypeObject {
+        let thread_id = thread::current().id();
+        loop {
+            {
+                let mut creating_thread = self.creating_thread.lock();
+                if let Some(&type_object) = self.value.get(py) {
+                    return type_object;
+                }
+                match *creating_thread {
+                    None => {
+                        *creating_thread = Some(thread_id);
+                        break;
+                    }
+                    Some(id) if id == thread_id => {
+                        panic!("Recursive initialization of the type object of {}", T::NAME)
+                    }
+                    Some(_) => (),
+                }
+            }
+            // The creating thread needs the GIL to finish.
+            py.allow_threads(|| {
+                let mut creating_thread = self.creating_thread.lock();
+                while creating_thread.is_some() {
+                    self.created.wait(&mut creating_thread);
+                }
+            });
+        }
+
+        // Wakes up the waiting threads once the type object is created, or if creating it
+        // panicked, in which case one of them tries again.
+        struct Created<'a>(&'a LazyStaticType);
+
+        impl Drop for Created<'_> {
+            fn drop(&mut self) {
+                *self.0.creating_thread.lock() = None;
+                self.0.created.notify_all();
+            }
+        }
+
+        let _created = Created(self);
+        let mut type_object = Box::new(PyClassTypeObject::new());
+        initialize_type_object::<T>(py, T::MODULE, type_object.as_mut()).unwrap_or_else(|e| {
+            e.print(py);
+            panic!("An error occurred while initializing class {}", T::NAME)
+        });
+        // The type object is the first field of `PyClassTypeObject`.
+        let type_object = Box::into_raw(type_object) as *mut ffi::PyTypeObject;
+        assert!(
+            self.value.set(py, type_object).is_ok(),
+            "type object of {} created twice",
+            T::NAME
+        );
+        type_object
+    }
 }
 
 fn initialize_tp_dict(
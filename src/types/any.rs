@@ -4,9 +4,13 @@ use crate::conversion::{
 };
 use crate::err::{PyDowncastError, PyErr, PyResult};
 use crate::exceptions::TypeError;
-use crate::types::{PyDict, PyIterator, PyList, PyString, PyTuple, PyType};
+use crate::type_object::{PyTypeInfo, PyTypeObject};
+use crate::types::{
+    PyBool, PyBytes, PyDict, PyFloat, PyIterator, PyList, PyLong, PySet, PyString, PyTuple, PyType,
+};
 use crate::{err, ffi, Py, PyNativeType, PyObject, Python};
 use libc::c_int;
+use std::borrow::Cow;
 use std::cell::UnsafeCell;
 use std::cmp::Ordering;
 
@@ -40,6 +44,7 @@ pub struct PyAny(UnsafeCell<ffi::PyObject>);
 impl crate::AsPyPointer for PyAny {
     #[inline]
     fn as_ptr(&self) -> *mut ffi::PyObject {
+        crate::debug::record(crate::debug::RefcountOp::AsPtr, self.0.get());
         self.0.get()
     }
 }
@@ -74,16 +79,99 @@ impl PyAny {
         <T as PyTryFrom>::try_from(self)
     }
 
+    /// Convert this PyAny to a concrete Python type, which must be exactly the type of this
+    /// object rather than one of its base classes.
+    ///
+    /// # Example
+    /// ```
+    /// use pyo3::prelude::*;
+    /// use pyo3::types::{PyBool, PyLong};
+    /// # let gil = Python::acquire_gil();
+    /// # let py = gil.python();
+    /// let any = py.eval("True", None, None).unwrap();
+    /// assert!(any.downcast_exact::<PyBool>().unwrap().is_true());
+    /// // `bool` is a subclass of `int`.
+    /// assert!(any.downcast::<PyLong>().is_ok());
+    /// assert!(any.downcast_exact::<PyLong>().is_err());
+    /// ```
+    pub fn downcast_exact<T>(&self) -> Result<&T, PyDowncastError>
+    where
+        for<'py> T: PyTryFrom<'py>,
+    {
+        <T as PyTryFrom>::try_from_exact(self)
+    }
+
+    /// Classifies this object by its exact type, to match on the common Python types.
+    ///
+    /// The type of the object is read once and compared with each type, which is cheaper than
+    /// trying [downcast_exact](#method.downcast_exact) for each of them. Instances of subclasses
+    /// of these types, such as `collections.OrderedDict`, are [ObjectKind::Other].
+    ///
+    /// # Example
+    /// ```
+    /// use pyo3::prelude::*;
+    /// use pyo3::types::ObjectKind;
+    ///
+    /// fn to_json(value: &PyAny) -> PyResult<String> {
+    ///     Ok(match value.classify() {
+    ///         ObjectKind::None => "null".to_string(),
+    ///         ObjectKind::Bool(value) => value.is_true().to_string(),
+    ///         ObjectKind::Int(value) => value.to_string(),
+    ///         ObjectKind::Str(value) => format!("{:?}", value.to_str()?),
+    ///         ObjectKind::List(items) => {
+    ///             let items: PyResult<Vec<_>> = items.iter().map(to_json).collect();
+    ///             format!("[{}]", items?.join(","))
+    ///         }
+    ///         _ => return Err(pyo3::exceptions::TypeError::py_err("unsupported type")),
+    ///     })
+    /// }
+    /// # let gil = Python::acquire_gil();
+    /// # let py = gil.python();
+    /// let value = py.eval("[None, True, 1, 'a']", None, None)?;
+    /// assert_eq!(to_json(value)?, r#"[null,true,1,"a"]"#);
+    /// # Ok::<(), PyErr>(())
+    /// ```
+    pub fn classify(&self) -> ObjectKind {
+        let py = self.py();
+        let ty = unsafe { ffi::Py_TYPE(self.as_ptr()) };
+        let is = |other: *mut ffi::PyTypeObject| ty == other;
+        unsafe {
+            if self.is_none() {
+                ObjectKind::None
+            } else if is(PyBool::type_object_raw(py)) {
+                ObjectKind::Bool(PyBool::unchecked_downcast(self))
+            } else if is(PyLong::type_object_raw(py)) {
+                ObjectKind::Int(PyLong::unchecked_downcast(self))
+            } else if is(PyFloat::type_object_raw(py)) {
+                ObjectKind::Float(PyFloat::unchecked_downcast(self))
+            } else if is(PyString::type_object_raw(py)) {
+                ObjectKind::Str(PyString::unchecked_downcast(self))
+            } else if is(PyBytes::type_object_raw(py)) {
+                ObjectKind::Bytes(PyBytes::unchecked_downcast(self))
+            } else if is(PyList::type_object_raw(py)) {
+                ObjectKind::List(PyList::unchecked_downcast(self))
+            } else if is(PyTuple::type_object_raw(py)) {
+                ObjectKind::Tuple(PyTuple::unchecked_downcast(self))
+            } else if is(PyDict::type_object_raw(py)) {
+                ObjectKind::Dict(PyDict::unchecked_downcast(self))
+            } else if is(PySet::type_object_raw(py)) {
+                ObjectKind::Set(PySet::unchecked_downcast(self))
+            } else {
+                ObjectKind::Other(self)
+            }
+        }
+    }
+
     /// Determines whether this object has the given attribute.
     ///
-    /// This is equivalent to the Python expression `hasattr(self, attr_name)`.
+    /// This is equivalent to the Python expression `hasattr(self, attr_name)`: only an
+    /// `AttributeError` means that the attribute is missing, and any other exception raised
+    /// while looking it up is returned.
     pub fn hasattr<N>(&self, attr_name: N) -> PyResult<bool>
     where
         N: ToPyObject,
     {
-        attr_name.with_borrowed_ptr(self.py(), |attr_name| unsafe {
-            Ok(ffi::PyObject_HasAttr(self.as_ptr(), attr_name) != 0)
-        })
+        self.getattr_opt(attr_name).map(|attr| attr.is_some())
     }
 
     /// Retrieves an attribute value.
@@ -99,6 +187,88 @@ impl PyAny {
         })
     }
 
+    /// Retrieves an attribute value, or `None` if the attribute does not exist.
+    ///
+    /// This is equivalent to the Python expression `getattr(self, attr_name, None)`, except
+    /// that an attribute whose value is `None` is distinguished from a missing one. Only an
+    /// `AttributeError` is suppressed; any other exception, e.g. raised by a property, is
+    /// returned. Where possible, the `AttributeError` is not even created, which makes this
+    /// cheaper than calling [getattr](PyAny::getattr) and discarding the error.
+    ///
+    /// As with `getattr`, `attr_name` can be a `&str` or a `&PyString`, which avoids creating
+    /// a new string for every lookup when it is [interned](PyString::intern).
+    pub fn getattr_opt<N>(&self, attr_name: N) -> PyResult<Option<&PyAny>>
+    where
+        N: ToPyObject,
+    {
+        attr_name.with_borrowed_ptr(self.py(), |attr_name| unsafe {
+            #[cfg(all(Py_3_7, not(Py_LIMITED_API), not(PyPy)))]
+            {
+                let mut attr = std::ptr::null_mut();
+                match ffi::_PyObject_LookupAttr(self.as_ptr(), attr_name, &mut attr) {
+                    -1 => Err(PyErr::fetch(self.py())),
+                    0 => Ok(None),
+                    _ => Ok(Some(self.py().from_owned_ptr(attr))),
+                }
+            }
+            #[cfg(not(all(Py_3_7, not(Py_LIMITED_API), not(PyPy))))]
+            {
+                match self
+                    .py()
+                    .from_owned_ptr_or_err(ffi::PyObject_GetAttr(self.as_ptr(), attr_name))
+                {
+                    Ok(attr) => Ok(Some(attr)),
+                    Err(err) if err.is_instance::<crate::exceptions::AttributeError>(self.py()) => {
+                        Ok(None)
+                    }
+                    Err(err) => Err(err),
+                }
+            }
+        })
+    }
+
+    /// Looks up a special method such as `__len__` on the type of this object, and binds it to
+    /// this object. Returns `None` if the type does not define it.
+    ///
+    /// This is how Python looks up special methods for operators and builtins: unlike
+    /// [getattr](PyAny::getattr), the instance dictionary and `__getattr__` are skipped, so an
+    /// attribute set on the instance does not override the method of its type.
+    pub fn lookup_special<N>(&self, attr_name: N) -> PyResult<Option<&PyAny>>
+    where
+        N: ToPyObject,
+    {
+        let py = self.py();
+        let attr_name = attr_name.to_object(py);
+        let ty = self.get_type();
+
+        #[cfg(all(not(Py_LIMITED_API), not(PyPy)))]
+        unsafe {
+            // `_PyType_Lookup` returns a borrowed reference, and never sets an exception.
+            let attr = ffi::_PyType_Lookup(ty.as_type_ptr(), attr_name.as_ptr());
+            if attr.is_null() {
+                return Ok(None);
+            }
+            match (*ffi::Py_TYPE(attr)).tp_descr_get {
+                Some(descr_get) => py
+                    .from_owned_ptr_or_err(descr_get(attr, self.as_ptr(), ty.as_ptr()))
+                    .map(Some),
+                None => Ok(Some(py.from_borrowed_ptr(attr))),
+            }
+        }
+
+        #[cfg(any(Py_LIMITED_API, PyPy))]
+        {
+            let attr = match lookup_in_mro(ty, &attr_name)? {
+                Some(attr) => attr,
+                None => return Ok(None),
+            };
+            match lookup_in_mro(attr.get_type(), &"__get__".to_object(py))? {
+                Some(descr_get) => descr_get.call1((attr, self, ty)).map(Some),
+                None => Ok(Some(attr)),
+            }
+        }
+    }
+
     /// Sets an attribute value.
     ///
     /// This is equivalent to the Python expression `self.attr_name = value`.
@@ -193,6 +363,98 @@ impl PyAny {
         }
     }
 
+    /// Compares two Python objects, returning the truth value of the result.
+    ///
+    /// This uses `PyObject_RichCompareBool`, so `self` is considered equal to itself for
+    /// `CompareOp::Eq` and `CompareOp::Ne` without calling `__eq__`, as is done by Python
+    /// containers.
+    fn rich_compare_bool<O>(&self, other: O, compare_op: CompareOp) -> PyResult<bool>
+    where
+        O: ToPyObject,
+    {
+        let result = other.with_borrowed_ptr(self.py(), |other| unsafe {
+            ffi::PyObject_RichCompareBool(self.as_ptr(), other, compare_op as c_int)
+        });
+        match result {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(PyErr::fetch(self.py())),
+        }
+    }
+
+    /// Tests whether two Python objects are equal.
+    ///
+    /// This is equivalent to the Python expression `self == other`, except that an object is
+    /// always equal to itself: for example, a NaN float is equal to itself, but not to another
+    /// NaN. Exceptions raised by `__eq__` are returned.
+    ///
+    /// Note that the `==` operator on `PyAny` compares object identity instead; see also
+    /// [is](PyAny::is).
+    pub fn eq<O>(&self, other: O) -> PyResult<bool>
+    where
+        O: ToPyObject,
+    {
+        self.rich_compare_bool(other, CompareOp::Eq)
+    }
+
+    /// Tests whether two Python objects are not equal.
+    ///
+    /// This is equivalent to the Python expression `self != other`, except that an object is
+    /// never unequal to itself.
+    pub fn ne<O>(&self, other: O) -> PyResult<bool>
+    where
+        O: ToPyObject,
+    {
+        self.rich_compare_bool(other, CompareOp::Ne)
+    }
+
+    /// Tests whether this object is less than another.
+    ///
+    /// This is equivalent to the Python expression `self < other`.
+    pub fn lt<O>(&self, other: O) -> PyResult<bool>
+    where
+        O: ToPyObject,
+    {
+        self.rich_compare_bool(other, CompareOp::Lt)
+    }
+
+    /// Tests whether this object is less than or equal to another.
+    ///
+    /// This is equivalent to the Python expression `self <= other`.
+    pub fn le<O>(&self, other: O) -> PyResult<bool>
+    where
+        O: ToPyObject,
+    {
+        self.rich_compare_bool(other, CompareOp::Le)
+    }
+
+    /// Tests whether this object is greater than another.
+    ///
+    /// This is equivalent to the Python expression `self > other`.
+    pub fn gt<O>(&self, other: O) -> PyResult<bool>
+    where
+        O: ToPyObject,
+    {
+        self.rich_compare_bool(other, CompareOp::Gt)
+    }
+
+    /// Tests whether this object is greater than or equal to another.
+    ///
+    /// This is equivalent to the Python expression `self >= other`.
+    pub fn ge<O>(&self, other: O) -> PyResult<bool>
+    where
+        O: ToPyObject,
+    {
+        self.rich_compare_bool(other, CompareOp::Ge)
+    }
+
+    /// Returns whether `self` and `other` are the same object.
+    ///
+    /// This is equivalent to the Python expression `self is other`.
+    pub fn is<T: AsPyPointer>(&self, other: &T) -> bool {
+        self.as_ptr() == other.as_ptr()
+    }
+
     /// Determines whether this object is callable.
     pub fn is_callable(&self) -> bool {
         unsafe { ffi::PyCallable_Check(self.as_ptr()) != 0 }
@@ -233,6 +495,14 @@ impl PyAny {
         self.call(args, None)
     }
 
+    /// Calls the object with positional and keyword arguments.
+    ///
+    /// This is equivalent to the Python expression `self(*args, **kwargs)`, and is a shorthand
+    /// for [call](PyAny::call) to be used with [kwargs!](crate::kwargs).
+    pub fn call_kwargs(&self, args: impl IntoPy<Py<PyTuple>>, kwargs: &PyDict) -> PyResult<&PyAny> {
+        self.call(args, Some(kwargs))
+    }
+
     /// Calls a method on the object.
     ///
     /// This is equivalent to the Python expression `self.name(*args, **kwargs)`.
@@ -290,6 +560,93 @@ impl PyAny {
         self.call_method(name, args, None)
     }
 
+    /// Calls a method on the object with positional and keyword arguments.
+    ///
+    /// This is equivalent to the Python expression `self.name(*args, **kwargs)`, and is a
+    /// shorthand for [call_method](PyAny::call_method) to be used with
+    /// [kwargs!](crate::kwargs).
+    ///
+    /// # Example
+    /// ```rust
+    /// # use pyo3::prelude::*;
+    /// use pyo3::kwargs;
+    ///
+    /// # fn main() -> PyResult<()> {
+    /// let gil = Python::acquire_gil();
+    /// let py = gil.python();
+    /// let list = vec![3, 6, 5, 4, 7].to_object(py);
+    /// let list = list.as_ref(py);
+    /// list.call_method_kwargs("sort", (), kwargs!(py, reverse = true)?)?;
+    /// assert_eq!(list.extract::<Vec<i32>>()?, vec![7, 6, 5, 4, 3]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn call_method_kwargs(
+        &self,
+        name: &str,
+        args: impl IntoPy<Py<PyTuple>>,
+        kwargs: &PyDict,
+    ) -> PyResult<&PyAny> {
+        self.call_method(name, args, Some(kwargs))
+    }
+
+    /// Calls a special method such as `__enter__`, looked up on the type of the object as with
+    /// [lookup_special](PyAny::lookup_special).
+    ///
+    /// This is how Python calls the special methods of statements, such as `__enter__` and
+    /// `__exit__` for `with`: an attribute of the same name set on the instance, or provided by
+    /// `__getattr__`, is ignored. Returns an `AttributeError` if the type does not define the
+    /// method.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use pyo3::prelude::*;
+    /// # fn main() -> PyResult<()> {
+    /// let gil = Python::acquire_gil();
+    /// let py = gil.python();
+    /// let lock = py.import("threading")?.call0("Lock")?;
+    /// lock.call_special_method0("__enter__")?;
+    /// assert!(lock.call_method0("locked")?.extract::<bool>()?);
+    /// lock.call_special_method1("__exit__", (py.None(), py.None(), py.None()))?;
+    /// assert!(!lock.call_method0("locked")?.extract::<bool>()?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn call_special_method(
+        &self,
+        name: &str,
+        args: impl IntoPy<Py<PyTuple>>,
+        kwargs: Option<&PyDict>,
+    ) -> PyResult<&PyAny> {
+        match self.lookup_special(name)? {
+            Some(method) => method.call(args, kwargs),
+            None => Err(crate::exceptions::AttributeError::py_err(format!(
+                "'{}' object has no special method '{}'",
+                self.get_type().name(),
+                name
+            ))),
+        }
+    }
+
+    /// Calls a special method without arguments, looked up on the type of the object.
+    ///
+    /// See [call_special_method](PyAny::call_special_method).
+    pub fn call_special_method0(&self, name: &str) -> PyResult<&PyAny> {
+        self.call_special_method(name, (), None)
+    }
+
+    /// Calls a special method with only positional arguments, looked up on the type of the
+    /// object.
+    ///
+    /// See [call_special_method](PyAny::call_special_method).
+    pub fn call_special_method1(
+        &self,
+        name: &str,
+        args: impl IntoPy<Py<PyTuple>>,
+    ) -> PyResult<&PyAny> {
+        self.call_special_method(name, args, None)
+    }
+
     /// Returns whether the object is considered to be true.
     ///
     /// This is equivalent to the Python expression `bool(self)`.
@@ -364,6 +721,34 @@ impl PyAny {
         Ok(PyIterator::from_object(self.py(), self)?)
     }
 
+    /// Takes an object and returns an iterator extracting each of its items as `T`.
+    ///
+    /// This is a shortcut for `self.iter()?.extract_each()`; see [PyIterator::extract_each].
+    ///
+    /// # Example
+    /// ```
+    /// use pyo3::prelude::*;
+    ///
+    /// let gil = Python::acquire_gil();
+    /// let py = gil.python();
+    /// let words = py.eval("'the quick brown fox'.split()", None, None)?;
+    /// let mut long = Vec::new();
+    /// for word in words.iter_extract::<&str>()? {
+    ///     let word = word?;
+    ///     if word.len() > 3 {
+    ///         long.push(word);
+    ///     }
+    /// }
+    /// assert_eq!(vec!["quick", "brown"], long);
+    /// # Ok::<(), PyErr>(())
+    /// ```
+    pub fn iter_extract<'a, T>(&'a self) -> PyResult<impl Iterator<Item = PyResult<T>> + 'a>
+    where
+        T: FromPyObject<'a> + 'a,
+    {
+        Ok(self.iter()?.extract_each())
+    }
+
     /// Returns the Python type object for this object's type.
     pub fn get_type(&self) -> &PyType {
         unsafe { PyType::from_type_ptr(self.py(), (*self.as_ptr()).ob_type) }
@@ -375,6 +760,21 @@ impl PyAny {
         unsafe { (*self.as_ptr()).ob_type }
     }
 
+    /// Checks whether this object is an instance of type `T`.
+    ///
+    /// This is equivalent to the Python expression `isinstance(self, T)`, and therefore honors
+    /// `__instancecheck__` overrides such as virtual subclasses registered with an ABC.
+    pub fn is_instance_of<T: PyTypeObject>(&self) -> PyResult<bool> {
+        T::type_object(self.py()).is_instance(self)
+    }
+
+    /// Checks whether the type of this object is `T` or a subclass of it, bypassing any
+    /// `__instancecheck__` override.
+    pub fn is_instance_of_no_hooks<T: PyTypeObject>(&self) -> bool {
+        let ty = T::type_object(self.py());
+        unsafe { ffi::PyObject_TypeCheck(self.as_ptr(), ty.as_type_ptr()) != 0 }
+    }
+
     /// Casts the PyObject to a concrete Python object type.
     ///
     /// This can cast only to native Python types, not types implemented in Rust.
@@ -395,6 +795,65 @@ impl PyAny {
         FromPyObject::extract(self)
     }
 
+    /// Extracts a string from the Python object, which must be a `str`.
+    ///
+    /// Unlike `extract::<&str>()`, this succeeds for strings containing unpaired surrogates
+    /// (e.g. from `os.fsdecode`), replacing them with `U+FFFD REPLACEMENT CHARACTER`.
+    /// See [PyString::to_string_lossy].
+    pub fn extract_lossy(&self) -> PyResult<Cow<str>> {
+        Ok(self.downcast::<PyString>()?.to_string_lossy())
+    }
+
+    /// Checks whether this object is a coroutine created by an `async def` function.
+    ///
+    /// This is equivalent to the Python expression `inspect.iscoroutine(self)`.
+    pub fn is_coroutine(&self) -> bool {
+        unsafe { ffi::PyCoro_Check(self.as_ptr()) != 0 }
+    }
+
+    /// Checks whether this object can be used in an `await` expression, i.e. whether
+    /// [PyAny::get_awaitable_iter] succeeds.
+    ///
+    /// This is true for coroutines, generator-based coroutines created by functions decorated
+    /// with `@types.coroutine`, and objects implementing `__await__`. Unlike the Python expression
+    /// `inspect.isawaitable(self)`, it is false for virtual subclasses of
+    /// `collections.abc.Awaitable` which don't implement `__await__`, as `await` rejects them.
+    pub fn is_awaitable(&self) -> PyResult<bool> {
+        if self.is_coroutine() || unsafe { am_await(self.get_type_ptr()) }.is_some() {
+            return Ok(true);
+        }
+        self.is_iterable_coroutine()
+    }
+
+    /// Checks whether this object is a generator created by a function decorated with
+    /// `@types.coroutine`.
+    fn is_iterable_coroutine(&self) -> PyResult<bool> {
+        if unsafe { ffi::PyGen_Check(self.as_ptr()) } == 0 {
+            return Ok(false);
+        }
+        let flags: c_int = self.getattr("gi_code")?.getattr("co_flags")?.extract()?;
+        Ok(flags & ffi::CO_ITERABLE_COROUTINE != 0)
+    }
+
+    /// Returns the iterator driving an `await` on this object.
+    ///
+    /// Like `await`, this is the object itself for coroutines and generator-based coroutines,
+    /// and the result of `__await__()` otherwise. Returns a `TypeError` if the object is not
+    /// awaitable.
+    pub fn get_awaitable_iter(&self) -> PyResult<&PyAny> {
+        let py = self.py();
+        if unsafe { ffi::PyCoro_CheckExact(self.as_ptr()) } != 0 || self.is_iterable_coroutine()? {
+            return Ok(self);
+        }
+        match unsafe { am_await(self.get_type_ptr()) } {
+            Some(am_await) => unsafe { py.from_owned_ptr_or_err(am_await(self.as_ptr())) },
+            None => Err(TypeError::py_err(format!(
+                "object {} can't be used in 'await' expression",
+                self.get_type().name()
+            ))),
+        }
+    }
+
     /// Returns the reference count for the Python object.
     pub fn get_refcnt(&self) -> isize {
         unsafe { ffi::Py_REFCNT(self.as_ptr()) }
@@ -420,9 +879,39 @@ impl PyAny {
         }
     }
 
+    /// Computes the "repr" representation of self, without ever raising an exception.
+    ///
+    /// If `repr(self)` raises an exception, it is discarded, and the default representation of
+    /// Python objects, `<ClassName object at 0x...>`, is returned instead. This is intended for
+    /// error and log messages, where another exception would hide the original failure.
+    ///
+    /// If `max_chars` is given, a longer representation is truncated to `max_chars` characters,
+    /// followed by `...`.
+    ///
+    /// # Example
+    /// ```
+    /// # use pyo3::prelude::*;
+    /// # let gil = Python::acquire_gil();
+    /// # let py = gil.python();
+    /// let numbers = py.eval("list(range(100))", None, None)?;
+    /// assert_eq!(numbers.repr_safe(Some(10)), "[0, 1, 2, ...");
+    /// # Ok::<(), PyErr>(())
+    /// ```
+    pub fn repr_safe(&self, max_chars: Option<usize>) -> String {
+        format_safe(self, self.repr(), max_chars)
+    }
+
+    /// Computes the "str" representation of self, without ever raising an exception.
+    ///
+    /// This falls back to the default representation and truncates it like
+    /// [repr_safe](#method.repr_safe).
+    pub fn str_safe(&self, max_chars: Option<usize>) -> String {
+        format_safe(self, self.str(), max_chars)
+    }
+
     /// Retrieves the hash code of self.
     ///
-    /// This is equivalent to the Python expression `hash(obi)`.
+    /// This is equivalent to the Python expression `hash(self)`.
     pub fn hash(&self) -> PyResult<isize> {
         let v = unsafe { ffi::PyObject_Hash(self.as_ptr()) };
         if v == -1 {
@@ -444,19 +933,164 @@ impl PyAny {
         }
     }
 
+    /// Formats self according to the format specification `spec`.
+    ///
+    /// This is equivalent to the Python expression `format(self, spec)`.
+    ///
+    /// # Example
+    /// ```
+    /// # use pyo3::prelude::*;
+    /// # let gil = Python::acquire_gil();
+    /// # let py = gil.python();
+    /// let pi = py.eval("3.14159", None, None)?;
+    /// assert_eq!(pi.format(".2f")?.to_str()?, "3.14");
+    /// # Ok::<(), PyErr>(())
+    /// ```
+    pub fn format(&self, spec: &str) -> PyResult<&PyString> {
+        let spec = PyString::new(self.py(), spec);
+        unsafe {
+            self.py()
+                .from_owned_ptr_or_err(ffi::PyObject_Format(self.as_ptr(), spec.as_ptr()))
+        }
+    }
+
+    /// Computes the absolute value of self.
+    ///
+    /// This is equivalent to the Python expression `abs(self)`.
+    pub fn abs(&self) -> PyResult<&PyAny> {
+        unsafe {
+            self.py()
+                .from_owned_ptr_or_err(ffi::PyNumber_Absolute(self.as_ptr()))
+        }
+    }
+
+    /// Computes the positive of self.
+    ///
+    /// This is equivalent to the Python expression `+self`.
+    pub fn pos(&self) -> PyResult<&PyAny> {
+        unsafe {
+            self.py()
+                .from_owned_ptr_or_err(ffi::PyNumber_Positive(self.as_ptr()))
+        }
+    }
+
+    /// Computes the negative of self.
+    ///
+    /// This is equivalent to the Python expression `-self`.
+    pub fn neg(&self) -> PyResult<&PyAny> {
+        unsafe {
+            self.py()
+                .from_owned_ptr_or_err(ffi::PyNumber_Negative(self.as_ptr()))
+        }
+    }
+
+    /// Computes the bitwise inversion of self.
+    ///
+    /// This is equivalent to the Python expression `~self`.
+    pub fn invert(&self) -> PyResult<&PyAny> {
+        unsafe {
+            self.py()
+                .from_owned_ptr_or_err(ffi::PyNumber_Invert(self.as_ptr()))
+        }
+    }
+
+    /// Computes the matrix multiplication of self and `other`.
+    ///
+    /// This is equivalent to the Python expression `self @ other`.
+    pub fn matmul<O>(&self, other: O) -> PyResult<&PyAny>
+    where
+        O: ToPyObject,
+    {
+        unsafe {
+            other.with_borrowed_ptr(self.py(), |other| {
+                self.py()
+                    .from_owned_ptr_or_err(ffi::PyNumber_MatrixMultiply(self.as_ptr(), other))
+            })
+        }
+    }
+
     /// Returns the list of attributes of this object.
     ///
     /// This is equivalent to the Python expression `dir(self)`.
-    pub fn dir(&self) -> &PyList {
-        unsafe { self.py().from_owned_ptr(ffi::PyObject_Dir(self.as_ptr())) }
+    pub fn dir(&self) -> PyResult<&PyList> {
+        unsafe {
+            self.py()
+                .from_owned_ptr_or_err(ffi::PyObject_Dir(self.as_ptr()))
+        }
+    }
+}
+
+/// Looks up `name` in the dictionaries of the classes in the MRO of `ty`, without invoking
+/// descriptors, like `_PyType_Lookup`.
+#[cfg(any(Py_LIMITED_API, PyPy))]
+fn lookup_in_mro<'p>(ty: &'p PyType, name: &PyObject) -> PyResult<Option<&'p PyAny>> {
+    for base in ty.getattr("__mro__")?.iter()? {
+        let dict = base?.getattr("__dict__")?;
+        match dict.get_item(name) {
+            Ok(attr) => return Ok(Some(attr)),
+            Err(err) if err.is_instance::<crate::exceptions::KeyError>(ty.py()) => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(None)
+}
+
+/// Returns the `am_await` slot of `ty`, if it has one.
+unsafe fn am_await(ty: *mut ffi::PyTypeObject) -> Option<ffi::unaryfunc> {
+    let as_async = (*ty).tp_as_async;
+    if as_async.is_null() {
+        None
+    } else {
+        (*as_async).am_await
+    }
+}
+
+/// The exact type of an object, returned by [PyAny::classify].
+#[derive(Clone, Copy, Debug)]
+pub enum ObjectKind<'p> {
+    /// `None`.
+    None,
+    /// `True` or `False`.
+    Bool(&'p PyBool),
+    /// An `int`.
+    Int(&'p PyLong),
+    /// A `float`.
+    Float(&'p PyFloat),
+    /// A `str`.
+    Str(&'p PyString),
+    /// A `bytes`.
+    Bytes(&'p PyBytes),
+    /// A `list`.
+    List(&'p PyList),
+    /// A `tuple`.
+    Tuple(&'p PyTuple),
+    /// A `dict`.
+    Dict(&'p PyDict),
+    /// A `set`.
+    Set(&'p PySet),
+    /// An object of any other type, including the subclasses of the types above.
+    Other(&'p PyAny),
+}
+
+/// Formats the result of `repr` or `str` for `repr_safe` and `str_safe`.
+fn format_safe(obj: &PyAny, formatted: PyResult<&PyString>, max_chars: Option<usize>) -> String {
+    let formatted = match formatted {
+        Ok(formatted) => formatted.to_string_lossy(),
+        // `PyType::name` reads the name of the type without calling Python code.
+        Err(_) => return format!("<{} object at {:p}>", obj.get_type().name(), obj.as_ptr()),
+    };
+    match max_chars.and_then(|max_chars| formatted.char_indices().nth(max_chars)) {
+        Some((end, _)) => format!("{}...", &formatted[..end]),
+        None => formatted.into_owned(),
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::types::{IntoPyDict, PyList};
-    use crate::Python;
+    use crate::exceptions::{TypeError, ValueError};
+    use crate::types::{IntoPyDict, PyBool, PyDict, PyList, PyLong, PyModule, PyString};
     use crate::ToPyObject;
+    use crate::{AsPyPointer, AsPyRef, PyErr, Python};
 
     #[test]
     fn test_call_for_non_existing_method() {
@@ -499,12 +1133,138 @@ mod test {
             .unwrap();
         let a = obj
             .dir()
+            .unwrap()
             .into_iter()
             .map(|x| x.extract::<String>().unwrap());
         let b = dir.into_iter().map(|x| x.extract::<String>().unwrap());
         assert!(a.eq(b));
     }
 
+    const ATTRS: &str = r#"
+class Attrs:
+    def __init__(self):
+        self.value = 1
+        self.none = None
+        self.__len__ = lambda: 0
+
+    @property
+    def failing(self):
+        raise ValueError("failing property")
+
+    def __len__(self):
+        return 3
+"#;
+
+    #[test]
+    fn test_getattr_opt() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let module = PyModule::from_code(py, ATTRS, "attrs.py", "pyo3_any_attrs").unwrap();
+        let obj = module.call0("Attrs").unwrap();
+
+        let value = obj.getattr_opt("value").unwrap().unwrap();
+        assert_eq!(1, value.extract::<i32>().unwrap());
+        assert!(obj.getattr_opt("none").unwrap().unwrap().is_none());
+        assert!(obj.getattr_opt("missing").unwrap().is_none());
+        let interned = PyString::intern(py, "value");
+        assert!(obj.getattr_opt(interned).unwrap().is_some());
+
+        assert!(obj.hasattr("value").unwrap());
+        assert!(!obj.hasattr("missing").unwrap());
+
+        // Only `AttributeError` is suppressed.
+        let err = obj.getattr_opt("failing").map(|_| ()).unwrap_err();
+        assert!(err.is_instance::<ValueError>(py));
+        let err = obj.hasattr("failing").unwrap_err();
+        assert!(err.is_instance::<ValueError>(py));
+    }
+
+    #[test]
+    fn test_lookup_special() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let module = PyModule::from_code(py, ATTRS, "attrs.py", "pyo3_any_special").unwrap();
+        let obj = module.call0("Attrs").unwrap();
+
+        // The instance attribute shadows the method for `getattr`, but not for `len()`.
+        let len = obj.getattr("__len__").unwrap().call0().unwrap();
+        assert_eq!(0, len.extract::<usize>().unwrap());
+        let len = obj.lookup_special("__len__").unwrap().unwrap();
+        assert_eq!(3, len.call0().unwrap().extract::<usize>().unwrap());
+        assert_eq!(3, obj.len().unwrap());
+
+        assert!(obj.lookup_special("value").unwrap().is_none());
+        assert!(obj.lookup_special("__missing__").unwrap().is_none());
+        let err = obj.lookup_special("failing").map(|_| ()).unwrap_err();
+        assert!(err.is_instance::<ValueError>(py));
+
+        let list = PyList::new(py, &[1, 2]);
+        let len = list
+            .lookup_special(PyString::intern(py, "__len__"))
+            .unwrap();
+        assert_eq!(2, len.unwrap().call0().unwrap().extract::<usize>().unwrap());
+    }
+
+    #[test]
+    fn test_call_special_method() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let module = PyModule::from_code(
+            py,
+            r#"
+class Manager:
+    def __init__(self):
+        self.__enter__ = lambda: "instance"
+        self.exited = None
+
+    def __enter__(self):
+        return "class"
+
+    def __exit__(self, *exc_info):
+        self.exited = exc_info
+        return True
+
+    def __getattr__(self, name):
+        return lambda *args: "getattr"
+
+def entered(manager):
+    with manager as value:
+        return value
+"#,
+            "manager.py",
+            "pyo3_any_call_special",
+        )
+        .unwrap();
+        let manager = module.call0("Manager").unwrap();
+
+        // `with` uses the method of the class, not the instance attribute.
+        let entered = module.call1("entered", (manager,)).unwrap();
+        assert_eq!("class", entered.extract::<&str>().unwrap());
+        let value = manager.call_special_method0("__enter__").unwrap();
+        assert_eq!("class", value.extract::<&str>().unwrap());
+        let value = manager.call_method0("__enter__").unwrap();
+        assert_eq!("instance", value.extract::<&str>().unwrap());
+
+        let suppressed = manager
+            .call_special_method1("__exit__", (py.None(), py.None(), py.None()))
+            .unwrap();
+        assert!(suppressed.is_true().unwrap());
+        assert_eq!(3, manager.getattr("exited").unwrap().len().unwrap());
+
+        // `__getattr__` does not provide special methods.
+        let value = manager.call_method0("__aenter__").unwrap();
+        assert_eq!("getattr", value.extract::<&str>().unwrap());
+        let err = manager
+            .call_special_method0("__aenter__")
+            .map(|_| ())
+            .unwrap_err();
+        assert!(err.is_instance::<crate::exceptions::AttributeError>(py));
+        assert_eq!(
+            "'Manager' object has no special method '__aenter__'",
+            err.to_object(py).as_ref(py).to_string()
+        );
+    }
+
     #[test]
     fn test_nan_eq() {
         let gil = Python::acquire_gil();
@@ -512,4 +1272,338 @@ mod test {
         let nan = py.eval("float('nan')", None, None).unwrap();
         assert!(nan.compare(nan).is_err());
     }
+
+    #[test]
+    fn test_rich_compare_bool() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let one = 1.to_object(py);
+        let one = one.as_ref(py);
+        assert!(one.eq(1).unwrap());
+        assert!(one.eq(1.0).unwrap());
+        assert!(!one.ne(1).unwrap());
+        assert!(one.ne(2).unwrap());
+        assert!(one.lt(2).unwrap());
+        assert!(one.le(1).unwrap());
+        assert!(!one.gt(1).unwrap());
+        assert!(one.ge(0).unwrap());
+        let err = one.lt("a").unwrap_err();
+        assert!(err.is_instance::<TypeError>(py));
+    }
+
+    #[test]
+    fn test_nan_rich_compare_bool() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let nan = py.eval("float('nan')", None, None).unwrap();
+        let other_nan = py.eval("float('nan')", None, None).unwrap();
+        assert!(nan.is(nan));
+        assert!(!nan.is(other_nan));
+        // Identity implies equality, as for `nan in [nan]`.
+        assert!(nan.eq(nan).unwrap());
+        assert!(!nan.ne(nan).unwrap());
+        assert!(!nan.eq(other_nan).unwrap());
+        assert!(nan.ne(other_nan).unwrap());
+        assert!(!nan.lt(nan).unwrap());
+        assert!(!nan.ge(nan).unwrap());
+    }
+
+    #[test]
+    fn test_eq_raises() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let code = r#"
+class FailingEq:
+    def __eq__(self, other):
+        raise ValueError("cannot compare")
+    __hash__ = object.__hash__
+"#;
+        let module = PyModule::from_code(py, code, "failing_eq.py", "pyo3_any_eq").unwrap();
+        let a = module.call0("FailingEq").unwrap();
+        let b = module.call0("FailingEq").unwrap();
+        let err = a.eq(b).unwrap_err();
+        assert!(err.is_instance::<ValueError>(py));
+        // `__eq__` is not called when comparing an object with itself.
+        assert!(a.eq(a).unwrap());
+        assert!(!a.is(b));
+    }
+
+    struct AbstractNumber;
+
+    crate::import_exception_type_object!(pyo3_any_test, AbstractNumber);
+
+    #[test]
+    fn test_is_instance_of() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let dict = PyDict::new(py);
+        assert!(dict.is_instance_of::<PyDict>().unwrap());
+        assert!(dict.is_instance_of_no_hooks::<PyDict>());
+        assert!(!dict.is_instance_of::<PyList>().unwrap());
+        assert!(!dict.is_instance_of_no_hooks::<PyList>());
+
+        let bool_type = py.get_type::<PyBool>();
+        assert!(bool_type.is_subclass_of::<PyLong>().unwrap());
+        assert!(bool_type.is_subclass_of_no_hooks::<PyLong>());
+        assert!(!bool_type.is_subclass_of::<PyDict>().unwrap());
+        assert!(!bool_type.is_subclass_of_no_hooks::<PyDict>());
+    }
+
+    #[test]
+    fn test_is_instance_of_virtual_subclass() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        PyModule::from_code(
+            py,
+            "import abc\nclass AbstractNumber(Exception, metaclass=abc.ABCMeta): pass\nAbstractNumber.register(int)\n",
+            "pyo3_any_test.py",
+            "pyo3_any_test",
+        )
+        .unwrap();
+
+        let number = 42.to_object(py);
+        let number = number.as_ref(py);
+        assert!(number.is_instance_of::<AbstractNumber>().unwrap());
+        assert!(!number.is_instance_of_no_hooks::<AbstractNumber>());
+
+        let int_type = number.get_type();
+        assert!(int_type.is_subclass_of::<AbstractNumber>().unwrap());
+        assert!(!int_type.is_subclass_of_no_hooks::<AbstractNumber>());
+    }
+
+    #[test]
+    fn test_downcast_error_message() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let obj = py.eval("42", None, None).unwrap();
+        let err = obj.downcast::<PyDict>().unwrap_err();
+        assert_eq!(err.to_string(), "expected 'dict', got 'int' (42)");
+        assert_eq!(err.to(), "dict");
+        // The object is kept for recovery.
+        assert!(err.from().is(obj));
+        assert_eq!(err.from().extract::<i32>().unwrap(), 42);
+        let err: PyErr = err.into();
+        assert!(err.is_instance::<TypeError>(py));
+
+        let obj = py
+            .eval("type('Outer', (), {'Inner': type('Inner', (), {'__qualname__': 'Outer.Inner'})}).Inner()", None, None)
+            .unwrap();
+        let err: PyErr = obj.downcast::<PyList>().unwrap_err().into();
+        assert!(err.is_instance::<TypeError>(py));
+        let message = err
+            .to_object(py)
+            .as_ref(py)
+            .str()
+            .unwrap()
+            .to_string()
+            .unwrap()
+            .into_owned();
+        assert!(
+            message.starts_with(
+                "expected 'list', got 'Outer.Inner' (<__main__.Outer.Inner object at "
+            ),
+            "{}",
+            message
+        );
+
+        let obj = py.eval("'x' * 1000", None, None).unwrap();
+        let err = obj.downcast::<PyList>().unwrap_err();
+        let message = format!("expected 'list', got 'str' ('{}...)", "x".repeat(99));
+        assert_eq!(err.to_string(), message);
+    }
+
+    #[test]
+    fn test_repr_safe() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let module = PyModule::from_code(
+            py,
+            r#"
+class Raises:
+    def __repr__(self):
+        raise ValueError("no repr")
+    __str__ = __repr__
+
+class Huge:
+    def __repr__(self):
+        return "x" * 10_000_000
+
+class Recursive:
+    def __repr__(self):
+        return repr(self)
+"#,
+            "repr_safe.py",
+            "repr_safe",
+        )
+        .unwrap();
+
+        let raises = module.call0("Raises").unwrap();
+        let fallback = format!("<Raises object at {:p}>", raises.as_ptr());
+        assert_eq!(raises.repr_safe(None), fallback);
+        assert_eq!(raises.str_safe(Some(100)), fallback);
+        assert_eq!(
+            format!("{} {:?}", raises, raises),
+            format!("{} {}", fallback, fallback)
+        );
+        assert!(!PyErr::occurred(py));
+
+        let huge = module.call0("Huge").unwrap();
+        assert_eq!(huge.repr_safe(Some(5)), "xxxxx...");
+        assert_eq!(huge.repr_safe(None).len(), 10_000_000);
+
+        let recursive = module.call0("Recursive").unwrap();
+        assert!(recursive
+            .repr_safe(None)
+            .starts_with("<Recursive object at 0x"));
+        assert!(!PyErr::occurred(py));
+
+        let text = PyString::new(py, "añb");
+        assert_eq!(text.str_safe(Some(2)), "añ...");
+        assert_eq!(text.str_safe(Some(3)), "añb");
+        assert_eq!(text.repr_safe(None), "'añb'");
+    }
+
+    #[test]
+    fn test_classify() {
+        use crate::types::{ObjectKind, PyBool, PyLong};
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let values = py
+            .eval(
+                "[None, True, 1, 1.5, 's', b'b', [], (), {}, set(), frozenset(), object()]",
+                None,
+                None,
+            )
+            .unwrap()
+            .downcast::<PyList>()
+            .unwrap();
+        let kinds: Vec<_> = values
+            .iter()
+            .map(|value| match value.classify() {
+                ObjectKind::None => "None",
+                ObjectKind::Bool(value) => {
+                    assert!(value.is_true());
+                    "Bool"
+                }
+                ObjectKind::Int(value) => {
+                    assert_eq!(value.extract::<i32>().unwrap(), 1);
+                    "Int"
+                }
+                ObjectKind::Float(_) => "Float",
+                ObjectKind::Str(_) => "Str",
+                ObjectKind::Bytes(_) => "Bytes",
+                ObjectKind::List(_) => "List",
+                ObjectKind::Tuple(_) => "Tuple",
+                ObjectKind::Dict(_) => "Dict",
+                ObjectKind::Set(_) => "Set",
+                ObjectKind::Other(_) => "Other",
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                "None", "Bool", "Int", "Float", "Str", "Bytes", "List", "Tuple", "Dict", "Set",
+                "Other", "Other"
+            ]
+        );
+
+        let subclasses = py
+            .eval(
+                "[type('Sub', (base,), {})() for base in (int, float, str, bytes, list, tuple, dict, set)]",
+                None,
+                None,
+            )
+            .unwrap()
+            .downcast::<PyList>()
+            .unwrap();
+        for value in subclasses.iter() {
+            match value.classify() {
+                ObjectKind::Other(other) => assert!(other.is(value)),
+                _ => panic!("{} is not classified as Other", value),
+            }
+        }
+
+        let bool_value = py.eval("False", None, None).unwrap();
+        assert!(!bool_value.downcast_exact::<PyBool>().unwrap().is_true());
+        assert!(bool_value.downcast::<PyLong>().is_ok());
+        assert!(bool_value.downcast_exact::<PyLong>().is_err());
+    }
+
+    #[test]
+    fn test_len_uses_slot() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let locals = PyDict::new(py);
+        py.run(
+            r#"
+class Sized(dict):
+    def __len__(self):
+        return 7
+
+sized = Sized(a=1)
+sized.__len__ = lambda: 99
+"#,
+            None,
+            Some(locals),
+        )
+        .unwrap();
+        let sized = locals.get_item("sized").unwrap();
+        assert_eq!(sized.len().unwrap(), 7);
+        let err = py.eval("object()", None, None).unwrap().len().unwrap_err();
+        assert!(err.is_instance::<TypeError>(py));
+    }
+
+    #[test]
+    fn test_hash_unhashable() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let list = PyList::empty(py);
+        assert!(list.hash().unwrap_err().is_instance::<TypeError>(py));
+        assert_eq!(5.to_object(py).as_ref(py).hash().unwrap(), 5);
+    }
+
+    #[test]
+    fn test_format() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let v = py.eval("255", None, None).unwrap();
+        assert_eq!(v.format("#x").unwrap().to_str().unwrap(), "0xff");
+        assert_eq!(v.format("").unwrap().to_str().unwrap(), "255");
+        assert!(v
+            .format("invalid")
+            .unwrap_err()
+            .is_instance::<ValueError>(py));
+    }
+
+    #[test]
+    fn test_number_operators() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let v = py.eval("-5", None, None).unwrap();
+        assert_eq!(v.abs().unwrap().extract::<i32>().unwrap(), 5);
+        assert_eq!(v.pos().unwrap().extract::<i32>().unwrap(), -5);
+        assert_eq!(v.neg().unwrap().extract::<i32>().unwrap(), 5);
+        assert_eq!(v.invert().unwrap().extract::<i32>().unwrap(), 4);
+
+        let s = PyString::new(py, "s");
+        assert!(s.abs().unwrap_err().is_instance::<TypeError>(py));
+        assert!(s.neg().unwrap_err().is_instance::<TypeError>(py));
+        assert!(s.matmul(1).unwrap_err().is_instance::<TypeError>(py));
+
+        let locals = PyDict::new(py);
+        py.run(
+            r#"
+class Matrix:
+    def __matmul__(self, other):
+        return ("matmul", other)
+"#,
+            None,
+            Some(locals),
+        )
+        .unwrap();
+        let matrix = locals.get_item("Matrix").unwrap().call0().unwrap();
+        let product: (String, i32) = matrix.matmul(3).unwrap().extract().unwrap();
+        assert_eq!(product, ("matmul".to_string(), 3));
+    }
 }
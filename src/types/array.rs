@@ -0,0 +1,325 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+use crate::buffer::{Element, PyBuffer};
+use crate::err::{PyDowncastError, PyErr, PyResult};
+use crate::instance::{AsPyRef, PyNativeType};
+use crate::once_cell::GILOnceCell;
+use crate::types::{PyAny, PyBytes, PyType};
+use crate::{exceptions, ffi, AsPyPointer, FromPyObject, IntoPy, Py, PyObject, PyTryFrom, Python};
+use std::ffi::CStr;
+use std::{mem, slice};
+
+/// Represents a one-dimensional array from the standard library `array` module, as created by
+/// `array.array(typecode, values)`.
+///
+/// The elements of an array are stored contiguously as values of a C type given by its
+/// typecode, e.g. `'d'` for `double` or `'i'` for `int`. They can be accessed without copying
+/// through the buffer protocol, as any Rust type implementing [Element] with the same size and
+/// kind as the typecode. To convert a `Vec` to an array and back, use [TypedArray].
+#[repr(transparent)]
+pub struct PyArray1D(PyAny);
+pyobject_native_type_named!(PyArray1D);
+pyobject_native_type_extract!(PyArray1D);
+
+impl PyArray1D {
+    /// Creates a new array with the given typecode, containing a copy of `values`.
+    ///
+    /// This is equivalent to the Python expression `array.array(typecode, values)`. Returns a
+    /// `TypeError` if `typecode` does not describe elements of type `T`, and a `ValueError` if
+    /// it is not a valid typecode.
+    pub fn from_slice<'p, T: Element>(
+        py: Python<'p>,
+        typecode: char,
+        values: &[T],
+    ) -> PyResult<&'p PyArray1D> {
+        check_typecode::<T>(typecode)?;
+        // Passing a `bytes` object as initializer copies its contents as machine values.
+        let bytes = unsafe {
+            slice::from_raw_parts(values.as_ptr() as *const u8, mem::size_of_val(values))
+        };
+        let mut code = [0; 4];
+        let array = get_array_type(py)
+            .call1((&*typecode.encode_utf8(&mut code), PyBytes::new(py, bytes)))?;
+        Ok(unsafe { <PyArray1D as PyTryFrom>::try_from_unchecked(array) })
+    }
+
+    /// Returns the typecode of the array.
+    ///
+    /// This is equivalent to the Python expression `self.typecode`.
+    pub fn typecode(&self) -> PyResult<char> {
+        let typecode: &str = self.getattr("typecode")?.extract()?;
+        Ok(typecode.chars().next().unwrap_or_default())
+    }
+
+    /// Returns the number of elements in the array.
+    ///
+    /// This is equivalent to the Python expression `len(self)`.
+    pub fn len(&self) -> usize {
+        unsafe { ffi::PySequence_Size(self.as_ptr()) as usize }
+    }
+
+    /// Returns whether the array is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Gets the buffer of the array, to access its elements as values of type `T`.
+    ///
+    /// While the buffer exists, the array cannot be resized. Returns a `TypeError` if the
+    /// typecode of the array does not describe elements of type `T`.
+    pub fn buffer<T: Element>(&self) -> PyResult<PyBuffer<T>> {
+        check_typecode::<T>(self.typecode()?)?;
+        PyBuffer::get(self)
+    }
+
+    /// Returns the elements of the array as a slice, without copying them.
+    ///
+    /// Returns a `TypeError` if the typecode of the array does not describe elements of type
+    /// `T`.
+    ///
+    /// # Safety
+    ///
+    /// The array must not be resized or modified, from Rust or from Python, while the slice
+    /// is alive. Use [buffer](PyArray1D::buffer) or [to_vec](PyArray1D::to_vec) if that cannot
+    /// be guaranteed.
+    pub unsafe fn as_slice<T: Element>(&self) -> PyResult<&[T]> {
+        let buffer = self.buffer::<T>()?;
+        let cells = buffer.as_slice(self.py()).expect("arrays are contiguous");
+        Ok(slice::from_raw_parts(
+            cells.as_ptr() as *const T,
+            cells.len(),
+        ))
+    }
+
+    /// Returns a copy of the elements of the array.
+    ///
+    /// Returns a `TypeError` if the typecode of the array does not describe elements of type
+    /// `T`.
+    pub fn to_vec<T: Element>(&self) -> PyResult<Vec<T>> {
+        self.buffer::<T>()?.to_vec(self.py())
+    }
+}
+
+/// Returns a `TypeError` if the array typecode `typecode` does not describe elements of type `T`.
+fn check_typecode<T: Element>(typecode: char) -> PyResult<()> {
+    let format = [typecode as u8, 0];
+    let format = CStr::from_bytes_with_nul(&format).ok();
+    if typecode.is_ascii() && format.map_or(false, T::is_compatible_format) {
+        Ok(())
+    } else {
+        Err(exceptions::TypeError::py_err(format!(
+            "array typecode '{}' is not compatible with element type `{}`",
+            typecode,
+            std::any::type_name::<T>()
+        )))
+    }
+}
+
+/// Returns the first array typecode describing elements of type `T`.
+fn typecode_for<T: Element>() -> char {
+    "bBhHiIlLqQfd"
+        .chars()
+        .find(|&typecode| check_typecode::<T>(typecode).is_ok())
+        .expect("no array typecode for element type")
+}
+
+/// Returns `array.array`.
+fn get_array_type(py: Python) -> &PyType {
+    static ARRAY_TYPE: GILOnceCell<Py<PyType>> = GILOnceCell::new();
+
+    ARRAY_TYPE
+        .get_or_init(py, || {
+            py.import("array")
+                .and_then(|array| Ok(array.get("array")?.downcast::<PyType>()?.into()))
+                .expect("failed to import array.array")
+        })
+        .as_ref(py)
+}
+
+impl<'v> PyTryFrom<'v> for PyArray1D {
+    fn try_from<V: Into<&'v PyAny>>(value: V) -> Result<&'v PyArray1D, PyDowncastError<'v>> {
+        let value = value.into();
+        let ty = get_array_type(value.py());
+        unsafe {
+            if ffi::PyObject_TypeCheck(value.as_ptr(), ty.as_type_ptr()) != 0 {
+                Ok(<PyArray1D as PyTryFrom>::try_from_unchecked(value))
+            } else {
+                Err(PyDowncastError::new(value, "array"))
+            }
+        }
+    }
+
+    fn try_from_exact<V: Into<&'v PyAny>>(value: V) -> Result<&'v PyArray1D, PyDowncastError<'v>> {
+        let value = value.into();
+        if value.get_type().is(get_array_type(value.py())) {
+            unsafe { Ok(<PyArray1D as PyTryFrom>::try_from_unchecked(value)) }
+        } else {
+            Err(PyDowncastError::new(value, "array"))
+        }
+    }
+
+    #[inline]
+    unsafe fn try_from_unchecked<V: Into<&'v PyAny>>(value: V) -> &'v PyArray1D {
+        let ptr = value.into() as *const _ as *const PyArray1D;
+        &*ptr
+    }
+}
+
+/// A `Vec` which is converted to and from an `array.array`, instead of a list.
+///
+/// The typecode of the created array is the first of `bBhHiIlLqQfd` which describes elements of
+/// type `T`, e.g. `'d'` for `f64` and `'i'` for `i32`. Extraction succeeds for any array whose
+/// typecode describes elements of type `T`.
+///
+/// # Example
+/// ```
+/// use pyo3::prelude::*;
+/// use pyo3::types::TypedArray;
+///
+/// #[pyfunction]
+/// fn linspace(start: f64, stop: f64, num: usize) -> TypedArray<f64> {
+///     let step = (stop - start) / (num - 1) as f64;
+///     TypedArray((0..num).map(|i| start + step * i as f64).collect())
+/// }
+///
+/// let gil = Python::acquire_gil();
+/// let py = gil.python();
+/// let linspace = pyo3::wrap_pyfunction!(linspace)(py);
+/// pyo3::py_run!(py, linspace, r#"
+/// values = linspace(0, 1, 3)
+/// assert values.typecode == 'd'
+/// assert values.tolist() == [0, 0.5, 1]
+/// "#);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TypedArray<T>(pub Vec<T>);
+
+impl<T: Element> IntoPy<PyObject> for TypedArray<T> {
+    fn into_py(self, py: Python) -> PyObject {
+        PyArray1D::from_slice(py, typecode_for::<T>(), &self.0)
+            .expect("failed to create array")
+            .into()
+    }
+}
+
+impl<'source, T: Element> FromPyObject<'source> for TypedArray<T> {
+    fn extract(obj: &'source PyAny) -> PyResult<Self> {
+        let array: &PyArray1D = obj.downcast().map_err(PyErr::from)?;
+        array.to_vec().map(TypedArray)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{PyArray1D, TypedArray};
+    use crate::exceptions::TypeError;
+    use crate::types::{IntoPyDict, PyList};
+    use crate::{AsPyRef, IntoPy, PyAny, PyObject, Python, ToPyObject};
+
+    #[test]
+    fn test_round_trip() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let ints = PyArray1D::from_slice(py, 'i', &[1i32, -2, 3]).unwrap();
+        assert_eq!('i', ints.typecode().unwrap());
+        assert_eq!(3, ints.len());
+        assert_eq!(&[1, -2, 3], unsafe { ints.as_slice::<i32>() }.unwrap());
+        assert_eq!(vec![1, -2, 3], ints.extract::<Vec<i32>>().unwrap());
+
+        let doubles = PyArray1D::from_slice(py, 'd', &[0.5f64, 1.5]).unwrap();
+        assert_eq!('d', doubles.typecode().unwrap());
+        assert_eq!(&[0.5, 1.5], unsafe { doubles.as_slice::<f64>() }.unwrap());
+        assert_eq!(vec![0.5, 1.5], doubles.to_vec::<f64>().unwrap());
+
+        let bytes = PyArray1D::from_slice(py, 'B', b"abc").unwrap();
+        assert_eq!('B', bytes.typecode().unwrap());
+        assert_eq!(b"abc", unsafe { bytes.as_slice::<u8>() }.unwrap());
+
+        let empty = PyArray1D::from_slice::<f64>(py, 'd', &[]).unwrap();
+        assert!(empty.is_empty());
+        assert!(unsafe { empty.as_slice::<f64>() }.unwrap().is_empty());
+        assert!(empty.to_vec::<f64>().unwrap().is_empty());
+        assert_eq!(0, empty.buffer::<f64>().unwrap().item_count());
+    }
+
+    #[test]
+    fn test_from_python() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let array = py
+            .eval(
+                "array.array('d', [1.0, 2.5])",
+                Some([("array", py.import("array").unwrap())].into_py_dict(py)),
+                None,
+            )
+            .unwrap();
+        let array: &PyArray1D = array.downcast().unwrap();
+        array.buffer::<f64>().unwrap().as_mut_slice(py).unwrap()[0].set(4.0);
+        assert_eq!(
+            vec![4.0, 2.5],
+            array
+                .call_method0("tolist")
+                .unwrap()
+                .extract::<Vec<f64>>()
+                .unwrap()
+        );
+
+        let list: &PyAny = PyList::new(py, &[1.0, 2.5]);
+        assert!(list.downcast::<PyArray1D>().is_err());
+    }
+
+    #[test]
+    fn test_typecode_mismatch() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let ints = PyArray1D::from_slice(py, 'i', &[1i32]).unwrap();
+        let err = unsafe { ints.as_slice::<f64>() }.map(|_| ()).unwrap_err();
+        assert!(err.is_instance::<TypeError>(py));
+        assert_eq!(
+            "array typecode 'i' is not compatible with element type `f64`",
+            err.to_object(py).as_ref(py).to_string()
+        );
+        assert!(ints.to_vec::<u32>().is_err());
+        assert!(ints.to_vec::<i64>().is_err());
+
+        let err = PyArray1D::from_slice(py, 'd', &[1i32])
+            .map(|_| ())
+            .unwrap_err();
+        assert!(err.is_instance::<TypeError>(py));
+        assert!(PyArray1D::from_slice(py, 'u', &[1u32]).is_err());
+    }
+
+    #[test]
+    fn test_typed_array() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let obj: PyObject = TypedArray(vec![1.0f64, 2.0]).into_py(py);
+        let array: &PyArray1D = obj.as_ref(py).downcast().unwrap();
+        assert_eq!('d', array.typecode().unwrap());
+        assert_eq!(
+            TypedArray(vec![1.0, 2.0]),
+            obj.extract::<TypedArray<f64>>(py).unwrap()
+        );
+
+        let obj: PyObject = TypedArray(vec![7u8, 8]).into_py(py);
+        assert_eq!(
+            'B',
+            obj.as_ref(py)
+                .downcast::<PyArray1D>()
+                .unwrap()
+                .typecode()
+                .unwrap()
+        );
+        let obj: PyObject = TypedArray(vec![-1i32]).into_py(py);
+        assert_eq!(
+            'i',
+            obj.as_ref(py)
+                .downcast::<PyArray1D>()
+                .unwrap()
+                .typecode()
+                .unwrap()
+        );
+        assert!(obj.extract::<TypedArray<f32>>(py).is_err());
+    }
+}
@@ -1,7 +1,7 @@
 // Copyright (c) 2017-present PyO3 Project and Contributors
 use crate::err::{PyErr, PyResult};
 use crate::instance::PyNativeType;
-use crate::{ffi, AsPyPointer, PyAny, Python};
+use crate::{exceptions, ffi, AsPyPointer, PyAny, Python};
 use std::os::raw::c_char;
 use std::slice;
 
@@ -54,8 +54,50 @@ impl PyByteArray {
         unsafe { ffi::PyByteArray_AsString(self.as_ptr()) as *mut u8 }
     }
 
+    /// Calls `f` with a copy of the contents of this bytearray.
+    ///
+    /// As `f` borrows a copy, it may run Python code which modifies or resizes the bytearray.
+    /// Use [PyByteArray::as_bytes] to avoid the copy.
+    pub fn with_bytes<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        f(&self.to_vec())
+    }
+
+    /// Calls `f` with a copy of the contents of this bytearray, and copies the slice back into
+    /// the bytearray once `f` returns.
+    ///
+    /// As `f` borrows a copy, it may run Python code which modifies or resizes the bytearray.
+    /// Returns an `IndexError`, leaving the bytearray as `f` left it, if the bytearray was shrunk
+    /// meanwhile. Use [PyByteArray::as_bytes_mut] to avoid the copies.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use pyo3::prelude::*;
+    /// # use pyo3::types::PyByteArray;
+    /// # let gil = GILGuard::acquire();
+    /// # let py = gil.python();
+    /// let bytearray = PyByteArray::new(py, b"Hello World.");
+    /// bytearray.with_bytes_mut(|bytes| bytes[11] = b'!')?;
+    /// assert_eq!(bytearray.to_vec(), b"Hello World!");
+    /// # Ok::<(), PyErr>(())
+    /// ```
+    pub fn with_bytes_mut<F, R>(&self, f: F) -> PyResult<R>
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        let mut bytes = self.to_vec();
+        let result = f(&mut bytes);
+        self.copy_from_slice(0, &bytes)?;
+        Ok(result)
+    }
+
     /// Get the contents of this buffer as a slice.
     ///
+    /// Prefer [PyByteArray::to_vec] or [PyByteArray::with_bytes], which are safe.
+    ///
     /// # Safety
     /// This bytearray must not be resized or edited while holding the slice.
     ///
@@ -79,6 +121,8 @@ impl PyByteArray {
 
     /// Get the contents of this buffer as a mutable slice.
     ///
+    /// Prefer [PyByteArray::copy_from_slice] or [PyByteArray::with_bytes_mut], which are safe.
+    ///
     /// # Safety
     /// This slice should only be used for short-lived operations that write to this bytearray
     /// without executing any Python code. See the safety note for [PyByteArray::as_bytes].
@@ -112,6 +156,34 @@ impl PyByteArray {
         unsafe { self.as_bytes() }.to_vec()
     }
 
+    /// Copies `data` into the bytearray, starting at `offset`.
+    ///
+    /// Returns an `IndexError` if `data` does not fit into the bytearray; use
+    /// [PyByteArray::resize] first to make room.
+    pub fn copy_from_slice(&self, offset: usize, data: &[u8]) -> PyResult<()> {
+        let len = self.len();
+        match offset.checked_add(data.len()) {
+            Some(end) if end <= len => {
+                // Copying runs no Python code.
+                unsafe { self.as_bytes_mut()[offset..end].copy_from_slice(data) };
+                Ok(())
+            }
+            _ => Err(exceptions::IndexError::py_err(format!(
+                "cannot copy {} bytes at offset {} into bytearray of length {}",
+                data.len(),
+                offset,
+                len
+            ))),
+        }
+    }
+
+    /// Appends `data` to the end of the bytearray.
+    pub fn extend_from_slice(&self, data: &[u8]) -> PyResult<()> {
+        let len = self.len();
+        self.resize(len + data.len())?;
+        self.copy_from_slice(len, data)
+    }
+
     /// Resizes the bytearray object to the new length `len`.
     ///
     /// Note that this will invalidate any pointers obtained by [PyByteArray::data], as well as
@@ -227,4 +299,89 @@ mod test {
         bytearray.resize(20).unwrap();
         assert_eq!(20, bytearray.len());
     }
+
+    #[test]
+    fn test_resize_keeps_data() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let src = b"Hello Python";
+        let bytearray = PyByteArray::new(py, src);
+
+        // Growing far beyond the initial allocation moves the buffer
+        bytearray.resize(1 << 20).unwrap();
+        assert_eq!(1 << 20, bytearray.len());
+        bytearray.with_bytes(|bytes| assert_eq!(src, &bytes[..src.len()]));
+
+        bytearray.resize(5).unwrap();
+        assert_eq!(b"Hello", bytearray.to_vec().as_slice());
+
+        bytearray.resize(0).unwrap();
+        assert!(bytearray.is_empty());
+        bytearray.resize(3).unwrap();
+        assert_eq!(3, bytearray.len());
+    }
+
+    #[test]
+    fn test_with_bytes_mut() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let bytearray = PyByteArray::new(py, b"Hello Python");
+        let len = bytearray
+            .with_bytes_mut(|bytes| {
+                bytes[0..5].copy_from_slice(b"Hi...");
+                bytes.len()
+            })
+            .unwrap();
+        assert_eq!(12, len);
+        assert_eq!(b"Hi... Python", bytearray.to_vec().as_slice());
+
+        // Python code may resize the bytearray while `f` holds the copy.
+        bytearray
+            .with_bytes_mut(|bytes| {
+                bytearray.extend_from_slice(b"!").unwrap();
+                bytes[0..5].copy_from_slice(b"Hello");
+            })
+            .unwrap();
+        assert_eq!(b"Hello Python!", bytearray.to_vec().as_slice());
+        let err = bytearray
+            .with_bytes_mut(|_| bytearray.resize(5).unwrap())
+            .unwrap_err();
+        assert!(err.is_instance::<exceptions::IndexError>(py));
+        assert_eq!(b"Hello", bytearray.to_vec().as_slice());
+    }
+
+    #[test]
+    fn test_copy_from_slice() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let bytearray = PyByteArray::new(py, b"Hello Python");
+        bytearray.copy_from_slice(6, b"Rust!!").unwrap();
+        assert_eq!(b"Hello Rust!!", bytearray.to_vec().as_slice());
+        bytearray.copy_from_slice(12, b"").unwrap();
+
+        for &(offset, data) in &[(7, &b"Rust!!"[..]), (13, &b""[..]), (usize::MAX, &b"!"[..])] {
+            let err = bytearray.copy_from_slice(offset, data).unwrap_err();
+            assert!(err.is_instance::<exceptions::IndexError>(py));
+        }
+        assert_eq!(b"Hello Rust!!", bytearray.to_vec().as_slice());
+    }
+
+    #[test]
+    fn test_extend_from_slice() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let bytearray = PyByteArray::new(py, b"");
+        bytearray.extend_from_slice(b"Hello").unwrap();
+        let data = vec![b'!'; 1 << 16];
+        bytearray.extend_from_slice(&data).unwrap();
+        assert_eq!(5 + (1 << 16), bytearray.len());
+        bytearray.with_bytes(|bytes| {
+            assert_eq!(b"Hello", &bytes[..5]);
+            assert!(bytes[5..].iter().all(|&b| b == b'!'));
+        });
+    }
 }
@@ -1,7 +1,8 @@
 use crate::{
-    ffi, AsPyPointer, FromPy, FromPyObject, PyAny, PyObject, PyResult, PyTryFrom, Python,
-    ToPyObject,
+    ffi, AsPyPointer, FromPy, FromPyObject, IntoPyPointer, PyAny, PyObject, PyResult, PyTryFrom,
+    Python, ToPyObject,
 };
+use std::mem::MaybeUninit;
 use std::ops::Index;
 use std::os::raw::c_char;
 use std::slice::SliceIndex;
@@ -26,6 +27,62 @@ impl PyBytes {
         unsafe { py.from_owned_ptr(ffi::PyBytes_FromStringAndSize(ptr, len)) }
     }
 
+    /// Creates a new Python bytestring object of length `len`, letting `init` write its contents
+    /// in place.
+    ///
+    /// This avoids building the data in a separate buffer before copying it into the bytestring.
+    /// The slice passed to `init` is zero-initialized. If `init` returns an error or panics, the
+    /// bytestring is freed right away.
+    ///
+    /// # Example
+    /// ```
+    /// use pyo3::{prelude::*, types::PyBytes};
+    /// let gil = Python::acquire_gil();
+    /// let py = gil.python();
+    /// let py_bytes = PyBytes::new_with(py, 10, |bytes: &mut [u8]| {
+    ///     bytes.copy_from_slice(b"Hello Rust");
+    ///     Ok(())
+    /// })
+    /// .unwrap();
+    /// assert_eq!(py_bytes.as_bytes(), b"Hello Rust");
+    /// ```
+    pub fn new_with<F>(py: Python, len: usize, init: F) -> PyResult<&PyBytes>
+    where
+        F: FnOnce(&mut [u8]) -> PyResult<()>,
+    {
+        unsafe {
+            let ptr = ffi::PyBytes_FromStringAndSize(std::ptr::null(), len as ffi::Py_ssize_t);
+            // Owned until `init` succeeds, so that the bytestring is freed if it fails or panics.
+            let bytes = PyObject::from_owned_ptr_or_err(py, ptr)?;
+            let buffer = ffi::PyBytes_AsString(ptr) as *mut u8;
+            debug_assert!(!buffer.is_null());
+            std::ptr::write_bytes(buffer, 0u8, len);
+            init(std::slice::from_raw_parts_mut(buffer, len))?;
+            Ok(py.from_owned_ptr(bytes.into_ptr()))
+        }
+    }
+
+    /// Creates a new Python bytestring object of length `len` without initializing its contents,
+    /// returning it together with its uninitialized buffer.
+    ///
+    /// # Safety
+    /// The whole buffer must be initialized before the bytestring is used, and the buffer must
+    /// not be written to afterwards, as bytestrings are immutable. In particular, the bytestring
+    /// must not be passed to any Python code before it is completely initialized.
+    #[allow(clippy::mut_from_ref)]
+    pub unsafe fn new_uninit(
+        py: Python,
+        len: usize,
+    ) -> PyResult<(&PyBytes, &mut [MaybeUninit<u8>])> {
+        let bytes: &PyBytes = py.from_owned_ptr_or_err(ffi::PyBytes_FromStringAndSize(
+            std::ptr::null(),
+            len as ffi::Py_ssize_t,
+        ))?;
+        let buffer = ffi::PyBytes_AsString(bytes.as_ptr()) as *mut MaybeUninit<u8>;
+        debug_assert!(!buffer.is_null());
+        Ok((bytes, std::slice::from_raw_parts_mut(buffer, len)))
+    }
+
     /// Creates a new Python byte string object from a raw pointer and length.
     ///
     /// Panics if out of memory.
@@ -71,8 +128,10 @@ impl<'a> FromPyObject<'a> for &'a [u8] {
 #[cfg(test)]
 mod test {
     use super::PyBytes;
+    use crate::exceptions::ValueError;
     use crate::FromPyObject;
     use crate::Python;
+    use std::mem::MaybeUninit;
 
     #[test]
     fn test_extract_bytes() {
@@ -84,6 +143,52 @@ mod test {
         assert_eq!(bytes, b"Hello Python");
     }
 
+    #[test]
+    fn test_bytes_new_with() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let len = 16 << 20;
+        let py_bytes = PyBytes::new_with(py, len, |bytes: &mut [u8]| {
+            assert!(bytes.iter().all(|&b| b == 0));
+            for (i, b) in bytes.iter_mut().enumerate() {
+                *b = i as u8;
+            }
+            Ok(())
+        })
+        .unwrap();
+        let bytes = py_bytes.as_bytes();
+        assert_eq!(len, bytes.len());
+        assert!(bytes.iter().enumerate().all(|(i, &b)| b == i as u8));
+
+        let empty = PyBytes::new_with(py, 0, |_| Ok(())).unwrap();
+        assert!(empty.as_bytes().is_empty());
+    }
+
+    #[test]
+    fn test_bytes_new_with_error() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let err = PyBytes::new_with(py, 10, |_bytes: &mut [u8]| {
+            Err(ValueError::py_err("Hello Crustaceans!"))
+        })
+        .unwrap_err();
+        assert!(err.is_instance::<ValueError>(py));
+    }
+
+    #[test]
+    fn test_bytes_new_uninit() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let py_bytes = unsafe {
+            let (py_bytes, buffer) = PyBytes::new_uninit(py, 5).unwrap();
+            for (dst, &src) in buffer.iter_mut().zip(b"Hello") {
+                *dst = MaybeUninit::new(src);
+            }
+            py_bytes
+        };
+        assert_eq!(b"Hello", py_bytes.as_bytes());
+    }
+
     #[test]
     fn test_bytes_index() {
         let gil = Python::acquire_gil();
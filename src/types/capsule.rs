@@ -0,0 +1,383 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+use crate::err::{self, PyErr, PyResult};
+use crate::instance::PyNativeType;
+use crate::panic::PanicException;
+use crate::{exceptions, ffi, AsPyPointer, PyAny, Python};
+use std::alloc::{alloc, dealloc, handle_alloc_error, Layout};
+use std::any::TypeId;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_void;
+use std::{mem, ptr};
+
+/// Represents a Python `PyCapsule`, an opaque container for a pointer used to share C-level
+/// APIs between extension modules.
+///
+/// Capsules created with [PyCapsule::new] own a Rust value, which is dropped by the capsule's
+/// destructor once the capsule itself is collected.
+///
+/// # Destructors and interpreter shutdown
+///
+/// A capsule stored as a module attribute usually lives until the interpreter is finalized.
+/// Python makes no guarantee about the order in which modules and their attributes are torn
+/// down at shutdown, and some objects may never be collected at all. The `Drop` implementation
+/// of a value stored in a capsule must therefore not rely on other Python objects still being
+/// alive, and must not assume that it runs at all.
+#[repr(transparent)]
+pub struct PyCapsule(PyAny);
+
+pyobject_native_var_type!(PyCapsule, ffi::PyCapsule_Type, ffi::PyCapsule_CheckExact);
+
+/// The header of the values owned by capsules created with [PyCapsule::new].
+///
+/// The capsule pointer points to the value, so that it can also be used as a plain `*const T`,
+/// e.g. by [PyCapsule::import] or by C code. The header is stored right before the value, so that
+/// it is found at the same offset whatever the type of the value.
+#[repr(C)]
+struct CapsuleHeader {
+    type_id: TypeId,
+    /// Drops the value and the header, and frees their allocation, given the capsule pointer.
+    drop: unsafe fn(*mut c_void),
+    name: CString,
+}
+
+/// Returns the layout of the allocation holding the header and a `T`, and the offset of the `T`.
+fn capsule_layout<T>() -> (Layout, usize) {
+    let header = Layout::new::<CapsuleHeader>();
+    let align = header.align().max(mem::align_of::<T>());
+    // The size of the header is a multiple of its alignment, so the header stays aligned.
+    let value_offset = header.size() + (align - header.size() % align) % align;
+    let layout = Layout::from_size_align(value_offset + mem::size_of::<T>(), align)
+        .expect("capsule value too large");
+    (layout, value_offset)
+}
+
+/// Returns the header of the value at `value`, which must be the pointer of a capsule created
+/// with [PyCapsule::new].
+unsafe fn header_of(value: *mut c_void) -> *mut CapsuleHeader {
+    (value as *mut u8).sub(mem::size_of::<CapsuleHeader>()) as *mut CapsuleHeader
+}
+
+unsafe fn drop_contents<T>(value: *mut c_void) {
+    struct Free(*mut u8, Layout);
+
+    impl Drop for Free {
+        fn drop(&mut self) {
+            unsafe { dealloc(self.0, self.1) }
+        }
+    }
+
+    let (layout, value_offset) = capsule_layout::<T>();
+    // Free the allocation even if dropping the value panics.
+    let _free = Free((value as *mut u8).sub(value_offset), layout);
+    ptr::drop_in_place(header_of(value));
+    ptr::drop_in_place(value as *mut T);
+}
+
+impl PyCapsule {
+    /// Creates a new capsule owning `value`, with the given name.
+    ///
+    /// By convention, the name of a capsule exported as a module attribute is the full dotted
+    /// path to that attribute, e.g. `"mymodule._C_API"`, which allows it to be retrieved with
+    /// [PyCapsule::import].
+    ///
+    /// The value is dropped when the capsule is collected, on whichever thread releases the last
+    /// reference to it, hence the `Send` bound.
+    pub fn new<'p, T: Send + 'static>(
+        py: Python<'p>,
+        value: T,
+        name: &CStr,
+    ) -> PyResult<&'p PyCapsule> {
+        let (layout, value_offset) = capsule_layout::<T>();
+        unsafe {
+            let base = alloc(layout);
+            if base.is_null() {
+                handle_alloc_error(layout);
+            }
+            let value_ptr = base.add(value_offset) as *mut c_void;
+            ptr::write(value_ptr as *mut T, value);
+            let header = header_of(value_ptr);
+            ptr::write(
+                header,
+                CapsuleHeader {
+                    type_id: TypeId::of::<T>(),
+                    drop: drop_contents::<T>,
+                    name: name.to_owned(),
+                },
+            );
+            let capsule =
+                ffi::PyCapsule_New(value_ptr, (*header).name.as_ptr(), Some(capsule_destructor));
+            if capsule.is_null() {
+                drop_contents::<T>(value_ptr);
+                return Err(PyErr::fetch(py));
+            }
+            Ok(py.from_owned_ptr(capsule))
+        }
+    }
+
+    /// Imports the capsule stored at `name`, a dotted path such as `"module.attribute"`,
+    /// and returns a reference to the value it points to.
+    ///
+    /// This wraps `PyCapsule_Import`, which also checks that the name of the capsule matches
+    /// `name`.
+    ///
+    /// # Safety
+    ///
+    /// The pointer held by the capsule must point to a valid `T`, which must stay alive as long
+    /// as the returned reference is used. This is usually the case for capsules exported by
+    /// modules, as they are only destroyed when the interpreter is finalized.
+    pub unsafe fn import<'p, T>(py: Python<'p>, name: &str) -> PyResult<&'p T> {
+        let name = CString::new(name)?;
+        let ptr = ffi::PyCapsule_Import(name.as_ptr(), 0);
+        if ptr.is_null() {
+            Err(PyErr::fetch(py))
+        } else {
+            Ok(&*(ptr as *const T))
+        }
+    }
+
+    /// Returns a reference to the value stored in the capsule.
+    ///
+    /// Returns a `TypeError` if the capsule was not created by [PyCapsule::new] with a value of
+    /// type `T`, and a `ValueError` if the capsule is not valid.
+    pub fn reference<T: 'static>(&self) -> PyResult<&T> {
+        // Validates the capsule and its name.
+        let value = self.pointer()?;
+        // The header is only read from the capsules created by `PyCapsule::new`, whose header
+        // has the same layout whatever the type of their value.
+        if !self.is_created_by_pyo3() || unsafe { (*header_of(value)).type_id } != TypeId::of::<T>()
+        {
+            return Err(exceptions::TypeError::py_err(format!(
+                "capsule does not contain a value of type `{}`",
+                std::any::type_name::<T>()
+            )));
+        }
+        Ok(unsafe { &*(value as *const T) })
+    }
+
+    /// Returns whether the capsule was created by [PyCapsule::new].
+    ///
+    /// These capsules, and only these, have `capsule_destructor` as destructor, which is not
+    /// generic so that it has a single address.
+    fn is_created_by_pyo3(&self) -> bool {
+        let destructor = unsafe { ffi::PyCapsule_GetDestructor(self.as_ptr()) };
+        destructor.map(|destructor| destructor as *const ())
+            == Some(capsule_destructor as *const ())
+    }
+
+    /// Returns the raw pointer stored in the capsule.
+    pub fn pointer(&self) -> PyResult<*mut c_void> {
+        unsafe {
+            let ptr =
+                ffi::PyCapsule_GetPointer(self.as_ptr(), ffi::PyCapsule_GetName(self.as_ptr()));
+            if ptr.is_null() {
+                Err(PyErr::fetch(self.py()))
+            } else {
+                Ok(ptr)
+            }
+        }
+    }
+
+    /// Returns the name of the capsule, or `None` if it has no name.
+    pub fn name(&self) -> PyResult<Option<&CStr>> {
+        unsafe {
+            let name = ffi::PyCapsule_GetName(self.as_ptr());
+            if !name.is_null() {
+                Ok(Some(CStr::from_ptr(name)))
+            } else if ffi::PyErr_Occurred().is_null() {
+                Ok(None)
+            } else {
+                Err(PyErr::fetch(self.py()))
+            }
+        }
+    }
+
+    /// Sets the context pointer of the capsule.
+    ///
+    /// The context is not used by Python or PyO3, and the capsule does not take ownership of it:
+    /// freeing the context, if needed, is the responsibility of the caller.
+    pub fn set_context(&self, context: *mut c_void) -> PyResult<()> {
+        let result = unsafe { ffi::PyCapsule_SetContext(self.as_ptr(), context) };
+        err::error_on_minusone(self.py(), result)
+    }
+
+    /// Returns the context pointer of the capsule, which is null if it was never set.
+    pub fn context(&self) -> PyResult<*mut c_void> {
+        unsafe {
+            let context = ffi::PyCapsule_GetContext(self.as_ptr());
+            if context.is_null() && !ffi::PyErr_Occurred().is_null() {
+                Err(PyErr::fetch(self.py()))
+            } else {
+                Ok(context)
+            }
+        }
+    }
+}
+
+unsafe extern "C" fn capsule_destructor(capsule: *mut ffi::PyObject) {
+    let pool = crate::GILPool::new();
+    let py = pool.python();
+    let value = ffi::PyCapsule_GetPointer(capsule, ffi::PyCapsule_GetName(capsule));
+    let drop_contents = (*header_of(value)).drop;
+    // Unwinding into Python would abort the process.
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| drop_contents(value)));
+    if let Err(payload) = result {
+        PanicException::from_panic_payload(py, payload).write_unraisable(py, None);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PyCapsule;
+    use crate::exceptions::{AttributeError, ImportError, TypeError};
+    use crate::types::PyModule;
+    use crate::Python;
+    use std::ffi::CStr;
+    use std::os::raw::c_void;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    #[derive(Debug)]
+    struct Api {
+        version: u32,
+        add: fn(i32, i32) -> i32,
+    }
+
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+
+    fn name(name: &[u8]) -> &CStr {
+        CStr::from_bytes_with_nul(name).unwrap()
+    }
+
+    #[test]
+    fn test_reference() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let api = Api { version: 3, add };
+        let capsule = PyCapsule::new(py, api, name(b"test.api\0")).unwrap();
+        assert_eq!(Some(name(b"test.api\0")), capsule.name().unwrap());
+
+        let api: &Api = capsule.reference().unwrap();
+        assert_eq!(3, api.version);
+        assert_eq!(5, (api.add)(2, 3));
+
+        let err = capsule.reference::<u32>().unwrap_err();
+        assert!(err.is_instance::<TypeError>(py));
+    }
+
+    #[test]
+    fn test_reference_alignment() {
+        #[derive(Debug)]
+        #[repr(align(64))]
+        struct Aligned(u8);
+
+        struct Empty;
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let capsule = PyCapsule::new(py, Aligned(5), name(b"test.aligned\0")).unwrap();
+        let aligned: &Aligned = capsule.reference().unwrap();
+        assert_eq!(5, aligned.0);
+        assert_eq!(0, aligned as *const Aligned as usize % 64);
+        assert!(capsule
+            .reference::<u8>()
+            .unwrap_err()
+            .is_instance::<TypeError>(py));
+
+        let capsule = PyCapsule::new(py, Empty, name(b"test.empty\0")).unwrap();
+        assert!(capsule.reference::<Empty>().is_ok());
+        assert!(capsule
+            .reference::<Aligned>()
+            .unwrap_err()
+            .is_instance::<TypeError>(py));
+    }
+
+    #[test]
+    fn test_foreign_capsule() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let datetime = py.import("datetime").unwrap();
+        let capsule: &PyCapsule = datetime
+            .getattr("datetime_CAPI")
+            .unwrap()
+            .downcast()
+            .unwrap();
+        assert_eq!(
+            Some(name(b"datetime.datetime_CAPI\0")),
+            capsule.name().unwrap()
+        );
+        assert!(!capsule.pointer().unwrap().is_null());
+        let err = capsule.reference::<Api>().unwrap_err();
+        assert!(err.is_instance::<TypeError>(py));
+    }
+
+    #[test]
+    fn test_drop() {
+        struct SetOnDrop(Arc<AtomicBool>);
+
+        impl Drop for SetOnDrop {
+            fn drop(&mut self) {
+                self.0.store(true, Ordering::Relaxed);
+            }
+        }
+
+        let dropped = Arc::new(AtomicBool::new(false));
+        {
+            let gil = Python::acquire_gil();
+            let py = gil.python();
+            let value = SetOnDrop(Arc::clone(&dropped));
+            let capsule = PyCapsule::new(py, value, name(b"test.drop\0")).unwrap();
+            assert!(!capsule
+                .reference::<SetOnDrop>()
+                .unwrap()
+                .0
+                .load(Ordering::Relaxed));
+            assert_eq!(1, capsule.get_refcnt());
+        }
+        assert!(dropped.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_import() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let module = PyModule::new(py, "pyo3_capsule_test").unwrap();
+        let api = Api { version: 7, add };
+        let capsule = PyCapsule::new(py, api, name(b"pyo3_capsule_test.api\0")).unwrap();
+        module.add("api", capsule).unwrap();
+        py.import("sys")
+            .unwrap()
+            .getattr("modules")
+            .unwrap()
+            .set_item("pyo3_capsule_test", module)
+            .unwrap();
+
+        let api: &Api = unsafe { PyCapsule::import(py, "pyo3_capsule_test.api") }.unwrap();
+        assert_eq!(7, api.version);
+        assert_eq!(capsule.pointer().unwrap(), api as *const Api as *mut c_void);
+
+        let err = unsafe { PyCapsule::import::<Api>(py, "pyo3_capsule_test.missing") }
+            .map(|_| ())
+            .unwrap_err();
+        assert!(err.is_instance::<AttributeError>(py));
+        let err = unsafe { PyCapsule::import::<Api>(py, "pyo3_no_such_module.api") }
+            .map(|_| ())
+            .unwrap_err();
+        assert!(err.is_instance::<ImportError>(py));
+    }
+
+    #[test]
+    fn test_context() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let capsule = PyCapsule::new(py, 0u8, name(b"test.context\0")).unwrap();
+        assert!(capsule.context().unwrap().is_null());
+
+        let mut context = 42u32;
+        let context_ptr = &mut context as *mut u32 as *mut c_void;
+        capsule.set_context(context_ptr).unwrap();
+        assert_eq!(context_ptr, capsule.context().unwrap());
+    }
+}
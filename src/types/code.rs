@@ -0,0 +1,90 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+use crate::err::PyResult;
+use crate::{ffi, AsPyPointer, PyAny, Python};
+
+/// Represents a Python code object, as returned by [Python::compile] or found in the `__code__`
+/// attribute of a function.
+#[repr(transparent)]
+pub struct PyCode(PyAny);
+
+pyobject_native_var_type!(PyCode, ffi::PyCode_Type, ffi::PyCode_Check);
+
+impl PyCode {
+    /// Returns the name of the function, or `"<module>"` for code compiled from source.
+    ///
+    /// This is the `co_name` attribute of the code object.
+    pub fn name(&self) -> PyResult<&str> {
+        self.getattr("co_name")?.extract()
+    }
+
+    /// Returns the file name the code was compiled from.
+    ///
+    /// This is the `co_filename` attribute of the code object.
+    pub fn filename(&self) -> PyResult<&str> {
+        self.getattr("co_filename")?.extract()
+    }
+
+    /// Returns the number of positional arguments, including positional-only ones.
+    ///
+    /// This is the `co_argcount` attribute of the code object.
+    pub fn arg_count(&self) -> PyResult<usize> {
+        self.getattr("co_argcount")?.extract()
+    }
+
+    /// Returns the number of positional-only arguments.
+    ///
+    /// This is the `co_posonlyargcount` attribute of the code object.
+    #[cfg(Py_3_8)]
+    pub fn posonly_arg_count(&self) -> PyResult<usize> {
+        self.getattr("co_posonlyargcount")?.extract()
+    }
+
+    /// Returns the number of keyword-only arguments.
+    ///
+    /// This is the `co_kwonlyargcount` attribute of the code object.
+    pub fn kwonly_arg_count(&self) -> PyResult<usize> {
+        self.getattr("co_kwonlyargcount")?.extract()
+    }
+
+    /// Returns the line number of the first line of the code.
+    ///
+    /// This is the `co_firstlineno` attribute of the code object.
+    pub fn first_line_number(&self) -> PyResult<usize> {
+        self.getattr("co_firstlineno")?.extract()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PyCode;
+    use crate::types::PyList;
+    use crate::{CompileMode, Python};
+
+    #[test]
+    fn test_attributes() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let code = py
+            .compile("x = 1", "attributes.py", CompileMode::Exec)
+            .unwrap();
+        assert_eq!(code.name().unwrap(), "<module>");
+        assert_eq!(code.filename().unwrap(), "attributes.py");
+        assert_eq!(code.arg_count().unwrap(), 0);
+        assert_eq!(code.first_line_number().unwrap(), 1);
+
+        let function = py
+            .eval("lambda a, b, *, c: None", None, None)
+            .unwrap()
+            .getattr("__code__")
+            .unwrap();
+        let code: &PyCode = function.downcast().unwrap();
+        assert_eq!(code.name().unwrap(), "<lambda>");
+        assert_eq!(code.arg_count().unwrap(), 2);
+        assert_eq!(code.kwonly_arg_count().unwrap(), 1);
+        #[cfg(Py_3_8)]
+        assert_eq!(code.posonly_arg_count().unwrap(), 0);
+
+        assert!(PyList::empty(py).downcast::<PyCode>().is_err());
+    }
+}
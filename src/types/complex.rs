@@ -1,7 +1,6 @@
-#[cfg(not(PyPy))]
+use crate::err::PyResult;
 use crate::instance::PyNativeType;
-use crate::{ffi, AsPyPointer, PyAny, Python};
-#[cfg(not(PyPy))]
+use crate::{exceptions, ffi, AsPyPointer, PyAny, Python};
 use std::ops::*;
 use std::os::raw::c_double;
 
@@ -33,97 +32,249 @@ impl PyComplex {
         unsafe { ffi::PyComplex_ImagAsDouble(self.as_ptr()) }
     }
     /// Returns `|self|`.
-    #[cfg(not(Py_LIMITED_API))]
-    #[cfg(not(PyPy))]
     pub fn abs(&self) -> c_double {
-        unsafe {
-            let val = (*(self.as_ptr() as *mut ffi::PyComplexObject)).cval;
-            ffi::_Py_c_abs(val)
+        c_ops::abs(self.cval())
+    }
+    /// Returns the complex conjugate of `self`.
+    ///
+    /// This is equivalent to the Python expression `self.conjugate()`.
+    pub fn conjugate(&self) -> &PyComplex {
+        PyComplex::from_doubles(self.py(), self.real(), -self.imag())
+    }
+    /// Returns `self ** other`.
+    ///
+    /// Like in Python, raising zero to a negative or complex power raises `ZeroDivisionError`,
+    /// and a result too large to be represented raises `OverflowError`.
+    pub fn pow(&self, other: &PyComplex) -> PyResult<&PyComplex> {
+        let (base, exp) = (self.cval(), other.cval());
+        if is_zero(base) && (exp.imag != 0.0 || exp.real < 0.0) {
+            return Err(exceptions::ZeroDivisionError::py_err(
+                "0.0 to a negative or complex power",
+            ));
+        }
+        let result = c_ops::pow(base, exp);
+        // CPython's `complex_pow` raises `OverflowError` when `errno` is `ERANGE` after
+        // `_Py_c_pow` and `_Py_ADJUST_ERANGE2`, which is exactly when a part of the result is
+        // infinite: an underflow resets `errno`, and `EDOM` is only set for the case above.
+        if result.real.is_infinite() || result.imag.is_infinite() {
+            return Err(exceptions::OverflowError::py_err("complex exponentiation"));
         }
+        Ok(from_cval(self.py(), result))
     }
-    /// Returns `self ** other`
-    #[cfg(not(Py_LIMITED_API))]
-    #[cfg(not(PyPy))]
-    pub fn pow(&self, other: &PyComplex) -> &PyComplex {
+
+    fn cval(&self) -> ffi::Py_complex {
+        #[cfg(not(any(Py_LIMITED_API, PyPy)))]
         unsafe {
-            self.py()
-                .from_owned_ptr(complex_operation(self, other, ffi::_Py_c_pow))
+            (*(self.as_ptr() as *mut ffi::PyComplexObject)).cval
+        }
+        #[cfg(any(Py_LIMITED_API, PyPy))]
+        ffi::Py_complex {
+            real: self.real(),
+            imag: self.imag(),
         }
     }
 }
 
-#[cfg(not(Py_LIMITED_API))]
-#[cfg(not(PyPy))]
-#[inline(always)]
-unsafe fn complex_operation(
-    l: &PyComplex,
-    r: &PyComplex,
-    operation: unsafe extern "C" fn(ffi::Py_complex, ffi::Py_complex) -> ffi::Py_complex,
-) -> *mut ffi::PyObject {
-    let l_val = (*(l.as_ptr() as *mut ffi::PyComplexObject)).cval;
-    let r_val = (*(r.as_ptr() as *mut ffi::PyComplexObject)).cval;
-    ffi::PyComplex_FromCComplex(operation(l_val, r_val))
+fn from_cval(py: Python, val: ffi::Py_complex) -> &PyComplex {
+    PyComplex::from_doubles(py, val.real, val.imag)
 }
 
-#[cfg(not(Py_LIMITED_API))]
-#[cfg(not(PyPy))]
-impl<'py> Add for &'py PyComplex {
-    type Output = &'py PyComplex;
-    fn add(self, other: &'py PyComplex) -> &'py PyComplex {
-        unsafe {
-            self.py()
-                .from_owned_ptr(complex_operation(self, other, ffi::_Py_c_sum))
-        }
+fn is_zero(val: ffi::Py_complex) -> bool {
+    val.real == 0.0 && val.imag == 0.0
+}
+
+/// Right-hand side operands of arithmetic operations on `PyComplex`.
+trait Operand {
+    fn cval(&self) -> ffi::Py_complex;
+}
+
+impl Operand for &PyComplex {
+    fn cval(&self) -> ffi::Py_complex {
+        PyComplex::cval(self)
     }
 }
 
-#[cfg(not(Py_LIMITED_API))]
-#[cfg(not(PyPy))]
-impl<'py> Sub for &'py PyComplex {
-    type Output = &'py PyComplex;
-    fn sub(self, other: &'py PyComplex) -> &'py PyComplex {
-        unsafe {
-            self.py()
-                .from_owned_ptr(complex_operation(self, other, ffi::_Py_c_diff))
+impl Operand for c_double {
+    fn cval(&self) -> ffi::Py_complex {
+        ffi::Py_complex {
+            real: *self,
+            imag: 0.0,
         }
     }
 }
 
-#[cfg(not(Py_LIMITED_API))]
-#[cfg(not(PyPy))]
-impl<'py> Mul for &'py PyComplex {
-    type Output = &'py PyComplex;
-    fn mul(self, other: &'py PyComplex) -> &'py PyComplex {
-        unsafe {
-            self.py()
-                .from_owned_ptr(complex_operation(self, other, ffi::_Py_c_prod))
+macro_rules! complex_operation {
+    ($trait:ident, $method:ident, $operation:path, $rhs:ty) => {
+        impl<'py> $trait<$rhs> for &'py PyComplex {
+            type Output = &'py PyComplex;
+            fn $method(self, other: $rhs) -> &'py PyComplex {
+                from_cval(self.py(), $operation(self.cval(), other.cval()))
+            }
         }
-    }
+    };
 }
 
-#[cfg(not(Py_LIMITED_API))]
-#[cfg(not(PyPy))]
-impl<'py> Div for &'py PyComplex {
-    type Output = &'py PyComplex;
-    fn div(self, other: &'py PyComplex) -> &'py PyComplex {
-        unsafe {
-            self.py()
-                .from_owned_ptr(complex_operation(self, other, ffi::_Py_c_quot))
+complex_operation!(Add, add, c_ops::sum, &'py PyComplex);
+complex_operation!(Add, add, c_ops::sum, c_double);
+complex_operation!(Sub, sub, c_ops::diff, &'py PyComplex);
+complex_operation!(Sub, sub, c_ops::diff, c_double);
+complex_operation!(Mul, mul, c_ops::prod, &'py PyComplex);
+complex_operation!(Mul, mul, c_ops::prod, c_double);
+
+macro_rules! complex_division {
+    ($rhs:ty) => {
+        /// Divides two complex numbers, raising `ZeroDivisionError` if the divisor is zero.
+        impl<'py> Div<$rhs> for &'py PyComplex {
+            type Output = PyResult<&'py PyComplex>;
+            fn div(self, other: $rhs) -> PyResult<&'py PyComplex> {
+                let divisor = other.cval();
+                if is_zero(divisor) {
+                    return Err(exceptions::ZeroDivisionError::py_err(
+                        "complex division by zero",
+                    ));
+                }
+                Ok(from_cval(self.py(), c_ops::quot(self.cval(), divisor)))
+            }
         }
-    }
+    };
 }
 
-#[cfg(not(Py_LIMITED_API))]
-#[cfg(not(PyPy))]
+complex_division!(&'py PyComplex);
+complex_division!(c_double);
+
 impl<'py> Neg for &'py PyComplex {
     type Output = &'py PyComplex;
     fn neg(self) -> &'py PyComplex {
-        unsafe {
-            let val = (*(self.as_ptr() as *mut ffi::PyComplexObject)).cval;
-            self.py()
-                .from_owned_ptr(ffi::PyComplex_FromCComplex(ffi::_Py_c_neg(val)))
+        from_cval(self.py(), c_ops::neg(self.cval()))
+    }
+}
+
+/// Complex arithmetic, using the helpers of the Python runtime where they are available.
+#[cfg(not(any(Py_LIMITED_API, PyPy)))]
+mod c_ops {
+    use crate::ffi::{self, Py_complex};
+    use std::os::raw::c_double;
+
+    pub fn sum(a: Py_complex, b: Py_complex) -> Py_complex {
+        unsafe { ffi::_Py_c_sum(a, b) }
+    }
+
+    pub fn diff(a: Py_complex, b: Py_complex) -> Py_complex {
+        unsafe { ffi::_Py_c_diff(a, b) }
+    }
+
+    pub fn prod(a: Py_complex, b: Py_complex) -> Py_complex {
+        unsafe { ffi::_Py_c_prod(a, b) }
+    }
+
+    pub fn quot(a: Py_complex, b: Py_complex) -> Py_complex {
+        unsafe { ffi::_Py_c_quot(a, b) }
+    }
+
+    pub fn pow(a: Py_complex, b: Py_complex) -> Py_complex {
+        unsafe { ffi::_Py_c_pow(a, b) }
+    }
+
+    pub fn neg(a: Py_complex) -> Py_complex {
+        unsafe { ffi::_Py_c_neg(a) }
+    }
+
+    pub fn abs(a: Py_complex) -> c_double {
+        unsafe { ffi::_Py_c_abs(a) }
+    }
+}
+
+/// Complex arithmetic, following the algorithms of CPython's `Objects/complexobject.c`.
+///
+/// Division by zero and raising zero to a negative or complex power are checked by the callers.
+#[cfg(any(Py_LIMITED_API, PyPy))]
+mod c_ops {
+    use crate::ffi::Py_complex;
+    use std::os::raw::c_double;
+
+    pub fn sum(a: Py_complex, b: Py_complex) -> Py_complex {
+        Py_complex {
+            real: a.real + b.real,
+            imag: a.imag + b.imag,
+        }
+    }
+
+    pub fn diff(a: Py_complex, b: Py_complex) -> Py_complex {
+        Py_complex {
+            real: a.real - b.real,
+            imag: a.imag - b.imag,
         }
     }
+
+    pub fn prod(a: Py_complex, b: Py_complex) -> Py_complex {
+        Py_complex {
+            real: a.real * b.real - a.imag * b.imag,
+            imag: a.real * b.imag + a.imag * b.real,
+        }
+    }
+
+    pub fn quot(a: Py_complex, b: Py_complex) -> Py_complex {
+        // Smith's algorithm, which avoids overflow for large divisors.
+        if b.real.abs() >= b.imag.abs() {
+            let ratio = b.imag / b.real;
+            let denom = b.real + b.imag * ratio;
+            Py_complex {
+                real: (a.real + a.imag * ratio) / denom,
+                imag: (a.imag - a.real * ratio) / denom,
+            }
+        } else if b.imag.abs() >= b.real.abs() {
+            let ratio = b.real / b.imag;
+            let denom = b.real * ratio + b.imag;
+            Py_complex {
+                real: (a.real * ratio + a.imag) / denom,
+                imag: (a.imag * ratio - a.real) / denom,
+            }
+        } else {
+            // At least one of the parts of the divisor is NaN.
+            Py_complex {
+                real: c_double::NAN,
+                imag: c_double::NAN,
+            }
+        }
+    }
+
+    pub fn pow(a: Py_complex, b: Py_complex) -> Py_complex {
+        if b.real == 0.0 && b.imag == 0.0 {
+            return Py_complex {
+                real: 1.0,
+                imag: 0.0,
+            };
+        }
+        if a.real == 0.0 && a.imag == 0.0 {
+            return Py_complex {
+                real: 0.0,
+                imag: 0.0,
+            };
+        }
+        let vabs = a.real.hypot(a.imag);
+        let at = a.imag.atan2(a.real);
+        let mut len = vabs.powf(b.real);
+        let mut phase = at * b.real;
+        if b.imag != 0.0 {
+            len /= (at * b.imag).exp();
+            phase += b.imag * vabs.ln();
+        }
+        Py_complex {
+            real: len * phase.cos(),
+            imag: len * phase.sin(),
+        }
+    }
+
+    pub fn neg(a: Py_complex) -> Py_complex {
+        Py_complex {
+            real: -a.real,
+            imag: -a.imag,
+        }
+    }
+
+    pub fn abs(a: Py_complex) -> c_double {
+        a.real.hypot(a.imag)
+    }
 }
 
 #[cfg(feature = "num-complex")]
@@ -225,9 +376,26 @@ mod complex_conversion {
 #[cfg(test)]
 mod test {
     use super::PyComplex;
+    use crate::exceptions::{OverflowError, ZeroDivisionError};
     use crate::Python;
     use assert_approx_eq::assert_approx_eq;
 
+    /// Evaluates `expr` in Python, with `l` and `r` bound to the given values.
+    fn eval<'p>(py: Python<'p>, expr: &str, l: &PyComplex, r: &PyComplex) -> &'p PyComplex {
+        let locals = crate::types::PyDict::new(py);
+        locals.set_item("l", l).unwrap();
+        locals.set_item("r", r).unwrap();
+        py.eval(expr, None, Some(locals))
+            .unwrap()
+            .downcast()
+            .unwrap()
+    }
+
+    fn assert_complex_eq(actual: &PyComplex, expected: &PyComplex) {
+        assert_approx_eq!(actual.real(), expected.real());
+        assert_approx_eq!(actual.imag(), expected.imag());
+    }
+
     #[test]
     fn test_from_double() {
         use assert_approx_eq::assert_approx_eq;
@@ -239,7 +407,6 @@ mod test {
         assert_approx_eq!(complex.imag(), 1.2);
     }
 
-    #[cfg(not(Py_LIMITED_API))]
     #[test]
     fn test_add() {
         let gil = Python::acquire_gil();
@@ -251,7 +418,6 @@ mod test {
         assert_approx_eq!(res.imag(), 3.8);
     }
 
-    #[cfg(not(Py_LIMITED_API))]
     #[test]
     fn test_sub() {
         let gil = Python::acquire_gil();
@@ -263,7 +429,6 @@ mod test {
         assert_approx_eq!(res.imag(), -1.4);
     }
 
-    #[cfg(not(Py_LIMITED_API))]
     #[test]
     fn test_mul() {
         let gil = Python::acquire_gil();
@@ -275,19 +440,17 @@ mod test {
         assert_approx_eq!(res.imag(), 9.0);
     }
 
-    #[cfg(not(Py_LIMITED_API))]
     #[test]
     fn test_div() {
         let gil = Python::acquire_gil();
         let py = gil.python();
         let l = PyComplex::from_doubles(py, 3.0, 1.2);
         let r = PyComplex::from_doubles(py, 1.0, 2.6);
-        let res = l / r;
+        let res = (l / r).unwrap();
         assert_approx_eq!(res.real(), 0.788_659_793_814_432_9);
         assert_approx_eq!(res.imag(), -0.850_515_463_917_525_7);
     }
 
-    #[cfg(not(Py_LIMITED_API))]
     #[test]
     fn test_neg() {
         let gil = Python::acquire_gil();
@@ -298,7 +461,6 @@ mod test {
         assert_approx_eq!(res.imag(), -1.2);
     }
 
-    #[cfg(not(Py_LIMITED_API))]
     #[test]
     fn test_abs() {
         let gil = Python::acquire_gil();
@@ -307,15 +469,88 @@ mod test {
         assert_approx_eq!(val.abs(), 3.231_098_884_280_702_2);
     }
 
-    #[cfg(not(Py_LIMITED_API))]
     #[test]
     fn test_pow() {
         let gil = Python::acquire_gil();
         let py = gil.python();
         let l = PyComplex::from_doubles(py, 3.0, 1.2);
         let r = PyComplex::from_doubles(py, 1.2, 2.6);
-        let val = l.pow(r);
+        let val = l.pow(r).unwrap();
         assert_approx_eq!(val.real(), -1.419_309_997_016_603_7);
         assert_approx_eq!(val.imag(), -0.541_297_466_033_544_6);
+
+        let pow = |base: f64, exp: f64| {
+            PyComplex::from_doubles(py, base, 0.0).pow(PyComplex::from_doubles(py, exp, 0.0))
+        };
+        let err = pow(10.0, 400.0).unwrap_err();
+        assert!(err.is_instance::<OverflowError>(py));
+        let err = pow(1e200, 2.5).unwrap_err();
+        assert!(err.is_instance::<OverflowError>(py));
+        let err = pow(0.0, -1.0).unwrap_err();
+        assert!(err.is_instance::<ZeroDivisionError>(py));
+        // An underflow is not an error.
+        let val = pow(0.5, 2000.0).unwrap();
+        assert_eq!((val.real(), val.imag()), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_conjugate() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let val = PyComplex::from_doubles(py, 3.0, 1.2);
+        let res = val.conjugate();
+        assert_approx_eq!(res.real(), 3.0);
+        assert_approx_eq!(res.imag(), -1.2);
+    }
+
+    #[test]
+    fn test_operations_match_python() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let l = PyComplex::from_doubles(py, 3.0, 1.2);
+        let r = PyComplex::from_doubles(py, -1.5, 2.6);
+        let f = PyComplex::from_doubles(py, 2.5, 0.0);
+
+        assert_complex_eq(l + r, eval(py, "l + r", l, r));
+        assert_complex_eq(l - r, eval(py, "l - r", l, r));
+        assert_complex_eq(l * r, eval(py, "l * r", l, r));
+        assert_complex_eq((l / r).unwrap(), eval(py, "l / r", l, r));
+        assert_complex_eq(l.pow(r).unwrap(), eval(py, "l ** r", l, r));
+        assert_complex_eq(-l, eval(py, "-l", l, r));
+        assert_complex_eq(l.conjugate(), eval(py, "l.conjugate()", l, r));
+        assert_approx_eq!(
+            l.abs(),
+            py.eval("abs(complex(3, 1.2))", None, None)
+                .unwrap()
+                .extract::<f64>()
+                .unwrap()
+        );
+
+        assert_complex_eq(l + 2.5, eval(py, "l + 2.5", l, f));
+        assert_complex_eq(l - 2.5, eval(py, "l - 2.5", l, f));
+        assert_complex_eq(l * 2.5, eval(py, "l * 2.5", l, f));
+        assert_complex_eq((l / 2.5).unwrap(), eval(py, "l / 2.5", l, f));
+    }
+
+    #[test]
+    fn test_zero_division() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let l = PyComplex::from_doubles(py, 3.0, 1.2);
+        let zero = PyComplex::from_doubles(py, 0.0, 0.0);
+
+        let err = (l / zero).map(|_| ()).unwrap_err();
+        assert!(err.is_instance::<ZeroDivisionError>(py));
+        let err = (l / 0.0).map(|_| ()).unwrap_err();
+        assert!(err.is_instance::<ZeroDivisionError>(py));
+
+        let err = zero
+            .pow(PyComplex::from_doubles(py, -1.0, 0.0))
+            .map(|_| ())
+            .unwrap_err();
+        assert!(err.is_instance::<ZeroDivisionError>(py));
+        let res = zero.pow(PyComplex::from_doubles(py, 2.0, 0.0)).unwrap();
+        assert_approx_eq!(res.real(), 0.0);
+        assert_approx_eq!(res.imag(), 0.0);
     }
 }
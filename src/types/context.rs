@@ -0,0 +1,289 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+use crate::err::{self, PyErr, PyResult};
+use crate::instance::PyNativeType;
+use crate::types::{PyAny, PyTuple};
+use crate::{exceptions, ffi, AsPyPointer, IntoPy, Py, Python, ToBorrowedObject};
+use std::ffi::CString;
+use std::ptr;
+
+/// Represents a `contextvars.Context`, a mapping from context variables to their values.
+///
+/// Each thread has a current context, in which [PyContextVar::get] and [PyContextVar::set]
+/// operate. Asynchronous frameworks such as `asyncio` give each task a copy of the context of
+/// the code which created it, so that values set by one task do not leak into others.
+#[repr(transparent)]
+pub struct PyContext(PyAny);
+
+pyobject_native_var_type!(
+    PyContext,
+    ffi::PyContext_Type,
+    Some("contextvars"),
+    ffi::PyContext_CheckExact
+);
+
+impl PyContext {
+    /// Creates a new, empty context.
+    ///
+    /// This is equivalent to the Python expression `contextvars.Context()`.
+    pub fn new(py: Python) -> PyResult<&PyContext> {
+        unsafe { py.from_owned_ptr_or_err(ffi::PyContext_New()) }
+    }
+
+    /// Returns a copy of the current context of this thread.
+    ///
+    /// This is equivalent to the Python expression `contextvars.copy_context()`.
+    pub fn copy_current(py: Python) -> PyResult<&PyContext> {
+        unsafe { py.from_owned_ptr_or_err(ffi::PyContext_CopyCurrent()) }
+    }
+
+    /// Returns a shallow copy of this context.
+    ///
+    /// This is equivalent to the Python expression `self.copy()`.
+    pub fn copy(&self) -> PyResult<&PyContext> {
+        unsafe {
+            self.py()
+                .from_owned_ptr_or_err(ffi::PyContext_Copy(self.as_ptr()))
+        }
+    }
+
+    /// Returns the value of `var` in this context, or `None` if it is not set.
+    ///
+    /// Unlike [PyContextVar::get], this does not fall back to the default value of the
+    /// variable.
+    pub fn get(&self, var: &PyContextVar) -> PyResult<Option<&PyAny>> {
+        match self.get_item(var) {
+            Ok(value) => Ok(Some(value)),
+            Err(err) if err.is_instance::<exceptions::KeyError>(self.py()) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Calls `callable` with `args` inside this context, and returns its result.
+    ///
+    /// This is equivalent to the Python expression `self.run(callable, *args)`: the context is
+    /// made the current context of this thread for the duration of the call, so that any
+    /// changes made to context variables are made in this context. Returns a `RuntimeError` if
+    /// the context has already been entered, in this or another thread.
+    pub fn run<'p>(
+        &'p self,
+        callable: &'p PyAny,
+        args: impl IntoPy<Py<PyTuple>>,
+    ) -> PyResult<&'p PyAny> {
+        let py = self.py();
+        let args = args.into_py(py);
+        err::error_on_minusone(py, unsafe { ffi::PyContext_Enter(self.as_ptr()) })?;
+        let result = callable.call1(args);
+        // The context must be exited even if the call failed; an error from the call takes
+        // precedence.
+        let exit = err::error_on_minusone(py, unsafe { ffi::PyContext_Exit(self.as_ptr()) });
+        let result = result?;
+        exit.map(|_| result)
+    }
+}
+
+/// Represents a `contextvars.ContextVar`, a variable whose value depends on the current
+/// context.
+///
+/// # Example
+/// ```
+/// use pyo3::prelude::*;
+/// use pyo3::types::{PyContext, PyContextVar};
+///
+/// # fn main() -> PyResult<()> {
+/// let gil = Python::acquire_gil();
+/// let py = gil.python();
+/// let trace_id = PyContextVar::new(py, "trace_id", None)?;
+/// let token = trace_id.set("abc123")?;
+/// assert_eq!("abc123", trace_id.get()?.unwrap().extract::<&str>()?);
+/// trace_id.reset(token)?;
+/// assert!(trace_id.get()?.is_none());
+/// # Ok(())
+/// # }
+/// ```
+#[repr(transparent)]
+pub struct PyContextVar(PyAny);
+
+pyobject_native_var_type!(
+    PyContextVar,
+    ffi::PyContextVar_Type,
+    Some("contextvars"),
+    ffi::PyContextVar_CheckExact
+);
+
+impl PyContextVar {
+    /// Creates a new context variable with the given name, and an optional default value.
+    ///
+    /// This is equivalent to the Python expression `contextvars.ContextVar(name)`, or
+    /// `contextvars.ContextVar(name, default=default)`. Context variables should be created
+    /// once, e.g. at module initialization, and not for every use: the values of a context
+    /// are only ever released along with the context itself.
+    pub fn new<'p>(
+        py: Python<'p>,
+        name: &str,
+        default: Option<&PyAny>,
+    ) -> PyResult<&'p PyContextVar> {
+        let name = CString::new(name)?;
+        let default = default.map_or(ptr::null_mut(), AsPyPointer::as_ptr);
+        unsafe { py.from_owned_ptr_or_err(ffi::PyContextVar_New(name.as_ptr(), default)) }
+    }
+
+    /// Returns the value of the variable in the current context.
+    ///
+    /// If the variable is not set in the current context, this returns its default value, or
+    /// `None` if it has none. This is equivalent to the Python expression `self.get(None)`,
+    /// except that a value of `None` is distinguished from a missing one.
+    pub fn get(&self) -> PyResult<Option<&PyAny>> {
+        let mut value = ptr::null_mut();
+        unsafe {
+            if ffi::PyContextVar_Get(self.as_ptr(), ptr::null_mut(), &mut value) == -1 {
+                return Err(PyErr::fetch(self.py()));
+            }
+            Ok(self.py().from_owned_ptr_or_opt(value))
+        }
+    }
+
+    /// Sets the value of the variable in the current context.
+    ///
+    /// This is equivalent to the Python expression `self.set(value)`. The returned token can
+    /// be passed to [reset](PyContextVar::reset) to restore the previous value.
+    pub fn set<V>(&self, value: V) -> PyResult<&PyContextToken>
+    where
+        V: ToBorrowedObject,
+    {
+        value.with_borrowed_ptr(self.py(), |value| unsafe {
+            self.py()
+                .from_owned_ptr_or_err(ffi::PyContextVar_Set(self.as_ptr(), value))
+        })
+    }
+
+    /// Restores the value the variable had before the call to [set](PyContextVar::set) which
+    /// returned `token`.
+    ///
+    /// This is equivalent to the Python expression `self.reset(token)`. Returns a `ValueError`
+    /// if the token was created in a different context, and a `RuntimeError` if it has already
+    /// been used.
+    pub fn reset(&self, token: &PyContextToken) -> PyResult<()> {
+        err::error_on_minusone(self.py(), unsafe {
+            ffi::PyContextVar_Reset(self.as_ptr(), token.as_ptr())
+        })
+    }
+
+    /// Returns the name of the variable.
+    pub fn name(&self) -> PyResult<&str> {
+        self.getattr("name")?.extract()
+    }
+}
+
+/// Represents a `contextvars.Token`, as returned by [PyContextVar::set].
+#[repr(transparent)]
+pub struct PyContextToken(PyAny);
+
+pyobject_native_var_type!(
+    PyContextToken,
+    ffi::PyContextToken_Type,
+    Some("contextvars"),
+    ffi::PyContextToken_CheckExact
+);
+
+impl PyContextToken {
+    /// Returns the variable which was set to create this token.
+    pub fn var(&self) -> PyResult<&PyContextVar> {
+        Ok(self.getattr("var")?.downcast()?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{PyContext, PyContextVar};
+    use crate::exceptions::{RuntimeError, ValueError};
+    use crate::types::IntoPyDict;
+    use crate::{AsPyPointer, AsPyRef, PyAny, Python, ToPyObject};
+
+    fn value(value: Option<&PyAny>) -> Option<String> {
+        value.map(|value| value.extract().unwrap())
+    }
+
+    #[test]
+    fn test_get_set_reset() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let default = "unset".to_object(py);
+        let var = PyContextVar::new(py, "test_get_set_reset", Some(default.as_ref(py))).unwrap();
+        assert_eq!("test_get_set_reset", var.name().unwrap());
+        assert_eq!(Some("unset".to_string()), value(var.get().unwrap()));
+
+        let token = var.set("a").unwrap();
+        assert_eq!(var.as_ptr(), token.var().unwrap().as_ptr());
+        assert_eq!(Some("a".to_string()), value(var.get().unwrap()));
+        let inner_token = var.set("b").unwrap();
+        assert_eq!(Some("b".to_string()), value(var.get().unwrap()));
+        var.reset(inner_token).unwrap();
+        assert_eq!(Some("a".to_string()), value(var.get().unwrap()));
+        var.reset(token).unwrap();
+        assert_eq!(Some("unset".to_string()), value(var.get().unwrap()));
+
+        let err = var.reset(token).unwrap_err();
+        assert!(err.is_instance::<RuntimeError>(py));
+
+        let var = PyContextVar::new(py, "no_default", None).unwrap();
+        assert!(var.get().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_context_isolation() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let var = PyContextVar::new(py, "trace_id", None).unwrap();
+        let token = var.set("request-1").unwrap();
+
+        let context = PyContext::copy_current(py).unwrap();
+        assert_eq!(
+            Some("request-1".to_string()),
+            value(context.get(var).unwrap())
+        );
+
+        let set_and_get = py
+            .eval(
+                "lambda value: (var.set(value), var.get())[1]",
+                Some([("var", var)].into_py_dict(py)),
+                None,
+            )
+            .unwrap();
+        let result = context.run(set_and_get, ("request-2",)).unwrap();
+        assert_eq!("request-2", result.extract::<&str>().unwrap());
+
+        // The change is only visible in the copied context.
+        assert_eq!(
+            Some("request-2".to_string()),
+            value(context.get(var).unwrap())
+        );
+        assert_eq!(Some("request-1".to_string()), value(var.get().unwrap()));
+
+        // A context cannot be entered twice, nor can a token be used in another context.
+        let run_nested = py
+            .eval(
+                "lambda: context.run(lambda: None)",
+                Some([("context", context)].into_py_dict(py)),
+                None,
+            )
+            .unwrap();
+        let err = context.run(run_nested, ()).unwrap_err();
+        assert!(err.is_instance::<RuntimeError>(py));
+        let reset = py
+            .eval(
+                "lambda: var.reset(token)",
+                Some([("var", var.to_object(py)), ("token", token.to_object(py))].into_py_dict(py)),
+                None,
+            )
+            .unwrap();
+        let err = context.run(reset, ()).unwrap_err();
+        assert!(err.is_instance::<ValueError>(py));
+        var.reset(token).unwrap();
+
+        let empty = PyContext::new(py).unwrap();
+        assert!(empty.get(var).unwrap().is_none());
+        let copy = context.copy().unwrap();
+        assert_eq!(Some("request-2".to_string()), value(copy.get(var).unwrap()));
+    }
+}
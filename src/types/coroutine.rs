@@ -0,0 +1,259 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+use crate::err::{PyErr, PyResult};
+use crate::instance::PyNativeType;
+use crate::types::generator::{resume, GeneratorResult};
+use crate::{ffi, AsPyPointer, PyAny, Python, ToPyObject};
+
+/// Represents a Python coroutine object, as created by calling an `async def` function.
+///
+/// A coroutine is driven like a generator: each [send](PyCoroutine::send) runs it until it
+/// suspends on an awaitable, which yields control back to the caller (usually an event loop), or
+/// until it returns.
+#[repr(transparent)]
+pub struct PyCoroutine(PyAny);
+
+pyobject_native_var_type!(PyCoroutine, ffi::PyCoro_Type, ffi::PyCoro_Check);
+
+impl PyCoroutine {
+    /// Resumes the coroutine, making the current suspension point evaluate to `value`.
+    ///
+    /// This is equivalent to the Python expression `self.send(value)`, except that the
+    /// `StopIteration` raised when the coroutine finishes is turned into
+    /// [GeneratorResult::Returned]. A coroutine that has not started yet must be sent `None`.
+    pub fn send(&self, value: &PyAny) -> PyResult<GeneratorResult> {
+        resume(self.py(), self.call_method1("send", (value,)))
+    }
+
+    /// Raises `exc` at the current suspension point of the coroutine.
+    ///
+    /// See [PyGenerator::throw](crate::types::PyGenerator::throw).
+    pub fn throw(&self, exc: PyErr) -> PyResult<GeneratorResult> {
+        let exc = exc.to_object(self.py());
+        resume(self.py(), self.call_method1("throw", (exc,)))
+    }
+
+    /// Closes the coroutine, raising `GeneratorExit` at its current suspension point.
+    ///
+    /// This is equivalent to the Python expression `self.close()`.
+    pub fn close(&self) -> PyResult<()> {
+        self.call_method0("close").map(drop)
+    }
+}
+
+/// Represents a Python asynchronous generator object, as created by calling an `async def`
+/// function containing `yield`.
+///
+/// Unlike [PyGenerator](crate::types::PyGenerator), its methods do not resume it directly, but
+/// return awaitables which do so when they are awaited.
+#[cfg(Py_3_6)]
+#[repr(transparent)]
+pub struct PyAsyncGenerator(PyAny);
+
+#[cfg(Py_3_6)]
+pyobject_native_var_type!(
+    PyAsyncGenerator,
+    ffi::PyAsyncGen_Type,
+    ffi::PyAsyncGen_Check
+);
+
+#[cfg(Py_3_6)]
+impl PyAsyncGenerator {
+    /// Returns an awaitable which resumes the generator with `value`, and evaluates to the next
+    /// value it yields.
+    ///
+    /// This is equivalent to the Python expression `self.asend(value)`.
+    pub fn asend(&self, value: &PyAny) -> PyResult<&PyAny> {
+        self.call_method1("asend", (value,))
+    }
+
+    /// Returns an awaitable which raises `exc` inside the generator, and evaluates to the next
+    /// value it yields.
+    ///
+    /// This is equivalent to the Python expression `self.athrow(exc)`.
+    pub fn athrow(&self, exc: PyErr) -> PyResult<&PyAny> {
+        let exc = exc.to_object(self.py());
+        self.call_method1("athrow", (exc,))
+    }
+
+    /// Returns an awaitable which closes the generator.
+    ///
+    /// This is equivalent to the Python expression `self.aclose()`.
+    pub fn aclose(&self) -> PyResult<&PyAny> {
+        self.call_method0("aclose")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PyCoroutine;
+    use crate::exceptions::{TypeError, ValueError};
+    use crate::types::{GeneratorResult, PyModule};
+    use crate::{AsPyRef, PyAny, Python, ToPyObject};
+
+    const CODE: &str = r#"
+import collections.abc
+import types
+
+class Suspend:
+    def __await__(self):
+        value = yield "suspended"
+        return value
+
+async def compute(x):
+    try:
+        y = await Suspend()
+    except ValueError:
+        y = -1
+    return x + y
+
+@types.coroutine
+def generator_based():
+    yield 1
+
+def generator():
+    yield 1
+
+class Registered:
+    pass
+
+collections.abc.Awaitable.register(Registered)
+
+async def countdown(n):
+    while n > 0:
+        yield n
+        n -= 1
+"#;
+
+    fn module(py: Python) -> &PyModule {
+        PyModule::from_code(py, CODE, "coroutine.py", "pyo3_coroutine_test").unwrap()
+    }
+
+    #[test]
+    fn test_send() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let coro = module(py).call1("compute", (10,)).unwrap();
+        assert!(coro.is_coroutine());
+        assert!(coro.is_awaitable().unwrap());
+        let coro: &PyCoroutine = coro.downcast().unwrap();
+
+        let none = py.None();
+        match coro.send(none.as_ref(py)).unwrap() {
+            GeneratorResult::Yielded(value) => {
+                assert_eq!(value.extract::<String>(py).unwrap(), "suspended")
+            }
+            GeneratorResult::Returned(_) => panic!("expected the coroutine to suspend"),
+        }
+        match coro.send(5.to_object(py).as_ref(py)).unwrap() {
+            GeneratorResult::Returned(value) => assert_eq!(value.extract::<i32>(py).unwrap(), 15),
+            GeneratorResult::Yielded(_) => panic!("expected the coroutine to return"),
+        }
+    }
+
+    #[test]
+    fn test_throw_and_close() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let coro: &PyCoroutine = module(py)
+            .call1("compute", (10,))
+            .unwrap()
+            .downcast()
+            .unwrap();
+        let none = py.None();
+        coro.send(none.as_ref(py)).unwrap();
+        match coro.throw(ValueError::py_err("cancelled")).unwrap() {
+            GeneratorResult::Returned(value) => assert_eq!(value.extract::<i32>(py).unwrap(), 9),
+            GeneratorResult::Yielded(_) => panic!("expected the coroutine to return"),
+        }
+
+        let coro: &PyCoroutine = module(py)
+            .call1("compute", (10,))
+            .unwrap()
+            .downcast()
+            .unwrap();
+        coro.send(none.as_ref(py)).unwrap();
+        coro.close().unwrap();
+    }
+
+    #[test]
+    fn test_awaitable() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let suspend = module(py).call0("Suspend").unwrap();
+        assert!(!suspend.is_coroutine());
+        assert!(suspend.is_awaitable().unwrap());
+        let iter = suspend.get_awaitable_iter().unwrap();
+        let value: String = iter.call_method0("__next__").unwrap().extract().unwrap();
+        assert_eq!(value, "suspended");
+
+        let number = 42.to_object(py);
+        let number: &PyAny = number.as_ref(py);
+        assert!(!number.is_coroutine());
+        assert!(!number.is_awaitable().unwrap());
+        let err = number.get_awaitable_iter().unwrap_err();
+        assert!(err.is_instance::<TypeError>(py));
+        assert!(number.downcast::<PyCoroutine>().is_err());
+
+        let generator_based = module(py).call0("generator_based").unwrap();
+        assert!(!generator_based.is_coroutine());
+        assert!(generator_based.is_awaitable().unwrap());
+        let iter = generator_based.get_awaitable_iter().unwrap();
+        assert!(iter.is(generator_based));
+        let value: i32 = iter.call_method0("__next__").unwrap().extract().unwrap();
+        assert_eq!(value, 1);
+
+        // A plain generator is not awaitable.
+        let generator = module(py).call0("generator").unwrap();
+        assert!(!generator.is_awaitable().unwrap());
+        let err = generator.get_awaitable_iter().unwrap_err();
+        assert!(err.is_instance::<TypeError>(py));
+
+        // `inspect.isawaitable` is true for virtual subclasses of `collections.abc.Awaitable`,
+        // which `await` rejects.
+        let registered = module(py).call0("Registered").unwrap();
+        assert!(!registered.is_awaitable().unwrap());
+        assert!(registered.get_awaitable_iter().is_err());
+
+        let coro = module(py).call1("compute", (1,)).unwrap();
+        assert!(coro.get_awaitable_iter().unwrap().is(coro));
+        coro.call_method0("close").unwrap();
+    }
+
+    #[cfg(Py_3_6)]
+    #[test]
+    fn test_async_generator() {
+        use super::PyAsyncGenerator;
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let agen: &PyAsyncGenerator = module(py)
+            .call1("countdown", (2,))
+            .unwrap()
+            .downcast()
+            .unwrap();
+        let none = py.None();
+
+        // Awaiting `asend` finishes immediately with the yielded value in `StopIteration`.
+        let next = |awaitable: &PyAny| -> Option<i32> {
+            assert!(awaitable.is_awaitable().unwrap());
+            let iter = awaitable.get_awaitable_iter().unwrap();
+            let err = iter.call_method1("send", (none.as_ref(py),)).unwrap_err();
+            if err.is_instance::<crate::exceptions::StopAsyncIteration>(py) {
+                return None;
+            }
+            assert!(err.is_instance::<crate::exceptions::StopIteration>(py));
+            Some(
+                err.to_object(py)
+                    .getattr(py, "value")
+                    .unwrap()
+                    .extract(py)
+                    .unwrap(),
+            )
+        };
+        assert_eq!(next(agen.asend(none.as_ref(py)).unwrap()), Some(2));
+        assert_eq!(next(agen.asend(none.as_ref(py)).unwrap()), Some(1));
+        assert_eq!(next(agen.asend(none.as_ref(py)).unwrap()), None);
+        agen.aclose().unwrap();
+    }
+}
@@ -17,6 +17,8 @@ use crate::ffi::{
     PyDateTime_DATE_GET_HOUR, PyDateTime_DATE_GET_MICROSECOND, PyDateTime_DATE_GET_MINUTE,
     PyDateTime_DATE_GET_SECOND,
 };
+#[cfg(not(PyPy))]
+use crate::ffi::{PyDateTime_DATE_GET_TZINFO, PyDateTime_TIME_GET_TZINFO};
 use crate::ffi::{
     PyDateTime_DELTA_GET_DAYS, PyDateTime_DELTA_GET_MICROSECONDS, PyDateTime_DELTA_GET_SECONDS,
 };
@@ -26,8 +28,12 @@ use crate::ffi::{
     PyDateTime_TIME_GET_SECOND,
 };
 use crate::object::PyObject;
-use crate::types::PyTuple;
-use crate::{AsPyPointer, PyAny, Python, ToPyObject};
+#[cfg(not(all(Py_3_7, not(PyPy))))]
+use crate::once_cell::GILOnceCell;
+use crate::types::{PyString, PyTuple};
+use crate::{AsPyPointer, PyAny, PyNativeType, Python, ToPyObject};
+#[cfg(not(all(Py_3_7, not(PyPy))))]
+use crate::{AsPyRef, Py};
 use std::os::raw::c_int;
 #[cfg(not(PyPy))]
 use std::ptr;
@@ -60,6 +66,8 @@ pub trait PyTimeAccess {
     fn get_microsecond(&self) -> u32;
     #[cfg(all(Py_3_6, not(PyPy)))]
     fn get_fold(&self) -> u8;
+    /// Returns the time zone of an aware object, or `None` if it is naive.
+    fn get_tzinfo(&self) -> Option<&PyTzInfo>;
 }
 
 /// Bindings around `datetime.date`
@@ -140,7 +148,7 @@ impl PyDateTime {
         minute: u8,
         second: u8,
         microsecond: u32,
-        tzinfo: Option<&PyObject>,
+        tzinfo: Option<&PyTzInfo>,
     ) -> PyResult<&'p PyDateTime> {
         unsafe {
             let ptr = (PyDateTimeAPI.DateTime_FromDateAndTime)(
@@ -158,6 +166,42 @@ impl PyDateTime {
         }
     }
 
+    #[cfg(Py_3_6)]
+    /// Alternate constructor that takes a `fold` argument
+    ///
+    /// `fold` disambiguates wall times which occur twice, e.g. when daylight saving time ends:
+    /// `false` selects the first occurrence, `true` the second.
+    ///
+    /// First available in Python 3.6.
+    pub fn new_with_fold<'p>(
+        py: Python<'p>,
+        year: i32,
+        month: u8,
+        day: u8,
+        hour: u8,
+        minute: u8,
+        second: u8,
+        microsecond: u32,
+        tzinfo: Option<&PyTzInfo>,
+        fold: bool,
+    ) -> PyResult<&'p PyDateTime> {
+        unsafe {
+            let ptr = (PyDateTimeAPI.DateTime_FromDateAndTimeAndFold)(
+                year,
+                c_int::from(month),
+                c_int::from(day),
+                c_int::from(hour),
+                c_int::from(minute),
+                c_int::from(second),
+                microsecond as c_int,
+                opt_to_pyobj(py, tzinfo),
+                fold as c_int,
+                PyDateTimeAPI.DateTimeType,
+            );
+            py.from_owned_ptr_or_err(ptr)
+        }
+    }
+
     /// Construct a `datetime` object from a POSIX timestamp
     ///
     /// This is equivalent to `datetime.datetime.from_timestamp`
@@ -191,6 +235,14 @@ impl PyDateTime {
             py.from_owned_ptr_or_err(ptr)
         }
     }
+
+    /// Returns the POSIX timestamp corresponding to this datetime.
+    ///
+    /// Naive datetimes are assumed to represent local time. This is equivalent to the Python
+    /// expression `self.timestamp()`.
+    pub fn timestamp(&self) -> PyResult<f64> {
+        self.call_method0("timestamp")?.extract()
+    }
 }
 
 impl PyDateAccess for PyDateTime {
@@ -228,6 +280,25 @@ impl PyTimeAccess for PyDateTime {
     fn get_fold(&self) -> u8 {
         unsafe { PyDateTime_DATE_GET_FOLD(self.as_ptr()) as u8 }
     }
+
+    #[cfg(not(PyPy))]
+    fn get_tzinfo(&self) -> Option<&PyTzInfo> {
+        unsafe {
+            if (*(self.as_ptr() as *mut ffi::PyDateTime_DateTime)).hastzinfo == 0 {
+                None
+            } else {
+                Some(
+                    self.py()
+                        .from_borrowed_ptr(PyDateTime_DATE_GET_TZINFO(self.as_ptr())),
+                )
+            }
+        }
+    }
+
+    #[cfg(PyPy)]
+    fn get_tzinfo(&self) -> Option<&PyTzInfo> {
+        tzinfo_attr(self)
+    }
 }
 
 /// Bindings for `datetime.time`
@@ -248,7 +319,7 @@ impl PyTime {
         minute: u8,
         second: u8,
         microsecond: u32,
-        tzinfo: Option<&PyObject>,
+        tzinfo: Option<&PyTzInfo>,
     ) -> PyResult<&'p PyTime> {
         unsafe {
             let ptr = (PyDateTimeAPI.Time_FromTime)(
@@ -273,7 +344,7 @@ impl PyTime {
         minute: u8,
         second: u8,
         microsecond: u32,
-        tzinfo: Option<&PyObject>,
+        tzinfo: Option<&PyTzInfo>,
         fold: bool,
     ) -> PyResult<&'p PyTime> {
         unsafe {
@@ -312,6 +383,25 @@ impl PyTimeAccess for PyTime {
     fn get_fold(&self) -> u8 {
         unsafe { PyDateTime_TIME_GET_FOLD(self.as_ptr()) as u8 }
     }
+
+    #[cfg(not(PyPy))]
+    fn get_tzinfo(&self) -> Option<&PyTzInfo> {
+        unsafe {
+            if (*(self.as_ptr() as *mut ffi::PyDateTime_Time)).hastzinfo == 0 {
+                None
+            } else {
+                Some(
+                    self.py()
+                        .from_borrowed_ptr(PyDateTime_TIME_GET_TZINFO(self.as_ptr())),
+                )
+            }
+        }
+    }
+
+    #[cfg(PyPy)]
+    fn get_tzinfo(&self) -> Option<&PyTzInfo> {
+        tzinfo_attr(self)
+    }
 }
 
 /// Bindings for `datetime.tzinfo`
@@ -327,6 +417,68 @@ pyobject_native_type!(
     PyTZInfo_Check
 );
 
+impl PyTzInfo {
+    /// Returns the `datetime.timezone.utc` singleton.
+    pub fn utc(py: Python) -> &PyTzInfo {
+        #[cfg(all(Py_3_7, not(PyPy)))]
+        unsafe {
+            py.from_borrowed_ptr(PyDateTimeAPI.TimeZone_UTC)
+        }
+        #[cfg(not(all(Py_3_7, not(PyPy))))]
+        {
+            static UTC: GILOnceCell<Py<PyTzInfo>> = GILOnceCell::new();
+            UTC.get_or_init(py, || {
+                let utc = py
+                    .import("datetime")
+                    .and_then(|datetime| datetime.get("timezone")?.getattr("utc"))
+                    .and_then(|utc| Ok(utc.downcast::<PyTzInfo>()?))
+                    .expect("failed to get datetime.timezone.utc");
+                utc.into()
+            })
+            .as_ref(py)
+        }
+    }
+
+    /// Creates a time zone with a fixed offset from UTC, and optionally a name.
+    ///
+    /// This is equivalent to the Python expression `datetime.timezone(offset, name)`. Raises
+    /// `ValueError` unless the offset is strictly between -24 and 24 hours.
+    pub fn fixed_offset<'p>(
+        py: Python<'p>,
+        offset: &PyDelta,
+        name: Option<&str>,
+    ) -> PyResult<&'p PyTzInfo> {
+        let name = name.map(|name| PyString::new(py, name));
+        #[cfg(all(Py_3_7, not(PyPy)))]
+        unsafe {
+            let name = name.map_or(ptr::null_mut(), AsPyPointer::as_ptr);
+            py.from_owned_ptr_or_err((PyDateTimeAPI.TimeZone_FromTimeZone)(offset.as_ptr(), name))
+        }
+        #[cfg(not(all(Py_3_7, not(PyPy))))]
+        {
+            let timezone = py.import("datetime")?.get("timezone")?;
+            let tz = match name {
+                Some(name) => timezone.call1((offset, name))?,
+                None => timezone.call1((offset,))?,
+            };
+            Ok(tz.downcast()?)
+        }
+    }
+
+    /// Returns the offset from UTC of the time zone at `dt`, or `None` if it is unknown.
+    ///
+    /// This is equivalent to the Python expression `self.utcoffset(dt)`. Fixed offset time
+    /// zones accept `None` for `dt`.
+    pub fn utcoffset(&self, dt: Option<&PyDateTime>) -> PyResult<Option<&PyDelta>> {
+        let offset = self.call_method1("utcoffset", (dt,))?;
+        if offset.is_none() {
+            Ok(None)
+        } else {
+            Ok(Some(offset.downcast()?))
+        }
+    }
+}
+
 /// Bindings for `datetime.timedelta`
 #[repr(transparent)]
 pub struct PyDelta(PyAny);
@@ -357,6 +509,58 @@ impl PyDelta {
             py.from_owned_ptr_or_err(ptr)
         }
     }
+
+    /// Creates a timedelta, normalizing the components like the Python constructor does.
+    ///
+    /// The result satisfies `0 <= seconds < 86400` and `0 <= microseconds < 1000000`, with any
+    /// excess (or deficit) carried into the next larger unit; e.g. `(0, -1, 0)` becomes
+    /// `(-1, 86399, 0)`. Raises `OverflowError` if the resulting number of days has a magnitude
+    /// larger than 999999999.
+    pub fn new_normalized<'p>(
+        py: Python<'p>,
+        days: i32,
+        seconds: i32,
+        microseconds: i32,
+    ) -> PyResult<&'p PyDelta> {
+        PyDelta::new(py, days, seconds, microseconds, true)
+    }
+
+    /// Returns the total duration of the timedelta in seconds.
+    ///
+    /// This is equivalent to the Python expression `self.total_seconds()`.
+    pub fn total_seconds(&self) -> f64 {
+        // Like Python, compute the exact number of microseconds first, which is exactly
+        // representable as `f64` for all but the most extreme deltas.
+        let microseconds = (i128::from(self.get_days()) * 86_400 + i128::from(self.get_seconds()))
+            * 1_000_000
+            + i128::from(self.get_microseconds());
+        microseconds as f64 / 1_000_000.0
+    }
+
+    /// Returns `self + other`, raising `OverflowError` if the result is out of range.
+    pub fn add(&self, other: &PyDelta) -> PyResult<&PyDelta> {
+        self.number_op(unsafe { ffi::PyNumber_Add(self.as_ptr(), other.as_ptr()) })
+    }
+
+    /// Returns `self - other`, raising `OverflowError` if the result is out of range.
+    pub fn sub(&self, other: &PyDelta) -> PyResult<&PyDelta> {
+        self.number_op(unsafe { ffi::PyNumber_Subtract(self.as_ptr(), other.as_ptr()) })
+    }
+
+    /// Returns `-self`, raising `OverflowError` if the result is out of range.
+    pub fn neg(&self) -> PyResult<&PyDelta> {
+        self.number_op(unsafe { ffi::PyNumber_Negative(self.as_ptr()) })
+    }
+
+    /// Returns `abs(self)`, raising `OverflowError` if the result is out of range.
+    pub fn abs(&self) -> PyResult<&PyDelta> {
+        self.number_op(unsafe { ffi::PyNumber_Absolute(self.as_ptr()) })
+    }
+
+    fn number_op(&self, result: *mut ffi::PyObject) -> PyResult<&PyDelta> {
+        let result: &PyAny = unsafe { self.py().from_owned_ptr_or_err(result)? };
+        Ok(result.downcast()?)
+    }
 }
 
 impl PyDeltaAccess for PyDelta {
@@ -373,11 +577,16 @@ impl PyDeltaAccess for PyDelta {
     }
 }
 
-// Utility function
-unsafe fn opt_to_pyobj(py: Python, opt: Option<&PyObject>) -> *mut ffi::PyObject {
+// Utility functions
+unsafe fn opt_to_pyobj(py: Python, opt: Option<&PyTzInfo>) -> *mut ffi::PyObject {
     // Convenience function for unpacking Options to either an Object or None
     match opt {
         Some(tzi) => tzi.as_ptr(),
         None => py.None().as_ptr(),
     }
 }
+
+#[cfg(PyPy)]
+fn tzinfo_attr(obj: &PyAny) -> Option<&PyTzInfo> {
+    obj.getattr("tzinfo").ok()?.downcast().ok()
+}
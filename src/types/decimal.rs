@@ -0,0 +1,328 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+use crate::err::{PyDowncastError, PyResult};
+use crate::instance::{AsPyRef, PyNativeType};
+use crate::once_cell::GILOnceCell;
+use crate::types::{PyAny, PyModule, PyType};
+use crate::{ffi, AsPyPointer, Py, PyObject, PyTryFrom, Python, ToPyObject};
+use std::fmt;
+
+/// Represents a Python `decimal.Decimal` object, a decimal floating point number with a
+/// precision given by the current decimal context.
+///
+/// Arithmetic on decimals is exact up to the precision of the current context, which can be
+/// set for a computation with [PyDecimal::with_precision]. Invalid operations and divisions by
+/// zero raise `decimal.InvalidOperation` and `decimal.DivisionByZero` under the default
+/// context. The `Display` implementation formats the value like `str(self)`.
+///
+/// Comparisons of values are available through the methods of [PyAny], e.g. [PyAny::lt]. As
+/// for other native types, `==` on `PyDecimal` references compares the identity of the objects,
+/// so use `PyAny::eq(a, b)` to compare their values.
+///
+/// # Example
+/// ```
+/// use pyo3::prelude::*;
+/// use pyo3::types::PyDecimal;
+///
+/// # fn main() -> PyResult<()> {
+/// let gil = Python::acquire_gil();
+/// let py = gil.python();
+/// let third = PyDecimal::with_precision(py, 30, || {
+///     PyDecimal::from_i64(py, 1)?.div(PyDecimal::from_i64(py, 3)?)
+/// })?;
+/// assert_eq!(third.to_string(), format!("0.{}", "3".repeat(30)));
+/// # Ok(())
+/// # }
+/// ```
+#[repr(transparent)]
+pub struct PyDecimal(PyAny);
+pyobject_native_type_named!(PyDecimal);
+pyobject_native_type_extract!(PyDecimal);
+
+impl PyDecimal {
+    /// Creates a decimal from its string representation.
+    ///
+    /// This is equivalent to the Python expression `decimal.Decimal(value)`. The value is
+    /// represented exactly, regardless of the precision of the current context. Returns
+    /// `decimal.InvalidOperation` if `value` is not a valid number.
+    pub fn new<'p>(py: Python<'p>, value: &str) -> PyResult<&'p PyDecimal> {
+        let decimal = get_decimal_type(py).call1((value,))?;
+        Ok(unsafe { <PyDecimal as PyTryFrom>::try_from_unchecked(decimal) })
+    }
+
+    /// Creates a decimal from a signed integer.
+    ///
+    /// This is equivalent to the Python expression `decimal.Decimal(value)`.
+    pub fn from_i64(py: Python, value: i64) -> PyResult<&PyDecimal> {
+        let decimal = get_decimal_type(py).call1((value,))?;
+        Ok(unsafe { <PyDecimal as PyTryFrom>::try_from_unchecked(decimal) })
+    }
+
+    /// Creates a decimal from an unsigned integer.
+    ///
+    /// This is equivalent to the Python expression `decimal.Decimal(value)`.
+    pub fn from_u64(py: Python, value: u64) -> PyResult<&PyDecimal> {
+        let decimal = get_decimal_type(py).call1((value,))?;
+        Ok(unsafe { <PyDecimal as PyTryFrom>::try_from_unchecked(decimal) })
+    }
+
+    /// Returns `self + other`, rounded to the precision of the current context.
+    pub fn add(&self, other: &PyDecimal) -> PyResult<&PyDecimal> {
+        self.number_op(unsafe { ffi::PyNumber_Add(self.as_ptr(), other.as_ptr()) })
+    }
+
+    /// Returns `self - other`, rounded to the precision of the current context.
+    pub fn sub(&self, other: &PyDecimal) -> PyResult<&PyDecimal> {
+        self.number_op(unsafe { ffi::PyNumber_Subtract(self.as_ptr(), other.as_ptr()) })
+    }
+
+    /// Returns `self * other`, rounded to the precision of the current context.
+    pub fn mul(&self, other: &PyDecimal) -> PyResult<&PyDecimal> {
+        self.number_op(unsafe { ffi::PyNumber_Multiply(self.as_ptr(), other.as_ptr()) })
+    }
+
+    /// Returns `self / other`, rounded to the precision of the current context.
+    ///
+    /// Returns `decimal.DivisionByZero` if `other` is zero, or `decimal.InvalidOperation` if
+    /// both are.
+    pub fn div(&self, other: &PyDecimal) -> PyResult<&PyDecimal> {
+        self.number_op(unsafe { ffi::PyNumber_TrueDivide(self.as_ptr(), other.as_ptr()) })
+    }
+
+    /// Returns `-self`.
+    pub fn neg(&self) -> PyResult<&PyDecimal> {
+        self.number_op(unsafe { ffi::PyNumber_Negative(self.as_ptr()) })
+    }
+
+    /// Rounds `self` to the exponent of `exp`, e.g. to two decimal places for an `exp` of
+    /// `0.01`.
+    ///
+    /// This is equivalent to the Python expression `self.quantize(exp)`, which rounds with
+    /// the rounding mode of the current context. Returns `decimal.InvalidOperation` if the
+    /// result would have more digits than the precision of the current context.
+    pub fn quantize(&self, exp: &PyDecimal) -> PyResult<&PyDecimal> {
+        Ok(self.call_method1("quantize", (exp,))?.downcast()?)
+    }
+
+    fn number_op(&self, result: *mut ffi::PyObject) -> PyResult<&PyDecimal> {
+        let result: &PyAny = unsafe { self.py().from_owned_ptr_or_err(result)? };
+        Ok(result.downcast()?)
+    }
+
+    /// Returns the precision of the current decimal context.
+    ///
+    /// This is equivalent to the Python expression `decimal.getcontext().prec`.
+    pub fn precision(py: Python) -> PyResult<u32> {
+        get_decimal_module(py)
+            .call0("getcontext")?
+            .getattr("prec")?
+            .extract()
+    }
+
+    /// Runs `f` with the precision of the decimal context of this thread set to `prec`
+    /// significant digits.
+    ///
+    /// `f` runs in a copy of the current context, as with `decimal.localcontext()` in Python,
+    /// and the current context is restored afterwards, even if `f` fails or panics. Returns a
+    /// `ValueError` if `prec` is zero.
+    pub fn with_precision<F, R>(py: Python, prec: u32, f: F) -> PyResult<R>
+    where
+        F: FnOnce() -> PyResult<R>,
+    {
+        let decimal = get_decimal_module(py);
+        let previous: PyObject = decimal.call0("getcontext")?.into();
+        let context = previous.call_method0(py, "copy")?;
+        context.as_ref(py).setattr("prec", prec)?;
+        decimal.call1("setcontext", (&context,))?;
+
+        struct RestoreContext<'p>(&'p PyModule, PyObject);
+
+        impl Drop for RestoreContext<'_> {
+            fn drop(&mut self) {
+                // Setting a context only fails for objects which are not contexts.
+                let _ = self.0.call1("setcontext", (&self.1,));
+            }
+        }
+
+        let _restore = RestoreContext(decimal, previous);
+        f()
+    }
+}
+
+impl ToPyObject for PyDecimal {
+    #[inline]
+    fn to_object(&self, py: Python) -> PyObject {
+        (**self).to_object(py)
+    }
+}
+
+impl fmt::Debug for PyDecimal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl fmt::Display for PyDecimal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+/// Returns the `decimal` module.
+fn get_decimal_module(py: Python) -> &PyModule {
+    static DECIMAL: GILOnceCell<Py<PyModule>> = GILOnceCell::new();
+
+    DECIMAL
+        .get_or_init(py, || {
+            py.import("decimal")
+                .expect("failed to import decimal")
+                .into()
+        })
+        .as_ref(py)
+}
+
+/// Returns `decimal.Decimal`.
+fn get_decimal_type(py: Python) -> &PyType {
+    static DECIMAL_TYPE: GILOnceCell<Py<PyType>> = GILOnceCell::new();
+
+    DECIMAL_TYPE
+        .get_or_init(py, || {
+            get_decimal_module(py)
+                .get("Decimal")
+                .and_then(|ty| Ok(ty.downcast::<PyType>()?.into()))
+                .expect("failed to import decimal.Decimal")
+        })
+        .as_ref(py)
+}
+
+impl<'v> PyTryFrom<'v> for PyDecimal {
+    fn try_from<V: Into<&'v PyAny>>(value: V) -> Result<&'v PyDecimal, PyDowncastError<'v>> {
+        let value = value.into();
+        let ty = get_decimal_type(value.py());
+        unsafe {
+            if ffi::PyObject_TypeCheck(value.as_ptr(), ty.as_type_ptr()) != 0 {
+                Ok(<PyDecimal as PyTryFrom>::try_from_unchecked(value))
+            } else {
+                Err(PyDowncastError::new(value, "Decimal"))
+            }
+        }
+    }
+
+    fn try_from_exact<V: Into<&'v PyAny>>(value: V) -> Result<&'v PyDecimal, PyDowncastError<'v>> {
+        let value = value.into();
+        if value.get_type().is(get_decimal_type(value.py())) {
+            unsafe { Ok(<PyDecimal as PyTryFrom>::try_from_unchecked(value)) }
+        } else {
+            Err(PyDowncastError::new(value, "Decimal"))
+        }
+    }
+
+    #[inline]
+    unsafe fn try_from_unchecked<V: Into<&'v PyAny>>(value: V) -> &'v PyDecimal {
+        let ptr = value.into() as *const _ as *const PyDecimal;
+        &*ptr
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PyDecimal;
+    use crate::exceptions::{ValueError, ZeroDivisionError};
+    use crate::{AsPyRef, PyAny, PyResult, Python, ToPyObject};
+
+    #[test]
+    fn test_exact_arithmetic() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let default_precision = PyDecimal::precision(py).unwrap();
+        PyDecimal::with_precision(py, 50, || -> PyResult<()> {
+            assert_eq!(50, PyDecimal::precision(py)?);
+            let a = PyDecimal::new(py, "12345678901234567890.12345678901234567890")?;
+            let b = PyDecimal::new(py, "0.0000000000000000000000000000000000000000000000001")?;
+
+            // 50 significant digits are kept exactly.
+            let sum = PyDecimal::new(py, "1")?.add(b)?;
+            assert_eq!(
+                "1.0000000000000000000000000000000000000000000000001",
+                sum.to_string()
+            );
+            assert!(PyAny::eq(sum.sub(b)?, PyDecimal::from_i64(py, 1)?)?);
+            assert_eq!(
+                "24691357802469135780.24691357802469135780",
+                a.mul(PyDecimal::from_u64(py, 2)?)?.to_string()
+            );
+            assert_eq!(
+                "-12345678901234567890.12345678901234567890",
+                a.neg()?.to_string()
+            );
+
+            // Beyond that, results are rounded.
+            let tenth_of_b = b.div(PyDecimal::from_i64(py, 10)?)?;
+            let one = PyDecimal::from_i64(py, 1)?;
+            assert!(PyAny::eq(one.add(tenth_of_b)?, one)?);
+            let seventh = PyDecimal::from_i64(py, 1)?.div(PyDecimal::from_i64(py, 7)?)?;
+            assert_eq!(
+                "0.14285714285714285714285714285714285714285714285714",
+                seventh.to_string()
+            );
+            assert!(seventh
+                .mul(PyDecimal::from_i64(py, 7)?)?
+                .lt(PyDecimal::from_i64(py, 1)?)?);
+            assert_eq!(
+                "0.14",
+                seventh.quantize(PyDecimal::new(py, "0.01")?)?.to_string()
+            );
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(default_precision, PyDecimal::precision(py).unwrap());
+    }
+
+    #[test]
+    fn test_signals() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let decimal = py.import("decimal").unwrap();
+        let invalid_operation = decimal.get("InvalidOperation").unwrap();
+        let division_by_zero = decimal.get("DivisionByZero").unwrap();
+        let zero = PyDecimal::from_i64(py, 0).unwrap();
+
+        let err = PyDecimal::new(py, "not a number").map(|_| ()).unwrap_err();
+        assert!(err.matches(py, invalid_operation));
+        let err = PyDecimal::from_i64(py, 1)
+            .unwrap()
+            .div(zero)
+            .map(|_| ())
+            .unwrap_err();
+        assert!(err.matches(py, division_by_zero));
+        assert!(err.is_instance::<ZeroDivisionError>(py));
+        let err = zero.div(zero).map(|_| ()).unwrap_err();
+        assert!(err.matches(py, invalid_operation));
+
+        // `quantize` fails if the result does not fit in the precision.
+        let err = PyDecimal::with_precision(py, 5, || {
+            PyDecimal::new(py, "123456")?.quantize(PyDecimal::new(py, "0.1")?)
+        })
+        .map(|_| ())
+        .unwrap_err();
+        assert!(err.matches(py, invalid_operation));
+
+        let precision = PyDecimal::precision(py).unwrap();
+        let err = PyDecimal::with_precision(py, 0, || Ok(()))
+            .map(|_| ())
+            .unwrap_err();
+        assert!(err.is_instance::<ValueError>(py));
+        assert_eq!(precision, PyDecimal::precision(py).unwrap());
+    }
+
+    #[test]
+    fn test_downcast() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let decimal = PyDecimal::new(py, "1.5").unwrap().to_object(py);
+        assert!(decimal.cast_as::<PyDecimal>(py).is_ok());
+        let float = 1.5.to_object(py);
+        assert!(float.cast_as::<PyDecimal>(py).is_err());
+        assert!(decimal.as_ref(py).eq(&float).unwrap());
+    }
+}
@@ -1,15 +1,16 @@
 // Copyright (c) 2017-present PyO3 Project and Contributors
 
-use crate::err::{self, PyErr, PyResult};
+use crate::err::{self, PyErr, PyResult, PyResultExt};
 use crate::instance::PyNativeType;
 use crate::object::PyObject;
 use crate::types::{PyAny, PyList};
-#[cfg(not(PyPy))]
-use crate::IntoPyPointer;
 use crate::{
-    ffi, AsPyPointer, FromPyObject, IntoPy, PyTryFrom, Python, ToBorrowedObject, ToPyObject,
+    exceptions, ffi, AsPyPointer, FromPyObject, IntoPy, PyTryFrom, Python, ToBorrowedObject,
+    ToPyObject,
 };
 use std::collections::{BTreeMap, HashMap};
+use std::marker::PhantomData;
+use std::os::raw::c_int;
 use std::ptr::NonNull;
 use std::{cmp, collections, hash};
 
@@ -30,28 +31,47 @@ impl PyDict {
         unsafe { py.from_owned_ptr::<PyDict>(ffi::PyDict_New()) }
     }
 
-    /// Creates a new dictionary from the sequence given.
+    /// Creates a new dictionary from the iterable given.
     ///
-    /// The sequence must consist of `(PyObject, PyObject)`. This is
+    /// The iterable must yield sequences of length 2, each holding a key and a value. This is
     /// equivalent to `dict([("a", 1), ("b", 2)])`.
     ///
-    /// Returns an error on invalid input. In the case of key collisions,
-    /// this keeps the last entry seen.
+    /// Returns an error on invalid input, with the same message as the `dict` constructor. In the
+    /// case of key collisions, this keeps the last entry seen.
     #[cfg(not(PyPy))]
-    pub fn from_sequence(py: Python, seq: PyObject) -> PyResult<&PyDict> {
+    pub fn from_sequence<'py>(py: Python<'py>, seq: &PyAny) -> PyResult<&'py PyDict> {
+        let dict = PyDict::new(py);
         unsafe {
-            let dict = py.from_owned_ptr::<PyDict>(ffi::PyDict_New());
-            match ffi::PyDict_MergeFromSeq2(dict.into_ptr(), seq.into_ptr(), 1i32) {
-                0 => Ok(dict),
-                -1 => Err(PyErr::fetch(py)),
-                _ => unreachable!(),
-            }
+            err::error_on_minusone(
+                py,
+                ffi::PyDict_MergeFromSeq2(dict.as_ptr(), seq.as_ptr(), 1),
+            )?;
         }
+        Ok(dict)
+    }
+
+    /// Creates a new dictionary from an iterator of key-value pairs.
+    ///
+    /// In the case of key collisions, this keeps the last entry seen. If an item cannot be
+    /// inserted, the error is wrapped in an exception of the same type reporting the index of
+    /// the failing pair, with the original error as its `__cause__`.
+    pub fn from_pairs<T, I>(py: Python, pairs: I) -> PyResult<&PyDict>
+    where
+        T: PyDictItem,
+        I: IntoIterator<Item = T>,
+    {
+        let dict = PyDict::new(py);
+        for (index, item) in pairs.into_iter().enumerate() {
+            dict.set_item(item.key(), item.value())
+                .with_context(py, || format!("cannot insert dictionary item #{}", index))?;
+        }
+        Ok(dict)
     }
 
     /// Returns a new dictionary that contains the same key-value pairs as self.
     ///
-    /// This is equivalent to the Python expression `dict(self)`.
+    /// This is a shallow copy: the keys and values themselves are not copied.
+    /// This is equivalent to the Python expression `self.copy()`.
     pub fn copy(&self) -> PyResult<&PyDict> {
         unsafe {
             self.py()
@@ -95,6 +115,8 @@ impl PyDict {
     /// Gets an item from the dictionary.
     ///
     /// Returns `None` if the item is not present, or if an error occurs.
+    /// Use [PyDict::get_item_with_error] to distinguish errors raised while looking up the key
+    /// (e.g. by an unhashable key) from missing items.
     ///
     /// To get a `KeyError` for non-existing keys, use `PyAny::get_item`.
     pub fn get_item<K>(&self, key: K) -> Option<&PyAny>
@@ -111,6 +133,28 @@ impl PyDict {
         })
     }
 
+    /// Gets an item from the dictionary.
+    ///
+    /// Returns `Ok(None)` if the item is not present, and an error if one is raised while
+    /// looking up the key, e.g. by the key's `__hash__` or `__eq__` methods.
+    pub fn get_item_with_error<K>(&self, key: K) -> PyResult<Option<&PyAny>>
+    where
+        K: ToBorrowedObject,
+    {
+        key.with_borrowed_ptr(self.py(), |key| unsafe {
+            let ptr = ffi::PyDict_GetItemWithError(self.as_ptr(), key);
+            if !ptr.is_null() {
+                // PyDict_GetItemWithError returns a borrowed ptr, must make it owned for safety.
+                ffi::Py_INCREF(ptr);
+                Ok(Some(self.py().from_owned_ptr(ptr)))
+            } else if PyErr::occurred(self.py()) {
+                Err(PyErr::fetch(self.py()))
+            } else {
+                Ok(None)
+            }
+        })
+    }
+
     /// Sets an item value.
     ///
     /// This is equivalent to the Python statement `self[key] = value`.
@@ -138,6 +182,53 @@ impl PyDict {
         })
     }
 
+    /// Updates the dictionary with the key-value pairs from `other`, overwriting existing keys.
+    ///
+    /// `other` may be a mapping or an iterable of key-value pairs.
+    /// This is equivalent to the Python expression `self.update(other)`.
+    pub fn update(&self, other: &PyAny) -> PyResult<()> {
+        self.merge(other, true)
+    }
+
+    /// Updates the dictionary with the key-value pairs from `other`, keeping the values of keys
+    /// which are already present.
+    ///
+    /// `other` may be a mapping or an iterable of key-value pairs.
+    pub fn update_if_missing(&self, other: &PyAny) -> PyResult<()> {
+        self.merge(other, false)
+    }
+
+    fn merge(&self, other: &PyAny, override_: bool) -> PyResult<()> {
+        // Like `dict.update`, treat anything with a `keys` method as a mapping.
+        if other.hasattr("keys")? {
+            return unsafe {
+                err::error_on_minusone(
+                    self.py(),
+                    ffi::PyDict_Merge(self.as_ptr(), other.as_ptr(), override_ as c_int),
+                )
+            };
+        }
+
+        #[cfg(not(PyPy))]
+        unsafe {
+            err::error_on_minusone(
+                self.py(),
+                ffi::PyDict_MergeFromSeq2(self.as_ptr(), other.as_ptr(), override_ as c_int),
+            )
+        }
+
+        #[cfg(PyPy)]
+        {
+            for item in other.iter()? {
+                let (key, value): (&PyAny, &PyAny) = item?.extract()?;
+                if override_ || !self.contains(key)? {
+                    self.set_item(key, value)?;
+                }
+            }
+            Ok(())
+        }
+    }
+
     /// Returns a list of dict keys.
     ///
     /// This is equivalent to the Python expression `list(dict.keys())`.
@@ -170,12 +261,54 @@ impl PyDict {
 
     /// Returns an iterator of `(key, value)` pairs in this dictionary.
     ///
-    /// Note that it's unsafe to use when the dictionary might be changed by
-    /// other code.
+    /// The dictionary must not be changed during iteration. As in Python, the iterator detects
+    /// changes of its size, and panics if it was changed; other changes, such as replacing a
+    /// value, may cause pairs to be skipped or yielded twice.
     pub fn iter(&self) -> PyDictIterator {
         PyDictIterator {
             dict: self.as_ref(),
             pos: 0,
+            len: unsafe { ffi::PyDict_Size(self.as_ptr()) },
+        }
+    }
+
+    /// Returns an iterator extracting the `(key, value)` pairs of this dictionary as `(K, V)`.
+    ///
+    /// Unlike extracting the whole dictionary as a `HashMap`, the pairs are extracted one at a
+    /// time, as they are consumed. If a key or value cannot be extracted, the iterator yields an
+    /// error naming the key, with the extraction error as its `__cause__`, and then stops.
+    ///
+    /// The dictionary must not be changed during iteration. As in Python, if its size changes,
+    /// the iterator yields a `RuntimeError` and stops; other changes, such as replacing a value,
+    /// may cause pairs to be skipped or yielded twice.
+    ///
+    /// # Example
+    /// ```
+    /// use pyo3::prelude::*;
+    /// use pyo3::types::PyDict;
+    ///
+    /// let gil = Python::acquire_gil();
+    /// let py = gil.python();
+    /// let dict: &PyDict = py.eval("{'a': 1, 'b': 20, 'c': 300}", None, None)?.downcast()?;
+    /// let mut large = Vec::new();
+    /// for pair in dict.iter_extract::<&str, i32>() {
+    ///     let (key, value) = pair?;
+    ///     if value >= 10 {
+    ///         large.push(key);
+    ///     }
+    /// }
+    /// assert_eq!(vec!["b", "c"], large);
+    /// # Ok::<(), PyErr>(())
+    /// ```
+    pub fn iter_extract<'py, K, V>(&'py self) -> impl Iterator<Item = PyResult<(K, V)>> + 'py
+    where
+        K: FromPyObject<'py> + 'py,
+        V: FromPyObject<'py> + 'py,
+    {
+        PyDictExtractIterator {
+            iter: self.iter(),
+            done: false,
+            marker: PhantomData,
         }
     }
 }
@@ -183,13 +316,26 @@ impl PyDict {
 pub struct PyDictIterator<'py> {
     dict: &'py PyAny,
     pos: isize,
+    /// The size of the dictionary when the iteration started.
+    len: ffi::Py_ssize_t,
 }
 
-impl<'py> Iterator for PyDictIterator<'py> {
-    type Item = (&'py PyAny, &'py PyAny);
+impl<'py> PyDictIterator<'py> {
+    /// Returns a `RuntimeError` if the size of the dictionary changed since the iteration
+    /// started.
+    fn check_size(&self) -> PyResult<()> {
+        if unsafe { ffi::PyDict_Size(self.dict.as_ptr()) } != self.len {
+            return Err(PyErr::new_with_gil::<exceptions::RuntimeError, _>(
+                self.dict.py(),
+                "dictionary changed size during iteration",
+            ));
+        }
+        Ok(())
+    }
 
+    /// Returns the next pair, without checking whether the dictionary changed.
     #[inline]
-    fn next(&mut self) -> Option<Self::Item> {
+    fn next_unchecked(&mut self) -> Option<(&'py PyAny, &'py PyAny)> {
         unsafe {
             let mut key: *mut ffi::PyObject = std::ptr::null_mut();
             let mut value: *mut ffi::PyObject = std::ptr::null_mut();
@@ -206,6 +352,54 @@ impl<'py> Iterator for PyDictIterator<'py> {
     }
 }
 
+impl<'py> Iterator for PyDictIterator<'py> {
+    type Item = (&'py PyAny, &'py PyAny);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.check_size().is_err() {
+            panic!("dictionary changed size during iteration");
+        }
+        self.next_unchecked()
+    }
+}
+
+struct PyDictExtractIterator<'py, K, V> {
+    iter: PyDictIterator<'py>,
+    done: bool,
+    marker: PhantomData<(K, V)>,
+}
+
+impl<'py, K, V> Iterator for PyDictExtractIterator<'py, K, V>
+where
+    K: FromPyObject<'py>,
+    V: FromPyObject<'py>,
+{
+    type Item = PyResult<(K, V)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let py = self.iter.dict.py();
+        if let Err(err) = self.iter.check_size() {
+            self.done = true;
+            return Some(Err(err));
+        }
+        let (key, value) = self.iter.next_unchecked()?;
+        let pair = K::extract(key)
+            .and_then(|key| Ok((key, V::extract(value)?)))
+            .with_context(py, || {
+                format!(
+                    "cannot extract dictionary item with key {}",
+                    key.repr_safe(Some(KEY_REPR_MAX_CHARS))
+                )
+            });
+        self.done = pair.is_err();
+        Some(pair)
+    }
+}
+
 impl<'a> std::iter::IntoIterator for &'a PyDict {
     type Item = (&'a PyAny, &'a PyAny);
     type IntoIter = PyDictIterator<'a>;
@@ -226,9 +420,13 @@ where
     }
 }
 
+/// The keys are inserted in their sorted order, which is the iteration order of the `dict`.
+///
+/// If distinct keys convert to equal Python objects, the `dict` keeps the value of the last one,
+/// like the Python expression `dict(items)`.
 impl<K, V> ToPyObject for collections::BTreeMap<K, V>
 where
-    K: cmp::Eq + ToPyObject,
+    K: ToPyObject,
     V: ToPyObject,
 {
     fn to_object(&self, py: Python) -> PyObject {
@@ -250,9 +448,11 @@ where
     }
 }
 
+/// Like the `ToPyObject` conversion, the keys are inserted in their sorted order, and the value
+/// of the last key is kept if distinct keys convert to equal Python objects.
 impl<K, V> IntoPy<PyObject> for collections::BTreeMap<K, V>
 where
-    K: cmp::Eq + IntoPy<PyObject>,
+    K: IntoPy<PyObject>,
     V: IntoPy<PyObject>,
 {
     fn into_py(self, py: Python) -> PyObject {
@@ -331,41 +531,260 @@ where
     S: hash::BuildHasher + Default,
 {
     fn extract(ob: &'source PyAny) -> Result<Self, PyErr> {
-        let dict = <PyDict as PyTryFrom>::try_from(ob)?;
         let mut ret = HashMap::default();
-        for (k, v) in dict.iter() {
-            ret.insert(K::extract(k)?, V::extract(v)?);
-        }
+        extract_items::<Self, K, V>(ob, |k, v| {
+            ret.insert(k, v);
+        })?;
         Ok(ret)
     }
 }
 
+/// If distinct keys of the `dict` extract to equal keys, the value of the last one in the
+/// iteration order of the `dict` is kept.
 impl<'source, K, V> FromPyObject<'source> for BTreeMap<K, V>
 where
     K: FromPyObject<'source> + cmp::Ord,
     V: FromPyObject<'source>,
 {
     fn extract(ob: &'source PyAny) -> Result<Self, PyErr> {
-        let dict = <PyDict as PyTryFrom>::try_from(ob)?;
         let mut ret = BTreeMap::new();
-        for (k, v) in dict.iter() {
-            ret.insert(K::extract(k)?, V::extract(v)?);
-        }
+        extract_items::<Self, K, V>(ob, |k, v| {
+            ret.insert(k, v);
+        })?;
         Ok(ret)
     }
 }
 
+/// Extracts the key-value pairs of the dictionary `ob` as a value of type `M`, passing each of
+/// them to `insert`.
+///
+/// The pairs of a `dict`, or of a subclass which inherits its `keys` and `items` methods, are
+/// read directly from the hash table. Other subclasses, such as `OrderedDict`, may present their
+/// items differently, so their `items` method is called, or their `keys` method and `__getitem__`
+/// if only `keys` is overridden.
+fn extract_items<'s, M, K, V>(ob: &'s PyAny, mut insert: impl FnMut(K, V)) -> PyResult<()>
+where
+    K: FromPyObject<'s>,
+    V: FromPyObject<'s>,
+{
+    let dict = <PyDict as PyTryFrom>::try_from(ob)?;
+    if unsafe { ffi::PyDict_CheckExact(ob.as_ptr()) } == 0 {
+        let ty = ob.get_type();
+        let base = ob.py().get_type::<PyDict>();
+        if !ty.getattr("items")?.is(base.getattr("items")?) {
+            for item in ob.call_method0("items")?.iter()? {
+                let (k, v): (&PyAny, &PyAny) = item?.extract()?;
+                let (k, v) = extract_pair::<M, K, V>(k, v)?;
+                insert(k, v);
+            }
+            return Ok(());
+        }
+        if !ty.getattr("keys")?.is(base.getattr("keys")?) {
+            for k in ob.call_method0("keys")?.iter()? {
+                let k = k?;
+                let (k, v) = extract_pair::<M, K, V>(k, ob.get_item(k)?)?;
+                insert(k, v);
+            }
+            return Ok(());
+        }
+    }
+
+    // Extracting a key or a value may run Python code which changes the dictionary.
+    let mut iter = dict.iter();
+    loop {
+        iter.check_size()?;
+        match iter.next_unchecked() {
+            Some((k, v)) => {
+                let (k, v) = extract_pair::<M, K, V>(k, v)?;
+                insert(k, v);
+            }
+            None => return Ok(()),
+        }
+    }
+}
+
+/// The maximum length of the representation of a key in error messages.
+const KEY_REPR_MAX_CHARS: usize = 100;
+
+/// Extracts a key-value pair of a dictionary being extracted as a value of type `M`.
+fn extract_pair<'s, M, K, V>(k: &'s PyAny, v: &'s PyAny) -> PyResult<(K, V)>
+where
+    K: FromPyObject<'s>,
+    V: FromPyObject<'s>,
+{
+    let key_repr = || k.repr_safe(Some(KEY_REPR_MAX_CHARS));
+    let key = K::extract(k)
+        .map_err(|e| e.in_container::<M>(k.py(), || format!("key {} itself", key_repr())))?;
+    let value =
+        V::extract(v).map_err(|e| e.in_container::<M>(k.py(), || format!("key {}", key_repr())))?;
+    Ok((key, value))
+}
+
 #[cfg(test)]
 mod test {
     use crate::conversion::IntoPy;
+    use crate::exceptions::{RuntimeError, TypeError, ValueError};
     use crate::instance::AsPyRef;
     use crate::types::dict::IntoPyDict;
     use crate::types::{PyDict, PyList, PyTuple};
-    use crate::PyObject;
     use crate::Python;
-    use crate::{PyTryFrom, ToPyObject};
+    use crate::{AsPyPointer, PyTryFrom, ToPyObject};
+    use crate::{PyErr, PyObject, PyResult};
     use std::collections::{BTreeMap, HashMap};
 
+    #[test]
+    fn test_iter_extract() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let dict = [(1, 10), (2, 25), (3, 30), (4, 45)].into_py_dict(py);
+        let odd: Vec<(u32, u32)> = dict
+            .iter_extract::<u32, u32>()
+            .filter(|pair| pair.as_ref().map_or(true, |(_, v)| v % 2 == 1))
+            .collect::<PyResult<_>>()
+            .unwrap();
+        assert_eq!(vec![(2, 25), (4, 45)], odd);
+
+        dict.set_item(5, "fifty").unwrap();
+        let pairs: Vec<_> = dict.iter_extract::<u32, u32>().collect();
+        assert_eq!(5, pairs.len());
+        let err = pairs.into_iter().last().unwrap().unwrap_err();
+        assert!(err.is_instance::<TypeError>(py));
+        assert_eq!(
+            "cannot extract dictionary item with key 5",
+            err.to_object(py).as_ref(py).to_string()
+        );
+    }
+
+    #[test]
+    fn test_extract_error_key_repr() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let dict = py
+            .eval(
+                "{type('BadRepr', (), {'__repr__': lambda self: 1 / 0})(): 1, 'y' * 1000: 2}",
+                None,
+                None,
+            )
+            .unwrap();
+
+        // The keys are represented in the messages, without raising another exception.
+        let err = dict.extract::<HashMap<String, i32>>().unwrap_err();
+        assert!(err.is_instance::<TypeError>(py));
+        assert!(err
+            .to_object(py)
+            .as_ref(py)
+            .to_string()
+            .contains(" at key <BadRepr object at 0x"));
+
+        let dict = dict.downcast::<PyDict>().unwrap();
+        dict.del_item(dict.keys().get_item(0)).unwrap();
+        let err = dict
+            .iter_extract::<String, String>()
+            .next()
+            .unwrap()
+            .unwrap_err();
+        let message = err.to_object(py).as_ref(py).to_string();
+        assert_eq!(
+            message,
+            format!(
+                "cannot extract dictionary item with key '{}...",
+                "y".repeat(99)
+            )
+        );
+    }
+
+    #[test]
+    fn test_iter_extract_mutation() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let dict = [("a", 1), ("b", 2), ("c", 3)].into_py_dict(py);
+        let mut iter = dict.iter_extract::<&str, i32>();
+        assert_eq!(("a", 1), iter.next().unwrap().unwrap());
+        dict.del_item("c").unwrap();
+        let err = iter.next().unwrap().unwrap_err();
+        assert!(err.is_instance::<RuntimeError>(py));
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "dictionary changed size during iteration")]
+    fn test_iter_mutation() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let dict = [("a", 1), ("b", 2)].into_py_dict(py);
+        for (key, _) in dict.iter() {
+            dict.del_item(key).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_extract_mutation() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let globals = PyDict::new(py);
+        py.run(
+            r#"
+class Grow:
+    def __index__(self):
+        d['new'] = 0
+        return 1
+
+d = {'a': Grow(), 'b': 2}
+"#,
+            Some(globals),
+            None,
+        )
+        .unwrap();
+        let dict = globals.get_item("d").unwrap();
+        let err = dict.extract::<HashMap<&str, i32>>().unwrap_err();
+        assert!(err.is_instance::<RuntimeError>(py));
+    }
+
+    #[test]
+    fn test_extract_subclass() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let locals = PyDict::new(py);
+        py.run(
+            r#"
+from collections import OrderedDict
+
+class Doubled(dict):
+    def items(self):
+        return [(k, v * 2) for k, v in super().items()]
+
+class Upper(dict):
+    def keys(self):
+        return [k.upper() for k in super().keys()]
+
+    def __getitem__(self, key):
+        return super().__getitem__(key.lower())
+
+class Plain(dict):
+    pass
+
+doubled = Doubled(a=1, b=2)
+upper = Upper(a=1)
+plain = Plain(a=1)
+ordered = OrderedDict(a=1, b=2)
+"#,
+            None,
+            Some(locals),
+        )
+        .unwrap();
+        let extract =
+            |name| -> BTreeMap<String, i32> { locals.get_item(name).unwrap().extract().unwrap() };
+        let pairs = |pairs: &[(&str, i32)]| -> BTreeMap<String, i32> {
+            pairs.iter().map(|&(k, v)| (k.to_string(), v)).collect()
+        };
+        assert_eq!(pairs(&[("a", 2), ("b", 4)]), extract("doubled"));
+        assert_eq!(pairs(&[("A", 1)]), extract("upper"));
+        assert_eq!(pairs(&[("a", 1)]), extract("plain"));
+        assert_eq!(pairs(&[("a", 1), ("b", 2)]), extract("ordered"));
+        let map: HashMap<String, i32> = locals.get_item("doubled").unwrap().extract().unwrap();
+        assert_eq!(Some(&4), map.get("b"));
+    }
+
     #[test]
     fn test_new() {
         let gil = Python::acquire_gil();
@@ -380,11 +799,12 @@ mod test {
     }
 
     #[test]
+    #[cfg(not(PyPy))]
     fn test_from_sequence() {
         let gil = Python::acquire_gil();
         let py = gil.python();
         let items = PyList::new(py, &vec![("a", 1), ("b", 2)]);
-        let dict = PyDict::from_sequence(py, items.to_object(py)).unwrap();
+        let dict = PyDict::from_sequence(py, items).unwrap();
         assert_eq!(1, dict.get_item("a").unwrap().extract::<i32>().unwrap());
         assert_eq!(2, dict.get_item("b").unwrap().extract::<i32>().unwrap());
         let map: HashMap<&str, i32> = [("a", 1), ("b", 2)].iter().cloned().collect();
@@ -394,11 +814,176 @@ mod test {
     }
 
     #[test]
+    #[cfg(not(PyPy))]
+    fn test_from_sequence_generator() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let items = py
+            .eval("((str(i), i) for i in range(3))", None, None)
+            .unwrap();
+        let dict = PyDict::from_sequence(py, items).unwrap();
+        let map: HashMap<String, i32> = (0..3).map(|i| (i.to_string(), i)).collect();
+        assert_eq!(map, dict.extract().unwrap());
+    }
+
+    #[test]
+    #[cfg(not(PyPy))]
     fn test_from_sequence_err() {
         let gil = Python::acquire_gil();
         let py = gil.python();
-        let items = PyList::new(py, &vec!["a", "b"]);
-        assert!(PyDict::from_sequence(py, items.to_object(py)).is_err());
+        let items = PyList::new(py, &vec!["ab", "c"]);
+        let err = PyDict::from_sequence(py, items).unwrap_err();
+        assert!(err.is_instance::<ValueError>(py));
+        assert_eq!(
+            err.to_object(py)
+                .as_ref(py)
+                .str()
+                .unwrap()
+                .to_string()
+                .unwrap(),
+            "dictionary update sequence element #1 has length 1; 2 is required"
+        );
+
+        let items = PyList::new(py, &vec![1]);
+        let err = PyDict::from_sequence(py, items).unwrap_err();
+        assert!(err.is_instance::<TypeError>(py));
+        assert_eq!(
+            err.to_object(py)
+                .as_ref(py)
+                .str()
+                .unwrap()
+                .to_string()
+                .unwrap(),
+            "cannot convert dictionary update sequence element #0 to a sequence"
+        );
+    }
+
+    #[test]
+    fn test_get_item_with_error() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let dict = [(7, 32)].into_py_dict(py);
+        assert_eq!(
+            32,
+            dict.get_item_with_error(7i32)
+                .unwrap()
+                .unwrap()
+                .extract::<i32>()
+                .unwrap()
+        );
+        assert!(dict.get_item_with_error(8i32).unwrap().is_none());
+
+        // Unhashable keys raise a TypeError
+        let unhashable = PyList::empty(py);
+        let err = dict.get_item_with_error(unhashable).unwrap_err();
+        assert!(err.is_instance::<TypeError>(py));
+        assert!(dict.get_item(unhashable).is_none());
+        assert!(dict.contains(unhashable).is_err());
+        assert!(dict.set_item(unhashable, 1).is_err());
+        assert!(dict.del_item(unhashable).is_err());
+    }
+
+    #[test]
+    fn test_get_item_with_error_raising_eq() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let locals = PyDict::new(py);
+        py.run(
+            r#"
+class Key:
+    def __hash__(self):
+        return 0
+    def __eq__(self, other):
+        raise ValueError("cannot compare")
+a = Key()
+b = Key()
+d = {a: 1}
+"#,
+            None,
+            Some(locals),
+        )
+        .unwrap();
+        let dict: &PyDict = locals.get_item("d").unwrap().downcast().unwrap();
+        let a = locals.get_item("a").unwrap();
+        let b = locals.get_item("b").unwrap();
+
+        // Identical keys do not need to be compared
+        assert!(dict.get_item_with_error(a).unwrap().is_some());
+
+        let err = dict.get_item_with_error(b).unwrap_err();
+        assert!(err.is_instance::<ValueError>(py));
+        assert!(dict.contains(b).unwrap_err().is_instance::<ValueError>(py));
+        assert!(dict.del_item(b).unwrap_err().is_instance::<ValueError>(py));
+        assert!(dict
+            .set_item(b, 2)
+            .unwrap_err()
+            .is_instance::<ValueError>(py));
+        assert_eq!(dict.len(), 1);
+    }
+
+    #[test]
+    fn test_from_pairs() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let dict = PyDict::from_pairs(py, vec![("a", 1), ("b", 2), ("a", 3)]).unwrap();
+        let map: HashMap<&str, i32> = [("a", 3), ("b", 2)].iter().cloned().collect();
+        assert_eq!(map, dict.extract().unwrap());
+
+        let unhashable = PyList::empty(py);
+        let err = PyDict::from_pairs(py, vec![(py.None(), 1), (unhashable.into(), 2)]).unwrap_err();
+        assert!(err.is_instance::<TypeError>(py));
+        let value = err.to_object(py);
+        let value = value.as_ref(py);
+        assert_eq!(
+            value.str().unwrap().to_string().unwrap(),
+            "cannot insert dictionary item #1"
+        );
+        let cause = PyErr::from_instance(value.getattr("__cause__").unwrap());
+        assert!(cause.is_instance::<TypeError>(py));
+    }
+
+    #[test]
+    fn test_update() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let dict = [("a", 1), ("b", 2)].into_py_dict(py);
+        let other = [("b", 3), ("c", 4)].into_py_dict(py);
+        dict.update(other).unwrap();
+        let map: HashMap<&str, i32> = [("a", 1), ("b", 3), ("c", 4)].iter().cloned().collect();
+        assert_eq!(map, dict.extract().unwrap());
+
+        let dict = [("a", 1), ("b", 2)].into_py_dict(py);
+        other.update(dict).unwrap();
+        let map: HashMap<&str, i32> = [("a", 1), ("b", 2), ("c", 4)].iter().cloned().collect();
+        assert_eq!(map, other.extract().unwrap());
+
+        let items = PyList::new(py, &[("b", 5), ("d", 6)]);
+        dict.update(items).unwrap();
+        let map: HashMap<&str, i32> = [("a", 1), ("b", 5), ("d", 6)].iter().cloned().collect();
+        assert_eq!(map, dict.extract().unwrap());
+
+        assert!(dict.update(PyList::new(py, &[1, 2])).is_err());
+    }
+
+    #[test]
+    fn test_update_if_missing() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let dict = [("a", 1), ("b", 2)].into_py_dict(py);
+        let other = [("b", 3), ("c", 4)].into_py_dict(py);
+        dict.update_if_missing(other).unwrap();
+        let map: HashMap<&str, i32> = [("a", 1), ("b", 2), ("c", 4)].iter().cloned().collect();
+        assert_eq!(map, dict.extract().unwrap());
+
+        let dict = [("a", 1), ("b", 2)].into_py_dict(py);
+        other.update_if_missing(dict).unwrap();
+        let map: HashMap<&str, i32> = [("a", 1), ("b", 3), ("c", 4)].iter().cloned().collect();
+        assert_eq!(map, other.extract().unwrap());
+
+        let items = PyList::new(py, &[("b", 5), ("d", 6)]);
+        dict.update_if_missing(items).unwrap();
+        let map: HashMap<&str, i32> = [("a", 1), ("b", 2), ("d", 6)].iter().cloned().collect();
+        assert_eq!(map, dict.extract().unwrap());
     }
 
     #[test]
@@ -410,6 +995,17 @@ mod test {
         let ndict = dict.copy().unwrap();
         assert_eq!(32, ndict.get_item(7i32).unwrap().extract::<i32>().unwrap());
         assert_eq!(None, ndict.get_item(8i32));
+
+        ndict.set_item(8i32, 1).unwrap();
+        assert_eq!(None, dict.get_item(8i32));
+
+        // The copy is shallow
+        dict.set_item(9i32, PyList::empty(py)).unwrap();
+        let ndict = dict.copy().unwrap();
+        assert_eq!(
+            ndict.get_item(9i32).unwrap().as_ptr(),
+            dict.get_item(9i32).unwrap().as_ptr()
+        );
     }
 
     #[test]
@@ -737,4 +1333,70 @@ mod test {
         assert_eq!(py_map.len(), 3);
         assert_eq!(py_map.get_item("b").unwrap().extract::<i32>().unwrap(), 2);
     }
+
+    /// A key which is only `Ord`, and converts to and from lowercase Python strings.
+    #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+    struct Lowercase(String);
+
+    impl ToPyObject for Lowercase {
+        fn to_object(&self, py: Python) -> PyObject {
+            self.0.to_lowercase().to_object(py)
+        }
+    }
+
+    impl<'a> crate::FromPyObject<'a> for Lowercase {
+        fn extract(ob: &'a crate::PyAny) -> PyResult<Self> {
+            Ok(Lowercase(ob.extract::<String>()?.to_lowercase()))
+        }
+    }
+
+    #[test]
+    fn test_btreemap_to_python_sorted() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let map: BTreeMap<i32, &str> = vec![(3, "c"), (1, "a"), (2, "b")].into_iter().collect();
+        let keys = |dict: PyObject| -> Vec<i32> {
+            dict.as_ref(py)
+                .call_method0("keys")
+                .unwrap()
+                .iter()
+                .unwrap()
+                .map(|key| key.unwrap().extract().unwrap())
+                .collect()
+        };
+        assert_eq!(keys(map.to_object(py)), [1, 2, 3]);
+        assert_eq!(keys(map.into_py(py)), [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_btreemap_colliding_keys() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        // "A" < "a", so the value of "a" is inserted last.
+        let map: BTreeMap<Lowercase, i32> = vec![
+            (Lowercase("a".to_string()), 2),
+            (Lowercase("A".to_string()), 1),
+        ]
+        .into_iter()
+        .collect();
+        let dict = map.to_object(py);
+        let dict = <PyDict as PyTryFrom>::try_from(dict.as_ref(py)).unwrap();
+        assert_eq!(dict.len(), 1);
+        assert_eq!(dict.get_item("a").unwrap().extract::<i32>().unwrap(), 2);
+
+        // The value of the last key in the iteration order of the dict is kept.
+        let dict = [("A", 1), ("a", 2), ("B", 3)].into_py_dict(py);
+        let map: BTreeMap<Lowercase, i32> = dict.extract().unwrap();
+        let expected: BTreeMap<Lowercase, i32> = vec![
+            (Lowercase("a".to_string()), 2),
+            (Lowercase("b".to_string()), 3),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(map, expected);
+        let dict = [("a", 2), ("A", 1)].into_py_dict(py);
+        let map: BTreeMap<Lowercase, i32> = dict.extract().unwrap();
+        assert_eq!(map[&Lowercase("a".to_string())], 1);
+    }
 }
@@ -1,6 +1,8 @@
 // Copyright (c) 2017-present PyO3 Project and Contributors
 //
 // based on Daniel Grunwald's https://github.com/dgrunwald/rust-cpython
+#[cfg(feature = "nightly")]
+use crate::types::sequence::PrimitiveItem;
 use crate::{
     ffi, AsPyPointer, FromPy, FromPyObject, PyAny, PyErr, PyNativeType, PyObject, PyResult, Python,
     ToPyObject,
@@ -79,6 +81,44 @@ impl<'source> FromPyObject<'source> for f32 {
     }
 }
 
+/// Returns the value of `item` if it is an exact `float`.
+#[cfg(all(feature = "nightly", not(Py_LIMITED_API)))]
+#[inline]
+unsafe fn exact_float_value(item: *mut ffi::PyObject) -> Option<f64> {
+    if ffi::PyFloat_CheckExact(item) != 0 {
+        Some(ffi::PyFloat_AS_DOUBLE(item))
+    } else {
+        None
+    }
+}
+
+#[cfg(feature = "nightly")]
+impl PrimitiveItem for f64 {
+    fn new_item(self, _py: Python) -> *mut ffi::PyObject {
+        unsafe { ffi::PyFloat_FromDouble(self) }
+    }
+
+    #[cfg(not(Py_LIMITED_API))]
+    unsafe fn extract_items(items: &[*mut ffi::PyObject]) -> Option<Vec<Self>> {
+        items.iter().map(|&item| exact_float_value(item)).collect()
+    }
+}
+
+#[cfg(feature = "nightly")]
+impl PrimitiveItem for f32 {
+    fn new_item(self, _py: Python) -> *mut ffi::PyObject {
+        unsafe { ffi::PyFloat_FromDouble(f64::from(self)) }
+    }
+
+    #[cfg(not(Py_LIMITED_API))]
+    unsafe fn extract_items(items: &[*mut ffi::PyObject]) -> Option<Vec<Self>> {
+        items
+            .iter()
+            .map(|&item| exact_float_value(item).map(|v| v as f32))
+            .collect()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::ffi::PyFloat_AS_DOUBLE;
@@ -0,0 +1,285 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+use crate::err::{PyDowncastError, PyErr, PyResult};
+use crate::instance::{AsPyRef, PyNativeType};
+use crate::once_cell::GILOnceCell;
+use crate::types::PyType;
+use crate::{
+    exceptions, ffi, AsPyPointer, FromPyObject, IntoPy, Py, PyAny, PyObject, Python, ToPyObject,
+};
+
+/// An exact rational number with a 64-bit numerator and denominator, converted to and from
+/// Python as `fractions.Fraction`.
+///
+/// A `Ratio` is always in lowest terms, with a positive denominator. It can be extracted from
+/// any instance of `numbers.Rational`, such as `Fraction` and `int`, and from any object
+/// implementing `__index__`, which gives a ratio with a denominator of 1. Extraction returns an
+/// `OverflowError` if the numerator or denominator does not fit in an `i64`, and a
+/// `ZeroDivisionError` if the denominator is zero.
+///
+/// # Example
+/// ```
+/// use pyo3::prelude::*;
+/// use pyo3::types::Ratio;
+///
+/// #[pyfunction]
+/// fn reciprocal(value: Ratio) -> Ratio {
+///     Ratio::new(value.denom(), value.numer())
+/// }
+///
+/// let gil = Python::acquire_gil();
+/// let py = gil.python();
+/// let reciprocal = pyo3::wrap_pyfunction!(reciprocal)(py);
+/// pyo3::py_run!(py, reciprocal, r#"
+/// from fractions import Fraction
+/// assert reciprocal(Fraction(-3, 4)) == Fraction(-4, 3)
+/// assert reciprocal(5) == Fraction(1, 5)
+/// "#);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Ratio {
+    numer: i64,
+    denom: i64,
+}
+
+impl Ratio {
+    /// Creates the ratio `numer / denom`, reduced to lowest terms.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `denom` is zero, or if the reduced ratio cannot be represented because its
+    /// numerator or denominator would be `i64::MIN` negated.
+    pub fn new(numer: i64, denom: i64) -> Ratio {
+        match Ratio::checked_new(numer, denom) {
+            Some(ratio) => ratio,
+            None if denom == 0 => panic!("denominator == 0"),
+            None => panic!("ratio overflowed"),
+        }
+    }
+
+    /// Creates the ratio `numer / denom`, reduced to lowest terms, or returns `None` if `denom`
+    /// is zero or the reduced ratio cannot be represented.
+    pub fn checked_new(numer: i64, denom: i64) -> Option<Ratio> {
+        if denom == 0 {
+            return None;
+        }
+        let divisor = gcd(numer, denom);
+        let (mut numer, mut denom) = (numer / divisor, denom / divisor);
+        if denom < 0 {
+            numer = numer.checked_neg()?;
+            denom = denom.checked_neg()?;
+        }
+        Some(Ratio { numer, denom })
+    }
+
+    /// Returns the numerator.
+    pub fn numer(&self) -> i64 {
+        self.numer
+    }
+
+    /// Returns the denominator, which is always positive.
+    pub fn denom(&self) -> i64 {
+        self.denom
+    }
+}
+
+impl From<i64> for Ratio {
+    fn from(value: i64) -> Ratio {
+        Ratio {
+            numer: value,
+            denom: 1,
+        }
+    }
+}
+
+/// Returns the greatest common divisor of `a` and `b`, which must not both be zero.
+///
+/// The result is positive, except if both `a` and `b` are `i64::MIN`, whose divisor cannot be
+/// negated.
+fn gcd(a: i64, b: i64) -> i64 {
+    // Work with non-positive values, as the magnitude of `i64::MIN` is not a valid `i64`.
+    let non_positive = |x: i64| if x > 0 { -x } else { x };
+    let (mut a, mut b) = (non_positive(a), non_positive(b));
+    while b != 0 {
+        // `i64::MIN % -1` overflows, although the remainder is zero.
+        let r = a.wrapping_rem(b);
+        a = b;
+        b = r;
+    }
+    a.checked_neg().unwrap_or(a)
+}
+
+/// Returns `numbers.Rational`.
+fn get_rational_abc(py: Python) -> &PyType {
+    static RATIONAL_ABC: GILOnceCell<Py<PyType>> = GILOnceCell::new();
+
+    RATIONAL_ABC
+        .get_or_init(py, || {
+            py.import("numbers")
+                .and_then(|numbers| Ok(numbers.get("Rational")?.downcast::<PyType>()?.into()))
+                .expect("failed to import numbers.Rational")
+        })
+        .as_ref(py)
+}
+
+/// Returns `fractions.Fraction`.
+fn get_fraction_type(py: Python) -> &PyType {
+    static FRACTION_TYPE: GILOnceCell<Py<PyType>> = GILOnceCell::new();
+
+    FRACTION_TYPE
+        .get_or_init(py, || {
+            py.import("fractions")
+                .and_then(|fractions| Ok(fractions.get("Fraction")?.downcast::<PyType>()?.into()))
+                .expect("failed to import fractions.Fraction")
+        })
+        .as_ref(py)
+}
+
+impl ToPyObject for Ratio {
+    fn to_object(&self, py: Python) -> PyObject {
+        get_fraction_type(py)
+            .call1((self.numer, self.denom))
+            .expect("failed to create Fraction")
+            .into()
+    }
+}
+
+impl IntoPy<PyObject> for Ratio {
+    fn into_py(self, py: Python) -> PyObject {
+        self.to_object(py)
+    }
+}
+
+impl<'source> FromPyObject<'source> for Ratio {
+    fn extract(obj: &'source PyAny) -> PyResult<Self> {
+        let py = obj.py();
+        unsafe {
+            if ffi::PyLong_Check(obj.as_ptr()) != 0 {
+                return obj.extract::<i64>().map(Ratio::from);
+            }
+        }
+        if get_rational_abc(py).is_instance(obj)? {
+            let numer: i64 = obj.getattr("numerator")?.extract()?;
+            let denom: i64 = obj.getattr("denominator")?.extract()?;
+            if denom == 0 {
+                return Err(exceptions::ZeroDivisionError::py_err(
+                    "rational with a zero denominator",
+                ));
+            }
+            return Ratio::checked_new(numer, denom).ok_or_else(|| {
+                exceptions::OverflowError::py_err("rational too large to convert to Ratio")
+            });
+        }
+        if unsafe { ffi::PyIndex_Check(obj.as_ptr()) } != 0 {
+            return obj.extract::<i64>().map(Ratio::from);
+        }
+        Err(PyErr::from(PyDowncastError::new(obj, "Rational")))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Ratio;
+    use crate::exceptions::{OverflowError, TypeError, ZeroDivisionError};
+    use crate::types::PyModule;
+    use crate::{AsPyRef, IntoPy, PyObject, Python, ToPyObject};
+
+    const CODE: &str = r#"
+import numbers
+
+class Index:
+    def __index__(self):
+        return -12
+
+class Broken:
+    numerator = 1
+    denominator = 0
+
+numbers.Rational.register(Broken)
+"#;
+
+    #[test]
+    fn test_reduction() {
+        assert_eq!(
+            (-3, 2),
+            (Ratio::new(6, -4).numer(), Ratio::new(6, -4).denom())
+        );
+        assert_eq!(Ratio::new(0, 1), Ratio::new(0, -5));
+        assert_eq!(Ratio::new(1, 1), Ratio::new(i64::MIN, i64::MIN));
+        assert_eq!(Ratio::from(i64::MIN), Ratio::new(i64::MIN, 1));
+        assert_eq!(None, Ratio::checked_new(i64::MIN, -1));
+        assert_eq!(None, Ratio::checked_new(1, 0));
+    }
+
+    #[test]
+    #[should_panic(expected = "denominator == 0")]
+    fn test_zero_denominator_panics() {
+        Ratio::new(1, 0);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let fractions = py.import("fractions").unwrap();
+        for &(numer, denom) in &[
+            (-7, 3),
+            (22, 7),
+            (-1, 2),
+            (5, 1),
+            (0, 1),
+            (i64::MAX, i64::MIN + 1),
+        ] {
+            let ratio = Ratio::new(numer, denom);
+            let obj: PyObject = ratio.into_py(py);
+            let expected = fractions.call1("Fraction", (numer, denom)).unwrap();
+            assert!(expected.eq(&obj).unwrap());
+            assert!(obj
+                .as_ref(py)
+                .get_type()
+                .is(fractions.get("Fraction").unwrap()));
+            assert_eq!(ratio, obj.extract::<Ratio>(py).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_extract_integers() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let module = PyModule::from_code(py, CODE, "fraction.py", "pyo3_fraction_test").unwrap();
+        assert_eq!(
+            Ratio::from(-12),
+            module.call0("Index").unwrap().extract().unwrap()
+        );
+        assert_eq!(Ratio::from(42), 42.to_object(py).extract(py).unwrap());
+        assert_eq!(Ratio::from(1), true.to_object(py).extract(py).unwrap());
+    }
+
+    #[test]
+    fn test_extract_errors() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let module = PyModule::from_code(py, CODE, "fraction.py", "pyo3_fraction_test").unwrap();
+        let err = module
+            .call0("Broken")
+            .unwrap()
+            .extract::<Ratio>()
+            .unwrap_err();
+        assert!(err.is_instance::<ZeroDivisionError>(py));
+
+        let huge = py
+            .eval("__import__('fractions').Fraction(2 ** 70, 3)", None, None)
+            .unwrap();
+        let err = huge.extract::<Ratio>().unwrap_err();
+        assert!(err.is_instance::<OverflowError>(py));
+        let err = py
+            .eval("2 ** 64", None, None)
+            .unwrap()
+            .extract::<Ratio>()
+            .unwrap_err();
+        assert!(err.is_instance::<OverflowError>(py));
+
+        let err = 0.5.to_object(py).extract::<Ratio>(py).unwrap_err();
+        assert!(err.is_instance::<TypeError>(py));
+    }
+}
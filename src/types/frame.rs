@@ -0,0 +1,104 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+use crate::err::PyResult;
+use crate::instance::PyNativeType;
+use crate::types::{PyCode, PyDict};
+use crate::{ffi, AsPyPointer, PyAny, Python};
+
+/// Represents a Python frame object, an entry of the call stack of the interpreter.
+///
+/// The frame of the innermost Python function currently executing is returned by
+/// [Python::current_frame].
+#[repr(transparent)]
+pub struct PyFrame(PyAny);
+
+pyobject_native_var_type!(
+    PyFrame,
+    ffi::frameobject::PyFrame_Type,
+    ffi::frameobject::PyFrame_Check
+);
+
+impl PyFrame {
+    /// Returns the code object being executed in this frame.
+    ///
+    /// This is the `f_code` attribute of the frame.
+    pub fn code(&self) -> &PyCode {
+        unsafe {
+            let code = ffi::frameobject::PyFrame_GetCode(self.as_ptr() as *mut _);
+            self.py().from_owned_ptr(code as *mut ffi::PyObject)
+        }
+    }
+
+    /// Returns the line number currently being executed in this frame.
+    ///
+    /// This is the `f_lineno` attribute of the frame.
+    pub fn line_number(&self) -> usize {
+        unsafe { ffi::frameobject::PyFrame_GetLineNumber(self.as_ptr() as *mut _) as usize }
+    }
+
+    /// Returns the frame of the caller, or `None` if this is the outermost frame.
+    ///
+    /// This is the `f_back` attribute of the frame.
+    pub fn back(&self) -> Option<&PyFrame> {
+        unsafe {
+            let back = ffi::frameobject::PyFrame_GetBack(self.as_ptr() as *mut _);
+            self.py().from_owned_ptr_or_opt(back as *mut ffi::PyObject)
+        }
+    }
+
+    /// Returns the local namespace of the frame.
+    ///
+    /// This is the `f_locals` attribute of the frame. For function frames, it is a snapshot of
+    /// the local variables at the time of the call.
+    pub fn locals(&self) -> PyResult<&PyAny> {
+        self.getattr("f_locals")
+    }
+
+    /// Returns the global namespace of the frame.
+    ///
+    /// This is the `f_globals` attribute of the frame.
+    pub fn globals(&self) -> PyResult<&PyDict> {
+        Ok(self.getattr("f_globals")?.downcast()?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PyFrame;
+    use crate::types::{IntoPyDict, PyList};
+    use crate::Python;
+
+    #[test]
+    fn test_frame() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let globals = [("marker", 42)].into_py_dict(py);
+        py.run(
+            "import sys\ndef inner(arg):\n    return sys._getframe()\nframe = inner(7)\n",
+            Some(globals),
+            None,
+        )
+        .unwrap();
+        let frame: &PyFrame = globals.get_item("frame").unwrap().downcast().unwrap();
+        assert_eq!(frame.code().name().unwrap(), "inner");
+        assert_eq!(frame.line_number(), 3);
+        let arg: i32 = frame
+            .locals()
+            .unwrap()
+            .get_item("arg")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(arg, 7);
+        let marker: i32 = frame
+            .globals()
+            .unwrap()
+            .get_item("marker")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(marker, 42);
+
+        assert!(PyList::empty(py).downcast::<PyFrame>().is_err());
+    }
+}
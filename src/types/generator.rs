@@ -0,0 +1,183 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+use crate::err::{PyErr, PyResult};
+use crate::instance::PyNativeType;
+use crate::{exceptions, ffi, AsPyPointer, PyAny, PyObject, Python, ToPyObject};
+
+/// Represents a Python generator object, as created by calling a function containing `yield`.
+///
+/// Unlike [PyIterator](crate::types::PyIterator), which can only advance a generator, this allows
+/// driving it as a coroutine: sending values into it, throwing exceptions at it, and retrieving
+/// the value it returns.
+#[repr(transparent)]
+pub struct PyGenerator(PyAny);
+
+pyobject_native_var_type!(PyGenerator, ffi::PyGen_Type, ffi::PyGen_Check);
+
+/// The outcome of resuming a generator with [PyGenerator::send] or [PyGenerator::throw].
+#[derive(Debug)]
+pub enum GeneratorResult {
+    /// The generator yielded a value, and can be resumed again.
+    Yielded(PyObject),
+    /// The generator finished, returning the given value (`None` for a bare `return`).
+    Returned(PyObject),
+}
+
+impl PyGenerator {
+    /// Resumes the generator, making the current `yield` expression evaluate to `value`.
+    ///
+    /// This is equivalent to the Python expression `self.send(value)`, except that the
+    /// `StopIteration` raised when the generator finishes is turned into
+    /// [GeneratorResult::Returned]. A generator that has not started yet must be sent `None`,
+    /// and resuming a generator that has already finished returns `Returned(None)`.
+    pub fn send(&self, value: &PyAny) -> PyResult<GeneratorResult> {
+        resume(self.py(), self.call_method1("send", (value,)))
+    }
+
+    /// Raises `exc` at the current `yield` expression of the generator.
+    ///
+    /// If the generator handles the exception and yields or returns, the outcome is returned as
+    /// for [send](PyGenerator::send); otherwise the exception propagates to the caller.
+    pub fn throw(&self, exc: PyErr) -> PyResult<GeneratorResult> {
+        let exc = exc.to_object(self.py());
+        resume(self.py(), self.call_method1("throw", (exc,)))
+    }
+
+    /// Closes the generator, raising `GeneratorExit` at its current `yield` expression.
+    ///
+    /// This is equivalent to the Python expression `self.close()`.
+    pub fn close(&self) -> PyResult<()> {
+        self.call_method0("close").map(drop)
+    }
+}
+
+/// Converts the result of a call to `send` or `throw` into a `GeneratorResult`, extracting the
+/// return value from `StopIteration`.
+pub(crate) fn resume(py: Python, result: PyResult<&PyAny>) -> PyResult<GeneratorResult> {
+    match result {
+        Ok(value) => Ok(GeneratorResult::Yielded(value.into())),
+        Err(err) if err.is_instance::<exceptions::StopIteration>(py) => {
+            let value = err.to_object(py).getattr(py, "value")?;
+            Ok(GeneratorResult::Returned(value))
+        }
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{GeneratorResult, PyGenerator};
+    use crate::exceptions::{KeyError, TypeError, ValueError};
+    use crate::types::{PyList, PyModule};
+    use crate::{AsPyRef, PyAny, PyObject, Python, ToPyObject};
+
+    const ECHO: &str = r#"
+def echo(log):
+    total = 0
+    while True:
+        try:
+            received = yield total
+        except ValueError:
+            log.append("caught")
+            continue
+        except GeneratorExit:
+            log.append("closed")
+            raise
+        if received is None:
+            return f"total: {total}"
+        total += received
+"#;
+
+    fn echo<'p>(py: Python<'p>, log: &PyList) -> &'p PyGenerator {
+        PyModule::from_code(py, ECHO, "echo.py", "pyo3_generator_test")
+            .unwrap()
+            .call1("echo", (log,))
+            .unwrap()
+            .downcast()
+            .unwrap()
+    }
+
+    fn yielded(py: Python, result: GeneratorResult) -> i32 {
+        match result {
+            GeneratorResult::Yielded(value) => value.extract(py).unwrap(),
+            GeneratorResult::Returned(_) => panic!("expected the generator to yield"),
+        }
+    }
+
+    fn returned(result: GeneratorResult) -> PyObject {
+        match result {
+            GeneratorResult::Returned(value) => value,
+            GeneratorResult::Yielded(_) => panic!("expected the generator to return"),
+        }
+    }
+
+    #[test]
+    fn test_send() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let gen = echo(py, PyList::empty(py));
+        let none = py.None();
+        let none: &PyAny = none.as_ref(py);
+
+        assert_eq!(yielded(py, gen.send(none).unwrap()), 0);
+        assert_eq!(
+            yielded(py, gen.send(3.to_object(py).as_ref(py)).unwrap()),
+            3
+        );
+        assert_eq!(
+            yielded(py, gen.send(4.to_object(py).as_ref(py)).unwrap()),
+            7
+        );
+        let result: String = returned(gen.send(none).unwrap()).extract(py).unwrap();
+        assert_eq!(result, "total: 7");
+
+        assert!(returned(gen.send(none).unwrap()).is_none(py));
+    }
+
+    #[test]
+    fn test_returns_none() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let gen: &PyGenerator = py
+            .eval("(x for x in [1])", None, None)
+            .unwrap()
+            .downcast()
+            .unwrap();
+        let none = py.None();
+        assert_eq!(yielded(py, gen.send(none.as_ref(py)).unwrap()), 1);
+        assert!(returned(gen.send(none.as_ref(py)).unwrap()).is_none(py));
+    }
+
+    #[test]
+    fn test_throw_and_close() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let log = PyList::empty(py);
+        let gen = echo(py, log);
+        let none = py.None();
+        yielded(py, gen.send(none.as_ref(py)).unwrap());
+        yielded(py, gen.send(5.to_object(py).as_ref(py)).unwrap());
+
+        let result = gen.throw(ValueError::py_err("ignored")).unwrap();
+        assert_eq!(yielded(py, result), 5);
+        let err = gen.throw(KeyError::py_err("fatal")).unwrap_err();
+        assert!(err.is_instance::<KeyError>(py));
+        // The generator is finished after an unhandled exception.
+        assert!(returned(gen.send(none.as_ref(py)).unwrap()).is_none(py));
+
+        let gen = echo(py, log);
+        yielded(py, gen.send(none.as_ref(py)).unwrap());
+        gen.close().unwrap();
+        let log: Vec<String> = log.extract().unwrap();
+        assert_eq!(log, ["caught", "closed"]);
+    }
+
+    #[test]
+    fn test_downcast() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let list = PyList::empty(py);
+        let err = list.downcast::<PyGenerator>().map(|_| ()).unwrap_err();
+        assert!(crate::PyErr::from(err).is_instance::<TypeError>(py));
+    }
+}
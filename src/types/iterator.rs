@@ -2,7 +2,12 @@
 //
 // based on Daniel Grunwald's https://github.com/dgrunwald/rust-cpython
 
-use crate::{ffi, AsPyPointer, PyAny, PyDowncastError, PyErr, PyNativeType, PyResult, Python};
+use crate::err::PyResultExt;
+use crate::{
+    ffi, AsPyPointer, FromPyObject, PyAny, PyDowncastError, PyErr, PyNativeType, PyResult, Python,
+};
+use std::iter::FromIterator;
+use std::marker::PhantomData;
 
 /// A Python iterator object.
 ///
@@ -29,7 +34,7 @@ pub struct PyIterator<'p>(&'p PyAny);
 
 impl<'p> PyIterator<'p> {
     /// Constructs a `PyIterator` from a Python iterator object.
-    pub fn from_object<T>(py: Python<'p>, obj: &T) -> Result<PyIterator<'p>, PyDowncastError>
+    pub fn from_object<T>(py: Python<'p>, obj: &T) -> Result<PyIterator<'p>, PyDowncastError<'p>>
     where
         T: AsPyPointer,
     {
@@ -38,7 +43,12 @@ impl<'p> PyIterator<'p> {
             // Returns NULL if an object cannot be iterated.
             if ptr.is_null() {
                 PyErr::fetch(py);
-                return Err(PyDowncastError);
+                // The error keeps the object, which may not outlive `'p`.
+                ffi::Py_INCREF(obj.as_ptr());
+                return Err(PyDowncastError::new(
+                    py.from_owned_ptr(obj.as_ptr()),
+                    "Iterator",
+                ));
             }
 
             if ffi::PyIter_Check(ptr) != 0 {
@@ -50,10 +60,87 @@ impl<'p> PyIterator<'p> {
                 // GILPool does not take ownership of the reference.
                 Ok(PyIterator(py.from_borrowed_ptr(ptr)))
             } else {
-                Err(PyDowncastError)
+                ffi::Py_DECREF(ptr);
+                ffi::Py_INCREF(obj.as_ptr());
+                Err(PyDowncastError::new(
+                    py.from_owned_ptr(obj.as_ptr()),
+                    "Iterator",
+                ))
             }
         }
     }
+
+    /// Returns an iterator extracting each item of this iterator as `T`.
+    ///
+    /// Errors raised by the Python iterator and extraction errors are both yielded as
+    /// `Some(Err(..))`, after which the iterator stops. An extraction error is wrapped in an
+    /// exception of the same type reporting the index of the failing item, with the original
+    /// error as its `__cause__`.
+    pub fn extract_each<T>(self) -> impl Iterator<Item = PyResult<T>> + 'p
+    where
+        T: FromPyObject<'p> + 'p,
+    {
+        ExtractEach {
+            iter: self,
+            index: 0,
+            done: false,
+            marker: PhantomData,
+        }
+    }
+
+    /// Extracts each item of this iterator, and collects them into a collection such as a `Vec`
+    /// or a `HashSet`.
+    ///
+    /// Returns the first error of [PyIterator::extract_each].
+    ///
+    /// # Example
+    /// ```
+    /// use pyo3::prelude::*;
+    ///
+    /// let gil = Python::acquire_gil();
+    /// let py = gil.python();
+    /// let squares = py.eval("(i * i for i in range(4))", None, None)?;
+    /// let squares = squares.iter()?.collect_extract::<Vec<u32>>()?;
+    /// assert_eq!(vec![0, 1, 4, 9], squares);
+    /// # Ok::<(), PyErr>(())
+    /// ```
+    pub fn collect_extract<C>(self) -> PyResult<C>
+    where
+        C: IntoIterator + FromIterator<<C as IntoIterator>::Item>,
+        C::Item: FromPyObject<'p> + 'p,
+    {
+        self.extract_each().collect()
+    }
+}
+
+struct ExtractEach<'p, T> {
+    iter: PyIterator<'p>,
+    /// The index of the next item.
+    index: usize,
+    done: bool,
+    marker: PhantomData<T>,
+}
+
+impl<'p, T> Iterator for ExtractEach<'p, T>
+where
+    T: FromPyObject<'p>,
+{
+    type Item = PyResult<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let py = self.iter.0.py();
+        let index = self.index;
+        let item = self.iter.next()?.and_then(|item| {
+            item.extract()
+                .with_context(py, || format!("cannot extract item #{}", index))
+        });
+        self.index += 1;
+        self.done = item.is_err();
+        Some(item)
+    }
 }
 
 impl<'p> Iterator for PyIterator<'p> {
@@ -90,13 +177,15 @@ impl<'p> Drop for PyIterator<'p> {
 
 #[cfg(test)]
 mod tests {
+    use crate::exceptions::{TypeError, ValueError};
     use crate::gil::GILPool;
     use crate::instance::AsPyRef;
     use crate::types::{PyDict, PyList};
     use crate::GILGuard;
     use crate::Python;
-    use crate::ToPyObject;
+    use crate::{PyResult, ToPyObject};
     use indoc::indoc;
+    use std::collections::HashSet;
 
     #[test]
     fn vec_iter() {
@@ -186,4 +275,69 @@ mod tests {
             assert_eq!(actual, *expected)
         }
     }
+
+    #[test]
+    fn extract_each() {
+        let generator = indoc!(
+            r#"
+            def mixed():
+                yield 1
+                yield 2
+                yield "three"
+                yield 4
+
+            def failing():
+                yield 1
+                raise ValueError("generator failed")
+        "#
+        );
+
+        let gil = GILGuard::acquire();
+        let py = gil.python();
+
+        let context = PyDict::new(py);
+        py.run(generator, None, Some(context)).unwrap();
+
+        let mixed = py.eval("mixed()", None, Some(context)).unwrap();
+        let items: Vec<PyResult<i32>> = mixed.iter_extract().unwrap().collect();
+        assert_eq!(3, items.len());
+        assert_eq!(1, *items[0].as_ref().unwrap());
+        assert_eq!(2, *items[1].as_ref().unwrap());
+        let err = items[2].as_ref().unwrap_err();
+        assert!(err.is_instance::<TypeError>(py));
+        assert_eq!(
+            "cannot extract item #2",
+            err.to_object(py).as_ref(py).to_string()
+        );
+        // The generator was not advanced past the failing item.
+        assert_eq!(
+            4,
+            mixed
+                .iter()
+                .unwrap()
+                .next()
+                .unwrap()
+                .unwrap()
+                .extract::<i32>()
+                .unwrap()
+        );
+
+        let failing = py.eval("failing()", None, Some(context)).unwrap();
+        let mut items = failing.iter_extract::<i32>().unwrap();
+        assert_eq!(1, items.next().unwrap().unwrap());
+        let err = items.next().unwrap().unwrap_err();
+        assert!(err.is_instance::<ValueError>(py));
+        assert!(items.next().is_none());
+
+        let numbers = py.eval("iter([3, 1, 3])", None, Some(context)).unwrap();
+        let numbers: HashSet<i32> = numbers.iter().unwrap().collect_extract().unwrap();
+        assert_eq!(vec![1, 3].into_iter().collect::<HashSet<_>>(), numbers);
+        let mixed = py.eval("mixed()", None, Some(context)).unwrap();
+        let err = mixed
+            .iter()
+            .unwrap()
+            .collect_extract::<Vec<i32>>()
+            .unwrap_err();
+        assert!(err.is_instance::<TypeError>(py));
+    }
 }
@@ -2,8 +2,13 @@
 //
 // based on Daniel Grunwald's https://github.com/dgrunwald/rust-cpython
 
-use crate::err::{self, PyResult};
+use crate::err::{self, PyErr, PyResult};
+use crate::exceptions;
 use crate::ffi::{self, Py_ssize_t};
+#[cfg(feature = "nightly")]
+use crate::types::sequence::PrimitiveItem;
+use crate::types::sequence::{fill_new_sequence, get_ssize_index, normalize_index};
+use crate::types::PyDict;
 use crate::{
     AsPyPointer, IntoPy, IntoPyPointer, PyAny, PyNativeType, PyObject, Python, ToBorrowedObject,
     ToPyObject,
@@ -22,16 +27,30 @@ impl PyList {
         T: ToPyObject,
         U: ExactSizeIterator<Item = T>,
     {
-        let elements_iter = elements.into_iter();
-        let len = elements_iter.len();
-        unsafe {
-            let ptr = ffi::PyList_New(len as Py_ssize_t);
-            for (i, e) in elements_iter.enumerate() {
-                let obj = e.to_object(py).into_ptr();
-                ffi::PyList_SetItem(ptr, i as Py_ssize_t, obj);
-            }
-            py.from_owned_ptr::<PyList>(ptr)
-        }
+        let elements = elements.into_iter().map(|e| Ok(e.to_object(py)));
+        unsafe { py.from_owned_ptr(new_list(py, elements).expect("failed to create list")) }
+    }
+
+    /// Constructs a new list from the results of a fallible conversion, such as
+    /// `values.iter().map(|v| convert(v))`.
+    ///
+    /// The list is allocated at its final size before the items are converted, so the results
+    /// are never collected into an intermediate `Vec`. Returns the first error yielded by
+    /// `elements`, without consuming the rest.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `elements` yields more or fewer items than its reported length.
+    pub fn new_from_exact_iter<T, U>(
+        py: Python<'_>,
+        elements: impl IntoIterator<Item = PyResult<T>, IntoIter = U>,
+    ) -> PyResult<&PyList>
+    where
+        T: IntoPy<PyObject>,
+        U: ExactSizeIterator<Item = PyResult<T>>,
+    {
+        let elements = elements.into_iter().map(|e| e.map(|e| e.into_py(py)));
+        unsafe { Ok(py.from_owned_ptr(new_list(py, elements)?)) }
     }
 
     /// Constructs a new empty list.
@@ -52,13 +71,16 @@ impl PyList {
 
     /// Gets the item at the specified index.
     ///
-    /// Panics if the index is out of range.
+    /// Negative indices count from the end of the list.
+    ///
+    /// Panics if the index is out of range; use [PyList::get_item_checked] to get an `IndexError`
+    /// instead.
     pub fn get_item(&self, index: isize) -> &PyAny {
-        assert!((index.abs() as usize) < self.len());
+        let index = normalize_index(index, self.len()).expect("list index out of range");
         unsafe {
-            let ptr = ffi::PyList_GetItem(self.as_ptr(), index as Py_ssize_t);
+            let ptr = ffi::PyList_GET_ITEM(self.as_ptr(), index as Py_ssize_t);
 
-            // PyList_GetItem return borrowed ptr; must make owned for safety (see #890).
+            // PyList_GET_ITEM return borrowed ptr; must make owned for safety (see #890).
             ffi::Py_INCREF(ptr);
             self.py().from_owned_ptr(ptr)
         }
@@ -66,27 +88,106 @@ impl PyList {
 
     /// Gets the item at the specified index.
     ///
+    /// Negative indices count from the end of the list, as in the Python expression `self[index]`.
+    /// Returns an `IndexError` if the index is out of range.
+    pub fn get_item_checked(&self, index: isize) -> PyResult<&PyAny> {
+        match normalize_index(index, self.len()) {
+            Some(index) => Ok(self.get_item(index as isize)),
+            None => Err(PyErr::new_with_gil::<exceptions::IndexError, _>(
+                self.py(),
+                "list index out of range",
+            )),
+        }
+    }
+
+    /// Gets the item at the specified index.
+    ///
+    /// Negative indices count from the end of the list.
+    ///
     /// Panics if the index is out of range.
     pub fn get_parked_item(&self, index: isize) -> PyObject {
+        self.get_item(index).into()
+    }
+
+    /// Sets the item at the specified index.
+    ///
+    /// Unlike [PyList::set_item_checked], negative indices are not supported.
+    /// Returns an `IndexError` if the index is out of range.
+    pub fn set_item<I>(&self, index: isize, item: I) -> PyResult<()>
+    where
+        I: ToPyObject,
+    {
         unsafe {
-            PyObject::from_borrowed_ptr(
+            err::error_on_minusone(
                 self.py(),
-                ffi::PyList_GetItem(self.as_ptr(), index as Py_ssize_t),
+                ffi::PyList_SetItem(self.as_ptr(), index, item.to_object(self.py()).into_ptr()),
             )
         }
     }
 
     /// Sets the item at the specified index.
     ///
-    /// Panics if the index is out of range.
-    pub fn set_item<I>(&self, index: isize, item: I) -> PyResult<()>
+    /// Negative indices count from the end of the list, as in the Python statement
+    /// `self[index] = item`. Returns an `IndexError` if the index is out of range.
+    pub fn set_item_checked<I>(&self, index: isize, item: I) -> PyResult<()>
     where
         I: ToPyObject,
     {
+        match normalize_index(index, self.len()) {
+            Some(index) => self.set_item(index as isize, item),
+            None => Err(PyErr::new_with_gil::<exceptions::IndexError, _>(
+                self.py(),
+                "list assignment index out of range",
+            )),
+        }
+    }
+
+    /// Takes the slice `self[low:high]` and returns it as a new list.
+    ///
+    /// Indices must be nonnegative, and out of range indices are clipped to the length of the
+    /// list, so the result is empty if `low >= high`.
+    pub fn get_slice(&self, low: usize, high: usize) -> &PyList {
+        unsafe {
+            self.py().from_owned_ptr(ffi::PyList_GetSlice(
+                self.as_ptr(),
+                get_ssize_index(low),
+                get_ssize_index(high),
+            ))
+        }
+    }
+
+    /// Replaces the slice `self[low:high]` with the items of the iterable `seq`.
+    ///
+    /// This is equivalent to the Python statement `self[low:high] = seq`. Indices are clipped
+    /// as in [PyList::get_slice].
+    pub fn set_slice(&self, low: usize, high: usize, seq: &PyAny) -> PyResult<()> {
         unsafe {
             err::error_on_minusone(
                 self.py(),
-                ffi::PyList_SetItem(self.as_ptr(), index, item.to_object(self.py()).into_ptr()),
+                ffi::PyList_SetSlice(
+                    self.as_ptr(),
+                    get_ssize_index(low),
+                    get_ssize_index(high),
+                    seq.as_ptr(),
+                ),
+            )
+        }
+    }
+
+    /// Deletes the slice `self[low:high]`.
+    ///
+    /// This is equivalent to the Python statement `del self[low:high]`. Indices are clipped
+    /// as in [PyList::get_slice].
+    pub fn del_slice(&self, low: usize, high: usize) -> PyResult<()> {
+        unsafe {
+            err::error_on_minusone(
+                self.py(),
+                ffi::PyList_SetSlice(
+                    self.as_ptr(),
+                    get_ssize_index(low),
+                    get_ssize_index(high),
+                    std::ptr::null_mut(),
+                ),
             )
         }
     }
@@ -103,7 +204,8 @@ impl PyList {
 
     /// Inserts an item at the specified index.
     ///
-    /// Panics if the index is out of range.
+    /// Like the Python expression `l.insert(index, item)`, negative indices count from the end of
+    /// the list, and indices out of range are clamped to the start or end of the list.
     pub fn insert<I>(&self, index: isize, item: I) -> PyResult<()>
     where
         I: ToBorrowedObject,
@@ -113,6 +215,31 @@ impl PyList {
         })
     }
 
+    /// Appends all items of an iterable to the list.
+    ///
+    /// This is equivalent to the Python expression `l.extend(iterable)`.
+    pub fn extend(&self, iterable: &PyAny) -> PyResult<()> {
+        let len = self.len() as Py_ssize_t;
+        unsafe {
+            err::error_on_minusone(
+                self.py(),
+                ffi::PyList_SetSlice(self.as_ptr(), len, len, iterable.as_ptr()),
+            )
+        }
+    }
+
+    /// Removes all items from the list.
+    ///
+    /// This is equivalent to the Python expression `l.clear()`.
+    pub fn clear(&self) -> PyResult<()> {
+        unsafe {
+            err::error_on_minusone(
+                self.py(),
+                ffi::PyList_SetSlice(self.as_ptr(), 0, ffi::PY_SSIZE_T_MAX, std::ptr::null_mut()),
+            )
+        }
+    }
+
     /// Returns an iterator over this list's items.
     pub fn iter(&self) -> PyListIterator {
         PyListIterator {
@@ -126,6 +253,19 @@ impl PyList {
         unsafe { err::error_on_minusone(self.py(), ffi::PyList_Sort(self.as_ptr())) }
     }
 
+    /// Sorts the list in-place, comparing the results of calling `key` on each item.
+    ///
+    /// Equivalent to the Python expression `l.sort(key=key, reverse=reverse)`. If `key` or a
+    /// comparison raises an exception, the error is returned and the list is left in some
+    /// unspecified order.
+    pub fn sort_by_key(&self, key: &PyAny, reverse: bool) -> PyResult<()> {
+        let kwargs = PyDict::new(self.py());
+        kwargs.set_item("key", key)?;
+        kwargs.set_item("reverse", reverse)?;
+        self.call_method("sort", (), Some(kwargs))?;
+        Ok(())
+    }
+
     /// Reverses the list in-place. Equivalent to the Python expression `l.reverse()`.
     pub fn reverse(&self) -> PyResult<()> {
         unsafe { err::error_on_minusone(self.py(), ffi::PyList_Reverse(self.as_ptr())) }
@@ -166,18 +306,33 @@ impl<T> ToPyObject for [T]
 where
     T: ToPyObject,
 {
+    #[cfg(not(feature = "nightly"))]
     fn to_object(&self, py: Python<'_>) -> PyObject {
+        let elements = self.iter().map(|e| Ok(e.to_object(py)));
         unsafe {
-            let ptr = ffi::PyList_New(self.len() as Py_ssize_t);
-            for (i, e) in self.iter().enumerate() {
-                let obj = e.to_object(py).into_ptr();
-                ffi::PyList_SetItem(ptr, i as Py_ssize_t, obj);
-            }
-            PyObject::from_owned_ptr_or_panic(py, ptr)
+            PyObject::from_owned_ptr(py, new_list(py, elements).expect("failed to create list"))
+        }
+    }
+
+    #[cfg(feature = "nightly")]
+    default fn to_object(&self, py: Python<'_>) -> PyObject {
+        let elements = self.iter().map(|e| Ok(e.to_object(py)));
+        unsafe {
+            PyObject::from_owned_ptr(py, new_list(py, elements).expect("failed to create list"))
         }
     }
 }
 
+#[cfg(feature = "nightly")]
+impl<T> ToPyObject for [T]
+where
+    T: ToPyObject + PrimitiveItem,
+{
+    fn to_object(&self, py: Python<'_>) -> PyObject {
+        new_list_from_primitives(py, self)
+    }
+}
+
 macro_rules! array_impls {
     ($($N:expr),+) => {
         $(
@@ -211,25 +366,155 @@ impl<T> IntoPy<PyObject> for Vec<T>
 where
     T: IntoPy<PyObject>,
 {
+    #[cfg(not(feature = "nightly"))]
     fn into_py(self, py: Python) -> PyObject {
+        let elements = self.into_iter().map(|e| Ok(e.into_py(py)));
         unsafe {
-            let ptr = ffi::PyList_New(self.len() as Py_ssize_t);
-            for (i, e) in self.into_iter().enumerate() {
-                let obj = e.into_py(py).into_ptr();
-                ffi::PyList_SetItem(ptr, i as Py_ssize_t, obj);
+            PyObject::from_owned_ptr(py, new_list(py, elements).expect("failed to create list"))
+        }
+    }
+
+    #[cfg(feature = "nightly")]
+    default fn into_py(self, py: Python) -> PyObject {
+        let elements = self.into_iter().map(|e| Ok(e.into_py(py)));
+        unsafe {
+            PyObject::from_owned_ptr(py, new_list(py, elements).expect("failed to create list"))
+        }
+    }
+}
+
+#[cfg(feature = "nightly")]
+impl<T> IntoPy<PyObject> for Vec<T>
+where
+    T: IntoPy<PyObject> + PrimitiveItem,
+{
+    fn into_py(self, py: Python) -> PyObject {
+        new_list_from_primitives(py, &self)
+    }
+}
+
+/// Creates a list from an iterator yielding exactly as many items as its reported length, and
+/// returns an owned pointer to it.
+fn new_list(
+    py: Python,
+    elements: impl ExactSizeIterator<Item = PyResult<PyObject>>,
+) -> PyResult<*mut ffi::PyObject> {
+    let len = elements.len();
+    unsafe {
+        let list = PyObject::from_owned_ptr_or_err(py, ffi::PyList_New(len as Py_ssize_t))?;
+        fill_new_sequence(list.as_ptr(), len, set_item, elements)?;
+        Ok(list.into_ptr())
+    }
+}
+
+/// Creates a list of primitive values, skipping the `PyObject` of each item.
+#[cfg(feature = "nightly")]
+fn new_list_from_primitives<T: PrimitiveItem>(py: Python, items: &[T]) -> PyObject {
+    unsafe {
+        let list =
+            PyObject::from_owned_ptr_or_panic(py, ffi::PyList_New(items.len() as Py_ssize_t));
+        for (index, &item) in items.iter().enumerate() {
+            let obj = item.new_item(py);
+            if obj.is_null() {
+                err::panic_after_error(py);
             }
-            PyObject::from_owned_ptr_or_panic(py, ptr)
+            set_item(list.as_ptr(), index as Py_ssize_t, obj);
         }
+        list
     }
 }
 
+/// Sets an item of a new list, stealing the reference to `item`.
+unsafe fn set_item(list: *mut ffi::PyObject, index: Py_ssize_t, item: *mut ffi::PyObject) {
+    #[cfg(not(Py_LIMITED_API))]
+    ffi::PyList_SET_ITEM(list, index, item);
+    #[cfg(Py_LIMITED_API)]
+    ffi::PyList_SetItem(list, index, item);
+}
+
 #[cfg(test)]
 mod test {
+    use crate::exceptions::{IndexError, TypeError, ZeroDivisionError};
     use crate::instance::AsPyRef;
+    use crate::types::IntoPyDict;
     use crate::types::PyList;
     use crate::Python;
     use crate::{IntoPy, PyObject, PyTryFrom, ToPyObject};
 
+    #[test]
+    fn test_new_from_exact_iter() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let values = vec![1.5, 2.5, 3.5];
+        let list = PyList::new_from_exact_iter(py, values.iter().map(|&v| Ok(v))).unwrap();
+        assert_eq!(values, list.extract::<Vec<f64>>().unwrap());
+
+        let mut converted = 0;
+        let err = PyList::new_from_exact_iter(
+            py,
+            (0..5).map(|i| {
+                converted += 1;
+                if i == 2 {
+                    Err(ZeroDivisionError::py_err("bad item"))
+                } else {
+                    Ok(i)
+                }
+            }),
+        )
+        .unwrap_err();
+        assert!(err.is_instance::<ZeroDivisionError>(py));
+        assert_eq!(3, converted);
+    }
+
+    #[test]
+    fn test_new_with_panicking_conversion() {
+        struct Explosive(bool);
+
+        impl ToPyObject for Explosive {
+            fn to_object(&self, py: Python) -> PyObject {
+                assert!(!self.0, "conversion failed");
+                py.None()
+            }
+        }
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let items = vec![Explosive(false), Explosive(true), Explosive(false)];
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            PyList::new(py, &items);
+        }));
+        assert!(result.is_err());
+        // The interpreter is left in a consistent state.
+        assert_eq!(1, PyList::new(py, &items[..1]).len());
+    }
+
+    #[test]
+    #[should_panic(expected = "iterator yielded fewer items than its reported length")]
+    fn test_new_with_short_iterator() {
+        struct Short(usize);
+
+        impl Iterator for Short {
+            type Item = usize;
+            fn next(&mut self) -> Option<usize> {
+                self.0 += 1;
+                if self.0 < 3 {
+                    Some(self.0)
+                } else {
+                    None
+                }
+            }
+        }
+
+        impl ExactSizeIterator for Short {
+            fn len(&self) -> usize {
+                5
+            }
+        }
+
+        let gil = Python::acquire_gil();
+        PyList::new(gil.python(), Short(0));
+    }
+
     #[test]
     fn test_new() {
         let gil = Python::acquire_gil();
@@ -265,6 +550,70 @@ mod test {
         assert_eq!(7, list.get_item(3).extract::<i32>().unwrap());
     }
 
+    #[test]
+    fn test_get_item_negative() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let list = PyList::new(py, &[2, 3, 5]);
+        assert_eq!(5, list.get_item(-1).extract::<i32>().unwrap());
+        assert_eq!(2, list.get_item(-3).extract::<i32>().unwrap());
+    }
+
+    #[test]
+    #[should_panic(expected = "list index out of range")]
+    fn test_get_item_out_of_range() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let list = PyList::new(py, &[2, 3, 5]);
+        list.get_item(-4);
+    }
+
+    #[test]
+    fn test_get_item_checked() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let list = PyList::new(py, &[2, 3, 5]);
+        assert_eq!(
+            2,
+            list.get_item_checked(0).unwrap().extract::<i32>().unwrap()
+        );
+        assert_eq!(
+            5,
+            list.get_item_checked(-1).unwrap().extract::<i32>().unwrap()
+        );
+        assert_eq!(
+            2,
+            list.get_item_checked(-3).unwrap().extract::<i32>().unwrap()
+        );
+        for &index in &[3, -4, isize::MAX, isize::MIN] {
+            let err = list.get_item_checked(index).unwrap_err();
+            assert!(err.is_instance::<IndexError>(py));
+        }
+        assert!(PyList::empty(py)
+            .get_item_checked(0)
+            .unwrap_err()
+            .is_instance::<IndexError>(py));
+    }
+
+    #[test]
+    fn test_set_item_checked() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let list = PyList::new(py, &[2, 3, 5]);
+        list.set_item_checked(-1, 7).unwrap();
+        list.set_item_checked(-3, 1).unwrap();
+        assert_eq!(vec![1, 3, 7], list.extract::<Vec<i32>>().unwrap());
+        for &index in &[3, -4] {
+            let err = list.set_item_checked(index, 0).unwrap_err();
+            assert!(err.is_instance::<IndexError>(py));
+        }
+        assert!(list
+            .set_item(3, 0)
+            .unwrap_err()
+            .is_instance::<IndexError>(py));
+        assert_eq!(vec![1, 3, 7], list.extract::<Vec<i32>>().unwrap());
+    }
+
     #[test]
     fn test_get_parked_item() {
         let gil = Python::acquire_gil();
@@ -326,6 +675,22 @@ mod test {
         assert_eq!(2, list.get_item(1).extract::<i32>().unwrap());
     }
 
+    #[test]
+    fn test_insert_out_of_range() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let list = PyList::new(py, &[2, 3]);
+        list.insert(-1, 1).unwrap();
+        list.insert(100, 4).unwrap();
+        list.insert(-100, 0).unwrap();
+        list.insert(isize::MAX, 5).unwrap();
+        list.insert(isize::MIN, -1).unwrap();
+        assert_eq!(
+            vec![-1, 0, 2, 1, 3, 4, 5],
+            list.extract::<Vec<i32>>().unwrap()
+        );
+    }
+
     #[test]
     fn test_insert_refcnt() {
         let gil = Python::acquire_gil();
@@ -426,6 +791,40 @@ mod test {
         assert_eq!(7, list.get_item(3).extract::<i32>().unwrap());
     }
 
+    #[test]
+    fn test_sort_error() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let list = PyList::new(py, &[3i32.to_object(py), "a".to_object(py)]);
+        let err = list.sort().unwrap_err();
+        assert!(err.is_instance::<TypeError>(py));
+    }
+
+    #[test]
+    fn test_sort_by_key() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let list = PyList::new(py, &["ccc", "a", "bb"]);
+        let len = py.eval("len", None, None).unwrap();
+        list.sort_by_key(len, false).unwrap();
+        assert_eq!(vec!["a", "bb", "ccc"], list.extract::<Vec<&str>>().unwrap());
+        list.sort_by_key(len, true).unwrap();
+        assert_eq!(vec!["ccc", "bb", "a"], list.extract::<Vec<&str>>().unwrap());
+    }
+
+    #[test]
+    fn test_sort_by_key_error() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let list = PyList::new(py, &[4, 3, 2, 1]);
+        let key = py
+            .eval("lambda x: x if x != 2 else 1 / 0", None, None)
+            .unwrap();
+        let err = list.sort_by_key(key, false).unwrap_err();
+        assert!(err.is_instance::<ZeroDivisionError>(py));
+        assert_eq!(4, list.len());
+    }
+
     #[test]
     fn test_reverse() {
         let gil = Python::acquire_gil();
@@ -443,6 +842,71 @@ mod test {
         assert_eq!(2, list.get_item(3).extract::<i32>().unwrap());
     }
 
+    #[test]
+    fn test_get_slice() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let list = PyList::new(py, &[2, 3, 5, 7]);
+        let slice = list.get_slice(1, 3);
+        assert_eq!(vec![3, 5], slice.extract::<Vec<i32>>().unwrap());
+        let slice = list.get_slice(1, usize::MAX);
+        assert_eq!(vec![3, 5, 7], slice.extract::<Vec<i32>>().unwrap());
+        assert!(list.get_slice(3, 1).is_empty());
+        assert!(list.get_slice(10, 20).is_empty());
+    }
+
+    #[test]
+    fn test_set_slice() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let list = PyList::new(py, &[2, 3, 5, 7]);
+        list.set_slice(1, 3, PyList::new(py, &[0, 0, 0])).unwrap();
+        assert_eq!(vec![2, 0, 0, 0, 7], list.extract::<Vec<i32>>().unwrap());
+        list.set_slice(0, usize::MAX, PyList::empty(py)).unwrap();
+        assert!(list.is_empty());
+        assert!(list.set_slice(0, 0, py.None().as_ref(py)).is_err());
+    }
+
+    #[test]
+    fn test_del_slice() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let list = PyList::new(py, &[2, 3, 5, 7]);
+        list.del_slice(1, 3).unwrap();
+        assert_eq!(vec![2, 7], list.extract::<Vec<i32>>().unwrap());
+        list.del_slice(3, 1).unwrap();
+        assert_eq!(vec![2, 7], list.extract::<Vec<i32>>().unwrap());
+        list.del_slice(1, usize::MAX).unwrap();
+        assert_eq!(vec![2], list.extract::<Vec<i32>>().unwrap());
+    }
+
+    #[test]
+    fn test_extend() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let list = PyList::new(py, &[1, 2]);
+        list.extend(PyList::new(py, &[3, 4])).unwrap();
+        let gen = py.eval("(i for i in range(5, 7))", None, None).unwrap();
+        list.extend(gen).unwrap();
+        list.extend(list).unwrap();
+        assert_eq!(
+            vec![1, 2, 3, 4, 5, 6, 1, 2, 3, 4, 5, 6],
+            list.extract::<Vec<i32>>().unwrap()
+        );
+        assert!(list.extend(py.None().as_ref(py)).is_err());
+    }
+
+    #[test]
+    fn test_clear() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let list = PyList::new(py, &[1, 2, 3]);
+        list.clear().unwrap();
+        assert!(list.is_empty());
+        list.clear().unwrap();
+        assert!(list.is_empty());
+    }
+
     #[test]
     fn test_array_into_py() {
         let gil = Python::acquire_gil();
@@ -452,4 +916,54 @@ mod test {
         assert_eq!(1, list.get_item(0).extract::<i32>().unwrap());
         assert_eq!(2, list.get_item(1).extract::<i32>().unwrap());
     }
+
+    #[test]
+    fn test_primitive_vec_roundtrip() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let is_list_of = |obj: &PyObject, item_type: &str| {
+            let code = format!(
+                "type(v) is list and all(type(i) is {} for i in v)",
+                item_type
+            );
+            py.eval(&code, None, Some([("v", obj)].into_py_dict(py)))
+                .unwrap()
+                .is_true()
+                .unwrap()
+        };
+
+        let ints = vec![0i64, -1, i64::min_value(), i64::max_value()];
+        let obj: PyObject = ints.clone().into_py(py);
+        assert!(is_list_of(&obj, "int"));
+        assert_eq!(ints, obj.extract::<Vec<i64>>(py).unwrap());
+        let obj = ints.to_object(py);
+        assert_eq!(ints, obj.extract::<Vec<i64>>(py).unwrap());
+
+        let bytes = vec![0u8, 1, 255];
+        let obj: PyObject = bytes.clone().into_py(py);
+        assert!(is_list_of(&obj, "int"));
+        assert_eq!(bytes, obj.extract::<Vec<u8>>(py).unwrap());
+        assert_eq!(
+            bytes,
+            bytes[..].to_object(py).extract::<Vec<u8>>(py).unwrap()
+        );
+
+        let unsigned = vec![u64::max_value()];
+        let obj: PyObject = unsigned.clone().into_py(py);
+        assert_eq!(unsigned, obj.extract::<Vec<u64>>(py).unwrap());
+
+        let floats = vec![1.5, -0.0, std::f64::INFINITY, 1e300];
+        let obj: PyObject = floats.clone().into_py(py);
+        assert!(is_list_of(&obj, "float"));
+        assert_eq!(floats, obj.extract::<Vec<f64>>(py).unwrap());
+        assert_eq!(
+            floats,
+            floats.to_object(py).extract::<Vec<f64>>(py).unwrap()
+        );
+        let obj: PyObject = vec![0.25f32].into_py(py);
+        assert_eq!(vec![0.25f32], obj.extract::<Vec<f32>>(py).unwrap());
+
+        let empty: PyObject = Vec::<i64>::new().into_py(py);
+        assert_eq!(0, empty.extract::<Vec<i64>>(py).unwrap().len());
+    }
 }
@@ -0,0 +1,307 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+use crate::err::{self, PyDowncastError, PyErr, PyResult};
+use crate::instance::{AsPyRef, PyNativeType};
+use crate::once_cell::GILOnceCell;
+use crate::pyclass::PyClass;
+use crate::type_object::PyTypeObject;
+use crate::types::{PyAny, PyList, PyType};
+use crate::{ffi, AsPyPointer, Py, PyTryFrom, Python, ToBorrowedObject};
+
+/// Represents a reference to a Python object supporting the mapping protocol.
+///
+/// Downcasting to `PyMapping` succeeds for dicts and instances of `collections.abc.Mapping`,
+/// including classes registered with [PyMapping::register]. Objects which merely define
+/// `__getitem__`, such as lists, are not mappings.
+#[repr(transparent)]
+pub struct PyMapping(PyAny);
+pyobject_native_type_named!(PyMapping);
+pyobject_native_type_extract!(PyMapping);
+
+impl PyMapping {
+    /// Returns the number of objects in the mapping.
+    ///
+    /// This is equivalent to the Python expression `len(self)`.
+    #[inline]
+    pub fn len(&self) -> PyResult<usize> {
+        let v = unsafe { ffi::PyMapping_Size(self.as_ptr()) };
+        if v == -1 {
+            Err(PyErr::fetch(self.py()))
+        } else {
+            Ok(v as usize)
+        }
+    }
+
+    /// Returns whether the mapping is empty.
+    #[inline]
+    pub fn is_empty(&self) -> PyResult<bool> {
+        self.len().map(|l| l == 0)
+    }
+
+    /// Determines if the mapping contains the specified key.
+    ///
+    /// This is equivalent to the Python expression `key in self`.
+    pub fn contains<K>(&self, key: K) -> PyResult<bool>
+    where
+        K: ToBorrowedObject,
+    {
+        let r = key.with_borrowed_ptr(self.py(), |key| unsafe {
+            ffi::PySequence_Contains(self.as_ptr(), key)
+        });
+        match r {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(PyErr::fetch(self.py())),
+        }
+    }
+
+    /// Gets the item in self with key `key`.
+    ///
+    /// This is equivalent to the Python expression `self[key]`. Returns a `KeyError` if the key
+    /// is missing.
+    #[inline]
+    pub fn get_item<K>(&self, key: K) -> PyResult<&PyAny>
+    where
+        K: ToBorrowedObject,
+    {
+        key.with_borrowed_ptr(self.py(), |key| unsafe {
+            self.py()
+                .from_owned_ptr_or_err(ffi::PyObject_GetItem(self.as_ptr(), key))
+        })
+    }
+
+    /// Sets the item in self with key `key`.
+    ///
+    /// This is equivalent to the Python statement `self[key] = value`.
+    #[inline]
+    pub fn set_item<K, V>(&self, key: K, value: V) -> PyResult<()>
+    where
+        K: ToBorrowedObject,
+        V: ToBorrowedObject,
+    {
+        key.with_borrowed_ptr(self.py(), move |key| {
+            value.with_borrowed_ptr(self.py(), |value| unsafe {
+                err::error_on_minusone(self.py(), ffi::PyObject_SetItem(self.as_ptr(), key, value))
+            })
+        })
+    }
+
+    /// Deletes the item with key `key`.
+    ///
+    /// This is equivalent to the Python statement `del self[key]`.
+    #[inline]
+    pub fn del_item<K>(&self, key: K) -> PyResult<()>
+    where
+        K: ToBorrowedObject,
+    {
+        key.with_borrowed_ptr(self.py(), |key| unsafe {
+            err::error_on_minusone(self.py(), ffi::PyObject_DelItem(self.as_ptr(), key))
+        })
+    }
+
+    /// Returns a list containing all keys in the mapping.
+    ///
+    /// This is equivalent to the Python expression `list(self.keys())`.
+    pub fn keys(&self) -> PyResult<&PyList> {
+        #[cfg(Py_3_7)]
+        unsafe {
+            self.py()
+                .from_owned_ptr_or_err(ffi::PyMapping_Keys(self.as_ptr()))
+        }
+        #[cfg(not(Py_3_7))]
+        {
+            self.view_to_list("keys")
+        }
+    }
+
+    /// Returns a list containing all values in the mapping.
+    ///
+    /// This is equivalent to the Python expression `list(self.values())`.
+    pub fn values(&self) -> PyResult<&PyList> {
+        #[cfg(Py_3_7)]
+        unsafe {
+            self.py()
+                .from_owned_ptr_or_err(ffi::PyMapping_Values(self.as_ptr()))
+        }
+        #[cfg(not(Py_3_7))]
+        {
+            self.view_to_list("values")
+        }
+    }
+
+    /// Returns a list of all `(key, value)` pairs in the mapping.
+    ///
+    /// This is equivalent to the Python expression `list(self.items())`.
+    pub fn items(&self) -> PyResult<&PyList> {
+        #[cfg(Py_3_7)]
+        unsafe {
+            self.py()
+                .from_owned_ptr_or_err(ffi::PyMapping_Items(self.as_ptr()))
+        }
+        #[cfg(not(Py_3_7))]
+        {
+            self.view_to_list("items")
+        }
+    }
+
+    /// Calls the method `name`, which returns a view before Python 3.7, and collects the
+    /// result into a list.
+    #[cfg(not(Py_3_7))]
+    fn view_to_list(&self, name: &str) -> PyResult<&PyList> {
+        let view = self.call_method0(name)?;
+        unsafe {
+            self.py()
+                .from_owned_ptr_or_err(ffi::PySequence_List(view.as_ptr()))
+        }
+    }
+
+    /// Registers the pyclass `T` as a virtual subclass of `collections.abc.Mapping`.
+    ///
+    /// Afterwards, `isinstance(obj, collections.abc.Mapping)` is true for instances of `T` on
+    /// the Python side, and they can be downcast to `PyMapping`. `T` should implement the
+    /// mapping protocol, and provide `keys`, `values` and `items` methods for them to be usable
+    /// with the corresponding methods of `PyMapping`.
+    pub fn register<T: PyClass>(py: Python) -> PyResult<()> {
+        let ty = T::type_object(py);
+        get_mapping_abc(py)?.call_method1("register", (ty,))?;
+        Ok(())
+    }
+}
+
+/// Returns `collections.abc.Mapping`.
+fn get_mapping_abc(py: Python) -> PyResult<&PyType> {
+    static MAPPING_ABC: GILOnceCell<Py<PyType>> = GILOnceCell::new();
+
+    MAPPING_ABC
+        .get_or_try_init(py, || {
+            let abc = py.import("collections.abc")?;
+            Ok(abc.get("Mapping")?.downcast::<PyType>()?.into())
+        })
+        .map(|abc| abc.as_ref(py))
+}
+
+impl<'v> PyTryFrom<'v> for PyMapping {
+    fn try_from<V: Into<&'v PyAny>>(value: V) -> Result<&'v PyMapping, PyDowncastError<'v>> {
+        let value = value.into();
+        // Dicts are checked first, as they are by far the most common mappings. Otherwise,
+        // `PyMapping_Check` is not reliable (it is true for any class defining `__getitem__`,
+        // including sequences), so defer to `collections.abc.Mapping`.
+        unsafe {
+            if ffi::PyDict_Check(value.as_ptr()) != 0 {
+                return Ok(<PyMapping as PyTryFrom>::try_from_unchecked(value));
+            }
+        }
+        // An error raised by `isinstance` (e.g. from a custom `__instancecheck__`), or by the
+        // import of `collections.abc`, is treated as the value not being a mapping.
+        if get_mapping_abc(value.py())
+            .and_then(|abc| abc.is_instance(value))
+            .unwrap_or(false)
+        {
+            unsafe { Ok(<PyMapping as PyTryFrom>::try_from_unchecked(value)) }
+        } else {
+            Err(PyDowncastError::new(value, "Mapping"))
+        }
+    }
+
+    fn try_from_exact<V: Into<&'v PyAny>>(value: V) -> Result<&'v PyMapping, PyDowncastError<'v>> {
+        <PyMapping as PyTryFrom>::try_from(value)
+    }
+
+    #[inline]
+    unsafe fn try_from_unchecked<V: Into<&'v PyAny>>(value: V) -> &'v PyMapping {
+        let ptr = value.into() as *const _ as *const PyMapping;
+        &*ptr
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::exceptions::KeyError;
+    use crate::types::{PyList, PyMapping, PySequence};
+    use crate::{PyTryFrom, Python, ToPyObject};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_dict() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let mut v = HashMap::new();
+        v.insert(1, 2);
+        let ob = v.to_object(py);
+        let mapping = ob.cast_as::<PyMapping>(py).unwrap();
+        assert_eq!(1, mapping.len().unwrap());
+        assert!(!mapping.is_empty().unwrap());
+        assert!(mapping.contains(1).unwrap());
+        assert!(!mapping.contains(2).unwrap());
+        assert_eq!(2, mapping.get_item(1).unwrap().extract::<i32>().unwrap());
+        let err = mapping.get_item(2).map(|_| ()).unwrap_err();
+        assert!(err.is_instance::<KeyError>(py));
+
+        mapping.set_item(3, 4).unwrap();
+        assert_eq!(2, mapping.len().unwrap());
+        mapping.del_item(1).unwrap();
+        assert_eq!(
+            vec![3],
+            mapping.keys().unwrap().extract::<Vec<i32>>().unwrap()
+        );
+        assert_eq!(
+            vec![4],
+            mapping.values().unwrap().extract::<Vec<i32>>().unwrap()
+        );
+        assert_eq!(
+            vec![(3, 4)],
+            mapping
+                .items()
+                .unwrap()
+                .extract::<Vec<(i32, i32)>>()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_ordered_dict() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let ob = py
+            .eval(
+                "collections.OrderedDict([('b', 1), ('a', 2)])",
+                Some(crate::types::IntoPyDict::into_py_dict(
+                    &[("collections", py.import("collections").unwrap())],
+                    py,
+                )),
+                None,
+            )
+            .unwrap();
+        let mapping = <PyMapping as PyTryFrom>::try_from(ob).unwrap();
+        mapping.set_item("c", 3).unwrap();
+        assert_eq!(3, mapping.len().unwrap());
+        assert!(mapping.contains("a").unwrap());
+        // Insertion order is preserved.
+        assert_eq!(
+            vec!["b", "a", "c"],
+            mapping.keys().unwrap().extract::<Vec<&str>>().unwrap()
+        );
+        assert_eq!(
+            vec![("b", 1), ("a", 2), ("c", 3)],
+            mapping
+                .items()
+                .unwrap()
+                .extract::<Vec<(&str, i32)>>()
+                .unwrap()
+        );
+        // An OrderedDict is not a sequence, even though it defines `__getitem__`.
+        assert!(<PySequence as PyTryFrom>::try_from(ob).is_err());
+    }
+
+    #[test]
+    fn test_sequences_are_not_mappings() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let list = PyList::new(py, &[1, 2, 3]);
+        assert!(<PyMapping as PyTryFrom>::try_from(list).is_err());
+        let string = "abc".to_object(py);
+        assert!(string.cast_as::<PyMapping>(py).is_err());
+        let number = 5.to_object(py);
+        assert!(number.cast_as::<PyMapping>(py).is_err());
+    }
+}
@@ -0,0 +1,233 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+use crate::buffer::{Element, PyBuffer};
+use crate::err::{PyErr, PyResult};
+use crate::instance::PyNativeType;
+use crate::once_cell::GILOnceCell;
+use crate::types::{PyDict, PyType};
+use crate::{ffi, AsPyPointer, Py, PyAny, Python};
+use std::os::raw::{c_int, c_void};
+use std::ptr;
+
+/// Represents a Python `memoryview`.
+#[repr(transparent)]
+pub struct PyMemoryView(PyAny);
+
+pyobject_native_var_type!(
+    PyMemoryView,
+    ffi::PyMemoryView_Type,
+    ffi::PyMemoryView_Check
+);
+
+impl PyMemoryView {
+    /// Creates a new memoryview over an object supporting the buffer protocol.
+    ///
+    /// This is equivalent to the Python expression `memoryview(src)`.
+    pub fn from_object(src: &PyAny) -> PyResult<&PyMemoryView> {
+        unsafe {
+            src.py()
+                .from_owned_ptr_or_err(ffi::PyMemoryView_FromObject(src.as_ptr()))
+        }
+    }
+
+    /// Creates a new writable memoryview over the contents of `data`, without copying them.
+    ///
+    /// The vector is moved into a Python object exporting its buffer, and is dropped once the
+    /// memoryview and all other views onto it have been released.
+    pub fn from_vec(py: Python, data: Vec<u8>) -> PyResult<&PyMemoryView> {
+        Self::from_object(VecBuffer::new(py, data)?)
+    }
+
+    /// Checks whether the memory is read-only.
+    pub fn readonly(&self) -> PyResult<bool> {
+        self.getattr("readonly")?.extract()
+    }
+
+    /// Returns the size in bytes of each element.
+    pub fn itemsize(&self) -> PyResult<usize> {
+        self.getattr("itemsize")?.extract()
+    }
+
+    /// Returns the number of dimensions of the memory.
+    pub fn ndim(&self) -> PyResult<usize> {
+        self.getattr("ndim")?.extract()
+    }
+
+    /// Returns the length of each dimension of the memory.
+    pub fn shape(&self) -> PyResult<Vec<usize>> {
+        self.getattr("shape")?.extract()
+    }
+
+    /// Returns the `struct` module format string describing each element.
+    pub fn format(&self) -> PyResult<String> {
+        self.getattr("format")?.extract()
+    }
+
+    /// Casts the memoryview to a new format and, optionally, a new shape.
+    ///
+    /// This is equivalent to the Python expression `self.cast(format, shape)`.
+    pub fn cast(&self, format: &str, shape: Option<&[usize]>) -> PyResult<&PyMemoryView> {
+        let kwargs = PyDict::new(self.py());
+        if let Some(shape) = shape {
+            kwargs.set_item("shape", shape)?;
+        }
+        Ok(self
+            .call_method("cast", (format,), Some(kwargs))?
+            .downcast()?)
+    }
+
+    /// Gets a [PyBuffer] for typed access to the elements of the memoryview.
+    ///
+    /// Returns a `BufferError` if the format of the memoryview does not match `T`.
+    pub fn buffer<T: Element>(&self) -> PyResult<PyBuffer<T>> {
+        PyBuffer::get(self)
+    }
+}
+
+/// Python object owning a `Vec<u8>` and exporting it through the buffer protocol.
+#[repr(C)]
+struct VecBuffer {
+    ob_base: ffi::PyObject,
+    data: Vec<u8>,
+}
+
+impl VecBuffer {
+    fn new(py: Python, data: Vec<u8>) -> PyResult<&PyAny> {
+        unsafe {
+            let ty = Self::type_object(py);
+            let alloc = (*ty).tp_alloc.unwrap_or(ffi::PyType_GenericAlloc);
+            let obj: &PyAny = py.from_owned_ptr_or_err(alloc(ty, 0))?;
+            ptr::write(&mut (*(obj.as_ptr() as *mut VecBuffer)).data, data);
+            Ok(obj)
+        }
+    }
+
+    fn type_object(py: Python) -> *mut ffi::PyTypeObject {
+        static TYPE_OBJECT: GILOnceCell<Py<PyType>> = GILOnceCell::new();
+
+        TYPE_OBJECT
+            .get_or_init(py, || unsafe {
+                let buffer_procs = Box::new(ffi::PyBufferProcs {
+                    bf_getbuffer: Some(vec_buffer_getbuffer),
+                    bf_releasebuffer: None,
+                });
+                let mut type_object = Box::new(ffi::PyTypeObject_INIT);
+                type_object.tp_name = "pyo3_runtime.VecBuffer\0".as_ptr() as *const _;
+                type_object.tp_basicsize = std::mem::size_of::<VecBuffer>() as ffi::Py_ssize_t;
+                type_object.tp_flags = ffi::Py_TPFLAGS_DEFAULT;
+                type_object.tp_dealloc = Some(vec_buffer_dealloc);
+                type_object.tp_as_buffer = Box::into_raw(buffer_procs);
+
+                let type_object = Box::into_raw(type_object);
+                if ffi::PyType_Ready(type_object) != 0 {
+                    PyErr::fetch(py).print(py);
+                    panic!("An error occurred while initializing class VecBuffer");
+                }
+                Py::from_borrowed_ptr(py, type_object as *mut ffi::PyObject)
+            })
+            .as_ptr() as *mut ffi::PyTypeObject
+    }
+}
+
+unsafe extern "C" fn vec_buffer_getbuffer(
+    obj: *mut ffi::PyObject,
+    view: *mut ffi::Py_buffer,
+    flags: c_int,
+) -> c_int {
+    let data = &mut (*(obj as *mut VecBuffer)).data;
+    ffi::PyBuffer_FillInfo(
+        view,
+        obj,
+        data.as_mut_ptr() as *mut c_void,
+        data.len() as ffi::Py_ssize_t,
+        0,
+        flags,
+    )
+}
+
+unsafe extern "C" fn vec_buffer_dealloc(obj: *mut ffi::PyObject) {
+    ptr::drop_in_place(&mut (*(obj as *mut VecBuffer)).data);
+    let free = (*ffi::Py_TYPE(obj)).tp_free.unwrap();
+    free(obj as *mut c_void);
+}
+
+#[cfg(test)]
+mod test {
+    use super::PyMemoryView;
+    use crate::exceptions::{BufferError, TypeError};
+    use crate::types::{IntoPyDict, PyBytes};
+    use crate::{AsPyRef, Python};
+
+    #[test]
+    fn test_from_bytes() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let bytes = PyBytes::new(py, b"Hello Python");
+        let view = PyMemoryView::from_object(bytes).unwrap();
+        assert!(view.readonly().unwrap());
+        assert_eq!(1, view.itemsize().unwrap());
+        assert_eq!(1, view.ndim().unwrap());
+        assert_eq!(vec![12], view.shape().unwrap());
+        assert_eq!("B", view.format().unwrap());
+        assert_eq!(
+            b"Hello Python".to_vec(),
+            view.buffer::<u8>().unwrap().to_vec(py).unwrap()
+        );
+        let err = view.buffer::<u32>().err().unwrap();
+        assert!(err.is_instance::<BufferError>(py));
+
+        let err = PyMemoryView::from_object(py.None().as_ref(py)).unwrap_err();
+        assert!(err.is_instance::<TypeError>(py));
+    }
+
+    #[test]
+    fn test_cast() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let data: Vec<u8> = [1u32, 2, 3, 4]
+            .iter()
+            .flat_map(|x| x.to_ne_bytes().to_vec())
+            .collect();
+        let view = PyMemoryView::from_object(PyBytes::new(py, &data)).unwrap();
+
+        let cast = view.cast("I", None).unwrap();
+        assert_eq!(4, cast.itemsize().unwrap());
+        assert_eq!(vec![4], cast.shape().unwrap());
+        assert_eq!(
+            vec![1, 2, 3, 4],
+            cast.buffer::<u32>().unwrap().to_vec(py).unwrap()
+        );
+
+        let cast = view.cast("I", Some(&[2, 2])).unwrap();
+        assert_eq!(2, cast.ndim().unwrap());
+        assert_eq!(vec![2, 2], cast.shape().unwrap());
+        assert_eq!(
+            vec![1, 2, 3, 4],
+            cast.buffer::<u32>().unwrap().to_vec(py).unwrap()
+        );
+
+        assert!(view.cast("I", Some(&[3])).is_err());
+    }
+
+    #[test]
+    fn test_from_vec() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let data = vec![1u8, 2, 3];
+        let ptr = data.as_ptr();
+        let view = PyMemoryView::from_vec(py, data).unwrap();
+        assert!(!view.readonly().unwrap());
+        assert_eq!(vec![3], view.shape().unwrap());
+        let buffer = view.buffer::<u8>().unwrap();
+        assert_eq!(ptr as *mut _, buffer.buf_ptr());
+        buffer.release(py);
+
+        let locals = [("view", view)].into_py_dict(py);
+        py.run(
+            "view[0] = 42; assert bytes(view) == b'\\x2a\\x02\\x03'; view.release()",
+            None,
+            Some(locals),
+        )
+        .unwrap();
+    }
+}
@@ -2,28 +2,50 @@
 
 //! Various types defined by the Python interpreter such as `int`, `str` and `tuple`.
 
-pub use self::any::PyAny;
+pub use self::any::{ObjectKind, PyAny};
+pub use self::array::{PyArray1D, TypedArray};
 pub use self::boolobject::PyBool;
 pub use self::bytearray::PyByteArray;
 pub use self::bytes::PyBytes;
+pub use self::capsule::PyCapsule;
+#[cfg(not(Py_LIMITED_API))]
+pub use self::code::PyCode;
 pub use self::complex::PyComplex;
+#[cfg(all(Py_3_7, not(Py_LIMITED_API), not(PyPy)))]
+pub use self::context::{PyContext, PyContextToken, PyContextVar};
+#[cfg(Py_3_6)]
+pub use self::coroutine::PyAsyncGenerator;
+pub use self::coroutine::PyCoroutine;
 pub use self::datetime::PyDeltaAccess;
 pub use self::datetime::{
     PyDate, PyDateAccess, PyDateTime, PyDelta, PyTime, PyTimeAccess, PyTzInfo,
 };
+pub use self::decimal::PyDecimal;
 pub use self::dict::{IntoPyDict, PyDict};
 pub use self::floatob::PyFloat;
+pub use self::fraction::Ratio;
+#[cfg(not(Py_LIMITED_API))]
+pub use self::frame::PyFrame;
+pub use self::generator::{GeneratorResult, PyGenerator};
 pub use self::iterator::PyIterator;
 pub use self::list::PyList;
-pub use self::module::PyModule;
-pub use self::num::PyLong;
+pub use self::mapping::PyMapping;
+pub use self::memoryview::PyMemoryView;
+pub use self::module::{ModuleState, PyModule};
+pub use self::namedtuple::PyNamedTupleType;
+pub use self::none::PyNone;
 pub use self::num::PyLong as PyInt;
+pub use self::num::{ByteOrder, PyLong};
+pub use self::pysuper::PySuper;
 pub use self::sequence::PySequence;
 pub use self::set::{PyFrozenSet, PySet};
 pub use self::slice::{PySlice, PySliceIndices};
 pub use self::string::{PyString, PyString as PyUnicode};
 pub use self::tuple::PyTuple;
 pub use self::typeobject::PyType;
+#[cfg(feature = "macros")]
+pub use self::typeobject::PyTypeBuilder;
+pub use self::weakref::{PyWeakrefProxy, PyWeakrefReference};
 
 #[macro_export]
 macro_rules! pyobject_native_type_named (
@@ -171,8 +193,7 @@ macro_rules! pyobject_native_type_convert(
             fn fmt(&self, f: &mut ::std::fmt::Formatter)
                    -> Result<(), ::std::fmt::Error>
             {
-                let s = self.repr().map_err(|_| ::std::fmt::Error)?;
-                f.write_str(&s.to_string_lossy())
+                f.write_str(&self.repr_safe(None))
             }
         }
 
@@ -180,28 +201,45 @@ macro_rules! pyobject_native_type_convert(
             fn fmt(&self, f: &mut ::std::fmt::Formatter)
                    -> Result<(), ::std::fmt::Error>
             {
-                let s = self.str().map_err(|_| ::std::fmt::Error)?;
-                f.write_str(&s.to_string_lossy())
+                f.write_str(&self.str_safe(None))
             }
         }
     };
 );
 
 mod any;
+mod array;
 mod boolobject;
 mod bytearray;
 mod bytes;
+mod capsule;
+#[cfg(not(Py_LIMITED_API))]
+mod code;
 mod complex;
+#[cfg(all(Py_3_7, not(Py_LIMITED_API), not(PyPy)))]
+mod context;
+mod coroutine;
 mod datetime;
+mod decimal;
 mod dict;
 mod floatob;
+mod fraction;
+#[cfg(not(Py_LIMITED_API))]
+mod frame;
+mod generator;
 mod iterator;
 mod list;
+mod mapping;
+mod memoryview;
 mod module;
+mod namedtuple;
+mod none;
 mod num;
+mod pysuper;
 mod sequence;
 mod set;
 mod slice;
 mod string;
 mod tuple;
 mod typeobject;
+mod weakref;
@@ -10,9 +10,10 @@ use crate::object::PyObject;
 use crate::pyclass::PyClass;
 use crate::type_object::PyTypeObject;
 use crate::types::PyTuple;
-use crate::types::{PyAny, PyDict, PyList};
+use crate::types::{PyAny, PyCapsule, PyDict, PyList};
 use crate::{AsPyPointer, IntoPy, Py, Python, ToPyObject};
 use std::ffi::{CStr, CString};
+use std::ops::Deref;
 use std::os::raw::c_char;
 use std::str;
 
@@ -41,6 +42,12 @@ impl PyModule {
     /// `file_name` is the file name to associate with the module
     /// (this is used when Python reports errors, for example).
     /// `module_name` is the name to give the module.
+    ///
+    /// The code is executed in the namespace of the new module, which has `__name__` and
+    /// `__file__` set accordingly. As with a regular import, the module is registered in
+    /// `sys.modules` under `module_name` before the code runs, so that it can import itself and
+    /// be found by `pickle`. If the code raises an exception, it is removed from `sys.modules`
+    /// again and the exception is returned; syntax errors carry `file_name` and the line number.
     pub fn from_code<'p>(
         py: Python<'p>,
         code: &str,
@@ -58,6 +65,7 @@ impl PyModule {
             }
 
             let mptr = ffi::PyImport_ExecCodeModuleEx(module.as_ptr(), cptr, filename.as_ptr());
+            ffi::Py_DECREF(cptr);
             if mptr.is_null() {
                 return Err(PyErr::fetch(py));
             }
@@ -157,13 +165,42 @@ impl PyModule {
     /// Adds a member to the module.
     ///
     /// This is a convenience function which can be used from the module's initialization function.
+    ///
+    /// By default, the member is exported: its name is appended to the module's index
+    /// (`__all__`), so that it is imported by `from module import *`. Names starting with an
+    /// underscore are not exported, following the Python convention for private names. Use
+    /// [add_with_export](PyModule::add_with_export) to choose explicitly.
     pub fn add<V>(&self, name: &str, value: V) -> PyResult<()>
     where
         V: ToPyObject,
     {
-        self.index()?
-            .append(name)
-            .expect("could not append __name__ to __all__");
+        self.add_with_export(name, value, !name.starts_with('_'))
+    }
+
+    /// Adds a member to the module, and appends its name to the module's index (`__all__`) if
+    /// `export` is true, whatever the name.
+    ///
+    /// # Example
+    /// ```
+    /// use pyo3::prelude::*;
+    ///
+    /// #[pymodule]
+    /// fn config(_py: Python, m: &PyModule) -> PyResult<()> {
+    ///     // Public, but not imported by `from config import *`.
+    ///     m.add_with_export("DEFAULTS", vec![1, 2, 3], false)?;
+    ///     // Imported by `from config import *`, despite the underscore.
+    ///     m.add_with_export("_version", "1.0", true)
+    /// }
+    /// ```
+    pub fn add_with_export<V>(&self, name: &str, value: V, export: bool) -> PyResult<()>
+    where
+        V: ToPyObject,
+    {
+        if export {
+            self.index()?
+                .append(name)
+                .expect("could not append __name__ to __all__");
+        }
         self.setattr(name, value)
     }
 
@@ -179,6 +216,31 @@ impl PyModule {
         self.add(T::NAME, <T as PyTypeObject>::type_object(self.py()))
     }
 
+    /// Adds a submodule to the module, making it importable as `parent.child`.
+    ///
+    /// The last component of the submodule's `__name__` is used as attribute name on this module.
+    /// The submodule's `__name__` is set to the full dotted path and its `__package__` to the
+    /// `__name__` of this module, and the submodule is registered in `sys.modules` under that
+    /// path, so that both
+    /// `import parent.child` and `from parent.child import x` work once `parent` is imported.
+    ///
+    /// Submodules declared with `#[pymodule]` inside the body of a `#[pymodule]` function are
+    /// added with this method.
+    pub fn add_submodule(&self, module: &PyModule) -> PyResult<()> {
+        let name = module.name()?;
+        let attr_name = name.rsplit('.').next().unwrap_or(name).to_string();
+        let package = self.name()?;
+        let qualified_name = format!("{}.{}", package, attr_name);
+
+        module.setattr("__name__", &qualified_name)?;
+        module.setattr("__package__", package)?;
+        self.py()
+            .import("sys")?
+            .getattr("modules")?
+            .set_item(&qualified_name, module)?;
+        self.add(&attr_name, module)
+    }
+
     /// Adds a function or a (sub)module to a module, using the functions __name__ as name.
     ///
     /// Use this together with the`#[pyfunction]` and [wrap_pyfunction!] or `#[pymodule]` and
@@ -201,4 +263,149 @@ impl PyModule {
             .expect("A function or module must have a __name__");
         self.add(name.extract(self.py()).unwrap(), function)
     }
+
+    /// Sets the `__getattr__` function of the module, which is called with the name of an
+    /// attribute that the module does not define (PEP 562, Python 3.7 and later).
+    ///
+    /// The function should return the value of the attribute, or raise `AttributeError` for
+    /// unknown names, so that `hasattr` and `getattr` with a default keep working. This allows
+    /// attributes which are expensive to compute to be created on first access.
+    ///
+    /// Attributes created this way are not listed by `dir(module)`, unless they are advertised
+    /// by the `__dir__` function of the module, which can be set with
+    /// [add_dir](PyModule::add_dir).
+    ///
+    /// ```rust
+    /// use pyo3::exceptions::AttributeError;
+    /// use pyo3::prelude::*;
+    /// use pyo3::wrap_pyfunction;
+    ///
+    /// #[pyfunction]
+    /// fn __getattr__(py: Python, name: &str) -> PyResult<PyObject> {
+    ///     match name {
+    ///         "TABLE" => Ok((0..256).collect::<Vec<u32>>().to_object(py)),
+    ///         _ => Err(AttributeError::py_err(format!(
+    ///             "module 'lazy' has no attribute '{}'",
+    ///             name
+    ///         ))),
+    ///     }
+    /// }
+    ///
+    /// #[pyfunction]
+    /// fn __dir__(py: Python) -> PyResult<Vec<String>> {
+    ///     let mut names: Vec<String> = py.import("lazy")?.dict().keys().extract()?;
+    ///     names.push("TABLE".to_string());
+    ///     Ok(names)
+    /// }
+    ///
+    /// #[pymodule]
+    /// fn lazy(_py: Python, m: &PyModule) -> PyResult<()> {
+    ///     m.add_getattr(wrap_pyfunction!(__getattr__))?;
+    ///     m.add_dir(wrap_pyfunction!(__dir__))
+    /// }
+    /// ```
+    pub fn add_getattr(&self, getattr: &impl Fn(Python) -> PyObject) -> PyResult<()> {
+        self.setattr("__getattr__", getattr(self.py()))
+    }
+
+    /// Sets the `__dir__` function of the module, which is called without arguments by
+    /// `dir(module)` to list the attributes of the module (PEP 562, Python 3.7 and later).
+    ///
+    /// The function should return a sequence of names, which replaces the names of the
+    /// attributes defined in the module, so it should list them as well as the attributes
+    /// provided by the `__getattr__` function of the module. See
+    /// [add_getattr](PyModule::add_getattr).
+    pub fn add_dir(&self, dir: &impl Fn(Python) -> PyObject) -> PyResult<()> {
+        self.setattr("__dir__", dir(self.py()))
+    }
+
+    /// Stores `value` as the state of the module, replacing the previous state if any.
+    ///
+    /// The state is owned by a capsule stored in the `__pyo3_state__` attribute of the module,
+    /// and is dropped when the module is collected. Each module object has its own state, so
+    /// two instances of a module created with [wrap_pymodule!] or imported in two
+    /// sub-interpreters do not share it.
+    ///
+    /// The state can be retrieved with [state](PyModule::state), or received by a
+    /// `#[pyfunction]` as a [ModuleState] argument.
+    pub fn set_state<T: Send + 'static>(&self, value: T) -> PyResult<()> {
+        let name = CStr::from_bytes_with_nul(STATE_CAPSULE_NAME).unwrap();
+        let capsule = PyCapsule::new(self.py(), value, name)?;
+        self.setattr(STATE_ATTRIBUTE, capsule)
+    }
+
+    /// Returns a reference to the state stored with [set_state](PyModule::set_state).
+    ///
+    /// Returns a `RuntimeError` if the module has no state, and a `TypeError` if the state is
+    /// not of type `T`.
+    pub fn state<T: 'static>(&self) -> PyResult<&T> {
+        let capsule = match self.getattr(STATE_ATTRIBUTE) {
+            Ok(capsule) => capsule,
+            Err(ref e) if e.is_instance::<exceptions::AttributeError>(self.py()) => {
+                return Err(exceptions::RuntimeError::py_err(format!(
+                    "module {} has no state",
+                    self.name()?
+                )))
+            }
+            Err(e) => return Err(e),
+        };
+        capsule.downcast::<PyCapsule>()?.reference()
+    }
+}
+
+const STATE_ATTRIBUTE: &str = "__pyo3_state__";
+const STATE_CAPSULE_NAME: &[u8] = b"pyo3 module state\0";
+
+/// The state of the module of a `#[pyfunction]`, stored with [PyModule::set_state].
+///
+/// A `#[pyfunction]` can take an argument of type `ModuleState<T>`, which is not passed by
+/// Python but set to the state of the module the function belongs to. The function must be added
+/// to the module with `wrap_pyfunction!(function, module)`, or defined with `#[pyfn]`; calling
+/// it otherwise, or when the module has no state of type `T`, raises an exception.
+///
+/// ```rust
+/// use pyo3::prelude::*;
+/// use pyo3::types::ModuleState;
+/// use pyo3::wrap_pyfunction;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// #[pyfunction]
+/// fn increment(counter: ModuleState<AtomicUsize>) -> usize {
+///     counter.fetch_add(1, Ordering::SeqCst) + 1
+/// }
+///
+/// #[pymodule]
+/// fn counter(_py: Python, m: &PyModule) -> PyResult<()> {
+///     m.set_state(AtomicUsize::new(0))?;
+///     m.add_wrapped(wrap_pyfunction!(increment, m))
+/// }
+/// # let gil = Python::acquire_gil();
+/// # let py = gil.python();
+/// # let m = pyo3::wrap_pymodule!(counter)(py);
+/// # pyo3::py_run!(py, m, "assert m.increment() == 1; assert m.increment() == 2");
+/// ```
+#[derive(Debug)]
+pub struct ModuleState<'p, T>(&'p T);
+
+impl<'p, T: 'static> ModuleState<'p, T> {
+    #[doc(hidden)]
+    /// Returns the state of `module`, the `self` pointer of a function.
+    pub unsafe fn from_module_ptr(py: Python<'p>, module: *mut ffi::PyObject) -> PyResult<Self> {
+        let module: Option<&PyAny> = py.from_borrowed_ptr_or_opt(module);
+        match module.and_then(|module| module.downcast::<PyModule>().ok()) {
+            Some(module) => module.state().map(ModuleState),
+            None => Err(exceptions::RuntimeError::py_err(
+                "the function does not belong to a module; add it with \
+                 `wrap_pyfunction!(function, module)` or define it with `#[pyfn]` to use its state",
+            )),
+        }
+    }
+}
+
+impl<'p, T> Deref for ModuleState<'p, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.0
+    }
 }
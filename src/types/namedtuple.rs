@@ -0,0 +1,200 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+use crate::err::PyResult;
+use crate::instance::PyNativeType;
+use crate::types::{PyAny, PyModule, PyTuple};
+use crate::{ffi, AsPyPointer, IntoPy, Py, PyTryFrom, Python};
+use std::os::raw::c_int;
+
+/// Represents a named tuple type, as created by `collections.namedtuple`.
+///
+/// Instances of a named tuple type are tuples whose members can also be accessed by name, which
+/// makes them a more readable return type than plain tuples for functions returning a small,
+/// fixed group of values. Creating the type is relatively expensive, so it should be done once,
+/// e.g. in a [GILOnceCell](crate::once_cell::GILOnceCell) or at module initialization.
+///
+/// # Example
+/// ```
+/// use pyo3::once_cell::GILOnceCell;
+/// use pyo3::prelude::*;
+/// use pyo3::types::PyNamedTupleType;
+///
+/// static STATS: GILOnceCell<Py<PyNamedTupleType>> = GILOnceCell::new();
+///
+/// fn stats(py: Python, values: &[f64]) -> PyResult<PyObject> {
+///     let ty = STATS
+///         .get_or_init(py, || {
+///             PyNamedTupleType::new(py, "Stats", &["min", "max", "mean"])
+///                 .expect("failed to create the Stats type")
+///                 .into()
+///         })
+///         .as_ref(py);
+///     let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+///     let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+///     let mean = values.iter().sum::<f64>() / values.len() as f64;
+///     Ok(ty.instantiate((min, max, mean))?.into())
+/// }
+///
+/// let gil = Python::acquire_gil();
+/// let py = gil.python();
+/// let result = stats(py, &[1.0, 2.0, 6.0]).unwrap();
+/// pyo3::py_run!(py, result, r#"
+/// assert result.mean == 3.0
+/// assert result[1] == 6.0
+/// low, high, _ = result
+/// assert (low, high) == (1.0, 6.0)
+/// "#);
+/// ```
+#[repr(transparent)]
+pub struct PyNamedTupleType(PyAny);
+
+pyobject_native_var_type!(PyNamedTupleType, ffi::PyType_Type, namedtuple_check);
+
+impl PyNamedTupleType {
+    /// Creates a new named tuple type with the given name and field names.
+    ///
+    /// This is equivalent to the Python expression `collections.namedtuple(name, fields)`.
+    /// Returns a `ValueError` if `name` or one of `fields` is not a valid identifier, or if a
+    /// field name is repeated.
+    ///
+    /// Instances of the type can only be pickled if it can be found by name in its module; use
+    /// [new_in_module](PyNamedTupleType::new_in_module) for types which are part of the API of
+    /// a module.
+    pub fn new<'p>(py: Python<'p>, name: &str, fields: &[&str]) -> PyResult<&'p PyNamedTupleType> {
+        let ty = py
+            .import("collections")?
+            .call1("namedtuple", (name, fields.to_vec()))?;
+        Ok(unsafe { <PyNamedTupleType as PyTryFrom>::try_from_unchecked(ty) })
+    }
+
+    /// Creates a new named tuple type, and adds it to `module`.
+    ///
+    /// The `__module__` attribute of the type is set to the name of `module`, so that instances
+    /// of the type can be pickled once the module is importable.
+    pub fn new_in_module<'p>(
+        module: &'p PyModule,
+        name: &str,
+        fields: &[&str],
+    ) -> PyResult<&'p PyNamedTupleType> {
+        let ty = PyNamedTupleType::new(module.py(), name, fields)?;
+        ty.setattr("__module__", module.name()?)?;
+        module.add(name, ty)?;
+        Ok(ty)
+    }
+
+    /// Returns the field names of the type.
+    ///
+    /// This is equivalent to the Python expression `self._fields`.
+    pub fn fields(&self) -> PyResult<&PyTuple> {
+        Ok(self.getattr("_fields")?.downcast()?)
+    }
+
+    /// Creates an instance of the type from the values of its fields, in order.
+    ///
+    /// This is equivalent to the Python expression `self(*values)`. Returns a `TypeError` if
+    /// the number of values does not match the number of fields.
+    pub fn instantiate(&self, values: impl IntoPy<Py<PyTuple>>) -> PyResult<&PyTuple> {
+        let instance = self.call1(values)?;
+        Ok(unsafe { <PyTuple as PyTryFrom>::try_from_unchecked(instance) })
+    }
+}
+
+/// Checks whether `op` is a subclass of `tuple` with a `_fields` attribute, the way
+/// `typing.NamedTuple` and `collections.namedtuple` types are recognized by the standard library.
+unsafe fn namedtuple_check(op: *mut ffi::PyObject) -> c_int {
+    if ffi::PyType_Check(op) == 0
+        || ffi::PyType_IsSubtype(op as *mut ffi::PyTypeObject, &mut ffi::PyTuple_Type) == 0
+    {
+        return 0;
+    }
+    ffi::PyObject_HasAttrString(op, "_fields\0".as_ptr() as *const _)
+}
+
+#[cfg(test)]
+mod test {
+    use super::PyNamedTupleType;
+    use crate::exceptions::{TypeError, ValueError};
+    use crate::types::{IntoPyDict, PyModule, PyTuple};
+    use crate::{PyAny, Python};
+
+    #[test]
+    fn test_access() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let ty = PyNamedTupleType::new(py, "Stats", &["min", "max", "mean"]).unwrap();
+        assert_eq!(
+            vec!["min", "max", "mean"],
+            ty.fields().unwrap().extract::<Vec<&str>>().unwrap()
+        );
+
+        let stats = ty.instantiate((1, 6, 3.0)).unwrap();
+        assert_eq!(6, stats.getattr("max").unwrap().extract::<i32>().unwrap());
+        assert_eq!(3.0, stats.get_item(2).extract::<f64>().unwrap());
+        let (min, max, mean): (i32, i32, f64) = stats.extract().unwrap();
+        assert_eq!((1, 6, 3.0), (min, max, mean));
+        py.run(
+            "min, max, mean = stats; assert (min, max, mean) == (1, 6, 3.0); assert stats.min == 1",
+            None,
+            Some([("stats", stats)].into_py_dict(py)),
+        )
+        .unwrap();
+
+        let err = ty.instantiate((1, 2)).map(|_| ()).unwrap_err();
+        assert!(err.is_instance::<TypeError>(py));
+        let err = PyNamedTupleType::new(py, "Stats", &["min", "min"])
+            .map(|_| ())
+            .unwrap_err();
+        assert!(err.is_instance::<ValueError>(py));
+    }
+
+    #[test]
+    fn test_downcast() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let ty: &PyAny = PyNamedTupleType::new(py, "Point", &["x", "y"]).unwrap();
+        assert!(ty.downcast::<PyNamedTupleType>().is_ok());
+        let typed: &PyAny = py
+            .eval(
+                "typing.NamedTuple('Point', [('x', int), ('y', int)])",
+                Some([("typing", py.import("typing").unwrap())].into_py_dict(py)),
+                None,
+            )
+            .unwrap();
+        assert!(typed.downcast::<PyNamedTupleType>().is_ok());
+        assert!(py
+            .get_type::<PyTuple>()
+            .downcast::<PyNamedTupleType>()
+            .is_err());
+        let point = typed.call1((1, 2)).unwrap();
+        assert!(point.downcast::<PyNamedTupleType>().is_err());
+    }
+
+    #[test]
+    fn test_pickle() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let module = PyModule::new(py, "pyo3_namedtuple_test").unwrap();
+        let ty = PyNamedTupleType::new_in_module(module, "Pair", &["first", "second"]).unwrap();
+        assert!(module.get("Pair").unwrap().is(ty));
+        assert_eq!(
+            "pyo3_namedtuple_test",
+            ty.getattr("__module__").unwrap().extract::<&str>().unwrap()
+        );
+
+        let sys = py.import("sys").unwrap();
+        sys.getattr("modules")
+            .unwrap()
+            .set_item("pyo3_namedtuple_test", module)
+            .unwrap();
+        let pair = ty.instantiate(("a", 1)).unwrap();
+        let pickle = py.import("pickle").unwrap();
+        let data = pickle.call1("dumps", (pair,)).unwrap();
+        let loaded = pickle.call1("loads", (data,)).unwrap();
+        assert!(loaded.get_type().is(ty));
+        assert_eq!(("a", 1), loaded.extract::<(&str, i32)>().unwrap());
+        sys.getattr("modules")
+            .unwrap()
+            .del_item("pyo3_namedtuple_test")
+            .unwrap();
+    }
+}
@@ -0,0 +1,99 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+use crate::err::PyDowncastError;
+use crate::{ffi, AsPyPointer, PyAny, PyObject, PyTryFrom, Python, ToPyObject};
+use std::fmt;
+
+/// Represents the Python `None` object.
+///
+/// `None` is a singleton, so a `PyNone` is always the same object, returned by [PyNone::get].
+/// Downcasting to `PyNone` checks the identity of the object, which is equivalent to
+/// [PyAny::is_none].
+///
+/// # Example
+/// ```
+/// use pyo3::prelude::*;
+/// use pyo3::types::PyNone;
+///
+/// # let gil = Python::acquire_gil();
+/// # let py = gil.python();
+/// let none = py.eval("None", None, None).unwrap();
+/// assert!(none.downcast::<PyNone>().unwrap().is(PyNone::get(py)));
+/// assert!(py.eval("0", None, None).unwrap().downcast::<PyNone>().is_err());
+/// ```
+#[repr(transparent)]
+pub struct PyNone(PyAny);
+pyobject_native_type_named!(PyNone);
+pyobject_native_type_extract!(PyNone);
+
+impl PyNone {
+    /// Returns the `None` object.
+    #[inline]
+    pub fn get(py: Python) -> &PyNone {
+        unsafe { py.from_borrowed_ptr(ffi::Py_None()) }
+    }
+}
+
+impl ToPyObject for PyNone {
+    #[inline]
+    fn to_object(&self, py: Python) -> PyObject {
+        py.None()
+    }
+}
+
+impl fmt::Debug for PyNone {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl fmt::Display for PyNone {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl<'v> PyTryFrom<'v> for PyNone {
+    fn try_from<V: Into<&'v PyAny>>(value: V) -> Result<&'v PyNone, PyDowncastError<'v>> {
+        let value = value.into();
+        if value.is_none() {
+            unsafe { Ok(<PyNone as PyTryFrom>::try_from_unchecked(value)) }
+        } else {
+            Err(PyDowncastError::new(value, "NoneType"))
+        }
+    }
+
+    /// `NoneType` cannot be subclassed, so this is the same as `try_from`.
+    fn try_from_exact<V: Into<&'v PyAny>>(value: V) -> Result<&'v PyNone, PyDowncastError<'v>> {
+        <PyNone as PyTryFrom>::try_from(value)
+    }
+
+    #[inline]
+    unsafe fn try_from_unchecked<V: Into<&'v PyAny>>(value: V) -> &'v PyNone {
+        let ptr = value.into() as *const _ as *const PyNone;
+        &*ptr
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PyNone;
+    use crate::{AsPyRef, PyAny, Python, ToPyObject};
+
+    #[test]
+    fn test_none() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let none = PyNone::get(py);
+        assert!(none.is_none());
+        assert_eq!(format!("{} {:?}", none, none), "None None");
+        assert!(none.to_object(py).is_none(py));
+        assert!(py.None().as_ref(py).downcast::<PyNone>().is_ok());
+        assert!(py.None().as_ref(py).downcast_exact::<PyNone>().is_ok());
+
+        let zero = 0.to_object(py);
+        let zero: &PyAny = zero.as_ref(py);
+        let err = zero.downcast::<PyNone>().unwrap_err();
+        assert_eq!(err.to_string(), "expected 'NoneType', got 'int' (0)");
+    }
+}
@@ -2,6 +2,8 @@
 //
 // based on Daniel Grunwald's https://github.com/dgrunwald/rust-cpython
 
+#[cfg(feature = "nightly")]
+use crate::types::sequence::PrimitiveItem;
 use crate::{
     exceptions, ffi, AsPyPointer, FromPyObject, IntoPy, PyAny, PyErr, PyNativeType, PyObject,
     PyResult, Python, ToPyObject,
@@ -111,6 +113,120 @@ pub struct PyLong(PyAny);
 
 pyobject_native_var_type!(PyLong, ffi::PyLong_Type, ffi::PyLong_Check);
 
+/// The order of the bytes of an integer, as used by [PyLong::to_bytes] and [PyLong::from_bytes].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteOrder {
+    /// The most significant byte comes first.
+    Big,
+    /// The least significant byte comes first.
+    Little,
+}
+
+impl ByteOrder {
+    #[cfg(any(Py_LIMITED_API, PyPy))]
+    fn as_str(self) -> &'static str {
+        match self {
+            ByteOrder::Big => "big",
+            ByteOrder::Little => "little",
+        }
+    }
+}
+
+impl PyLong {
+    /// Creates a Python `int` from its representation as an array of bytes.
+    ///
+    /// If `signed` is true, the bytes are interpreted as a two's complement integer.
+    /// This is equivalent to the Python expression `int.from_bytes(bytes, byteorder, signed=signed)`.
+    pub fn from_bytes<'p>(
+        py: Python<'p>,
+        bytes: &[u8],
+        byteorder: ByteOrder,
+        signed: bool,
+    ) -> PyResult<&'p PyLong> {
+        #[cfg(not(Py_LIMITED_API))]
+        unsafe {
+            let obj = ffi::_PyLong_FromByteArray(
+                bytes.as_ptr(),
+                bytes.len(),
+                (byteorder == ByteOrder::Little) as c_int,
+                signed as c_int,
+            );
+            py.from_owned_ptr_or_err(obj)
+        }
+        #[cfg(Py_LIMITED_API)]
+        {
+            let kwargs = crate::types::PyDict::new(py);
+            kwargs.set_item("signed", signed)?;
+            let obj = py.get_type::<PyLong>().call_method(
+                "from_bytes",
+                (crate::types::PyBytes::new(py, bytes), byteorder.as_str()),
+                Some(kwargs),
+            )?;
+            Ok(obj.downcast()?)
+        }
+    }
+
+    /// Returns the representation of the integer as an array of `len` bytes.
+    ///
+    /// If `signed` is true, negative integers are represented in two's complement. Raises
+    /// `OverflowError` if the integer cannot be represented in `len` bytes, or if it is negative
+    /// and `signed` is false.
+    ///
+    /// This is equivalent to the Python expression `self.to_bytes(len, byteorder, signed=signed)`.
+    pub fn to_bytes(&self, len: usize, byteorder: ByteOrder, signed: bool) -> PyResult<Vec<u8>> {
+        #[cfg(not(any(Py_LIMITED_API, PyPy)))]
+        unsafe {
+            let mut buffer = vec![0; len];
+            let ok = ffi::_PyLong_AsByteArray(
+                self.as_ptr() as *mut ffi::PyLongObject,
+                buffer.as_mut_ptr(),
+                len,
+                (byteorder == ByteOrder::Little) as c_int,
+                signed as c_int,
+            );
+            if ok == -1 {
+                Err(PyErr::fetch(self.py()))
+            } else {
+                Ok(buffer)
+            }
+        }
+        #[cfg(any(Py_LIMITED_API, PyPy))]
+        {
+            let kwargs = crate::types::PyDict::new(self.py());
+            kwargs.set_item("signed", signed)?;
+            self.call_method("to_bytes", (len, byteorder.as_str()), Some(kwargs))?
+                .extract()
+        }
+    }
+
+    /// Returns the number of bits needed to represent the absolute value of the integer.
+    ///
+    /// This is equivalent to the Python expression `self.bit_length()`.
+    pub fn bit_length(&self) -> PyResult<usize> {
+        #[cfg(not(any(Py_LIMITED_API, PyPy)))]
+        {
+            let bits = unsafe { ffi::_PyLong_NumBits(self.as_ptr()) };
+            err_if_invalid_value(self.py(), -1, bits).map(|bits| bits as usize)
+        }
+        #[cfg(any(Py_LIMITED_API, PyPy))]
+        {
+            self.call_method0("bit_length")?.extract()
+        }
+    }
+
+    /// Returns `-1`, `0` or `1` depending on whether the integer is negative, zero or positive.
+    pub fn sign(&self) -> PyResult<i32> {
+        #[cfg(not(any(Py_LIMITED_API, PyPy)))]
+        {
+            Ok(unsafe { ffi::_PyLong_Sign(self.as_ptr()) })
+        }
+        #[cfg(any(Py_LIMITED_API, PyPy))]
+        {
+            Ok(self.compare(0)? as i32)
+        }
+    }
+}
+
 macro_rules! int_fits_c_long {
     ($rust_type:ty) => {
         impl ToPyObject for $rust_type {
@@ -133,6 +249,12 @@ macro_rules! int_fits_c_long {
         impl<'source> FromPyObject<'source> for $rust_type {
             fn extract(obj: &'source PyAny) -> PyResult<Self> {
                 let ptr = obj.as_ptr();
+                #[cfg(not(any(Py_LIMITED_API, PyPy)))]
+                {
+                    if let Some(val) = unsafe { int_value_in_range(ptr) } {
+                        return Ok(val);
+                    }
+                }
                 let val = unsafe {
                     let num = ffi::PyNumber_Index(ptr);
                     if num.is_null() {
@@ -146,9 +268,70 @@ macro_rules! int_fits_c_long {
                 <$rust_type>::try_from(val).map_err(|_| exceptions::OverflowError.into())
             }
         }
+
+        #[cfg(feature = "nightly")]
+        impl PrimitiveItem for $rust_type {
+            #![cfg_attr(feature = "cargo-clippy", allow(clippy::cast_lossless))]
+            fn new_item(self, _py: Python) -> *mut ffi::PyObject {
+                unsafe { ffi::PyLong_FromLong(self as c_long) }
+            }
+
+            #[cfg(not(Py_LIMITED_API))]
+            unsafe fn extract_items(items: &[*mut ffi::PyObject]) -> Option<Vec<Self>> {
+                items
+                    .iter()
+                    .map(|&item| {
+                        let val = exact_int_value(item, -1, ffi::PyLong_AsLong)?;
+                        <$rust_type>::try_from(val).ok()
+                    })
+                    .collect()
+            }
+        }
     };
 }
 
+/// Returns the value of `obj` if it is an `int` whose value fits in `T`.
+///
+/// This skips the call to `PyNumber_Index` and its new reference for the common case of an
+/// `int` which fits in 64 bits. Any other object, or value out of range, is left to the general
+/// path, which raises the appropriate error.
+#[cfg(not(any(Py_LIMITED_API, PyPy)))]
+#[inline]
+unsafe fn int_value_in_range<T: TryFrom<i64>>(obj: *mut ffi::PyObject) -> Option<T> {
+    if ffi::PyLong_Check(obj) == 0 {
+        return None;
+    }
+    let mut overflow = 0;
+    let val = ffi::PyLong_AsLongLongAndOverflow(obj, &mut overflow);
+    // This cannot raise for an `int`, but only reports whether the value fits in 64 bits.
+    if overflow != 0 {
+        return None;
+    }
+    T::try_from(val).ok()
+}
+
+/// Returns the value of `item` if it is an exact `int` which `as_value` converts without error.
+///
+/// Instances of subclasses, such as `bool`, and values which cannot be converted are left to
+/// `FromPyObject::extract`, which raises the appropriate error.
+#[cfg(all(feature = "nightly", not(Py_LIMITED_API)))]
+#[inline]
+unsafe fn exact_int_value<T: PartialEq>(
+    item: *mut ffi::PyObject,
+    invalid_value: T,
+    as_value: unsafe extern "C" fn(*mut ffi::PyObject) -> T,
+) -> Option<T> {
+    if ffi::PyLong_CheckExact(item) == 0 {
+        return None;
+    }
+    let val = as_value(item);
+    if val == invalid_value && !ffi::PyErr_Occurred().is_null() {
+        ffi::PyErr_Clear();
+        return None;
+    }
+    Some(val)
+}
+
 macro_rules! int_convert_u64_or_i64 {
     ($rust_type:ty, $pylong_from_ll_or_ull:expr, $pylong_as_ll_or_ull:expr) => {
         impl ToPyObject for $rust_type {
@@ -166,6 +349,12 @@ macro_rules! int_convert_u64_or_i64 {
         impl<'source> FromPyObject<'source> for $rust_type {
             fn extract(ob: &'source PyAny) -> PyResult<$rust_type> {
                 let ptr = ob.as_ptr();
+                #[cfg(not(any(Py_LIMITED_API, PyPy)))]
+                {
+                    if let Some(val) = unsafe { int_value_in_range(ptr) } {
+                        return Ok(val);
+                    }
+                }
                 unsafe {
                     let num = ffi::PyNumber_Index(ptr);
                     if num.is_null() {
@@ -178,6 +367,21 @@ macro_rules! int_convert_u64_or_i64 {
                 }
             }
         }
+
+        #[cfg(feature = "nightly")]
+        impl PrimitiveItem for $rust_type {
+            fn new_item(self, _py: Python) -> *mut ffi::PyObject {
+                unsafe { $pylong_from_ll_or_ull(self) }
+            }
+
+            #[cfg(not(Py_LIMITED_API))]
+            unsafe fn extract_items(items: &[*mut ffi::PyObject]) -> Option<Vec<Self>> {
+                items
+                    .iter()
+                    .map(|&item| exact_int_value(item, !0, $pylong_as_ll_or_ull))
+                    .collect()
+            }
+        }
     };
 }
 
@@ -440,6 +644,72 @@ mod test {
     use crate::Python;
     use crate::ToPyObject;
 
+    #[test]
+    fn test_extract_boundaries() {
+        use crate::types::PyAny;
+        use crate::{AsPyRef, FromPyObject, PyErr};
+        use std::fmt::Debug;
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        fn check<'p, T>(py: Python<'p>, code: &str, expected: Result<T, &str>)
+        where
+            T: FromPyObject<'p> + PartialEq + Debug,
+        {
+            let obj: &PyAny = py.eval(code, None, None).unwrap();
+            let result = obj.extract::<T>().map_err(|err: PyErr| {
+                let value = err.to_object(py);
+                format!(
+                    "{}: {}",
+                    value.as_ref(py).get_type().name(),
+                    value.as_ref(py).str().unwrap()
+                )
+            });
+            assert_eq!(
+                expected.map_err(str::to_string),
+                result,
+                "extracting {} from {}",
+                std::any::type_name::<T>(),
+                code
+            );
+            assert!(!PyErr::occurred(py));
+        }
+        const TOO_LARGE: &str = "OverflowError: Python int too large to convert to C long";
+        const NEGATIVE: &str = "OverflowError: can't convert negative int to unsigned";
+        const NOT_INT: &str = "TypeError: 'float' object cannot be interpreted as an integer";
+
+        check::<i64>(py, "-2**63", Ok(i64::min_value()));
+        check::<i64>(py, "-2**63 - 1", Err(TOO_LARGE));
+        check::<i64>(py, "2**63 - 1", Ok(i64::max_value()));
+        check::<i64>(py, "2**63", Err(TOO_LARGE));
+        check::<i64>(py, "True", Ok(1));
+        check::<i64>(py, "type('Int', (int,), {})(5)", Ok(5));
+        check::<i64>(
+            py,
+            "type('Index', (), {'__index__': lambda self: 7})()",
+            Ok(7),
+        );
+        check::<i64>(py, "1.5", Err(NOT_INT));
+
+        check::<u64>(py, "-1", Err(NEGATIVE));
+        check::<u64>(py, "0", Ok(0));
+        check::<u64>(py, "2**63", Ok(1 << 63));
+        check::<u64>(py, "2**64 - 1", Ok(u64::max_value()));
+        check::<u64>(py, "2**64", Err("OverflowError: int too big to convert"));
+        check::<u64>(
+            py,
+            "type('Index', (), {'__index__': lambda self: 7})()",
+            Ok(7),
+        );
+        check::<u64>(py, "1.5", Err(NOT_INT));
+
+        check::<i32>(py, "-1", Ok(-1));
+        check::<i32>(py, "2**63 - 1", Err("OverflowError: "));
+        check::<i32>(py, "2**63", Err(TOO_LARGE));
+        check::<u8>(py, "-1", Err("OverflowError: "));
+        check::<u8>(py, "True", Ok(1));
+    }
+
     #[test]
     fn test_u32_max() {
         let gil = Python::acquire_gil();
@@ -541,6 +811,81 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_bytes_round_trip() {
+        use crate::types::{ByteOrder, IntoPyDict, PyLong};
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        for (expr, signed) in &[
+            ("0", false),
+            ("2 ** 256 - 1", false),
+            ("-(2 ** 255)", true),
+            ("2 ** 255 - 1", true),
+            ("-1", true),
+            ("-123456789 ** 7", true),
+        ] {
+            let num: &PyLong = py.eval(expr, None, None).unwrap().downcast().unwrap();
+            for &byteorder in &[ByteOrder::Big, ByteOrder::Little] {
+                let bytes = num.to_bytes(32, byteorder, *signed).unwrap();
+                let order = if byteorder == ByteOrder::Big {
+                    "big"
+                } else {
+                    "little"
+                };
+                let expected: Vec<u8> = num
+                    .call_method(
+                        "to_bytes",
+                        (32, order),
+                        Some([("signed", *signed)].into_py_dict(py)),
+                    )
+                    .unwrap()
+                    .extract()
+                    .unwrap();
+                assert_eq!(bytes, expected);
+
+                let back = PyLong::from_bytes(py, &bytes, byteorder, *signed).unwrap();
+                assert_eq!(
+                    back.compare(num).unwrap(),
+                    std::cmp::Ordering::Equal,
+                    "{} did not round-trip",
+                    expr
+                );
+            }
+        }
+
+        let max = PyLong::from_bytes(py, &[0xff; 32], ByteOrder::Big, false).unwrap();
+        assert_eq!(max.bit_length().unwrap(), 256);
+        assert_eq!(max.sign().unwrap(), 1);
+        let min = PyLong::from_bytes(py, &[0xff; 32], ByteOrder::Big, true).unwrap();
+        assert_eq!(min.extract::<i32>().unwrap(), -1);
+        assert_eq!(min.sign().unwrap(), -1);
+        let zero = PyLong::from_bytes(py, &[], ByteOrder::Little, true).unwrap();
+        assert_eq!(zero.bit_length().unwrap(), 0);
+        assert_eq!(zero.sign().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_bytes_overflow() {
+        use crate::exceptions::OverflowError;
+        use crate::types::{ByteOrder, PyLong};
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let too_big: &PyLong = py.eval("2 ** 256", None, None).unwrap().downcast().unwrap();
+        let err = too_big.to_bytes(32, ByteOrder::Big, false).unwrap_err();
+        assert!(err.is_instance::<OverflowError>(py));
+        assert!(too_big.to_bytes(33, ByteOrder::Big, false).is_ok());
+
+        let too_big: &PyLong = py.eval("2 ** 255", None, None).unwrap().downcast().unwrap();
+        let err = too_big.to_bytes(32, ByteOrder::Big, true).unwrap_err();
+        assert!(err.is_instance::<OverflowError>(py));
+
+        let negative: &PyLong = py.eval("-1", None, None).unwrap().downcast().unwrap();
+        let err = negative.to_bytes(32, ByteOrder::Big, false).unwrap_err();
+        assert!(err.is_instance::<OverflowError>(py));
+    }
+
     macro_rules! test_common (
         ($test_mod_name:ident, $t:ty) => (
             mod $test_mod_name {
@@ -0,0 +1,122 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+use crate::err::PyResult;
+use crate::type_object::PyTypeObject;
+use crate::types::{PyAny, PyType};
+use crate::{ffi, AsPyPointer, PyTryFrom, Python};
+use std::os::raw::c_int;
+
+/// Represents a Python `super` object, used to call the implementation of a method in the
+/// next class of the MRO, as with the two-argument form `super(type, obj)` in Python.
+///
+/// Attribute lookups and method calls on a `PySuper`, e.g. with [getattr](PyAny::getattr) or
+/// [call_method](PyAny::call_method), skip `type` and the classes before it in the MRO of the
+/// type of `obj`. Inside `#[pymethods]`, [PyRef::py_super](crate::PyRef::py_super) creates one
+/// for the class being implemented.
+///
+/// # Example
+/// ```
+/// use pyo3::prelude::*;
+///
+/// #[pyclass(subclass)]
+/// struct Base {}
+///
+/// #[pymethods]
+/// impl Base {
+///     fn greet(&self) -> String {
+///         "hello".to_string()
+///     }
+/// }
+///
+/// #[pyclass(extends=Base)]
+/// struct Child {}
+///
+/// #[pymethods]
+/// impl Child {
+///     fn greet(self_: PyRef<Self>) -> PyResult<String> {
+///         let base: String = self_.py_super()?.call_method0("greet")?.extract()?;
+///         Ok(format!("{} world", base))
+///     }
+/// }
+///
+/// let gil = Python::acquire_gil();
+/// let py = gil.python();
+/// let child = PyCell::new(py, PyClassInitializer::from(Base {}).add_subclass(Child {})).unwrap();
+/// pyo3::py_run!(py, child, "assert child.greet() == 'hello world'");
+/// ```
+#[repr(transparent)]
+pub struct PySuper(PyAny);
+
+pyobject_native_var_type!(PySuper, ffi::PySuper_Type, super_check);
+
+impl PySuper {
+    /// Creates a new `super` object.
+    ///
+    /// This is equivalent to the Python expression `super(ty, obj)`. Returns a `TypeError` if
+    /// `obj` is neither an instance nor a subclass of `ty`.
+    pub fn new<'p>(py: Python<'p>, ty: &PyType, obj: &PyAny) -> PyResult<&'p PySuper> {
+        let result = PySuper::type_object(py).call1((ty, obj))?;
+        Ok(unsafe { <PySuper as PyTryFrom>::try_from_unchecked(result) })
+    }
+}
+
+unsafe fn super_check(op: *mut ffi::PyObject) -> c_int {
+    ffi::PyObject_TypeCheck(op, &mut ffi::PySuper_Type)
+}
+
+#[cfg(test)]
+mod test {
+    use super::PySuper;
+    use crate::exceptions::{AttributeError, TypeError};
+    use crate::types::{PyList, PyModule, PyType};
+    use crate::{AsPyRef, PyAny, Python, ToPyObject};
+
+    #[test]
+    fn test_python_classes() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let code = r#"
+class A:
+    def name(self):
+        return "A"
+
+class B(A):
+    def name(self):
+        return "B"
+
+class C(B):
+    pass
+"#;
+        let module = PyModule::from_code(py, code, "pysuper.py", "pyo3_super_test").unwrap();
+        let b: &PyType = module.get("B").unwrap().downcast().unwrap();
+        let c: &PyType = module.get("C").unwrap().downcast().unwrap();
+        let obj = c.call0().unwrap();
+
+        let name =
+            |sup: &PySuper| -> String { sup.call_method0("name").unwrap().extract().unwrap() };
+        assert_eq!("B", name(PySuper::new(py, c, obj).unwrap()));
+        assert_eq!("A", name(PySuper::new(py, b, obj).unwrap()));
+        let sup: &PyAny = PySuper::new(py, b, obj).unwrap();
+        assert!(sup.downcast::<PySuper>().is_ok());
+        assert!(PyList::empty(py).downcast::<PySuper>().is_err());
+
+        let a: &PyType = module.get("A").unwrap().downcast().unwrap();
+        let err = PySuper::new(py, a, obj)
+            .unwrap()
+            .getattr("name")
+            .map(|_| ())
+            .unwrap_err();
+        assert!(err.is_instance::<AttributeError>(py));
+    }
+
+    #[test]
+    fn test_unrelated_object() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let one = 1.to_object(py);
+        let err = PySuper::new(py, py.get_type::<PyList>(), one.as_ref(py))
+            .map(|_| ())
+            .unwrap_err();
+        assert!(err.is_instance::<TypeError>(py));
+    }
+}
@@ -3,12 +3,21 @@
 use crate::err::{self, PyDowncastError, PyErr, PyResult};
 use crate::exceptions;
 use crate::ffi::{self, Py_ssize_t};
-use crate::instance::PyNativeType;
-use crate::types::{PyAny, PyList, PyTuple};
-use crate::AsPyPointer;
+use crate::instance::{AsPyRef, PyNativeType};
+use crate::once_cell::GILOnceCell;
+use crate::pyclass::PyClass;
+use crate::type_object::PyTypeObject;
+use crate::types::{PyAny, PyList, PyTuple, PyType};
+use crate::{AsPyPointer, IntoPyPointer, Py, PyObject, Python};
 use crate::{FromPyObject, PyTryFrom, ToBorrowedObject};
+use std::cmp;
 
 /// Represents a reference to a Python object supporting the sequence protocol.
+///
+/// Downcasting to `PySequence` succeeds for lists, tuples and instances of
+/// `collections.abc.Sequence`, which includes `str`, `bytes` and `range`, as well as classes
+/// registered with [PySequence::register]. Objects which merely define `__getitem__`, such as
+/// dicts, are not sequences.
 #[repr(transparent)]
 pub struct PySequence(PyAny);
 pyobject_native_type_named!(PySequence);
@@ -239,22 +248,63 @@ impl PySequence {
     }
 
     /// Returns a fresh list based on the Sequence.
+    ///
+    /// This is equivalent to the Python expression `list(self)`.
     #[inline]
-    pub fn list(&self) -> PyResult<&PyList> {
+    pub fn to_list(&self) -> PyResult<&PyList> {
         unsafe {
             self.py()
                 .from_owned_ptr_or_err(ffi::PySequence_List(self.as_ptr()))
         }
     }
 
+    /// Returns a fresh list based on the Sequence.
+    #[inline]
+    #[deprecated(since = "0.12.0", note = "renamed to PySequence::to_list")]
+    pub fn list(&self) -> PyResult<&PyList> {
+        self.to_list()
+    }
+
     /// Returns a fresh tuple based on the Sequence.
+    ///
+    /// This is equivalent to the Python expression `tuple(self)`.
     #[inline]
-    pub fn tuple(&self) -> PyResult<&PyTuple> {
+    pub fn to_tuple(&self) -> PyResult<&PyTuple> {
         unsafe {
             self.py()
                 .from_owned_ptr_or_err(ffi::PySequence_Tuple(self.as_ptr()))
         }
     }
+
+    /// Returns a fresh tuple based on the Sequence.
+    #[inline]
+    #[deprecated(since = "0.12.0", note = "renamed to PySequence::to_tuple")]
+    pub fn tuple(&self) -> PyResult<&PyTuple> {
+        self.to_tuple()
+    }
+
+    /// Registers the pyclass `T` as a virtual subclass of `collections.abc.Sequence`.
+    ///
+    /// Afterwards, `isinstance(obj, collections.abc.Sequence)` is true for instances of `T` on
+    /// the Python side, and they can be downcast to `PySequence`. `T` should implement the
+    /// sequence protocol, i.e. `__len__` and `__getitem__` taking integer indices.
+    pub fn register<T: PyClass>(py: Python) -> PyResult<()> {
+        let ty = T::type_object(py);
+        get_sequence_abc(py)?.call_method1("register", (ty,))?;
+        Ok(())
+    }
+}
+
+/// Returns `collections.abc.Sequence`.
+fn get_sequence_abc(py: Python) -> PyResult<&PyType> {
+    static SEQUENCE_ABC: GILOnceCell<Py<PyType>> = GILOnceCell::new();
+
+    SEQUENCE_ABC
+        .get_or_try_init(py, || {
+            let abc = py.import("collections.abc")?;
+            Ok(abc.get("Sequence")?.downcast::<PyType>()?.into())
+        })
+        .map(|abc| abc.as_ref(py))
 }
 
 macro_rules! array_impls {
@@ -267,14 +317,14 @@ macro_rules! array_impls {
                 #[cfg(not(feature = "nightly"))]
                 fn extract(obj: &'a PyAny) -> PyResult<Self> {
                     let mut array = [T::default(); $N];
-                    extract_sequence_into_slice(obj, &mut array)?;
+                    extract_sequence_into_slice::<Self, _>(obj, &mut array)?;
                     Ok(array)
                 }
 
                 #[cfg(feature = "nightly")]
                 default fn extract(obj: &'a PyAny) -> PyResult<Self> {
                     let mut array = [T::default(); $N];
-                    extract_sequence_into_slice(obj, &mut array)?;
+                    extract_sequence_into_slice::<Self, _>(obj, &mut array)?;
                     Ok(array)
                 }
             }
@@ -295,7 +345,7 @@ macro_rules! array_impls {
                         buf.release(obj.py());
                     }
                     // fall back to sequence protocol
-                    extract_sequence_into_slice(obj, &mut array)?;
+                    extract_sequence_into_slice::<Self, _>(obj, &mut array)?;
                     Ok(array)
                 }
             }
@@ -326,20 +376,90 @@ where
 impl<'source, T> FromPyObject<'source> for Vec<T>
 where
     for<'a> T: FromPyObject<'a> + crate::buffer::Element,
+{
+    default fn extract(obj: &'source PyAny) -> PyResult<Self> {
+        extract_buffer_or_sequence(obj)
+    }
+}
+
+#[cfg(feature = "nightly")]
+impl<'source, T> FromPyObject<'source> for Vec<T>
+where
+    for<'a> T: FromPyObject<'a> + crate::buffer::Element + PrimitiveItem,
 {
     fn extract(obj: &'source PyAny) -> PyResult<Self> {
-        // first try buffer protocol
-        if let Ok(buf) = crate::buffer::PyBuffer::get(obj) {
-            if buf.dimensions() == 1 {
-                if let Ok(v) = buf.to_vec(obj.py()) {
-                    buf.release(obj.py());
-                    return Ok(v);
-                }
+        #[cfg(not(Py_LIMITED_API))]
+        {
+            if let Some(v) =
+                unsafe { exact_sequence_items(obj).and_then(|items| T::extract_items(items)) }
+            {
+                return Ok(v);
             }
-            buf.release(obj.py());
         }
-        // fall back to sequence protocol
-        extract_sequence(obj)
+        extract_buffer_or_sequence(obj)
+    }
+}
+
+/// The primitive types whose `Vec`s are converted from and to lists directly, without creating
+/// a reference to each item, with the `nightly` feature.
+///
+/// This trait is not exported, so that only the numeric types of PyO3 implement it.
+#[cfg(feature = "nightly")]
+pub trait PrimitiveItem: Copy {
+    /// Creates the Python object of `self`, returning a new reference, or null with an exception
+    /// set.
+    fn new_item(self, py: Python) -> *mut ffi::PyObject;
+
+    /// Extracts the items of an exact `list` or `tuple`, or returns `None` if they must be
+    /// extracted one by one with `extract`.
+    ///
+    /// When it returns `Some`, the values must be those `extract` would return; it must return
+    /// `None` for any item `extract` might handle differently, e.g. an instance of a subclass.
+    ///
+    /// # Safety
+    /// The GIL must be held, and `items` must be valid pointers.
+    #[cfg(not(Py_LIMITED_API))]
+    unsafe fn extract_items(items: &[*mut ffi::PyObject]) -> Option<Vec<Self>>;
+}
+
+#[cfg(feature = "nightly")]
+fn extract_buffer_or_sequence<T>(obj: &PyAny) -> PyResult<Vec<T>>
+where
+    for<'a> T: FromPyObject<'a> + crate::buffer::Element,
+{
+    // first try buffer protocol
+    if let Ok(buf) = crate::buffer::PyBuffer::get(obj) {
+        if buf.dimensions() == 1 {
+            if let Ok(v) = buf.to_vec(obj.py()) {
+                buf.release(obj.py());
+                return Ok(v);
+            }
+        }
+        buf.release(obj.py());
+    }
+    // fall back to sequence protocol
+    extract_sequence(obj)
+}
+
+/// Returns the items of `obj` if it is an exact `list` or `tuple`.
+///
+/// The slice must not be used after calling into Python code, which could modify the list.
+#[cfg(all(feature = "nightly", not(Py_LIMITED_API)))]
+unsafe fn exact_sequence_items(obj: &PyAny) -> Option<&[*mut ffi::PyObject]> {
+    let ptr = obj.as_ptr();
+    if ffi::PyList_CheckExact(ptr) != 0 {
+        let list = ptr as *mut ffi::PyListObject;
+        let len = ffi::Py_SIZE(ptr) as usize;
+        if len == 0 {
+            return Some(&[]);
+        }
+        Some(std::slice::from_raw_parts((*list).ob_item, len))
+    } else if ffi::PyTuple_CheckExact(ptr) != 0 {
+        let tuple = ptr as *mut ffi::PyTupleObject;
+        let len = ffi::Py_SIZE(ptr) as usize;
+        Some(std::slice::from_raw_parts((*tuple).ob_item.as_ptr(), len))
+    } else {
+        None
     }
 }
 
@@ -347,43 +467,147 @@ fn extract_sequence<'s, T>(obj: &'s PyAny) -> PyResult<Vec<T>>
 where
     T: FromPyObject<'s>,
 {
-    let seq = <PySequence as PyTryFrom>::try_from(obj)?;
+    let seq = sequence_protocol(obj)?;
     let mut v = Vec::with_capacity(seq.len().unwrap_or(0) as usize);
-    for item in seq.iter()? {
-        v.push(item?.extract::<T>()?);
+    for (index, item) in seq.iter()?.enumerate() {
+        let item = item?
+            .extract::<T>()
+            .map_err(|e| e.in_container::<Vec<T>>(obj.py(), || format!("index {}", index)))?;
+        v.push(item);
     }
     Ok(v)
 }
 
-fn extract_sequence_into_slice<'s, T>(obj: &'s PyAny, slice: &mut [T]) -> PyResult<()>
+/// Extracts the items of `obj` into `slice`, which is part of a value of type `A`.
+fn extract_sequence_into_slice<'s, A: ?Sized, T>(obj: &'s PyAny, slice: &mut [T]) -> PyResult<()>
 where
     T: FromPyObject<'s>,
 {
-    let seq = <PySequence as PyTryFrom>::try_from(obj)?;
+    let seq = sequence_protocol(obj)?;
     if seq.len()? as usize != slice.len() {
         return Err(exceptions::BufferError::py_err(
             "Slice length does not match buffer length.",
         ));
     }
-    for (value, item) in slice.iter_mut().zip(seq.iter()?) {
-        *value = item?.extract::<T>()?;
+    for (index, (value, item)) in slice.iter_mut().zip(seq.iter()?).enumerate() {
+        *value = item?
+            .extract::<T>()
+            .map_err(|e| e.in_container::<A>(obj.py(), || format!("index {}", index)))?;
+    }
+    Ok(())
+}
+
+/// Checks that `obj` supports the sequence protocol.
+///
+/// This is more lenient than downcasting to `PySequence`, so that objects such as numpy arrays,
+/// which are not registered with `collections.abc.Sequence`, can still be extracted.
+fn sequence_protocol(obj: &PyAny) -> Result<&PySequence, PyDowncastError> {
+    unsafe {
+        if ffi::PySequence_Check(obj.as_ptr()) != 0 {
+            Ok(<PySequence as PyTryFrom>::try_from_unchecked(obj))
+        } else {
+            Err(PyDowncastError::new(obj, "Sequence"))
+        }
+    }
+}
+
+/// Resolves a possibly negative `index` into a sequence of length `len` the way Python does,
+/// returning `None` if it is out of range.
+pub(crate) fn normalize_index(index: isize, len: usize) -> Option<usize> {
+    let index = if index < 0 {
+        index.checked_add(len as isize)?
+    } else {
+        index
+    };
+    if index >= 0 && (index as usize) < len {
+        Some(index as usize)
+    } else {
+        None
+    }
+}
+
+/// Converts a nonnegative index into a `Py_ssize_t`, clipping it to `PY_SSIZE_T_MAX`.
+pub(crate) fn get_ssize_index(index: usize) -> Py_ssize_t {
+    cmp::min(index, ffi::PY_SSIZE_T_MAX as usize) as Py_ssize_t
+}
+
+/// Fills the `len` slots of `seq`, a list or tuple which was just created with `len` empty slots,
+/// with the items of `elements`, storing each with `set_item`.
+///
+/// If an item is an error, or the conversion of an item panics, the remaining slots are filled
+/// with `None`, so that `seq` never has empty slots once it is released.
+///
+/// # Panics
+///
+/// Panics if `elements` yields more or fewer than `len` items.
+pub(crate) unsafe fn fill_new_sequence(
+    seq: *mut ffi::PyObject,
+    len: usize,
+    set_item: unsafe fn(*mut ffi::PyObject, Py_ssize_t, *mut ffi::PyObject),
+    mut elements: impl Iterator<Item = PyResult<PyObject>>,
+) -> PyResult<()> {
+    struct Guard {
+        seq: *mut ffi::PyObject,
+        len: usize,
+        filled: usize,
+        set_item: unsafe fn(*mut ffi::PyObject, Py_ssize_t, *mut ffi::PyObject),
+    }
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            for i in self.filled..self.len {
+                unsafe {
+                    ffi::Py_INCREF(ffi::Py_None());
+                    (self.set_item)(self.seq, i as Py_ssize_t, ffi::Py_None());
+                }
+            }
+        }
+    }
+
+    let mut guard = Guard {
+        seq,
+        len,
+        filled: 0,
+        set_item,
+    };
+    while guard.filled < len {
+        let item = elements
+            .next()
+            .expect("iterator yielded fewer items than its reported length")?;
+        set_item(seq, guard.filled as Py_ssize_t, item.into_ptr());
+        guard.filled += 1;
     }
+    assert!(
+        elements.next().is_none(),
+        "iterator yielded more items than its reported length"
+    );
     Ok(())
 }
 
 impl<'v> PyTryFrom<'v> for PySequence {
-    fn try_from<V: Into<&'v PyAny>>(value: V) -> Result<&'v PySequence, PyDowncastError> {
+    fn try_from<V: Into<&'v PyAny>>(value: V) -> Result<&'v PySequence, PyDowncastError<'v>> {
         let value = value.into();
+        // Lists and tuples are checked first, as they are by far the most common sequences.
+        // Otherwise, `PySequence_Check` is not reliable (it is true for any class defining
+        // `__getitem__`, including mappings), so defer to `collections.abc.Sequence`.
         unsafe {
-            if ffi::PySequence_Check(value.as_ptr()) != 0 {
-                Ok(<PySequence as PyTryFrom>::try_from_unchecked(value))
-            } else {
-                Err(PyDowncastError)
+            if ffi::PyList_Check(value.as_ptr()) != 0 || ffi::PyTuple_Check(value.as_ptr()) != 0 {
+                return Ok(<PySequence as PyTryFrom>::try_from_unchecked(value));
             }
         }
+        // An error raised by `isinstance` (e.g. from a custom `__instancecheck__`), or by the
+        // import of `collections.abc`, is treated as the value not being a sequence.
+        if get_sequence_abc(value.py())
+            .and_then(|abc| abc.is_instance(value))
+            .unwrap_or(false)
+        {
+            unsafe { Ok(<PySequence as PyTryFrom>::try_from_unchecked(value)) }
+        } else {
+            Err(PyDowncastError::new(value, "Sequence"))
+        }
     }
 
-    fn try_from_exact<V: Into<&'v PyAny>>(value: V) -> Result<&'v PySequence, PyDowncastError> {
+    fn try_from_exact<V: Into<&'v PyAny>>(value: V) -> Result<&'v PySequence, PyDowncastError<'v>> {
         <PySequence as PyTryFrom>::try_from(value)
     }
 
@@ -396,12 +620,14 @@ impl<'v> PyTryFrom<'v> for PySequence {
 
 #[cfg(test)]
 mod test {
+    use crate::exceptions::TypeError;
     use crate::instance::AsPyRef;
     use crate::object::PyObject;
     use crate::types::PySequence;
     use crate::AsPyPointer;
     use crate::Python;
     use crate::{PyTryFrom, ToPyObject};
+    use std::collections::{BTreeMap, HashMap};
 
     fn get_object() -> PyObject {
         // Convenience function for getting a single unique object
@@ -652,7 +878,7 @@ mod test {
         let v = vec!["foo", "bar"];
         let ob = v.to_object(py);
         let seq = ob.cast_as::<PySequence>(py).unwrap();
-        assert!(seq.list().is_ok());
+        assert!(seq.to_list().is_ok());
     }
 
     #[test]
@@ -662,7 +888,7 @@ mod test {
         let v = "foo";
         let ob = v.to_object(py);
         let seq = <PySequence as PyTryFrom>::try_from(ob.as_ref(py)).unwrap();
-        assert!(seq.list().is_ok());
+        assert!(seq.to_list().is_ok());
     }
 
     #[test]
@@ -672,7 +898,7 @@ mod test {
         let v = ("foo", "bar");
         let ob = v.to_object(py);
         let seq = ob.cast_as::<PySequence>(py).unwrap();
-        assert!(seq.tuple().is_ok());
+        assert!(seq.to_tuple().is_ok());
     }
 
     #[test]
@@ -682,7 +908,7 @@ mod test {
         let v = vec!["foo", "bar"];
         let ob = v.to_object(py);
         let seq = ob.cast_as::<PySequence>(py).unwrap();
-        assert!(seq.tuple().is_ok());
+        assert!(seq.to_tuple().is_ok());
     }
 
     #[test]
@@ -738,7 +964,7 @@ mod test {
         let seq = ob.cast_as::<PySequence>(py).unwrap();
         let type_ptr = seq.as_ref();
         let seq_from = unsafe { <PySequence as PyTryFrom>::try_from_unchecked(type_ptr) };
-        assert!(seq_from.list().is_ok());
+        assert!(seq_from.to_list().is_ok());
     }
 
     #[test]
@@ -753,4 +979,112 @@ mod test {
         let empty_seq = empty_list.cast_as::<PySequence>(py).unwrap();
         assert_eq!(empty_seq.is_empty().unwrap(), true);
     }
+
+    #[test]
+    fn test_extract_error_path() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let obj = py
+            .eval(
+                "[{}, {'a': (1, 1.5)}, {'b': (2, 2.5), 'foo': (3, 'x')}]",
+                None,
+                None,
+            )
+            .unwrap();
+        let err = obj
+            .extract::<Vec<BTreeMap<String, (i64, f64)>>>()
+            .unwrap_err();
+        assert!(err.is_instance::<TypeError>(py));
+        assert_eq!(
+            "error extracting Vec<BTreeMap<String, (i64, f64)>> at index 2 \u{2192} \
+             key 'foo' \u{2192} tuple element 1: must be real number, not str",
+            err.to_object(py).as_ref(py).to_string()
+        );
+
+        // Wrappers without a location of their own keep the path of the item.
+        let err = obj
+            .extract::<Option<Vec<BTreeMap<String, (i64, f64)>>>>()
+            .unwrap_err();
+        assert_eq!(
+            "error extracting Option<Vec<BTreeMap<String, (i64, f64)>>> at index 2 \u{2192} \
+             key 'foo' \u{2192} tuple element 1: must be real number, not str",
+            err.to_object(py).as_ref(py).to_string()
+        );
+
+        // Errors outside of containers are unchanged.
+        let err = py
+            .eval("'x'", None, None)
+            .unwrap()
+            .extract::<Option<f64>>()
+            .unwrap_err();
+        assert_eq!(
+            "must be real number, not str",
+            err.to_object(py).as_ref(py).to_string()
+        );
+        let err = py
+            .eval("{1: 2}", None, None)
+            .unwrap()
+            .extract::<HashMap<String, i32>>()
+            .unwrap_err();
+        assert!(err.is_instance::<TypeError>(py));
+        assert!(err
+            .to_object(py)
+            .as_ref(py)
+            .to_string()
+            .starts_with("error extracting HashMap<String, i32"));
+        assert!(err
+            .to_object(py)
+            .as_ref(py)
+            .to_string()
+            .contains(" at key 1 itself: "));
+    }
+
+    #[test]
+    fn test_extract_primitive_vecs() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let eval = |code| py.eval(code, None, None).unwrap();
+
+        assert_eq!(
+            vec![1.5, -2.0, 1e300],
+            eval("[1.5, -2.0, 1e300]").extract::<Vec<f64>>().unwrap()
+        );
+        assert_eq!(
+            vec![1i64, -2, i64::max_value()],
+            eval("(1, -2, 2**63 - 1)").extract::<Vec<i64>>().unwrap()
+        );
+        assert_eq!(
+            vec![0u64, u64::max_value()],
+            eval("[0, 2**64 - 1]").extract::<Vec<u64>>().unwrap()
+        );
+        assert_eq!(Vec::<u8>::new(), eval("[]").extract::<Vec<u8>>().unwrap());
+
+        // Mixed content, bools and subclasses are extracted one by one, as before.
+        assert_eq!(
+            vec![1.0, 2.5],
+            eval("[1, 2.5]").extract::<Vec<f64>>().unwrap()
+        );
+        assert_eq!(
+            vec![1i32, 0, 7],
+            eval("[True, False, 7]").extract::<Vec<i32>>().unwrap()
+        );
+        assert_eq!(
+            vec![2.5],
+            eval("[type('Half', (float,), {'__float__': lambda self: 0.5})(2.5)]")
+                .extract::<Vec<f64>>()
+                .unwrap()
+        );
+        assert!(eval("[1.0]").extract::<Vec<i64>>().is_err());
+
+        // Errors keep their location.
+        let err = eval("[1, 300]").extract::<Vec<u8>>().unwrap_err();
+        assert!(err.is_instance::<crate::exceptions::OverflowError>(py));
+        assert_eq!(
+            "error extracting Vec<u8> at index 1: ",
+            err.to_object(py).as_ref(py).to_string()
+        );
+        let err = eval("[-1]").extract::<Vec<u64>>().unwrap_err();
+        assert!(err.is_instance::<crate::exceptions::OverflowError>(py));
+        assert!(!crate::PyErr::occurred(py));
+    }
 }
@@ -3,8 +3,8 @@
 
 use crate::err::{self, PyErr, PyResult};
 use crate::{
-    ffi, AsPyPointer, FromPy, FromPyObject, IntoPy, PyAny, PyNativeType, PyObject, Python,
-    ToBorrowedObject, ToPyObject,
+    exceptions, ffi, AsPyPointer, FromPy, FromPyObject, IntoPy, PyAny, PyNativeType, PyObject,
+    PyTryFrom, Python, ToBorrowedObject, ToPyObject,
 };
 use std::cmp;
 use std::collections::{BTreeSet, HashSet};
@@ -78,12 +78,18 @@ impl PySet {
     }
 
     /// Removes the element from the set if it is present.
-    pub fn discard<K>(&self, key: K)
+    ///
+    /// Returns whether the element was present.
+    pub fn discard<K>(&self, key: K) -> PyResult<bool>
     where
         K: ToPyObject,
     {
         key.with_borrowed_ptr(self.py(), |key| unsafe {
-            ffi::PySet_Discard(self.as_ptr(), key);
+            match ffi::PySet_Discard(self.as_ptr(), key) {
+                1 => Ok(true),
+                0 => Ok(false),
+                _ => Err(PyErr::fetch(self.py())),
+            }
         })
     }
 
@@ -98,15 +104,70 @@ impl PySet {
     }
 
     /// Removes and returns an arbitrary element from the set.
-    pub fn pop(&self) -> Option<PyObject> {
+    ///
+    /// Returns `None` if the set is empty.
+    pub fn pop(&self) -> PyResult<Option<PyObject>> {
         let element =
             unsafe { PyObject::from_owned_ptr_or_err(self.py(), ffi::PySet_Pop(self.as_ptr())) };
         match element {
-            Ok(e) => Some(e),
-            Err(_) => None,
+            Ok(e) => Ok(Some(e)),
+            Err(e) if self.is_empty() && e.is_instance::<exceptions::KeyError>(self.py()) => {
+                Ok(None)
+            }
+            Err(e) => Err(e),
         }
     }
 
+    /// Returns a new set with the elements of `self` and of the iterable `other`.
+    ///
+    /// This is equivalent to the Python expression `self.union(other)`.
+    pub fn union(&self, other: &PyAny) -> PyResult<&PySet> {
+        set_operation(self, "union", other)
+    }
+
+    /// Returns a new set with the elements common to `self` and the iterable `other`.
+    ///
+    /// This is equivalent to the Python expression `self.intersection(other)`.
+    pub fn intersection(&self, other: &PyAny) -> PyResult<&PySet> {
+        set_operation(self, "intersection", other)
+    }
+
+    /// Returns a new set with the elements of `self` that are not in the iterable `other`.
+    ///
+    /// This is equivalent to the Python expression `self.difference(other)`.
+    pub fn difference(&self, other: &PyAny) -> PyResult<&PySet> {
+        set_operation(self, "difference", other)
+    }
+
+    /// Returns a new set with the elements in either `self` or the iterable `other`, but not
+    /// in both.
+    ///
+    /// This is equivalent to the Python expression `self.symmetric_difference(other)`.
+    pub fn symmetric_difference(&self, other: &PyAny) -> PyResult<&PySet> {
+        set_operation(self, "symmetric_difference", other)
+    }
+
+    /// Checks whether every element of `self` is in the iterable `other`.
+    ///
+    /// This is equivalent to the Python expression `self.issubset(other)`.
+    pub fn is_subset(&self, other: &PyAny) -> PyResult<bool> {
+        set_predicate(self, "issubset", other)
+    }
+
+    /// Checks whether every element of the iterable `other` is in `self`.
+    ///
+    /// This is equivalent to the Python expression `self.issuperset(other)`.
+    pub fn is_superset(&self, other: &PyAny) -> PyResult<bool> {
+        set_predicate(self, "issuperset", other)
+    }
+
+    /// Checks whether `self` has no elements in common with the iterable `other`.
+    ///
+    /// This is equivalent to the Python expression `self.isdisjoint(other)`.
+    pub fn is_disjoint(&self, other: &PyAny) -> PyResult<bool> {
+        set_predicate(self, "isdisjoint", other)
+    }
+
     /// Returns an iterator of values in this set.
     ///
     /// Note that it can be unsafe to use when the set might be changed by other code.
@@ -172,7 +233,7 @@ where
 
 impl<T> ToPyObject for collections::BTreeSet<T>
 where
-    T: hash::Hash + Eq + ToPyObject,
+    T: ToPyObject,
 {
     fn to_object(&self, py: Python) -> PyObject {
         let set = PySet::new::<T>(py, &[]).expect("Failed to construct empty set");
@@ -201,20 +262,21 @@ where
     }
 }
 
+/// A `HashSet` can be extracted from any iterable except `str` and `bytes`, like the Python
+/// expression `set(iterable)`.
 impl<'source, K, S> FromPyObject<'source> for HashSet<K, S>
 where
     K: FromPyObject<'source> + cmp::Eq + hash::Hash,
     S: hash::BuildHasher + Default,
 {
     fn extract(ob: &'source PyAny) -> PyResult<Self> {
-        let set: &PySet = ob.downcast()?;
-        set.iter().map(K::extract).collect()
+        extract_set_items(ob, "HashSet")
     }
 }
 
 impl<K> FromPy<BTreeSet<K>> for PyObject
 where
-    K: IntoPy<PyObject> + cmp::Ord + ToPyObject,
+    K: IntoPy<PyObject>,
 {
     fn from_py(src: BTreeSet<K>, py: Python) -> Self {
         let set = PySet::empty(py).expect("Failed to construct empty set");
@@ -227,16 +289,38 @@ where
     }
 }
 
+/// A `BTreeSet` can be extracted from any iterable except `str` and `bytes`, like the Python
+/// expression `set(iterable)`.
 impl<'source, K> FromPyObject<'source> for BTreeSet<K>
 where
     K: FromPyObject<'source> + cmp::Ord,
 {
     fn extract(ob: &'source PyAny) -> PyResult<Self> {
-        let set: &PySet = ob.downcast()?;
-        set.iter().map(K::extract).collect()
+        extract_set_items(ob, "BTreeSet")
     }
 }
 
+/// Collects the items of the iterable `ob` into the set type `C`.
+///
+/// `str` and `bytes` are rejected, as collecting their characters or bytes into a set is
+/// rarely intended.
+fn extract_set_items<'source, K, C>(ob: &'source PyAny, set_type: &str) -> PyResult<C>
+where
+    K: FromPyObject<'source>,
+    C: std::iter::FromIterator<K>,
+{
+    let is_text =
+        unsafe { ffi::PyUnicode_Check(ob.as_ptr()) != 0 || ffi::PyBytes_Check(ob.as_ptr()) != 0 };
+    if is_text {
+        return Err(exceptions::TypeError::py_err(format!(
+            "Can't extract `{}` to `{}`",
+            ob.get_type().name(),
+            set_type
+        )));
+    }
+    ob.iter()?.map(|item| item?.extract()).collect()
+}
+
 impl PyFrozenSet {
     /// Creates a new frozenset.
     ///
@@ -278,6 +362,56 @@ impl PyFrozenSet {
         })
     }
 
+    /// Returns a new frozenset with the elements of `self` and of the iterable `other`.
+    ///
+    /// This is equivalent to the Python expression `self.union(other)`.
+    pub fn union(&self, other: &PyAny) -> PyResult<&PyFrozenSet> {
+        set_operation(self, "union", other)
+    }
+
+    /// Returns a new frozenset with the elements common to `self` and the iterable `other`.
+    ///
+    /// This is equivalent to the Python expression `self.intersection(other)`.
+    pub fn intersection(&self, other: &PyAny) -> PyResult<&PyFrozenSet> {
+        set_operation(self, "intersection", other)
+    }
+
+    /// Returns a new frozenset with the elements of `self` that are not in the iterable `other`.
+    ///
+    /// This is equivalent to the Python expression `self.difference(other)`.
+    pub fn difference(&self, other: &PyAny) -> PyResult<&PyFrozenSet> {
+        set_operation(self, "difference", other)
+    }
+
+    /// Returns a new frozenset with the elements in either `self` or the iterable `other`, but
+    /// not in both.
+    ///
+    /// This is equivalent to the Python expression `self.symmetric_difference(other)`.
+    pub fn symmetric_difference(&self, other: &PyAny) -> PyResult<&PyFrozenSet> {
+        set_operation(self, "symmetric_difference", other)
+    }
+
+    /// Checks whether every element of `self` is in the iterable `other`.
+    ///
+    /// This is equivalent to the Python expression `self.issubset(other)`.
+    pub fn is_subset(&self, other: &PyAny) -> PyResult<bool> {
+        set_predicate(self, "issubset", other)
+    }
+
+    /// Checks whether every element of the iterable `other` is in `self`.
+    ///
+    /// This is equivalent to the Python expression `self.issuperset(other)`.
+    pub fn is_superset(&self, other: &PyAny) -> PyResult<bool> {
+        set_predicate(self, "issuperset", other)
+    }
+
+    /// Checks whether `self` has no elements in common with the iterable `other`.
+    ///
+    /// This is equivalent to the Python expression `self.isdisjoint(other)`.
+    pub fn is_disjoint(&self, other: &PyAny) -> PyResult<bool> {
+        set_predicate(self, "isdisjoint", other)
+    }
+
     /// Returns an iterator of values in this frozen set.
     ///
     /// Note that it can be unsafe to use when the set might be changed by other code.
@@ -300,10 +434,25 @@ impl<'a> std::iter::IntoIterator for &'a PyFrozenSet {
     }
 }
 
+/// Calls the set method `name` returning a new set of type `T`.
+fn set_operation<'py, T>(set: &'py PyAny, name: &str, other: &PyAny) -> PyResult<&'py T>
+where
+    for<'a> T: PyTryFrom<'a>,
+{
+    Ok(set.call_method1(name, (other,))?.downcast()?)
+}
+
+/// Calls the set method `name` returning a bool.
+fn set_predicate(set: &PyAny, name: &str, other: &PyAny) -> PyResult<bool> {
+    set.call_method1(name, (other,))?.is_true()
+}
+
 #[cfg(test)]
 mod test {
     use super::{PyFrozenSet, PySet};
-    use crate::{AsPyRef, IntoPy, PyObject, PyTryFrom, Python, ToPyObject};
+    use crate::exceptions::TypeError;
+    use crate::types::{PyAny, PyList};
+    use crate::{AsPyRef, IntoPy, PyObject, PyResult, PyTryFrom, Python, ToPyObject};
     use std::collections::{BTreeSet, HashSet};
     use std::iter::FromIterator;
 
@@ -365,10 +514,12 @@ mod test {
         let gil = Python::acquire_gil();
         let py = gil.python();
         let set = PySet::new(py, &[1]).unwrap();
-        set.discard(2);
+        assert!(!set.discard(2).unwrap());
         assert_eq!(1, set.len());
-        set.discard(1);
+        assert!(set.discard(1).unwrap());
         assert_eq!(0, set.len());
+        assert!(!set.discard(1).unwrap());
+        assert!(set.discard(PyList::empty(py)).is_err());
     }
 
     #[test]
@@ -385,15 +536,105 @@ mod test {
         let gil = Python::acquire_gil();
         let py = gil.python();
         let set = PySet::new(py, &[1]).unwrap();
-        let val = set.pop();
-        assert!(val.is_some());
-        let val2 = set.pop();
+        let val = set.pop().unwrap().unwrap();
+        assert_eq!(1, val.extract::<i32>(py).unwrap());
+        let val2 = set.pop().unwrap();
         assert!(val2.is_none());
         assert!(py
             .eval("print('Exception state should not be set.')", None, None)
             .is_ok());
     }
 
+    fn extract_set(set: &PyAny) -> BTreeSet<i32> {
+        set.iter()
+            .unwrap()
+            .map(|item| item.unwrap().extract().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_set_algebra() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let set = PySet::new(py, &[1, 2, 3]).unwrap();
+        let other = PySet::new(py, &[3, 4]).unwrap();
+        let list = PyList::new(py, &[2, 5]);
+
+        let expected = BTreeSet::from_iter(vec![1, 2, 3, 4]);
+        assert_eq!(expected, extract_set(set.union(other).unwrap()));
+        let expected = BTreeSet::from_iter(vec![1, 2, 3, 5]);
+        assert_eq!(expected, extract_set(set.union(list).unwrap()));
+
+        let expected = BTreeSet::from_iter(vec![3]);
+        assert_eq!(expected, extract_set(set.intersection(other).unwrap()));
+        let expected = BTreeSet::from_iter(vec![2]);
+        assert_eq!(expected, extract_set(set.intersection(list).unwrap()));
+
+        let expected = BTreeSet::from_iter(vec![1, 2]);
+        assert_eq!(expected, extract_set(set.difference(other).unwrap()));
+        let expected = BTreeSet::from_iter(vec![1, 3]);
+        assert_eq!(expected, extract_set(set.difference(list).unwrap()));
+
+        let expected = BTreeSet::from_iter(vec![1, 2, 4]);
+        assert_eq!(
+            expected,
+            extract_set(set.symmetric_difference(other).unwrap())
+        );
+        let expected = BTreeSet::from_iter(vec![1, 3, 5]);
+        assert_eq!(
+            expected,
+            extract_set(set.symmetric_difference(list).unwrap())
+        );
+
+        // The operands are left unchanged
+        assert_eq!(3, set.len());
+        assert_eq!(2, other.len());
+
+        assert!(set.union(py.None().as_ref(py)).is_err());
+        assert!(set.union(PyList::new(py, &[PyList::empty(py)])).is_err());
+    }
+
+    #[test]
+    fn test_set_comparisons() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let set = PySet::new(py, &[1, 2]).unwrap();
+        let superset = PyList::new(py, &[1, 2, 3]);
+        let disjoint = PySet::new(py, &[3, 4]).unwrap();
+
+        assert!(set.is_subset(superset).unwrap());
+        assert!(!set.is_superset(superset).unwrap());
+        assert!(set.is_superset(PyList::new(py, &[2])).unwrap());
+        assert!(set.is_subset(set).unwrap());
+        assert!(set.is_superset(set).unwrap());
+        assert!(set.is_disjoint(disjoint).unwrap());
+        assert!(!set.is_disjoint(superset).unwrap());
+        assert!(set.is_subset(py.None().as_ref(py)).is_err());
+    }
+
+    #[test]
+    fn test_set_algebra_empty() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let set = PySet::new(py, &[1, 2]).unwrap();
+        let empty = PySet::empty(py).unwrap();
+        let empty_list = PyList::empty(py);
+
+        assert_eq!(2, set.union(empty_list).unwrap().len());
+        assert!(set.intersection(empty).unwrap().is_empty());
+        assert_eq!(2, set.difference(empty_list).unwrap().len());
+        assert!(empty.difference(set).unwrap().is_empty());
+        assert_eq!(2, empty.symmetric_difference(set).unwrap().len());
+        assert!(empty.union(empty_list).unwrap().is_empty());
+
+        assert!(empty.is_subset(set).unwrap());
+        assert!(empty.is_subset(empty_list).unwrap());
+        assert!(set.is_superset(empty_list).unwrap());
+        assert!(!empty.is_superset(set).unwrap());
+        assert!(empty.is_disjoint(set).unwrap());
+        assert!(empty.is_disjoint(empty_list).unwrap());
+    }
+
     #[test]
     fn test_set_iter() {
         let gil = Python::acquire_gil();
@@ -458,6 +699,38 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_frozenset_algebra() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let set = PyFrozenSet::new(py, &[1, 2, 3]).unwrap();
+        let other = PySet::new(py, &[3, 4]).unwrap();
+        let list = PyList::new(py, &[2, 5]);
+
+        let union = set.union(other).unwrap();
+        assert_eq!(BTreeSet::from_iter(vec![1, 2, 3, 4]), extract_set(union));
+        let expected = BTreeSet::from_iter(vec![2]);
+        assert_eq!(expected, extract_set(set.intersection(list).unwrap()));
+        let expected = BTreeSet::from_iter(vec![1, 2]);
+        assert_eq!(expected, extract_set(set.difference(other).unwrap()));
+        let expected = BTreeSet::from_iter(vec![1, 3, 5]);
+        assert_eq!(
+            expected,
+            extract_set(set.symmetric_difference(list).unwrap())
+        );
+
+        assert!(set.is_superset(list).is_ok());
+        assert!(!set.is_superset(list).unwrap());
+        assert!(set.is_superset(PyList::new(py, &[1, 3])).unwrap());
+        assert!(set.is_subset(union).unwrap());
+        assert!(!set.is_disjoint(other).unwrap());
+
+        let empty = PyFrozenSet::new::<i32>(py, &[]).unwrap();
+        assert!(empty.union(PyList::empty(py)).unwrap().is_empty());
+        assert!(empty.is_subset(set).unwrap());
+        assert!(empty.is_disjoint(set).unwrap());
+    }
+
     #[test]
     fn test_extract_hashset() {
         let gil = Python::acquire_gil();
@@ -498,4 +771,69 @@ mod test {
         assert_eq!(bt, bto.extract(py).unwrap());
         assert_eq!(hs, hso.extract(py).unwrap());
     }
+
+    /// A value which is only `Ord`, not `Hash`.
+    #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+    struct OrdOnly(i32);
+
+    impl ToPyObject for OrdOnly {
+        fn to_object(&self, py: Python) -> PyObject {
+            self.0.to_object(py)
+        }
+    }
+
+    impl IntoPy<PyObject> for OrdOnly {
+        fn into_py(self, py: Python) -> PyObject {
+            self.0.into_py(py)
+        }
+    }
+
+    #[test]
+    fn test_btreeset_ord_only() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let set: BTreeSet<OrdOnly> = vec![OrdOnly(1), OrdOnly(2)].into_iter().collect();
+        let expected: BTreeSet<i32> = BTreeSet::from_iter(vec![1, 2]);
+        assert_eq!(expected, set.to_object(py).extract(py).unwrap());
+        let set: PyObject = set.into_py(py);
+        assert_eq!(expected, set.extract(py).unwrap());
+    }
+
+    fn check_extract_iterable<C>(py: Python, expected: C)
+    where
+        C: for<'a> crate::FromPyObject<'a> + PartialEq + std::fmt::Debug,
+    {
+        let extract = |code| -> PyResult<C> { py.eval(code, None, None).unwrap().extract() };
+        assert_eq!(extract("{3, 1, 2}").unwrap(), expected);
+        assert_eq!(extract("frozenset([1, 2, 3])").unwrap(), expected);
+        assert_eq!(extract("[3, 1, 2, 1, 3]").unwrap(), expected);
+        assert_eq!(extract("(1, 2, 3)").unwrap(), expected);
+        assert_eq!(extract("(i + 1 for i in range(3))").unwrap(), expected);
+        assert_eq!(extract("{1: 'a', 2: 'b', 3: 'c'}").unwrap(), expected);
+
+        for code in &["1", "[1, 'a']", "'abc'", "b'abc'", "''"] {
+            let err = extract(code).unwrap_err();
+            assert!(err.is_instance::<TypeError>(py), "{}", code);
+        }
+    }
+
+    #[test]
+    fn test_btreeset_extract_iterable() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        check_extract_iterable(py, BTreeSet::from_iter(vec![1, 2, 3]));
+        let empty: BTreeSet<i32> = py.eval("[]", None, None).unwrap().extract().unwrap();
+        assert!(empty.is_empty());
+        let chars: PyResult<BTreeSet<String>> = py.eval("'abc'", None, None).unwrap().extract();
+        assert!(chars.is_err());
+    }
+
+    #[test]
+    fn test_hashset_extract_iterable() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        check_extract_iterable::<HashSet<i32>>(py, HashSet::from_iter(vec![1, 2, 3]));
+        let chars: PyResult<HashSet<String>> = py.eval("'abc'", None, None).unwrap().extract();
+        assert!(chars.is_err());
+    }
 }
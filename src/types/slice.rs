@@ -20,6 +20,7 @@ pyobject_native_type!(
 );
 
 /// Represents Python `slice` indices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct PySliceIndices {
     pub start: isize,
     pub stop: isize,
@@ -54,32 +55,35 @@ impl PySlice {
     /// Retrieves the start, stop, and step indices from the slice object,
     /// assuming a sequence of length `length`, and stores the length of the
     /// slice in its `slicelength` member.
+    ///
+    /// Out of range indices are clipped like in Python, so `slicelength` is zero for empty
+    /// slices. With a negative step, `start` is the index of the first item taken and `stop`
+    /// lies before the last one, e.g. `-1` for a slice reaching the start of the sequence.
     #[inline]
-    pub fn indices(&self, length: c_long) -> PyResult<PySliceIndices> {
-        // non-negative Py_ssize_t should always fit into Rust usize
-        unsafe {
-            let slicelength: isize = 0;
-            let start: isize = 0;
-            let stop: isize = 0;
-            let step: isize = 0;
-            let r = ffi::PySlice_GetIndicesEx(
+    pub fn indices(&self, length: isize) -> PyResult<PySliceIndices> {
+        let mut slicelength: Py_ssize_t = 0;
+        let mut start: Py_ssize_t = 0;
+        let mut stop: Py_ssize_t = 0;
+        let mut step: Py_ssize_t = 0;
+        let r = unsafe {
+            ffi::PySlice_GetIndicesEx(
                 self.as_ptr(),
                 length as Py_ssize_t,
-                &start as *const _ as *mut _,
-                &stop as *const _ as *mut _,
-                &step as *const _ as *mut _,
-                &slicelength as *const _ as *mut _,
-            );
-            if r == 0 {
-                Ok(PySliceIndices {
-                    start,
-                    stop,
-                    step,
-                    slicelength,
-                })
-            } else {
-                Err(PyErr::fetch(self.py()))
-            }
+                &mut start,
+                &mut stop,
+                &mut step,
+                &mut slicelength,
+            )
+        };
+        if r == 0 {
+            Ok(PySliceIndices {
+                start,
+                stop,
+                step,
+                slicelength,
+            })
+        } else {
+            Err(PyErr::fetch(self.py()))
         }
     }
 }
@@ -89,3 +93,76 @@ impl ToPyObject for PySliceIndices {
         PySlice::new(py, self.start, self.stop, self.step).into()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::exceptions::ValueError;
+    use crate::types::{PySlice, PySliceIndices};
+    use crate::Python;
+
+    fn indices(py: Python, code: &str, length: isize) -> PySliceIndices {
+        let slice: &PySlice = py.eval(code, None, None).unwrap().downcast().unwrap();
+        slice.indices(length).unwrap()
+    }
+
+    #[test]
+    fn test_indices() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let slice = PySlice::new(py, 1, 10, 2);
+        let expected = PySliceIndices {
+            start: 1,
+            stop: 10,
+            step: 2,
+            slicelength: 5,
+        };
+        assert_eq!(expected, slice.indices(100).unwrap());
+
+        let expected = PySliceIndices {
+            start: 1,
+            stop: 4,
+            step: 2,
+            slicelength: 2,
+        };
+        assert_eq!(expected, slice.indices(4).unwrap());
+        assert_eq!(expected, indices(py, "slice(-3, None, 2)", 4));
+    }
+
+    #[test]
+    fn test_indices_negative_step() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let expected = PySliceIndices {
+            start: 4,
+            stop: -1,
+            step: -1,
+            slicelength: 5,
+        };
+        assert_eq!(expected, indices(py, "slice(None, None, -1)", 5));
+
+        let expected = PySliceIndices {
+            start: 4,
+            stop: 0,
+            step: -2,
+            slicelength: 2,
+        };
+        assert_eq!(expected, indices(py, "slice(10, 0, -2)", 5));
+    }
+
+    #[test]
+    fn test_indices_empty() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        assert_eq!(0, indices(py, "slice(3, 1)", 5).slicelength);
+        assert_eq!(0, indices(py, "slice(1, 3, -1)", 5).slicelength);
+        assert_eq!(0, indices(py, "slice(10, 20)", 5).slicelength);
+        assert_eq!(0, indices(py, "slice(None)", 0).slicelength);
+
+        let slice: &PySlice = py
+            .eval("slice(None, None, 0)", None, None)
+            .unwrap()
+            .downcast()
+            .unwrap();
+        assert!(slice.indices(5).unwrap_err().is_instance::<ValueError>(py));
+    }
+}
@@ -6,7 +6,7 @@ use crate::{
     PyTryFrom, Python, ToPyObject,
 };
 use std::borrow::Cow;
-use std::ffi::CStr;
+use std::ffi::CString;
 use std::os::raw::c_char;
 use std::str;
 
@@ -23,18 +23,72 @@ impl PyString {
     ///
     /// Panics if out of memory.
     pub fn new<'p>(py: Python<'p>, s: &str) -> &'p PyString {
+        unsafe { py.from_owned_ptr(new_string(s)) }
+    }
+
+    /// Creates a new interned Python string object.
+    ///
+    /// Interned strings with the same contents are the same object, which makes them cheaper to
+    /// compare and to use as dictionary keys, e.g. for attribute names.
+    ///
+    /// Panics if out of memory.
+    pub fn intern<'p>(py: Python<'p>, s: &str) -> &'p PyString {
         let ptr = s.as_ptr() as *const c_char;
         let len = s.len() as ffi::Py_ssize_t;
-        unsafe { py.from_owned_ptr(ffi::PyUnicode_FromStringAndSize(ptr, len)) }
+        unsafe {
+            let mut ob = ffi::PyUnicode_FromStringAndSize(ptr, len);
+            if !ob.is_null() {
+                ffi::PyUnicode_InternInPlace(&mut ob);
+            }
+            py.from_owned_ptr(ob)
+        }
     }
 
+    /// Decodes a bytes-like object into a Python string object.
+    ///
+    /// This is equivalent to the Python expression `str(src, encoding, errors)`. For example,
+    /// decoding with the `surrogateescape` error handler maps undecodable bytes to unpaired
+    /// surrogates, which [PyString::encode] can turn back into the original bytes.
     pub fn from_object<'p>(src: &'p PyAny, encoding: &str, errors: &str) -> PyResult<&'p PyString> {
+        let encoding = CString::new(encoding)?;
+        let errors = CString::new(errors)?;
         unsafe {
             src.py()
                 .from_owned_ptr_or_err::<PyString>(ffi::PyUnicode_FromEncodedObject(
                     src.as_ptr(),
-                    encoding.as_ptr() as *const c_char,
-                    errors.as_ptr() as *const c_char,
+                    encoding.as_ptr(),
+                    errors.as_ptr(),
+                ))
+        }
+    }
+
+    /// Encodes the Python string into a bytes object.
+    ///
+    /// This is equivalent to the Python expression `self.encode(encoding, errors)`. In
+    /// particular, encoding with the `surrogateescape` error handler recovers the original bytes
+    /// of a string containing unpaired surrogates produced by `os.fsdecode`.
+    ///
+    /// # Example
+    /// ```
+    /// # use pyo3::prelude::*;
+    /// # use pyo3::types::PyString;
+    /// # let gil = Python::acquire_gil();
+    /// # let py = gil.python();
+    /// let s: &PyString = py.eval(r"'abc\udc80'", None, None)?.downcast()?;
+    /// assert!(s.to_str().is_err());
+    /// let bytes = s.encode("utf-8", "surrogateescape")?;
+    /// assert_eq!(bytes.as_bytes(), b"abc\x80");
+    /// # Ok::<(), PyErr>(())
+    /// ```
+    pub fn encode(&self, encoding: &str, errors: &str) -> PyResult<&PyBytes> {
+        let encoding = CString::new(encoding)?;
+        let errors = CString::new(errors)?;
+        unsafe {
+            self.py()
+                .from_owned_ptr_or_err(ffi::PyUnicode_AsEncodedString(
+                    self.as_ptr(),
+                    encoding.as_ptr(),
+                    errors.as_ptr(),
                 ))
         }
     }
@@ -56,11 +110,25 @@ impl PyString {
         }
     }
 
+    /// Gets the Python string as a Rust string slice, without copying it.
+    ///
+    /// The UTF-8 representation is cached inside the Python string object, so this only
+    /// encodes the string the first time it is called.
+    ///
+    /// Returns a `UnicodeEncodeError` if the input is not valid unicode
+    /// (containing unpaired surrogates).
+    pub fn to_str(&self) -> PyResult<&str> {
+        let bytes = self.as_bytes()?;
+        // PyUnicode_AsUTF8AndSize only succeeds for strings which can be encoded as UTF-8
+        Ok(unsafe { str::from_utf8_unchecked(bytes) })
+    }
+
     /// Converts the `PyString` into a Rust string.
+    ///
+    /// Returns a `UnicodeEncodeError` if the input is not valid unicode
+    /// (containing unpaired surrogates).
     pub fn to_string(&self) -> PyResult<Cow<str>> {
-        let bytes = self.as_bytes()?;
-        let string = std::str::from_utf8(bytes)?;
-        Ok(Cow::Borrowed(string))
+        self.to_str().map(Cow::Borrowed)
     }
 
     /// Converts the `PyString` into a Rust string.
@@ -71,16 +139,9 @@ impl PyString {
         match self.to_string() {
             Ok(s) => s,
             Err(_) => {
-                let bytes = unsafe {
-                    self.py()
-                        .from_owned_ptr::<PyBytes>(ffi::PyUnicode_AsEncodedString(
-                            self.as_ptr(),
-                            CStr::from_bytes_with_nul(b"utf-8\0").unwrap().as_ptr(),
-                            CStr::from_bytes_with_nul(b"surrogatepass\0")
-                                .unwrap()
-                                .as_ptr(),
-                        ))
-                };
+                let bytes = self
+                    .encode("utf-8", "surrogatepass")
+                    .expect("Failed to encode string with surrogatepass");
                 String::from_utf8_lossy(bytes.as_bytes())
             }
         }
@@ -89,6 +150,38 @@ impl PyString {
 
 /// Converts a Rust `str` to a Python object.
 /// See `PyString::new` for details on the conversion.
+/// Creates a Python string object with the contents of `s`, and returns an owned pointer to it.
+///
+/// ASCII strings, such as identifiers, are copied directly into a new compact ASCII string,
+/// instead of being decoded from UTF-8.
+#[cfg(all(Py_3_7, not(Py_LIMITED_API), not(PyPy)))]
+unsafe fn new_string(s: &str) -> *mut ffi::PyObject {
+    if !s.is_ascii() {
+        return new_string_from_utf8(s);
+    }
+    let ptr = ffi::PyUnicode_New(s.len() as ffi::Py_ssize_t, 127);
+    if ptr.is_null() {
+        return ptr;
+    }
+    // The UTF-8 representation of a compact ASCII string is its character data, which
+    // `PyUnicode_New` allocated and terminated with a NUL, so this cannot fail.
+    let data = ffi::PyUnicode_AsUTF8AndSize(ptr, std::ptr::null_mut()) as *mut u8;
+    debug_assert!(!data.is_null());
+    std::ptr::copy_nonoverlapping(s.as_ptr(), data, s.len());
+    ptr
+}
+
+#[cfg(not(all(Py_3_7, not(Py_LIMITED_API), not(PyPy))))]
+unsafe fn new_string(s: &str) -> *mut ffi::PyObject {
+    new_string_from_utf8(s)
+}
+
+unsafe fn new_string_from_utf8(s: &str) -> *mut ffi::PyObject {
+    let ptr = s.as_ptr() as *const c_char;
+    let len = s.len() as ffi::Py_ssize_t;
+    ffi::PyUnicode_FromStringAndSize(ptr, len)
+}
+
 impl ToPyObject for str {
     #[inline]
     fn to_object(&self, py: Python) -> PyObject {
@@ -146,14 +239,7 @@ impl<'source> crate::FromPyObject<'source> for Cow<'source, str> {
 /// Accepts Python `str` and `unicode` objects.
 impl<'a> crate::FromPyObject<'a> for &'a str {
     fn extract(ob: &'a PyAny) -> PyResult<Self> {
-        let s: Cow<'a, str> = crate::FromPyObject::extract(ob)?;
-        match s {
-            Cow::Borrowed(r) => Ok(r),
-            Cow::Owned(r) => {
-                let r = ob.py().register_any(r);
-                Ok(r.as_str())
-            }
-        }
+        <PyString as PyTryFrom>::try_from(ob)?.to_str()
     }
 }
 
@@ -170,12 +256,58 @@ impl<'source> FromPyObject<'source> for String {
 #[cfg(test)]
 mod test {
     use super::PyString;
+    use crate::exceptions::UnicodeEncodeError;
     use crate::instance::AsPyRef;
     use crate::object::PyObject;
     use crate::Python;
-    use crate::{FromPyObject, PyTryFrom, ToPyObject};
+    use crate::{AsPyPointer, FromPyObject, PyTryFrom, ToPyObject};
     use std::borrow::Cow;
 
+    #[test]
+    fn test_new_matches_utf8_decoding() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let cases = [
+            "",
+            "a",
+            "snake_case_identifier",
+            "embedded\0nul\0",
+            "\x7f",
+            "caf\u{e9}",
+            "\u{ff}",
+            "\u{3b1}\u{3b2}\u{3b3}",
+            "crab \u{1f980}",
+        ];
+        for &case in cases.iter() {
+            let fast = PyString::new(py, case);
+            let decoded: &PyString = unsafe {
+                py.from_owned_ptr(crate::ffi::PyUnicode_FromStringAndSize(
+                    case.as_ptr() as *const _,
+                    case.len() as crate::ffi::Py_ssize_t,
+                ))
+            };
+            // The strings use the same representation, e.g. compact ASCII. This is checked first,
+            // as converting a string to UTF-8 caches the result in the string.
+            let sizeof = |s: &PyString| -> usize {
+                py.import("sys")
+                    .unwrap()
+                    .call1("getsizeof", (s,))
+                    .unwrap()
+                    .extract()
+                    .unwrap()
+            };
+            assert_eq!(sizeof(fast), sizeof(decoded), "{:?}", case);
+            assert_eq!(fast.hash().unwrap(), decoded.hash().unwrap());
+            assert_eq!(case, fast.to_str().unwrap());
+            assert_eq!(case.chars().count(), fast.len().unwrap());
+            assert_eq!(std::cmp::Ordering::Equal, fast.compare(decoded).unwrap());
+            assert_eq!(
+                case.is_ascii(),
+                fast.call_method1("encode", ("ascii",)).is_ok()
+            );
+        }
+    }
+
     #[test]
     fn test_non_bmp() {
         let gil = Python::acquire_gil();
@@ -237,6 +369,84 @@ mod test {
         assert_eq!(Cow::Borrowed(s), py_string.to_string().unwrap());
     }
 
+    #[test]
+    fn test_to_str() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let s = "Hello 🐈 \u{1F30F}";
+        let py_string = PyString::new(py, s);
+        assert_eq!(s, py_string.to_str().unwrap());
+        // The UTF-8 representation is cached in the string object
+        assert_eq!(
+            py_string.to_str().unwrap().as_ptr(),
+            py_string.to_str().unwrap().as_ptr()
+        );
+    }
+
+    #[test]
+    fn test_to_str_surrogate() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let obj: PyObject = py.eval(r#"'\udc80'"#, None, None).unwrap().into();
+        let py_string = <PyString as PyTryFrom>::try_from(obj.as_ref(py)).unwrap();
+        let err = py_string.to_str().unwrap_err();
+        assert!(err.is_instance::<UnicodeEncodeError>(py));
+        assert!(py_string.to_string().is_err());
+        assert_eq!(py_string.to_string_lossy(), "\u{FFFD}\u{FFFD}\u{FFFD}");
+    }
+
+    #[test]
+    fn test_extract_surrogate() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let obj = py.eval(r#"'abc\udc80'"#, None, None).unwrap();
+
+        let err = obj.extract::<String>().unwrap_err();
+        assert!(err.is_instance::<UnicodeEncodeError>(py));
+        let value = err.to_object(py);
+        let value = value.as_ref(py);
+        assert_eq!(
+            3,
+            value.getattr("start").unwrap().extract::<usize>().unwrap()
+        );
+        assert_eq!(4, value.getattr("end").unwrap().extract::<usize>().unwrap());
+        assert!(obj
+            .extract::<&str>()
+            .unwrap_err()
+            .is_instance::<UnicodeEncodeError>(py));
+
+        assert_eq!("abc\u{FFFD}\u{FFFD}\u{FFFD}", obj.extract_lossy().unwrap());
+        assert!(py.None().as_ref(py).extract_lossy().is_err());
+    }
+
+    #[test]
+    fn test_surrogateescape_round_trip() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let obj = py.eval(r#"'\udc80'"#, None, None).unwrap();
+        let py_string = <PyString as PyTryFrom>::try_from(obj).unwrap();
+
+        let bytes = py_string.encode("utf-8", "surrogateescape").unwrap();
+        assert_eq!(b"\x80", bytes.as_bytes());
+        let decoded = PyString::from_object(bytes, "utf-8", "surrogateescape").unwrap();
+        assert!(decoded.compare(py_string).unwrap() == std::cmp::Ordering::Equal);
+
+        assert!(py_string.encode("utf-8", "strict").is_err());
+        assert!(PyString::from_object(bytes, "utf-8", "strict").is_err());
+        assert!(py_string.encode("utf-8\0", "strict").is_err());
+    }
+
+    #[test]
+    fn test_intern() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let s = "a rather long string which is not interned automatically";
+        let interned = PyString::intern(py, s);
+        assert_eq!(s, interned.to_str().unwrap());
+        assert_eq!(interned.as_ptr(), PyString::intern(py, s).as_ptr());
+        assert_ne!(interned.as_ptr(), PyString::new(py, s).as_ptr());
+    }
+
     #[test]
     fn test_to_string_lossy() {
         let gil = Python::acquire_gil();
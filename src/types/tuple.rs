@@ -1,6 +1,7 @@
 // Copyright (c) 2017-present PyO3 Project and Contributors
 
 use crate::ffi::{self, Py_ssize_t};
+use crate::types::sequence::{fill_new_sequence, get_ssize_index, normalize_index};
 use crate::{
     exceptions, AsPyPointer, FromPy, FromPyObject, IntoPy, IntoPyPointer, Py, PyAny, PyErr,
     PyNativeType, PyObject, PyResult, PyTryFrom, Python, ToPyObject,
@@ -22,15 +23,30 @@ impl PyTuple {
         T: ToPyObject,
         U: ExactSizeIterator<Item = T>,
     {
-        let elements_iter = elements.into_iter();
-        let len = elements_iter.len();
-        unsafe {
-            let ptr = ffi::PyTuple_New(len as Py_ssize_t);
-            for (i, e) in elements_iter.enumerate() {
-                ffi::PyTuple_SetItem(ptr, i as Py_ssize_t, e.to_object(py).into_ptr());
-            }
-            py.from_owned_ptr(ptr)
-        }
+        let elements = elements.into_iter().map(|e| Ok(e.to_object(py)));
+        unsafe { py.from_owned_ptr(new_tuple(py, elements).expect("failed to create tuple")) }
+    }
+
+    /// Constructs a new tuple from the results of a fallible conversion, such as
+    /// `values.iter().map(|v| convert(v))`.
+    ///
+    /// The tuple is allocated at its final size before the items are converted, so the results
+    /// are never collected into an intermediate `Vec`. Returns the first error yielded by
+    /// `elements`, without consuming the rest.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `elements` yields more or fewer items than its reported length.
+    pub fn new_from_exact_iter<T, U>(
+        py: Python,
+        elements: impl IntoIterator<Item = PyResult<T>, IntoIter = U>,
+    ) -> PyResult<&PyTuple>
+    where
+        T: IntoPy<PyObject>,
+        U: ExactSizeIterator<Item = PyResult<T>>,
+    {
+        let elements = elements.into_iter().map(|e| e.map(|e| e.into_py(py)));
+        unsafe { Ok(py.from_owned_ptr(new_tuple(py, elements)?)) }
     }
 
     /// Constructs an empty tuple (on the Python side, a singleton object).
@@ -52,10 +68,22 @@ impl PyTuple {
     }
 
     /// Takes a slice of the tuple pointed from `low` to `high` and returns it as a new tuple.
+    #[deprecated(since = "0.12.0", note = "use PyTuple::get_slice instead")]
     pub fn slice(&self, low: isize, high: isize) -> &PyTuple {
+        self.get_slice(low.max(0) as usize, high.max(0) as usize)
+    }
+
+    /// Takes the slice `self[low:high]` and returns it as a new tuple.
+    ///
+    /// Indices must be nonnegative, and out of range indices are clipped to the length of the
+    /// tuple, so the result is empty if `low >= high`.
+    pub fn get_slice(&self, low: usize, high: usize) -> &PyTuple {
         unsafe {
-            self.py()
-                .from_owned_ptr(ffi::PyTuple_GetSlice(self.as_ptr(), low, high))
+            self.py().from_owned_ptr(ffi::PyTuple_GetSlice(
+                self.as_ptr(),
+                get_ssize_index(low),
+                get_ssize_index(high),
+            ))
         }
     }
 
@@ -70,7 +98,8 @@ impl PyTuple {
 
     /// Gets the tuple item at the specified index.
     ///
-    /// Panics if the index is out of range.
+    /// Panics if the index is out of range; use [PyTuple::get_item_checked] to get an
+    /// `IndexError` instead.
     pub fn get_item(&self, index: usize) -> &PyAny {
         assert!(index < self.len());
         unsafe {
@@ -79,6 +108,20 @@ impl PyTuple {
         }
     }
 
+    /// Gets the tuple item at the specified index.
+    ///
+    /// Negative indices count from the end of the tuple, as in the Python expression
+    /// `self[index]`. Returns an `IndexError` if the index is out of range.
+    pub fn get_item_checked(&self, index: isize) -> PyResult<&PyAny> {
+        match normalize_index(index, self.len()) {
+            Some(index) => Ok(self.get_item(index)),
+            None => Err(PyErr::new_with_gil::<exceptions::IndexError, _>(
+                self.py(),
+                "tuple index out of range",
+            )),
+        }
+    }
+
     /// Returns `self` as a slice of objects.
     pub fn as_slice(&self) -> &[&PyAny] {
         // This is safe because &PyAny has the same memory layout as *mut ffi::PyObject,
@@ -181,7 +224,9 @@ macro_rules! tuple_conversion ({$length:expr,$(($refN:ident, $n:tt, $T:ident)),+
             let slice = t.as_slice();
             if t.len() == $length {
                 Ok((
-                    $(slice[$n].extract::<$T>()?,)+
+                    $(slice[$n].extract::<$T>().map_err(|e| {
+                        e.in_container::<Self>(obj.py(), || format!("tuple element {}", $n))
+                    })?,)+
                 ))
             } else {
                 Err(wrong_tuple_length(t, $length))
@@ -245,12 +290,47 @@ tuple_conversion!(
     (ref8, 8, I)
 );
 
+/// Creates a tuple from an iterator yielding exactly as many items as its reported length, and
+/// returns an owned pointer to it.
+fn new_tuple(
+    py: Python,
+    elements: impl ExactSizeIterator<Item = PyResult<PyObject>>,
+) -> PyResult<*mut ffi::PyObject> {
+    let len = elements.len();
+    unsafe {
+        let tuple = PyObject::from_owned_ptr_or_err(py, ffi::PyTuple_New(len as Py_ssize_t))?;
+        fill_new_sequence(tuple.as_ptr(), len, set_item, elements)?;
+        Ok(tuple.into_ptr())
+    }
+}
+
+/// Sets an item of a new tuple, stealing the reference to `item`.
+unsafe fn set_item(tuple: *mut ffi::PyObject, index: Py_ssize_t, item: *mut ffi::PyObject) {
+    #[cfg(not(Py_LIMITED_API))]
+    ffi::PyTuple_SET_ITEM(tuple, index, item);
+    #[cfg(Py_LIMITED_API)]
+    ffi::PyTuple_SetItem(tuple, index, item);
+}
+
 #[cfg(test)]
 mod test {
+    use crate::exceptions::IndexError;
     use crate::types::{PyAny, PyTuple};
     use crate::{AsPyRef, PyTryFrom, Python, ToPyObject};
     use std::collections::HashSet;
 
+    #[test]
+    fn test_new_from_exact_iter() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let tuple = PyTuple::new_from_exact_iter(py, vec![Ok("a"), Ok("b")]).unwrap();
+        assert_eq!(("a", "b"), tuple.extract::<(&str, &str)>().unwrap());
+
+        let err = PyTuple::new_from_exact_iter(py, vec![Ok(1), Err(IndexError::py_err("bad"))])
+            .unwrap_err();
+        assert!(err.is_instance::<IndexError>(py));
+    }
+
     #[test]
     fn test_new() {
         let gil = Python::acquire_gil();
@@ -290,6 +370,50 @@ mod test {
         assert_eq!(3, iter.next().unwrap().extract().unwrap());
     }
 
+    #[test]
+    fn test_get_item_checked() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let tuple = PyTuple::new(py, &[1, 2, 3]);
+        assert_eq!(
+            1,
+            tuple.get_item_checked(0).unwrap().extract::<i32>().unwrap()
+        );
+        assert_eq!(
+            3,
+            tuple
+                .get_item_checked(-1)
+                .unwrap()
+                .extract::<i32>()
+                .unwrap()
+        );
+        assert_eq!(
+            1,
+            tuple
+                .get_item_checked(-3)
+                .unwrap()
+                .extract::<i32>()
+                .unwrap()
+        );
+        for &index in &[3, -4] {
+            let err = tuple.get_item_checked(index).unwrap_err();
+            assert!(err.is_instance::<IndexError>(py));
+        }
+    }
+
+    #[test]
+    fn test_get_slice() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let tuple = PyTuple::new(py, &[1, 2, 3]);
+        let slice = tuple.get_slice(1, 3);
+        assert_eq!((2, 3), slice.extract::<(i32, i32)>().unwrap());
+        let slice = tuple.get_slice(1, usize::MAX);
+        assert_eq!((2, 3), slice.extract::<(i32, i32)>().unwrap());
+        assert!(tuple.get_slice(2, 1).is_empty());
+        assert!(tuple.get_slice(10, 20).is_empty());
+    }
+
     #[test]
     fn test_into_iter() {
         let gil = Python::acquire_gil();
@@ -5,7 +5,10 @@
 use crate::err::{PyErr, PyResult};
 use crate::instance::PyNativeType;
 use crate::type_object::PyTypeObject;
-use crate::{ffi, AsPyPointer, PyAny, Python};
+use crate::types::{PyDict, PyTuple};
+use crate::{ffi, AsPyPointer, PyAny, PyTryFrom, Python};
+#[cfg(feature = "macros")]
+use crate::{prelude::*, ToPyObject};
 use std::borrow::Cow;
 use std::ffi::CStr;
 
@@ -22,6 +25,86 @@ impl PyType {
         T::type_object(py)
     }
 
+    /// Creates a new Python class, like the `class` statement or `type(name, bases, namespace)`.
+    ///
+    /// The metaclass is the most derived metaclass of `bases`, and `bases` defaults to `object`
+    /// if it is empty. Unless `namespace` sets them, `__qualname__` is `name`, and `__module__`
+    /// is the `__name__` of the module of the Python code calling Rust like for `type`, or
+    /// `builtins` if Rust is not called from Python code.
+    ///
+    /// [PyType::builder] creates a class whose methods are Rust closures.
+    ///
+    /// # Example
+    /// ```
+    /// # use pyo3::prelude::*;
+    /// use pyo3::types::{IntoPyDict, PyTuple, PyType};
+    /// # let gil = Python::acquire_gil();
+    /// # let py = gil.python();
+    /// let namespace = [("answer", 42)].into_py_dict(py);
+    /// let class = PyType::create(py, "Question", PyTuple::empty(py), namespace)?;
+    /// let answer: i32 = class.call0()?.getattr("answer")?.extract()?;
+    /// assert_eq!(answer, 42);
+    /// # Ok::<(), PyErr>(())
+    /// ```
+    pub fn create<'p>(
+        py: Python<'p>,
+        name: &str,
+        bases: &PyTuple,
+        namespace: &PyDict,
+    ) -> PyResult<&'p PyType> {
+        let namespace = namespace.copy()?;
+        // `type` would take it from the frame of `types.new_class` instead.
+        if !namespace.contains("__module__")? {
+            let globals =
+                unsafe { py.from_borrowed_ptr_or_opt::<PyDict>(ffi::PyEval_GetGlobals()) };
+            match globals.and_then(|globals| globals.get_item("__name__")) {
+                Some(module) => namespace.set_item("__module__", module)?,
+                None => namespace.set_item("__module__", "builtins")?,
+            }
+        }
+        // `types.new_class` selects the metaclass and calls its `__prepare__` like the `class`
+        // statement, and the namespace it prepares is then updated with `namespace`.
+        let fill_namespace = py
+            .import("operator")?
+            .call1("methodcaller", ("update", namespace))?;
+        let class = py
+            .import("types")?
+            .call1("new_class", (name, bases, py.None(), fill_namespace))?;
+        Ok(<PyType as PyTryFrom>::try_from(class)?)
+    }
+
+    /// Returns a builder for a new Python class named `name`, whose methods can be Rust
+    /// closures.
+    ///
+    /// # Example
+    /// ```
+    /// # use pyo3::prelude::*;
+    /// use pyo3::{types::PyType, PyNativeType};
+    /// # let gil = Python::acquire_gil();
+    /// # let py = gil.python();
+    /// let greeter = PyType::builder(py, "Greeter")
+    ///     .module("plugins")
+    ///     .attr("greeting", "Hello")
+    ///     .method("greet", |slf, args, _kwargs| {
+    ///         let greeting: String = slf.getattr("greeting")?.extract()?;
+    ///         let name: &str = args.get_item(0).extract()?;
+    ///         Ok(format!("{}, {}!", greeting, name).to_object(slf.py()))
+    ///     })
+    ///     .build()?;
+    /// let greeting: String = greeter.call0()?.call_method1("greet", ("world",))?.extract()?;
+    /// assert_eq!(greeting, "Hello, world!");
+    /// # Ok::<(), PyErr>(())
+    /// ```
+    #[cfg(feature = "macros")]
+    pub fn builder<'p>(py: Python<'p>, name: &str) -> PyTypeBuilder<'p> {
+        PyTypeBuilder {
+            py,
+            name: name.to_string(),
+            bases: Vec::new(),
+            members: Vec::new(),
+        }
+    }
+
     /// Retrieves the underlying FFI pointer associated with this Python object.
     #[inline]
     pub unsafe fn as_type_ptr(&self) -> *mut ffi::PyTypeObject {
@@ -44,7 +127,19 @@ impl PyType {
     /// Checks whether `self` is subclass of type `T`.
     ///
     /// Equivalent to Python's `issubclass` function.
+    #[deprecated(since = "0.12.0", note = "renamed to PyType::is_subclass_of")]
     pub fn is_subclass<T>(&self) -> PyResult<bool>
+    where
+        T: PyTypeObject,
+    {
+        self.is_subclass_of::<T>()
+    }
+
+    /// Checks whether `self` is subclass of type `T`.
+    ///
+    /// Equivalent to Python's `issubclass` function, and therefore honors `__subclasscheck__`
+    /// overrides such as virtual subclasses registered with an ABC.
+    pub fn is_subclass_of<T>(&self) -> PyResult<bool>
     where
         T: PyTypeObject,
     {
@@ -59,6 +154,16 @@ impl PyType {
         }
     }
 
+    /// Checks whether `self` is `T` or a subclass of it, bypassing any `__subclasscheck__`
+    /// override.
+    pub fn is_subclass_of_no_hooks<T>(&self) -> bool
+    where
+        T: PyTypeObject,
+    {
+        let ty = T::type_object(self.py());
+        unsafe { ffi::PyType_IsSubtype(self.as_type_ptr(), ty.as_type_ptr()) != 0 }
+    }
+
     /// Check whether `obj` is an instance of `self`.
     ///
     /// Equivalent to Python's `isinstance` function.
@@ -73,3 +178,201 @@ impl PyType {
         }
     }
 }
+
+/// A method of a class created by [PyTypeBuilder], called with the instance, the positional
+/// arguments and the keyword arguments.
+#[cfg(feature = "macros")]
+type MethodClosure =
+    dyn Fn(&PyAny, &PyTuple, Option<&PyDict>) -> PyResult<PyObject> + Send + 'static;
+
+#[cfg(feature = "macros")]
+enum Member {
+    Attr(PyObject),
+    Method(Box<MethodClosure>),
+}
+
+/// A builder for a new Python class, created by [PyType::builder].
+#[cfg(feature = "macros")]
+pub struct PyTypeBuilder<'p> {
+    py: Python<'p>,
+    name: String,
+    bases: Vec<&'p PyType>,
+    members: Vec<(String, Member)>,
+}
+
+#[cfg(feature = "macros")]
+impl<'p> PyTypeBuilder<'p> {
+    /// Adds a base class. The class derives from `object` if no base is added.
+    pub fn base(mut self, base: &'p PyType) -> Self {
+        self.bases.push(base);
+        self
+    }
+
+    /// Sets the `__module__` of the class.
+    pub fn module(self, module: &str) -> Self {
+        let module = module.to_object(self.py);
+        self.attr("__module__", module)
+    }
+
+    /// Sets the `__qualname__` of the class, which is its name by default.
+    pub fn qualname(self, qualname: &str) -> Self {
+        let qualname = qualname.to_object(self.py);
+        self.attr("__qualname__", qualname)
+    }
+
+    /// Adds a class attribute.
+    pub fn attr(mut self, name: &str, value: impl ToPyObject) -> Self {
+        let value = value.to_object(self.py);
+        self.members.push((name.to_string(), Member::Attr(value)));
+        self
+    }
+
+    /// Adds a method, which is called with the instance, the positional arguments and the
+    /// keyword arguments.
+    pub fn method<F>(mut self, name: &str, method: F) -> Self
+    where
+        F: Fn(&PyAny, &PyTuple, Option<&PyDict>) -> PyResult<PyObject> + Send + 'static,
+    {
+        self.members
+            .push((name.to_string(), Member::Method(Box::new(method))));
+        self
+    }
+
+    /// Creates the class with [PyType::create].
+    pub fn build(self) -> PyResult<&'p PyType> {
+        let py = self.py;
+        let namespace = PyDict::new(py);
+        for (name, member) in self.members {
+            match member {
+                Member::Attr(value) => namespace.set_item(name, value)?,
+                Member::Method(method) => {
+                    namespace.set_item(name, Py::new(py, ClosureMethod { method })?)?
+                }
+            }
+        }
+        let bases = PyTuple::new(py, self.bases);
+        PyType::create(py, &self.name, bases, namespace)
+    }
+}
+
+/// The callable of a method added by [PyTypeBuilder::method], bound to the instances like a
+/// Python function.
+#[cfg(feature = "macros")]
+#[pyclass]
+struct ClosureMethod {
+    method: Box<MethodClosure>,
+}
+
+#[cfg(feature = "macros")]
+#[pymethods]
+impl ClosureMethod {
+    #[call]
+    #[args(args = "*", kwargs = "**")]
+    fn __call__(&self, args: &PyTuple, kwargs: Option<&PyDict>) -> PyResult<PyObject> {
+        let slf = args.get_item_checked(0).map_err(|_| {
+            crate::exceptions::TypeError::py_err("method called without an instance")
+        })?;
+        (self.method)(slf, args.split_from(1), kwargs)
+    }
+}
+
+#[cfg(feature = "macros")]
+#[pyproto]
+impl crate::class::PyDescrProtocol for ClosureMethod {
+    fn __get__(
+        slf: PyRef<'p, Self>,
+        instance: &'p PyAny,
+        _owner: Option<&'p PyType>,
+    ) -> PyResult<PyObject> {
+        let py = instance.py();
+        if instance.is_none() {
+            return Ok(slf.into_py(py));
+        }
+        let method = py.import("types")?.call1("MethodType", (slf, instance))?;
+        Ok(method.into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PyType;
+    use crate::types::{IntoPyDict, PyDict, PyTuple};
+    use crate::{py_run, PyNativeType, Python, ToPyObject};
+
+    #[test]
+    fn test_create() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let meta = py
+            .eval("type('Meta', (type,), {'tag': 'meta'})", None, None)
+            .unwrap();
+        let base = meta
+            .call1(("Base", PyTuple::empty(py), PyDict::new(py)))
+            .unwrap();
+        let namespace = [("answer", 42)].into_py_dict(py);
+        let cls = PyType::create(py, "Derived", PyTuple::new(py, &[base]), namespace).unwrap();
+        py_run!(
+            py,
+            cls base meta,
+            r#"
+            assert type(cls) is meta
+            assert cls.__mro__ == (cls, base, object)
+            assert cls.answer == 42
+            assert cls.__qualname__ == "Derived"
+            "#
+        );
+        // Not taken from the frame of `types.new_class`.
+        assert_eq!(cls.getattr("__module__").unwrap().to_string(), "builtins");
+        assert!(!namespace.contains("__module__").unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "macros")]
+    fn test_builder() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let counter = PyType::builder(py, "Counter")
+            .module("plugins")
+            .qualname("outer.Counter")
+            .attr("step", 2)
+            .method("advance", |slf, args, kwargs| {
+                let times: i32 = match kwargs.and_then(|kwargs| kwargs.get_item("times")) {
+                    Some(times) => times.extract()?,
+                    None => 1,
+                };
+                let step: i32 = slf.getattr("step")?.extract()?;
+                let start: i32 = args.get_item(0).extract()?;
+                Ok((start + step * times).to_object(slf.py()))
+            })
+            .method("make_class", |slf, _args, _kwargs| {
+                let py = slf.py();
+                let namespace = PyDict::new(py);
+                Ok(PyType::create(py, "Made", PyTuple::empty(py), namespace)?.to_object(py))
+            })
+            .build()
+            .unwrap();
+        py_run!(
+            py,
+            counter,
+            r#"
+            assert counter.__name__ == "Counter"
+            assert counter.__qualname__ == "outer.Counter"
+            assert counter.__module__ == "plugins"
+            assert counter().advance(1) == 3
+            assert counter().advance(1, times=3) == 7
+            assert counter.advance(counter(), 1) == 3
+            # Like `type`, from the module of the calling Python code.
+            assert counter().make_class().__module__ == __name__
+
+            class Faster(counter):
+                step = 10
+
+                def advance(self, start):
+                    return super().advance(start) + 1
+
+            assert Faster().advance(1) == 12
+            assert isinstance(Faster(), counter)
+            "#
+        );
+    }
+}
@@ -0,0 +1,255 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+use crate::err::PyResult;
+use crate::instance::PyNativeType;
+use crate::{ffi, AsPyPointer, PyAny, Python};
+use std::ptr;
+
+/// Represents a Python weak reference, as created by `weakref.ref(obj)`.
+///
+/// A weak reference does not keep its referent alive. This makes it suitable for storing
+/// references to Python-owned objects in Rust structs without creating reference cycles which
+/// the garbage collector cannot see through: store a `Py<PyWeakrefReference>` and
+/// [upgrade](PyWeakrefReference::upgrade) it whenever the referent is needed.
+///
+/// # Example
+/// ```
+/// use pyo3::prelude::*;
+/// use pyo3::types::PyWeakrefReference;
+///
+/// struct Plugin {
+///     host: Py<PyWeakrefReference>,
+/// }
+///
+/// # fn main() -> PyResult<()> {
+/// let gil = Python::acquire_gil();
+/// let py = gil.python();
+/// let host = py.eval("type('Host', (), {})()", None, None)?;
+/// let plugin = Plugin {
+///     host: PyWeakrefReference::new(host)?.into(),
+/// };
+/// assert!(plugin.host.as_ref(py).upgrade().is_some());
+/// # Ok(())
+/// # }
+/// ```
+#[repr(transparent)]
+pub struct PyWeakrefReference(PyAny);
+
+pyobject_native_var_type!(
+    PyWeakrefReference,
+    ffi::_PyWeakref_RefType,
+    ffi::PyWeakref_CheckRef
+);
+
+impl PyWeakrefReference {
+    /// Creates a new weak reference to `obj`.
+    ///
+    /// This is equivalent to the Python expression `weakref.ref(obj)`. Returns a `TypeError` if
+    /// the type of `obj` does not support weak references, e.g. for `int` or `object()`.
+    pub fn new(obj: &PyAny) -> PyResult<&PyWeakrefReference> {
+        unsafe {
+            obj.py()
+                .from_owned_ptr_or_err(ffi::PyWeakref_NewRef(obj.as_ptr(), ptr::null_mut()))
+        }
+    }
+
+    /// Creates a new weak reference to `obj`, with a callback called with the weak reference
+    /// as argument when `obj` is about to be finalized.
+    ///
+    /// This is equivalent to the Python expression `weakref.ref(obj, callback)`. The callback
+    /// is not called if the weak reference itself is collected first.
+    pub fn new_with_callback<'p>(
+        obj: &'p PyAny,
+        callback: &PyAny,
+    ) -> PyResult<&'p PyWeakrefReference> {
+        unsafe {
+            obj.py()
+                .from_owned_ptr_or_err(ffi::PyWeakref_NewRef(obj.as_ptr(), callback.as_ptr()))
+        }
+    }
+
+    /// Returns the referent, or `None` if it has been collected.
+    ///
+    /// This is equivalent to the Python expression `self()`, except that `None` is returned
+    /// instead of the Python `None` object. The returned reference keeps the referent alive
+    /// until the current `GILPool` is dropped.
+    pub fn upgrade(&self) -> Option<&PyAny> {
+        unsafe { upgrade(self.py(), self.as_ptr()) }
+    }
+}
+
+/// Represents a Python weak reference proxy, as created by `weakref.proxy(obj)`.
+///
+/// A proxy forwards attribute accesses and most operations to its referent, and raises a
+/// `ReferenceError` once the referent has been collected. Downcasting to `PyWeakrefProxy`
+/// succeeds for proxies to callable objects too.
+#[repr(transparent)]
+pub struct PyWeakrefProxy(PyAny);
+
+pyobject_native_var_type!(
+    PyWeakrefProxy,
+    ffi::_PyWeakref_ProxyType,
+    ffi::PyWeakref_CheckProxy
+);
+
+impl PyWeakrefProxy {
+    /// Creates a new weak reference proxy to `obj`.
+    ///
+    /// This is equivalent to the Python expression `weakref.proxy(obj)`. Returns a `TypeError`
+    /// if the type of `obj` does not support weak references.
+    pub fn new(obj: &PyAny) -> PyResult<&PyWeakrefProxy> {
+        unsafe {
+            obj.py()
+                .from_owned_ptr_or_err(ffi::PyWeakref_NewProxy(obj.as_ptr(), ptr::null_mut()))
+        }
+    }
+
+    /// Creates a new weak reference proxy to `obj`, with a callback called with the proxy as
+    /// argument when `obj` is about to be finalized.
+    ///
+    /// This is equivalent to the Python expression `weakref.proxy(obj, callback)`.
+    pub fn new_with_callback<'p>(obj: &'p PyAny, callback: &PyAny) -> PyResult<&'p PyWeakrefProxy> {
+        unsafe {
+            obj.py()
+                .from_owned_ptr_or_err(ffi::PyWeakref_NewProxy(obj.as_ptr(), callback.as_ptr()))
+        }
+    }
+
+    /// Returns the referent, or `None` if it has been collected.
+    ///
+    /// The returned reference keeps the referent alive until the current `GILPool` is dropped.
+    pub fn upgrade(&self) -> Option<&PyAny> {
+        unsafe { upgrade(self.py(), self.as_ptr()) }
+    }
+}
+
+/// Returns the referent of the weak reference or proxy `weakref`, if it is still alive.
+unsafe fn upgrade(py: Python, weakref: *mut ffi::PyObject) -> Option<&PyAny> {
+    // `PyWeakref_GetObject` returns a borrowed reference, which is `None` once the referent is
+    // dead. It only fails if `weakref` is not a weak reference, which the callers rule out.
+    let referent = ffi::PyWeakref_GetObject(weakref);
+    if referent.is_null() || referent == ffi::Py_None() {
+        None
+    } else {
+        Some(py.from_borrowed_ptr(referent))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{PyWeakrefProxy, PyWeakrefReference};
+    use crate::exceptions::{ReferenceError, TypeError};
+    use crate::types::{IntoPyDict, PyList, PyModule};
+    use crate::{AsPyPointer, AsPyRef, Py, PyAny, PyObject, Python};
+
+    const CODE: &str = r#"
+class Plugin:
+    def __init__(self, name):
+        self.name = name
+"#;
+
+    /// Creates a new `Plugin` instance, which is only referenced by the returned object.
+    fn plugin(py: Python, name: &str) -> PyObject {
+        let pool = unsafe { py.new_pool() };
+        let py = pool.python();
+        let module = PyModule::from_code(py, CODE, "plugin.py", "pyo3_weakref_test").unwrap();
+        module.call1("Plugin", (name,)).unwrap().into()
+    }
+
+    fn collect(py: Python) {
+        py.import("gc").unwrap().call0("collect").unwrap();
+    }
+
+    #[test]
+    fn test_reference() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let obj = plugin(py, "a");
+
+        let weak: Py<PyWeakrefReference> = {
+            let pool = unsafe { py.new_pool() };
+            let py = pool.python();
+            let weak = PyWeakrefReference::new(obj.as_ref(py)).unwrap();
+            let referent = weak.upgrade().unwrap();
+            assert_eq!(obj.as_ptr(), referent.as_ptr());
+            let name: String = referent.getattr("name").unwrap().extract().unwrap();
+            assert_eq!("a", name);
+            weak.into()
+        };
+
+        drop(obj);
+        collect(py);
+        assert!(weak.as_ref(py).upgrade().is_none());
+        // Calling the weak reference from Python returns `None`.
+        assert!(weak.as_ref(py).call0().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_callback() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let obj = plugin(py, "b");
+        let log = PyList::empty(py);
+        let callback = py
+            .eval(
+                "lambda weak: log.append(weak() is None)",
+                Some([("log", log)].into_py_dict(py)),
+                None,
+            )
+            .unwrap();
+
+        let weak: Py<PyWeakrefReference> = {
+            let pool = unsafe { py.new_pool() };
+            let py = pool.python();
+            PyWeakrefReference::new_with_callback(obj.as_ref(py), callback)
+                .unwrap()
+                .into()
+        };
+        assert_eq!(0, log.len());
+        drop(obj);
+        collect(py);
+        assert!(weak.as_ref(py).upgrade().is_none());
+        assert_eq!(vec![true], log.extract::<Vec<bool>>().unwrap());
+    }
+
+    #[test]
+    fn test_proxy() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let obj = plugin(py, "c");
+
+        let proxy: Py<PyWeakrefProxy> = {
+            let pool = unsafe { py.new_pool() };
+            let py = pool.python();
+            let proxy = PyWeakrefProxy::new(obj.as_ref(py)).unwrap();
+            let name: String = proxy.getattr("name").unwrap().extract().unwrap();
+            assert_eq!("c", name);
+            assert_eq!(obj.as_ptr(), proxy.upgrade().unwrap().as_ptr());
+            assert!(proxy.downcast::<PyWeakrefReference>().is_err());
+            proxy.into()
+        };
+
+        drop(obj);
+        collect(py);
+        let proxy = proxy.as_ref(py);
+        assert!(proxy.upgrade().is_none());
+        let err = proxy.getattr("name").map(|_| ()).unwrap_err();
+        assert!(err.is_instance::<ReferenceError>(py));
+    }
+
+    #[test]
+    fn test_unsupported_type() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let obj: &PyAny = py.eval("object()", None, None).unwrap();
+        let err = PyWeakrefReference::new(obj).map(|_| ()).unwrap_err();
+        assert!(err.is_instance::<TypeError>(py));
+        let err = PyWeakrefProxy::new(obj).map(|_| ()).unwrap_err();
+        assert!(err.is_instance::<TypeError>(py));
+
+        let weak = PyWeakrefReference::new(py.get_type::<PyWeakrefReference>()).unwrap();
+        let weak: &PyAny = weak;
+        assert!(weak.downcast::<PyWeakrefReference>().is_ok());
+        assert!(weak.downcast::<PyWeakrefProxy>().is_err());
+    }
+}
@@ -0,0 +1,109 @@
+//! The scenario of the `pyo3::asyncio` tests, run against each runtime in its own test binary.
+
+use pyo3::asyncio;
+use pyo3::exceptions::{RuntimeError, ValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyModule;
+use pyo3::wrap_pyfunction;
+use std::sync::Once;
+use std::thread;
+
+/// Initializes Python without importing `threading` in the current thread.
+///
+/// When the thread which initialized Python imports `threading` (which `asyncio` does), the
+/// `threading._shutdown` at exit waits for the test threads, which are never joined.
+pub fn prepare_python() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        pyo3::prepare_freethreaded_python();
+        thread::spawn(|| {
+            let gil = Python::acquire_gil();
+            gil.python().import("threading").unwrap();
+        })
+        .join()
+        .unwrap();
+    });
+}
+
+#[pyfunction]
+fn answer(py: Python) -> PyResult<&PyAny> {
+    asyncio::future_into_py(py, async { Ok(21) })
+}
+
+const HELPERS: &str = r#"
+import asyncio
+
+# `answer` is added by Rust.
+async def double_answer():
+    return 2 * await answer()
+
+async def fail():
+    await asyncio.sleep(0)
+    raise ValueError("failed")
+"#;
+
+fn helpers(py: Python) -> &PyModule {
+    let helpers =
+        PyModule::from_code(py, HELPERS, "asyncio_helpers.py", "asyncio_helpers").unwrap();
+    helpers.add_wrapped(wrap_pyfunction!(answer)).unwrap();
+    helpers
+}
+
+/// Awaits the coroutine `name()` of the helpers, from the current thread of the runtime.
+async fn await_helper(name: &'static str) -> PyResult<PyObject> {
+    let future = {
+        let gil = Python::acquire_gil();
+        asyncio::into_future(helpers(gil.python()).call0(name)?)?
+    };
+    future.await
+}
+
+/// Runs futures which await Python coroutines, which await Rust futures in turn, with
+/// `asyncio::run`.
+pub fn run_scenario() {
+    prepare_python();
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let slept: String = asyncio::run(py, async {
+        let sleep = {
+            let gil = Python::acquire_gil();
+            let py = gil.python();
+            let sleep = py.import("asyncio")?.call1("sleep", (0.01, "slept"))?;
+            asyncio::into_future(sleep)?
+        };
+        let slept = sleep.await?;
+        let gil = Python::acquire_gil();
+        slept.extract(gil.python())
+    })
+    .unwrap();
+    assert_eq!(slept, "slept");
+
+    let answer: u32 = asyncio::run(py, async {
+        let doubled = await_helper("double_answer").await?;
+        let gil = Python::acquire_gil();
+        doubled.extract(gil.python())
+    })
+    .unwrap();
+    assert_eq!(answer, 42);
+
+    let err = asyncio::run(py, async {
+        await_helper("fail").await?;
+        Ok(())
+    })
+    .unwrap_err();
+    assert!(err.is_instance::<ValueError>(py));
+
+    let nested = asyncio::run(py, async {
+        let gil = Python::acquire_gil();
+        let nested = asyncio::run(gil.python(), async { Ok(()) });
+        Ok(nested.map_err(|err| err.is_instance::<RuntimeError>(gil.python())))
+    })
+    .unwrap();
+    assert_eq!(nested, Err(true));
+
+    // The event loop is gone once `run` returns.
+    let sleep = py.import("asyncio").unwrap().call1("sleep", (0,)).unwrap();
+    assert!(asyncio::into_future(sleep).is_err());
+    sleep.call_method0("close").unwrap();
+}
@@ -0,0 +1,14 @@
+# system configuration generated and used by the sysconfig module
+build_time_vars = {'ABIFLAGS': 'm',
+ 'BLDLIBRARY': '-L. -lpython3.7m',
+ 'CONFIG_ARGS': "'--prefix=/opt/_internal/cpython-3.7.8' '--enable-shared' "
+                "'--with-pydebug'",
+ 'EXT_SUFFIX': '.cpython-37m-aarch64-linux-gnu.so',
+ 'LDLIBRARY': 'libpython3.7m.so',
+ 'MULTIARCH': 'aarch64-linux-gnu',
+ 'Py_DEBUG': 1,
+ 'Py_ENABLE_SHARED': 1,
+ 'SIZEOF_VOID_P': 8,
+ 'SOABI': 'cpython-37m-aarch64-linux-gnu',
+ 'VERSION': '3.7',
+ 'WITH_THREAD': 1}
@@ -0,0 +1,28 @@
+# system configuration generated and used by the sysconfig module
+build_time_vars = {'ABIFLAGS': '',
+ 'AR': 'ar',
+ 'BINDIR': '/opt/_internal/cpython-3.8.5/bin',
+ 'BLDLIBRARY': 'libpython3.8.a',
+ 'BLDSHARED': 'gcc -pthread -shared -L/opt/_internal/cpython-3.8.5/lib '
+              '-Wl,-rpath,/opt/_internal/cpython-3.8.5/lib',
+ 'BOOTSTRAP_HEADERS': '\\',
+ 'CC': 'gcc -pthread',
+ 'CONFIG_ARGS': "'--prefix=/opt/_internal/cpython-3.8.5' "
+                "'--disable-shared' 'LDFLAGS=-Wl,-rpath "
+                "/opt/_internal/cpython-3.8.5/lib'",
+ 'COUNT_ALLOCS': 0,
+ 'EXT_SUFFIX': '.cpython-38-x86_64-linux-gnu.so',
+ 'HAVE_FORK': 1,
+ 'LDLIBRARY': 'libpython3.8.a',
+ 'LDVERSION': '3.8',
+ 'LIBDIR': '/opt/_internal/cpython-3.8.5/lib',
+ 'MULTIARCH': 'x86_64-linux-gnu',
+ 'Py_DEBUG': 0,
+ 'Py_ENABLE_SHARED': 0,
+ 'Py_TRACE_REFS': 0,
+ 'SIZEOF_VOID_P': 8,
+ 'SOABI': 'cpython-38-x86_64-linux-gnu',
+ 'TZPATH': '/usr/share/zoneinfo:/usr/lib/zoneinfo:/usr/share/lib/zoneinfo:/etc/zoneinfo',
+ 'VERSION': '3.8',
+ 'WITH_DOC_STRINGS': 1,
+ 'WITH_THREAD': 1}
@@ -0,0 +1,12 @@
+#![cfg(feature = "async-std-runtime")]
+
+use pyo3::asyncio::{self, AsyncStdRuntime};
+
+mod asyncio_common;
+
+#[test]
+fn test_async_std_runtime() {
+    asyncio::init_runtime(AsyncStdRuntime).unwrap();
+    asyncio_common::run_scenario();
+    assert!(asyncio::init_runtime(AsyncStdRuntime).is_err());
+}
@@ -0,0 +1,43 @@
+#![cfg(feature = "tokio-runtime")]
+
+use pyo3::asyncio::{self, Runtime, TokioRuntime};
+use std::sync::mpsc;
+
+mod asyncio_common;
+
+#[test]
+fn test_tokio_runtime() {
+    asyncio::init_runtime(TokioRuntime::multi_thread().unwrap()).unwrap();
+    asyncio_common::run_scenario();
+    assert!(asyncio::init_runtime(TokioRuntime::multi_thread().unwrap()).is_err());
+}
+
+#[test]
+fn test_tokio_current_thread() {
+    let runtime = TokioRuntime::current_thread().unwrap();
+    let (sender, receiver) = mpsc::channel();
+    runtime.spawn(Box::pin(async move {
+        sender
+            .send(std::thread::current().name().map(String::from))
+            .unwrap();
+    }));
+    assert_eq!(
+        receiver.recv().unwrap().as_ref().map(String::as_str),
+        Some("pyo3-tokio")
+    );
+}
+
+#[test]
+fn test_tokio_current_thread_shutdown() {
+    let runtime = TokioRuntime::current_thread().unwrap();
+    let (sender, receiver) = mpsc::channel::<()>();
+    // Never completes, so that it is dropped with `sender` when the runtime shuts down.
+    let (never_sent, pending) = tokio::sync::oneshot::channel::<()>();
+    runtime.spawn(Box::pin(async move {
+        let _sender = sender;
+        let _never_sent = never_sent;
+        let _ = pending.await;
+    }));
+    drop(runtime);
+    assert!(receiver.recv().is_err());
+}
@@ -1,9 +1,10 @@
-use pyo3::buffer::PyBuffer;
+use pyo3::buffer::{ArrayExport, PyBuffer};
 use pyo3::class::PyBufferProtocol;
 use pyo3::exceptions::BufferError;
 use pyo3::ffi;
 use pyo3::prelude::*;
-use pyo3::types::IntoPyDict;
+use pyo3::types::{IntoPyDict, PyByteArray, PyBytes};
+use pyo3::wrap_pyfunction;
 use pyo3::AsPyPointer;
 use std::ffi::CStr;
 use std::os::raw::{c_int, c_void};
@@ -127,3 +128,161 @@ fn test_buffer_referenced() {
 
     assert!(drop_called.load(Ordering::Relaxed));
 }
+
+/// Fills a writable buffer with as much of `b"hello"` as fits, like `io.RawIOBase.readinto`.
+#[pyfunction]
+fn readinto(py: Python, buf: &PyAny) -> PyResult<usize> {
+    let buffer = PyBuffer::<u8>::get_writable(buf)?;
+    let slice = buffer
+        .as_mut_slice(py)
+        .ok_or_else(|| BufferError::py_err("buffer is not contiguous"))?;
+    let data = b"hello";
+    let len = data.len().min(slice.len());
+    for (cell, &byte) in slice.iter().zip(&data[..len]) {
+        cell.set(byte);
+    }
+    Ok(len)
+}
+
+#[test]
+fn test_readinto() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let readinto = wrap_pyfunction!(readinto)(py);
+    let instance = Py::new(
+        py,
+        TestBufferClass {
+            vec: vec![b'a'],
+            drop_called: Arc::new(AtomicBool::new(false)),
+        },
+    )
+    .unwrap();
+    let env = [("readinto", readinto), ("readonly", instance.to_object(py))].into_py_dict(py);
+    py.run(
+        r#"
+b = bytearray(8)
+assert readinto(b) == 5
+assert b == b"hello\0\0\0"
+b = bytearray(3)
+assert readinto(memoryview(b)) == 3
+assert b == b"hel"
+
+for target in [b"abc", memoryview(b"abc"), readonly]:
+    try:
+        readinto(target)
+    except BufferError:
+        pass
+    else:
+        assert False, "expected BufferError"
+"#,
+        None,
+        Some(env),
+    )
+    .unwrap();
+
+    // Buffers requested read-only are still mutable if the exporter allows it.
+    let bytearray = PyByteArray::new(py, b"abc");
+    let buffer = PyBuffer::<u8>::get(bytearray).unwrap();
+    assert!(buffer.as_mut_slice(py).is_some());
+    let bytes = PyBytes::new(py, b"abc");
+    let buffer = PyBuffer::<u8>::get(bytes).unwrap();
+    assert!(buffer.readonly());
+    assert!(buffer.as_mut_slice(py).is_none());
+    assert!(PyBuffer::<u8>::get_writable(bytes).is_err());
+}
+
+#[test]
+fn test_array_export() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let export = ArrayExport::from_vec(py, vec![1.0f64, 2.0, 3.0, 4.0, 5.0, 6.0], &[2, 3]).unwrap();
+    let env = [("export", &export)].into_py_dict(py);
+    py.run(
+        r#"
+view = memoryview(export)
+assert view.format == "d"
+assert view.shape == (2, 3)
+assert view.strides == (24, 8)
+assert view.tolist() == [[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]
+view[1, 2] = 60.0
+
+interface = export.__array_interface__
+assert interface["version"] == 3
+assert interface["typestr"] in ("<f8", ">f8")
+assert interface["shape"] == (2, 3)
+assert interface["strides"] == (24, 8)
+assert interface["data"][1] is False
+"#,
+        None,
+        Some(env),
+    )
+    .unwrap();
+
+    let buffer = PyBuffer::<f64>::get(export.as_ref(py)).unwrap();
+    let export = export.as_ref(py).borrow();
+    assert_eq!(vec![2, 3], export.shape());
+    assert!(export.as_slice::<i64>().is_none());
+    let values = export.as_slice::<f64>().unwrap();
+    assert_eq!(60.0, values[5].get());
+    values[0].set(10.0);
+    assert_eq!(10.0, buffer.to_vec(py).unwrap()[0]);
+}
+
+#[test]
+fn test_array_export_readonly() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let export = ArrayExport::from_vec_readonly(py, vec![1u8, 2, 3], &[3]).unwrap();
+    let env = [("export", &export)].into_py_dict(py);
+    py.run(
+        r#"
+view = memoryview(export)
+assert view.readonly
+assert bytes(view) == b"\x01\x02\x03"
+assert export.__array_interface__["typestr"] == "|u1"
+assert export.__array_interface__["data"][1] is True
+"#,
+        None,
+        Some(env),
+    )
+    .unwrap();
+    assert!(PyBuffer::<u8>::get_writable(export.as_ref(py)).is_err());
+
+    let err = ArrayExport::from_vec(py, vec![0i32; 5], &[2, 3])
+        .map(|_| ())
+        .unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::ValueError>(py));
+}
+
+#[test]
+fn test_array_export_numpy() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    if py.import("numpy").is_err() {
+        return;
+    }
+    let export = ArrayExport::from_vec(py, vec![1i32, 2, 3, 4], &[2, 2]).unwrap();
+    let readonly = ArrayExport::from_vec_readonly(py, vec![1.5f32, 2.5], &[2]).unwrap();
+    let env = [("export", &export), ("readonly", &readonly)].into_py_dict(py);
+    py.run(
+        r#"
+import numpy
+array = numpy.asarray(export)
+assert array.dtype == numpy.int32
+assert array.shape == (2, 2)
+assert array.tolist() == [[1, 2], [3, 4]]
+array[0, 1] = 20
+del export
+assert array.sum() == 28
+
+array = numpy.asarray(readonly)
+assert array.dtype == numpy.float32
+assert not array.flags.writeable
+"#,
+        None,
+        Some(env),
+    )
+    .unwrap();
+    let export = export.as_ref(py).borrow();
+    assert_eq!(20, export.as_slice::<i32>().unwrap()[1].get());
+}
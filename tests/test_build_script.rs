@@ -0,0 +1,4 @@
+//! Runs the unit tests of the build script, which cargo does not build as a test target.
+#[allow(dead_code)]
+#[path = "../build.rs"]
+mod build_script;
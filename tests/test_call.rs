@@ -0,0 +1,283 @@
+use pyo3::prelude::*;
+use pyo3::types::{IntoPyDict, PyDict, PyTuple};
+
+mod common;
+
+#[pyclass]
+struct Positional {}
+
+#[pymethods]
+impl Positional {
+    #[call]
+    fn __call__(&self, a: i32, b: i32) -> i32 {
+        a * 10 + b
+    }
+}
+
+#[pyclass]
+struct Signature {}
+
+#[pymethods]
+impl Signature {
+    #[call]
+    #[args(a, b = 2, args = "*", c = 3, kwargs = "**")]
+    fn __call__(
+        &self,
+        py: Python,
+        a: i32,
+        b: i32,
+        args: &PyTuple,
+        c: i32,
+        kwargs: Option<&PyDict>,
+    ) -> PyObject {
+        (a, b, args, c, kwargs).to_object(py)
+    }
+}
+
+#[pyclass]
+struct KeywordOnly {}
+
+#[pymethods]
+impl KeywordOnly {
+    #[call]
+    #[args(a, "*", b = "Vec::new()", c = "None")]
+    fn __call__(&self, a: &str, b: Vec<i32>, c: Option<&PyAny>) -> String {
+        format!("{} {:?} {:?}", a, b, c.map(|c| c.to_string()))
+    }
+}
+
+#[pyclass]
+struct VarArgs {}
+
+#[pymethods]
+impl VarArgs {
+    #[call]
+    #[args(args = "*")]
+    fn __call__(&self, args: Vec<i32>) -> i32 {
+        args.iter().sum()
+    }
+}
+
+#[pyclass]
+struct NoArgs {}
+
+#[pymethods]
+impl NoArgs {
+    #[call]
+    fn __call__(&self) -> &'static str {
+        "called"
+    }
+}
+
+#[pyclass(subclass)]
+struct Counter {
+    count: u32,
+}
+
+#[pymethods]
+impl Counter {
+    #[new]
+    fn new() -> Self {
+        Counter { count: 0 }
+    }
+
+    #[call]
+    #[args(step = 1)]
+    fn __call__(&mut self, step: u32) -> PyResult<u32> {
+        if step == 0 {
+            return Err(pyo3::exceptions::ValueError::py_err(
+                "step must be positive",
+            ));
+        }
+        self.count += step;
+        Ok(self.count)
+    }
+}
+
+#[pyclass(extends=Counter)]
+struct SubCounter {}
+
+#[pyclass(extends=Counter)]
+struct DoubleCounter {
+    doubled: u32,
+}
+
+#[pymethods]
+impl DoubleCounter {
+    #[call]
+    fn __call__(mut slf: PyRefMut<Self>) -> u32 {
+        slf.doubled += 2;
+        slf.doubled
+    }
+}
+
+/// Defines `both(f, *args, **kwargs)`, which calls `f` through vectorcall and through `tp_call`,
+/// checks that the results or the exceptions are the same, and returns them.
+fn run_both(py: Python, locals: &PyDict, code: &str) {
+    let harness = r#"
+def both(f, *args, **kwargs):
+    def outcome(call):
+        try:
+            return call(*args, **kwargs)
+        except Exception as e:
+            return (type(e), str(e))
+    vectorcall = outcome(f)
+    tp_call = outcome(f.__call__)
+    assert vectorcall == tp_call, (vectorcall, tp_call)
+    return vectorcall
+"#;
+    py.run(harness, None, Some(locals)).unwrap();
+    py.run(code, None, Some(locals))
+        .map_err(|e| e.print(py))
+        .unwrap();
+}
+
+#[test]
+fn call_entry_points() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let locals = [
+        (
+            "positional",
+            Py::new(py, Positional {}).unwrap().to_object(py),
+        ),
+        (
+            "signature",
+            Py::new(py, Signature {}).unwrap().to_object(py),
+        ),
+        ("kwonly", Py::new(py, KeywordOnly {}).unwrap().to_object(py)),
+        ("varargs", Py::new(py, VarArgs {}).unwrap().to_object(py)),
+        ("noargs", Py::new(py, NoArgs {}).unwrap().to_object(py)),
+    ]
+    .into_py_dict(py);
+    run_both(
+        py,
+        locals,
+        r#"
+assert both(positional, 1, 2) == 12
+assert both(positional, 1, b=2) == 12
+assert both(positional, b=2, a=1) == 12
+assert both(positional, *[1], **{'b': 2}) == 12
+assert both(positional, 1) == (TypeError, "Positional.__call__() missing required positional argument: b")
+assert both(positional, 1, 2, 3) == (TypeError, "Positional.__call__() takes at most 2 positional arguments (3 given)")
+assert both(positional, 1, a=1) == (TypeError, "Positional.__call__() got multiple values for argument: a")
+assert both(positional, 1, 2, d=3)[0] is TypeError
+assert both(positional, 1, 'x')[0] is TypeError
+
+assert both(signature, 1) == (1, 2, (), 3, None)
+assert both(signature, 1, 5, 6, 7) == (1, 5, (6, 7), 3, None)
+assert both(signature, 1, c=4) == (1, 2, (), 4, None)
+assert both(signature, 1, 5, c=4, d=5, e=6) == (1, 5, (), 4, {'d': 5, 'e': 6})
+assert both(signature, a=1, d=5) == (1, 2, (), 3, {'d': 5})
+assert both(signature, b=1)[0] is TypeError
+assert both(signature, 1, a=2)[0] is TypeError
+
+assert both(kwonly, 'x', b=[1, 2]) == "x [1, 2] None"
+assert both(kwonly, 'x', b=(), c=1.5) == "x [] Some(\"1.5\")"
+assert both(kwonly, 'x') == "x [] None"
+assert both(kwonly, 'x', [1]) == (TypeError, "KeywordOnly.__call__() takes at most 1 positional argument (2 given)")
+assert both(kwonly, 'x', b=['y'])[0] is TypeError
+
+assert both(varargs) == 0
+assert both(varargs, 1, 2, 3) == 6
+assert both(varargs, *range(100)) == 4950
+assert both(varargs, 1, x=2)[0] is TypeError
+assert both(varargs, 'x')[0] is TypeError
+
+assert both(noargs) == "called"
+assert both(noargs, 1, x=2) == "called"
+"#,
+    );
+}
+
+#[test]
+fn call_mutable_state() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let counter = Py::new(py, Counter { count: 0 }).unwrap();
+    let locals = [("counter", counter.to_object(py))].into_py_dict(py);
+    run_both(
+        py,
+        locals,
+        r#"
+assert counter() == 1
+assert counter.__call__() == 2
+assert counter(step=3) == 5
+assert both(counter, 0) == (ValueError, "step must be positive")
+assert both(counter, -1)[0] is OverflowError
+"#,
+    );
+    assert_eq!(5, counter.as_ref(py).borrow().count);
+
+    // The borrow checker of the cell works through both entry points.
+    let _borrowed = counter.as_ref(py).borrow();
+    run_both(
+        py,
+        locals,
+        "assert both(counter)[0].__name__ == 'PyBorrowMutError'",
+    );
+}
+
+#[test]
+fn call_subclasses() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let counter = py.get_type::<Counter>();
+    let sub = Py::new(
+        py,
+        PyClassInitializer::from(Counter { count: 0 }).add_subclass(SubCounter {}),
+    )
+    .unwrap();
+    let double = Py::new(
+        py,
+        PyClassInitializer::from(Counter { count: 0 }).add_subclass(DoubleCounter { doubled: 0 }),
+    )
+    .unwrap();
+    let locals = [
+        ("Counter", counter.to_object(py)),
+        ("sub", sub.to_object(py)),
+        ("double", double.to_object(py)),
+    ]
+    .into_py_dict(py);
+    run_both(
+        py,
+        locals,
+        r#"
+# A Rust subclass without `__call__` uses the one of its base.
+assert sub() == 1
+assert sub.__call__(2) == 3
+assert both(sub, 0)[0] is ValueError
+
+# A Rust subclass with its own `__call__`.
+assert double() == 2
+assert double.__call__() == 4
+
+class PyCounter(Counter):
+    pass
+
+class Reset(Counter):
+    def __call__(self, step=1):
+        return 'reset'
+
+c = PyCounter()
+assert c() == 1
+assert c.__call__(2) == 3
+assert both(c, 0)[0] is ValueError
+assert both(Reset(), 2) == 'reset'
+"#,
+    );
+}
+
+#[test]
+#[cfg(all(Py_3_8, not(Py_LIMITED_API), not(PyPy)))]
+fn call_uses_vectorcall() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let have_vectorcall = |ty: &pyo3::types::PyType| {
+        ty.getattr("__flags__").unwrap().extract::<u64>().unwrap() & (1 << 11) != 0
+    };
+    assert!(have_vectorcall(py.get_type::<Positional>()));
+    assert!(have_vectorcall(py.get_type::<Counter>()));
+    assert!(have_vectorcall(py.get_type::<DoubleCounter>()));
+    assert!(!have_vectorcall(py.get_type::<SubCounter>()));
+}
@@ -1,5 +1,6 @@
 use pyo3::prelude::*;
 use pyo3::py_run;
+use pyo3::types::IntoPyDict;
 
 mod common;
 
@@ -222,3 +223,145 @@ except BaseException as e:
     test(unsendable_base.as_ref());
     test(unsendable_child.as_ref());
 }
+
+#[pyclass]
+struct LazilyInitialized {}
+
+#[pymethods]
+impl LazilyInitialized {
+    #[classattr]
+    fn answer() -> i32 {
+        // Let other threads run while the type object is being initialized.
+        let gil = Python::acquire_gil();
+        gil.python()
+            .allow_threads(|| std::thread::sleep(std::time::Duration::from_millis(1)));
+        42
+    }
+}
+
+#[pyfunction]
+fn make_lazily_initialized(py: Python) -> PyResult<Py<LazilyInitialized>> {
+    Py::new(py, LazilyInitialized {})
+}
+
+#[test]
+fn type_object_initialized_once_across_threads() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let make = pyo3::wrap_pyfunction!(make_lazily_initialized)(py);
+    let globals = [("make", make)].into_py_dict(py);
+    let source = pyo3::indoc::indoc!(
+        r#"
+import threading
+
+barrier = threading.Barrier(16)
+objects = []
+
+def create():
+    barrier.wait()
+    obj = make()
+    # The type must be fully initialized as soon as an instance exists.
+    assert type(obj).answer == 42
+    objects.append(obj)
+
+threads = [threading.Thread(target=create) for _ in range(16)]
+for thread in threads:
+    thread.start()
+for thread in threads:
+    thread.join()
+
+assert len(objects) == 16
+assert len({type(obj) for obj in objects}) == 1
+"#
+    );
+    py.run(source, Some(globals), None)
+        .map_err(|e| e.print(py))
+        .unwrap();
+}
+
+#[pyclass]
+struct Point {
+    #[pyo3(get)]
+    x: f64,
+    #[pyo3(get)]
+    y: f64,
+}
+
+#[pyclass(dict)]
+struct PointWithDict {
+    _x: f64,
+    _y: f64,
+}
+
+#[pyclass(dict, weakref)]
+struct PointWithDictAndWeakref {
+    _x: f64,
+    _y: f64,
+}
+
+#[pyclass(dict, weakref, unsendable)]
+struct UnsendablePoint {
+    _x: f64,
+    _y: f64,
+}
+
+#[test]
+fn instance_layout() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let header = std::mem::size_of::<pyo3::ffi::PyObject>();
+    let word = std::mem::size_of::<usize>();
+    let basicsize = |ty: &pyo3::types::PyType| -> usize {
+        ty.getattr("__basicsize__").unwrap().extract().unwrap()
+    };
+
+    // The fields, and one word for the borrow flag.
+    let point = py.get_type::<Point>();
+    assert_eq!(header + 16 + word, basicsize(point));
+    assert_eq!(std::mem::size_of::<PyCell<Point>>(), basicsize(point));
+    py_run!(
+        py,
+        point,
+        "assert point.__dictoffset__ == 0; assert point.__weakrefoffset__ == 0"
+    );
+
+    // One word for each of `dict` and `weakref`.
+    assert_eq!(
+        header + 16 + 2 * word,
+        basicsize(py.get_type::<PointWithDict>())
+    );
+    assert_eq!(
+        header + 16 + 3 * word,
+        basicsize(py.get_type::<PointWithDictAndWeakref>())
+    );
+    let unsendable = py.get_type::<UnsendablePoint>();
+    assert_eq!(
+        std::mem::size_of::<PyCell<UnsendablePoint>>(),
+        basicsize(unsendable)
+    );
+}
+
+#[test]
+fn dict_and_weakref_slots() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let both = PyCell::new(py, PointWithDictAndWeakref { _x: 0.0, _y: 0.0 }).unwrap();
+    let unsendable = PyCell::new(py, UnsendablePoint { _x: 0.0, _y: 0.0 }).unwrap();
+    py_run!(
+        py,
+        both unsendable,
+        r#"
+import weakref
+for obj in (both, unsendable):
+    ty = type(obj)
+    assert ty.__dictoffset__ != ty.__weakrefoffset__
+    assert max(ty.__dictoffset__, ty.__weakrefoffset__) < ty.__basicsize__
+    obj.attr = 42
+    assert obj.__dict__ == {'attr': 42}
+    ref = weakref.ref(obj)
+    assert ref() is obj
+"#
+    );
+    // The thread checker of an unsendable class is not overwritten by its `__dict__`.
+    assert!(unsendable.try_borrow().is_ok());
+}
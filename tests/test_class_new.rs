@@ -23,6 +23,18 @@ fn empty_class_with_new() {
         .is_ok());
 }
 
+#[test]
+fn cast_to_other_class_fails() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let obj = py.get_type::<EmptyClassWithNew>().call0().unwrap();
+    let err = obj.cast_as::<PyCell<NewWithOneArg>>().unwrap_err();
+    assert_eq!(err.to(), "NewWithOneArg");
+    assert!(err
+        .to_string()
+        .starts_with("expected 'NewWithOneArg', got 'EmptyClassWithNew' ("));
+}
+
 #[pyclass]
 #[derive(Debug)]
 struct NewWithOneArg {
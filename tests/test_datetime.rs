@@ -1,5 +1,6 @@
 use pyo3::prelude::*;
-use pyo3::types::IntoPyDict;
+use pyo3::types::{IntoPyDict, PyDateAccess};
+use pyo3::AsPyPointer;
 
 #[allow(clippy::trivially_copy_pass_by_ref)]
 fn _get_subclasses<'p>(
@@ -107,9 +108,9 @@ fn test_datetime_utc() {
     let py = gil.python();
     let datetime = py.import("datetime").map_err(|e| e.print(py)).unwrap();
     let timezone = datetime.get("timezone").unwrap();
-    let utc = timezone.getattr("utc").unwrap().to_object(py);
+    let utc = timezone.getattr("utc").unwrap().downcast().unwrap();
 
-    let dt = PyDateTime::new(py, 2018, 1, 1, 0, 0, 0, 0, Some(&utc)).unwrap();
+    let dt = PyDateTime::new(py, 2018, 1, 1, 0, 0, 0, 0, Some(utc)).unwrap();
 
     let locals = [("dt", dt)].into_py_dict(py);
 
@@ -121,6 +122,254 @@ fn test_datetime_utc() {
     assert_approx_eq!(offset, 0f32);
 }
 
+#[cfg(Py_3_6)]
+#[test]
+fn test_datetime_dst_boundary() {
+    use pyo3::types::{PyDateTime, PyTimeAccess, PyTzInfo};
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let module = PyModule::from_code(
+        py,
+        r#"
+from datetime import datetime, timedelta, tzinfo
+
+class Eastern(tzinfo):
+    """US Eastern time in 2020, when daylight saving time ended at 2:00 on November 1st."""
+
+    def utcoffset(self, dt):
+        return timedelta(hours=-5) + self.dst(dt)
+
+    def dst(self, dt):
+        naive = dt.replace(tzinfo=None)
+        if naive < datetime(2020, 11, 1, 1):
+            return timedelta(hours=1)
+        if naive < datetime(2020, 11, 1, 2) and not dt.fold:
+            return timedelta(hours=1)
+        return timedelta(0)
+
+    def tzname(self, dt):
+        return "EDT" if self.dst(dt) else "EST"
+
+eastern = Eastern()
+"#,
+        "eastern.py",
+        "eastern",
+    )
+    .unwrap();
+    let eastern: &PyTzInfo = module.get("eastern").unwrap().downcast().unwrap();
+
+    // 1:30 occurs twice, first in daylight saving time and then in standard time.
+    let first =
+        PyDateTime::new_with_fold(py, 2020, 11, 1, 1, 30, 0, 0, Some(eastern), false).unwrap();
+    let second =
+        PyDateTime::new_with_fold(py, 2020, 11, 1, 1, 30, 0, 0, Some(eastern), true).unwrap();
+    let first_timestamp = first.timestamp().unwrap();
+    assert_eq!(first_timestamp, 1_604_208_600.0);
+    assert_eq!(second.timestamp().unwrap() - first_timestamp, 3600.0);
+
+    for (dt, fold) in &[(first, 0), (second, 1)] {
+        assert_eq!(dt.get_year(), 2020);
+        assert_eq!(dt.get_month(), 11);
+        assert_eq!(dt.get_day(), 1);
+        assert_eq!(dt.get_hour(), 1);
+        assert_eq!(dt.get_minute(), 30);
+        assert_eq!(dt.get_second(), 0);
+        assert_eq!(dt.get_microsecond(), 0);
+        #[cfg(not(PyPy))]
+        assert_eq!(dt.get_fold(), *fold);
+        assert_eq!(dt.get_tzinfo().unwrap().as_ptr(), eastern.as_ptr());
+    }
+
+    let naive = PyDateTime::new(py, 2020, 11, 1, 1, 30, 0, 0, None).unwrap();
+    assert!(naive.get_tzinfo().is_none());
+}
+
+#[test]
+fn test_datetime_from_timestamp() {
+    use pyo3::types::{PyDate, PyDateTime, PyTimeAccess, PyTzInfo};
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let utc: &PyTzInfo = py
+        .import("datetime")
+        .unwrap()
+        .get("timezone")
+        .unwrap()
+        .getattr("utc")
+        .unwrap()
+        .downcast()
+        .unwrap();
+
+    let dt = PyDateTime::from_timestamp(py, 1_604_208_600.5, Some(utc)).unwrap();
+    assert_eq!((dt.get_year(), dt.get_month(), dt.get_day()), (2020, 11, 1));
+    assert_eq!(
+        (dt.get_hour(), dt.get_minute(), dt.get_second()),
+        (5, 30, 0)
+    );
+    assert_eq!(dt.get_microsecond(), 500_000);
+    assert_eq!(dt.get_tzinfo().unwrap().as_ptr(), utc.as_ptr());
+    assert_eq!(dt.timestamp().unwrap(), 1_604_208_600.5);
+
+    let date = PyDate::from_timestamp(py, 1_604_208_600).unwrap();
+    assert_eq!(date.get_year(), 2020);
+}
+
+fn delta_tuple(delta: &pyo3::types::PyDelta) -> (i32, i32, i32) {
+    use pyo3::types::PyDeltaAccess;
+    (
+        delta.get_days(),
+        delta.get_seconds(),
+        delta.get_microseconds(),
+    )
+}
+
+#[test]
+fn test_delta_normalized() {
+    use pyo3::types::PyDelta;
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    for &(days, seconds, microseconds) in &[
+        (0, -1, 0),
+        (-1, 86399, 0),
+        (0, 0, -1),
+        (1, 90000, 1_500_000),
+        (0, -86401, -1_000_001),
+    ] {
+        let delta = PyDelta::new_normalized(py, days, seconds, microseconds).unwrap();
+        let locals = [("delta", delta)].into_py_dict(py);
+        let expected: (i32, i32, i32) = py
+            .eval(
+                &format!(
+                    "(lambda d: (d.days, d.seconds, d.microseconds))(\
+                     __import__('datetime').timedelta({}, {}, {}))",
+                    days, seconds, microseconds
+                ),
+                None,
+                None,
+            )
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(delta_tuple(delta), expected);
+        let total_seconds: f64 = py
+            .eval("delta.total_seconds()", None, Some(locals))
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(delta.total_seconds(), total_seconds);
+    }
+    assert_eq!(
+        delta_tuple(PyDelta::new_normalized(py, 0, -1, 0).unwrap()),
+        (-1, 86399, 0)
+    );
+
+    let err = PyDelta::new_normalized(py, 999_999_999, 86400, 0).unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::OverflowError>(py));
+}
+
+#[test]
+fn test_delta_arithmetic() {
+    use pyo3::types::PyDelta;
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let a = PyDelta::new_normalized(py, 1, 3600, 250_000).unwrap();
+    let b = PyDelta::new_normalized(py, -3, 86399, 999_999).unwrap();
+    let locals = [("a", a), ("b", b)].into_py_dict(py);
+    let python = |expr: &str| -> (i32, i32, i32) {
+        let delta: &PyDelta = py
+            .eval(expr, None, Some(locals))
+            .unwrap()
+            .downcast()
+            .unwrap();
+        delta_tuple(delta)
+    };
+
+    assert_eq!(delta_tuple(a.add(b).unwrap()), python("a + b"));
+    assert_eq!(delta_tuple(a.sub(b).unwrap()), python("a - b"));
+    assert_eq!(delta_tuple(b.sub(a).unwrap()), python("b - a"));
+    assert_eq!(delta_tuple(a.neg().unwrap()), python("-a"));
+    assert_eq!(delta_tuple(b.neg().unwrap()), python("-b"));
+    assert_eq!(delta_tuple(a.abs().unwrap()), python("abs(a)"));
+    assert_eq!(delta_tuple(b.abs().unwrap()), python("abs(b)"));
+
+    let max = PyDelta::new_normalized(py, 999_999_999, 86399, 999_999).unwrap();
+    let err = max.add(a).unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::OverflowError>(py));
+    let min = PyDelta::new_normalized(py, -999_999_999, 0, 0).unwrap();
+    let err = min.sub(a).unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::OverflowError>(py));
+}
+
+#[test]
+fn test_tzinfo_utc() {
+    use pyo3::types::{PyDateTime, PyTzInfo};
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let utc = PyTzInfo::utc(py);
+    let expected = py
+        .import("datetime")
+        .unwrap()
+        .get("timezone")
+        .unwrap()
+        .getattr("utc")
+        .unwrap();
+    assert_eq!(utc.as_ptr(), expected.as_ptr());
+    assert_eq!(PyTzInfo::utc(py).as_ptr(), utc.as_ptr());
+
+    let offset = utc.utcoffset(None).unwrap().unwrap();
+    assert_eq!(delta_tuple(offset), (0, 0, 0));
+
+    let dt = PyDateTime::new(py, 2020, 6, 1, 12, 0, 0, 0, Some(utc)).unwrap();
+    let iso: String = dt.call_method0("isoformat").unwrap().extract().unwrap();
+    assert_eq!(iso, "2020-06-01T12:00:00+00:00");
+}
+
+#[test]
+fn test_tzinfo_fixed_offset() {
+    use pyo3::types::{PyDateTime, PyDelta, PyTzInfo};
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let offset = PyDelta::new_normalized(py, 0, 5 * 3600 + 30 * 60, 0).unwrap();
+    let ist = PyTzInfo::fixed_offset(py, offset, Some("IST")).unwrap();
+    let dt = PyDateTime::new(py, 2020, 6, 1, 12, 0, 0, 0, Some(ist)).unwrap();
+
+    let iso: String = dt.call_method0("isoformat").unwrap().extract().unwrap();
+    assert!(iso.ends_with("+05:30"), "{}", iso);
+    let name: String = dt.call_method0("tzname").unwrap().extract().unwrap();
+    assert_eq!(name, "IST");
+    assert_eq!(
+        delta_tuple(ist.utcoffset(Some(dt)).unwrap().unwrap()),
+        (0, 19800, 0)
+    );
+
+    let unnamed = PyTzInfo::fixed_offset(py, offset.neg().unwrap(), None).unwrap();
+    let name: String = unnamed
+        .call_method1("tzname", (py.None(),))
+        .unwrap()
+        .extract()
+        .unwrap();
+    assert_eq!(name, "UTC-05:30");
+
+    let too_large = PyDelta::new_normalized(py, 1, 0, 0).unwrap();
+    let err = PyTzInfo::fixed_offset(py, too_large, None)
+        .map(|_| ())
+        .unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::ValueError>(py));
+
+    // The abstract base class does not implement `utcoffset`.
+    let naive: &PyTzInfo = py
+        .eval("__import__('datetime').tzinfo()", None, None)
+        .unwrap()
+        .downcast()
+        .unwrap();
+    assert!(naive.utcoffset(None).is_err());
+}
+
 #[cfg(Py_3_6)]
 static INVALID_DATES: &[(i32, u8, u8)] = &[
     (-1, 1, 1),
@@ -167,7 +167,7 @@ impl PySequenceProtocol for Sequence {
     }
 
     fn __getitem__(&self, key: isize) -> PyResult<String> {
-        let idx = usize::try_from(key)?;
+        let idx = usize::try_from(key).map_err(|_| PyErr::new::<IndexError, _>(()))?;
         if let Some(s) = self.fields.get(idx) {
             Ok(s.clone())
         } else {
@@ -176,7 +176,7 @@ impl PySequenceProtocol for Sequence {
     }
 
     fn __setitem__(&mut self, idx: isize, value: String) -> PyResult<()> {
-        let idx = usize::try_from(idx)?;
+        let idx = usize::try_from(idx).map_err(|_| PyErr::new::<IndexError, _>(()))?;
         if let Some(elem) = self.fields.get_mut(idx) {
             *elem = value;
             Ok(())
@@ -1,4 +1,6 @@
+use pyo3::panic::{PanicException, PanicInfo};
 use pyo3::prelude::*;
+use pyo3::types::IntoPyDict;
 use pyo3::{exceptions, py_run, wrap_pyfunction, PyErr, PyResult};
 use std::error::Error;
 use std::fmt;
@@ -28,7 +30,8 @@ fn test_filenotfounderror() {
         try:
             fail_to_open_file()
         except FileNotFoundError as e:
-            assert str(e) == "No such file or directory (os error 2)"
+            assert e.errno == 2
+            assert str(e) == "[Errno 2] No such file or directory"
         "#
     );
 }
@@ -85,10 +88,86 @@ fn test_exception_nosegfault() {
         TcpListener::bind("no:address")?;
         Ok(())
     }
-    fn parse_int() -> PyResult<()> {
-        "@_@".parse::<i64>()?;
-        Ok(())
-    }
     assert!(io_err().is_err());
-    assert!(parse_int().is_err());
+    #[cfg(feature = "std-error-conversions")]
+    {
+        fn parse_int() -> PyResult<()> {
+            "@_@".parse::<i64>()?;
+            Ok(())
+        }
+        assert!(parse_int().is_err());
+    }
+}
+
+#[pyfunction]
+fn panic_with_message() {
+    panic!("{} went wrong", "something");
+}
+
+fn custom_panic_hook(py: Python, info: &PanicInfo) -> PyErr {
+    let message = info.message().unwrap_or("panic from Rust code");
+    match py.get_type::<PanicException>().call1((message,)) {
+        Ok(exc) => {
+            exc.setattr("thread_name", info.thread_name()).unwrap();
+            exc.setattr("custom", true).unwrap();
+            PyErr::from_instance(exc)
+        }
+        Err(e) => e,
+    }
+}
+
+#[test]
+fn test_panic_hook() {
+    // If -Cpanic=abort is specified, we can't catch panic.
+    if option_env!("RUSTFLAGS")
+        .map(|s| s.contains("-Cpanic=abort"))
+        .unwrap_or(false)
+    {
+        return;
+    }
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let panic_with_message = wrap_pyfunction!(panic_with_message)(py);
+    let panic_exception = py.get_type::<PanicException>();
+    assert!(py
+        .is_subclass::<PanicException, exceptions::BaseException>()
+        .unwrap());
+
+    py_run!(
+        py,
+        panic_with_message panic_exception,
+        r#"
+        import pyo3_runtime
+        assert pyo3_runtime.PanicException is panic_exception
+        try:
+            panic_with_message()
+        except pyo3_runtime.PanicException as e:
+            assert e.args == ("something went wrong",)
+            assert not hasattr(e, "custom")
+        else:
+            assert False
+        "#
+    );
+
+    pyo3::panic::set_hook(custom_panic_hook);
+    let locals = [("panic_with_message", panic_with_message)].into_py_dict(py);
+    // Fetching the re-raised PanicException resumes the panic.
+    let resumed = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        py.run(
+            r#"
+try:
+    panic_with_message()
+except BaseException as e:
+    assert e.args == ("something went wrong",)
+    assert e.custom
+    assert e.thread_name == "test_panic_hook"
+    raise
+"#,
+            None,
+            Some(locals),
+        )
+    }));
+    assert!(pyo3::panic::take_hook().is_some());
+    assert!(resumed.is_err());
 }
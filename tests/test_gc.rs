@@ -3,7 +3,8 @@ use pyo3::class::PyTraverseError;
 use pyo3::class::PyVisit;
 use pyo3::prelude::*;
 use pyo3::type_object::PyTypeObject;
-use pyo3::{py_run, AsPyPointer, PyCell, PyTryInto};
+use pyo3::types::PyDict;
+use pyo3::{py_run, wrap_pyfunction, AsPyPointer, PyCell, PyTryInto};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
@@ -306,3 +307,118 @@ fn gc_during_borrow() {
         drop(guard);
     }
 }
+
+#[pyclass]
+struct PanicOnDrop {}
+
+#[pymethods]
+impl PanicOnDrop {
+    #[new]
+    fn new() -> Self {
+        PanicOnDrop {}
+    }
+}
+
+impl Drop for PanicOnDrop {
+    fn drop(&mut self) {
+        panic!("drop failed");
+    }
+}
+
+#[test]
+#[cfg(Py_3_8)]
+fn panic_in_drop_is_unraisable() {
+    // If -Cpanic=abort is specified, we can't catch panic.
+    if option_env!("RUSTFLAGS")
+        .map(|s| s.contains("-Cpanic=abort"))
+        .unwrap_or(false)
+    {
+        return;
+    }
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let make = py.get_type::<PanicOnDrop>();
+    py_run!(
+        py,
+        make,
+        r#"
+        import sys
+        reported = []
+        original_hook = sys.unraisablehook
+        sys.unraisablehook = reported.append
+        try:
+            obj = make()
+            del obj
+        finally:
+            sys.unraisablehook = original_hook
+        assert len(reported) == 1
+        assert reported[0].exc_type.__name__ == "PanicException"
+        assert str(reported[0].exc_value) == "drop failed"
+        "#
+    );
+}
+
+#[pyfunction]
+fn panic_in_del() {
+    panic!("__del__ failed");
+}
+
+#[test]
+#[cfg(Py_3_8)]
+fn panic_in_del_during_pool_drop_while_unwinding() {
+    // If -Cpanic=abort is specified, we can't catch panic.
+    if option_env!("RUSTFLAGS")
+        .map(|s| s.contains("-Cpanic=abort"))
+        .unwrap_or(false)
+    {
+        return;
+    }
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let globals = PyDict::new(py);
+    globals
+        .set_item("panic_in_del", wrap_pyfunction!(panic_in_del)(py))
+        .unwrap();
+    globals
+        .set_item("PanicOnDrop", py.get_type::<PanicOnDrop>())
+        .unwrap();
+    py.run(
+        r#"
+import sys
+reported = []
+original_hook = sys.unraisablehook
+sys.unraisablehook = reported.append
+
+class WithDel:
+    def __del__(self):
+        panic_in_del()
+"#,
+        Some(globals),
+        None,
+    )
+    .unwrap();
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let pool = unsafe { py.new_pool() };
+        let py = pool.python();
+        // Only owned by the pool, which drops them while unwinding.
+        py.eval("WithDel()", Some(globals), None).unwrap();
+        py.eval("PanicOnDrop()", Some(globals), None).unwrap();
+        panic!("unwinding");
+    }));
+    assert!(result.is_err());
+
+    py.run(
+        r#"
+sys.unraisablehook = original_hook
+assert len(reported) == 2, reported
+assert all(r.exc_type.__name__ == "PanicException" for r in reported)
+assert {str(r.exc_value) for r in reported} == {"__del__ failed", "drop failed"}
+"#,
+        Some(globals),
+        None,
+    )
+    .unwrap();
+}
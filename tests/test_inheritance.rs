@@ -91,6 +91,42 @@ fn call_base_and_sub_methods() {
     );
 }
 
+#[pyclass(extends=BaseClass)]
+struct OverridingSubClass {}
+
+#[pymethods]
+impl OverridingSubClass {
+    #[new]
+    fn new() -> (Self, BaseClass) {
+        (OverridingSubClass {}, BaseClass { val1: 10 })
+    }
+
+    fn base_method(self_: PyRef<Self>, x: usize) -> PyResult<usize> {
+        let base: usize = self_
+            .py_super()?
+            .call_method1("base_method", (x,))?
+            .extract()?;
+        Ok(base + 1)
+    }
+}
+
+#[test]
+fn call_overridden_base_method() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let obj = PyCell::new(py, OverridingSubClass::new()).unwrap();
+    py_run!(py, obj, "assert obj.base_method(10) == 101");
+
+    let base: usize = obj
+        .py_super()
+        .unwrap()
+        .call_method1("base_method", (10,))
+        .unwrap()
+        .extract()
+        .unwrap();
+    assert_eq!(100, base);
+}
+
 #[test]
 fn mutation_fails() {
     let gil = Python::acquire_gil();
@@ -113,7 +149,8 @@ impl BaseClassWithResult {
     #[new]
     fn new(value: isize) -> PyResult<Self> {
         Ok(Self {
-            _val: std::convert::TryFrom::try_from(value)?,
+            _val: std::convert::TryFrom::try_from(value)
+                .map_err(|_| pyo3::exceptions::OverflowError::py_err("negative value"))?,
         })
     }
 }
@@ -142,7 +179,7 @@ fn handle_result_in_new() {
 try:
     subclass(-10)
     assert Fals
-except ValueError as e:
+except OverflowError as e:
     pass
 except Exception as e:
     raise e
@@ -203,3 +240,43 @@ fn inherit_dict() {
         r#"dict_sub[0] = 1; assert dict_sub[0] == 1; assert dict_sub._name == "Hello :)""#
     );
 }
+
+#[pyclass(extends=PyDict)]
+struct DictWithDefault {
+    #[pyo3(get)]
+    misses: usize,
+}
+
+#[pymethods]
+impl DictWithDefault {
+    #[new]
+    fn new() -> Self {
+        DictWithDefault { misses: 0 }
+    }
+
+    fn get(mut self_: PyRefMut<Self>, key: &PyAny) -> PyResult<PyObject> {
+        let value = self_.py_super()?.call_method1("get", (key, "default"))?;
+        if value.extract::<&str>().ok() == Some("default") {
+            self_.misses += 1;
+        }
+        Ok(value.into())
+    }
+}
+
+#[test]
+fn call_overridden_dict_method() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let dict_sub = pyo3::PyCell::new(py, DictWithDefault::new()).unwrap();
+    py_run!(
+        py,
+        dict_sub,
+        r#"
+    dict_sub["a"] = 1
+    assert dict_sub.get("a") == 1
+    assert dict_sub.get("b") == "default"
+    assert dict_sub.misses == 1
+    assert isinstance(dict_sub, dict)
+"#
+    );
+}
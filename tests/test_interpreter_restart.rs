@@ -0,0 +1,37 @@
+//! Finalizing and initializing the interpreter again, in a separate binary as it invalidates the
+//! objects of all other tests.
+use pyo3::prelude::*;
+use pyo3::{ffi, import_cached};
+
+fn dumps(py: Python) -> PyResult<String> {
+    import_cached!(py, "json")?.call1("dumps", (1,))?.extract()
+}
+
+#[test]
+#[cfg(not(PyPy))]
+fn import_cached_after_restart() {
+    pyo3::prepare_freethreaded_python();
+    {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        assert_eq!(dumps(py).unwrap(), "1");
+        assert_eq!(py.import_cached("json").unwrap().name().unwrap(), "json");
+    }
+
+    unsafe {
+        ffi::PyGILState_Ensure();
+        assert_eq!(ffi::Py_FinalizeEx(), 0);
+        ffi::Py_InitializeEx(0);
+        ffi::PyEval_SaveThread();
+    }
+
+    for _ in 0..2 {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        // The modules cached in the finalized interpreter are imported again.
+        let json = py.import("json").unwrap();
+        assert!(import_cached!(py, "json").unwrap().is(json));
+        assert!(py.import_cached("json").unwrap().is(json));
+        assert_eq!(dumps(py).unwrap(), "1");
+    }
+}
@@ -124,3 +124,42 @@ fn test_reversed() {
 
     run("m = Mapping(['1', '2']); assert set(reversed(m)) == {'1', '2'}");
 }
+
+#[test]
+fn test_register_mapping() {
+    use pyo3::types::PyMapping;
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let mapping = Py::new(
+        py,
+        Mapping {
+            index: HashMap::new(),
+        },
+    )
+    .unwrap();
+    let mapping: &PyAny = mapping.as_ref(py);
+    assert!(mapping.downcast::<PyMapping>().is_err());
+
+    PyMapping::register::<Mapping>(py).unwrap();
+    let d = [("mapping", mapping)].into_py_dict(py);
+    py.run(
+        "import collections.abc; assert isinstance(mapping, collections.abc.Mapping)",
+        None,
+        Some(d),
+    )
+    .unwrap();
+
+    let mapping = mapping.downcast::<PyMapping>().unwrap();
+    assert!(mapping.is_empty().unwrap());
+    mapping.set_item("a", 1).unwrap();
+    mapping.set_item("b", 2).unwrap();
+    assert_eq!(2, mapping.len().unwrap());
+    assert_eq!(
+        2,
+        mapping.get_item("b").unwrap().extract::<usize>().unwrap()
+    );
+    mapping.del_item("a").unwrap();
+    let err = mapping.get_item("a").map(|_| ()).unwrap_err();
+    assert!(err.is_instance::<KeyError>(py));
+}
@@ -528,3 +528,149 @@ fn test_from_sequence() {
     let typeobj = py.get_type::<FromSequence>();
     py_assert!(py, typeobj, "typeobj(range(0, 4)).numbers == [0, 1, 2, 3]")
 }
+
+#[test]
+fn meth_args_error_messages() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let inst = Py::new(py, MethArgs {}).unwrap();
+    let d = [("inst", &inst)].into_py_dict(py);
+    let message = |code: &str| {
+        let err = py.run(code, None, Some(d)).unwrap_err();
+        assert!(err.is_instance::<pyo3::exceptions::TypeError>(py));
+        err.to_object(py).as_ref(py).to_string()
+    };
+    assert_eq!(
+        message("inst.get_pos_arg_kw()"),
+        "MethArgs.get_pos_arg_kw() missing required positional argument: a"
+    );
+    assert_eq!(
+        message("inst.get_pos_arg_kw(1, a=1)"),
+        "MethArgs.get_pos_arg_kw() got multiple values for argument: a"
+    );
+    assert_eq!(
+        message("inst.get_pos_arg_kw(b=2)"),
+        "MethArgs.get_pos_arg_kw() missing required positional argument: a"
+    );
+    assert_eq!(
+        message("inst.get_pos_arg_kw_sep1(1, 2, 3)"),
+        "MethArgs.get_pos_arg_kw_sep1() takes at most 2 positional arguments (3 given)"
+    );
+    assert_eq!(
+        message("inst.get_pos_arg_kw_sep1(1, d=4)"),
+        "MethArgs.get_pos_arg_kw_sep1() got an unexpected keyword argument: d"
+    );
+    assert_eq!(
+        message("inst.get_pos_arg_kw_sep1(a=1, b=2, c=3, d=4, e=5)"),
+        "MethArgs.get_pos_arg_kw_sep1() got an unexpected keyword argument: d"
+    );
+    assert_eq!(
+        message("inst.get_optional(1, 2)"),
+        "MethArgs.get_optional() takes at most 1 positional argument (2 given)"
+    );
+    assert_eq!(
+        message("inst.get_pos_kw(1, 2)"),
+        "MethArgs.get_pos_kw() takes at most 1 positional argument (2 given)"
+    );
+    assert_eq!(
+        message("inst.get_kwarg(100)"),
+        "MethArgs.get_kwarg() takes at most 0 positional arguments (1 given)"
+    );
+    assert_eq!(
+        message("inst.get_pos_arg_kw_sep1(b=2, d=4)"),
+        "MethArgs.get_pos_arg_kw_sep1() missing required positional argument: a"
+    );
+}
+
+#[pyclass]
+#[derive(Debug)]
+struct Reentrant {
+    value: i32,
+}
+
+#[pymethods]
+impl Reentrant {
+    fn value(&self) -> i32 {
+        self.value
+    }
+
+    fn set_value(&mut self, value: i32) {
+        self.value = value;
+    }
+
+    fn update(&mut self, callback: &PyAny) -> PyResult<()> {
+        self.value = callback.call0()?.extract()?;
+        Ok(())
+    }
+
+    fn inspect(&self, callback: &PyAny) -> PyResult<PyObject> {
+        callback.call0().map(Into::into)
+    }
+}
+
+#[test]
+fn reentrant_borrow_errors() {
+    use pyo3::pycell::{PyBorrowError, PyBorrowMutError};
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let obj = PyCell::new(py, Reentrant { value: 0 }).unwrap();
+    let run = |code: &str| {
+        // The callbacks look up `obj` in the globals.
+        let globals = [("obj", obj)].into_py_dict(py);
+        py.run(code, Some(globals), None).unwrap_err()
+    };
+
+    let err = run("obj.update(lambda: obj.value())");
+    assert!(err.is_instance::<PyBorrowError>(py));
+    assert!(err.is_instance::<pyo3::exceptions::RuntimeError>(py));
+    assert_eq!(
+        err.to_object(py)
+            .as_ref(py)
+            .str()
+            .unwrap()
+            .to_string()
+            .unwrap(),
+        "cannot call 'Reentrant.value': the 'Reentrant' object is already mutably borrowed, \
+         probably by a method of the object taking `&mut self` which called 'Reentrant.value' \
+         again, e.g. through a callback (a re-entrant call)"
+    );
+
+    let err = run("obj.inspect(lambda: obj.set_value(1))");
+    assert!(err.is_instance::<PyBorrowMutError>(py));
+    assert_eq!(
+        err.to_object(py)
+            .as_ref(py)
+            .str()
+            .unwrap()
+            .to_string()
+            .unwrap(),
+        "cannot call 'Reentrant.set_value': the 'Reentrant' object is already borrowed, \
+         probably by a method of the object which called 'Reentrant.set_value', which takes \
+         `&mut self`, e.g. through a callback (a re-entrant call)"
+    );
+
+    // Re-entrant calls which only borrow immutably succeed.
+    let globals = [("obj", obj)].into_py_dict(py);
+    py.run(
+        "assert obj.inspect(lambda: obj.value()) == 0",
+        Some(globals),
+        None,
+    )
+    .unwrap();
+
+    // Borrow errors from Rust record the class but no method.
+    let _borrowed = obj.borrow_mut();
+    let err = obj.try_borrow().unwrap_err();
+    assert_eq!(err.class_name(), "Reentrant");
+    assert_eq!(err.method(), None);
+    assert_eq!(
+        err.to_string(),
+        "the 'Reentrant' object is already mutably borrowed"
+    );
+    let err = obj.try_borrow_mut().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "the 'Reentrant' object is already borrowed"
+    );
+}
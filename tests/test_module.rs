@@ -1,6 +1,7 @@
 use pyo3::prelude::*;
 
-use pyo3::types::{IntoPyDict, PyTuple};
+use pyo3::types::{IntoPyDict, PyDict, PyTuple};
+use pyo3::AsPyPointer;
 
 mod common;
 
@@ -148,6 +149,67 @@ fn test_module_from_code() {
     assert_eq!(ret_value, 3);
 }
 
+#[test]
+fn test_module_from_code_namespace() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let plugin = PyModule::from_code(
+        py,
+        "GREETING = 'hello'\ndef greet(name):\n    return f'{GREETING} {name} from {__name__}'\ndef fail():\n    raise ValueError('plugin failed')\n",
+        "plugin_source.py",
+        "from_code_plugin",
+    )
+    .unwrap();
+
+    assert_eq!(plugin.name().unwrap(), "from_code_plugin");
+    assert_eq!(plugin.filename().unwrap(), "plugin_source.py");
+    let greeting: String = plugin.call1("greet", ("Rust",)).unwrap().extract().unwrap();
+    assert_eq!(greeting, "hello Rust from from_code_plugin");
+
+    pyo3::py_run!(
+        py,
+        plugin,
+        r#"
+        import sys, traceback
+        assert sys.modules["from_code_plugin"] is plugin
+        try:
+            plugin.fail()
+        except ValueError as e:
+            frame = traceback.extract_tb(e.__traceback__)[-1]
+            assert frame.filename == "plugin_source.py"
+            assert frame.lineno == 5
+            assert frame.name == "fail"
+        else:
+            assert False
+        "#
+    );
+}
+
+#[test]
+fn test_module_from_code_syntax_error() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let err = PyModule::from_code(py, "x = 1\ndef broken(:\n", "broken.py", "broken")
+        .map(|_| ())
+        .unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::SyntaxError>(py));
+    let err = err.to_object(py);
+    let filename: String = err.getattr(py, "filename").unwrap().extract(py).unwrap();
+    let lineno: i32 = err.getattr(py, "lineno").unwrap().extract(py).unwrap();
+    assert_eq!(filename, "broken.py");
+    assert_eq!(lineno, 2);
+
+    let err = PyModule::from_code(py, "raise RuntimeError('init')", "raises.py", "raises")
+        .map(|_| ())
+        .unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::RuntimeError>(py));
+    let sys = py.import("sys").unwrap();
+    py_assert!(py, sys, "'broken' not in sys.modules");
+    py_assert!(py, sys, "'raises' not in sys.modules");
+}
+
 #[pyfunction]
 fn r#move() -> usize {
     42
@@ -285,3 +347,388 @@ fn test_vararg_module() {
     py_assert!(py, m, "m.int_vararg_fn() == [5, ()]");
     py_assert!(py, m, "m.int_vararg_fn(1, 2) == [1, (2,)]");
 }
+
+#[pymodule]
+fn package(_py: Python, m: &PyModule) -> PyResult<()> {
+    /// The core of the package.
+    #[pymodule]
+    fn _core(_py: Python, m: &PyModule) -> PyResult<()> {
+        #[pymodule(io)]
+        fn io_module(_py: Python, m: &PyModule) -> PyResult<()> {
+            #[pyfn(m, "read")]
+            fn read() -> &'static str {
+                "data"
+            }
+            Ok(())
+        }
+
+        m.add("answer", 42)?;
+        Ok(())
+    }
+
+    m.add_submodule(PyModule::new(_py, "utils")?)?;
+    Ok(())
+}
+
+#[test]
+fn test_submodule_import() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let package = pyo3::wrap_pymodule!(package)(py);
+    let sys_modules = py.import("sys").unwrap().get("modules").unwrap();
+    sys_modules.set_item("package", &package).unwrap();
+
+    pyo3::py_run!(
+        py,
+        package,
+        r#"
+        import importlib
+        core = importlib.import_module("package._core")
+        assert core is package._core
+        assert core.__name__ == "package._core"
+        assert core.__package__ == "package"
+        assert core.__doc__ == "The core of the package."
+        assert core.answer == 42
+        io = importlib.import_module("package._core.io")
+        assert io is core.io
+        assert io.__name__ == "package._core.io"
+        assert io.__package__ == "package._core"
+        assert io.read() == "data"
+        from package._core.io import read
+        assert read() == "data"
+        import package.utils
+        assert package.utils.__name__ == "package.utils"
+        assert package.__all__ == ["utils"]
+        "#
+    );
+}
+
+#[pyclass]
+struct ExportedClass {}
+
+#[pyfunction]
+fn exported_function() -> usize {
+    1
+}
+
+#[pymodule]
+fn star_module(py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_class::<ExportedClass>()?;
+    m.add_wrapped(pyo3::wrap_pyfunction!(exported_function))?;
+    m.add("exported_constant", 2)?;
+    m.add("_private_constant", 3)?;
+    m.setattr("unexported_constant", 4)?;
+    m.add_with_export("hidden_constant", 5, false)?;
+    m.add_with_export("_exported_private_constant", 6, true)?;
+    m.add_submodule(PyModule::new(py, "exported_submodule")?)?;
+    Ok(())
+}
+
+#[test]
+fn test_module_star_import() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let module = pyo3::wrap_pymodule!(star_module)(py);
+    let module: &PyModule = module.extract(py).unwrap();
+    let sys_modules = py.import("sys").unwrap().get("modules").unwrap();
+    sys_modules.set_item("star_module", module).unwrap();
+
+    assert!(module.dict().contains("unexported_constant").unwrap());
+    assert!(module.dict().contains("hidden_constant").unwrap());
+    let index: Vec<String> = module.index().unwrap().extract().unwrap();
+    assert_eq!(
+        index,
+        [
+            "ExportedClass",
+            "exported_function",
+            "exported_constant",
+            "_exported_private_constant",
+            "exported_submodule"
+        ]
+    );
+
+    let namespace = PyDict::new(py);
+    py.run("from star_module import *", None, Some(namespace))
+        .unwrap();
+    let mut names: Vec<String> = namespace.keys().extract().unwrap();
+    names.sort();
+    assert_eq!(
+        names,
+        [
+            "ExportedClass",
+            "_exported_private_constant",
+            "exported_constant",
+            "exported_function",
+            "exported_submodule"
+        ]
+    );
+}
+
+/// A module using multi-phase initialization, with state of its own.
+#[pymodule(multi_phase)]
+fn multi_phase_module(py: Python, m: &PyModule) -> PyResult<()> {
+    use pyo3::wrap_pyfunction;
+
+    m.add_class::<ValueClass>()?;
+    m.add_wrapped(wrap_pyfunction!(double))?;
+    m.add("state", pyo3::types::PyList::empty(py))?;
+    Ok(())
+}
+
+/// Creates a `multi_phase_module` from its definition, like the import system.
+#[pyfunction]
+fn create_multi_phase_module(py: Python, spec: &PyAny) -> PyResult<PyObject> {
+    unsafe {
+        let def = PyInit_multi_phase_module() as *mut pyo3::ffi::PyModuleDef;
+        PyObject::from_owned_ptr_or_err(py, pyo3::ffi::PyModule_FromDefAndSpec(def, spec.as_ptr()))
+    }
+}
+
+/// Executes a `multi_phase_module`, like the import system.
+#[pyfunction]
+fn exec_multi_phase_module(py: Python, module: &PyModule) -> PyResult<()> {
+    unsafe {
+        let def = PyInit_multi_phase_module() as *mut pyo3::ffi::PyModuleDef;
+        if pyo3::ffi::PyModule_ExecDef(module.as_ptr(), def) != 0 {
+            return Err(PyErr::fetch(py));
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_multi_phase_reimport() {
+    use pyo3::wrap_pyfunction;
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let locals = [
+        (
+            "create_module",
+            wrap_pyfunction!(create_multi_phase_module)(py),
+        ),
+        ("exec_module", wrap_pyfunction!(exec_multi_phase_module)(py)),
+    ]
+    .into_py_dict(py);
+    py.run(
+        r#"
+import importlib.util
+import sys
+
+class Loader:
+    def create_module(self, spec):
+        return create_module(spec)
+
+    def exec_module(self, module):
+        exec_module(module)
+
+class Finder:
+    def find_spec(self, name, path, target=None):
+        if name == "multi_phase_module":
+            return importlib.util.spec_from_loader(name, Loader())
+
+finder = Finder()
+sys.meta_path.insert(0, finder)
+try:
+    import multi_phase_module as first
+    assert first.__doc__ == "A module using multi-phase initialization, with state of its own."
+    assert first.__spec__.name == "multi_phase_module"
+    first.state.append("first")
+
+    # The module is initialized again when it is imported again.
+    del sys.modules["multi_phase_module"]
+    import multi_phase_module as second
+    assert second is not first
+    assert second.state == [] and first.state == ["first"]
+    assert first.double(2) == 4 and second.double(3) == 6
+    # `#[pyclass]` type objects are static, so shared between the modules.
+    assert first.ValueClass is second.ValueClass
+    assert isinstance(first.ValueClass(1), second.ValueClass)
+
+    # Reloading executes the module again.
+    second.state.append("second")
+    importlib.reload(second)
+    assert second.state == []
+finally:
+    sys.meta_path.remove(finder)
+    sys.modules.pop("multi_phase_module", None)
+"#,
+        Some(locals),
+        None,
+    )
+    .map_err(|e| e.print(py))
+    .unwrap();
+}
+
+#[test]
+fn test_multi_phase_wrap_pymodule() {
+    use pyo3::wrap_pymodule;
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let first = wrap_pymodule!(multi_phase_module)(py);
+    let second = wrap_pymodule!(multi_phase_module)(py);
+    pyo3::py_run!(
+        py,
+        first second,
+        r#"
+assert first is not second and first.state is not second.state
+assert first.__name__ == "multi_phase_module"
+assert first.double(21) == 42
+"#
+    );
+}
+
+static COUNTER_DROPS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+struct Counter(std::sync::atomic::AtomicU64);
+
+impl Drop for Counter {
+    fn drop(&mut self) {
+        COUNTER_DROPS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+#[pyfunction]
+fn increment(counter: pyo3::types::ModuleState<Counter>, step: u64) -> u64 {
+    counter
+        .0
+        .fetch_add(step, std::sync::atomic::Ordering::SeqCst)
+        + step
+}
+
+#[pymodule]
+fn counter_module(_py: Python, m: &PyModule) -> PyResult<()> {
+    use pyo3::wrap_pyfunction;
+
+    m.set_state(Counter(std::sync::atomic::AtomicU64::new(0)))?;
+    m.add_wrapped(wrap_pyfunction!(increment, m))?;
+
+    #[pyfn(m, "get")]
+    fn get(counter: pyo3::types::ModuleState<Counter>) -> u64 {
+        counter.0.load(std::sync::atomic::Ordering::SeqCst)
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_module_state() {
+    use pyo3::{wrap_pyfunction, wrap_pymodule};
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let drops = COUNTER_DROPS.load(std::sync::atomic::Ordering::SeqCst);
+    {
+        let pool = unsafe { py.new_pool() };
+        let py = pool.python();
+        let first = wrap_pymodule!(counter_module)(py);
+        let second = wrap_pymodule!(counter_module)(py);
+        let unbound = wrap_pyfunction!(increment)(py);
+        let other = PyModule::new(py, "other").unwrap();
+        other.set_state(42u32).unwrap();
+        let wrong_state = wrap_pyfunction!(increment, other)(py);
+        let no_state = wrap_pyfunction!(increment, PyModule::new(py, "empty").unwrap())(py);
+        pyo3::py_run!(
+            py,
+            first second unbound wrong_state no_state,
+            r#"
+assert first.increment(1) == 1
+assert first.increment(step=2) == 3
+assert first.get() == 3
+assert second.get() == 0
+assert second.increment(5) == 5
+assert first.get() == 3
+
+for f, error in [(unbound, RuntimeError), (wrong_state, TypeError), (no_state, RuntimeError)]:
+    try:
+        f(1)
+    except error:
+        pass
+    else:
+        assert False, f
+"#
+        );
+        let second: &PyModule = second.as_ref(py).downcast().unwrap();
+        let state = second.state::<Counter>().unwrap();
+        assert_eq!(5, state.0.load(std::sync::atomic::Ordering::SeqCst));
+        assert!(second.state::<u32>().is_err());
+    }
+    // The functions refer to their module, so the modules are only collected by the gc.
+    pyo3::gc::collect(py).unwrap();
+    assert_eq!(
+        drops + 2,
+        COUNTER_DROPS.load(std::sync::atomic::Ordering::SeqCst)
+    );
+}
+
+static TABLE_BUILDS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+#[pyfunction]
+fn lazy_getattr(py: Python, name: &str) -> PyResult<PyObject> {
+    match name {
+        "TABLE" => {
+            TABLE_BUILDS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            let table: Vec<u32> = (0..16).map(|i| i * i).collect();
+            let module = py.import("lazy_module")?;
+            // Cache the table in the module, so that `__getattr__` is not called again.
+            module.setattr("TABLE", &table)?;
+            Ok(table.to_object(py))
+        }
+        _ => Err(pyo3::exceptions::AttributeError::py_err(format!(
+            "module 'lazy_module' has no attribute '{}'",
+            name
+        ))),
+    }
+}
+
+#[pyfunction]
+fn lazy_dir(py: Python) -> PyResult<Vec<String>> {
+    let module = py.import("lazy_module")?;
+    let mut names: Vec<String> = module.dict().keys().extract()?;
+    if !names.iter().any(|name| name == "TABLE") {
+        names.push("TABLE".to_string());
+    }
+    Ok(names)
+}
+
+#[pymodule]
+fn lazy_module(_py: Python, m: &PyModule) -> PyResult<()> {
+    use pyo3::wrap_pyfunction;
+
+    m.add("EAGER", 1)?;
+    m.add_getattr(wrap_pyfunction!(lazy_getattr))?;
+    m.add_dir(wrap_pyfunction!(lazy_dir))
+}
+
+#[test]
+#[cfg(Py_3_7)]
+fn test_module_getattr() {
+    use pyo3::wrap_pymodule;
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let lazy_module = wrap_pymodule!(lazy_module)(py);
+    py.import("sys")
+        .unwrap()
+        .getattr("modules")
+        .unwrap()
+        .set_item("lazy_module", &lazy_module)
+        .unwrap();
+    py_assert!(py, lazy_module, "lazy_module.EAGER == 1");
+    py_assert!(py, lazy_module, "'TABLE' in dir(lazy_module)");
+    py_assert!(py, lazy_module, "'EAGER' in dir(lazy_module)");
+    py_assert!(py, lazy_module, "lazy_module.TABLE[3] == 9");
+    py_assert!(py, lazy_module, "lazy_module.TABLE[15] == 225");
+    assert_eq!(TABLE_BUILDS.load(std::sync::atomic::Ordering::SeqCst), 1);
+    py_assert!(py, lazy_module, "not hasattr(lazy_module, 'MISSING')");
+    py_assert!(py, lazy_module, "getattr(lazy_module, 'MISSING', 2) == 2");
+    py_expect_exception!(py, lazy_module, "lazy_module.MISSING", AttributeError);
+    py_assert!(py, lazy_module, "dir(lazy_module).count('TABLE') == 1");
+    py.import("sys")
+        .unwrap()
+        .getattr("modules")
+        .unwrap()
+        .del_item("lazy_module")
+        .unwrap();
+}
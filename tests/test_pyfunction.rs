@@ -62,3 +62,41 @@ assert a, array.array("i", [2, 4, 6, 8])
 "#
     );
 }
+
+#[pyfunction]
+fn caller_location(py: Python) -> PyResult<Vec<(String, usize, String)>> {
+    py.caller_location(2)
+}
+
+#[test]
+fn test_caller_location() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let module = PyModule::from_code(
+        py,
+        r#"
+def outer(f):
+    return inner(f)
+
+def inner(f):
+    return f()
+"#,
+        "callers.py",
+        "callers",
+    )
+    .unwrap();
+    let f = wrap_pyfunction!(caller_location)(py);
+    let locations: Vec<(String, usize, String)> =
+        module.call1("outer", (f,)).unwrap().extract().unwrap();
+    assert_eq!(
+        locations,
+        vec![
+            ("callers.py".to_string(), 6, "inner".to_string()),
+            ("callers.py".to_string(), 3, "outer".to_string()),
+        ]
+    );
+
+    // Without any Python code running, there is no frame.
+    assert!(py.current_frame().is_none());
+    assert!(py.caller_location(2).unwrap().is_empty());
+}
@@ -0,0 +1,80 @@
+use pyo3::exceptions::ValueError;
+use pyo3::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+#[pyo3::test]
+fn without_python() {
+    let sum: i32 = Python::with_gil(|py| py.eval("1 + 1", None, None).unwrap().extract().unwrap());
+    assert_eq!(2, sum);
+}
+
+#[pyo3::test]
+fn with_python(py: Python) {
+    assert!(py.eval("True", None, None).unwrap().is_true().unwrap());
+}
+
+#[pyo3::test]
+fn returns_ok(py: Python) -> PyResult<()> {
+    let sum: i32 = py.eval("sum(range(4))", None, None)?.extract()?;
+    assert_eq!(6, sum);
+    Ok(())
+}
+
+#[pyo3::test]
+#[should_panic(expected = "ZeroDivisionError: division by zero")]
+fn fails_with_traceback(py: Python) -> PyResult<()> {
+    py.run("def divide(x):\n    return x / 0\n\ndivide(1)", None, None)?;
+    Ok(())
+}
+
+#[pyo3::test]
+#[should_panic(expected = "in divide")]
+fn fails_with_frames(py: Python) -> PyResult<()> {
+    py.run("def divide(x):\n    return x / 0\n\ndivide(1)", None, None)?;
+    Ok(())
+}
+
+#[pyo3::test]
+#[should_panic(expected = "ValueError: from Rust")]
+fn fails_with_rust_error() -> PyResult<()> {
+    Err(ValueError::py_err("from Rust"))
+}
+
+#[pyo3::test]
+#[should_panic(expected = "plain panic")]
+fn panics(_py: Python) {
+    panic!("plain panic")
+}
+
+/// The number of shared tests running, which must be 0 during the exclusive tests.
+static RUNNING: AtomicUsize = AtomicUsize::new(0);
+
+fn run_shared(py: Python) {
+    RUNNING.fetch_add(1, Ordering::SeqCst);
+    py.allow_threads(|| thread::sleep(Duration::from_millis(50)));
+    RUNNING.fetch_sub(1, Ordering::SeqCst);
+}
+
+#[pyo3::test]
+fn shared_1(py: Python) {
+    run_shared(py)
+}
+
+#[pyo3::test]
+fn shared_2(py: Python) {
+    run_shared(py)
+}
+
+#[pyo3::test]
+fn shared_3(py: Python) {
+    run_shared(py)
+}
+
+#[pyo3::test(exclusive)]
+fn exclusive(py: Python) {
+    assert_eq!(0, RUNNING.load(Ordering::SeqCst));
+    py.allow_threads(|| thread::sleep(Duration::from_millis(100)));
+    assert_eq!(0, RUNNING.load(Ordering::SeqCst));
+}
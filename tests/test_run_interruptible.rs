@@ -0,0 +1,57 @@
+//! Contains a single test, so that it runs in the thread which initializes the interpreter, the
+//! only thread in which Python handles signals.
+use pyo3::exceptions::KeyboardInterrupt;
+use pyo3::prelude::*;
+use pyo3::types::IntoPyDict;
+
+#[test]
+fn test_run_interruptible_sigint() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let signal = py.import("signal").unwrap();
+    if !signal.hasattr("raise_signal").unwrap() {
+        // `signal.raise_signal` was added in Python 3.8.
+        return;
+    }
+    signal
+        .call1(
+            "signal",
+            (
+                signal.get("SIGINT").unwrap(),
+                signal.get("SIG_IGN").unwrap(),
+            ),
+        )
+        .unwrap();
+    let handler_is_ignored = || {
+        py.eval(
+            "signal.getsignal(signal.SIGINT) is signal.SIG_IGN",
+            None,
+            Some([("signal", signal)].into_py_dict(py)),
+        )
+        .unwrap()
+        .is_true()
+        .unwrap()
+    };
+
+    let err = py
+        .run_interruptible(
+            "import signal; signal.raise_signal(signal.SIGINT)",
+            None,
+            None,
+        )
+        .unwrap_err();
+    assert!(err.is_instance::<KeyboardInterrupt>(py));
+    assert!(handler_is_ignored());
+
+    py.run_interruptible("x = 1", None, None).unwrap();
+    assert!(handler_is_ignored());
+
+    // The ignored signal does not interrupt the code run afterwards.
+    py.run(
+        "import signal; signal.raise_signal(signal.SIGINT)",
+        None,
+        None,
+    )
+    .unwrap();
+    assert!(py.check_signals().is_ok());
+}
@@ -1,7 +1,7 @@
-use pyo3::class::PySequenceProtocol;
+use pyo3::class::{PyMappingProtocol, PySequenceProtocol};
 use pyo3::exceptions::{IndexError, ValueError};
 use pyo3::prelude::*;
-use pyo3::types::{IntoPyDict, PyList};
+use pyo3::types::{IntoPyDict, PyList, PySlice};
 
 use pyo3::py_run;
 
@@ -265,3 +265,109 @@ fn test_option_list_get() {
     py_assert!(py, list, "list[1] == None");
     py_expect_exception!(py, list, "list[2]", IndexError);
 }
+
+#[pyclass]
+struct SliceableList {
+    items: Py<PyList>,
+}
+
+#[pyproto]
+impl PyMappingProtocol for SliceableList {
+    fn __len__(&self) -> usize {
+        let gil = Python::acquire_gil();
+        self.items.as_ref(gil.python()).len()
+    }
+
+    fn __getitem__(&self, idx: &PyAny) -> PyResult<PyObject> {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let items = self.items.as_ref(py);
+        if let Ok(slice) = idx.downcast::<PySlice>() {
+            let indices = slice.indices(items.len() as isize)?;
+            if indices.step == 1 {
+                return Ok(items
+                    .get_slice(indices.start as usize, indices.stop as usize)
+                    .into());
+            }
+            let result = PyList::empty(py);
+            for i in 0..indices.slicelength {
+                result.append(items.get_item(indices.start + i * indices.step))?;
+            }
+            Ok(result.into())
+        } else {
+            Ok(items.get_item_checked(idx.extract()?)?.into())
+        }
+    }
+}
+
+#[test]
+fn test_getitem_slice() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let list = PyCell::new(
+        py,
+        SliceableList {
+            items: PyList::new(py, &[0, 1, 2, 3, 4]).into(),
+        },
+    )
+    .unwrap();
+
+    py_assert!(py, list, "list[1] == 1");
+    py_assert!(py, list, "list[-1] == 4");
+    py_expect_exception!(py, list, "list[5]", IndexError);
+    py_expect_exception!(py, list, "list[-6]", IndexError);
+    py_assert!(py, list, "list[1:3] == [1, 2]");
+    py_assert!(py, list, "list[-2:] == [3, 4]");
+    py_assert!(py, list, "list[::2] == [0, 2, 4]");
+    py_assert!(py, list, "list[::-1] == [4, 3, 2, 1, 0]");
+    py_assert!(py, list, "list[3:0:-2] == [3, 1]");
+    py_assert!(py, list, "list[3:1] == []");
+    py_assert!(py, list, "list[1:3:-1] == []");
+    py_assert!(py, list, "list[10:] == []");
+    py_expect_exception!(py, list, "list[::0]", ValueError);
+}
+
+#[test]
+fn test_register_sequence() {
+    use pyo3::types::PySequence;
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let seq = Py::new(
+        py,
+        ByteSequence {
+            elements: vec![1, 2, 1],
+        },
+    )
+    .unwrap();
+    let seq: &PyAny = seq.as_ref(py);
+    assert!(seq.downcast::<PySequence>().is_err());
+
+    PySequence::register::<ByteSequence>(py).unwrap();
+    let d = [("seq", seq)].into_py_dict(py);
+    py.run(
+        "import collections.abc; assert isinstance(seq, collections.abc.Sequence)",
+        None,
+        Some(d),
+    )
+    .unwrap();
+
+    let seq = seq.downcast::<PySequence>().unwrap();
+    assert_eq!(3, seq.len().unwrap());
+    assert_eq!(2, seq.get_item(1).unwrap().extract::<u8>().unwrap());
+    seq.set_item(1, 3).unwrap();
+    seq.del_item(0).unwrap();
+    assert_eq!(0, seq.index(3u8).unwrap());
+    #[cfg(not(PyPy))]
+    assert_eq!(1, seq.count(1u8).unwrap());
+    assert!(seq.contains(1u8).unwrap());
+    assert_eq!(
+        vec![3, 1],
+        seq.to_list().unwrap().extract::<Vec<u8>>().unwrap()
+    );
+    assert_eq!(
+        (3, 1),
+        seq.to_tuple().unwrap().extract::<(u8, u8)>().unwrap()
+    );
+}
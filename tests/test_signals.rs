@@ -0,0 +1,127 @@
+//! Runs in the main thread, which initializes the interpreter, since Python only handles signals
+//! in that thread.
+use pyo3::exceptions::{KeyboardInterrupt, RuntimeError};
+use pyo3::prelude::*;
+use pyo3::signals::{self, Interrupt};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Polls `interrupt` until it is set, for at most 10 seconds, and returns whether it was set.
+fn wait_for_interrupt(interrupt: &Interrupt) -> bool {
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_secs(10) {
+        if interrupt.is_interrupted() {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+    false
+}
+
+#[cfg(unix)]
+fn raise_sigint() {
+    unsafe { libc::kill(libc::getpid(), libc::SIGINT) };
+}
+
+#[cfg(unix)]
+fn test_interrupt(py: Python) {
+    let start = Instant::now();
+    let err = signals::with_interrupt_check(py, Duration::from_millis(10), |interrupt| {
+        raise_sigint();
+        wait_for_interrupt(interrupt)
+    })
+    .unwrap_err();
+    assert!(err.is_instance::<KeyboardInterrupt>(py));
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    // A signal raised at the end of the work is not left pending.
+    let err =
+        signals::with_interrupt_check(py, Duration::from_secs(60), |_| raise_sigint()).unwrap_err();
+    assert!(err.is_instance::<KeyboardInterrupt>(py));
+    assert!(py.check_signals().is_ok());
+}
+
+/// Raises `SIGUSR1`, whose handler raises an exception, in a loop without the GIL which checks for
+/// signals with the GIL acquired again.
+#[cfg(unix)]
+fn test_check_signals(py: Python) {
+    py.run(
+        r#"
+import signal
+
+def on_usr1(signum, frame):
+    raise RuntimeError("received SIGUSR1")
+
+signal.signal(signal.SIGUSR1, on_usr1)
+"#,
+        None,
+        None,
+    )
+    .unwrap();
+
+    let mut iterations = 0;
+    let result: PyResult<()> = py.allow_threads(|| {
+        for i in 0..1000 {
+            if i == 10 {
+                unsafe { libc::kill(libc::getpid(), libc::SIGUSR1) };
+            }
+            Python::with_gil(|py| py.check_signals())?;
+            iterations += 1;
+        }
+        Ok(())
+    });
+    let err = result.unwrap_err();
+    assert!(err.is_instance::<RuntimeError>(py));
+    assert_eq!("received SIGUSR1", err.to_object(py).as_ref(py).to_string());
+    assert_eq!(10, iterations);
+    assert!(py.check_signals().is_ok());
+
+    py.run(
+        "import signal; signal.signal(signal.SIGUSR1, signal.SIG_DFL)",
+        None,
+        None,
+    )
+    .unwrap();
+}
+
+fn test_no_signal(py: Python) {
+    let borrowed = vec![1, 2, 3];
+    let sum = signals::with_interrupt_check(py, Duration::from_millis(1), |interrupt| {
+        thread::sleep(Duration::from_millis(20));
+        assert!(!interrupt.is_interrupted());
+        borrowed.iter().sum::<i32>()
+    })
+    .unwrap();
+    assert_eq!(6, sum);
+}
+
+fn test_panic(py: Python) {
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(|_| ()));
+    let result = std::panic::catch_unwind(|| {
+        signals::with_interrupt_check(py, Duration::from_millis(1), |_| panic!("work failed"))
+    });
+    std::panic::set_hook(hook);
+    let payload = result.unwrap_err();
+    assert_eq!(Some(&"work failed"), payload.downcast_ref::<&str>());
+}
+
+fn main() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    // PyO3 initializes the interpreter without Python's signal handlers.
+    py.run(
+        "import signal; signal.signal(signal.SIGINT, signal.default_int_handler)",
+        None,
+        None,
+    )
+    .unwrap();
+
+    #[cfg(unix)]
+    test_interrupt(py);
+    #[cfg(unix)]
+    test_check_signals(py);
+    test_no_signal(py);
+    test_panic(py);
+    println!("test_signals: ok");
+}
@@ -0,0 +1,89 @@
+//! Changes to the state of `sys`, in a separate binary so that other tests are not affected.
+use pyo3::prelude::*;
+use pyo3::types::PyList;
+use pyo3::{sys, AsPyRef, PyNativeType};
+use std::env;
+use std::fs;
+
+/// Restores `sys.path` on drop.
+struct RestorePath<'p>(&'p PyList, PyObject);
+
+impl<'p> RestorePath<'p> {
+    fn save(py: Python<'p>) -> Self {
+        let path = sys::path(py).unwrap();
+        let saved = path.call_method0("copy").unwrap().to_object(py);
+        RestorePath(path, saved)
+    }
+}
+
+impl Drop for RestorePath<'_> {
+    fn drop(&mut self) {
+        let saved = self.1.as_ref(self.0.py());
+        self.0.set_slice(0, self.0.len(), saved).unwrap();
+    }
+}
+
+#[test]
+fn import_from_prepended_path() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let directory = env::temp_dir().join(format!("pyo3-sys-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    fs::write(directory.join("pyo3_sys_first.py"), "VALUE = 1\n").unwrap();
+    let other = directory.join("other");
+    fs::create_dir_all(&other).unwrap();
+    fs::write(other.join("pyo3_sys_first.py"), "VALUE = 2\n").unwrap();
+    fs::write(other.join("pyo3_sys_last.py"), "VALUE = 3\n").unwrap();
+
+    let length = sys::path(py).unwrap().len();
+    {
+        let _restore = RestorePath::save(py);
+        sys::append_path(py, &other).unwrap();
+        sys::prepend_path(py, &directory).unwrap();
+        let path = sys::path(py).unwrap();
+        assert_eq!(path.len(), length + 2);
+        let first: String = path.get_item(0).extract().unwrap();
+        assert_eq!(first, directory.to_str().unwrap());
+
+        let first = py.import("pyo3_sys_first").unwrap();
+        assert_eq!(first.get("VALUE").unwrap().extract::<i32>().unwrap(), 1);
+        let last = py.import("pyo3_sys_last").unwrap();
+        assert_eq!(last.get("VALUE").unwrap().extract::<i32>().unwrap(), 3);
+
+        let modules = py.import("sys").unwrap().getattr("modules").unwrap();
+        modules.del_item("pyo3_sys_first").unwrap();
+        modules.del_item("pyo3_sys_last").unwrap();
+    }
+    assert_eq!(sys::path(py).unwrap().len(), length);
+    assert!(py.import("pyo3_sys_last").is_err());
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn recursion_limit() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let saved = sys::recursion_limit(py).unwrap();
+    sys::set_recursion_limit(py, saved + 100).unwrap();
+    assert_eq!(sys::recursion_limit(py).unwrap(), saved + 100);
+    let err = sys::set_recursion_limit(py, 0).unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::ValueError>(py));
+    sys::set_recursion_limit(py, saved).unwrap();
+    assert_eq!(sys::recursion_limit(py).unwrap(), saved);
+}
+
+#[test]
+fn executable() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let expected: Option<String> = py
+        .eval("__import__('sys').executable or None", None, None)
+        .unwrap()
+        .extract()
+        .unwrap();
+    let executable = sys::executable(py).unwrap();
+    assert_eq!(
+        executable.as_ref().map(|path| path.to_str().unwrap()),
+        expected.as_ref().map(String::as_str)
+    );
+}
@@ -1,7 +1,7 @@
 use pyo3::prelude::*;
 use pyo3::types::IntoPyDict;
 use pyo3::types::{PyDict, PyTuple};
-use pyo3::{py_run, wrap_pyfunction, AsPyRef, PyCell};
+use pyo3::{kwargs, py_run, wrap_pyfunction, AsPyRef, PyCell};
 
 mod common;
 
@@ -181,3 +181,141 @@ fn incorrect_iter() {
         .eval("print('Exception state should not be set.')", None, None)
         .is_ok());
 }
+
+const SESSION: &str = r#"
+class Session:
+    def __init__(self, base_url):
+        self.base_url = base_url
+
+    def request(self, method, path, *, params=None, timeout=None, verify=True, headers=None):
+        return {
+            "method": method,
+            "url": self.base_url + path,
+            "params": params,
+            "timeout": timeout,
+            "verify": verify,
+            "headers": headers,
+        }
+
+    def get(self, path, **kwargs):
+        return self.request("GET", path, **kwargs)
+
+def fetch(url, *, retries=0, backoff=1.0):
+    return (url, retries, backoff)
+"#;
+
+#[test]
+fn test_kwargs() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let globals = PyDict::new(py);
+    py.run(SESSION, Some(globals), None).unwrap();
+
+    let session = globals
+        .get_item("Session")
+        .unwrap()
+        .call1(("https://example.com",))
+        .unwrap();
+    let response = session
+        .call_method_kwargs(
+            "get",
+            ("/search",),
+            kwargs!(
+                py,
+                timeout = 5,
+                verify = false,
+                params = vec![("q", "pyo3")],
+            )
+            .unwrap(),
+        )
+        .unwrap();
+    let response = response.downcast::<PyDict>().unwrap();
+    let item = |key| response.get_item(key).unwrap();
+    assert_eq!("GET", item("method").extract::<&str>().unwrap());
+    assert_eq!(
+        "https://example.com/search",
+        item("url").extract::<&str>().unwrap()
+    );
+    assert_eq!(5, item("timeout").extract::<i32>().unwrap());
+    assert!(!item("verify").extract::<bool>().unwrap());
+    assert_eq!(
+        vec![("q".to_string(), "pyo3".to_string())],
+        item("params").extract::<Vec<(String, String)>>().unwrap()
+    );
+    assert!(item("headers").is_none());
+
+    let fetch = globals.get_item("fetch").unwrap();
+    let result: (String, u32, f64) = fetch
+        .call_kwargs(("/",), kwargs!(py, retries = 3).unwrap())
+        .unwrap()
+        .extract()
+        .unwrap();
+    assert_eq!(("/".to_string(), 3, 1.0), result);
+    let result: (String, u32, f64) = fetch
+        .call_kwargs(("/",), kwargs!(py).unwrap())
+        .unwrap()
+        .extract()
+        .unwrap();
+    assert_eq!(("/".to_string(), 0, 1.0), result);
+
+    // Unknown keyword arguments are rejected by Python.
+    let err = fetch
+        .call_kwargs(("/",), kwargs!(py, retry = 3).unwrap())
+        .map(|_| ())
+        .unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::TypeError>(py));
+}
+
+#[test]
+fn test_literal_macros() {
+    use pyo3::{py_dict, py_list, py_tuple};
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let describe = py
+        .eval(
+            "lambda *args, **kwargs: (repr(args), sorted(kwargs.items()))",
+            None,
+            None,
+        )
+        .unwrap();
+
+    let name = String::from("pyo3");
+    let rest = py_tuple!(py, 3.5, None::<i32>);
+    let args = py_tuple!(
+        py,
+        1,
+        &name,
+        py_list!(py, true, "x", py_tuple!(py)),
+        py_dict!(py, "k" => py_list!(py, 1, 2), 7 => "seven").unwrap(),
+        ..rest,
+    );
+    assert_eq!(args.len(), 6);
+    let (args, kwargs): (String, Vec<(String, i32)>) = describe
+        .call(args, Some(py_dict!(py, "b" => 2, "a" => 1).unwrap()))
+        .unwrap()
+        .extract()
+        .unwrap();
+    assert_eq!(
+        args,
+        "(1, 'pyo3', [True, 'x', ()], {'k': [1, 2], 7: 'seven'}, 3.5, None)"
+    );
+    assert_eq!(kwargs, [("a".to_string(), 1), ("b".to_string(), 2)]);
+
+    // Spreading in the middle, and sixteen elements.
+    let list = py_list!(py, 0, ..py_tuple!(py, 1, 2), ..vec![3, 4], 5);
+    let expected: Vec<i32> = (0..6).collect();
+    assert_eq!(list.extract::<Vec<i32>>().unwrap(), expected);
+    let tuple = py_tuple!(py, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15);
+    let expected: Vec<i32> = (0..16).collect();
+    assert_eq!(tuple.extract::<Vec<i32>>().unwrap(), expected);
+
+    assert!(py_list!(py).is_empty());
+    assert!(py_dict!(py).unwrap().is_empty());
+    // The last value of a repeated key is kept, like in Python.
+    let dict = py_dict!(py, "a" => 1, "a" => 2).unwrap();
+    assert_eq!(dict.get_item("a").unwrap().extract::<i32>().unwrap(), 2);
+    // Keys must be hashable.
+    let err = py_dict!(py, py_list!(py) => 1).unwrap_err();
+    assert!(err.is_instance::<pyo3::exceptions::TypeError>(py));
+}
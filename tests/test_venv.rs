@@ -0,0 +1,111 @@
+//! Activation of virtual environments, in a separate binary as it changes `sys.path`.
+use pyo3::exceptions::{OSError, RuntimeError};
+use pyo3::prelude::*;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Creates a virtual environment with a package, in the layout of `python -m venv`.
+fn create_venv(py: Python, name: &str, version: &str) -> PathBuf {
+    let venv = env::temp_dir().join(format!("pyo3-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&venv);
+    let version_info = py.import("sys").unwrap().getattr("version_info").unwrap();
+    let major: u8 = version_info.getattr("major").unwrap().extract().unwrap();
+    let minor: u8 = version_info.getattr("minor").unwrap().extract().unwrap();
+    let site_packages = if cfg!(windows) {
+        venv.join("Lib").join("site-packages")
+    } else {
+        venv.join("lib")
+            .join(format!("python{}.{}", major, minor))
+            .join("site-packages")
+    };
+    let package = site_packages.join("only_in_venv");
+    fs::create_dir_all(&package).unwrap();
+    fs::write(package.join("__init__.py"), "VALUE = 42\n").unwrap();
+    let extra = venv.join("extra");
+    fs::create_dir_all(&extra).unwrap();
+    fs::write(extra.join("from_pth.py"), "").unwrap();
+    fs::write(
+        site_packages.join("extra.pth"),
+        format!("{}\n", extra.display()),
+    )
+    .unwrap();
+    let version = if version.is_empty() {
+        format!("{}.{}.0", major, minor)
+    } else {
+        version.to_string()
+    };
+    fs::write(
+        venv.join("pyvenv.cfg"),
+        format!(
+            "home = /usr/bin\ninclude-system-site-packages = false\nversion = {}\n",
+            version
+        ),
+    )
+    .unwrap();
+    venv
+}
+
+fn same_path(py: Python, a: &str, b: &Path) -> bool {
+    let os_path = py.import("os.path").unwrap();
+    let normalize = |p: &PyAny| -> String {
+        let p = os_path.call1("abspath", (p,)).unwrap();
+        os_path.call1("normcase", (p,)).unwrap().extract().unwrap()
+    };
+    normalize(a.to_object(py).as_ref(py)) == normalize(b.to_string_lossy().to_object(py).as_ref(py))
+}
+
+#[test]
+fn test_venv() {
+    pyo3::prepare_freethreaded_python();
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    // Failures leave sys.path untouched.
+    let missing = env::temp_dir().join("pyo3-missing-venv");
+    let err = pyo3::prepare_freethreaded_python_with_venv(Some(&missing)).unwrap_err();
+    assert!(err.is_instance::<OSError>(py));
+    let other_version = create_venv(py, "other-version", "2.7.18");
+    let err = pyo3::prepare_freethreaded_python_with_venv(Some(&other_version)).unwrap_err();
+    assert!(err.is_instance::<RuntimeError>(py));
+    fs::remove_dir_all(&other_version).unwrap();
+    assert!(py.import("only_in_venv").is_err());
+
+    let base_site_packages: Vec<String> = py
+        .import("site")
+        .unwrap()
+        .call0("getsitepackages")
+        .unwrap()
+        .extract()
+        .unwrap();
+
+    let venv = create_venv(py, "venv", "");
+    env::set_var("VIRTUAL_ENV", &venv);
+    pyo3::prepare_freethreaded_python_with_venv(None).unwrap();
+    env::remove_var("VIRTUAL_ENV");
+
+    let value: i32 = py
+        .import("only_in_venv")
+        .unwrap()
+        .getattr("VALUE")
+        .unwrap()
+        .extract()
+        .unwrap();
+    assert_eq!(value, 42);
+    py.import("from_pth").unwrap();
+
+    let sys = py.import("sys").unwrap();
+    let prefix: String = sys.getattr("prefix").unwrap().extract().unwrap();
+    let base_prefix: String = sys.getattr("base_prefix").unwrap().extract().unwrap();
+    assert!(same_path(py, &prefix, &venv));
+    assert!(!same_path(py, &base_prefix, &venv));
+
+    let path: Vec<String> = sys.getattr("path").unwrap().extract().unwrap();
+    assert!(path[0].contains("site-packages"));
+    assert!(path[0].starts_with(&*venv.to_string_lossy()));
+    for base in &base_site_packages {
+        assert!(!path.iter().any(|p| same_path(py, p, Path::new(base))));
+    }
+
+    fs::remove_dir_all(&venv).unwrap();
+}